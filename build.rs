@@ -1,7 +1,18 @@
+use std::path::PathBuf;
+
+/// The app's icon, rendered at build time for panels and HiDPI displays that ask for
+/// pixels rather than vectors.
+const ICON: &str = "resources/icons/hicolor/scalable/apps/icon.svg";
+
+/// Sizes in pixels the icon is rendered at, following the hicolor theme's folders.
+const ICON_SIZES: [u32; 7] = [16, 24, 32, 48, 64, 128, 256];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Rebuild if i18n files change
     println!("cargo:rerun-if-changed=i18n");
 
+    render_icons()?;
+
     // Emit version information (if not cached by just vendor)
     let mut vergen = vergen::EmitBuilder::builder();
 
@@ -17,3 +28,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     vergen.fail_on_error().emit()?;
     Ok(())
 }
+
+/// Renders the icon as a PNG of each size into `OUT_DIR/icons`, along with `icons.rs`
+/// listing them for `src/icons.rs` to include.
+fn render_icons() -> Result<(), Box<dyn std::error::Error>> {
+    use resvg::{tiny_skia, usvg};
+
+    println!("cargo:rerun-if-changed={ICON}");
+
+    let out = PathBuf::from(std::env::var("OUT_DIR")?).join("icons");
+    std::fs::create_dir_all(&out)?;
+
+    let tree = usvg::Tree::from_data(&std::fs::read(ICON)?, &usvg::Options::default())?;
+    let side = tree.size().width().max(tree.size().height());
+
+    let mut listing = String::from("pub const APP_PNGS: &[(u32, &[u8])] = &[\n");
+
+    for size in ICON_SIZES {
+        let mut pixmap = tiny_skia::Pixmap::new(size, size).ok_or("icon size is zero")?;
+        let scale = size as f32 / side;
+
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+
+        let path = out.join(format!("{size}.png"));
+        pixmap.save_png(&path)?;
+        listing.push_str(&format!("    ({size}, include_bytes!({path:?})),\n"));
+    }
+
+    listing.push_str("];\n");
+    std::fs::write(out.join("icons.rs"), listing)?;
+    Ok(())
+}
@@ -5,6 +5,31 @@ welcome = Welcome to COSMIC! ✨
 welcome-body = This is the welcome page!!
 page-id = Page { $num }
 git-description = Git commit {$hash} on {$date}
+settings = Settings
+settings-save = Save Settings
+username = Username:
+username-placeholder = Enter your username
+unknown-user = Unknown User
+click-me = Click me
+
+# Canvas page
+canvas-welcome = Welcome to the Kawaii Canvas!
+canvas-hint = Move your mouse around to see the shapes react.
+popup-title = This is a popup on page 1!
+popup-body = This is the body of the popup.
+
+# Profile page
+page-two-title = Page 2 Content
+page-two-body = This is page 2 with custom content!
+welcome-back = Welcome back, { $name }!
+username-from-system = Using OS username. Go to Settings in the View menu to set a custom username.
+username-hint = Go to Settings in the View menu to update your username
+
+# Library search
+search-placeholder = Search...
+search-no-results = 🔍 No results found
+search-no-matches = No items match "{ $query }"
+search-try-again = Try a different search term
 
 # Kawaii Page 1 messages
 kawaii-title = 🌸 Kawaii Libby 🌸
@@ -12,3 +37,633 @@ kawaii-welcome = ✨ Welcome to the cutest app ever! ✨
 kawaii-face = (´｡• ᵕ •｡`) ♡
 kawaii-button = 🎉 Click me nya~ 🎉
 kawaii-footer = Made with 💕 and lots of cuteness!
+
+# Updates
+update-check = Check for newer versions of Libby
+language = Language
+language-system = Same as the system
+language-fallback = Languages for what isn't translated, in order and separated by commas, before English
+update-daily = Daily
+update-weekly = Weekly
+update-never = Never
+update-check-now = Check now
+update-available = Libby { $version } is available
+update-release-notes = Release notes
+update-none = Libby is up to date
+
+# Crash reports
+crash-reports = After a crash, offer to send its report to help fix it
+crash-endpoint = Address to send crash reports to
+crash-title = Libby quit unexpectedly
+crash-body = { $count ->
+    [one] A report of the crash was saved. Send it to { $endpoint }? It holds the version of Libby, where it crashed and what it was last doing, but nothing you read or typed.
+   *[other] Reports of { $count } crashes were saved. Send them to { $endpoint }? They hold the version of Libby, where it crashed and what it was last doing, but nothing you read or typed.
+}
+crash-send = Send
+crash-sending = Sending…
+crash-dont-send = Don't send
+crash-failed = Couldn't send: { $reason }
+
+# Library page
+cancel = Cancel
+select-all = Select all
+clear-selection = Clear selection
+bulk-selected = { $count ->
+    [one] 1 book selected
+   *[other] { $count } books selected
+}
+bulk-value-placeholder = Tag, series or collection name
+bulk-add-tag = Add tag
+bulk-set-series = Set series
+bulk-move-to-collection = Move to collection
+bulk-convert-epub = Convert to EPUB
+bulk-delete = Delete
+bulk-progress = { $done } of { $total } done
+
+# Trash
+trash = Trash
+trash-empty = The trash is empty.
+trash-days-left = { $days ->
+    [one] Deleted permanently in 1 day
+   *[other] Deleted permanently in { $days } days
+}
+trash-toast = { $count ->
+    [one] Moved 1 book to the trash
+   *[other] Moved { $count } books to the trash
+}
+trash-move-files = Move files of deleted books into the trash folder
+undo = Undo
+restore = Restore
+delete-permanently = Delete permanently
+empty-trash = Empty trash
+
+# Import
+import = Add
+import-files = Add files…
+import-url = Add from URL…
+import-url-hint = The book is downloaded into the library, which takes EPUB and PDF files.
+import-url-placeholder = Address of an EPUB or PDF
+import-queued = Downloading the book…
+import-done = Added { $title } to the library
+import-failed = Couldn't add the book: { $reason }
+library-load-failed = Couldn't read your library, so changes won't be saved: { $reason }
+retry = Try again
+library-empty = Your library is empty
+library-empty-hint = Books you add, whether EPUBs, PDFs or comics, show up here.
+
+# Export
+file = File
+export = Export
+export-library = Export library…
+export-path = Destination file
+export-done = { $count ->
+    [one] Exported 1 book
+   *[other] Exported { $count } books
+}
+export-failed = The library could not be exported
+field-title = Title
+field-author = Author
+field-description = Description
+field-tags = Tags
+field-series = Series
+field-collection = Collection
+field-progress = Reading progress
+field-rating = Rating
+field-path = File path
+
+# Book details
+book-missing = This book is no longer in the library.
+book-series = Series: { $series }
+book-collection = Collection: { $collection }
+notes = Notes
+note-placeholder = Write a note…
+add-note = Add note
+
+# Library verification
+verify-library = Verify library
+verify-progress = Checked { $done } of { $total } files
+verify-clean = All book files are intact.
+verify-summary = Checked { $total } files in { $seconds } seconds, hashing the { $hashed } new or changed since last time.
+verify-full = Check every file
+verify-missing = The file is missing.
+verify-corrupted = The file's contents have changed.
+verify-moved = The file was found at { $path }
+relocate = Use new location
+remove = Remove
+
+# Reader
+read = Read
+reader-chapter = Chapter { $current } of { $total }
+reader-previous-page = Previous page
+reader-next-page = Next page
+reader-open-failed = The book could not be opened
+reader-page = Page { $current } of { $total }
+reader-pages = Pages { $first }–{ $second } of { $total }
+zoom-fit-width = Fit width
+zoom-fit-page = Fit page
+
+# Bookmarks
+bookmarks = Bookmarks
+bookmarks-empty = No bookmarks yet
+bookmarks-no-book = Open a book to see its bookmarks
+bookmark-chapter = Chapter { $chapter }, { $percent }%
+bookmark-page = Page { $page }
+bookmark-placeholder = Label (optional)
+add-bookmark = Add bookmark
+
+# Annotations
+annotations = Annotations
+annotations-empty = Click paragraphs in the reader to select them for highlighting
+annotations-no-book = Open a book to see its annotations
+annotations-exported = Annotations exported to { $path }
+annotations-export-failed = Annotations could not be exported
+highlight-note-placeholder = Add a note
+reader-chapter-short = Chapter { $chapter }
+export-markdown = Export Markdown
+export-json = Export JSON
+
+# Read aloud
+read-aloud-speed = Speed
+read-aloud-default-voice = Default voice
+read-aloud-unavailable = Reading aloud needs speech-dispatcher to be installed
+
+# Typography
+typography = Typography
+typography-this-book = Use these settings for this book only
+typography-font = Font
+font-serif = Serif
+font-sans-serif = Sans serif
+font-monospace = Monospace
+typography-size = Text size: { $size } px
+typography-line-height = Line height: { $percent }%
+typography-margin = Margins: { $margin } px
+typography-justify = Justify text
+
+# Reading palettes
+palette-app = Match app theme
+palette-light = Light
+palette-sepia = Sepia
+palette-dark = Dark
+palette-black = Black
+palette-custom = Custom colors
+palette-customize = Customize…
+palette-foreground = Text
+palette-background = Background
+
+# Page layout
+spread = Page layout
+spread-auto = Automatic
+spread-single = Single page
+spread-double = Two pages
+
+# Dictionary
+dictionary-looking-up = Looking up…
+dictionary-not-found = No definition found
+dictionary-failed = Lookup failed: { $reason }
+dictionary-source = From { $source }
+dictionary-copy = Copy
+dictionary-online = Look up words on Wiktionary when no local dictionary has them
+close = Close
+
+# Translation
+translate = Translate
+translation = Translation
+translating = Translating…
+translate-detect = Detect language
+translate-failed = Translation failed: { $reason }
+translate-no-server = Set a translation server in Settings first
+translate-server = LibreTranslate server
+translate-api-key = API key (optional)
+
+# Footnotes
+footnote-loading = Loading note…
+footnote-not-found = This note could not be shown here.
+footnote-failed = Failed to read the note: { $reason }
+footnote-go-to = Go to note
+
+# Shortcuts
+shortcuts = Shortcuts
+shortcuts-hint = Keys and gestures which control the reader
+shortcuts-reset = Restore defaults
+shortcut-add = Add shortcut
+shortcut-press = Press a key, swipe, or click the edge of a page… (Esc to cancel)
+shortcut-previous-page = Previous page
+shortcut-next-page = Next page
+shortcut-chapter-start = Start of chapter
+shortcut-chapter-end = End of chapter
+shortcut-previous-chapter = Previous chapter
+shortcut-next-chapter = Next chapter
+shortcut-fullscreen = Fullscreen
+gesture-swipe-left = Swipe left
+gesture-swipe-right = Swipe right
+gesture-tap-left = Click left edge
+gesture-tap-right = Click right edge
+
+# Table of contents
+toc = Contents
+toc-empty = This book has no table of contents
+
+# Search in book
+sort-by = Sort by
+sort-added = Date added
+sort-title = Title
+sort-author = Author
+sort-progress = Progress
+search-book = Search in book
+search-book-placeholder = Find in this book
+search-book-no-book = Open a book to search it
+search-book-searching = Searching… { $count } found
+search-book-count = { $count ->
+    [0] No matches
+    [one] 1 match
+   *[other] { $count } matches
+}
+search-book-position = Match { $current } of { $total }
+
+# Position sync
+sync-folder = Sync reading positions through folder
+sync-folder-placeholder = A folder synced between your devices
+sync-furthest = Keep the furthest position
+sync-latest = Keep the latest position
+sync-now = Sync now
+sync-updated = { $count ->
+    [one] Continued 1 book from another device
+   *[other] Continued { $count } books from another device
+}
+webdav-server = Sync the catalog, bookmarks and annotations through WebDAV, such as Nextcloud
+webdav-user = User name
+webdav-password = Password or app password
+webdav-scheduled = Sync in the background every 15 minutes
+companion = Sync with a companion server, or another device running libby
+companion-server = Server address, or a pairing link
+companion-code = Pairing code
+companion-pair = Pair
+companion-pairing = Pairing…
+companion-pair-failed = Couldn't pair: { $reason }
+companion-paired = Paired with { $server }
+companion-unpair = Unpair
+companion-host = Let other devices pair and sync with this one
+companion-host-address = Address: { $address }
+companion-host-code = Pairing code: { $code }
+companion-host-no-code = Couldn't make a pairing code, so no device can pair. Try a new code.
+companion-host-renew = New code
+companion-host-pair = Pair a device
+companion-host-stop = Stop pairing
+companion-host-window = Pairing ends in two minutes, or once a device has paired.
+companion-host-failed = Other devices can't sync with this one: { $reason }
+companion-host-devices = Paired devices
+companion-device-paired = { $device } paired with this device
+companion-confirm-title = Pair a new device?
+companion-confirm-body = A device calling itself { $device }, at { $address }, entered the pairing code. Once paired, it can read and change your library.
+companion-confirm = Pair
+companion-confirm-decline = Don't pair
+sync-catalog-updated = { $count ->
+    [one] Updated 1 book from another device
+   *[other] Updated { $count } books from other devices
+}
+sync-catalog-conflicts = { $count ->
+    [one] A change made on another device conflicted with yours; yours was kept
+   *[other] { $count } changes made on other devices conflicted with yours; yours were kept
+}
+
+# Network
+proxy = Proxy
+proxy-placeholder = Such as http://proxy:8080 or socks5://proxy:1080, or empty to use the system's
+proxy-invalid = This proxy can't be used: { $reason }
+certificates = Trusted certificates
+certificates-placeholder = A PEM file, such as your proxy's certificate, or empty for only the built-in ones
+certificates-invalid = These certificates can't be used: { $reason }
+connection-test = Test connection
+connection-testing = Connecting…
+connection-ok = Connected
+connection-failed = Couldn't connect: { $reason }
+cache = Cache
+cache-size = Pages and covers take up { $size }
+cache-size-unknown = Pages and covers kept for offline use
+cache-limit = Most the cache holds in MiB, or 0 for { $default }. Press Enter to apply.
+cache-clear = Clear cache
+cache-cleared = Cleared the cache
+cache-clear-failed = Couldn't clear the cache: { $reason }
+offline = You're offline. The feed will refresh when the network is back.
+
+# Downloads
+downloads = Downloads
+downloads-empty = Nothing downloaded yet.
+downloads-clear = Clear finished
+download = Download
+download-placeholder = Address of a file to download
+download-queued = Waiting
+download-size = { $received } of { $total }
+download-paused = Paused at { $size }
+download-failed = Failed: { $reason }
+download-done = Done
+download-finished = Downloaded { $name }
+download-limit = Bandwidth limit in KiB per second, or 0 for none
+
+# Sources
+sources = Sources
+sources-hint = Catalogs to browse and download books from. Passwords are kept in the keyring.
+sources-empty = No sources yet.
+source-add = Add source
+source-name = Name
+source-url = Address
+source-opds = OPDS catalog
+source-http = Web folder
+source-credentials = Sign in, if the source asks to
+source-user = User name
+source-password = Password
+source-password-keep = Password, unchanged if empty
+source-test = Test connection
+source-testing = Testing…
+source-test-passed = Connected, with { $entries ->
+    [one] one entry
+   *[other] { $entries } entries
+} at the top.
+source-test-failed = Couldn't connect: { $reason }
+source-save = Save
+
+# Webhooks
+webhooks = Webhooks
+webhooks-hint = Addresses sent a JSON payload when something happens, such as to log your reading to another service.
+webhooks-empty = No webhooks yet.
+webhook-add = Add webhook
+webhook-name = Name
+webhook-url = Address
+webhook-events = Fire when
+webhook-book-finished = A book is finished
+webhook-book-imported = A book is added from a download
+webhook-template = Payload
+webhook-template-hint = JSON in which {"{{"}event{"}}"}, {"{{"}title{"}}"}, {"{{"}author{"}}"}, {"{{"}progress{"}}"} and {"{{"}time{"}}"} are filled in.
+webhook-test = Send test
+webhook-test-passed = The test was delivered.
+webhook-test-failed = Couldn't send the test: { $reason }
+webhook-sample-title = A Test Book
+webhook-sample-author = An Author
+
+# Catalogs
+catalogs = Catalogs
+catalogs-empty = No sources are enabled. Add or enable one under Sources.
+catalog-open = Open
+catalog-download = Download { $format }
+catalog-queued = Downloading { $name }
+catalog-loading = Loading…
+catalog-failed = Couldn't load the catalog: { $reason }
+catalog-empty = Nothing here.
+catalog-offline = You're offline. Catalogs can't be browsed until the network is back.
+
+# Discover
+discover = Discover
+discover-search = Search for books
+discover-offline = You're offline. Free books can't be browsed until the network is back.
+
+# News
+news = News
+news-placeholder = Address of an RSS or Atom feed
+news-following = You already follow this feed.
+news-failed = Couldn't follow the feed: { $reason }
+news-feed-failed = Couldn't fetch { $url }: { $reason }
+news-empty = Follow the feeds of book blogs and publishers to read their articles here.
+news-all = All
+news-mark-read = Mark all as read
+news-mark-unread = Mark as unread
+news-read = Read
+news-open = Open in browser
+news-isbn = ISBN { $isbn }
+unfollow = Unfollow
+
+# Wishlist
+wishlist = Wishlist
+wishlist-hint = Books to get someday, added from the ISBNs articles mention.
+wishlist-empty = Nothing on your wishlist yet.
+wishlist-add = Add to wishlist
+wishlist-on = On wishlist
+wishlist-added = Added ISBN { $isbn } to your wishlist
+
+# Nearby
+nearby = Nearby
+nearby-share = Share your library with Libby on other devices on this network
+nearby-off = Turn on sharing to find Libby on other devices on this network.
+nearby-empty = Looking for Libby on other devices on this network…
+nearby-failed = Couldn't share the library: { $reason }
+nearby-send = Send to { $device }
+nearby-offered = Offered the book to { $device }
+nearby-offer-failed = Couldn't offer the book to { $device }: { $reason }
+nearby-offer-title = Receive a book?
+nearby-offer-body = { $device } wants to send you { $title } ({ $size }).
+nearby-accept = Accept
+nearby-decline = Decline
+nearby-asking = Waiting for { $device } to let you see its books…
+nearby-refused = Didn't download { $title }: it isn't a book Libby can read.
+nearby-access-title = Let a device see your books?
+nearby-access-body = A device calling itself { $device }, at { $address }, wants to see the books you share. Once allowed, it can list and download them until you turn sharing off.
+nearby-access-allow = Allow
+nearby-access-deny = Don't allow
+
+# Accounts
+accounts = Accounts
+account-hint = Sign in to Bluesky or another atproto service with an app password, created in your account's privacy and security settings.
+account-handle = Handle or email, like alice.bsky.social
+account-app-password = App password
+account-log-in = Sign in
+account-logging-in = Signing in…
+account-log-out = Sign out
+account-failed = Could not sign in: { $reason }
+account-signed-in = Signed in with your atproto account.
+account-pds-sync = Keep reading progress, finished books and ratings on your PDS
+
+# Sharing
+share = Share
+share-title = Share to Bluesky
+share-attribution = — { $title } by { $author }
+share-attribution-title = — { $title }
+share-finished = Just finished reading { $title } by { $author }.
+share-finished-title = Just finished reading { $title }.
+share-length = { $length } of { $max } characters
+share-attach-cover = Attach the cover
+share-cover-alt = Cover of { $title }
+share-post = Post
+share-posting = Posting…
+share-posted = Shared to Bluesky
+share-failed = Could not post: { $reason }
+
+# Feed
+feed = Feed
+feed-placeholder = Profile or feed link, or empty for your timeline
+feed-timeline = Your timeline
+feed-app-author = Posts by the author of Libby. Sign in to see your timeline.
+feed-author = Posts by { $actor }
+feed-custom = Custom feed
+feed-reposted-by = Reposted by @{ $handle }
+feed-counts = 💬 { $replies }  🔁 { $reposts }  ♥ { $likes }
+feed-loading = Loading posts…
+feed-more = Load more
+feed-empty = No posts yet
+feed-end = You've reached the end of the feed
+feed-failed = Could not load posts: { $reason }
+
+# Following
+following = Following
+following-hint = Get a desktop notification when these accounts post.
+following-empty = You're not following anyone yet
+follow = Follow
+follow-placeholder = Handle, like alice.bsky.social
+follow-notify = Notify
+follow-new-post = New post by { $author }
+follow-new-posts = { $count } new posts by { $author }
+
+# Speed reading
+rsvp-progress = Word { $current } of { $total }
+rsvp-wpm = { $wpm } words per minute
+
+# Comics
+comic-panel = Page { $page }, panel { $panel } of { $panels }
+comic-right-to-left = Right to left
+comic-panels = Panel by panel
+
+# Tray
+tray = Show an icon in the panel's tray
+tray-close = Hide the window to the tray when it's closed
+tray-toggle = Show or hide window
+tray-continue = Continue reading { $title }
+tray-quit = Quit
+tray-failed = Couldn't show the tray icon, so closing the window quits Libby
+
+# Panel applet
+applet-streak = { $streak ->
+    [0] No reading streak
+    [one] Read 1 day in a row
+   *[other] Read { $streak } days in a row
+}
+applet-reading = { $title } · { $streak ->
+    [0] no streak
+    [one] 1 day
+   *[other] { $streak } days
+}
+
+# Notifications
+notifications = Notify when imports and syncs finish while the window is in the background
+notification-show-posts = Show posts
+notification-show-library = Show library
+
+# File chooser
+choose = Choose…
+file-dialog-books = Books
+file-dialog-certificates = Certificates
+file-dialog-exports = Exported libraries
+file-dialog-failed = Couldn't show the file chooser: { $reason }
+
+# Citations
+copy-citation = Copy citation
+copy-quote = Copy with source
+
+# Global shortcuts
+global-shortcuts = Summon Libby with shortcuts that work from anywhere
+global-summon = Show Libby
+global-search = Search the library
+global-summon-trigger = Keys to show Libby, such as LOGO+ALT+L
+global-search-trigger = Keys to search the library, such as LOGO+ALT+F
+global-shortcuts-hint = Press Enter to ask for the keys typed. The desktop may let you choose others.
+global-shortcuts-failed = Couldn't set up global shortcuts
+
+# Session restore
+restore-title = Restore previous session?
+restore-body = Libby didn't quit normally last time. You can go back to the book, page and text you had open.
+restore = Restore
+restore-dismiss = Start fresh
+
+# Reader windows
+read-in-window = Read in new window
+reader-window-title = { $title } — Libby
+
+# Header
+compact-header = Compact header, with the menus behind one button and no page title
+
+# Theme
+app-theme = Appearance
+app-theme-system = Match desktop
+app-theme-light = Light
+app-theme-dark = Dark
+
+# Power
+ignore-power-saving = Keep animations and background syncs at full speed on battery
+
+# Screen
+inhibit-reason = Reading
+
+# Recent books
+hide-recent-books = Leave recently read books out of the menu of the icon in the dock
+recent-continue = Continue: { $title }
+
+# Printing
+print = Print…
+print-report = Print reading report…
+printed = Sent to the printer
+print-failed = Couldn't print
+report-title = Reading report
+report-streak = Reading streak: { $streak ->
+    [one] { $streak } day
+   *[other] { $streak } days
+}
+report-finished = Books finished: { $count }
+report-reading = Reading
+report-finished-books = Finished
+report-book = { $title } — { $progress }%
+report-book-by = { $title } by { $author } — { $progress }%
+
+# Screen readers
+a11y-annotations = Annotations
+a11y-back = Back
+a11y-book = { $progress }% read
+a11y-book-by = By { $author }, { $progress }% read
+a11y-bookmarks = Bookmarks
+a11y-cancel = Cancel
+a11y-close = Close
+a11y-close-book = Close book
+a11y-collapse = Collapse
+a11y-contents = Contents
+a11y-continuous = Scroll continuously
+a11y-delete = Delete
+a11y-dialog-opened = { $title } dialog
+a11y-edit = Edit
+a11y-expand = Expand
+a11y-fullscreen = Fullscreen
+a11y-leave-fullscreen = Leave fullscreen
+a11y-next-chapter = Next chapter
+a11y-next-match = Next match
+a11y-next-page = Next page
+a11y-open-in-browser = Open in browser
+a11y-paginated = Turn pages
+a11y-palettes = Reading palettes
+a11y-page-shown = { $page } page
+a11y-pause = Pause
+a11y-play-pause = Play or pause
+a11y-previous-chapter = Previous chapter
+a11y-previous-match = Previous match
+a11y-previous-page = Previous page
+a11y-read-aloud = Read aloud
+a11y-refresh = Refresh
+a11y-remove = Remove
+a11y-resume = Resume
+a11y-retry = Retry
+a11y-rewind = Rewind
+a11y-search = Search in book
+a11y-share = Share
+a11y-single-pages = Single pages
+a11y-speed-reading = Speed reading
+a11y-spreads = Two-page spreads
+a11y-typography = Typography
+a11y-zoom-in = Zoom in
+a11y-zoom-out = Zoom out
+
+# Sounds
+sounds = Sounds
+sound-page-turn = Rustle when turning pages
+sound-imported = Chime when a book is imported
+sound-achievement = Fanfare when a book is finished
+
+# Scripting
+command-socket = Take commands from scripts over a local socket
+command-socket-path = Listening at { $path }
+
+# Profiling
+profiling-overlay = View { $view } ms, draw { $draw } ms, { $messages } messages/s
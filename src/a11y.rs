@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Names and descriptions read out by screen readers, for widgets whose purpose isn't
+//! spelled out by the text they show, such as buttons showing only an icon, and
+//! announcements of what changed after pressing them.
+//!
+//! The toolkit has no live regions, so announcements are spoken through speech-dispatcher,
+//! which screen readers such as Orca speak through too, while AT-SPI reports that one is
+//! running.
+
+use cosmic::widget::{self, icon};
+use futures_util::{SinkExt, Stream, StreamExt};
+use std::borrow::Cow;
+use std::process::Stdio;
+use tokio::process::Command;
+
+#[zbus::proxy(
+    interface = "org.a11y.Status",
+    default_service = "org.a11y.Bus",
+    default_path = "/org/a11y/bus",
+    gen_blocking = false
+)]
+trait Status {
+    #[zbus(property)]
+    fn screen_reader_enabled(&self) -> zbus::Result<bool>;
+}
+
+/// A button showing only an icon, named for screen readers by what it does.
+pub fn icon_button<'a, Message: Clone + 'static>(
+    icon_name: &'static str,
+    name: impl Into<Cow<'a, str>>,
+) -> widget::Button<'a, Message> {
+    widget::Button::from(widget::button::icon(icon::from_name(icon_name))).name(name)
+}
+
+/// A link, with a description read out after its label.
+pub fn described_link<'a, Message: Clone + 'static>(
+    label: impl Into<Cow<'a, str>>,
+    description: impl Into<Cow<'a, str>>,
+) -> widget::Button<'a, Message> {
+    widget::Button::from(widget::button::link(label)).description(description)
+}
+
+/// Yields whether a screen reader is running, then again whenever that changes.
+pub fn screen_reader() -> impl Stream<Item = bool> {
+    cosmic::iced::stream::channel(4, |mut output| async move {
+        let status = match zbus::Connection::session().await {
+            Ok(connection) => StatusProxy::new(&connection).await,
+            Err(why) => Err(why),
+        };
+
+        let status = match status {
+            Ok(status) => status,
+            Err(why) => {
+                tracing::error!("failed to watch for a screen reader: {why}");
+                return;
+            }
+        };
+
+        // Property streams yield the current value first, so this starts with it.
+        let mut changes = status.receive_screen_reader_enabled_changed().await;
+
+        while let Some(change) = changes.next().await {
+            let enabled = change.get().await.unwrap_or_default();
+
+            if output.send(enabled).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Speaks `text` straight away, as an assertive live region would, cutting off ordinary
+/// speech from the screen reader.
+pub async fn announce(text: String) {
+    let status = Command::new("spd-say")
+        .args(["--priority", "message", "--application-name", "libby", "--"])
+        .arg(text)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+
+    if let Err(why) = status {
+        tracing::error!("failed to announce to the screen reader: {why}");
+    }
+}
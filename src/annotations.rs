@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Writes a book's highlights and their notes out as Markdown or JSON, or lays them out
+//! for printing.
+
+use crate::library::Book;
+use crate::print::{Block, Document};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// File format of an annotations export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Json,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// Suggested destination for the book's annotations, in the user's documents folder.
+pub fn default_path(book: &Book, format: Format) -> PathBuf {
+    let title: String = book
+        .title
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let title = match title.trim() {
+        "" => "book",
+        title => title,
+    };
+
+    dirs::document_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join(format!("{title} annotations.{}", format.extension()))
+}
+
+/// Renders the book's highlights in the requested format.
+pub fn render(book: &Book, format: Format) -> String {
+    match format {
+        Format::Markdown => {
+            let mut out = format!("# {}\n", book.title);
+
+            if !book.author.is_empty() {
+                let _ = writeln!(out, "\n*{}*", book.author);
+            }
+
+            let mut chapter = None;
+
+            for highlight in &book.highlights {
+                if chapter != Some(highlight.location.chapter) {
+                    chapter = Some(highlight.location.chapter);
+                    let _ = writeln!(out, "\n## Chapter {}", highlight.location.chapter + 1);
+                }
+
+                out.push('\n');
+
+                for line in highlight.text.lines() {
+                    let _ = writeln!(out, "> {line}");
+                }
+
+                if !highlight.note.is_empty() {
+                    let _ = writeln!(out, "\n{}", highlight.note);
+                }
+            }
+
+            out
+        }
+
+        Format::Json => {
+            let value = serde_json::json!({
+                "title": book.title,
+                "author": book.author,
+                "highlights": book.highlights,
+            });
+
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        }
+    }
+}
+
+/// Lays the book's highlights out for printing, in the order of the Markdown export.
+pub fn document(book: &Book) -> Document {
+    let mut blocks = vec![Block::Title(book.title.clone())];
+
+    if !book.author.is_empty() {
+        blocks.push(Block::Text(book.author.clone()));
+    }
+
+    let mut chapter = None;
+
+    for highlight in &book.highlights {
+        if chapter != Some(highlight.location.chapter) {
+            chapter = Some(highlight.location.chapter);
+            blocks.push(Block::Heading(format!(
+                "Chapter {}",
+                highlight.location.chapter + 1
+            )));
+        }
+
+        blocks.push(Block::Quote(highlight.text.clone()));
+
+        if !highlight.note.is_empty() {
+            blocks.push(Block::Text(highlight.note.clone()));
+        }
+    }
+
+    Document {
+        title: book.title.clone(),
+        blocks,
+    }
+}
@@ -1,29 +1,159 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::config::Config;
+use crate::a11y;
+#[cfg(feature = "count-allocations")]
+use crate::alloc_count;
+use crate::annotations;
+use crate::atproto;
+use crate::bidi;
+use crate::bulk::{self, BulkAction};
+use crate::cache;
+use crate::catalog;
+use crate::citation;
+use crate::clipboard;
+use crate::collation::{self, Collation};
+use crate::comic::{self, Comic};
+use crate::companion;
+use crate::config::{AppTheme, Config, Spread};
+use crate::control::{self, Control};
+use crate::crash;
+use crate::dictionary;
+use crate::discover;
+use crate::download::{self, DownloadId};
+use crate::drag::BookFile;
+use crate::epub::{self, Chapter, Epub};
+use crate::export::{self, Field};
+use crate::file_dialog;
 use crate::fl;
+use crate::follow;
+use crate::global_shortcut;
+use crate::host::{self, Host};
+use crate::i18n;
+use crate::icons;
+use crate::import;
+use crate::inhibit;
+use crate::integrity;
+use crate::ipc;
+use crate::lan::{self, Lan};
+use crate::library::{self, Book, BookId, Bookmark, Flow, HighlightColor, Library, Location, Note};
+use crate::mpris::{self, Mpris};
+use crate::net::{self, Credentials};
+use crate::notify;
+use crate::opds;
+use crate::palette::{self, Palette};
+use crate::pdf;
+use crate::power::{self, Power};
+use crate::print;
+#[cfg(feature = "profiling")]
+use crate::profiling;
+use crate::quicklist;
+use crate::reader::{self, Appearance, ComicReader, EpubReader, PdfReader, Reader, Zoom};
+use crate::roaming;
+use crate::rss;
+use crate::rsvp;
+use crate::search;
+use crate::search_provider::{self, SearchProvider};
+use crate::secrets;
+use crate::session::{self, Session};
+use crate::share;
+use crate::shortcuts::{self, ReaderAction, SwipeTracker};
+use crate::sound::{self, Sound};
+use crate::sources::{self, Source, SourceKind};
+use crate::speech;
+use crate::sync;
+use crate::thumbnail;
+use crate::toc;
+use crate::translate;
+use crate::trash;
+use crate::tray;
+use crate::typography::{FontFamily, Typography};
+use crate::update;
+use crate::webdav;
+use crate::webhook::{self, Webhook};
+use crate::wishlist::Wishlist;
+use crate::worker::{self, Priority};
 use cosmic::app::context_drawer;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::alignment::{Horizontal, Vertical};
-use cosmic::iced::mouse;
-use cosmic::iced::widget::Stack;
-use cosmic::iced::{Alignment, Color, Length, Point, Rectangle, Subscription};
+use cosmic::iced::keyboard::{self, key::Named, Key, Modifiers};
+use cosmic::iced::widget::{qr_code, scrollable, text_editor, Stack};
+use cosmic::iced::{event, mouse, window};
+use cosmic::iced::{Alignment, Color, Length, Point, Rectangle, Size, Subscription};
 use cosmic::prelude::*;
 use cosmic::widget::canvas::{self, Frame, Geometry, Path};
 use cosmic::widget::{self, button, dialog, icon, menu, nav_bar};
 use cosmic::{cosmic_theme, theme};
-use futures_util::SinkExt;
-use std::collections::HashMap;
+use futures_util::{FutureExt, TryFutureExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
-const APP_ICON: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
 
-#[derive(Debug, Clone)]
-pub struct FixtureItem {
-    name: String,
-    description: String,
-}
+/// The app author's atproto account, whose posts the feed page shows until signed in.
+const AUTHOR_DID: &str = "did:plc:ngokl2gnmpbvuvrfckja3g7p";
+
+/// Distance scrolled up past the top of the feed which refreshes it.
+const PULL_TO_REFRESH: f32 = 120.0;
+
+/// Window width from which the reader shows two pages side by side.
+const SPREAD_WIDTH: f32 = 1400.0;
+
+/// Time between frames of animations.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Time between frames of animations while saving power.
+const SAVING_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the reading area and canvas take to fade between light and dark.
+const THEME_FADE: Duration = Duration::from_millis(400);
+
+/// Size the reader's own window opens at.
+const READER_WINDOW_WIDTH: f32 = 900.0;
+const READER_WINDOW_HEIGHT: f32 = 1000.0;
+
+/// Pages skipped by the chapter shortcuts in a PDF or comic.
+const PDF_CHAPTER_PAGES: usize = 10;
+
+/// Time without moving the mouse after which fullscreen reading hides its controls.
+const CONTROLS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Height of a row on the library page, fixed so that only the rows in view need to be
+/// built, however large the library.
+const LIBRARY_ROW_HEIGHT: f32 = 56.0;
+
+/// Width of the column marking where each letter starts in the sorted library.
+const LIBRARY_LETTER_WIDTH: f32 = 32.0;
+
+/// Rows on either side of those in view shown as placeholders, as the list draws a frame
+/// at its new offset before its rows are built again.
+const LIBRARY_OVERSCAN: usize = 8;
+
+/// Height of the library's list assumed until it's first scrolled, enough to fill most
+/// screens.
+const LIBRARY_INITIAL_HEIGHT: f32 = 1440.0;
+
+/// Most characters of a book's description shown on its row in the library.
+const LIBRARY_DESCRIPTION_LENGTH: usize = 120;
+
+/// How long settings must go unchanged before they are written out, so that dragging a
+/// slider or typing writes them once rather than at every step.
+const CONFIG_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// How long the catalog must go unchanged before it is saved, likewise so that typing a
+/// note or turning pages saves it once rather than at every key or page.
+const LIBRARY_SAVE_DELAY: Duration = Duration::from_millis(1000);
+
+/// Saves of the catalog started, counting up, so that one overtaken by a later save
+/// before its turn to be written is skipped.
+static LIBRARY_SAVES: AtomicU64 = AtomicU64::new(0);
+
+/// The last save of the catalog written, locked while writing so that writes don't
+/// overlap.
+static LIBRARY_WRITTEN: Mutex<u64> = Mutex::new(0);
 
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
@@ -38,13 +168,468 @@ pub struct AppModel {
     key_binds: HashMap<menu::KeyBind, MenuAction>,
     // Configuration data that persists between application runs.
     config: Config,
+    /// When the configuration last changed, while it waits to be written out.
+    config_changed: Option<Instant>,
+    /// When the catalog last changed, while it waits to be saved.
+    library_changed: Option<Instant>,
     /// Animation state for kawaii canvas
     animation_time: Instant,
+    /// Whether the app's theme is dark.
+    dark: bool,
+    /// How the computer is powered.
+    power: Power,
+    /// Whether a screen reader is running, to which changes are announced.
+    screen_reader: bool,
+    /// When the theme last switched between light and dark, while still fading.
+    theme_switched: Option<Instant>,
     show_popup: bool,
     search_expanded: bool,
     search_query: String,
     search_input_id: cosmic::iced::widget::text_input::Id,
-    fixture_data: Vec<FixtureItem>,
+    /// ID of the import dialog's address field, focused when the dialog opens.
+    import_input_id: cosmic::iced::widget::text_input::Id,
+    /// ID of the field searching the open book, focused when its drawer opens.
+    book_search_input_id: cosmic::iced::widget::text_input::Id,
+    /// The book catalog shown on the library page.
+    library: Library,
+    /// Whether the catalog has been read, which happens once the window is shown.
+    /// Until then it's empty, and isn't saved so as not to overwrite the real one.
+    library_loaded: bool,
+    /// Files asked to be opened before the catalog was read, to open once it is.
+    waiting_files: Vec<PathBuf>,
+    /// Books imported before the catalog was read, to add once it is.
+    waiting_imports: Vec<Result<Book, String>>,
+    /// When libby started, to measure how long starting takes.
+    started: Instant,
+    library_scroll_id: scrollable::Id,
+    /// How far the library's list is scrolled, and how much of it is in view.
+    library_offset: f32,
+    library_height: f32,
+    /// Order of the library's list.
+    library_sort: library::Sort,
+    /// How the library's titles and authors are grouped by letter.
+    collation: Collation,
+    /// Books selected for bulk actions.
+    selected: HashSet<BookId>,
+    /// Value used by the tag, series and collection bulk actions.
+    bulk_input: String,
+    /// The bulk action currently running in the background, if any.
+    bulk_job: Option<BulkJob>,
+    /// Toast notifications shown over the page content.
+    toasts: widget::toaster::Toasts<Message>,
+    /// The export dialog, while it is open.
+    export_dialog: Option<ExportDialog>,
+    /// Crash reports waiting for consent to be sent, while the dialog asking is open.
+    crash_dialog: Option<CrashDialog>,
+    /// The session of a run that ended unexpectedly, while the dialog offering to restore
+    /// it is open.
+    restore_dialog: Option<Session>,
+    /// The session as last written, so it's only written again once it changes.
+    session: Session,
+    /// Address typed into the dialog adding a book from the web, while it is open.
+    import_dialog: Option<String>,
+    /// Text of the note being written in the book details drawer.
+    note_input: String,
+    /// Label typed for the next bookmark.
+    bookmark_input: String,
+    /// Whether the reader's quick menu of palettes is open.
+    palette_menu: bool,
+    /// Width of the main window, for choosing when to show two-page spreads.
+    window_width: f32,
+    /// Hex colors typed for the custom palette, as foreground and background.
+    custom_palette_input: (String, String),
+    /// Fallback languages typed in the settings, which take effect once submitted.
+    language_fallback_input: String,
+    /// The library verification currently running in the background, if any.
+    verify_job: Option<VerifyJob>,
+    /// Books flagged by the last library verification.
+    verify_results: Vec<(BookId, integrity::Status)>,
+    /// How the last library verification went, once finished.
+    last_verify: Option<VerifySummary>,
+    /// The book open in the reader, if any.
+    reader: Option<Reader>,
+    /// Nav bar entry of the reader page while a book is open.
+    reader_nav: Option<nav_bar::Id>,
+    /// Window of its own the reader is shown in, instead of a page of the main window.
+    reader_window: Option<window::Id>,
+    /// Book given on the command line and the page to open it at, until it's opened.
+    start_page: Option<(PathBuf, usize)>,
+    /// Action waiting for a key or gesture to be bound to it.
+    capturing_shortcut: Option<ReaderAction>,
+    /// Recognizes touchpad swipes in the reader.
+    swipe: SwipeTracker,
+    /// Whether the reader's table of contents is shown.
+    toc_sidebar: bool,
+    /// Table of contents entries expanded or collapsed by the reader.
+    toc_toggled: HashSet<usize>,
+    /// Text typed into the search drawer of the reader.
+    book_search_input: String,
+    /// The last search within the open book, if any.
+    book_search: Option<BookSearch>,
+    /// Whether the reader fills the screen, without the header or navigation bar.
+    fullscreen: bool,
+    /// Whether the navigation bar was shown before entering fullscreen.
+    nav_bar_before_fullscreen: bool,
+    /// When the mouse last moved while reading fullscreen.
+    last_activity: Instant,
+    /// Whether fullscreen reading has hidden its controls and the cursor.
+    controls_hidden: bool,
+    /// The signed in atproto account, if any.
+    account: Option<Account>,
+    /// Handle or email typed into the accounts section.
+    account_identifier: String,
+    /// App password typed into the accounts section.
+    account_password: String,
+    /// Whether signing in is underway.
+    logging_in: bool,
+    /// Why signing in last failed.
+    account_error: Option<String>,
+    /// The dialog previewing a post, while it is open.
+    share_dialog: Option<ShareDialog>,
+    /// Posts shown on the feed page.
+    feed: FeedState,
+    /// Handle typed to follow an account.
+    follow_input: String,
+    /// Files being downloaded, shown in the downloads drawer.
+    downloads: download::Queue,
+    /// Running downloads, which are paused by dropping their handle.
+    download_tasks: HashMap<DownloadId, cosmic::iced::task::Handle>,
+    download_throttle: download::Throttle,
+    /// Address typed into the downloads drawer.
+    download_input: String,
+    /// Whether followed accounts are being checked for new posts.
+    polling_followed: bool,
+    /// Whether reading data is being synced with the PDS.
+    pds_syncing: bool,
+    /// Whether the catalog is being synced with the WebDAV server.
+    webdav_syncing: bool,
+    /// Password for the WebDAV server, once read from the keyring.
+    webdav_password: Option<String>,
+    /// Whether the network could be reached the last time it was used.
+    online: bool,
+    /// Why the proxy typed in Settings can't be used.
+    proxy_error: Option<String>,
+    /// Why the certificates chosen in Settings can't be used.
+    certificates_error: Option<String>,
+    /// Outcome of the last connection test, or `None` while one runs.
+    connection_test: Option<Option<Result<(), String>>>,
+    /// Bytes the cache of network responses takes up, once measured.
+    cache_size: Option<u64>,
+    /// Source being added or edited in the sources drawer.
+    source_form: Option<SourceForm>,
+    /// The webhook being added or edited in the webhooks context page.
+    webhook_form: Option<WebhookForm>,
+    /// Feeds of the news page, and the article being read.
+    news: NewsState,
+    /// Books to get someday.
+    wishlist: Wishlist,
+    /// The source browsed on the catalogs page.
+    catalog: CatalogState,
+    /// What is shown on the discovery page.
+    discover: DiscoverState,
+    /// The library shared on the local network, while sharing is on.
+    lan: Option<Lan>,
+    /// Other instances sharing their library on the local network.
+    peers: Vec<lan::Peer>,
+    /// The instance whose library is browsed on the nearby page.
+    peer: PeerState,
+    /// Books other instances offered to send, waiting to be accepted or declined.
+    offers: VecDeque<lan::Offer>,
+    /// Tokens other instances let this one browse with, by their address.
+    peer_tokens: HashMap<String, String>,
+    /// The instance asking to browse the library, for the user to let or turn away.
+    access_request: Option<lan::Request>,
+    /// Whether the window was hidden to the tray.
+    window_hidden: bool,
+    /// Whether the tray couldn't be shown, so closing the window quits after all.
+    tray_failed: bool,
+    /// Whether the window has keyboard focus, so notifications aren't needed.
+    focused: bool,
+    /// Keys asked for by the global shortcuts, copied from the configuration once edited
+    /// so they aren't bound again on every key typed.
+    global_triggers: (String, String),
+    /// Playback shown by the media controls while reading aloud.
+    mpris: Mpris,
+    /// Books the desktop's launcher can find.
+    search_provider: SearchProvider,
+    /// What is being read, as reported over the D-Bus control interface.
+    control: Control,
+    /// A release newer than the running version, once one is found.
+    available_update: Option<update::Release>,
+    /// Token from pairing with the companion server, once read from the keyring.
+    companion_token: Option<String>,
+    /// Pairing code typed into Settings.
+    companion_code: String,
+    /// Whether pairing with the companion server is underway.
+    companion_pairing: bool,
+    /// Why pairing last failed.
+    companion_error: Option<String>,
+    /// Whether the catalog is being synced with the companion server or host.
+    companion_syncing: bool,
+    /// The companion server this instance hosts, while hosting is on.
+    host: Option<Host>,
+    /// The pairing link as a QR code, while hosting.
+    host_qr: Option<qr_code::Data>,
+    /// Why other devices can't reach the host.
+    host_error: Option<String>,
+    /// The device which got the pairing code right, for the user to confirm.
+    pairing_request: Option<host::Request>,
+    /// Languages to choose from in the settings, by code, the first empty for the
+    /// system's.
+    languages: Vec<String>,
+    /// Names of [`Self::languages`] as shown in the settings.
+    language_names: Vec<String>,
+}
+
+/// Feeds of the news page, and the article being read.
+#[derive(Default)]
+struct NewsState {
+    /// Address typed to follow a feed.
+    input: String,
+    /// Fetched feeds, or why they couldn't be, by address.
+    feeds: HashMap<String, Result<rss::Feed, String>>,
+    /// Addresses of the feeds being fetched.
+    loading: HashSet<String>,
+    /// The feed whose articles are listed, or `None` for those of every feed.
+    filter: Option<String>,
+    /// The article being read, by the address of its feed and its ID.
+    article: Option<(String, String)>,
+    /// Which articles have been read.
+    read: rss::Read,
+    /// Why the feed typed couldn't be followed.
+    error: Option<String>,
+}
+
+/// Fields of a webhook being added or edited.
+#[derive(Default)]
+struct WebhookForm {
+    /// ID of the webhook being edited, or `None` for a new one.
+    id: Option<u64>,
+    name: String,
+    url: String,
+    events: HashSet<webhook::Event>,
+    template: text_editor::Content,
+    testing: bool,
+    /// Outcome of the last test.
+    test: Option<Result<(), String>>,
+}
+
+impl WebhookForm {
+    /// The webhook the form describes, under the given ID.
+    fn webhook(&self, id: u64) -> Webhook {
+        let url = self.url.trim().to_string();
+        let name = self.name.trim();
+
+        Webhook {
+            id,
+            name: if name.is_empty() {
+                url.clone()
+            } else {
+                name.to_string()
+            },
+            url,
+            events: webhook::Event::ALL
+                .into_iter()
+                .filter(|event| self.events.contains(event))
+                .collect(),
+            template: self.template.text(),
+            enabled: true,
+        }
+    }
+}
+
+/// Fields of a source being added or edited.
+#[derive(Default)]
+struct SourceForm {
+    /// ID of the source being edited, or `None` for a new one.
+    id: Option<u64>,
+    name: String,
+    url: String,
+    kind: SourceKind,
+    user: String,
+    /// Password typed into the form, or empty to keep the stored one.
+    password: String,
+    testing: bool,
+    /// Entries found by the last connection test, or why it failed.
+    test: Option<Result<usize, String>>,
+}
+
+impl SourceForm {
+    /// The source the form describes, under the given ID.
+    fn source(&self, id: u64) -> Source {
+        let url = self.url.trim().to_string();
+        let name = self.name.trim();
+
+        Source {
+            id,
+            name: if name.is_empty() {
+                url.clone()
+            } else {
+                name.to_string()
+            },
+            url,
+            kind: self.kind,
+            user: self.user.trim().to_string(),
+            enabled: true,
+        }
+    }
+}
+
+/// The source open on the catalogs page, and where within it.
+#[derive(Default)]
+struct CatalogState {
+    /// ID of the open source, or `None` to list the sources.
+    source: Option<u64>,
+    credentials: Option<Credentials>,
+    feed: Option<opds::Feed>,
+    /// Address of the page shown.
+    url: String,
+    /// Addresses of the pages gone through to reach this one.
+    history: Vec<String>,
+    /// Stops the fetch underway when dropped.
+    task: Option<cosmic::iced::task::Handle>,
+    /// Why the last fetch failed.
+    error: Option<String>,
+}
+
+/// The site browsed on the discovery page, and where within it.
+#[derive(Default)]
+struct DiscoverState {
+    /// Index of the site in [`discover::SITES`].
+    site: usize,
+    query: String,
+    feed: Option<opds::Feed>,
+    /// Addresses of the pages gone through to reach this one.
+    history: Vec<String>,
+    /// Address of the page shown.
+    url: String,
+    /// Stops the fetch underway when dropped.
+    task: Option<cosmic::iced::task::Handle>,
+    /// Why the last fetch failed.
+    error: Option<String>,
+    /// Covers fetched so far, by address.
+    covers: thumbnail::Cache,
+}
+
+/// The instance whose library is browsed on the nearby page.
+#[derive(Default)]
+struct PeerState {
+    /// ID of the instance, or `None` to list the instances.
+    id: Option<String>,
+    books: Option<Vec<lan::SharedBook>>,
+    /// Stops the fetch underway when dropped.
+    task: Option<cosmic::iced::task::Handle>,
+    /// Why the last fetch failed.
+    error: Option<String>,
+}
+
+/// Posts of the feed page, and the fetching of more.
+#[derive(Default)]
+struct FeedState {
+    posts: Vec<atproto::Post>,
+    /// Where the next page starts, or `None` at the end of the feed.
+    cursor: Option<String>,
+    /// Whether a page has been fetched since the feed was chosen.
+    loaded: bool,
+    /// Stops the fetch underway when dropped.
+    task: Option<cosmic::iced::task::Handle>,
+    /// Why the last fetch failed.
+    error: Option<String>,
+    /// Thumbnails fetched so far, by URL.
+    images: thumbnail::Cache,
+    /// Feed typed on the page, chosen once submitted.
+    input: String,
+    /// Whether the feed is scrolled to its top.
+    at_top: bool,
+    /// Distance scrolled up past the top, which refreshes the feed once far enough.
+    overscroll: f32,
+}
+
+/// A post being previewed before it is shared.
+struct ShareDialog {
+    book: BookId,
+    text: text_editor::Content,
+    /// The book's cover as a JPEG, once it has been rendered.
+    cover: Option<Vec<u8>>,
+    cover_handle: Option<widget::image::Handle>,
+    attach_cover: bool,
+    posting: bool,
+    /// Why posting last failed.
+    error: Option<String>,
+}
+
+/// An atproto account signed in from the accounts section.
+struct Account {
+    session: atproto::Session,
+    profile: Option<atproto::Profile>,
+    avatar: Option<widget::image::Handle>,
+}
+
+/// Tracks the progress of a running library verification.
+struct VerifyJob {
+    handle: cosmic::iced::task::Handle,
+    total: usize,
+    done: usize,
+    /// How many files were hashed, rather than taken as intact for being unchanged.
+    hashed: usize,
+    started: Instant,
+}
+
+/// What a finished library verification did, and how long it took.
+struct VerifySummary {
+    checked: usize,
+    hashed: usize,
+    duration: Duration,
+}
+
+/// Matches of a search within the open book, gathered as the search runs.
+struct BookSearch {
+    matches: Vec<search::Match>,
+    /// Index of the match last jumped to.
+    current: Option<usize>,
+    /// Stops the search when dropped; cleared once every chapter or page is searched.
+    task: Option<cosmic::iced::task::Handle>,
+}
+
+/// Crash reports from earlier runs, offered to be sent.
+struct CrashDialog {
+    reports: Vec<PathBuf>,
+    /// The latest report, shown for the user to see what would be sent.
+    preview: String,
+    sending: bool,
+    /// Why sending last failed.
+    error: Option<String>,
+}
+
+/// Choices made in the export dialog.
+struct ExportDialog {
+    format: export::Format,
+    fields: HashSet<Field>,
+    path: String,
+}
+
+/// What the app is doing at the moment, deciding which subscriptions run.
+struct Activity {
+    /// The window is on screen rather than hidden to the tray.
+    shown: bool,
+    /// Something animates where it can be seen.
+    animating: bool,
+    /// Background syncs and checks may run: the app is online and not saving power.
+    background: bool,
+    /// Something syncs with a server, paired devices or the local network.
+    syncing: bool,
+    /// Downloads are running or waiting to.
+    downloading: bool,
+}
+
+/// Tracks the progress of a running bulk action.
+struct BulkJob {
+    handle: cosmic::iced::task::Handle,
+    total: usize,
+    done: usize,
+    failed: usize,
+    /// Books moved into the trash by this job, offered for undo once it finishes.
+    trashed: Vec<BookId>,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -52,9 +637,10 @@ pub struct AppModel {
 pub enum Message {
     OpenRepositoryUrl,
     OpenAuthorUrl,
-    SubscriptionChannel,
     ToggleContextPage(ContextPage),
     TogglePopup,
+    /// A page picked from the nav bar drawn at the right edge.
+    NavSelect(nav_bar::Id),
     UpdateConfig(Config),
     LaunchUrl(String),
     Tick,
@@ -65,6 +651,718 @@ pub enum Message {
     SearchFocused,
     ClearSearch,
     ExpandSearch,
+    ToggleSelected(BookId, bool),
+    SelectAll,
+    ClearSelection,
+    BulkInput(String),
+    Bulk(BulkAction),
+    BulkProgress(bulk::Progress),
+    BulkFinished,
+    CancelBulk,
+    RestoreBooks(Vec<BookId>),
+    BooksRestored(Vec<Book>),
+    PurgeBooks(Vec<BookId>),
+    CloseToast(widget::toaster::ToastId),
+    SetTrashFiles(bool),
+    SetDictionaryOnline(bool),
+    SetTranslateUrl(String),
+    SetTranslateApiKey(String),
+    SetSyncFolder(String),
+    SetSyncResolution(sync::Resolution),
+    SyncPositions,
+    SetWebdavUrl(String),
+    SetWebdavUser(String),
+    SetWebdavPassword(String),
+    SetWebdavScheduled(bool),
+    SetUpdateCheck(update::Frequency),
+    SetLanguage(usize),
+    SetLanguageFallback(String),
+    /// Switches to the fallback languages once they're entered.
+    ApplyLanguageFallback,
+    SetLanSharing(bool),
+    LanEvent(lan::Event),
+    Discovered(lan::Discovery),
+    OpenPeer(String),
+    ClosePeer,
+    /// Books of the instance with this ID, along with the token they were listed for.
+    PeerBooks(String, Result<(String, Vec<lan::SharedBook>), String>),
+    DownloadFromPeer(String, lan::SharedBook),
+    SendToPeer(String, BookId),
+    SentToPeer(String, Result<(), String>),
+    AcceptOffer,
+    DeclineOffer,
+    /// Lets the instance asking browse the library, or turns it away.
+    ConfirmAccess(bool),
+    SetCrashReports(bool),
+    SetCrashEndpoint(String),
+    SetTray(bool),
+    SetHideRecentBooks(bool),
+    SetSoundPageTurn(bool),
+    SetSoundImported(bool),
+    SetSoundAchievement(bool),
+    SetCommandSocket(bool),
+    SetCloseToTray(bool),
+    Tray(tray::Action),
+    SetGlobalShortcuts(bool),
+    SetCompactHeader(bool),
+    SetAppTheme(AppTheme),
+    /// The system switched between light and dark.
+    SystemThemeMode(cosmic::cosmic_theme::ThemeMode),
+    SetSummonTrigger(String),
+    SetSearchTrigger(String),
+    /// Binds the global shortcuts again with the keys typed in the settings.
+    ApplyGlobalTriggers,
+    GlobalShortcut(global_shortcut::Shortcut),
+    CloseRequested(window::Id),
+    WindowFocused(bool),
+    SetNotifications(bool),
+    /// A desktop notification was clicked, to do what it offered.
+    NotificationClicked(Box<Message>),
+    CopyCitation(BookId, citation::Style),
+    /// Opens books from the file system, adding those not yet in the library.
+    OpenFiles(Vec<PathBuf>),
+    /// Reads the catalog again, after reading it failed.
+    LoadLibrary,
+    /// The catalog read after starting, unless reading it failed.
+    LibraryLoaded(Result<Library, String>),
+    FileOpened(Result<Book, String>),
+    /// Shows the file chooser for a file or folder.
+    ChooseFile(FileTarget),
+    FileChosen(FileTarget, Result<Vec<PathBuf>, String>),
+    Mpris(mpris::Command),
+    SearchProvider(search_provider::Command),
+    Control(control::Command),
+    SendCrashReports,
+    CrashReportsSent(Result<(), String>),
+    DismissCrashReports,
+    /// Writes where the user is, to restore if libby exits unexpectedly.
+    SaveSession,
+    /// Writes the settings out, once they have stopped changing.
+    WriteConfig,
+    /// Saves the catalog once it has stopped changing, or right away if set.
+    WriteLibrary(bool),
+    RestoreSession,
+    DismissSession,
+    /// Checks for a newer version, even if no check is due if set.
+    CheckForUpdates(bool),
+    UpdateChecked(bool, Result<Option<update::Release>, String>),
+    SyncWebdav,
+    WebdavSynced(Result<catalog::Outcome, String>),
+    WebdavPasswordLoaded(Result<Option<String>, String>),
+    SetCompanionUrl(String),
+    CompanionCode(String),
+    PairCompanion,
+    CompanionPaired(Result<String, String>),
+    CompanionTokenLoaded(Result<Option<String>, String>),
+    UnpairCompanion,
+    SyncCompanion,
+    CompanionSynced(Result<catalog::Outcome, String>),
+    SetCompanionHost(bool),
+    RenewPairingCode,
+    OpenPairing,
+    ClosePairing,
+    PairingTimedOut,
+    ConfirmPairing(bool),
+    ForgetCompanionDevice(usize),
+    HostEvent(host::Event),
+    PositionsPulled(Result<HashMap<String, sync::Record>, String>),
+    PositionsPushed(Result<(), String>),
+    AccountIdentifier(String),
+    AccountPassword(String),
+    LogIn,
+    LoggedIn(Result<atproto::Session, String>),
+    SessionResumed(Result<Option<atproto::Session>, String>),
+    ProfileLoaded(Result<atproto::Profile, String>),
+    LogOut,
+    ShareHighlight(usize),
+    ShareBook(BookId),
+    ShareEdit(text_editor::Action),
+    ShareCoverLoaded(BookId, Result<Option<Vec<u8>>, String>),
+    ShareAttachCover(bool),
+    SharePost,
+    SharePosted(Result<atproto::Session, String>),
+    CloseShareDialog,
+    LoadFeed(bool),
+    FeedLoaded(
+        bool,
+        Result<(atproto::FeedPage, Option<atproto::Session>), String>,
+    ),
+    FeedImageLoaded(String, Result<widget::image::Handle, String>),
+    FeedInput(String),
+    ChooseFeed,
+    FeedScrolled(scrollable::Viewport),
+    LibraryScrolled(scrollable::Viewport),
+    SetLibrarySort(library::Sort),
+    FeedWheel(f32),
+    SetPdsSync(bool),
+    PdsSynced(Result<(Vec<roaming::Update>, Option<atproto::Session>), String>),
+    ShowFeed(String),
+    DownloadInput(String),
+    Download(String),
+    DownloadProgress(DownloadId, download::Progress),
+    PauseDownload(DownloadId),
+    ResumeDownload(DownloadId),
+    CancelDownload(DownloadId),
+    ClearDownloads,
+    SetDownloadLimit(String),
+    SetCacheLimit(String),
+    /// Applies the cache limit once it's entered, deleting what's over it.
+    ApplyCacheLimit,
+    SetProxy(String),
+    SetCertificates(String),
+    TestConnection,
+    CacheSized(u64),
+    ClearCache,
+    CacheCleared(Result<(), String>),
+    ConnectionTested(Result<(), String>),
+    Online(bool),
+    PowerChanged(Power),
+    ScreenReaderChanged(bool),
+    SetIgnorePowerSaving(bool),
+    ProbeNetwork,
+    NewSource,
+    EditSource(u64),
+    SourceName(String),
+    SourceUrl(String),
+    SetSourceKind(SourceKind),
+    SourceUser(String),
+    SourcePassword(String),
+    TestSource,
+    SourceTested(Result<usize, String>),
+    SaveSource,
+    CancelSource,
+    RemoveSource(u64),
+    SetSourceEnabled(u64, bool),
+    NewsInput(String),
+    FollowNews,
+    UnfollowNews(String),
+    RefreshNews,
+    NewsFollowed(String, Result<rss::Feed, String>),
+    NewsFetched(String, Result<rss::Feed, String>),
+    FilterNews(Option<String>),
+    OpenArticle(String, String),
+    CloseArticle,
+    SetArticleRead(String, String, bool),
+    MarkNewsRead,
+    AddToWishlist(String, String),
+    RemoveFromWishlist(String),
+    NewWebhook,
+    EditWebhook(u64),
+    WebhookName(String),
+    WebhookUrl(String),
+    SetWebhookEvent(webhook::Event, bool),
+    WebhookTemplate(text_editor::Action),
+    TestWebhook,
+    WebhookTested(Result<(), String>),
+    SaveWebhook,
+    CancelWebhook,
+    RemoveWebhook(u64),
+    SetWebhookEnabled(u64, bool),
+    WebhookFired(String, Result<(), String>),
+    OpenCatalog(u64),
+    CatalogOpened(u64, Result<Option<Credentials>, String>),
+    BrowseCatalog(String),
+    CatalogBack,
+    CatalogMore,
+    CatalogLoaded(bool, Result<opds::Feed, String>),
+    DiscoverSite(usize),
+    DiscoverQuery(String),
+    DiscoverSearch,
+    DiscoverBrowse(String),
+    DiscoverBack,
+    DiscoverMore,
+    DiscoverLoaded(bool, Result<opds::Feed, String>),
+    DiscoverCoverLoaded(String, Result<widget::image::Handle, String>),
+    ImportBook(String, String),
+    CloseCatalog,
+    DownloadBook(String, String),
+    FollowInput(String),
+    Follow(String),
+    Unfollow(usize),
+    SetFollowNotify(usize, bool),
+    PollFollowed,
+    FollowedPolled(Vec<follow::Update>),
+    OpenExportDialog,
+    CloseExportDialog,
+    OpenImportDialog,
+    CloseImportDialog,
+    ImportUrl(String),
+    ImportFromUrl,
+    BookImported(Result<Book, String>),
+    ExportFormat(export::Format),
+    ExportField(Field, bool),
+    ExportPath(String),
+    Export,
+    Exported(Result<usize, String>),
+    NoteInput(String),
+    AddNote(BookId),
+    EditNote(BookId, usize, String),
+    DeleteNote(BookId, usize),
+    BookmarkInput(String),
+    AddBookmark,
+    GoToBookmark(usize),
+    DeleteBookmark(usize),
+    SelectBlock(usize),
+    HighlightNoteInput(String),
+    AddHighlight(HighlightColor),
+    CancelHighlight,
+    GoToHighlight(usize),
+    EditHighlightNote(usize, String),
+    DeleteHighlight(usize),
+    ExportAnnotations(annotations::Format),
+    AnnotationsExported(Result<PathBuf, String>),
+    PrintAnnotations,
+    PrintReport,
+    /// Whether the document was printed, or why it couldn't be.
+    Printed(Result<bool, String>),
+    ToggleReadAloud,
+    SpeechVoices(Result<Vec<String>, String>),
+    SpeechPlay,
+    SpeechRate(i32),
+    SpeechApply,
+    SpeechVoice(usize),
+    SpeechProgress(Result<usize, String>),
+    SpeechFinished,
+    ToggleRsvp,
+    RsvpPlay,
+    RsvpTick,
+    RsvpRewind,
+    RsvpWpm(u32),
+    SetTypography(Typography),
+    TypographyOverride(bool),
+    TogglePaletteMenu,
+    SetPalette(Palette),
+    CustomForeground(String),
+    CustomBackground(String),
+    ReaderSpreadScrolled(scrollable::Viewport),
+    ToggleSpread,
+    SetSpread(Spread),
+    SetFlow(Flow),
+    ReaderWheel(f32),
+    WindowResized(window::Id, f32),
+    /// Opens the book in a window of its own.
+    OpenReaderWindow(BookId),
+    LookUpBlock(usize),
+    LookUp(String),
+    DefinitionFound(String, Result<Option<dictionary::Definition>, String>),
+    CloseDefinition,
+    CopyDefinition,
+    /// Copies the selected passage followed by the book it's from.
+    CopyQuote,
+    TranslateSelection,
+    TranslationLanguages(Result<Vec<translate::Language>, String>),
+    TranslateSource(usize),
+    TranslateTarget(usize),
+    Translated(Result<String, String>),
+    CopyTranslation,
+    CloseTranslation,
+    OpenFootnote(String),
+    FootnoteLoaded(String, Result<Option<epub::Note>, String>),
+    GoToFootnote,
+    CloseFootnote,
+    ToggleFullscreen,
+    ReaderActivity,
+    HideReaderControls,
+    /// Checks the library's files, hashing even those unchanged when `true`.
+    VerifyLibrary(bool),
+    VerifyProgress(integrity::Report),
+    VerifyFinished,
+    CancelVerify,
+    RelocateBook(BookId, PathBuf),
+    RemoveMissingBook(BookId),
+    MissingBookRemoved(Box<Book>),
+    OpenReader(BookId),
+    ReaderOpened(BookId, Result<Epub, String>),
+    ReaderChapter(usize),
+    ReaderChapterLoaded(usize, f32, Result<Chapter, String>),
+    /// An image of the open chapter, by book, chapter and image, has been read.
+    ReaderImage(BookId, usize, usize, reader::Picture),
+    ReaderScrolled(scrollable::Viewport),
+    ReaderTurnPage(bool),
+    ToggleToc,
+    ToggleTocEntry(usize),
+    TocJump(usize),
+    BookSearchInput(String),
+    BookSearch,
+    BookSearchResults(Vec<search::Match>),
+    BookSearchFinished,
+    BookSearchGoTo(usize),
+    BookSearchStep(bool),
+    ReaderKey(Key, Modifiers),
+    ReaderSwipe(f32),
+    ReaderGesture(&'static str),
+    ReaderAction(ReaderAction),
+    CaptureShortcut(ReaderAction),
+    RemoveShortcut(String),
+    ResetShortcuts,
+    PdfOpened(BookId, PathBuf, Result<pdf::Document, String>),
+    PdfPage(usize),
+    PdfPageRendered(BookId, usize, Result<widget::image::Handle, String>),
+    PdfThumbnail(BookId, usize, widget::image::Handle),
+    PdfZoom(Zoom),
+    ComicOpened(BookId, Result<Comic, String>),
+    ComicPage(usize),
+    ComicPageLoaded(BookId, usize, Result<comic::Page, String>),
+    ComicZoom(Zoom),
+    ComicRightToLeft(bool),
+    ToggleComicPanels,
+    CloseReader,
+}
+
+impl Message {
+    /// The name of the variant, which crash reports list without the contents.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Message::OpenRepositoryUrl => "OpenRepositoryUrl",
+            Message::OpenAuthorUrl => "OpenAuthorUrl",
+            Message::ToggleContextPage(..) => "ToggleContextPage",
+            Message::TogglePopup => "TogglePopup",
+            Message::NavSelect(..) => "NavSelect",
+            Message::UpdateConfig(..) => "UpdateConfig",
+            Message::LaunchUrl(..) => "LaunchUrl",
+            Message::Tick => "Tick",
+            Message::GoToPage3 => "GoToPage3",
+            Message::UpdateUsername(..) => "UpdateUsername",
+            Message::SaveSettings => "SaveSettings",
+            Message::SearchChanged(..) => "SearchChanged",
+            Message::SearchFocused => "SearchFocused",
+            Message::ClearSearch => "ClearSearch",
+            Message::ExpandSearch => "ExpandSearch",
+            Message::ToggleSelected(..) => "ToggleSelected",
+            Message::SelectAll => "SelectAll",
+            Message::ClearSelection => "ClearSelection",
+            Message::BulkInput(..) => "BulkInput",
+            Message::Bulk(..) => "Bulk",
+            Message::BulkProgress(..) => "BulkProgress",
+            Message::BulkFinished => "BulkFinished",
+            Message::CancelBulk => "CancelBulk",
+            Message::RestoreBooks(..) => "RestoreBooks",
+            Message::BooksRestored(..) => "BooksRestored",
+            Message::PurgeBooks(..) => "PurgeBooks",
+            Message::CloseToast(..) => "CloseToast",
+            Message::SetTrashFiles(..) => "SetTrashFiles",
+            Message::SetDictionaryOnline(..) => "SetDictionaryOnline",
+            Message::SetTranslateUrl(..) => "SetTranslateUrl",
+            Message::SetTranslateApiKey(..) => "SetTranslateApiKey",
+            Message::SetSyncFolder(..) => "SetSyncFolder",
+            Message::SetSyncResolution(..) => "SetSyncResolution",
+            Message::SyncPositions => "SyncPositions",
+            Message::SetWebdavUrl(..) => "SetWebdavUrl",
+            Message::SetWebdavUser(..) => "SetWebdavUser",
+            Message::SetWebdavPassword(..) => "SetWebdavPassword",
+            Message::SetWebdavScheduled(..) => "SetWebdavScheduled",
+            Message::SetUpdateCheck(..) => "SetUpdateCheck",
+            Message::SetLanguage(..) => "SetLanguage",
+            Message::SetLanguageFallback(..) => "SetLanguageFallback",
+            Message::ApplyLanguageFallback => "ApplyLanguageFallback",
+            Message::SetLanSharing(..) => "SetLanSharing",
+            Message::LanEvent(..) => "LanEvent",
+            Message::Discovered(..) => "Discovered",
+            Message::OpenPeer(..) => "OpenPeer",
+            Message::ClosePeer => "ClosePeer",
+            Message::PeerBooks(..) => "PeerBooks",
+            Message::DownloadFromPeer(..) => "DownloadFromPeer",
+            Message::SendToPeer(..) => "SendToPeer",
+            Message::SentToPeer(..) => "SentToPeer",
+            Message::AcceptOffer => "AcceptOffer",
+            Message::DeclineOffer => "DeclineOffer",
+            Message::ConfirmAccess(..) => "ConfirmAccess",
+            Message::SetCrashReports(..) => "SetCrashReports",
+            Message::SetCrashEndpoint(..) => "SetCrashEndpoint",
+            Message::SetTray(..) => "SetTray",
+            Message::SetHideRecentBooks(..) => "SetHideRecentBooks",
+            Message::SetSoundPageTurn(..) => "SetSoundPageTurn",
+            Message::SetSoundImported(..) => "SetSoundImported",
+            Message::SetSoundAchievement(..) => "SetSoundAchievement",
+            Message::SetCommandSocket(..) => "SetCommandSocket",
+            Message::SetCloseToTray(..) => "SetCloseToTray",
+            Message::Tray(..) => "Tray",
+            Message::SetGlobalShortcuts(..) => "SetGlobalShortcuts",
+            Message::SetCompactHeader(..) => "SetCompactHeader",
+            Message::SetAppTheme(..) => "SetAppTheme",
+            Message::SystemThemeMode(..) => "SystemThemeMode",
+            Message::SetSummonTrigger(..) => "SetSummonTrigger",
+            Message::SetSearchTrigger(..) => "SetSearchTrigger",
+            Message::ApplyGlobalTriggers => "ApplyGlobalTriggers",
+            Message::GlobalShortcut(..) => "GlobalShortcut",
+            Message::CloseRequested(..) => "CloseRequested",
+            Message::WindowFocused(..) => "WindowFocused",
+            Message::SetNotifications(..) => "SetNotifications",
+            Message::NotificationClicked(..) => "NotificationClicked",
+            Message::CopyCitation(..) => "CopyCitation",
+            Message::OpenFiles(..) => "OpenFiles",
+            Message::LoadLibrary => "LoadLibrary",
+            Message::LibraryLoaded(..) => "LibraryLoaded",
+            Message::FileOpened(..) => "FileOpened",
+            Message::ChooseFile(..) => "ChooseFile",
+            Message::FileChosen(..) => "FileChosen",
+            Message::Mpris(..) => "Mpris",
+            Message::SearchProvider(..) => "SearchProvider",
+            Message::Control(..) => "Control",
+            Message::SendCrashReports => "SendCrashReports",
+            Message::CrashReportsSent(..) => "CrashReportsSent",
+            Message::DismissCrashReports => "DismissCrashReports",
+            Message::SaveSession => "SaveSession",
+            Message::WriteConfig => "WriteConfig",
+            Message::WriteLibrary(..) => "WriteLibrary",
+            Message::RestoreSession => "RestoreSession",
+            Message::DismissSession => "DismissSession",
+            Message::CheckForUpdates(..) => "CheckForUpdates",
+            Message::UpdateChecked(..) => "UpdateChecked",
+            Message::SyncWebdav => "SyncWebdav",
+            Message::WebdavSynced(..) => "WebdavSynced",
+            Message::WebdavPasswordLoaded(..) => "WebdavPasswordLoaded",
+            Message::SetCompanionUrl(..) => "SetCompanionUrl",
+            Message::CompanionCode(..) => "CompanionCode",
+            Message::PairCompanion => "PairCompanion",
+            Message::CompanionPaired(..) => "CompanionPaired",
+            Message::CompanionTokenLoaded(..) => "CompanionTokenLoaded",
+            Message::UnpairCompanion => "UnpairCompanion",
+            Message::SyncCompanion => "SyncCompanion",
+            Message::CompanionSynced(..) => "CompanionSynced",
+            Message::SetCompanionHost(..) => "SetCompanionHost",
+            Message::RenewPairingCode => "RenewPairingCode",
+            Message::OpenPairing => "OpenPairing",
+            Message::ClosePairing => "ClosePairing",
+            Message::PairingTimedOut => "PairingTimedOut",
+            Message::ConfirmPairing(..) => "ConfirmPairing",
+            Message::ForgetCompanionDevice(..) => "ForgetCompanionDevice",
+            Message::HostEvent(..) => "HostEvent",
+            Message::PositionsPulled(..) => "PositionsPulled",
+            Message::PositionsPushed(..) => "PositionsPushed",
+            Message::AccountIdentifier(..) => "AccountIdentifier",
+            Message::AccountPassword(..) => "AccountPassword",
+            Message::LogIn => "LogIn",
+            Message::LoggedIn(..) => "LoggedIn",
+            Message::SessionResumed(..) => "SessionResumed",
+            Message::ProfileLoaded(..) => "ProfileLoaded",
+            Message::LogOut => "LogOut",
+            Message::ShareHighlight(..) => "ShareHighlight",
+            Message::ShareBook(..) => "ShareBook",
+            Message::ShareEdit(..) => "ShareEdit",
+            Message::ShareCoverLoaded(..) => "ShareCoverLoaded",
+            Message::ShareAttachCover(..) => "ShareAttachCover",
+            Message::SharePost => "SharePost",
+            Message::SharePosted(..) => "SharePosted",
+            Message::CloseShareDialog => "CloseShareDialog",
+            Message::LoadFeed(..) => "LoadFeed",
+            Message::FeedLoaded(..) => "FeedLoaded",
+            Message::FeedImageLoaded(..) => "FeedImageLoaded",
+            Message::FeedInput(..) => "FeedInput",
+            Message::ChooseFeed => "ChooseFeed",
+            Message::FeedScrolled(..) => "FeedScrolled",
+            Message::LibraryScrolled(..) => "LibraryScrolled",
+            Message::SetLibrarySort(..) => "SetLibrarySort",
+            Message::FeedWheel(..) => "FeedWheel",
+            Message::SetPdsSync(..) => "SetPdsSync",
+            Message::PdsSynced(..) => "PdsSynced",
+            Message::ShowFeed(..) => "ShowFeed",
+            Message::DownloadInput(..) => "DownloadInput",
+            Message::Download(..) => "Download",
+            Message::DownloadProgress(..) => "DownloadProgress",
+            Message::PauseDownload(..) => "PauseDownload",
+            Message::ResumeDownload(..) => "ResumeDownload",
+            Message::CancelDownload(..) => "CancelDownload",
+            Message::ClearDownloads => "ClearDownloads",
+            Message::SetDownloadLimit(..) => "SetDownloadLimit",
+            Message::SetCacheLimit(..) => "SetCacheLimit",
+            Message::ApplyCacheLimit => "ApplyCacheLimit",
+            Message::SetProxy(..) => "SetProxy",
+            Message::SetCertificates(..) => "SetCertificates",
+            Message::TestConnection => "TestConnection",
+            Message::CacheSized(..) => "CacheSized",
+            Message::ClearCache => "ClearCache",
+            Message::CacheCleared(..) => "CacheCleared",
+            Message::ConnectionTested(..) => "ConnectionTested",
+            Message::Online(..) => "Online",
+            Message::PowerChanged(..) => "PowerChanged",
+            Message::ScreenReaderChanged(..) => "ScreenReaderChanged",
+            Message::SetIgnorePowerSaving(..) => "SetIgnorePowerSaving",
+            Message::ProbeNetwork => "ProbeNetwork",
+            Message::NewSource => "NewSource",
+            Message::EditSource(..) => "EditSource",
+            Message::SourceName(..) => "SourceName",
+            Message::SourceUrl(..) => "SourceUrl",
+            Message::SetSourceKind(..) => "SetSourceKind",
+            Message::SourceUser(..) => "SourceUser",
+            Message::SourcePassword(..) => "SourcePassword",
+            Message::TestSource => "TestSource",
+            Message::SourceTested(..) => "SourceTested",
+            Message::SaveSource => "SaveSource",
+            Message::CancelSource => "CancelSource",
+            Message::RemoveSource(..) => "RemoveSource",
+            Message::SetSourceEnabled(..) => "SetSourceEnabled",
+            Message::NewsInput(..) => "NewsInput",
+            Message::FollowNews => "FollowNews",
+            Message::UnfollowNews(..) => "UnfollowNews",
+            Message::RefreshNews => "RefreshNews",
+            Message::NewsFollowed(..) => "NewsFollowed",
+            Message::NewsFetched(..) => "NewsFetched",
+            Message::FilterNews(..) => "FilterNews",
+            Message::OpenArticle(..) => "OpenArticle",
+            Message::CloseArticle => "CloseArticle",
+            Message::SetArticleRead(..) => "SetArticleRead",
+            Message::MarkNewsRead => "MarkNewsRead",
+            Message::AddToWishlist(..) => "AddToWishlist",
+            Message::RemoveFromWishlist(..) => "RemoveFromWishlist",
+            Message::NewWebhook => "NewWebhook",
+            Message::EditWebhook(..) => "EditWebhook",
+            Message::WebhookName(..) => "WebhookName",
+            Message::WebhookUrl(..) => "WebhookUrl",
+            Message::SetWebhookEvent(..) => "SetWebhookEvent",
+            Message::WebhookTemplate(..) => "WebhookTemplate",
+            Message::TestWebhook => "TestWebhook",
+            Message::WebhookTested(..) => "WebhookTested",
+            Message::SaveWebhook => "SaveWebhook",
+            Message::CancelWebhook => "CancelWebhook",
+            Message::RemoveWebhook(..) => "RemoveWebhook",
+            Message::SetWebhookEnabled(..) => "SetWebhookEnabled",
+            Message::WebhookFired(..) => "WebhookFired",
+            Message::OpenCatalog(..) => "OpenCatalog",
+            Message::CatalogOpened(..) => "CatalogOpened",
+            Message::BrowseCatalog(..) => "BrowseCatalog",
+            Message::CatalogBack => "CatalogBack",
+            Message::CatalogMore => "CatalogMore",
+            Message::CatalogLoaded(..) => "CatalogLoaded",
+            Message::DiscoverSite(..) => "DiscoverSite",
+            Message::DiscoverQuery(..) => "DiscoverQuery",
+            Message::DiscoverSearch => "DiscoverSearch",
+            Message::DiscoverBrowse(..) => "DiscoverBrowse",
+            Message::DiscoverBack => "DiscoverBack",
+            Message::DiscoverMore => "DiscoverMore",
+            Message::DiscoverLoaded(..) => "DiscoverLoaded",
+            Message::DiscoverCoverLoaded(..) => "DiscoverCoverLoaded",
+            Message::ImportBook(..) => "ImportBook",
+            Message::CloseCatalog => "CloseCatalog",
+            Message::DownloadBook(..) => "DownloadBook",
+            Message::FollowInput(..) => "FollowInput",
+            Message::Follow(..) => "Follow",
+            Message::Unfollow(..) => "Unfollow",
+            Message::SetFollowNotify(..) => "SetFollowNotify",
+            Message::PollFollowed => "PollFollowed",
+            Message::FollowedPolled(..) => "FollowedPolled",
+            Message::OpenExportDialog => "OpenExportDialog",
+            Message::CloseExportDialog => "CloseExportDialog",
+            Message::OpenImportDialog => "OpenImportDialog",
+            Message::CloseImportDialog => "CloseImportDialog",
+            Message::ImportUrl(..) => "ImportUrl",
+            Message::ImportFromUrl => "ImportFromUrl",
+            Message::BookImported(..) => "BookImported",
+            Message::ExportFormat(..) => "ExportFormat",
+            Message::ExportField(..) => "ExportField",
+            Message::ExportPath(..) => "ExportPath",
+            Message::Export => "Export",
+            Message::Exported(..) => "Exported",
+            Message::NoteInput(..) => "NoteInput",
+            Message::AddNote(..) => "AddNote",
+            Message::EditNote(..) => "EditNote",
+            Message::DeleteNote(..) => "DeleteNote",
+            Message::BookmarkInput(..) => "BookmarkInput",
+            Message::AddBookmark => "AddBookmark",
+            Message::GoToBookmark(..) => "GoToBookmark",
+            Message::DeleteBookmark(..) => "DeleteBookmark",
+            Message::SelectBlock(..) => "SelectBlock",
+            Message::HighlightNoteInput(..) => "HighlightNoteInput",
+            Message::AddHighlight(..) => "AddHighlight",
+            Message::CancelHighlight => "CancelHighlight",
+            Message::GoToHighlight(..) => "GoToHighlight",
+            Message::EditHighlightNote(..) => "EditHighlightNote",
+            Message::DeleteHighlight(..) => "DeleteHighlight",
+            Message::ExportAnnotations(..) => "ExportAnnotations",
+            Message::AnnotationsExported(..) => "AnnotationsExported",
+            Message::PrintAnnotations => "PrintAnnotations",
+            Message::PrintReport => "PrintReport",
+            Message::Printed(..) => "Printed",
+            Message::ToggleReadAloud => "ToggleReadAloud",
+            Message::SpeechVoices(..) => "SpeechVoices",
+            Message::SpeechPlay => "SpeechPlay",
+            Message::SpeechRate(..) => "SpeechRate",
+            Message::SpeechApply => "SpeechApply",
+            Message::SpeechVoice(..) => "SpeechVoice",
+            Message::SpeechProgress(..) => "SpeechProgress",
+            Message::SpeechFinished => "SpeechFinished",
+            Message::ToggleRsvp => "ToggleRsvp",
+            Message::RsvpPlay => "RsvpPlay",
+            Message::RsvpTick => "RsvpTick",
+            Message::RsvpRewind => "RsvpRewind",
+            Message::RsvpWpm(..) => "RsvpWpm",
+            Message::SetTypography(..) => "SetTypography",
+            Message::TypographyOverride(..) => "TypographyOverride",
+            Message::TogglePaletteMenu => "TogglePaletteMenu",
+            Message::SetPalette(..) => "SetPalette",
+            Message::CustomForeground(..) => "CustomForeground",
+            Message::CustomBackground(..) => "CustomBackground",
+            Message::ReaderSpreadScrolled(..) => "ReaderSpreadScrolled",
+            Message::ToggleSpread => "ToggleSpread",
+            Message::SetSpread(..) => "SetSpread",
+            Message::SetFlow(..) => "SetFlow",
+            Message::ReaderWheel(..) => "ReaderWheel",
+            Message::WindowResized(..) => "WindowResized",
+            Message::OpenReaderWindow(..) => "OpenReaderWindow",
+            Message::LookUpBlock(..) => "LookUpBlock",
+            Message::LookUp(..) => "LookUp",
+            Message::DefinitionFound(..) => "DefinitionFound",
+            Message::CloseDefinition => "CloseDefinition",
+            Message::CopyDefinition => "CopyDefinition",
+            Message::CopyQuote => "CopyQuote",
+            Message::TranslateSelection => "TranslateSelection",
+            Message::TranslationLanguages(..) => "TranslationLanguages",
+            Message::TranslateSource(..) => "TranslateSource",
+            Message::TranslateTarget(..) => "TranslateTarget",
+            Message::Translated(..) => "Translated",
+            Message::CopyTranslation => "CopyTranslation",
+            Message::CloseTranslation => "CloseTranslation",
+            Message::OpenFootnote(..) => "OpenFootnote",
+            Message::FootnoteLoaded(..) => "FootnoteLoaded",
+            Message::GoToFootnote => "GoToFootnote",
+            Message::CloseFootnote => "CloseFootnote",
+            Message::ToggleFullscreen => "ToggleFullscreen",
+            Message::ReaderActivity => "ReaderActivity",
+            Message::HideReaderControls => "HideReaderControls",
+            Message::VerifyLibrary(..) => "VerifyLibrary",
+            Message::VerifyProgress(..) => "VerifyProgress",
+            Message::VerifyFinished => "VerifyFinished",
+            Message::CancelVerify => "CancelVerify",
+            Message::RelocateBook(..) => "RelocateBook",
+            Message::RemoveMissingBook(..) => "RemoveMissingBook",
+            Message::MissingBookRemoved(..) => "MissingBookRemoved",
+            Message::OpenReader(..) => "OpenReader",
+            Message::ReaderOpened(..) => "ReaderOpened",
+            Message::ReaderChapter(..) => "ReaderChapter",
+            Message::ReaderChapterLoaded(..) => "ReaderChapterLoaded",
+            Message::ReaderImage(..) => "ReaderImage",
+            Message::ReaderScrolled(..) => "ReaderScrolled",
+            Message::ReaderTurnPage(..) => "ReaderTurnPage",
+            Message::ToggleToc => "ToggleToc",
+            Message::ToggleTocEntry(..) => "ToggleTocEntry",
+            Message::TocJump(..) => "TocJump",
+            Message::BookSearchInput(..) => "BookSearchInput",
+            Message::BookSearch => "BookSearch",
+            Message::BookSearchResults(..) => "BookSearchResults",
+            Message::BookSearchFinished => "BookSearchFinished",
+            Message::BookSearchGoTo(..) => "BookSearchGoTo",
+            Message::BookSearchStep(..) => "BookSearchStep",
+            Message::ReaderKey(..) => "ReaderKey",
+            Message::ReaderSwipe(..) => "ReaderSwipe",
+            Message::ReaderGesture(..) => "ReaderGesture",
+            Message::ReaderAction(..) => "ReaderAction",
+            Message::CaptureShortcut(..) => "CaptureShortcut",
+            Message::RemoveShortcut(..) => "RemoveShortcut",
+            Message::ResetShortcuts => "ResetShortcuts",
+            Message::PdfOpened(..) => "PdfOpened",
+            Message::PdfPage(..) => "PdfPage",
+            Message::PdfPageRendered(..) => "PdfPageRendered",
+            Message::PdfThumbnail(..) => "PdfThumbnail",
+            Message::PdfZoom(..) => "PdfZoom",
+            Message::ComicOpened(..) => "ComicOpened",
+            Message::ComicPage(..) => "ComicPage",
+            Message::ComicPageLoaded(..) => "ComicPageLoaded",
+            Message::ComicZoom(..) => "ComicZoom",
+            Message::ComicRightToLeft(..) => "ComicRightToLeft",
+            Message::ToggleComicPanels => "ToggleComicPanels",
+            Message::CloseReader => "CloseReader",
+        }
+    }
 }
 
 /// Create a COSMIC application from the app model
@@ -73,7 +1371,7 @@ impl cosmic::Application for AppModel {
     type Executor = cosmic::executor::Default;
 
     /// Data that your application receives to its init method.
-    type Flags = ();
+    type Flags = Flags;
 
     /// Messages which the application and its widgets will emit.
     type Message = Message;
@@ -90,28 +1388,32 @@ impl cosmic::Application for AppModel {
     }
 
     /// Initializes the application with any given flags and startup commands.
-    fn init(
-        core: cosmic::Core,
-        _flags: Self::Flags,
-    ) -> (Self, Task<cosmic::Action<Self::Message>>) {
-        // Create a nav bar with three page items.
+    fn init(core: cosmic::Core, flags: Self::Flags) -> (Self, Task<cosmic::Action<Self::Message>>) {
+        // Create a nav bar with a page for each feature built in.
         let mut nav = nav_bar::Model::default();
 
-        nav.insert()
-            .text(fl!("page-id", num = 1))
-            .data::<Page>(Page::Page1)
-            .icon(icon::from_name("applications-science-symbolic"))
-            .activate();
+        let pages = [
+            (Page::Page1, "applications-science-symbolic"),
+            (Page::Page2, "applications-system-symbolic"),
+            (Page::Page3, "applications-games-symbolic"),
+            (Page::Feed, "emblem-shared-symbolic"),
+            (Page::Catalogs, "folder-remote-symbolic"),
+            (Page::Discover, "system-search-symbolic"),
+            (Page::News, "application-rss+xml-symbolic"),
+            (Page::Nearby, "network-workgroup-symbolic"),
+        ];
 
-        nav.insert()
-            .text(fl!("page-id", num = 2))
-            .data::<Page>(Page::Page2)
-            .icon(icon::from_name("applications-system-symbolic"));
+        // Pages of features left out of the build aren't offered.
+        for (page, icon_name) in pages.into_iter().filter(|(page, _)| page.built()) {
+            nav.insert()
+                .text(page.label().unwrap_or_default())
+                .data::<Page>(page)
+                .icon(icon::from_name(icon_name));
+        }
 
-        nav.insert()
-            .text(fl!("page-id", num = 3))
-            .data::<Page>(Page::Page3)
-            .icon(icon::from_name("applications-games-symbolic"));
+        if let Some(first) = nav.iter().next() {
+            nav.activate(first);
+        }
 
         // Construct the app model with the runtime's core.
         let mut app = AppModel {
@@ -132,72 +1434,246 @@ impl cosmic::Application for AppModel {
                     }
                 })
                 .unwrap_or_default(),
+            config_changed: None,
+            library_changed: None,
             animation_time: Instant::now(),
+            dark: theme::active().cosmic().is_dark,
+            power: Power::default(),
+            screen_reader: false,
+            theme_switched: None,
             show_popup: false,
             search_expanded: false,
             search_query: String::new(),
             search_input_id: cosmic::iced::widget::text_input::Id::unique(),
-            fixture_data: vec![
-                FixtureItem {
-                    name: "Apple".to_string(),
-                    description: "A sweet, red fruit".to_string(),
-                },
-                FixtureItem {
-                    name: "Banana".to_string(),
-                    description: "A long, yellow fruit".to_string(),
-                },
-                FixtureItem {
-                    name: "Orange".to_string(),
-                    description: "A round, orange fruit".to_string(),
-                },
-                FixtureItem {
-                    name: "test".to_string(),
-                    description: "ok".to_string(),
-                },
-            ],
+            import_input_id: cosmic::iced::widget::text_input::Id::unique(),
+            book_search_input_id: cosmic::iced::widget::text_input::Id::unique(),
+            library: Library::default(),
+            library_loaded: false,
+            waiting_files: Vec::new(),
+            waiting_imports: Vec::new(),
+            started: flags.started,
+            library_scroll_id: scrollable::Id::unique(),
+            library_offset: 0.0,
+            library_height: LIBRARY_INITIAL_HEIGHT,
+            library_sort: library::Sort::default(),
+            collation: Collation::new(),
+            selected: HashSet::new(),
+            bulk_input: String::new(),
+            bulk_job: None,
+            toasts: widget::toaster::Toasts::new(Message::CloseToast),
+            export_dialog: None,
+            import_dialog: None,
+            crash_dialog: None,
+            restore_dialog: None,
+            session: Session::default(),
+            note_input: String::new(),
+            bookmark_input: String::new(),
+            palette_menu: false,
+            window_width: 0.0,
+            custom_palette_input: (String::new(), String::new()),
+            language_fallback_input: String::new(),
+            verify_job: None,
+            verify_results: Vec::new(),
+            last_verify: None,
+            reader: None,
+            reader_nav: None,
+            reader_window: None,
+            start_page: None,
+            capturing_shortcut: None,
+            swipe: SwipeTracker::default(),
+            toc_sidebar: false,
+            toc_toggled: HashSet::new(),
+            book_search_input: String::new(),
+            book_search: None,
+            fullscreen: false,
+            nav_bar_before_fullscreen: false,
+            last_activity: Instant::now(),
+            controls_hidden: false,
+            account: None,
+            account_identifier: String::new(),
+            account_password: String::new(),
+            logging_in: false,
+            account_error: None,
+            share_dialog: None,
+            feed: FeedState::default(),
+            follow_input: String::new(),
+            downloads: download::Queue::default(),
+            download_tasks: HashMap::new(),
+            download_throttle: download::Throttle::new(0),
+            download_input: String::new(),
+            polling_followed: false,
+            pds_syncing: false,
+            webdav_syncing: false,
+            webdav_password: None,
+            online: true,
+            source_form: None,
+            webhook_form: None,
+            news: NewsState {
+                read: rss::Read::load(),
+                ..NewsState::default()
+            },
+            wishlist: Wishlist::load(),
+            catalog: CatalogState::default(),
+            discover: DiscoverState::default(),
+            available_update: None,
+            lan: None,
+            peers: Vec::new(),
+            peer: PeerState::default(),
+            offers: VecDeque::new(),
+            peer_tokens: HashMap::new(),
+            access_request: None,
+            window_hidden: false,
+            tray_failed: false,
+            focused: true,
+            global_triggers: Default::default(),
+            mpris: Mpris::new(),
+            search_provider: SearchProvider::default(),
+            control: Control::default(),
+            proxy_error: None,
+            certificates_error: None,
+            connection_test: None,
+            cache_size: None,
+            companion_token: None,
+            companion_code: String::new(),
+            companion_pairing: false,
+            companion_error: None,
+            companion_syncing: false,
+            host: None,
+            host_qr: None,
+            host_error: None,
+            pairing_request: None,
+            languages: std::iter::once(String::new())
+                .chain(i18n::available().iter().map(ToString::to_string))
+                .collect(),
+            language_names: Vec::new(),
         };
 
+        app.feed.input = app.config.feed.clone();
+        app.language_fallback_input = app.config.language_fallback.clone();
+        app.feed.at_top = true;
+        app.download_throttle.set_limit(app.config.download_limit);
+        cache::set_capacity(app.config.cache_limit);
+        app.apply_language();
+
+        if let Err(why) = net::set_proxy(&app.config.proxy) {
+            tracing::error!("failed to use proxy {}: {why}", app.config.proxy);
+        }
+
+        if let Err(why) = net::set_certificates(&app.config.certificates) {
+            tracing::error!(
+                "failed to trust certificates in {}: {why}",
+                app.config.certificates
+            );
+        }
+
+        app.global_triggers = (
+            app.config.summon_trigger.clone(),
+            app.config.search_trigger.clone(),
+        );
+
+        if app.config.companion_host {
+            app.host = Some(Host::load());
+            app.refresh_pairing();
+        }
+
         // Create a startup command that sets the window title.
-        let command = app.update_title();
+        let mut commands = vec![app.update_title(), app.apply_theme()];
+
+        // Read the catalog while the first frame is drawn, as the first page doesn't
+        // need it; the rest of starting up that does waits for it.
+        commands.push(load_library(Library::load));
+
+        // Sign back in to the atproto account from the last run.
+        commands.push(Task::perform(atproto::resume(), |result| {
+            cosmic::Action::App(Message::SessionResumed(result))
+        }));
+
+        commands.push(app.update(Message::CheckForUpdates(false)));
+
+        // The page asked for applies to the first book only.
+        app.start_page = flags
+            .page
+            .zip(flags.files.first().cloned())
+            .map(|(page, path)| (path, page));
+
+        if !flags.files.is_empty() {
+            commands.push(app.update(Message::OpenFiles(flags.files)));
+        }
+
+        if let Some(name) = flags.library {
+            let source = app
+                .config
+                .sources
+                .iter()
+                .find(|source| source.name.eq_ignore_ascii_case(name.trim()))
+                .map(|source| source.id);
+
+            match source {
+                Some(id) => {
+                    app.activate_page(Page::Catalogs);
+                    commands.push(app.update_title());
+                    commands.push(app.update(Message::OpenCatalog(id)));
+                }
+                None => tracing::warn!("no catalog is named {name}"),
+            }
+        }
+
+        if flags.fullscreen {
+            commands.push(app.set_fullscreen(true));
+        }
+
+        // Ask to send the reports of crashes since the last launch, if opted in to.
+        if app.config.crash_reports && !app.config.crash_endpoint.trim().is_empty() {
+            let reports = crash::pending();
+
+            if let Some(latest) = reports.last() {
+                app.crash_dialog = Some(CrashDialog {
+                    preview: std::fs::read_to_string(latest).unwrap_or_default(),
+                    reports,
+                    sending: false,
+                    error: None,
+                });
+            }
+        }
+
+        // Offer to go back to where the user was when the last run ended unexpectedly.
+        app.restore_dialog = session::load().filter(|session| !session.is_empty());
 
-        (app, command)
+        commands.push(Task::perform(
+            secrets::load(webdav::PASSWORD_KEY.to_string()),
+            |result| cosmic::Action::App(Message::WebdavPasswordLoaded(result)),
+        ));
+
+        if !app.config.companion_url.is_empty() {
+            commands.push(Task::perform(
+                secrets::load(companion::TOKEN_KEY.to_string()),
+                |result| cosmic::Action::App(Message::CompanionTokenLoaded(result)),
+            ));
+        }
+
+        if cfg!(debug_assertions) {
+            tracing::debug!("ready to draw {:?} after starting", app.started.elapsed());
+        }
+
+        (app, Task::batch(commands))
     }
 
-    /// Elements to pack at the start of the header bar.
+    /// Elements to pack at the start of the header bar, the menus where lines run left
+    /// to right.
     fn header_start(&self) -> Vec<Element<Self::Message>> {
-        let menu_bar = menu::bar(vec![menu::Tree::with_children(
-            menu::root(fl!("view")).apply(Element::from),
-            menu::items(
-                &self.key_binds,
-                vec![
-                    menu::Item::Button(fl!("about"), None, MenuAction::About),
-                    menu::Item::Button("Settings".to_string(), None, MenuAction::Settings),
-                ],
-            ),
-        )]);
-
-        vec![menu_bar.into()]
+        if i18n::is_rtl() {
+            self.header_actions()
+        } else {
+            self.header_menus()
+        }
     }
 
-    /// Elements to pack at the end of the header bar.
+    /// Elements to pack at the end of the header bar, mirroring the start.
     fn header_end(&self) -> Vec<Element<Self::Message>> {
-        if self.search_expanded {
-            let search_input = widget::text_input::search_input("Search...", &self.search_query)
-                .on_input(Message::SearchChanged)
-                .on_clear(Message::ClearSearch)
-                .id(self.search_input_id.clone().into())
-                .width(Length::Fixed(200.0));
-
-            vec![search_input.into()]
+        if i18n::is_rtl() {
+            self.header_menus()
         } else {
-            // Show just the search icon
-            let search_icon = icon::from_name("system-search-symbolic")
-                .size(16)
-                .apply(widget::button::custom)
-                .on_press(Message::ExpandSearch)
-                .padding(8);
-
-            vec![search_icon.into()]
+            self.header_actions()
         }
     }
 
@@ -206,6 +1682,26 @@ impl cosmic::Application for AppModel {
         Some(&self.nav)
     }
 
+    /// The nav bar at the start of the window, left out when it belongs at the end.
+    fn nav_bar(&self) -> Option<Element<cosmic::Action<Self::Message>>> {
+        if !self.core.nav_bar_active() || bidi::nav_trailing(self.core.is_condensed()) {
+            return None;
+        }
+
+        let mut nav = widget::nav_bar(&self.nav, |id| {
+            cosmic::Action::Cosmic(cosmic::app::Action::NavBar(id))
+        })
+        .into_container()
+        .width(Length::Shrink)
+        .height(Length::Fill);
+
+        if !self.core.is_condensed() {
+            nav = nav.max_width(280);
+        }
+
+        Some(nav.into())
+    }
+
     /// Display a context drawer if the context page is requested.
     fn context_drawer(&self) -> Option<context_drawer::ContextDrawer<Self::Message>> {
         if !self.core.window.show_context {
@@ -222,7 +1718,77 @@ impl cosmic::Application for AppModel {
                 self.settings(),
                 Message::ToggleContextPage(ContextPage::Settings),
             )
-            .title("Settings"),
+            .title(fl!("settings")),
+            ContextPage::Trash => context_drawer::context_drawer(
+                self.trash(),
+                Message::ToggleContextPage(ContextPage::Trash),
+            )
+            .title(fl!("trash")),
+            ContextPage::Verify => context_drawer::context_drawer(
+                self.verify(),
+                Message::ToggleContextPage(ContextPage::Verify),
+            )
+            .title(fl!("verify-library")),
+            ContextPage::Bookmarks => context_drawer::context_drawer(
+                self.bookmarks(),
+                Message::ToggleContextPage(ContextPage::Bookmarks),
+            )
+            .title(fl!("bookmarks")),
+            ContextPage::Annotations => context_drawer::context_drawer(
+                self.annotations(),
+                Message::ToggleContextPage(ContextPage::Annotations),
+            )
+            .title(fl!("annotations")),
+            ContextPage::Typography => context_drawer::context_drawer(
+                self.typography_settings(),
+                Message::ToggleContextPage(ContextPage::Typography),
+            )
+            .title(fl!("typography")),
+            ContextPage::Shortcuts => context_drawer::context_drawer(
+                self.shortcuts(),
+                Message::ToggleContextPage(ContextPage::Shortcuts),
+            )
+            .title(fl!("shortcuts")),
+            ContextPage::Downloads => context_drawer::context_drawer(
+                self.downloads(),
+                Message::ToggleContextPage(ContextPage::Downloads),
+            )
+            .title(fl!("downloads")),
+            ContextPage::Sources => context_drawer::context_drawer(
+                self.sources(),
+                Message::ToggleContextPage(ContextPage::Sources),
+            )
+            .title(fl!("sources")),
+            ContextPage::Webhooks => context_drawer::context_drawer(
+                self.webhooks(),
+                Message::ToggleContextPage(ContextPage::Webhooks),
+            )
+            .title(fl!("webhooks")),
+            ContextPage::Wishlist => context_drawer::context_drawer(
+                self.wishlist(),
+                Message::ToggleContextPage(ContextPage::Wishlist),
+            )
+            .title(fl!("wishlist")),
+            ContextPage::Following => context_drawer::context_drawer(
+                self.following(),
+                Message::ToggleContextPage(ContextPage::Following),
+            )
+            .title(fl!("following")),
+            ContextPage::Search => context_drawer::context_drawer(
+                self.book_search(),
+                Message::ToggleContextPage(ContextPage::Search),
+            )
+            .title(fl!("search-book")),
+            ContextPage::BookDetails(id) => context_drawer::context_drawer(
+                self.book_details(id),
+                Message::ToggleContextPage(ContextPage::BookDetails(id)),
+            )
+            .title(
+                self.library
+                    .get(id)
+                    .map(|book| book.title.clone())
+                    .unwrap_or_default(),
+            ),
         })
     }
 
@@ -231,24 +1797,28 @@ impl cosmic::Application for AppModel {
     /// Application events will be processed through the view. Any messages emitted by
     /// events received by widgets will be passed to the update method.
     fn view(&self) -> Element<Self::Message> {
+        #[cfg(feature = "profiling")]
+        let _span = tracing::info_span!("view").entered();
+        #[cfg(feature = "profiling")]
+        let started = Instant::now();
+
         let active_page = self
             .nav
             .data::<Page>(self.nav.active())
             .copied()
             .unwrap_or(Page::Page1);
 
-        match active_page {
+        let page: Element<Self::Message> = match active_page {
             Page::Page1 => {
-                let canvas = cosmic::widget::canvas(KawaiiCanvas::new(self.animation_time))
-                    .width(Length::Fill)
-                    .height(Length::Fill);
+                let canvas =
+                    cosmic::widget::canvas(KawaiiCanvas::new(self.animation_time, self.darkness()))
+                        .width(Length::Fill)
+                        .height(Length::Fill);
 
                 let text_content = widget::column()
-                    .push(widget::text::title1("Welcome to the Kawaii Canvas!"))
-                    .push(widget::text(
-                        "Move your mouse around to see the shapes react.",
-                    ))
-                    .push(widget::button::standard("Click me").on_press(Message::TogglePopup))
+                    .push(widget::text::title1(fl!("canvas-welcome")))
+                    .push(widget::text(fl!("canvas-hint")))
+                    .push(widget::button::standard(fl!("click-me")).on_press(Message::TogglePopup))
                     .spacing(10)
                     .padding(20)
                     .align_x(Horizontal::Center)
@@ -265,32 +1835,43 @@ impl cosmic::Application for AppModel {
                 stack.into()
             }
             Page::Page2 => {
-                let display_username = if self.config.username.is_empty() {
+                let display_username = if let Some(account) = &self.account {
+                    account.session.handle.clone()
+                } else if self.config.username.is_empty() {
                     // Fallback to OS username
                     std::env::var("USER")
                         .or_else(|_| std::env::var("USERNAME"))
-                        .unwrap_or_else(|_| "Unknown User".to_string())
+                        .unwrap_or_else(|_| fl!("unknown-user"))
                 } else {
                     self.config.username.clone()
                 };
 
                 let username_text =
-                    widget::text::title2(format!("Welcome back, {}!", display_username));
-                let info_text = if self.config.username.is_empty() {
-                    widget::text("Using OS username. Go to Settings in the View menu to set a custom username.")
+                    widget::text::title2(fl!("welcome-back", name = display_username));
+                let info_text = if self.account.is_some() {
+                    widget::text(fl!("account-signed-in"))
+                } else if self.config.username.is_empty() {
+                    widget::text(fl!("username-from-system"))
                 } else {
-                    widget::text("Go to Settings in the View menu to update your username")
+                    widget::text(fl!("username-hint"))
                 };
 
+                let avatar = self
+                    .account
+                    .as_ref()
+                    .and_then(|account| account.avatar.clone())
+                    .map(|avatar| widget::image(avatar).width(64).height(64));
+
                 widget::column()
-                    .push(widget::text::title1("Page 2 Content"))
+                    .push(widget::text::title1(fl!("page-two-title")))
                     .push(widget::vertical_space().height(10))
+                    .push_maybe(avatar)
                     .push(username_text)
                     .push(widget::vertical_space().height(5))
                     .push(info_text)
                     .push(widget::vertical_space().height(20))
-                    .push(widget::text("This is page 2 with custom content!"))
-                    .push(widget::button::standard("Click me").on_press(Message::GoToPage3))
+                    .push(widget::text(fl!("page-two-body")))
+                    .push(widget::button::standard(fl!("click-me")).on_press(Message::GoToPage3))
                     .spacing(10)
                     .apply(widget::container)
                     .width(Length::Fill)
@@ -299,87 +1880,57 @@ impl cosmic::Application for AppModel {
                     .align_y(Vertical::Center)
                     .into()
             }
-            Page::Page3 => {
-                let mut col = widget::column().push(widget::text::title1("Page 3"));
+            Page::Page3 => self.library_page(),
+            Page::Feed => self.feed_page(),
+            Page::Catalogs => self.catalogs_page(),
+            Page::Discover => self.discover_page(),
+            Page::News => self.news_page(),
+            Page::Nearby => self.nearby_page(),
+            Page::Reader => self.reader_view().unwrap_or_else(|| self.library_page()),
+        };
 
-                if self.search_query.is_empty() {
-                    for item in &self.fixture_data {
-                        col = col.push(widget::text(&item.name));
-                        col = col.push(widget::text(&item.description));
-                    }
-                } else {
-                    let filtered_data: Vec<_> = self
-                        .fixture_data
-                        .iter()
-                        .filter(|item| {
-                            item.name
-                                .to_lowercase()
-                                .contains(&self.search_query.to_lowercase())
-                                || item
-                                    .description
-                                    .to_lowercase()
-                                    .contains(&self.search_query.to_lowercase())
-                        })
-                        .collect();
-
-                    if filtered_data.is_empty() {
-                        col = col.push(widget::vertical_space().height(20));
-                        col = col.push(widget::text::title3("🔍 No results found"));
-                        col = col.push(widget::text(format!(
-                            "No items match \"{}\"",
-                            self.search_query
-                        )));
-                        col = col.push(widget::text("Try a different search term"));
-                    } else {
-                        for item in filtered_data {
-                            col = col.push(widget::text(&item.name));
-                            col = col.push(widget::text(&item.description));
-                        }
-                    }
-                }
+        #[cfg(feature = "profiling")]
+        let page = {
+            profiling::record(profiling::Phase::View, started.elapsed());
+            profiling_overlay(page)
+        };
 
-                col.spacing(10)
-                    .apply(widget::container)
-                    .width(Length::Fill)
-                    .height(Length::Fill)
-                    .align_x(Horizontal::Center)
-                    .align_y(Vertical::Center)
-                    .into()
-            }
-        }
+        // Right-to-left languages read the nav bar from the right edge of the window.
+        let page = if self.core.nav_bar_active() && bidi::nav_trailing(self.core.is_condensed()) {
+            let nav = widget::nav_bar(&self.nav, Message::NavSelect)
+                .into_container()
+                .width(Length::Shrink)
+                .height(Length::Fill)
+                .max_width(280);
+
+            widget::row()
+                .push(widget::container(page).width(Length::Fill))
+                .push(nav)
+                .spacing(8)
+                .into()
+        } else {
+            page
+        };
+
+        widget::toaster(&self.toasts, page)
     }
 
     /// Register subscriptions for this application.
     ///
     /// Subscriptions are long-running async tasks running in the background which
-    /// emit messages to the application through a channel. They are started at the
-    /// beginning of the application, and persist through its lifetime.
+    /// emit messages to the application through a channel. Only those for what the app
+    /// is doing at the moment run, so that nothing wakes it up for features which are
+    /// off or idle.
     fn subscription(&self) -> Subscription<Self::Message> {
-        struct MySubscription;
+        let activity = self.activity();
 
-        Subscription::batch(vec![
-            // Create a subscription which emits updates through a channel.
-            Subscription::run_with_id(
-                std::any::TypeId::of::<MySubscription>(),
-                cosmic::iced::stream::channel(4, move |mut channel| async move {
-                    _ = channel.send(Message::SubscriptionChannel).await;
+        let mut subscriptions = self.system_subscriptions(&activity);
+        subscriptions.extend(self.input_subscriptions());
+        subscriptions.extend(self.sync_subscriptions(&activity));
+        subscriptions.extend(self.desktop_subscriptions());
+        subscriptions.extend(self.timer_subscriptions(&activity));
 
-                    futures_util::future::pending().await
-                }),
-            ),
-            // Animation timer for kawaii canvas
-            cosmic::iced::time::every(Duration::from_millis(16)).map(|_| Message::Tick),
-            // Watch for application configuration changes.
-            self.core()
-                .watch_config::<Config>(Self::APP_ID)
-                .map(|update| {
-                    // for why in update.errors {
-                    //     tracing::error!(?why, "app config error");
-                    // }
-
-                    Message::UpdateConfig(update.config)
-                }),
-        ])
+        Subscription::batch(subscriptions)
     }
 
     /// Handles messages emitted by the application and its widgets.
@@ -387,25 +1938,29 @@ impl cosmic::Application for AppModel {
     /// Tasks may be returned for asynchronous execution of code in the background
     /// on the application's async runtime.
     fn update(&mut self, message: Self::Message) -> Task<cosmic::Action<Self::Message>> {
+        crash::record(message.name());
+
+        #[cfg(feature = "profiling")]
+        let _span = tracing::info_span!("update").entered();
+        #[cfg(feature = "profiling")]
+        profiling::message();
+
         match message {
             Message::OpenRepositoryUrl => {
                 _ = open::that_detached(REPOSITORY);
             }
             Message::OpenAuthorUrl => {
-                _ = open::that_detached(
-                    "https://deer.social/profile/did:plc:ngokl2gnmpbvuvrfckja3g7p",
-                );
-            }
-
-            Message::SubscriptionChannel => {
-                println!("button clicked");
-                // For example purposes only.
+                _ = open::that_detached(format!("https://deer.social/profile/{AUTHOR_DID}"));
             }
 
             Message::TogglePopup => {
                 self.show_popup = !self.show_popup;
             }
 
+            Message::NavSelect(id) => {
+                return self.on_nav_select(id);
+            }
+
             Message::ToggleContextPage(context_page) => {
                 if self.context_page == context_page {
                     // Close the context drawer if the toggled context page is the same.
@@ -415,20 +1970,75 @@ impl cosmic::Application for AppModel {
                     self.context_page = context_page;
                     self.core.window.show_context = true;
                 }
+
+                // Pairing is only possible while its code is shown in the settings.
+                if !(self.core.window.show_context && self.context_page == ContextPage::Settings) {
+                    self.close_pairing();
+                }
+
+                if self.core.window.show_context && self.context_page == ContextPage::Settings {
+                    return Task::perform(cache::size(), |size| {
+                        cosmic::Action::App(Message::CacheSized(size))
+                    });
+                }
+
+                if self.core.window.show_context && self.context_page == ContextPage::Search {
+                    return focus_field(self.book_search_input_id.clone());
+                }
             }
 
             Message::UpdateConfig(config) => {
+                // Changes made here and not yet written out win over what's on disk,
+                // which may be no more than an earlier write coming back.
+                if self.config_changed.is_some() {
+                    return Task::none();
+                }
+
+                self.download_throttle.set_limit(config.download_limit);
+
+                if config.cache_limit != self.config.cache_limit {
+                    cache::set_capacity(config.cache_limit);
+                }
+
+                if config.proxy != self.config.proxy {
+                    self.proxy_error = net::set_proxy(&config.proxy).err();
+                }
+
+                if config.certificates != self.config.certificates {
+                    self.certificates_error = net::set_certificates(&config.certificates).err();
+                }
+
+                if config.language_fallback != self.config.language_fallback {
+                    self.language_fallback_input = config.language_fallback.clone();
+                }
+
+                let language_changed = config.language != self.config.language
+                    || config.language_fallback != self.config.language_fallback;
                 self.config = config;
+
+                if language_changed {
+                    self.apply_language();
+                    return Task::batch([self.apply_spread(), self.update_title()]);
+                }
+
+                return self.apply_spread();
             }
 
             Message::LaunchUrl(url) => match open::that_detached(&url) {
-                Ok(()) => {}
+                Ok(()) => tracing::debug!(%url, "opened the link"),
                 Err(err) => {
-                    eprintln!("failed to open {url:?}: {err}");
+                    tracing::error!(%url, "failed to open the link: {err}");
                 }
             },
 
-            Message::Tick => {}
+            Message::Tick => {
+                if self
+                    .theme_switched
+                    .is_some_and(|switched| switched.elapsed() >= THEME_FADE)
+                {
+                    self.theme_switched = None;
+                }
+            }
 
             Message::GoToPage3 => {
                 // Find the nav ID for page 3
@@ -439,7 +2049,7 @@ impl cosmic::Application for AppModel {
 
                 if let Some(id) = page3_id {
                     self.nav.activate(id);
-                    return self.update_title();
+                    return self.page_shown();
                 }
             }
 
@@ -448,15 +2058,15 @@ impl cosmic::Application for AppModel {
             }
 
             Message::SaveSettings => {
-                // Save config to persistent storage
-                if let Ok(config_context) =
-                    cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-                {
-                    let _ = self.config.write_entry(&config_context);
+                self.save_config();
+
+                if let Some(password) = &self.webdav_password {
+                    return store_webdav_password(password.clone());
                 }
             }
             Message::SearchChanged(query) => {
                 self.search_query = query;
+                let to_top = self.scroll_library_to_top();
                 let page3_id = self
                     .nav
                     .iter()
@@ -465,125 +2075,9012 @@ impl cosmic::Application for AppModel {
                 if let Some(id) = page3_id {
                     let id = id.clone();
                     self.nav.activate(id);
-                    return self.update_title();
+                    return Task::batch([to_top, self.update_title()]);
                 }
+
+                return to_top;
             }
             Message::SearchFocused => {}
             Message::ClearSearch => {
                 self.search_query.clear();
                 self.search_expanded = false; // Collapse back to icon
+                return self.scroll_library_to_top();
             }
             Message::ExpandSearch => {
                 self.search_expanded = true;
-                return Task::batch([
-                    cosmic::iced::widget::text_input::focus(self.search_input_id.clone()),
-                    cosmic::iced::widget::text_input::select_all(self.search_input_id.clone()),
-                ]);
+                return focus_field(self.search_input_id.clone());
             }
-        }
-        Task::none()
-    }
-
-    /// Called when a nav item is selected.
-    fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<cosmic::Action<Self::Message>> {
-        // Activate the page in the model.
-        self.nav.activate(id);
+            Message::ToggleSelected(id, selected) => {
+                if selected {
+                    self.selected.insert(id);
+                } else {
+                    self.selected.remove(&id);
+                }
+            }
+            Message::SelectAll => {
+                self.selected = self.visible_books().iter().map(|book| book.id).collect();
+            }
+            Message::ClearSelection => {
+                self.selected.clear();
+            }
+            Message::BulkInput(value) => {
+                self.bulk_input = value;
+            }
+            Message::Bulk(action) => {
+                if self.bulk_job.is_some() {
+                    return Task::none();
+                }
 
-        self.update_title()
-    }
+                let books: Vec<Book> = self
+                    .library
+                    .books()
+                    .iter()
+                    .filter(|book| self.selected.contains(&book.id))
+                    .cloned()
+                    .collect();
 
-    /// Called when search is triggered.
-    fn on_search(&mut self) -> Task<cosmic::Action<Self::Message>> {
-        self.search_expanded = true;
-        Task::batch(vec![
-            cosmic::iced::widget::focus_next(),
-            Task::done(cosmic::Action::from(Message::SearchFocused)),
-        ])
-    }
+                let total = books.len();
+                let (task, handle) = Task::run(bulk::run(books, action), Message::BulkProgress)
+                    .chain(Task::done(Message::BulkFinished))
+                    .abortable();
 
-    fn dialog(&self) -> Option<Element<Message>> {
-        if self.show_popup {
-            let active_page = self
-                .nav
-                .data::<Page>(self.nav.active())
-                .copied()
-                .unwrap_or(Page::Page1);
+                self.bulk_job = Some(BulkJob {
+                    handle,
+                    total,
+                    done: 0,
+                    failed: 0,
+                    trashed: Vec::new(),
+                });
 
-            match active_page {
-                Page::Page1 => Some(
-                    dialog()
-                        .title("This is a popup on page 1!")
-                        .body("This is the body of the popup.")
-                        .icon(icon::from_name("face-cool-symbolic"))
-                        .primary_action(button::standard("Close").on_press(Message::TogglePopup))
-                        .into(),
-                ),
-                _ => None,
+                return task.map(cosmic::Action::App);
             }
-        } else {
+            Message::BulkProgress(progress) => {
+                let Some(job) = self.bulk_job.as_mut() else {
+                    return Task::none();
+                };
+
+                job.done += 1;
+
+                match progress {
+                    bulk::Progress::Updated(id, change) => {
+                        // Books deleted meanwhile are left deleted.
+                        if let Some(book) = self.library.get_mut(id) {
+                            change.apply(book);
+
+                            if book.is_trashed() {
+                                self.selected.remove(&id);
+                                job.trashed.push(id);
+                            }
+                        }
+                    }
+                    bulk::Progress::Failed(id, why) => {
+                        job.failed += 1;
+                        tracing::error!("bulk action failed for book {id}: {why}");
+                    }
+                }
+            }
+            Message::BulkFinished => {
+                let Some(job) = self.bulk_job.take() else {
+                    return Task::none();
+                };
+
+                if job.failed > 0 {
+                    tracing::error!(
+                        "bulk action failed for {} of {} books",
+                        job.failed,
+                        job.total
+                    );
+                }
+
+                self.bulk_input.clear();
+                self.save_library();
+
+                return self.push_trash_toast(job.trashed);
+            }
+            Message::CancelBulk => {
+                let Some(job) = self.bulk_job.take() else {
+                    return Task::none();
+                };
+
+                job.handle.abort();
+                self.save_library();
+
+                return self.push_trash_toast(job.trashed);
+            }
+            Message::RestoreBooks(ids) => {
+                let books: Vec<Book> = ids
+                    .iter()
+                    .filter_map(|&id| self.library.get(id))
+                    .filter(|book| book.is_trashed())
+                    .cloned()
+                    .collect();
+
+                return Task::perform(
+                    async move {
+                        let mut restored = Vec::with_capacity(books.len());
+
+                        for book in books {
+                            let id = book.id;
+                            match trash::restore(book).await {
+                                Ok(book) => restored.push(book),
+                                Err(why) => tracing::error!("failed to restore book {id}: {why}"),
+                            }
+                        }
+
+                        restored
+                    },
+                    |restored| cosmic::Action::App(Message::BooksRestored(restored)),
+                );
+            }
+            Message::BooksRestored(books) => {
+                for book in books {
+                    self.library.replace(book);
+                }
+
+                self.save_library();
+            }
+            Message::PurgeBooks(ids) => {
+                let mut purged = Vec::with_capacity(ids.len());
+
+                for id in ids {
+                    if self.library.get(id).is_some_and(Book::is_trashed) {
+                        purged.extend(self.library.remove(id));
+                    }
+                }
+
+                self.save_library();
+                self.forget_pds_records(&purged);
+
+                return Task::future(trash::purge(purged)).discard();
+            }
+            Message::CloseToast(id) => {
+                self.toasts.remove(id);
+            }
+            Message::SetTrashFiles(trash_files) => {
+                self.config.trash_files = trash_files;
+            }
+            Message::SetDictionaryOnline(online) => {
+                self.config.dictionary_online = online;
+            }
+            Message::SetTranslateUrl(url) => {
+                self.config.translate_url = url;
+            }
+            Message::SetTranslateApiKey(key) => {
+                self.config.translate_api_key = key;
+            }
+            Message::SetSyncFolder(folder) => {
+                self.config.sync_folder = folder;
+            }
+            Message::SetSyncResolution(resolution) => {
+                self.config.sync_resolution = resolution;
+            }
+            Message::SyncPositions => {
+                let finished = self.record_reading_position();
+                return Task::batch([
+                    finished,
+                    self.push_positions(),
+                    self.pull_positions(),
+                    self.sync_pds(),
+                    self.sync_webdav(),
+                    self.sync_companion(),
+                ]);
+            }
+            Message::SetWebdavUrl(url) => {
+                self.config.webdav_url = url;
+            }
+            Message::SetWebdavUser(user) => {
+                self.config.webdav_user = user;
+            }
+            Message::SetWebdavPassword(password) => {
+                self.webdav_password = Some(password);
+            }
+            Message::SetWebdavScheduled(scheduled) => {
+                self.config.webdav_scheduled = scheduled;
+            }
+            Message::SetUpdateCheck(frequency) => {
+                self.config.update_check = frequency;
+                self.save_config();
+            }
+            Message::SetLanguageFallback(fallback) => {
+                self.language_fallback_input = fallback;
+            }
+            Message::ApplyLanguageFallback => {
+                self.config.language_fallback = self.language_fallback_input.clone();
+                self.save_config();
+                self.apply_language();
+
+                return self.update_title();
+            }
+            Message::SetLanguage(index) => {
+                if let Some(language) = self.languages.get(index) {
+                    self.config.language = language.clone();
+                    self.save_config();
+                    self.apply_language();
+
+                    return self.update_title();
+                }
+            }
+            Message::SetLanSharing(sharing) => {
+                self.config.lan_sharing = sharing;
+                self.save_config();
+
+                if sharing {
+                    self.lan = Some(Lan::new(sync::device_name(), self.library.books()));
+                } else {
+                    self.lan = None;
+                    self.peers.clear();
+                    self.peer = PeerState::default();
+                    self.peer_tokens.clear();
+                    self.access_request = None;
+                }
+            }
+            Message::LanEvent(event) => match event {
+                lan::Event::Offered(offer) => {
+                    self.offers.push_back(offer);
+
+                    // Later offers wait behind the one being shown.
+                    if self.offers.len() == 1 {
+                        return self.announce_dialog(fl!("nearby-offer-title"));
+                    }
+                }
+                lan::Event::Requested(request) => {
+                    self.access_request = Some(request);
+                    return self.announce_dialog(fl!("nearby-access-title"));
+                }
+                lan::Event::Withdrawn(id) => {
+                    if self
+                        .access_request
+                        .as_ref()
+                        .is_some_and(|request| request.id == id)
+                    {
+                        self.access_request = None;
+                    }
+                }
+                lan::Event::Failed(why) => {
+                    tracing::error!("failed to share the library: {why}");
+
+                    return self.push_toast(fl!("nearby-failed", reason = why));
+                }
+            },
+            Message::Discovered(discovery) => match discovery {
+                lan::Discovery::Found(peer) => {
+                    self.peers.retain(|known| known.id != peer.id);
+                    self.peers.push(peer);
+                    self.peers.sort_by(|a, b| a.device.cmp(&b.device));
+                }
+                lan::Discovery::Lost(id) => {
+                    self.peers.retain(|peer| peer.id != id);
+                }
+            },
+            Message::OpenPeer(id) => {
+                let Some(peer) = self.peers.iter().find(|peer| peer.id == id) else {
+                    return Task::none();
+                };
+
+                let browse = lan::browse(
+                    peer.url.clone(),
+                    sync::device_name(),
+                    self.peer_tokens.get(&peer.url).cloned(),
+                );
+
+                let (task, handle) = Task::perform(browse, {
+                    let id = id.clone();
+                    move |result| cosmic::Action::App(Message::PeerBooks(id.clone(), result))
+                })
+                .abortable();
+
+                self.peer = PeerState {
+                    id: Some(id),
+                    task: Some(handle.abort_on_drop()),
+                    ..PeerState::default()
+                };
+
+                return task;
+            }
+            Message::ClosePeer => {
+                self.peer = PeerState::default();
+            }
+            Message::PeerBooks(id, result) => {
+                if self.peer.id.as_ref() != Some(&id) {
+                    return Task::none();
+                }
+
+                self.peer.task = None;
+
+                match result {
+                    Ok((token, books)) => {
+                        if let Some(peer) = self.peers.iter().find(|peer| peer.id == id) {
+                            self.peer_tokens.insert(peer.url.clone(), token);
+                        }
+
+                        self.peer.books = Some(books);
+                    }
+                    Err(why) => self.peer.error = Some(why),
+                }
+            }
+            Message::DownloadFromPeer(url, book) => {
+                let auth = self
+                    .peer_tokens
+                    .get(&url)
+                    .map(|token| lan::credentials(token));
+                return self.import_from_peer(&url, &book, auth);
+            }
+            Message::SendToPeer(id, book) => {
+                let (Some(lan), Some(peer)) =
+                    (&self.lan, self.peers.iter().find(|peer| peer.id == id))
+                else {
+                    return Task::none();
+                };
+
+                let Some(book) = lan.book(book) else {
+                    return Task::none();
+                };
+
+                let Some(token) = lan.grant(book.id) else {
+                    return Task::none();
+                };
+
+                let device = peer.device.clone();
+
+                return Task::perform(
+                    lan::offer(peer.url.clone(), sync::device_name(), book, token),
+                    move |result| cosmic::Action::App(Message::SentToPeer(device.clone(), result)),
+                );
+            }
+            Message::SentToPeer(device, result) => {
+                let text = match result {
+                    Ok(()) => fl!("nearby-offered", device = device),
+                    Err(why) => fl!("nearby-offer-failed", device = device, reason = why),
+                };
+
+                return self.push_toast(text);
+            }
+            Message::AcceptOffer => {
+                if let Some(offer) = self.offers.pop_front() {
+                    let auth = Some(lan::credentials(&offer.token));
+                    return self.import_from_peer(&offer.url, &offer.book, auth);
+                }
+            }
+            Message::DeclineOffer => {
+                self.offers.pop_front();
+            }
+            Message::ConfirmAccess(accepted) => {
+                if let (Some(lan), Some(request)) = (&self.lan, self.access_request.take()) {
+                    lan.confirm(request.id, accepted);
+                }
+            }
+            Message::SetCrashReports(enabled) => {
+                self.config.crash_reports = enabled;
+                self.save_config();
+            }
+            Message::SetCrashEndpoint(endpoint) => {
+                self.config.crash_endpoint = endpoint;
+            }
+            Message::SetHideRecentBooks(hide) => {
+                self.config.hide_recent_books = hide;
+                self.save_config();
+            }
+            Message::SetSoundPageTurn(enabled) => {
+                self.config.sound_page_turn = enabled;
+                self.save_config();
+            }
+            Message::SetSoundImported(enabled) => {
+                self.config.sound_imported = enabled;
+                self.save_config();
+            }
+            Message::SetSoundAchievement(enabled) => {
+                self.config.sound_achievement = enabled;
+                self.save_config();
+            }
+            Message::SetCommandSocket(enabled) => {
+                self.config.command_socket = enabled;
+                self.save_config();
+            }
+            Message::SetTray(shown) => {
+                self.config.tray = shown;
+                self.tray_failed = false;
+                self.save_config();
+
+                // Don't leave the window hidden with no tray to bring it back from.
+                if !shown && self.window_hidden {
+                    return self.set_window_hidden(false);
+                }
+            }
+            Message::SetCloseToTray(enabled) => {
+                self.config.close_to_tray = enabled;
+                self.save_config();
+            }
+            Message::Tray(action) => match action {
+                tray::Action::Toggle => {
+                    return self.set_window_hidden(!self.window_hidden);
+                }
+                tray::Action::Continue => {
+                    let Some(id) = self.library.last_read().map(|book| book.id) else {
+                        return Task::none();
+                    };
+
+                    return Task::batch([
+                        self.set_window_hidden(false),
+                        self.update(Message::OpenReader(id)),
+                    ]);
+                }
+                tray::Action::Quit => {
+                    return self.quit();
+                }
+                tray::Action::Failed => {
+                    self.tray_failed = true;
+
+                    let show = self.set_window_hidden(false);
+                    let toast = self.push_toast(fl!("tray-failed"));
+
+                    return Task::batch([show, toast]);
+                }
+            },
+            Message::SetGlobalShortcuts(enabled) => {
+                self.config.global_shortcuts = enabled;
+                self.save_config();
+            }
+            Message::SetSummonTrigger(trigger) => {
+                self.config.summon_trigger = trigger;
+                self.save_config();
+            }
+            Message::SetSearchTrigger(trigger) => {
+                self.config.search_trigger = trigger;
+                self.save_config();
+            }
+            Message::ApplyGlobalTriggers => {
+                self.global_triggers = (
+                    self.config.summon_trigger.clone(),
+                    self.config.search_trigger.clone(),
+                );
+            }
+            Message::GlobalShortcut(shortcut) => match shortcut {
+                global_shortcut::Shortcut::Summon => {
+                    return self.set_window_hidden(false);
+                }
+                global_shortcut::Shortcut::Search => {
+                    // Pressed again while searching, it puts the search away.
+                    if self.focused && !self.window_hidden && self.search_expanded {
+                        return self.update(Message::ClearSearch);
+                    }
+
+                    return Task::batch([
+                        self.set_window_hidden(false),
+                        self.update(Message::ExpandSearch),
+                    ]);
+                }
+                global_shortcut::Shortcut::Failed => {
+                    return self.push_toast(fl!("global-shortcuts-failed"));
+                }
+            },
+            Message::SetAppTheme(app_theme) => {
+                self.config.app_theme = app_theme;
+                self.save_config();
+                return self.apply_theme();
+            }
+            Message::SystemThemeMode(_) => {
+                if self.config.app_theme == AppTheme::System {
+                    return self.apply_theme();
+                }
+            }
+            Message::SetCompactHeader(compact) => {
+                self.config.compact_header = compact;
+                self.save_config();
+                return self.update_title();
+            }
+            Message::WindowFocused(focused) => {
+                self.focused = focused;
+            }
+            Message::SetNotifications(enabled) => {
+                self.config.notifications = enabled;
+                self.save_config();
+            }
+            Message::NotificationClicked(message) => {
+                return Task::batch([self.set_window_hidden(false), self.update(*message)]);
+            }
+            Message::SearchProvider(command) => match command {
+                search_provider::Command::Open(id) => {
+                    return Task::batch([
+                        self.set_window_hidden(false),
+                        self.update(Message::OpenReader(id)),
+                    ]);
+                }
+                search_provider::Command::Search(query) => {
+                    return Task::batch([
+                        self.set_window_hidden(false),
+                        self.update(Message::ExpandSearch),
+                        self.update(Message::SearchChanged(query)),
+                    ]);
+                }
+            },
+            Message::Control(command) => {
+                tracing::debug!(?command, "received a control command");
+
+                match command {
+                    control::Command::Activate => return self.set_window_hidden(false),
+                    control::Command::Open(path) => {
+                        return Task::batch([
+                            self.set_window_hidden(false),
+                            self.update(Message::OpenFiles(vec![path])),
+                        ]);
+                    }
+                    control::Command::Search(query) => {
+                        return Task::batch([
+                            self.set_window_hidden(false),
+                            self.update(Message::ExpandSearch),
+                            self.update(Message::SearchChanged(query)),
+                        ]);
+                    }
+                }
+            }
+            Message::Mpris(command) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let Some(read_aloud) = reader.read_aloud.as_ref() else {
+                    return Task::none();
+                };
+
+                let playing = read_aloud.is_playing();
+                let current = read_aloud.current();
+
+                match command {
+                    mpris::Command::Raise => return self.set_window_hidden(false),
+                    mpris::Command::Play if !playing => return reader.play(),
+                    mpris::Command::Pause | mpris::Command::Stop => {
+                        return reader.stop_speaking();
+                    }
+                    mpris::Command::PlayPause => return self.update(Message::SpeechPlay),
+                    mpris::Command::Seek(sentences) => {
+                        let index = current.saturating_add_signed(sentences as isize);
+                        return reader.speak_from(index);
+                    }
+                    mpris::Command::SetPosition(index) => return reader.speak_from(index),
+                    mpris::Command::Next | mpris::Command::Previous => {
+                        let index = if command == mpris::Command::Next {
+                            if !reader.has_next_chapter() {
+                                return Task::none();
+                            }
+
+                            reader.chapter_index + 1
+                        } else {
+                            // The first chapter starts over instead.
+                            let Some(index) = reader.chapter_index.checked_sub(1) else {
+                                return reader.speak_from(0);
+                            };
+
+                            index
+                        };
+
+                        let stop = reader.stop_speaking();
+
+                        if let Some(read_aloud) = reader.read_aloud.as_mut() {
+                            read_aloud.resume = playing;
+                        }
+
+                        return stop.chain(self.load_chapter(index, 0.0));
+                    }
+                    mpris::Command::Play => (),
+                }
+            }
+            Message::LibraryLoaded(Ok(library)) => {
+                self.library = library;
+                self.library_loaded = true;
+
+                if cfg!(debug_assertions) {
+                    tracing::debug!(
+                        "read the library {:?} after starting",
+                        self.started.elapsed()
+                    );
+                }
+
+                let mut tasks = Vec::new();
+
+                // Purge books which have outlived their time in the trash.
+                let cutoff = trash::now().saturating_sub(trash::RETENTION.as_secs());
+                let expired = self.library.take_expired(cutoff);
+
+                if !expired.is_empty() {
+                    self.save_library();
+                    self.forget_pds_records(&expired);
+                    tasks.push(Task::future(trash::purge(expired)).discard());
+                }
+
+                if self.config.lan_sharing {
+                    self.lan = Some(Lan::new(sync::device_name(), self.library.books()));
+                }
+
+                self.search_provider.set_books(self.library.books());
+
+                // Pick up where other devices left off, which waited for the catalog.
+                tasks.push(self.pull_positions());
+                tasks.push(self.sync_pds());
+                tasks.push(self.sync_companion());
+
+                for result in std::mem::take(&mut self.waiting_imports) {
+                    tasks.push(self.update(Message::BookImported(result)));
+                }
+
+                let files = std::mem::take(&mut self.waiting_files);
+
+                if !files.is_empty() {
+                    tasks.push(self.update(Message::OpenFiles(files)));
+                }
+
+                return Task::batch(tasks);
+            }
+            Message::LibraryLoaded(Err(why)) => {
+                tracing::error!("failed to read the library: {why}");
+
+                let text = fl!("library-load-failed", reason = why);
+                let announcement = self.announce(text.clone());
+                let toast = widget::toaster::Toast::new(text)
+                    .action(fl!("retry"), |_| Message::LoadLibrary);
+
+                return Task::batch([
+                    self.toasts.push(toast).map(cosmic::Action::App),
+                    announcement,
+                ]);
+            }
+            Message::LoadLibrary => return load_library(Library::reload),
+            Message::OpenFiles(paths) if !self.library_loaded => {
+                self.waiting_files.extend(paths);
+            }
+            Message::OpenFiles(paths) => {
+                let tasks = paths.into_iter().map(|path| {
+                    let known = self
+                        .library
+                        .books()
+                        .iter()
+                        .find(|book| !book.is_trashed() && book.path.as_ref() == Some(&path))
+                        .map(|book| book.id);
+
+                    match known {
+                        Some(id) => self.update(Message::OpenReader(id)),
+                        None => Task::perform(import::import_file(path), |result| {
+                            cosmic::Action::App(Message::FileOpened(result))
+                        }),
+                    }
+                });
+
+                return Task::batch(tasks.collect::<Vec<_>>());
+            }
+            Message::FileOpened(result) => {
+                let imported = result.is_ok();
+                let task = self.update(Message::BookImported(result));
+
+                // The imported book is the one last added to the catalog.
+                let Some(id) = imported
+                    .then(|| self.library.books().last().map(|book| book.id))
+                    .flatten()
+                else {
+                    return task;
+                };
+
+                return Task::batch([task, self.update(Message::OpenReader(id))]);
+            }
+            Message::CopyCitation(id, style) => {
+                if let Some(book) = self.library.get(id) {
+                    return clipboard::write(citation::cite(book, style));
+                }
+            }
+            Message::ChooseFile(target) => {
+                let chosen = match target {
+                    FileTarget::ImportBooks => file_dialog::open_files(
+                        fl!("import-files"),
+                        file_dialog::Filter::Books,
+                        true,
+                    )
+                    .boxed(),
+                    FileTarget::ExportPath => {
+                        let current = self
+                            .export_dialog
+                            .as_ref()
+                            .map(|state| PathBuf::from(state.path.trim()));
+
+                        file_dialog::save_file(
+                            fl!("export-library"),
+                            file_dialog::Filter::Exports,
+                            current,
+                        )
+                        .map_ok(Vec::from_iter)
+                        .boxed()
+                    }
+                    FileTarget::SyncFolder => file_dialog::open_folder(fl!("sync-folder"))
+                        .map_ok(Vec::from_iter)
+                        .boxed(),
+                    FileTarget::Certificates => file_dialog::open_files(
+                        fl!("certificates"),
+                        file_dialog::Filter::Certificates,
+                        false,
+                    )
+                    .boxed(),
+                };
+
+                return Task::perform(chosen, move |result| {
+                    cosmic::Action::App(Message::FileChosen(target, result))
+                });
+            }
+            Message::FileChosen(target, result) => {
+                let paths = match result {
+                    Ok(paths) => paths,
+                    Err(why) => {
+                        tracing::error!("failed to choose a file: {why}");
+                        return self.push_toast(fl!("file-dialog-failed", reason = why));
+                    }
+                };
+
+                // Nothing is chosen when the chooser is cancelled.
+                let Some(first) = paths.first() else {
+                    return Task::none();
+                };
+
+                let first = first.to_string_lossy().into_owned();
+
+                match target {
+                    FileTarget::ImportBooks => {
+                        self.import_dialog = None;
+
+                        return Task::batch(paths.into_iter().map(|path| {
+                            Task::perform(import::import_file(path), |result| {
+                                cosmic::Action::App(Message::BookImported(result))
+                            })
+                        }));
+                    }
+                    FileTarget::ExportPath => return self.update(Message::ExportPath(first)),
+                    FileTarget::SyncFolder => return self.update(Message::SetSyncFolder(first)),
+                    FileTarget::Certificates => {
+                        return self.update(Message::SetCertificates(first));
+                    }
+                }
+            }
+            Message::CloseRequested(id) => {
+                // The main window is only hidden while the reader's window stays open.
+                if (self.config.close_to_tray && self.tray_shown()) || self.reader_window.is_some()
+                {
+                    self.window_hidden = true;
+                    return window::change_mode(id, window::Mode::Hidden);
+                }
+
+                return self.quit();
+            }
+            Message::SendCrashReports => {
+                let Some(dialog) = self.crash_dialog.as_mut() else {
+                    return Task::none();
+                };
+
+                dialog.sending = true;
+                dialog.error = None;
+
+                return Task::perform(
+                    crash::submit(self.config.crash_endpoint.clone(), dialog.reports.clone()),
+                    |result| cosmic::Action::App(Message::CrashReportsSent(result)),
+                );
+            }
+            Message::CrashReportsSent(result) => match result {
+                Ok(()) => self.crash_dialog = None,
+                Err(why) => {
+                    if let Some(dialog) = self.crash_dialog.as_mut() {
+                        dialog.sending = false;
+                        dialog.error = Some(why);
+                    }
+                }
+            },
+            Message::DismissCrashReports => {
+                if let Some(dialog) = self.crash_dialog.take() {
+                    return Task::future(crash::dismiss(dialog.reports)).discard();
+                }
+            }
+            Message::SaveSession => {
+                // The session being offered isn't overwritten until it's been answered.
+                if self.restore_dialog.is_some() {
+                    return Task::none();
+                }
+
+                let session = self.current_session();
+
+                if session != self.session {
+                    self.session = session.clone();
+                    return Task::future(session::save(session)).discard();
+                }
+            }
+            Message::WriteConfig => {
+                let settled = self
+                    .config_changed
+                    .is_some_and(|changed| changed.elapsed() >= CONFIG_SAVE_DELAY);
+
+                if settled {
+                    self.config_changed = None;
+                    return Task::future(write_config(self.config.clone())).discard();
+                }
+            }
+            Message::RestoreSession => {
+                if let Some(session) = self.restore_dialog.take() {
+                    return self.restore_session(session);
+                }
+            }
+            Message::DismissSession => {
+                self.restore_dialog = None;
+            }
+            Message::CheckForUpdates(forced) => {
+                let now = library::now();
+
+                if !self.online
+                    || !forced
+                        && !self
+                            .config
+                            .update_check
+                            .is_due(self.config.update_checked, now)
+                {
+                    return Task::none();
+                }
+
+                self.config.update_checked = now;
+                self.save_config();
+
+                return Task::perform(
+                    update::check(
+                        REPOSITORY.to_string(),
+                        env!("CARGO_PKG_VERSION").to_string(),
+                    ),
+                    move |result| cosmic::Action::App(Message::UpdateChecked(forced, result)),
+                );
+            }
+            Message::UpdateChecked(forced, result) => {
+                let release = match result {
+                    Ok(Some(release)) => release,
+                    Ok(None) if forced => {
+                        return self.push_toast(fl!("update-none"));
+                    }
+                    Ok(None) => return Task::none(),
+                    Err(why) => {
+                        tracing::error!("failed to check for updates: {why}");
+                        return Task::none();
+                    }
+                };
+
+                // Each release is only announced once per run.
+                if self.available_update.as_ref() == Some(&release) && !forced {
+                    return Task::none();
+                }
+
+                self.available_update = Some(release.clone());
+
+                let summary = fl!("update-available", version = release.version.as_str());
+                let url = release.url.clone();
+                let announcement = self.announce(summary.clone());
+
+                let toast = self
+                    .toasts
+                    .push(
+                        widget::toaster::Toast::new(summary.clone())
+                            .action(fl!("update-release-notes"), move |_| {
+                                Message::LaunchUrl(url.clone())
+                            }),
+                    )
+                    .map(cosmic::Action::App);
+
+                let notification = self.notify(
+                    summary,
+                    String::new(),
+                    Some((
+                        fl!("update-release-notes"),
+                        Message::LaunchUrl(release.url.clone()),
+                    )),
+                );
+
+                return Task::batch([toast, announcement, notification]);
+            }
+            Message::SyncWebdav => {
+                let finished = self.record_reading_position();
+                return Task::batch([finished, self.sync_webdav()]);
+            }
+            Message::WebdavPasswordLoaded(result) => match result {
+                Ok(password) => self.webdav_password = Some(password.unwrap_or_default()),
+                Err(why) => tracing::error!("failed to read the WebDAV password: {why}"),
+            },
+            Message::WebdavSynced(result) => {
+                self.webdav_syncing = false;
+
+                match result {
+                    Ok(outcome) => return self.apply_synced_catalog(outcome),
+                    Err(why) => {
+                        tracing::error!("failed to sync the catalog with the WebDAV server: {why}")
+                    }
+                }
+            }
+            Message::SetCompanionUrl(url) => {
+                // A pasted pairing link fills in the code as well.
+                match companion::parse_link(&url) {
+                    Some((server, code)) => {
+                        self.config.companion_url = server;
+                        self.companion_code = code;
+                    }
+                    None => self.config.companion_url = url,
+                }
+            }
+            Message::CompanionCode(code) => {
+                self.companion_code = code;
+            }
+            Message::PairCompanion => {
+                let url = self.config.companion_url.trim().to_string();
+
+                if url.is_empty() || self.companion_pairing {
+                    return Task::none();
+                }
+
+                self.companion_pairing = true;
+                self.companion_error = None;
+
+                return Task::perform(
+                    companion::pair(url, self.companion_code.clone(), sync::device_name()),
+                    |result| cosmic::Action::App(Message::CompanionPaired(result)),
+                );
+            }
+            Message::CompanionPaired(result) => {
+                self.companion_pairing = false;
+
+                let token = match result {
+                    Ok(token) => token,
+                    Err(why) => {
+                        self.companion_error = Some(why);
+                        return Task::none();
+                    }
+                };
+
+                self.companion_code.clear();
+                self.companion_token = Some(token.clone());
+                self.config.companion_url = self.config.companion_url.trim().to_string();
+                self.save_config();
+
+                let store = Task::future(async move {
+                    if let Err(why) = secrets::store(companion::TOKEN_KEY.to_string(), token).await
+                    {
+                        tracing::error!("failed to store the companion token: {why}");
+                    }
+                })
+                .discard();
+
+                return Task::batch([store, self.sync_companion()]);
+            }
+            Message::CompanionTokenLoaded(result) => match result {
+                Ok(token) => {
+                    self.companion_token = token;
+                    return self.sync_companion();
+                }
+                Err(why) => tracing::error!("failed to read the companion token: {why}"),
+            },
+            Message::UnpairCompanion => {
+                self.companion_token = None;
+                self.config.companion_url.clear();
+                self.save_config();
+
+                return Task::future(async {
+                    if let Err(why) = secrets::forget(companion::TOKEN_KEY.to_string()).await {
+                        tracing::error!("failed to forget the companion token: {why}");
+                    }
+                })
+                .discard();
+            }
+            Message::SyncCompanion => {
+                let finished = self.record_reading_position();
+                return Task::batch([finished, self.sync_companion()]);
+            }
+            Message::CompanionSynced(result) => {
+                self.companion_syncing = false;
+
+                match result {
+                    Ok(outcome) => return self.apply_synced_catalog(outcome),
+                    Err(why) => {
+                        tracing::error!("failed to sync the catalog with the companion: {why}")
+                    }
+                }
+            }
+            Message::SetCompanionHost(hosting) => {
+                self.config.companion_host = hosting;
+                self.save_config();
+
+                self.host = hosting.then(Host::load);
+                self.host_error = None;
+                self.refresh_pairing();
+
+                return self.sync_companion();
+            }
+            Message::RenewPairingCode => {
+                if let Some(host) = &self.host {
+                    host.renew_code();
+                }
+
+                self.refresh_pairing();
+            }
+            Message::OpenPairing => {
+                if let Some(host) = &self.host {
+                    host.open_pairing();
+                }
+
+                self.refresh_pairing();
+
+                return Task::perform(tokio::time::sleep(host::PAIRING_WINDOW), |()| {
+                    cosmic::Action::App(Message::PairingTimedOut)
+                });
+            }
+            Message::ClosePairing => self.close_pairing(),
+            Message::PairingTimedOut => {
+                // Pairing started again meanwhile is left open for its own while.
+                if !self.host.as_ref().is_some_and(Host::pairing_open) {
+                    self.close_pairing();
+                }
+            }
+            Message::ConfirmPairing(accepted) => {
+                if let (Some(host), Some(request)) = (&self.host, self.pairing_request.take()) {
+                    host.confirm(request.id, accepted);
+                }
+            }
+            Message::ForgetCompanionDevice(index) => {
+                if let Some(host) = &self.host {
+                    host.forget_device(index);
+                }
+            }
+            Message::HostEvent(event) => match event {
+                host::Event::Changed(_) => return self.update(Message::SyncCompanion),
+                host::Event::Requested(request) => {
+                    self.pairing_request = Some(request);
+                    return self.announce_dialog(fl!("companion-confirm-title"));
+                }
+                host::Event::Withdrawn(id) => {
+                    if self
+                        .pairing_request
+                        .as_ref()
+                        .is_some_and(|request| request.id == id)
+                    {
+                        self.pairing_request = None;
+                    }
+                }
+                host::Event::Paired(device) => {
+                    // Pairing ended with the device, so stop showing its code.
+                    self.refresh_pairing();
+
+                    return self.push_toast(fl!("companion-device-paired", device = device));
+                }
+                host::Event::Failed(why) => self.host_error = Some(why),
+            },
+            Message::PositionsPulled(result) => {
+                let records = match result {
+                    Ok(records) => records,
+                    Err(why) => {
+                        tracing::error!("failed to read synced positions: {why}");
+                        return Task::none();
+                    }
+                };
+
+                let device = sync::device_name();
+                let resolution = self.config.sync_resolution;
+                let open = self.reader.as_ref().map(Reader::book_id);
+                let mut updated = 0;
+
+                for book in self.library.books_mut() {
+                    // Leave the open book where it is being read.
+                    if Some(book.id) == open {
+                        continue;
+                    }
+
+                    let Some(record) = records.get(&sync::key(book)) else {
+                        continue;
+                    };
+
+                    let Some(location) = record.location() else {
+                        continue;
+                    };
+
+                    let wins = match sync::record(book, &device) {
+                        Some(local) => record.wins_over(&local, resolution),
+                        None => true,
+                    };
+
+                    if wins {
+                        book.location = Some(location);
+                        book.progress = record.progress;
+                        book.location_updated = record.updated;
+                        updated += 1;
+                    }
+                }
+
+                if updated > 0 {
+                    self.save_library();
+
+                    return self.push_toast(fl!("sync-updated", count = updated));
+                }
+            }
+            Message::PositionsPushed(result) => {
+                if let Err(why) = result {
+                    tracing::error!("failed to sync reading positions: {why}");
+                }
+            }
+            Message::AccountIdentifier(identifier) => {
+                self.account_identifier = identifier;
+            }
+            Message::AccountPassword(password) => {
+                self.account_password = password;
+            }
+            Message::LogIn => {
+                if self.logging_in
+                    || self.account_identifier.trim().is_empty()
+                    || self.account_password.is_empty()
+                {
+                    return Task::none();
+                }
+
+                self.logging_in = true;
+                self.account_error = None;
+
+                return Task::perform(
+                    atproto::login(
+                        self.account_identifier.clone(),
+                        self.account_password.clone(),
+                    ),
+                    |result| cosmic::Action::App(Message::LoggedIn(result)),
+                );
+            }
+            Message::LoggedIn(result) => {
+                self.logging_in = false;
+
+                match result {
+                    Ok(session) => {
+                        self.account_identifier.clear();
+                        self.account_password.clear();
+                        return self.sign_in(session);
+                    }
+                    Err(why) => self.account_error = Some(why),
+                }
+            }
+            Message::SessionResumed(result) => match result {
+                Ok(Some(session)) => return self.sign_in(session),
+                Ok(None) => (),
+                Err(why) => tracing::error!("failed to restore the atproto session: {why}"),
+            },
+            Message::ProfileLoaded(result) => match result {
+                Ok(profile) => {
+                    if let Some(account) = &mut self.account {
+                        account.avatar = profile
+                            .avatar
+                            .clone()
+                            .map(widget::image::Handle::from_bytes);
+                        account.profile = Some(profile);
+                    }
+                }
+                Err(why) => tracing::error!("failed to load the atproto profile: {why}"),
+            },
+            Message::LogOut => {
+                if let Some(account) = self.account.take() {
+                    return Task::future(async move {
+                        if let Err(why) = atproto::logout(account.session).await {
+                            tracing::error!("failed to sign out of atproto: {why}");
+                        }
+                    })
+                    .discard()
+                    .chain(self.reset_feed());
+                }
+            }
+            Message::ShareHighlight(index) => {
+                let Some(reader) = &self.reader else {
+                    return Task::none();
+                };
+
+                let id = reader.book_id();
+
+                let Some(book) = self.library.get(id) else {
+                    return Task::none();
+                };
+
+                let Some(highlight) = book.highlights.get(index) else {
+                    return Task::none();
+                };
+
+                let attribution = if book.author.is_empty() {
+                    fl!("share-attribution-title", title = book.title.as_str())
+                } else {
+                    fl!(
+                        "share-attribution",
+                        title = book.title.as_str(),
+                        author = book.author.as_str()
+                    )
+                };
+
+                let text = share::quote(&highlight.text, &attribution);
+                return self.open_share_dialog(id, text);
+            }
+            Message::ShareBook(id) => {
+                let Some(book) = self.library.get(id) else {
+                    return Task::none();
+                };
+
+                let text = if book.author.is_empty() {
+                    fl!("share-finished-title", title = book.title.as_str())
+                } else {
+                    fl!(
+                        "share-finished",
+                        title = book.title.as_str(),
+                        author = book.author.as_str()
+                    )
+                };
+
+                return self.open_share_dialog(id, text);
+            }
+            Message::ShareEdit(action) => {
+                if let Some(state) = self.share_dialog.as_mut() {
+                    state.text.perform(action);
+                }
+            }
+            Message::ShareCoverLoaded(id, result) => {
+                let Some(state) = self.share_dialog.as_mut().filter(|state| state.book == id)
+                else {
+                    return Task::none();
+                };
+
+                match result {
+                    Ok(cover) => {
+                        state.cover_handle = cover.clone().map(widget::image::Handle::from_bytes);
+                        state.cover = cover;
+                    }
+                    Err(why) => tracing::error!("failed to render cover to share: {why}"),
+                }
+            }
+            Message::ShareAttachCover(attach) => {
+                if let Some(state) = self.share_dialog.as_mut() {
+                    state.attach_cover = attach;
+                }
+            }
+            Message::SharePost => {
+                let (Some(state), Some(account)) = (self.share_dialog.as_mut(), &self.account)
+                else {
+                    return Task::none();
+                };
+
+                if state.posting {
+                    return Task::none();
+                }
+
+                let title = self
+                    .library
+                    .get(state.book)
+                    .map(|book| book.title.clone())
+                    .unwrap_or_default();
+
+                let image = state
+                    .cover
+                    .clone()
+                    .filter(|_| state.attach_cover)
+                    .map(|bytes| atproto::Image {
+                        bytes,
+                        mime: "image/jpeg",
+                        alt: fl!("share-cover-alt", title = title),
+                    });
+
+                state.posting = true;
+                state.error = None;
+
+                return Task::perform(
+                    atproto::post(
+                        account.session.clone(),
+                        state.text.text().trim().to_string(),
+                        image,
+                    ),
+                    |result| cosmic::Action::App(Message::SharePosted(result)),
+                );
+            }
+            Message::SharePosted(result) => match result {
+                Ok(session) => {
+                    self.share_dialog = None;
+
+                    if let Some(account) = &mut self.account {
+                        account.session = session;
+                    }
+
+                    return self.push_toast(fl!("share-posted"));
+                }
+                Err(why) => {
+                    if let Some(state) = self.share_dialog.as_mut() {
+                        state.posting = false;
+                        state.error = Some(why);
+                    }
+                }
+            },
+            Message::CloseShareDialog => {
+                self.share_dialog = None;
+            }
+            Message::LoadFeed(refresh) => {
+                let exhausted = self.feed.loaded && self.feed.cursor.is_none();
+
+                if !refresh && (self.feed.task.is_some() || exhausted) {
+                    return Task::none();
+                }
+
+                let cursor = if refresh {
+                    None
+                } else {
+                    self.feed.cursor.clone()
+                };
+
+                self.feed.error = None;
+                self.feed.overscroll = 0.0;
+
+                let session = self.account.as_ref().map(|account| account.session.clone());
+                let (task, handle) = Task::perform(
+                    atproto::feed(session, self.chosen_feed(), cursor),
+                    move |result| cosmic::Action::App(Message::FeedLoaded(refresh, result)),
+                )
+                .abortable();
+
+                self.feed.task = Some(handle.abort_on_drop());
+                return task;
+            }
+            Message::FeedLoaded(refresh, result) => {
+                self.feed.task = None;
+                self.feed.loaded = true;
+
+                let (page, session) = match result {
+                    Ok(loaded) => loaded,
+                    Err(why) => {
+                        self.feed.error = Some(why);
+                        return Task::none();
+                    }
+                };
+
+                if let (Some(session), Some(account)) = (session, &mut self.account) {
+                    account.session = session;
+                }
+
+                if refresh {
+                    self.feed.posts.clear();
+                }
+
+                let urls: HashSet<String> = page
+                    .posts
+                    .iter()
+                    .flat_map(|post| post.images.iter().map(|(url, _)| url.clone()))
+                    .filter(|url| !self.feed.images.contains(url))
+                    .collect();
+
+                self.feed.posts.extend(page.posts);
+                self.feed.cursor = page.cursor;
+
+                return Task::batch(urls.into_iter().map(|url| {
+                    let load =
+                        thumbnail::load(atproto::fetch_image(url.clone()), thumbnail::PICTURE_SIDE);
+
+                    Task::perform(load, move |result| {
+                        cosmic::Action::App(Message::FeedImageLoaded(url.clone(), result))
+                    })
+                }));
+            }
+            Message::FeedImageLoaded(url, result) => match result {
+                Ok(handle) => self.feed.images.insert(url, handle),
+                Err(why) => tracing::error!("failed to fetch {url}: {why}"),
+            },
+            Message::FeedInput(input) => {
+                self.feed.input = input;
+            }
+            Message::ChooseFeed => {
+                self.config.feed = self.feed.input.trim().to_string();
+                self.save_config();
+                return self.reset_feed();
+            }
+            Message::LibraryScrolled(viewport) => {
+                self.library_offset = viewport.absolute_offset().y;
+                self.library_height = viewport.bounds().height;
+            }
+            Message::SetLibrarySort(sort) => {
+                self.library_sort = sort;
+                return self.scroll_library_to_top();
+            }
+            Message::FeedScrolled(viewport) => {
+                self.feed.at_top = viewport.absolute_offset().y <= 0.0;
+
+                if !self.feed.at_top {
+                    self.feed.overscroll = 0.0;
+                }
+
+                if viewport.relative_offset().y >= 0.9 {
+                    return self.update(Message::LoadFeed(false));
+                }
+            }
+            Message::SetPdsSync(enabled) => {
+                self.config.pds_sync = enabled;
+                self.save_config();
+
+                if enabled {
+                    return self.sync_pds();
+                }
+            }
+            Message::PdsSynced(result) => {
+                self.pds_syncing = false;
+
+                let (updates, session) = match result {
+                    Ok(synced) => synced,
+                    Err(why) => {
+                        tracing::error!("failed to sync reading data with the PDS: {why}");
+                        return Task::none();
+                    }
+                };
+
+                if let (Some(session), Some(account)) = (session, &mut self.account) {
+                    account.session = session;
+                }
+
+                // The records of deleted books are gone now.
+                if !self.config.pds_deleted.is_empty() {
+                    self.config.pds_deleted.clear();
+                    self.save_config();
+                }
+
+                let open = self.reader.as_ref().map(Reader::book_id);
+                let mut updated = 0;
+
+                for update in updates {
+                    // Leave the open book where it is being read.
+                    if Some(update.id) == open {
+                        continue;
+                    }
+
+                    let Some(book) = self.library.get_mut(update.id) else {
+                        continue;
+                    };
+
+                    if let Some(record) = update.position {
+                        if let Some(location) = record.location() {
+                            book.location = Some(location);
+                            book.progress = record.progress;
+                            book.location_updated = record.updated;
+                        }
+                    }
+
+                    if update.rating.is_some() {
+                        book.rating = update.rating;
+                    }
+
+                    updated += 1;
+                }
+
+                if updated > 0 {
+                    self.save_library();
+
+                    return self.push_toast(fl!("sync-updated", count = updated));
+                }
+            }
+            Message::ShowFeed(feed) => {
+                self.feed.input = feed;
+
+                if let Some(id) = self
+                    .nav
+                    .iter()
+                    .find(|&id| self.nav.data::<Page>(id) == Some(&Page::Feed))
+                {
+                    self.nav.activate(id);
+                }
+
+                return Task::batch([self.page_shown(), self.update(Message::ChooseFeed)]);
+            }
+            Message::FollowInput(input) => {
+                self.follow_input = input;
+            }
+            Message::DownloadInput(url) => {
+                self.download_input = url;
+            }
+            Message::Download(url) => {
+                let url = url.trim().to_string();
+
+                if url.is_empty() {
+                    return Task::none();
+                }
+
+                let Some(dir) = dirs::download_dir() else {
+                    tracing::warn!("no downloads folder to save {url} into");
+                    return Task::none();
+                };
+
+                let destination = download::destination(&dir, &download::file_name(&url));
+                self.downloads
+                    .push(url, destination, download::Purpose::Save, None);
+                self.download_input.clear();
+
+                return self.start_downloads();
+            }
+            Message::DownloadProgress(id, progress) => {
+                let Some(item) = self.downloads.get_mut(id) else {
+                    return Task::none();
+                };
+
+                match progress {
+                    download::Progress::Started { received, total } => {
+                        item.received = received;
+                        item.total = total;
+                    }
+                    download::Progress::Received(received) => {
+                        item.received = received;
+                    }
+                    download::Progress::Retrying(why) => {
+                        tracing::warn!("retrying download of {}: {why}", item.url);
+                    }
+                    download::Progress::Finished => {
+                        item.state = download::State::Done;
+                        let name = item.name();
+                        self.download_tasks.remove(&id);
+
+                        if item.purpose == download::Purpose::Import {
+                            let import =
+                                Task::perform(import::import(item.destination.clone()), |result| {
+                                    cosmic::Action::App(Message::BookImported(result))
+                                });
+
+                            return Task::batch([import, self.start_downloads()]);
+                        }
+
+                        let toast = self.push_toast(fl!("download-finished", name = name));
+
+                        return Task::batch([toast, self.start_downloads()]);
+                    }
+                    download::Progress::Failed(why) => {
+                        tracing::error!("failed to download {}: {why}", item.url);
+                        item.state = download::State::Failed(why);
+                        self.download_tasks.remove(&id);
+
+                        return self.start_downloads();
+                    }
+                }
+            }
+            Message::PauseDownload(id) => {
+                if let Some(item) = self.downloads.get_mut(id) {
+                    if matches!(
+                        item.state,
+                        download::State::Queued | download::State::Active
+                    ) {
+                        item.state = download::State::Paused;
+                        // Dropping the handle stops the download, keeping what it received.
+                        self.download_tasks.remove(&id);
+                    }
+                }
+
+                return self.start_downloads();
+            }
+            Message::ResumeDownload(id) => {
+                if let Some(item) = self.downloads.get_mut(id) {
+                    if matches!(
+                        item.state,
+                        download::State::Paused | download::State::Failed(_)
+                    ) {
+                        item.state = download::State::Queued;
+                    }
+                }
+
+                return self.start_downloads();
+            }
+            Message::CancelDownload(id) => {
+                self.download_tasks.remove(&id);
+
+                let Some(item) = self.downloads.remove(id) else {
+                    return Task::none();
+                };
+
+                let start = self.start_downloads();
+
+                if item.state == download::State::Done {
+                    return start;
+                }
+
+                let partial = download::partial(&item.destination);
+
+                return Task::batch([
+                    start,
+                    Task::future(async move {
+                        _ = tokio::fs::remove_file(partial).await;
+                    })
+                    .discard(),
+                ]);
+            }
+            Message::ClearDownloads => {
+                self.downloads.clear_finished();
+            }
+            Message::SetProxy(proxy) => {
+                self.proxy_error = net::set_proxy(&proxy).err();
+                self.config.proxy = proxy;
+                self.connection_test = None;
+            }
+            Message::SetCertificates(path) => {
+                self.certificates_error = net::set_certificates(&path).err();
+                self.config.certificates = path;
+                self.connection_test = None;
+            }
+            Message::TestConnection => {
+                self.connection_test = Some(None);
+
+                return Task::perform(net::test(), |result| {
+                    cosmic::Action::App(Message::ConnectionTested(result))
+                });
+            }
+            Message::ConnectionTested(result) => {
+                self.connection_test = Some(Some(result));
+            }
+            Message::CacheSized(size) => {
+                self.cache_size = Some(size);
+            }
+            Message::SetCacheLimit(limit) => {
+                let digits: String = limit.chars().filter(char::is_ascii_digit).collect();
+
+                self.config.cache_limit = digits.parse().unwrap_or(0);
+                self.save_config();
+            }
+            Message::ApplyCacheLimit => {
+                cache::set_capacity(self.config.cache_limit);
+
+                return Task::perform(cache::shrink(), |size| {
+                    cosmic::Action::App(Message::CacheSized(size))
+                });
+            }
+            Message::ClearCache => {
+                return Task::perform(cache::clear(), |result| {
+                    cosmic::Action::App(Message::CacheCleared(result))
+                });
+            }
+            Message::CacheCleared(result) => {
+                let text = match result {
+                    Ok(()) => {
+                        self.cache_size = Some(0);
+                        fl!("cache-cleared")
+                    }
+                    Err(why) => {
+                        tracing::error!("failed to clear the cache: {why}");
+                        fl!("cache-clear-failed", reason = why)
+                    }
+                };
+
+                return self.push_toast(text);
+            }
+            Message::PowerChanged(power) => {
+                self.power = power;
+                return self.resume_deferred_work();
+            }
+            Message::ScreenReaderChanged(enabled) => {
+                self.screen_reader = enabled;
+            }
+            Message::SetIgnorePowerSaving(ignore) => {
+                self.config.ignore_power_saving = ignore;
+                self.save_config();
+                return self.resume_deferred_work();
+            }
+            Message::Online(online) => {
+                self.online = online;
+
+                // Try again what failed while offline.
+                if online
+                    && self.feed.error.is_some()
+                    && self.nav.active_data::<Page>() == Some(&Page::Feed)
+                {
+                    return self.update(Message::LoadFeed(true));
+                }
+            }
+            Message::ProbeNetwork => {
+                return Task::future(net::probe()).discard();
+            }
+            Message::NewSource => {
+                self.source_form = Some(SourceForm::default());
+            }
+            Message::EditSource(id) => {
+                if let Some(source) = self.config.sources.iter().find(|source| source.id == id) {
+                    self.source_form = Some(SourceForm {
+                        id: Some(id),
+                        name: source.name.clone(),
+                        url: source.url.clone(),
+                        kind: source.kind,
+                        user: source.user.clone(),
+                        ..SourceForm::default()
+                    });
+                }
+            }
+            Message::SourceName(name) => {
+                if let Some(form) = &mut self.source_form {
+                    form.name = name;
+                }
+            }
+            Message::SourceUrl(url) => {
+                if let Some(form) = &mut self.source_form {
+                    form.url = url;
+                    form.test = None;
+                }
+            }
+            Message::SetSourceKind(kind) => {
+                if let Some(form) = &mut self.source_form {
+                    form.kind = kind;
+                    form.test = None;
+                }
+            }
+            Message::SourceUser(user) => {
+                if let Some(form) = &mut self.source_form {
+                    form.user = user;
+                    form.test = None;
+                }
+            }
+            Message::SourcePassword(password) => {
+                if let Some(form) = &mut self.source_form {
+                    form.password = password;
+                    form.test = None;
+                }
+            }
+            Message::TestSource => {
+                let Some(form) = &mut self.source_form else {
+                    return Task::none();
+                };
+
+                form.testing = true;
+                form.test = None;
+
+                let source = form.source(form.id.unwrap_or(u64::MAX));
+
+                return Task::perform(sources::test(source, form.password.clone()), |result| {
+                    cosmic::Action::App(Message::SourceTested(result))
+                });
+            }
+            Message::SourceTested(result) => {
+                if let Some(form) = &mut self.source_form {
+                    form.testing = false;
+                    form.test = Some(result);
+                }
+            }
+            Message::SaveSource => {
+                let Some(form) = self.source_form.take() else {
+                    return Task::none();
+                };
+
+                let id = form
+                    .id
+                    .unwrap_or_else(|| sources::next_id(&self.config.sources));
+                let mut source = form.source(id);
+
+                match self
+                    .config
+                    .sources
+                    .iter_mut()
+                    .find(|source| source.id == id)
+                {
+                    Some(existing) => {
+                        source.enabled = existing.enabled;
+                        *existing = source.clone();
+                    }
+                    None => {
+                        source.enabled = true;
+                        self.config.sources.push(source.clone());
+                    }
+                }
+
+                self.save_config();
+
+                let key = source.secret_key();
+                let password = form.password;
+
+                // A password is only kept for a user to sign in as.
+                return Task::future(async move {
+                    let result = if source.user.is_empty() {
+                        secrets::forget(key).await
+                    } else if !password.is_empty() {
+                        secrets::store(key, password).await
+                    } else {
+                        Ok(())
+                    };
+
+                    if let Err(why) = result {
+                        tracing::error!("failed to store the password of {}: {why}", source.name);
+                    }
+                })
+                .discard();
+            }
+            Message::CancelSource => {
+                self.source_form = None;
+            }
+            Message::RemoveSource(id) => {
+                let Some(index) = self
+                    .config
+                    .sources
+                    .iter()
+                    .position(|source| source.id == id)
+                else {
+                    return Task::none();
+                };
+
+                let source = self.config.sources.remove(index);
+                self.save_config();
+
+                if self.catalog.source == Some(id) {
+                    self.catalog = CatalogState::default();
+                }
+
+                return Task::future(async move {
+                    if let Err(why) = secrets::forget(source.secret_key()).await {
+                        tracing::error!("failed to forget the password of {}: {why}", source.name);
+                    }
+                })
+                .discard();
+            }
+            Message::NewsInput(input) => {
+                self.news.input = input;
+                self.news.error = None;
+            }
+            Message::FollowNews => {
+                let url = self.news.input.trim().to_string();
+
+                if url.is_empty() || self.news.loading.contains(&url) {
+                    return Task::none();
+                }
+
+                if self.config.news.iter().any(|followed| followed.url == url) {
+                    self.news.error = Some(fl!("news-following"));
+                    return Task::none();
+                }
+
+                self.news.loading.insert(url.clone());
+
+                return Task::perform(rss::fetch(url.clone()), move |result| {
+                    cosmic::Action::App(Message::NewsFollowed(url.clone(), result))
+                });
+            }
+            Message::UnfollowNews(url) => {
+                self.config.news.retain(|followed| followed.url != url);
+                self.save_config();
+
+                self.news.feeds.remove(&url);
+                self.news.read.forget(&url);
+                self.save_news();
+
+                if self.news.filter.as_ref() == Some(&url) {
+                    self.news.filter = None;
+                }
+            }
+            Message::RefreshNews => {
+                let urls: Vec<String> = self
+                    .config
+                    .news
+                    .iter()
+                    .map(|followed| followed.url.clone())
+                    .filter(|url| !self.news.loading.contains(url))
+                    .collect();
+
+                self.news.loading.extend(urls.iter().cloned());
+
+                return Task::batch(urls.into_iter().map(|url| {
+                    Task::perform(rss::fetch(url.clone()), move |result| {
+                        cosmic::Action::App(Message::NewsFetched(url.clone(), result))
+                    })
+                }));
+            }
+            Message::NewsFollowed(url, result) => {
+                self.news.loading.remove(&url);
+
+                // A feed typed in is followed once it turns out to be one.
+                match result {
+                    Ok(feed) => {
+                        self.config.news.push(rss::Followed {
+                            url: url.clone(),
+                            title: feed.title.clone(),
+                        });
+                        self.save_config();
+
+                        self.news.input.clear();
+                        self.news.feeds.insert(url, Ok(feed));
+                    }
+                    Err(why) => self.news.error = Some(why),
+                }
+            }
+            Message::NewsFetched(url, result) => {
+                self.news.loading.remove(&url);
+
+                // The feed may have been unfollowed while it was fetched.
+                if !self.config.news.iter().any(|followed| followed.url == url) {
+                    return Task::none();
+                }
+
+                if let Ok(feed) = &result {
+                    self.news.read.prune(&url, feed);
+                    self.save_news();
+                }
+
+                // Keep the articles fetched before if the feed can't be fetched now.
+                match (result, self.news.feeds.get(&url)) {
+                    (Err(why), Some(Ok(_))) => {
+                        tracing::error!("failed to fetch news feed {url}: {why}");
+                    }
+                    (result, _) => {
+                        self.news.feeds.insert(url, result);
+                    }
+                }
+            }
+            Message::FilterNews(filter) => {
+                self.news.filter = filter;
+            }
+            Message::OpenArticle(feed, id) => {
+                self.news.read.set_read(&feed, &id, true);
+                self.save_news();
+                self.news.article = Some((feed, id));
+            }
+            Message::CloseArticle => {
+                self.news.article = None;
+            }
+            Message::SetArticleRead(feed, id, read) => {
+                self.news.read.set_read(&feed, &id, read);
+                self.save_news();
+            }
+            Message::MarkNewsRead => {
+                for (url, feed) in &self.news.feeds {
+                    if self
+                        .news
+                        .filter
+                        .as_ref()
+                        .is_some_and(|filter| filter != url)
+                    {
+                        continue;
+                    }
+
+                    for article in feed.iter().flat_map(|feed| &feed.articles) {
+                        self.news.read.set_read(url, &article.id, true);
+                    }
+                }
+
+                self.save_news();
+            }
+            Message::AddToWishlist(isbn, note) => {
+                self.wishlist.add(isbn.clone(), note);
+                self.save_wishlist();
+
+                return self.push_toast(fl!("wishlist-added", isbn = isbn));
+            }
+            Message::RemoveFromWishlist(isbn) => {
+                self.wishlist.remove(&isbn);
+                self.save_wishlist();
+            }
+            Message::NewWebhook => {
+                self.webhook_form = Some(WebhookForm {
+                    events: webhook::Event::ALL.into_iter().collect(),
+                    template: text_editor::Content::with_text(webhook::DEFAULT_TEMPLATE),
+                    ..WebhookForm::default()
+                });
+            }
+            Message::EditWebhook(id) => {
+                if let Some(webhook) = self.config.webhooks.iter().find(|hook| hook.id == id) {
+                    self.webhook_form = Some(WebhookForm {
+                        id: Some(id),
+                        name: webhook.name.clone(),
+                        url: webhook.url.clone(),
+                        events: webhook.events.iter().copied().collect(),
+                        template: text_editor::Content::with_text(&webhook.template),
+                        ..WebhookForm::default()
+                    });
+                }
+            }
+            Message::WebhookName(name) => {
+                if let Some(form) = &mut self.webhook_form {
+                    form.name = name;
+                }
+            }
+            Message::WebhookUrl(url) => {
+                if let Some(form) = &mut self.webhook_form {
+                    form.url = url;
+                    form.test = None;
+                }
+            }
+            Message::SetWebhookEvent(event, fired) => {
+                if let Some(form) = &mut self.webhook_form {
+                    if fired {
+                        form.events.insert(event);
+                    } else {
+                        form.events.remove(&event);
+                    }
+                }
+            }
+            Message::WebhookTemplate(action) => {
+                if let Some(form) = &mut self.webhook_form {
+                    if action.is_edit() {
+                        form.test = None;
+                    }
+
+                    form.template.perform(action);
+                }
+            }
+            Message::TestWebhook => {
+                let Some(form) = &mut self.webhook_form else {
+                    return Task::none();
+                };
+
+                form.test = None;
+
+                let event = webhook::Event::ALL
+                    .into_iter()
+                    .find(|event| form.events.contains(event))
+                    .unwrap_or(webhook::Event::BookFinished);
+                let sample = Book {
+                    title: fl!("webhook-sample-title"),
+                    author: fl!("webhook-sample-author"),
+                    progress: 1.0,
+                    ..Book::default()
+                };
+
+                match webhook::render(&form.template.text(), event, &sample) {
+                    Ok(payload) => {
+                        form.testing = true;
+
+                        return Task::perform(webhook::fire(form.url.clone(), payload), |result| {
+                            cosmic::Action::App(Message::WebhookTested(result))
+                        });
+                    }
+                    Err(why) => form.test = Some(Err(why)),
+                }
+            }
+            Message::WebhookTested(result) => {
+                if let Some(form) = &mut self.webhook_form {
+                    form.testing = false;
+                    form.test = Some(result);
+                }
+            }
+            Message::SaveWebhook => {
+                let Some(form) = self.webhook_form.take() else {
+                    return Task::none();
+                };
+
+                let id = form
+                    .id
+                    .unwrap_or_else(|| webhook::next_id(&self.config.webhooks));
+                let mut webhook = form.webhook(id);
+
+                match self.config.webhooks.iter_mut().find(|hook| hook.id == id) {
+                    Some(existing) => {
+                        webhook.enabled = existing.enabled;
+                        *existing = webhook;
+                    }
+                    None => self.config.webhooks.push(webhook),
+                }
+
+                self.save_config();
+            }
+            Message::CancelWebhook => {
+                self.webhook_form = None;
+            }
+            Message::RemoveWebhook(id) => {
+                self.config.webhooks.retain(|webhook| webhook.id != id);
+                self.save_config();
+            }
+            Message::SetWebhookEnabled(id, enabled) => {
+                if let Some(webhook) = self.config.webhooks.iter_mut().find(|hook| hook.id == id) {
+                    webhook.enabled = enabled;
+                    self.save_config();
+                }
+            }
+            Message::WebhookFired(name, result) => {
+                if let Err(why) = result {
+                    tracing::error!("failed to fire webhook {name}: {why}");
+                }
+            }
+            Message::SetSourceEnabled(id, enabled) => {
+                if let Some(source) = self
+                    .config
+                    .sources
+                    .iter_mut()
+                    .find(|source| source.id == id)
+                {
+                    source.enabled = enabled;
+                    self.save_config();
+                }
+
+                if !enabled && self.catalog.source == Some(id) {
+                    self.catalog = CatalogState::default();
+                }
+            }
+            Message::OpenCatalog(id) => {
+                let Some(source) = self.config.sources.iter().find(|source| source.id == id) else {
+                    return Task::none();
+                };
+
+                self.catalog = CatalogState {
+                    source: Some(id),
+                    url: source.url.clone(),
+                    ..CatalogState::default()
+                };
+
+                let (task, handle) = Task::perform(
+                    sources::credentials(source.user.clone(), id),
+                    move |result| cosmic::Action::App(Message::CatalogOpened(id, result)),
+                )
+                .abortable();
+
+                self.catalog.task = Some(handle.abort_on_drop());
+                return task;
+            }
+            Message::CatalogOpened(id, result) => {
+                if self.catalog.source != Some(id) {
+                    return Task::none();
+                }
+
+                match result {
+                    Ok(credentials) => {
+                        self.catalog.credentials = credentials;
+                        return self.load_catalog(self.catalog.url.clone(), false);
+                    }
+                    Err(why) => {
+                        self.catalog.task = None;
+                        self.catalog.error = Some(why);
+                    }
+                }
+            }
+            Message::BrowseCatalog(url) => {
+                let previous = std::mem::take(&mut self.catalog.url);
+                self.catalog.history.push(previous);
+                self.catalog.url = url.clone();
+                return self.load_catalog(url, false);
+            }
+            Message::CatalogBack => match self.catalog.history.pop() {
+                Some(url) => {
+                    self.catalog.url = url.clone();
+                    return self.load_catalog(url, false);
+                }
+                None => self.catalog = CatalogState::default(),
+            },
+            Message::CatalogMore => {
+                if self.catalog.task.is_some() {
+                    return Task::none();
+                }
+
+                if let Some(next) = self
+                    .catalog
+                    .feed
+                    .as_ref()
+                    .and_then(|feed| feed.next.clone())
+                {
+                    return self.load_catalog(next, true);
+                }
+            }
+            Message::CatalogLoaded(append, result) => {
+                self.catalog.task = None;
+
+                match result {
+                    Ok(feed) => match &mut self.catalog.feed {
+                        Some(shown) if append => {
+                            shown.entries.extend(feed.entries);
+                            shown.next = feed.next;
+                        }
+                        _ => self.catalog.feed = Some(feed),
+                    },
+                    Err(why) => self.catalog.error = Some(why),
+                }
+            }
+            Message::DiscoverSite(index) => {
+                let Some(site) = discover::SITES.get(index) else {
+                    return Task::none();
+                };
+
+                self.discover.site = index;
+                self.discover.history.clear();
+                self.discover.url = site.home.to_string();
+                return self.load_discover(site.home.to_string(), false);
+            }
+            Message::DiscoverQuery(query) => {
+                self.discover.query = query;
+            }
+            Message::DiscoverSearch => {
+                if self.discover.query.trim().is_empty() {
+                    return Task::none();
+                }
+
+                let url = discover::SITES[self.discover.site].search_url(&self.discover.query);
+                return self.update(Message::DiscoverBrowse(url));
+            }
+            Message::DiscoverBrowse(url) => {
+                let previous = std::mem::take(&mut self.discover.url);
+                self.discover.history.push(previous);
+                self.discover.url = url.clone();
+                return self.load_discover(url, false);
+            }
+            Message::DiscoverBack => {
+                if let Some(url) = self.discover.history.pop() {
+                    self.discover.url = url.clone();
+                    return self.load_discover(url, false);
+                }
+            }
+            Message::DiscoverMore => {
+                if self.discover.task.is_some() {
+                    return Task::none();
+                }
+
+                if let Some(next) = self
+                    .discover
+                    .feed
+                    .as_ref()
+                    .and_then(|feed| feed.next.clone())
+                {
+                    return self.load_discover(next, true);
+                }
+            }
+            Message::DiscoverLoaded(append, result) => {
+                self.discover.task = None;
+
+                let feed = match result {
+                    Ok(feed) => feed,
+                    Err(why) => {
+                        self.discover.error = Some(why);
+                        return Task::none();
+                    }
+                };
+
+                let covers: HashSet<String> = feed
+                    .entries
+                    .iter()
+                    .filter_map(|entry| entry.cover.clone())
+                    .filter(|url| !self.discover.covers.contains(url))
+                    .collect();
+
+                match &mut self.discover.feed {
+                    Some(shown) if append => {
+                        shown.entries.extend(feed.entries);
+                        shown.next = feed.next;
+                    }
+                    _ => self.discover.feed = Some(feed),
+                }
+
+                return Task::batch(covers.into_iter().map(|url| {
+                    let load = thumbnail::load(
+                        opds::fetch_cover(url.clone(), None),
+                        thumbnail::COVER_SIDE,
+                    );
+
+                    Task::perform(load, move |result| {
+                        cosmic::Action::App(Message::DiscoverCoverLoaded(url.clone(), result))
+                    })
+                }));
+            }
+            Message::DiscoverCoverLoaded(url, result) => match result {
+                Ok(handle) => self.discover.covers.insert(url, handle),
+                Err(why) => tracing::error!("failed to fetch {url}: {why}"),
+            },
+            Message::ImportBook(url, name) => {
+                return self.import_book(url, name, None);
+            }
+            Message::CloseCatalog => {
+                self.catalog = CatalogState::default();
+            }
+            Message::DownloadBook(url, name) => {
+                let Some(dir) = dirs::download_dir() else {
+                    tracing::warn!("no downloads folder to save {url} into");
+                    return Task::none();
+                };
+
+                let destination = download::destination(&dir, &name);
+                self.downloads.push(
+                    url,
+                    destination,
+                    download::Purpose::Save,
+                    self.catalog.credentials.clone(),
+                );
+
+                let toast = self.push_toast(fl!("catalog-queued", name = name));
+
+                return Task::batch([toast, self.start_downloads()]);
+            }
+            Message::SetDownloadLimit(limit) => {
+                let digits: String = limit.chars().filter(char::is_ascii_digit).collect();
+
+                self.config.download_limit = digits.parse().unwrap_or(0);
+                self.download_throttle.set_limit(self.config.download_limit);
+                self.save_config();
+            }
+            Message::Follow(actor) => {
+                let actor = actor.trim().trim_start_matches('@').to_string();
+
+                if actor.is_empty()
+                    || self
+                        .config
+                        .followed
+                        .iter()
+                        .any(|followed| followed.actor == actor)
+                {
+                    return Task::none();
+                }
+
+                self.config.followed.push(follow::Followed {
+                    actor,
+                    notify: true,
+                    last_seen: 0,
+                });
+                self.follow_input.clear();
+                self.save_config();
+
+                // Note where the account is at, so only posts from now on are news.
+                return self.update(Message::PollFollowed);
+            }
+            Message::Unfollow(index) => {
+                if index < self.config.followed.len() {
+                    self.config.followed.remove(index);
+                    self.save_config();
+                }
+            }
+            Message::SetFollowNotify(index, notify) => {
+                if let Some(followed) = self.config.followed.get_mut(index) {
+                    followed.notify = notify;
+
+                    // Start afresh, rather than notifying of everything posted meanwhile.
+                    followed.last_seen = 0;
+                    self.save_config();
+
+                    if notify {
+                        return self.update(Message::PollFollowed);
+                    }
+                }
+            }
+            Message::PollFollowed => {
+                let followed: Vec<follow::Followed> = self
+                    .config
+                    .followed
+                    .iter()
+                    .filter(|followed| followed.notify)
+                    .cloned()
+                    .collect();
+
+                if self.polling_followed || followed.is_empty() {
+                    return Task::none();
+                }
+
+                self.polling_followed = true;
+
+                return Task::perform(follow::poll(followed), |updates| {
+                    cosmic::Action::App(Message::FollowedPolled(updates))
+                });
+            }
+            Message::FollowedPolled(updates) => {
+                self.polling_followed = false;
+
+                let mut notifications = Vec::new();
+
+                for update in updates {
+                    let Some(followed) = self
+                        .config
+                        .followed
+                        .iter_mut()
+                        .find(|followed| followed.actor == update.actor)
+                    else {
+                        continue;
+                    };
+
+                    followed.last_seen = update.newest;
+
+                    if update.posts.len() > follow::MAX_NOTIFICATIONS {
+                        notifications.push((
+                            fl!(
+                                "follow-new-posts",
+                                author = update.actor.as_str(),
+                                count = update.posts.len()
+                            ),
+                            String::new(),
+                            update.actor.clone(),
+                        ));
+                        continue;
+                    }
+
+                    for post in update.posts {
+                        let author = post.display_name.as_deref().unwrap_or(&post.handle);
+                        notifications.push((
+                            fl!("follow-new-post", author = author),
+                            post.text.clone(),
+                            update.actor.clone(),
+                        ));
+                    }
+                }
+
+                self.save_config();
+
+                return Task::batch(notifications.into_iter().map(|(summary, body, actor)| {
+                    self.notify(
+                        summary,
+                        body,
+                        Some((fl!("notification-show-posts"), Message::ShowFeed(actor))),
+                    )
+                }));
+            }
+            Message::FeedWheel(y) => {
+                if y <= 0.0 || !self.feed.at_top {
+                    self.feed.overscroll = 0.0;
+                    return Task::none();
+                }
+
+                self.feed.overscroll += y;
+
+                if self.feed.overscroll >= PULL_TO_REFRESH && self.feed.task.is_none() {
+                    return self.update(Message::LoadFeed(true));
+                }
+            }
+            Message::OpenImportDialog => {
+                self.import_dialog = Some(String::new());
+
+                return Task::batch([
+                    focus_field(self.import_input_id.clone()),
+                    self.announce_dialog(fl!("import-url")),
+                ]);
+            }
+            Message::CloseImportDialog => {
+                self.import_dialog = None;
+            }
+            Message::ImportUrl(url) => {
+                if let Some(input) = self.import_dialog.as_mut() {
+                    *input = url;
+                }
+            }
+            Message::ImportFromUrl => {
+                let Some(url) = self.import_dialog.take() else {
+                    return Task::none();
+                };
+                let url = url.trim().to_string();
+                let name = download::file_name(&url);
+
+                return self.import_book(url, name, None);
+            }
+            Message::BookImported(result) if !self.library_loaded => {
+                self.waiting_imports.push(result);
+            }
+            Message::BookImported(result) => {
+                let (text, webhooks, open) = match result {
+                    Ok(book) => {
+                        let title = book.title.clone();
+                        let webhooks = self.fire_webhooks(webhook::Event::BookImported, &book);
+                        let id = self.library.insert(book);
+                        self.save_library();
+                        (
+                            fl!("import-done", title = title),
+                            webhooks,
+                            Some((fl!("read"), Message::OpenReader(id))),
+                        )
+                    }
+                    Err(why) => {
+                        tracing::error!("failed to import a downloaded book: {why}");
+                        (fl!("import-failed", reason = why), Task::none(), None)
+                    }
+                };
+
+                let sound = if open.is_some() {
+                    self.play_sound(Sound::Imported)
+                } else {
+                    Task::none()
+                };
+
+                let notification = self.notify_in_background(text.clone(), open);
+                let toast = self.push_toast(text);
+
+                return Task::batch([toast, webhooks, notification, sound]);
+            }
+            Message::OpenExportDialog => {
+                let format = export::Format::default();
+
+                self.export_dialog = Some(ExportDialog {
+                    format,
+                    fields: Field::ALL.into_iter().collect(),
+                    path: export::default_path(format).to_string_lossy().into_owned(),
+                });
+
+                return self.announce_dialog(fl!("export-library"));
+            }
+            Message::CloseExportDialog => {
+                self.export_dialog = None;
+            }
+            Message::ExportFormat(format) => {
+                if let Some(state) = self.export_dialog.as_mut() {
+                    state.format = format;
+                    state.path = PathBuf::from(&state.path)
+                        .with_extension(format.extension())
+                        .to_string_lossy()
+                        .into_owned();
+                }
+            }
+            Message::ExportField(field, included) => {
+                if let Some(state) = self.export_dialog.as_mut() {
+                    if included {
+                        state.fields.insert(field);
+                    } else {
+                        state.fields.remove(&field);
+                    }
+                }
+            }
+            Message::ExportPath(path) => {
+                if let Some(state) = self.export_dialog.as_mut() {
+                    state.path = path;
+                }
+            }
+            Message::Export => {
+                let Some(state) = self.export_dialog.take() else {
+                    return Task::none();
+                };
+
+                let fields: Vec<Field> = Field::ALL
+                    .into_iter()
+                    .filter(|field| state.fields.contains(field))
+                    .collect();
+
+                let books: Vec<Book> = self
+                    .library
+                    .books()
+                    .iter()
+                    .filter(|book| !book.is_trashed())
+                    .cloned()
+                    .collect();
+
+                let path = PathBuf::from(state.path);
+
+                return Task::perform(
+                    async move {
+                        let contents = export::render(&books, &fields, state.format);
+
+                        tokio::fs::write(&path, contents)
+                            .await
+                            .map(|()| books.len())
+                            .map_err(|why| format!("{}: {why}", path.display()))
+                    },
+                    |result| cosmic::Action::App(Message::Exported(result)),
+                );
+            }
+            Message::NoteInput(text) => {
+                self.note_input = text;
+            }
+            Message::AddNote(id) => {
+                let text = std::mem::take(&mut self.note_input);
+
+                if let Some(book) = self.library.get_mut(id) {
+                    book.notes.push(Note {
+                        created: library::now(),
+                        text: text.trim().to_string(),
+                    });
+                    self.save_library();
+                }
+            }
+            Message::EditNote(id, index, text) => {
+                if let Some(note) = self
+                    .library
+                    .get_mut(id)
+                    .and_then(|book| book.notes.get_mut(index))
+                {
+                    note.text = text;
+                    self.save_library();
+                }
+            }
+            Message::WriteLibrary(now) => {
+                let settled = self
+                    .library_changed
+                    .is_some_and(|changed| now || changed.elapsed() >= LIBRARY_SAVE_DELAY);
+
+                if settled {
+                    self.library_changed = None;
+                    return self.write_library();
+                }
+            }
+            Message::DeleteNote(id, index) => {
+                if let Some(book) = self.library.get_mut(id) {
+                    if index < book.notes.len() {
+                        book.notes.remove(index);
+                        self.save_library();
+                    }
+                }
+            }
+            Message::BookmarkInput(text) => {
+                self.bookmark_input = text;
+            }
+            Message::AddBookmark => {
+                let Some(reader) = &self.reader else {
+                    return Task::none();
+                };
+
+                let bookmark = Bookmark {
+                    created: library::now(),
+                    location: reader.location(),
+                    label: std::mem::take(&mut self.bookmark_input).trim().to_string(),
+                };
+
+                if let Some(book) = self.library.get_mut(reader.book_id()) {
+                    book.add_bookmark(bookmark);
+                    self.save_library();
+                }
+            }
+            Message::GoToBookmark(index) => {
+                let Some(location) = self.reader.as_ref().and_then(|reader| {
+                    let book = self.library.get(reader.book_id())?;
+                    Some(book.bookmarks.get(index)?.location)
+                }) else {
+                    return Task::none();
+                };
+
+                return match &self.reader {
+                    Some(Reader::Epub(_)) => self.load_chapter(location.chapter, location.offset),
+                    Some(Reader::Pdf(_)) => self.update(Message::PdfPage(location.chapter)),
+                    Some(Reader::Comic(_)) => self.update(Message::ComicPage(location.chapter)),
+                    None => Task::none(),
+                };
+            }
+            Message::DeleteBookmark(index) => {
+                let Some(id) = self.reader.as_ref().map(Reader::book_id) else {
+                    return Task::none();
+                };
+
+                if let Some(book) = self.library.get_mut(id) {
+                    if index < book.bookmarks.len() {
+                        book.bookmarks.remove(index);
+                        self.save_library();
+                    }
+                }
+            }
+            Message::SelectBlock(index) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.select_block(index);
+                }
+            }
+            Message::HighlightNoteInput(text) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.note_input = text;
+                }
+            }
+            Message::AddHighlight(color) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let id = reader.book_id;
+
+                if let Some(highlight) = reader.take_highlight(color) {
+                    if let Some(book) = self.library.get_mut(id) {
+                        book.add_highlight(highlight);
+                        self.save_library();
+                    }
+                }
+            }
+            Message::CancelHighlight => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.clear_selection();
+                }
+            }
+            Message::LookUpBlock(index) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.look_up_in(index);
+                }
+            }
+            Message::LookUp(word) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let word = dictionary::normalize(&word);
+                if word.is_empty() {
+                    return Task::none();
+                }
+
+                reader.look_up = Some(reader::LookUp {
+                    word: word.clone(),
+                    result: None,
+                });
+
+                return Task::perform(
+                    dictionary::lookup(word.clone(), self.config.dictionary_online),
+                    move |result| cosmic::Action::App(Message::DefinitionFound(word, result)),
+                );
+            }
+            Message::DefinitionFound(word, result) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    if let Some(look_up) = reader.look_up.as_mut().filter(|l| l.word == word) {
+                        look_up.result = Some(result);
+                    }
+                }
+            }
+            Message::CloseDefinition => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.close_look_up();
+                }
+            }
+            Message::CopyDefinition => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_ref() {
+                    if let Some(Some(Ok(Some(definition)))) =
+                        reader.look_up.as_ref().map(|look_up| &look_up.result)
+                    {
+                        return cosmic::iced::clipboard::write(definition.to_text());
+                    }
+                }
+            }
+            Message::CopyQuote => {
+                let Some(Reader::Epub(reader)) = self.reader.as_ref() else {
+                    return Task::none();
+                };
+
+                let (Some(text), Some(book)) =
+                    (reader.selected_text(), self.library.get(reader.book_id))
+                else {
+                    return Task::none();
+                };
+
+                let chapter = toc_title(&reader.epub.toc, reader.chapter_index);
+                return clipboard::write(citation::quote(&text, book, chapter));
+            }
+            Message::TranslateSelection => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let Some(text) = reader.selected_text() else {
+                    return Task::none();
+                };
+
+                if self.config.translate_url.trim().is_empty() {
+                    return self.push_toast(fl!("translate-no-server"));
+                }
+
+                let source = match self.config.translate_source.as_str() {
+                    "" => translate::AUTO.to_string(),
+                    code => code.to_string(),
+                };
+                let target = match self.config.translate_target.as_str() {
+                    "" => "en".to_string(),
+                    code => code.to_string(),
+                };
+
+                reader.translation = Some(reader::Translation::new(text, source, target));
+
+                let languages = Task::perform(
+                    translate::languages(self.config.translate_url.clone()),
+                    |result| cosmic::Action::App(Message::TranslationLanguages(result)),
+                );
+
+                return Task::batch([languages, self.translate()]);
+            }
+            Message::TranslationLanguages(result) => match result {
+                Ok(languages) => {
+                    if let Some(translation) = self.translation_mut() {
+                        translation.set_languages(languages);
+                    }
+                }
+                Err(why) => tracing::error!("failed to list translation languages: {why}"),
+            },
+            Message::TranslateSource(index) => {
+                let Some(translation) = self.translation_mut() else {
+                    return Task::none();
+                };
+
+                let Some(code) = translation.source_code(index).map(str::to_string) else {
+                    return Task::none();
+                };
+
+                translation.source = code.clone();
+                self.config.translate_source = code;
+                self.save_config();
+                return self.translate();
+            }
+            Message::TranslateTarget(index) => {
+                let Some(translation) = self.translation_mut() else {
+                    return Task::none();
+                };
+
+                let Some(code) = translation.target_code(index).map(str::to_string) else {
+                    return Task::none();
+                };
+
+                translation.target = code.clone();
+                self.config.translate_target = code;
+                self.save_config();
+                return self.translate();
+            }
+            Message::Translated(result) => {
+                if let Some(translation) = self.translation_mut() {
+                    translation.result = Some(result);
+                    translation.task = None;
+                }
+            }
+            Message::CopyTranslation => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_ref() {
+                    if let Some(Some(Ok(text))) = reader
+                        .translation
+                        .as_ref()
+                        .map(|translation| &translation.result)
+                    {
+                        return cosmic::iced::clipboard::write(text.clone());
+                    }
+                }
+            }
+            Message::CloseTranslation => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.translation = None;
+                }
+            }
+            Message::OpenFootnote(target) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                reader.close_look_up();
+                reader.translation = None;
+                reader.footnote = Some(reader::Footnote {
+                    target: target.clone(),
+                    chapter: reader.epub.chapter_of(&target),
+                    result: None,
+                });
+
+                return Task::perform(
+                    reader::load_footnote(reader.epub.clone(), target.clone()),
+                    move |result| cosmic::Action::App(Message::FootnoteLoaded(target, result)),
+                );
+            }
+            Message::FootnoteLoaded(target, result) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    if let Some(footnote) = reader.footnote.as_mut().filter(|f| f.target == target)
+                    {
+                        footnote.result = Some(result);
+                    }
+                }
+            }
+            Message::GoToFootnote => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let Some(footnote) = reader.footnote.take() else {
+                    return Task::none();
+                };
+
+                let Some(chapter) = footnote.chapter else {
+                    return Task::none();
+                };
+
+                let offset = match footnote.result {
+                    Some(Ok(Some(note))) => note.offset,
+                    _ => 0.0,
+                };
+
+                return self.load_chapter(chapter, offset);
+            }
+            Message::CloseFootnote => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.footnote = None;
+                }
+            }
+            Message::GoToHighlight(index) => {
+                let Some(location) = self.reader.as_ref().and_then(|reader| {
+                    let book = self.library.get(reader.book_id())?;
+                    Some(book.highlights.get(index)?.location)
+                }) else {
+                    return Task::none();
+                };
+
+                return self.load_chapter(location.chapter, location.offset);
+            }
+            Message::EditHighlightNote(index, text) => {
+                let Some(id) = self.reader.as_ref().map(Reader::book_id) else {
+                    return Task::none();
+                };
+
+                if let Some(highlight) = self
+                    .library
+                    .get_mut(id)
+                    .and_then(|book| book.highlights.get_mut(index))
+                {
+                    highlight.note = text;
+                    self.save_library();
+                }
+            }
+            Message::DeleteHighlight(index) => {
+                let Some(id) = self.reader.as_ref().map(Reader::book_id) else {
+                    return Task::none();
+                };
+
+                if let Some(book) = self.library.get_mut(id) {
+                    if index < book.highlights.len() {
+                        book.highlights.remove(index);
+                        self.save_library();
+                    }
+                }
+            }
+            Message::ExportAnnotations(format) => {
+                let Some(book) = self
+                    .reader
+                    .as_ref()
+                    .and_then(|reader| self.library.get(reader.book_id()))
+                else {
+                    return Task::none();
+                };
+
+                let path = annotations::default_path(book, format);
+                let contents = annotations::render(book, format);
+
+                return Task::perform(
+                    async move {
+                        tokio::fs::write(&path, contents)
+                            .await
+                            .map(|_| path.clone())
+                            .map_err(|why| format!("{}: {why}", path.display()))
+                    },
+                    |result| cosmic::Action::App(Message::AnnotationsExported(result)),
+                );
+            }
+            Message::AnnotationsExported(result) => {
+                let text = match result {
+                    Ok(path) => fl!("annotations-exported", path = path.display().to_string()),
+                    Err(why) => {
+                        tracing::error!("failed to export annotations: {why}");
+                        fl!("annotations-export-failed")
+                    }
+                };
+
+                return self.push_toast(text);
+            }
+            Message::PrintAnnotations => {
+                let Some(book) = self
+                    .reader
+                    .as_ref()
+                    .and_then(|reader| self.library.get(reader.book_id()))
+                else {
+                    return Task::none();
+                };
+
+                return Task::perform(print::print(annotations::document(book)), |result| {
+                    cosmic::Action::App(Message::Printed(result))
+                });
+            }
+            Message::PrintReport => {
+                return Task::perform(print::print(self.reading_report()), |result| {
+                    cosmic::Action::App(Message::Printed(result))
+                });
+            }
+            Message::Printed(result) => {
+                let text = match result {
+                    Ok(true) => fl!("printed"),
+                    Ok(false) => return Task::none(),
+                    Err(why) => {
+                        tracing::error!("failed to print: {why}");
+                        fl!("print-failed")
+                    }
+                };
+
+                return self.push_toast(text);
+            }
+            Message::VerifyLibrary(full) => {
+                if self.verify_job.is_some() {
+                    return Task::none();
+                }
+
+                let books: Vec<Book> = self
+                    .library
+                    .books()
+                    .iter()
+                    .filter(|book| !book.is_trashed() && book.path.is_some())
+                    .cloned()
+                    .collect();
+
+                let total = books.len();
+                let (task, handle) =
+                    Task::run(integrity::verify(books, full), Message::VerifyProgress)
+                        .chain(Task::done(Message::VerifyFinished))
+                        .abortable();
+
+                self.verify_job = Some(VerifyJob {
+                    handle,
+                    total,
+                    done: 0,
+                    hashed: 0,
+                    started: Instant::now(),
+                });
+                self.verify_results.clear();
+                self.last_verify = None;
+                self.context_page = ContextPage::Verify;
+                self.core.window.show_context = true;
+                self.close_pairing();
+
+                return task.map(cosmic::Action::App);
+            }
+            Message::VerifyProgress(report) => {
+                if let Some(job) = self.verify_job.as_mut() {
+                    job.done += 1;
+                    job.hashed += usize::from(report.hashed);
+                }
+
+                if let Some(book) = self.library.get_mut(report.id) {
+                    report.apply(book);
+                }
+
+                if report.status != integrity::Status::Ok {
+                    self.verify_results.push((report.id, report.status));
+                }
+            }
+            Message::VerifyFinished => {
+                if let Some(job) = self.verify_job.take() {
+                    let summary = VerifySummary {
+                        checked: job.done,
+                        hashed: job.hashed,
+                        duration: job.started.elapsed(),
+                    };
+
+                    tracing::info!(
+                        "verified {} books in {:?}, hashing {}",
+                        summary.checked,
+                        summary.duration,
+                        summary.hashed
+                    );
+                    self.last_verify = Some(summary);
+                }
+
+                self.save_library();
+            }
+            Message::CancelVerify => {
+                if let Some(job) = self.verify_job.take() {
+                    job.handle.abort();
+                }
+
+                self.save_library();
+            }
+            Message::RelocateBook(id, path) => {
+                if let Some(book) = self.library.get_mut(id) {
+                    book.path = Some(path);
+                    self.save_library();
+                }
+
+                self.verify_results.retain(|(flagged, _)| *flagged != id);
+            }
+            Message::RemoveMissingBook(id) => {
+                let Some(book) = self.library.get(id).cloned() else {
+                    return Task::none();
+                };
+
+                return Task::perform(trash::trash(book, false), move |result| match result {
+                    Ok(book) => cosmic::Action::App(Message::MissingBookRemoved(Box::new(book))),
+                    Err(why) => {
+                        tracing::error!("failed to remove book {id}: {why}");
+                        cosmic::Action::None
+                    }
+                });
+            }
+            Message::MissingBookRemoved(book) => {
+                let id = book.id;
+
+                self.library.replace(*book);
+                self.verify_results.retain(|(flagged, _)| *flagged != id);
+                self.save_library();
+
+                return self.push_trash_toast(vec![id]);
+            }
+            Message::OpenReader(id) => {
+                let Some(book) = self.library.get(id) else {
+                    return Task::none();
+                };
+
+                let Some(path) = book.path.clone() else {
+                    return Task::none();
+                };
+
+                let extension = book.extension().unwrap_or_default();
+
+                return if extension == "pdf" {
+                    Task::perform(reader::open_pdf(path.clone()), move |result| {
+                        cosmic::Action::App(Message::PdfOpened(id, path.clone(), result))
+                    })
+                } else if comic::EXTENSIONS.contains(&extension.as_str()) {
+                    Task::perform(reader::open_comic(path), move |result| {
+                        cosmic::Action::App(Message::ComicOpened(id, result))
+                    })
+                } else {
+                    Task::perform(reader::open_epub(path), move |result| {
+                        cosmic::Action::App(Message::ReaderOpened(id, result))
+                    })
+                };
+            }
+            Message::ReaderOpened(id, result) => {
+                let epub = match result {
+                    Ok(epub) => epub,
+                    Err(why) => {
+                        tracing::error!("failed to open book {id}: {why}");
+                        return self.reader_open_failed();
+                    }
+                };
+
+                let title = epub.title.clone();
+
+                // Fixed-layout books are pages of images, read like comics.
+                if epub.fixed_layout {
+                    return self.show_comic(id, Comic::from_epub(epub), title);
+                }
+
+                let mut location = self.resume_location(id);
+                location.chapter = location.chapter.min(epub.chapter_count().saturating_sub(1));
+
+                let mut reader = EpubReader::new(id, epub);
+                reader.flow = self
+                    .library
+                    .get(id)
+                    .map(|book| book.flow)
+                    .unwrap_or_default();
+
+                return Task::batch([
+                    self.show_reader(Reader::Epub(reader), title),
+                    self.load_chapter(location.chapter, location.offset),
+                ]);
+            }
+            Message::PdfOpened(id, path, result) => {
+                let document = match result {
+                    Ok(document) => document,
+                    Err(why) => {
+                        tracing::error!("failed to open book {id}: {why}");
+                        return self.reader_open_failed();
+                    }
+                };
+
+                let location = self.resume_location(id);
+                let title = document.title.clone().unwrap_or_default();
+                let mut reader = PdfReader::new(id, path, document, location.chapter);
+                let render_page = reader.render_page();
+
+                // Thumbnails wait while saving power, until it's no longer needed.
+                let render_thumbnails = if self.saving_power() {
+                    Task::none()
+                } else {
+                    reader.render_thumbnails()
+                };
+
+                return Task::batch([
+                    self.show_reader(Reader::Pdf(reader), title),
+                    render_page,
+                    render_thumbnails,
+                ]);
+            }
+            Message::PdfPage(index) => {
+                if let Some(Reader::Pdf(reader)) = self.reader.as_mut() {
+                    let task = reader.go_to(index);
+                    let finished = self.record_reading_position();
+                    self.save_library();
+                    return Task::batch([task, finished]);
+                }
+            }
+            Message::PdfPageRendered(id, index, result) => match (self.reader.as_mut(), result) {
+                (Some(Reader::Pdf(reader)), Ok(page)) if reader.book_id == id => {
+                    reader.set_page(index, page);
+                }
+                (_, Err(why)) => {
+                    tracing::error!("failed to render page {index} of book {id}: {why}");
+                }
+                _ => (),
+            },
+            Message::PdfThumbnail(id, index, thumbnail) => {
+                if let Some(Reader::Pdf(reader)) = self.reader.as_mut() {
+                    if reader.book_id == id {
+                        reader.set_thumbnail(index, thumbnail);
+                    }
+                }
+            }
+            Message::PdfZoom(zoom) => {
+                if let Some(Reader::Pdf(reader)) = self.reader.as_mut() {
+                    return reader.set_zoom(zoom);
+                }
+            }
+            Message::ComicOpened(id, result) => match result {
+                Ok(comic) => return self.show_comic(id, comic, String::new()),
+                Err(why) => {
+                    tracing::error!("failed to open book {id}: {why}");
+                    return self.reader_open_failed();
+                }
+            },
+            Message::ComicPage(index) => {
+                if let Some(Reader::Comic(reader)) = self.reader.as_mut() {
+                    let task = reader.go_to(index);
+                    let finished = self.record_reading_position();
+                    self.save_library();
+                    return Task::batch([task, finished]);
+                }
+            }
+            Message::ComicPageLoaded(id, index, result) => match (self.reader.as_mut(), result) {
+                (Some(Reader::Comic(reader)), Ok(page)) if reader.book_id == id => {
+                    reader.set_page(index, page);
+                }
+                (_, Err(why)) => {
+                    tracing::error!("failed to load page {index} of book {id}: {why}");
+                }
+                _ => (),
+            },
+            Message::ComicZoom(zoom) => {
+                if let Some(Reader::Comic(reader)) = self.reader.as_mut() {
+                    reader.zoom = zoom;
+                }
+            }
+            Message::ComicRightToLeft(right_to_left) => {
+                let Some(Reader::Comic(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let task = reader.set_right_to_left(right_to_left);
+
+                if let Some(book) = self.library.get_mut(reader.book_id) {
+                    book.right_to_left = right_to_left;
+                    self.save_library();
+                }
+
+                return task;
+            }
+            Message::ToggleComicPanels => {
+                if let Some(Reader::Comic(reader)) = self.reader.as_mut() {
+                    return reader.toggle_panels();
+                }
+            }
+            Message::ReaderChapter(index) => {
+                return self.load_chapter(index, 0.0);
+            }
+            Message::ReaderChapterLoaded(index, offset, result) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                match result {
+                    Ok(chapter) => {
+                        let mut task = reader.set_chapter(index, chapter, offset);
+
+                        // Keep reading aloud across the chapter break.
+                        if let Some(read_aloud) = reader.read_aloud.as_mut() {
+                            if std::mem::take(&mut read_aloud.resume) {
+                                task = task.chain(reader.play());
+                            }
+                        }
+
+                        let finished = self.record_reading_position();
+                        self.save_library();
+                        return Task::batch([task, finished]);
+                    }
+                    Err(why) => {
+                        tracing::error!("failed to load chapter {index}: {why}");
+                    }
+                }
+            }
+            Message::ReaderImage(id, chapter, image, picture) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    if reader.book_id == id {
+                        reader.set_image(chapter, image, picture);
+                    }
+                }
+            }
+            Message::ReaderScrolled(viewport) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    let task = reader.scrolled(viewport);
+                    let finished = self.record_reading_position();
+                    return Task::batch([task, finished]);
+                }
+            }
+            Message::ReaderSpreadScrolled(viewport) => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_ref() {
+                    return reader.spread_scrolled(viewport);
+                }
+            }
+            Message::ToggleSpread => {
+                let spread = self.reader.as_ref().is_some_and(Reader::spread);
+                self.config.spread = if spread {
+                    Spread::Single
+                } else {
+                    Spread::Double
+                };
+                self.save_config();
+                return self.apply_spread();
+            }
+            Message::SetSpread(spread) => {
+                self.config.spread = spread;
+                self.save_config();
+                return self.apply_spread();
+            }
+            Message::SetFlow(flow) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let task = reader.set_flow(flow);
+
+                if let Some(book) = self.library.get_mut(reader.book_id) {
+                    book.flow = flow;
+                    self.save_library();
+                }
+
+                return task;
+            }
+            Message::ReaderWheel(y) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_ref() else {
+                    return Task::none();
+                };
+
+                let index = reader.chapter_index;
+
+                if y < 0.0 && reader.at_end() && reader.has_next_chapter() {
+                    return self.load_chapter(index + 1, 0.0);
+                }
+
+                if y > 0.0 && reader.at_start() && index > 0 {
+                    return self.load_chapter(index - 1, 1.0);
+                }
+            }
+            Message::WindowResized(id, width) => {
+                // Spreads depend on the width of whichever window shows the reader.
+                if self.reader_window.or(self.core.main_window_id()) == Some(id) {
+                    self.window_width = width;
+                    return self.apply_spread();
+                }
+            }
+            Message::OpenReaderWindow(book) => {
+                if let Some(id) = self.reader_window {
+                    return Task::batch([
+                        window::gain_focus(id),
+                        self.update(Message::OpenReader(book)),
+                    ]);
+                }
+
+                let size = Size::new(READER_WINDOW_WIDTH, READER_WINDOW_HEIGHT);
+                let (id, open) = window::open(window::Settings {
+                    size,
+                    exit_on_close_request: false,
+                    ..Default::default()
+                });
+
+                self.reader_window = Some(id);
+                self.window_width = size.width;
+
+                // A book already open moves out of the main window.
+                if let Some(nav_id) = self.reader_nav.take() {
+                    self.nav.remove(nav_id);
+                    self.activate_page(Page::Page3);
+                }
+
+                return Task::batch([
+                    open.discard(),
+                    self.update(Message::OpenReader(book)),
+                    self.update_title(),
+                ]);
+            }
+            Message::ReaderTurnPage(forward) => {
+                return match self.turn_page(forward) {
+                    Some(task) => Task::batch([task, self.play_sound(Sound::PageTurn)]),
+                    None => Task::none(),
+                };
+            }
+            Message::ToggleToc => {
+                self.toc_sidebar = !self.toc_sidebar;
+            }
+            Message::ToggleTocEntry(id) => {
+                if !self.toc_toggled.remove(&id) {
+                    self.toc_toggled.insert(id);
+                }
+            }
+            Message::TocJump(target) => {
+                return match self.reader {
+                    Some(Reader::Epub(_)) => self.update(Message::ReaderChapter(target)),
+                    Some(Reader::Pdf(_)) => self.update(Message::PdfPage(target)),
+                    Some(Reader::Comic(_)) => self.update(Message::ComicPage(target)),
+                    None => Task::none(),
+                };
+            }
+            Message::BookSearchInput(query) => {
+                self.book_search_input = query;
+            }
+            Message::BookSearch => {
+                let query = self.book_search_input.trim().to_string();
+
+                if query.is_empty() {
+                    self.book_search = None;
+                    return Task::none();
+                }
+
+                let task = match &self.reader {
+                    Some(Reader::Epub(reader)) => Task::run(
+                        search::search_epub(reader.epub.clone(), query),
+                        Message::BookSearchResults,
+                    ),
+                    Some(Reader::Pdf(reader)) => Task::run(
+                        search::search_pdf(reader.path.clone(), query),
+                        Message::BookSearchResults,
+                    ),
+                    // Comics have no text to search.
+                    Some(Reader::Comic(_)) | None => return Task::none(),
+                };
+
+                let (task, handle) = task
+                    .chain(Task::done(Message::BookSearchFinished))
+                    .abortable();
+
+                // Replacing the previous search stops it.
+                self.book_search = Some(BookSearch {
+                    matches: Vec::new(),
+                    current: None,
+                    task: Some(handle.abort_on_drop()),
+                });
+
+                return task.map(cosmic::Action::App);
+            }
+            Message::BookSearchResults(matches) => {
+                if let Some(book_search) = self.book_search.as_mut() {
+                    book_search.matches.extend(matches);
+                }
+            }
+            Message::BookSearchFinished => {
+                if let Some(book_search) = self.book_search.as_mut() {
+                    book_search.task = None;
+                }
+            }
+            Message::BookSearchGoTo(index) => {
+                let Some(book_search) = self.book_search.as_mut() else {
+                    return Task::none();
+                };
+
+                let Some(location) = book_search.matches.get(index).map(|found| found.location)
+                else {
+                    return Task::none();
+                };
+
+                book_search.current = Some(index);
+
+                return match &self.reader {
+                    Some(Reader::Epub(_)) => self.load_chapter(location.chapter, location.offset),
+                    Some(Reader::Pdf(_)) => self.update(Message::PdfPage(location.chapter)),
+                    Some(Reader::Comic(_)) => self.update(Message::ComicPage(location.chapter)),
+                    None => Task::none(),
+                };
+            }
+            Message::BookSearchStep(forward) => {
+                let Some(book_search) = &self.book_search else {
+                    return Task::none();
+                };
+
+                let count = book_search.matches.len();
+
+                if count == 0 {
+                    return Task::none();
+                }
+
+                let index = match (book_search.current, forward) {
+                    (None, true) => 0,
+                    (None, false) => count - 1,
+                    (Some(current), true) => (current + 1) % count,
+                    (Some(current), false) => (current + count - 1) % count,
+                };
+
+                return self.update(Message::BookSearchGoTo(index));
+            }
+            Message::ReaderKey(key, modifiers) => {
+                // Tab always moves focus, and Escape is left to `on_escape`, which also
+                // cancels binding a shortcut.
+                if matches!(key, Key::Named(Named::Tab | Named::Escape)) {
+                    return Task::none();
+                }
+
+                if let Some(trigger) = shortcuts::key_trigger(&key, modifiers) {
+                    return self.trigger_shortcut(trigger);
+                }
+            }
+            Message::ReaderSwipe(x) => {
+                // Horizontal scrolling pans zoomed pages instead.
+                let zoom = match &self.reader {
+                    Some(Reader::Pdf(reader)) => Some(reader.zoom),
+                    Some(Reader::Comic(reader)) => Some(reader.zoom),
+                    _ => None,
+                };
+
+                if matches!(zoom, Some(Zoom::Percent(_))) {
+                    return Task::none();
+                }
+
+                if let Some(swipe) = self.swipe.scrolled(x, Instant::now()) {
+                    return self.trigger_shortcut(swipe.to_string());
+                }
+            }
+            Message::ReaderGesture(gesture) => {
+                return self.trigger_shortcut(gesture.to_string());
+            }
+            Message::ReaderAction(action) => {
+                let Some(reader) = self.reader.as_ref() else {
+                    return Task::none();
+                };
+
+                let location = reader.location();
+
+                return match (reader, action) {
+                    (_, ReaderAction::ToggleFullscreen) => self.update(Message::ToggleFullscreen),
+                    (_, ReaderAction::PreviousPage) => self.update(Message::ReaderTurnPage(false)),
+                    (_, ReaderAction::NextPage) => self.update(Message::ReaderTurnPage(true)),
+                    (Reader::Epub(reader), ReaderAction::ChapterStart) => {
+                        reader.scroll_to_edge(false)
+                    }
+                    (Reader::Epub(reader), ReaderAction::ChapterEnd) => reader.scroll_to_edge(true),
+                    (Reader::Epub(_), ReaderAction::PreviousChapter) => {
+                        match location.chapter.checked_sub(1) {
+                            Some(index) => self.update(Message::ReaderChapter(index)),
+                            None => Task::none(),
+                        }
+                    }
+                    (Reader::Epub(reader), ReaderAction::NextChapter) => {
+                        if reader.has_next_chapter() {
+                            self.update(Message::ReaderChapter(location.chapter + 1))
+                        } else {
+                            Task::none()
+                        }
+                    }
+                    (Reader::Pdf(_), ReaderAction::ChapterStart) => {
+                        self.update(Message::PdfPage(0))
+                    }
+                    (Reader::Pdf(reader), ReaderAction::ChapterEnd) => {
+                        self.update(Message::PdfPage(reader.page_count().saturating_sub(1)))
+                    }
+                    (Reader::Pdf(_), ReaderAction::PreviousChapter) => self.update(
+                        Message::PdfPage(location.chapter.saturating_sub(PDF_CHAPTER_PAGES)),
+                    ),
+                    (Reader::Pdf(reader), ReaderAction::NextChapter) => {
+                        let last = reader.page_count().saturating_sub(1);
+                        self.update(Message::PdfPage(
+                            (location.chapter + PDF_CHAPTER_PAGES).min(last),
+                        ))
+                    }
+                    (Reader::Comic(_), ReaderAction::ChapterStart) => {
+                        self.update(Message::ComicPage(0))
+                    }
+                    (Reader::Comic(reader), ReaderAction::ChapterEnd) => {
+                        self.update(Message::ComicPage(reader.page_count().saturating_sub(1)))
+                    }
+                    (Reader::Comic(_), ReaderAction::PreviousChapter) => self.update(
+                        Message::ComicPage(location.chapter.saturating_sub(PDF_CHAPTER_PAGES)),
+                    ),
+                    (Reader::Comic(reader), ReaderAction::NextChapter) => {
+                        let last = reader.page_count().saturating_sub(1);
+                        self.update(Message::ComicPage(
+                            (location.chapter + PDF_CHAPTER_PAGES).min(last),
+                        ))
+                    }
+                };
+            }
+            Message::CaptureShortcut(action) => {
+                self.capturing_shortcut = Some(action);
+            }
+            Message::RemoveShortcut(trigger) => {
+                self.config.shortcuts.unbind(&trigger);
+                self.save_config();
+            }
+            Message::ResetShortcuts => {
+                self.config.shortcuts = shortcuts::Shortcuts::default();
+                self.capturing_shortcut = None;
+                self.save_config();
+            }
+            Message::CloseReader => {
+                let finished = self.record_reading_position();
+                self.save_library();
+
+                let windowed = self.set_fullscreen(false);
+                let stop = self.stop_speaking();
+                let push = Task::batch([self.push_positions(), self.sync_pds()]);
+                self.reader = None;
+                self.book_search = None;
+
+                if let Some(nav_id) = self.reader_nav.take() {
+                    self.nav.remove(nav_id);
+                    self.activate_page(Page::Page3);
+                }
+
+                let close = match self.reader_window.take() {
+                    Some(id) => window::close(id),
+                    None => Task::none(),
+                };
+
+                // With the main window closed too, nothing is left open.
+                let quit = if self.window_hidden && !self.tray_shown() {
+                    self.quit()
+                } else {
+                    Task::none()
+                };
+
+                return Task::batch([
+                    windowed,
+                    stop,
+                    push,
+                    finished,
+                    close,
+                    self.update_title(),
+                    quit,
+                ]);
+            }
+            Message::ToggleFullscreen => {
+                return self.set_fullscreen(!self.fullscreen);
+            }
+            Message::ReaderActivity => {
+                self.last_activity = Instant::now();
+                self.controls_hidden = false;
+            }
+            Message::HideReaderControls => {
+                if self.fullscreen && self.last_activity.elapsed() >= CONTROLS_TIMEOUT {
+                    self.controls_hidden = true;
+                }
+            }
+            Message::ToggleReadAloud => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                if reader.read_aloud.is_some() {
+                    return reader.close_read_aloud();
+                }
+
+                let options = speech::Options {
+                    rate: self.config.speech_rate,
+                    voice: Some(self.config.speech_voice.clone()).filter(|v| !v.is_empty()),
+                };
+
+                reader.open_read_aloud(options, fl!("read-aloud-default-voice"));
+
+                return Task::perform(speech::voices(), |result| {
+                    cosmic::Action::App(Message::SpeechVoices(result))
+                });
+            }
+            Message::SpeechVoices(result) => match result {
+                Ok(voices) => {
+                    if let Some(read_aloud) = self.read_aloud_mut() {
+                        read_aloud.voices.extend(voices);
+                    }
+                }
+                Err(why) => {
+                    tracing::error!("failed to list voices: {why}");
+                    return self.push_toast(fl!("read-aloud-unavailable"));
+                }
+            },
+            Message::SpeechPlay => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    let playing = reader
+                        .read_aloud
+                        .as_ref()
+                        .is_some_and(|read_aloud| read_aloud.is_playing());
+
+                    return if playing {
+                        reader.stop_speaking()
+                    } else {
+                        reader.play()
+                    };
+                }
+            }
+            Message::ToggleRsvp => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    if reader.rsvp.take().is_none() {
+                        reader.open_rsvp();
+                    }
+                }
+            }
+            Message::RsvpPlay => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.toggle_rsvp();
+                }
+            }
+            Message::RsvpTick => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    return reader.rsvp_tick();
+                }
+            }
+            Message::RsvpRewind => {
+                if let Some(Reader::Epub(reader)) = self.reader.as_mut() {
+                    reader.rewind_rsvp();
+                }
+            }
+            Message::RsvpWpm(wpm) => {
+                self.config.rsvp_wpm = wpm;
+            }
+            Message::SpeechRate(rate) => {
+                if let Some(read_aloud) = self.read_aloud_mut() {
+                    read_aloud.options.rate = rate;
+                }
+
+                self.config.speech_rate = rate;
+            }
+            Message::SpeechApply => {
+                self.save_config();
+                return self.restart_speaking();
+            }
+            Message::SpeechVoice(index) => {
+                let Some(read_aloud) = self.read_aloud_mut() else {
+                    return Task::none();
+                };
+
+                // The first entry stands for the synthesizer's default voice.
+                let voice = read_aloud.voices.get(index).filter(|_| index > 0).cloned();
+                read_aloud.options.voice = voice.clone();
+
+                self.config.speech_voice = voice.unwrap_or_default();
+                self.save_config();
+                return self.restart_speaking();
+            }
+            Message::SpeechProgress(result) => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                match result {
+                    Ok(index) => {
+                        let task = reader.sentence_spoken(index);
+                        let finished = self.record_reading_position();
+                        return Task::batch([task, finished]);
+                    }
+                    Err(why) => {
+                        tracing::error!("failed to read aloud: {why}");
+                        let stop = reader.stop_speaking();
+
+                        return Task::batch([stop, self.push_toast(fl!("read-aloud-unavailable"))]);
+                    }
+                }
+            }
+            Message::SpeechFinished => {
+                let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+                    return Task::none();
+                };
+
+                let finished = reader
+                    .read_aloud
+                    .as_ref()
+                    .is_some_and(|read_aloud| read_aloud.is_finished());
+                let next = reader.chapter_index + 1;
+
+                // The stream has ended, so there is nothing left to silence.
+                let _ = reader.stop_speaking();
+
+                if finished && reader.has_next_chapter() {
+                    if let Some(read_aloud) = reader.read_aloud.as_mut() {
+                        read_aloud.resume = true;
+                    }
+
+                    return self.load_chapter(next, 0.0);
+                }
+            }
+            Message::SetTypography(typography) => {
+                let book = self
+                    .reader
+                    .as_ref()
+                    .and_then(|reader| self.library.get_mut(reader.book_id()))
+                    .filter(|book| book.typography.is_some());
+
+                if let Some(book) = book {
+                    book.typography = Some(typography);
+                    self.save_library();
+                } else {
+                    self.config.typography = typography;
+                    self.save_config();
+                }
+            }
+            Message::TypographyOverride(enabled) => {
+                let Some(id) = self.reader.as_ref().map(Reader::book_id) else {
+                    return Task::none();
+                };
+
+                if let Some(book) = self.library.get_mut(id) {
+                    book.typography = enabled.then(|| self.config.typography.clone());
+                    self.save_library();
+                }
+            }
+            Message::TogglePaletteMenu => {
+                self.palette_menu = !self.palette_menu;
+            }
+            Message::SetPalette(palette) => {
+                self.palette_menu = false;
+                self.config.palette = palette;
+                self.save_config();
+            }
+            Message::CustomForeground(text) => {
+                if let Some(color) = palette::parse_hex(&text) {
+                    self.config.custom_palette.foreground = color;
+                    self.config.palette = Palette::Custom;
+                    self.save_config();
+                }
+
+                self.custom_palette_input.0 = text;
+            }
+            Message::CustomBackground(text) => {
+                if let Some(color) = palette::parse_hex(&text) {
+                    self.config.custom_palette.background = color;
+                    self.config.palette = Palette::Custom;
+                    self.save_config();
+                }
+
+                self.custom_palette_input.1 = text;
+            }
+            Message::Exported(result) => {
+                let text = match result {
+                    Ok(count) => fl!("export-done", count = count),
+                    Err(why) => {
+                        tracing::error!("failed to export library: {why}");
+                        fl!("export-failed")
+                    }
+                };
+
+                return self.push_toast(text);
+            }
+        }
+        Task::none()
+    }
+
+    /// Called when a nav item is selected.
+    fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<cosmic::Action<Self::Message>> {
+        // Activate the page in the model.
+        self.nav.activate(id);
+
+        // Fetch the feed the first time it is shown.
+        if self.nav.active_data::<Page>() == Some(&Page::Feed) && !self.feed.loaded {
+            return Task::batch([self.page_shown(), self.update(Message::LoadFeed(true))]);
+        }
+
+        // Fetch the followed news feeds the first time the news page is shown.
+        if self.nav.active_data::<Page>() == Some(&Page::News)
+            && self.news.feeds.is_empty()
+            && self.news.loading.is_empty()
+        {
+            return Task::batch([self.page_shown(), self.update(Message::RefreshNews)]);
+        }
+
+        // Open the first site the first time the discovery page is shown.
+        if self.nav.active_data::<Page>() == Some(&Page::Discover)
+            && self.discover.feed.is_none()
+            && self.discover.task.is_none()
+        {
+            return Task::batch([
+                self.page_shown(),
+                self.update(Message::DiscoverSite(self.discover.site)),
+            ]);
+        }
+
+        self.page_shown()
+    }
+
+    /// Called when libby is launched again while running, to raise the window and open
+    /// any books it was launched with.
+    fn dbus_activation(
+        &mut self,
+        msg: cosmic::dbus_activation::Message,
+    ) -> Task<cosmic::Action<Self::Message>> {
+        let files = match msg.msg {
+            cosmic::dbus_activation::Details::Open { url } => url
+                .iter()
+                .filter_map(|url| url.to_file_path().ok())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Task::batch([
+            self.set_window_hidden(false),
+            self.update(Message::OpenFiles(files)),
+        ])
+    }
+
+    /// Called when a window is asked to close, to hide the main window to the tray or
+    /// quit, or to close the book in the reader's window.
+    fn on_close_requested(&self, id: window::Id) -> Option<Self::Message> {
+        if self.reader_window == Some(id) {
+            return Some(Message::CloseReader);
+        }
+
+        (self.core.main_window_id() == Some(id)).then_some(Message::CloseRequested(id))
+    }
+
+    /// The reader's own window.
+    fn view_window(&self, id: window::Id) -> Element<Self::Message> {
+        match self.reader_view() {
+            Some(reader) if self.reader_window == Some(id) => reader,
+            _ => widget::horizontal_space().into(),
+        }
+    }
+
+    /// Called when search is triggered.
+    fn on_search(&mut self) -> Task<cosmic::Action<Self::Message>> {
+        self.search_expanded = true;
+        Task::batch(vec![
+            focus_field(self.search_input_id.clone()),
+            Task::done(cosmic::Action::from(Message::SearchFocused)),
+        ])
+    }
+
+    /// Called when Escape is pressed, to back out of whatever was opened last: binding a
+    /// shortcut, then a dialog, the context drawer, search, and fullscreen reading.
+    fn on_escape(&mut self) -> Task<cosmic::Action<Self::Message>> {
+        if self.capturing_shortcut.take().is_some() {
+            return Task::none();
+        }
+
+        if self.dialog().is_some() {
+            return match self.dialog_cancel() {
+                Some(message) => self.update(message),
+                None => Task::none(),
+            };
+        }
+
+        if self.core.window.show_context {
+            self.core.window.show_context = false;
+            self.close_pairing();
+            return Task::none();
+        }
+
+        if self.search_expanded {
+            return self.update(Message::ClearSearch);
+        }
+
+        self.set_fullscreen(false)
+    }
+
+    fn dialog(&self) -> Option<Element<Message>> {
+        if let Some(state) = &self.export_dialog {
+            return Some(self.export_dialog(state));
+        }
+
+        if let Some(state) = &self.crash_dialog {
+            return Some(self.crash_dialog(state));
+        }
+
+        if self.restore_dialog.is_some() {
+            return Some(self.restore_dialog());
+        }
+
+        if let Some(request) = &self.pairing_request {
+            return Some(self.pairing_dialog(request));
+        }
+
+        if let Some(request) = &self.access_request {
+            return Some(self.access_dialog(request));
+        }
+
+        if let Some(offer) = self.offers.front() {
+            return Some(self.offer_dialog(offer));
+        }
+
+        if let Some(url) = &self.import_dialog {
+            return Some(self.import_dialog(url));
+        }
+
+        if let Some(state) = &self.share_dialog {
+            return Some(self.share_dialog(state));
+        }
+
+        if self.show_popup {
+            let active_page = self
+                .nav
+                .data::<Page>(self.nav.active())
+                .copied()
+                .unwrap_or(Page::Page1);
+
+            match active_page {
+                Page::Page1 => Some(
+                    dialog()
+                        .title(fl!("popup-title"))
+                        .body(fl!("popup-body"))
+                        .icon(icon::from_name("face-cool-symbolic"))
+                        .primary_action(
+                            button::standard(fl!("close")).on_press(Message::TogglePopup),
+                        )
+                        .into(),
+                ),
+                _ => None,
+            }
+        } else {
             None
         }
     }
-}
+}
+
+impl AppModel {
+    /// The about page for this app.
+    pub fn about(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let icon = widget::icon(icons::app()).size(128);
+
+        let title = widget::text::title2(fl!("app-title"));
+        let author = widget::button::link("nandi.weird.one").on_press(Message::OpenAuthorUrl);
+
+        let hash = env!("VERGEN_GIT_SHA");
+        let short_hash: String = hash.chars().take(7).collect();
+        let date = env!("VERGEN_GIT_COMMIT_DATE");
+
+        let link = widget::button::link(REPOSITORY)
+            .on_press(Message::OpenRepositoryUrl)
+            .padding(0);
+
+        let update = self.available_update.as_ref().map(|release| {
+            widget::button::link(fl!("update-available", version = release.version.as_str()))
+                .on_press(Message::LaunchUrl(release.url.clone()))
+                .padding(0)
+        });
+
+        widget::column()
+            .push(icon)
+            .push(title)
+            .push(author)
+            .push(link)
+            .push_maybe(update)
+            .push(
+                widget::button::link(fl!(
+                    "git-description",
+                    hash = short_hash.as_str(),
+                    date = date
+                ))
+                .on_press(Message::LaunchUrl(format!("{REPOSITORY}/commits/{hash}")))
+                .padding(0),
+            )
+            .align_x(Alignment::Center)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// The settings page for this app.
+    pub fn settings(&self) -> Element<Message> {
+        widget::column()
+            .push(widget::text::title2(fl!("settings")))
+            .push(widget::vertical_space().height(20))
+            .push(widget::text(fl!("username")))
+            .push(
+                widget::text_input(fl!("username-placeholder"), &self.config.username)
+                    .on_input(Message::UpdateUsername)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::checkbox(fl!("trash-move-files"), self.config.trash_files)
+                    .on_toggle(Message::SetTrashFiles),
+            )
+            .push(
+                widget::checkbox(fl!("dictionary-online"), self.config.dictionary_online)
+                    .on_toggle(Message::SetDictionaryOnline),
+            )
+            .push(widget::text(fl!("translate-server")))
+            .push(
+                widget::text_input("https://libretranslate.com", &self.config.translate_url)
+                    .on_input(Message::SetTranslateUrl)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::text_input(fl!("translate-api-key"), &self.config.translate_api_key)
+                    .on_input(Message::SetTranslateApiKey)
+                    .width(Length::Fill),
+            )
+            .push(widget::text(fl!("sync-folder")))
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input(
+                            fl!("sync-folder-placeholder"),
+                            &self.config.sync_folder,
+                        )
+                        .on_input(Message::SetSyncFolder)
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::standard(fl!("choose"))
+                            .on_press(Message::ChooseFile(FileTarget::SyncFolder)),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push(
+                [
+                    (sync::Resolution::Furthest, fl!("sync-furthest")),
+                    (sync::Resolution::Latest, fl!("sync-latest")),
+                ]
+                .into_iter()
+                .fold(widget::row().spacing(10), |row, (resolution, label)| {
+                    row.push(widget::radio(
+                        label,
+                        resolution,
+                        Some(self.config.sync_resolution),
+                        Message::SetSyncResolution,
+                    ))
+                }),
+            )
+            .push(widget::text(fl!("webdav-server")))
+            .push(
+                widget::text_input(
+                    "https://cloud.example.com/remote.php/dav/files/…",
+                    &self.config.webdav_url,
+                )
+                .on_input(Message::SetWebdavUrl)
+                .width(Length::Fill),
+            )
+            .push(
+                widget::row()
+                    .spacing(10)
+                    .push(
+                        widget::text_input(fl!("webdav-user"), &self.config.webdav_user)
+                            .on_input(Message::SetWebdavUser)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::secure_input(
+                            fl!("webdav-password"),
+                            self.webdav_password.as_deref().unwrap_or_default(),
+                            None,
+                            true,
+                        )
+                        .on_input(Message::SetWebdavPassword)
+                        .width(Length::Fill),
+                    ),
+            )
+            .push(
+                widget::checkbox(fl!("webdav-scheduled"), self.config.webdav_scheduled)
+                    .on_toggle(Message::SetWebdavScheduled),
+            )
+            .push(self.companion_settings())
+            .push(
+                widget::button::standard(fl!("sync-now")).on_press_maybe(
+                    (!self.config.sync_folder.trim().is_empty()
+                        || !self.config.webdav_url.trim().is_empty()
+                        || self.companion_remote().is_some())
+                    .then_some(Message::SyncPositions),
+                ),
+            )
+            .push(widget::text(fl!("proxy")))
+            .push(
+                widget::text_input(fl!("proxy-placeholder"), &self.config.proxy)
+                    .on_input(Message::SetProxy)
+                    .width(Length::Fill),
+            )
+            .push_maybe(
+                self.proxy_error
+                    .as_ref()
+                    .map(|why| widget::text::caption(fl!("proxy-invalid", reason = why.as_str()))),
+            )
+            .push(widget::text(fl!("certificates")))
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input(
+                            fl!("certificates-placeholder"),
+                            &self.config.certificates,
+                        )
+                        .on_input(Message::SetCertificates)
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::standard(fl!("choose"))
+                            .on_press(Message::ChooseFile(FileTarget::Certificates)),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push_maybe(self.certificates_error.as_ref().map(|why| {
+                widget::text::caption(fl!("certificates-invalid", reason = why.as_str()))
+            }))
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(fl!("connection-test")).on_press_maybe(
+                            (self.connection_test != Some(None)).then_some(Message::TestConnection),
+                        ),
+                    )
+                    .push_maybe(self.connection_test.as_ref().map(|test| {
+                        widget::text::caption(match test {
+                            None => fl!("connection-testing"),
+                            Some(Ok(())) => fl!("connection-ok"),
+                            Some(Err(why)) => fl!("connection-failed", reason = why.as_str()),
+                        })
+                    }))
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push(widget::text::heading(fl!("cache")))
+            .push(
+                widget::row()
+                    .push(widget::text(match self.cache_size {
+                        Some(size) => fl!("cache-size", size = format_size(size)),
+                        None => fl!("cache-size-unknown"),
+                    }))
+                    .push(widget::button::standard(fl!("cache-clear")).on_press_maybe(
+                        (self.cache_size != Some(0)).then_some(Message::ClearCache),
+                    ))
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push(widget::text(fl!(
+                "cache-limit",
+                default = cache::DEFAULT_CAPACITY
+            )))
+            .push(
+                widget::text_input("0", self.config.cache_limit.to_string())
+                    .on_input(Message::SetCacheLimit)
+                    .on_submit(|_| Message::ApplyCacheLimit)
+                    .width(Length::Fixed(120.0)),
+            )
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(fl!("sources"))
+                            .on_press(Message::ToggleContextPage(ContextPage::Sources)),
+                    )
+                    .push(
+                        widget::button::standard(fl!("webhooks"))
+                            .on_press(Message::ToggleContextPage(ContextPage::Webhooks)),
+                    )
+                    .spacing(10),
+            )
+            .push(widget::text(fl!("update-check")))
+            .push(
+                [
+                    (update::Frequency::Daily, fl!("update-daily")),
+                    (update::Frequency::Weekly, fl!("update-weekly")),
+                    (update::Frequency::Never, fl!("update-never")),
+                ]
+                .into_iter()
+                .fold(widget::row().spacing(10), |row, (frequency, label)| {
+                    row.push(widget::radio(
+                        label,
+                        frequency,
+                        Some(self.config.update_check),
+                        Message::SetUpdateCheck,
+                    ))
+                })
+                .push(
+                    widget::button::standard(fl!("update-check-now"))
+                        .on_press_maybe(self.online.then_some(Message::CheckForUpdates(true))),
+                )
+                .align_y(Alignment::Center),
+            )
+            .push(
+                widget::checkbox(fl!("crash-reports"), self.config.crash_reports)
+                    .on_toggle(Message::SetCrashReports),
+            )
+            .push_maybe(self.config.crash_reports.then(|| {
+                widget::text_input(fl!("crash-endpoint"), &self.config.crash_endpoint)
+                    .on_input(Message::SetCrashEndpoint)
+                    .width(Length::Fill)
+            }))
+            .push(widget::checkbox(fl!("tray"), self.config.tray).on_toggle(Message::SetTray))
+            .push_maybe(self.config.tray.then(|| {
+                widget::checkbox(fl!("tray-close"), self.config.close_to_tray)
+                    .on_toggle(Message::SetCloseToTray)
+            }))
+            .push(
+                widget::checkbox(fl!("notifications"), self.config.notifications)
+                    .on_toggle(Message::SetNotifications),
+            )
+            .push(
+                widget::checkbox(fl!("hide-recent-books"), self.config.hide_recent_books)
+                    .on_toggle(Message::SetHideRecentBooks),
+            )
+            .push(
+                widget::checkbox(fl!("command-socket"), self.config.command_socket)
+                    .on_toggle(Message::SetCommandSocket),
+            )
+            .push_maybe(
+                ipc::path()
+                    .filter(|_| self.config.command_socket)
+                    .map(|path| {
+                        widget::text::caption(fl!(
+                            "command-socket-path",
+                            path = path.display().to_string()
+                        ))
+                    }),
+            )
+            .push(widget::text::heading(fl!("sounds")))
+            .push(
+                widget::checkbox(fl!("sound-page-turn"), self.config.sound_page_turn)
+                    .on_toggle(Message::SetSoundPageTurn),
+            )
+            .push(
+                widget::checkbox(fl!("sound-imported"), self.config.sound_imported)
+                    .on_toggle(Message::SetSoundImported),
+            )
+            .push(
+                widget::checkbox(fl!("sound-achievement"), self.config.sound_achievement)
+                    .on_toggle(Message::SetSoundAchievement),
+            )
+            .push(widget::text(fl!("language")))
+            .push(widget::dropdown(
+                &self.language_names,
+                self.languages
+                    .iter()
+                    .position(|language| *language == self.config.language),
+                Message::SetLanguage,
+            ))
+            .push(widget::text(fl!("language-fallback")))
+            .push(
+                widget::text_input("pt, es", &self.language_fallback_input)
+                    .on_input(Message::SetLanguageFallback)
+                    .on_submit(|_| Message::ApplyLanguageFallback)
+                    .width(Length::Fill),
+            )
+            .push(widget::text::heading(fl!("app-theme")))
+            .push(
+                [
+                    (AppTheme::System, fl!("app-theme-system")),
+                    (AppTheme::Light, fl!("app-theme-light")),
+                    (AppTheme::Dark, fl!("app-theme-dark")),
+                ]
+                .into_iter()
+                .fold(widget::row().spacing(10), |row, (app_theme, label)| {
+                    row.push(widget::radio(
+                        label,
+                        app_theme,
+                        Some(self.config.app_theme),
+                        Message::SetAppTheme,
+                    ))
+                }),
+            )
+            .push(
+                widget::checkbox(fl!("ignore-power-saving"), self.config.ignore_power_saving)
+                    .on_toggle(Message::SetIgnorePowerSaving),
+            )
+            .push(
+                widget::checkbox(fl!("compact-header"), self.config.compact_header)
+                    .on_toggle(Message::SetCompactHeader),
+            )
+            .push(
+                widget::checkbox(fl!("global-shortcuts"), self.config.global_shortcuts)
+                    .on_toggle(Message::SetGlobalShortcuts),
+            )
+            .push_maybe(self.config.global_shortcuts.then(|| {
+                widget::column()
+                    .push(
+                        widget::text_input(
+                            fl!("global-summon-trigger"),
+                            &self.config.summon_trigger,
+                        )
+                        .on_input(Message::SetSummonTrigger)
+                        .on_submit(|_| Message::ApplyGlobalTriggers)
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        widget::text_input(
+                            fl!("global-search-trigger"),
+                            &self.config.search_trigger,
+                        )
+                        .on_input(Message::SetSearchTrigger)
+                        .on_submit(|_| Message::ApplyGlobalTriggers)
+                        .width(Length::Fill),
+                    )
+                    .push(widget::text::caption(fl!("global-shortcuts-hint")))
+                    .spacing(10)
+            }))
+            .push(widget::vertical_space().height(20))
+            .push_maybe(cfg!(feature = "atproto").then(|| widget::text::title4(fl!("accounts"))))
+            .push_maybe(cfg!(feature = "atproto").then(|| self.account_settings()))
+            .push(widget::vertical_space().height(20))
+            .push(
+                widget::button::standard(fl!("settings-save"))
+                    .on_press(Message::SaveSettings)
+                    .width(Length::Fill),
+            )
+            .spacing(10)
+            .padding(20)
+            .align_x(Alignment::Center)
+            .into()
+    }
+
+    /// The companion section of the settings page, to pair with a companion server or
+    /// let other devices pair with this one.
+    fn companion_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text(fl!("companion")))
+            .spacing(10)
+            .width(Length::Fill);
+
+        if self.companion_token.is_some() && !self.config.companion_url.is_empty() {
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::text(fl!(
+                            "companion-paired",
+                            server = self.config.companion_url.as_str()
+                        ))
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::destructive(fl!("companion-unpair"))
+                            .on_press(Message::UnpairCompanion),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        } else if self.host.is_none() {
+            let can_pair = !self.companion_pairing
+                && !self.config.companion_url.trim().is_empty()
+                && !self.companion_code.trim().is_empty();
+
+            col = col
+                .push(
+                    widget::text_input(fl!("companion-server"), &self.config.companion_url)
+                        .on_input(Message::SetCompanionUrl)
+                        .width(Length::Fill),
+                )
+                .push(
+                    widget::row()
+                        .push(
+                            widget::text_input(fl!("companion-code"), &self.companion_code)
+                                .on_input(Message::CompanionCode)
+                                .on_submit_maybe(can_pair.then_some(|_| Message::PairCompanion))
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            widget::button::standard(if self.companion_pairing {
+                                fl!("companion-pairing")
+                            } else {
+                                fl!("companion-pair")
+                            })
+                            .on_press_maybe(can_pair.then_some(Message::PairCompanion)),
+                        )
+                        .spacing(space_xxs)
+                        .align_y(Alignment::Center),
+                )
+                .push_maybe(self.companion_error.as_ref().map(|why| {
+                    widget::text::caption(fl!("companion-pair-failed", reason = why.as_str()))
+                }));
+        }
+
+        // A device paired with a server doesn't host one of its own.
+        if self.companion_token.is_some() && !self.config.companion_url.is_empty() {
+            return col.into();
+        }
+
+        col = col.push(
+            widget::checkbox(fl!("companion-host"), self.config.companion_host)
+                .on_toggle(Message::SetCompanionHost),
+        );
+
+        let Some(host) = &self.host else {
+            return col.into();
+        };
+
+        if let Some(why) = &self.host_error {
+            return col
+                .push(widget::text::caption(fl!(
+                    "companion-host-failed",
+                    reason = why.as_str()
+                )))
+                .into();
+        }
+
+        let address = host::address(companion::PORT).unwrap_or_default();
+
+        let mut pairing = widget::column()
+            .push(widget::text(fl!(
+                "companion-host-address",
+                address = address
+            )))
+            .spacing(space_xxs)
+            .width(Length::Fill);
+
+        pairing = if host.pairing_open() {
+            let code = match host.code() {
+                Some(code) => widget::text::heading(fl!("companion-host-code", code = code)),
+                None => widget::text::heading(fl!("companion-host-no-code")),
+            };
+
+            pairing
+                .push(code)
+                .push(widget::text::caption(fl!("companion-host-window")))
+                .push(
+                    widget::row()
+                        .push(
+                            widget::button::standard(fl!("companion-host-renew"))
+                                .on_press(Message::RenewPairingCode),
+                        )
+                        .push(
+                            widget::button::standard(fl!("companion-host-stop"))
+                                .on_press(Message::ClosePairing),
+                        )
+                        .spacing(space_xxs),
+                )
+        } else {
+            pairing.push(
+                widget::button::standard(fl!("companion-host-pair")).on_press(Message::OpenPairing),
+            )
+        };
+
+        let devices = host.devices();
+
+        if !devices.is_empty() {
+            pairing = pairing.push(widget::text::caption(fl!("companion-host-devices")));
+        }
+
+        for (index, device) in devices.into_iter().enumerate() {
+            pairing = pairing.push(
+                widget::row()
+                    .push(widget::text(device).width(Length::Fill))
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-remove"))
+                            .on_press(Message::ForgetCompanionDevice(index)),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.push(
+            widget::row()
+                .push(pairing)
+                .push_maybe(self.host_qr.as_ref().map(|data| qr_code(data).cell_size(4)))
+                .spacing(10),
+        )
+        .into()
+    }
+
+    /// The accounts section of the settings page, to sign in to or out of atproto.
+    fn account_settings(&self) -> Element<Message> {
+        if let Some(account) = &self.account {
+            return widget::column()
+                .push(self.account_badge(account))
+                .push(
+                    widget::checkbox(fl!("account-pds-sync"), self.config.pds_sync)
+                        .on_toggle(Message::SetPdsSync),
+                )
+                .push(widget::button::destructive(fl!("account-log-out")).on_press(Message::LogOut))
+                .spacing(10)
+                .width(Length::Fill)
+                .into();
+        }
+
+        let can_log_in = !self.logging_in
+            && !self.account_identifier.trim().is_empty()
+            && !self.account_password.is_empty();
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("account-hint")))
+            .push(
+                widget::text_input(fl!("account-handle"), &self.account_identifier)
+                    .on_input(Message::AccountIdentifier)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::secure_input(
+                    fl!("account-app-password"),
+                    &self.account_password,
+                    None,
+                    true,
+                )
+                .on_input(Message::AccountPassword)
+                .on_submit_maybe(can_log_in.then_some(|_| Message::LogIn))
+                .width(Length::Fill),
+            )
+            .push(
+                widget::button::suggested(if self.logging_in {
+                    fl!("account-logging-in")
+                } else {
+                    fl!("account-log-in")
+                })
+                .on_press_maybe(can_log_in.then_some(Message::LogIn)),
+            );
+
+        if let Some(why) = &self.account_error {
+            col = col.push(widget::text(fl!("account-failed", reason = why.as_str())));
+        }
+
+        col.spacing(10).width(Length::Fill).into()
+    }
+
+    /// The signed in account's avatar beside its name and handle.
+    fn account_badge<'a>(&'a self, account: &'a Account) -> Element<'a, Message> {
+        let handle = account
+            .profile
+            .as_ref()
+            .map_or(&account.session.handle, |profile| &profile.handle);
+
+        let mut names = widget::column();
+
+        if let Some(name) = account
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.display_name.as_ref())
+        {
+            names = names.push(widget::text::heading(name));
+        }
+
+        let avatar: Element<Message> = match &account.avatar {
+            Some(avatar) => widget::image(avatar.clone()).width(40).height(40).into(),
+            None => icon::from_name("avatar-default-symbolic")
+                .size(40)
+                .icon()
+                .into(),
+        };
+
+        widget::row()
+            .push(avatar)
+            .push(names.push(widget::text(format!("@{handle}"))))
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+    }
+
+    /// The feed page, listing posts of the chosen feed and fetching more near the end.
+    pub fn feed_page(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let header = widget::row()
+            .push(widget::text::title1(fl!("feed")).width(Length::Fill))
+            .push(
+                widget::text_input(fl!("feed-placeholder"), &self.feed.input)
+                    .on_input(Message::FeedInput)
+                    .on_submit(|_| Message::ChooseFeed)
+                    .width(Length::Fixed(320.0)),
+            )
+            .push(
+                a11y::icon_button("view-refresh-symbolic", fl!("a11y-refresh"))
+                    .on_press_maybe(self.feed.task.is_none().then_some(Message::LoadFeed(true))),
+            )
+            .push(
+                widget::button::standard(fl!("following"))
+                    .on_press(Message::ToggleContextPage(ContextPage::Following)),
+            )
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let chosen = self.chosen_feed();
+
+        let source = match &chosen {
+            atproto::Feed::Timeline => fl!("feed-timeline"),
+            atproto::Feed::Author(actor) if actor == AUTHOR_DID => fl!("feed-app-author"),
+            atproto::Feed::Author(actor) => fl!("feed-author", actor = actor.as_str()),
+            atproto::Feed::Generator(_) => fl!("feed-custom"),
+        };
+
+        let mut source = widget::row()
+            .push(widget::text::caption(source))
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        // Offer to follow the account whose posts are showing.
+        if let atproto::Feed::Author(actor) = chosen {
+            let followed = self
+                .config
+                .followed
+                .iter()
+                .any(|followed| followed.actor == actor);
+
+            if !followed {
+                source = source
+                    .push(widget::button::text(fl!("follow")).on_press(Message::Follow(actor)));
+            }
+        }
+
+        let mut posts = widget::column().spacing(10);
+
+        if !self.online {
+            posts = posts.push(
+                widget::container(widget::text(fl!("offline")))
+                    .class(theme::Container::Card)
+                    .padding(10)
+                    .width(Length::Fill),
+            );
+        }
+
+        for post in &self.feed.posts {
+            posts = posts.push(self.feed_post(post));
+        }
+
+        posts = posts.push(if self.feed.task.is_some() {
+            Element::from(widget::text(fl!("feed-loading")))
+        } else if let Some(why) = &self.feed.error {
+            widget::text(fl!("feed-failed", reason = why.as_str())).into()
+        } else if self.feed.cursor.is_some() {
+            widget::button::standard(fl!("feed-more"))
+                .on_press(Message::LoadFeed(false))
+                .into()
+        } else if self.feed.posts.is_empty() {
+            widget::text(fl!("feed-empty")).into()
+        } else {
+            widget::text::caption(fl!("feed-end")).into()
+        });
+
+        widget::column()
+            .push(header)
+            .push(source)
+            .push(
+                widget::scrollable(posts.padding([0, 12, 0, 0]))
+                    .on_scroll(Message::FeedScrolled)
+                    .height(Length::Fill),
+            )
+            .spacing(space_xxs)
+            .padding(20)
+            .into()
+    }
+
+    fn feed_post<'a>(&'a self, post: &'a atproto::Post) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut author = widget::row().spacing(space_xxs).align_y(Alignment::Center);
+
+        if let Some(name) = &post.display_name {
+            author = author.push(widget::text::heading(name));
+        }
+
+        let mut col = widget::column()
+            .push(author.push(widget::text::caption(format!("@{}", post.handle))))
+            .spacing(space_xxs);
+
+        if let Some(by) = &post.reposted_by {
+            col = col.push(widget::text::caption(fl!(
+                "feed-reposted-by",
+                handle = by.as_str()
+            )));
+        }
+
+        if !post.text.is_empty() {
+            col = col.push(widget::text(&post.text));
+        }
+
+        let images = post
+            .images
+            .iter()
+            .filter_map(|(url, _)| self.feed.images.get(url))
+            .fold(widget::row().spacing(space_xxs), |row, handle| {
+                row.push(widget::image(handle.clone()).height(Length::Fixed(160.0)))
+            });
+
+        let created = chrono::DateTime::parse_from_rfc3339(&post.created)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        col.push(images)
+            .push(
+                widget::row()
+                    .push(widget::text::caption(fl!(
+                        "feed-counts",
+                        replies = post.replies,
+                        reposts = post.reposts,
+                        likes = post.likes
+                    )))
+                    .push(widget::horizontal_space())
+                    .push(
+                        widget::button::link(created)
+                            .on_press(Message::LaunchUrl(post.web_url()))
+                            .padding(0),
+                    )
+                    .align_y(Alignment::Center),
+            )
+            .apply(widget::container)
+            .padding(12)
+            .width(Length::Fill)
+            .class(theme::Container::Card)
+            .into()
+    }
+
+    /// The drawer listing followed accounts, with whether each raises notifications.
+    /// The downloads drawer, with a progress bar for each download.
+    pub fn downloads(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input(fl!("download-placeholder"), &self.download_input)
+                            .on_input(Message::DownloadInput)
+                            .on_submit(Message::Download)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::standard(fl!("download")).on_press_maybe(
+                            (!self.download_input.trim().is_empty())
+                                .then(|| Message::Download(self.download_input.clone())),
+                        ),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            )
+            .spacing(space_xxs);
+
+        if self.downloads.downloads().is_empty() {
+            col = col.push(widget::text(fl!("downloads-empty")));
+        }
+
+        for item in self.downloads.downloads() {
+            let size = match item.total {
+                Some(total) => fl!(
+                    "download-size",
+                    received = format_size(item.received),
+                    total = format_size(total)
+                ),
+                None => format_size(item.received),
+            };
+
+            let status = match &item.state {
+                download::State::Queued => fl!("download-queued"),
+                download::State::Active => size,
+                download::State::Paused => fl!("download-paused", size = size),
+                download::State::Failed(why) => fl!("download-failed", reason = why.as_str()),
+                download::State::Done => fl!("download-done"),
+            };
+
+            let mut buttons = widget::row().spacing(space_xxs);
+
+            match item.state {
+                download::State::Queued | download::State::Active => {
+                    buttons = buttons.push(
+                        a11y::icon_button("media-playback-pause-symbolic", fl!("a11y-pause"))
+                            .on_press(Message::PauseDownload(item.id)),
+                    );
+                }
+                download::State::Paused => {
+                    buttons = buttons.push(
+                        a11y::icon_button("media-playback-start-symbolic", fl!("a11y-resume"))
+                            .on_press(Message::ResumeDownload(item.id)),
+                    );
+                }
+                download::State::Failed(_) => {
+                    buttons = buttons.push(
+                        a11y::icon_button("view-refresh-symbolic", fl!("a11y-retry"))
+                            .on_press(Message::ResumeDownload(item.id)),
+                    );
+                }
+                download::State::Done => (),
+            }
+
+            buttons = buttons.push(
+                a11y::icon_button("window-close-symbolic", fl!("a11y-cancel"))
+                    .on_press(Message::CancelDownload(item.id)),
+            );
+
+            col = col.push(
+                widget::column()
+                    .push(
+                        widget::row()
+                            .push(widget::text(item.name()).width(Length::Fill))
+                            .push(buttons)
+                            .spacing(space_xxs)
+                            .align_y(Alignment::Center),
+                    )
+                    .push(
+                        bidi::progress_bar(0.0..=1.0, item.fraction().unwrap_or(0.0))
+                            .height(Length::Fixed(4.0)),
+                    )
+                    .push(widget::text::caption(status))
+                    .spacing(space_xxs),
+            );
+        }
+
+        if self
+            .downloads
+            .downloads()
+            .iter()
+            .any(|item| item.state == download::State::Done)
+        {
+            col = col.push(
+                widget::button::standard(fl!("downloads-clear")).on_press(Message::ClearDownloads),
+            );
+        }
+
+        col.push(widget::text(fl!("download-limit")))
+            .push(
+                widget::text_input("0", self.config.download_limit.to_string())
+                    .on_input(Message::SetDownloadLimit)
+                    .width(Length::Fixed(120.0)),
+            )
+            .into()
+    }
+
+    pub fn following(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("following-hint")))
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input(fl!("follow-placeholder"), &self.follow_input)
+                            .on_input(Message::FollowInput)
+                            .on_submit(Message::Follow)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::standard(fl!("follow")).on_press_maybe(
+                            (!self.follow_input.trim().is_empty())
+                                .then(|| Message::Follow(self.follow_input.clone())),
+                        ),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            )
+            .spacing(space_xxs);
+
+        if self.config.followed.is_empty() {
+            col = col.push(widget::text(fl!("following-empty")));
+        }
+
+        for (index, followed) in self.config.followed.iter().enumerate() {
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::button::link(format!("@{}", followed.actor))
+                            .on_press(Message::ShowFeed(followed.actor.clone()))
+                            .width(Length::Fill),
+                    )
+                    .push(widget::text::caption(fl!("follow-notify")))
+                    .push(
+                        widget::toggler(followed.notify)
+                            .on_toggle(move |notify| Message::SetFollowNotify(index, notify)),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-remove"))
+                            .on_press(Message::Unfollow(index)),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.into()
+    }
+
+    /// The sources drawer, to add, edit, remove, enable and disable catalogs.
+    pub fn sources(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        if let Some(form) = &self.source_form {
+            return self.source_editor(form);
+        }
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("sources-hint")))
+            .push(widget::button::standard(fl!("source-add")).on_press(Message::NewSource))
+            .spacing(space_xxs);
+
+        if self.config.sources.is_empty() {
+            col = col.push(widget::text(fl!("sources-empty")));
+        }
+
+        for source in &self.config.sources {
+            let id = source.id;
+
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::column()
+                            .push(widget::text(&source.name))
+                            .push(widget::text::caption(&source.url))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::toggler(source.enabled)
+                            .on_toggle(move |enabled| Message::SetSourceEnabled(id, enabled)),
+                    )
+                    .push(
+                        a11y::icon_button("document-edit-symbolic", fl!("a11y-edit"))
+                            .on_press(Message::EditSource(id)),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-remove"))
+                            .on_press(Message::RemoveSource(id)),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.into()
+    }
+
+    /// The news page, listing the articles of followed feeds or reading one.
+    pub fn news_page(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let header = widget::row()
+            .push(widget::text::title1(fl!("news")).width(Length::Fill))
+            .push(
+                widget::text_input(fl!("news-placeholder"), &self.news.input)
+                    .on_input(Message::NewsInput)
+                    .on_submit(|_| Message::FollowNews)
+                    .width(Length::Fixed(320.0)),
+            )
+            .push(widget::button::standard(fl!("follow")).on_press_maybe(
+                (!self.news.input.trim().is_empty()).then_some(Message::FollowNews),
+            ))
+            .push(
+                a11y::icon_button("view-refresh-symbolic", fl!("a11y-refresh")).on_press_maybe(
+                    (self.news.loading.is_empty() && !self.config.news.is_empty())
+                        .then_some(Message::RefreshNews),
+                ),
+            )
+            .push(
+                widget::button::standard(fl!("wishlist"))
+                    .on_press(Message::ToggleContextPage(ContextPage::Wishlist)),
+            )
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let mut col = widget::column()
+            .push(header)
+            .push_maybe(
+                self.news
+                    .error
+                    .as_ref()
+                    .map(|why| widget::text::caption(fl!("news-failed", reason = why.as_str()))),
+            )
+            .spacing(10)
+            .padding(20);
+
+        let article = self.news.article.as_ref().and_then(|(url, id)| {
+            let feed = self.news.feeds.get(url)?.as_ref().ok()?;
+            let article = feed.articles.iter().find(|article| &article.id == id)?;
+            Some((url, feed, article))
+        });
+
+        if let Some((url, feed, article)) = article {
+            return col.push(self.news_article(url, feed, article)).into();
+        }
+
+        let mut filters: Vec<Element<Message>> = vec![if self.news.filter.is_none() {
+            widget::button::suggested(fl!("news-all"))
+        } else {
+            widget::button::standard(fl!("news-all"))
+        }
+        .on_press(Message::FilterNews(None))
+        .into()];
+
+        for followed in &self.config.news {
+            let title = if followed.title.is_empty() {
+                followed.url.as_str()
+            } else {
+                followed.title.as_str()
+            };
+
+            filters.push(
+                if self.news.filter.as_ref() == Some(&followed.url) {
+                    widget::button::suggested(title)
+                } else {
+                    widget::button::standard(title)
+                }
+                .on_press(Message::FilterNews(Some(followed.url.clone())))
+                .into(),
+            );
+        }
+
+        let mut actions = widget::row()
+            .push(widget::horizontal_space())
+            .push(widget::button::text(fl!("news-mark-read")).on_press(Message::MarkNewsRead))
+            .spacing(space_xxs);
+
+        if let Some(url) = &self.news.filter {
+            actions = actions.push(
+                widget::button::text(fl!("unfollow")).on_press(Message::UnfollowNews(url.clone())),
+            );
+        }
+
+        col = col
+            .push(
+                widget::flex_row(filters)
+                    .row_spacing(space_xxs)
+                    .column_spacing(space_xxs),
+            )
+            .push(actions);
+
+        if self.config.news.is_empty() {
+            return col.push(widget::text(fl!("news-empty"))).into();
+        }
+
+        let mut articles: Vec<(&String, &rss::Feed, &rss::Article)> = self
+            .news
+            .feeds
+            .iter()
+            .filter(|(url, _)| {
+                self.news
+                    .filter
+                    .as_ref()
+                    .is_none_or(|filter| filter == *url)
+            })
+            .filter_map(|(url, feed)| Some((url, feed.as_ref().ok()?)))
+            .flat_map(|(url, feed)| {
+                feed.articles
+                    .iter()
+                    .map(move |article| (url, feed, article))
+            })
+            .collect();
+
+        articles.sort_by_key(|(_, _, article)| std::cmp::Reverse(article.published));
+
+        let mut list = widget::column().spacing(space_xxs);
+
+        for (url, feed, article) in articles {
+            let read = self.news.read.is_read(url, &article.id);
+
+            let title = if read {
+                widget::text::body(&article.title)
+            } else {
+                widget::text::heading(&article.title)
+            };
+
+            let mut caption = feed.title.clone();
+
+            if let Some(published) = article.published.and_then(|at| u64::try_from(at).ok()) {
+                caption.push_str(" · ");
+                caption.push_str(&format_timestamp(published));
+            }
+
+            list = list.push(
+                widget::container(
+                    widget::row()
+                        .push(
+                            widget::column()
+                                .push(title)
+                                .push(widget::text::caption(caption))
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            widget::button::standard(fl!("news-read"))
+                                .on_press(Message::OpenArticle(url.clone(), article.id.clone())),
+                        )
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                )
+                .class(theme::Container::Card)
+                .padding(10)
+                .width(Length::Fill),
+            );
+        }
+
+        for (url, feed) in &self.news.feeds {
+            if let Err(why) = feed {
+                list = list.push(widget::text::caption(fl!(
+                    "news-feed-failed",
+                    url = url.as_str(),
+                    reason = why.as_str()
+                )));
+            }
+        }
+
+        if !self.news.loading.is_empty() {
+            list = list.push(widget::text(fl!("catalog-loading")));
+        }
+
+        col.push(widget::scrollable(list.padding([0, 12, 0, 0])).height(Length::Fill))
+            .into()
+    }
+
+    /// An article of a news feed, with the ISBNs it mentions to add to the wishlist.
+    fn news_article<'a>(
+        &'a self,
+        url: &'a str,
+        feed: &'a rss::Feed,
+        article: &'a rss::Article,
+    ) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut caption = feed.title.clone();
+
+        if let Some(published) = article.published.and_then(|at| u64::try_from(at).ok()) {
+            caption.push_str(" · ");
+            caption.push_str(&format_timestamp(published));
+        }
+
+        let mut body = widget::column()
+            .push(widget::text::caption(caption))
+            .push(
+                widget::row()
+                    .push_maybe((!article.link.is_empty()).then(|| {
+                        widget::button::standard(fl!("news-open"))
+                            .on_press(Message::LaunchUrl(article.link.clone()))
+                    }))
+                    .push(widget::button::text(fl!("news-mark-unread")).on_press(
+                        Message::SetArticleRead(url.to_string(), article.id.clone(), false),
+                    ))
+                    .spacing(space_xxs),
+            )
+            .spacing(10);
+
+        for isbn in &article.isbns {
+            let wished = self.wishlist.contains(isbn);
+
+            body = body.push(
+                widget::row()
+                    .push(widget::text(fl!("news-isbn", isbn = isbn.as_str())).width(Length::Fill))
+                    .push(
+                        widget::button::standard(if wished {
+                            fl!("wishlist-on")
+                        } else {
+                            fl!("wishlist-add")
+                        })
+                        .on_press_maybe(
+                            (!wished).then(|| {
+                                Message::AddToWishlist(isbn.clone(), article.title.clone())
+                            }),
+                        ),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        body = body.push(widget::text(&article.text));
+
+        widget::column()
+            .push(
+                widget::row()
+                    .push(
+                        a11y::icon_button(bidi::icon("go-previous-symbolic"), fl!("a11y-back"))
+                            .on_press(Message::CloseArticle),
+                    )
+                    .push(widget::text::title3(&article.title))
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push(widget::scrollable(body.padding([0, 12, 0, 0])).height(Length::Fill))
+            .spacing(10)
+            .into()
+    }
+
+    /// The wishlist context page.
+    pub fn wishlist(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("wishlist-hint")))
+            .spacing(space_xxs);
+
+        if self.wishlist.wishes.is_empty() {
+            col = col.push(widget::text(fl!("wishlist-empty")));
+        }
+
+        for wish in self.wishlist.wishes.iter().rev() {
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::column()
+                            .push(widget::text(fl!("news-isbn", isbn = wish.isbn.as_str())))
+                            .push(widget::text::caption(&wish.note))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        a11y::icon_button("web-browser-symbolic", fl!("a11y-open-in-browser"))
+                            .on_press(Message::LaunchUrl(wish.url())),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-remove"))
+                            .on_press(Message::RemoveFromWishlist(wish.isbn.clone())),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.into()
+    }
+
+    /// The webhooks context page, listing webhooks or editing one.
+    pub fn webhooks(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        if let Some(form) = &self.webhook_form {
+            return self.webhook_editor(form);
+        }
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("webhooks-hint")))
+            .push(widget::button::standard(fl!("webhook-add")).on_press(Message::NewWebhook))
+            .spacing(space_xxs);
+
+        if self.config.webhooks.is_empty() {
+            col = col.push(widget::text(fl!("webhooks-empty")));
+        }
+
+        for webhook in &self.config.webhooks {
+            let id = webhook.id;
+
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::column()
+                            .push(widget::text(&webhook.name))
+                            .push(widget::text::caption(&webhook.url))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::toggler(webhook.enabled)
+                            .on_toggle(move |enabled| Message::SetWebhookEnabled(id, enabled)),
+                    )
+                    .push(
+                        a11y::icon_button("document-edit-symbolic", fl!("a11y-edit"))
+                            .on_press(Message::EditWebhook(id)),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-remove"))
+                            .on_press(Message::RemoveWebhook(id)),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.into()
+    }
+
+    fn webhook_editor<'a>(&'a self, form: &'a WebhookForm) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let complete = !form.url.trim().is_empty() && !form.events.is_empty();
+
+        let test = form.test.as_ref().map(|test| {
+            widget::text::caption(match test {
+                Ok(()) => fl!("webhook-test-passed"),
+                Err(why) => fl!("webhook-test-failed", reason = why.as_str()),
+            })
+        });
+
+        let events = webhook::Event::ALL.into_iter().fold(
+            widget::column().spacing(space_xxs),
+            |col, event| {
+                let label = match event {
+                    webhook::Event::BookFinished => fl!("webhook-book-finished"),
+                    webhook::Event::BookImported => fl!("webhook-book-imported"),
+                };
+
+                col.push(
+                    widget::checkbox(label, form.events.contains(&event))
+                        .on_toggle(move |fired| Message::SetWebhookEvent(event, fired)),
+                )
+            },
+        );
+
+        widget::column()
+            .push(widget::text(fl!("webhook-name")))
+            .push(
+                widget::text_input(fl!("webhook-name"), &form.name)
+                    .on_input(Message::WebhookName)
+                    .width(Length::Fill),
+            )
+            .push(widget::text(fl!("webhook-url")))
+            .push(
+                widget::text_input("https://example.com/hook", &form.url)
+                    .on_input(Message::WebhookUrl)
+                    .width(Length::Fill),
+            )
+            .push(widget::text(fl!("webhook-events")))
+            .push(events)
+            .push(widget::text(fl!("webhook-template")))
+            .push(widget::text::caption(fl!("webhook-template-hint")))
+            .push(
+                widget::text_editor(&form.template)
+                    .on_action(Message::WebhookTemplate)
+                    .height(Length::Fixed(160.0)),
+            )
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(if form.testing {
+                            fl!("source-testing")
+                        } else {
+                            fl!("webhook-test")
+                        })
+                        .on_press_maybe(
+                            (complete && !form.testing).then_some(Message::TestWebhook),
+                        ),
+                    )
+                    .push(widget::horizontal_space())
+                    .push(widget::button::standard(fl!("cancel")).on_press(Message::CancelWebhook))
+                    .push(
+                        widget::button::suggested(fl!("source-save"))
+                            .on_press_maybe(complete.then_some(Message::SaveWebhook)),
+                    )
+                    .spacing(space_xxs),
+            )
+            .push_maybe(test)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    fn source_editor<'a>(&'a self, form: &'a SourceForm) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let complete = !form.url.trim().is_empty();
+
+        let password_placeholder = if form.id.is_some() && !form.user.is_empty() {
+            fl!("source-password-keep")
+        } else {
+            fl!("source-password")
+        };
+
+        let test = form.test.as_ref().map(|test| {
+            widget::text::caption(match test {
+                Ok(entries) => fl!("source-test-passed", entries = *entries),
+                Err(why) => fl!("source-test-failed", reason = why.as_str()),
+            })
+        });
+
+        widget::column()
+            .push(widget::text(fl!("source-name")))
+            .push(
+                widget::text_input(fl!("source-name"), &form.name)
+                    .on_input(Message::SourceName)
+                    .width(Length::Fill),
+            )
+            .push(widget::text(fl!("source-url")))
+            .push(
+                widget::text_input("https://example.com/opds", &form.url)
+                    .on_input(Message::SourceUrl)
+                    .width(Length::Fill),
+            )
+            // Without OPDS, plain listings are all a source can be.
+            .push_maybe(cfg!(feature = "opds").then(|| {
+                [
+                    (SourceKind::Opds, fl!("source-opds")),
+                    (SourceKind::Http, fl!("source-http")),
+                ]
+                .into_iter()
+                .fold(widget::row().spacing(10), |row, (kind, label)| {
+                    row.push(widget::radio(
+                        label,
+                        kind,
+                        Some(form.kind),
+                        Message::SetSourceKind,
+                    ))
+                })
+            }))
+            .push(widget::text(fl!("source-credentials")))
+            .push(
+                widget::row()
+                    .push(
+                        widget::text_input(fl!("source-user"), &form.user)
+                            .on_input(Message::SourceUser)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::secure_input(password_placeholder, &form.password, None, true)
+                            .on_input(Message::SourcePassword)
+                            .width(Length::Fill),
+                    )
+                    .spacing(space_xxs),
+            )
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(if form.testing {
+                            fl!("source-testing")
+                        } else {
+                            fl!("source-test")
+                        })
+                        .on_press_maybe((complete && !form.testing).then_some(Message::TestSource)),
+                    )
+                    .push(widget::horizontal_space())
+                    .push(widget::button::standard(fl!("cancel")).on_press(Message::CancelSource))
+                    .push(
+                        widget::button::suggested(fl!("source-save"))
+                            .on_press_maybe(complete.then_some(Message::SaveSource)),
+                    )
+                    .spacing(space_xxs),
+            )
+            .push_maybe(test)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// The catalogs page, listing enabled sources and browsing the one opened.
+    pub fn catalogs_page(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(source) = self
+            .catalog
+            .source
+            .and_then(|id| self.config.sources.iter().find(|source| source.id == id))
+        else {
+            return self.catalog_sources();
+        };
+
+        let title = self
+            .catalog
+            .feed
+            .as_ref()
+            .map(|feed| feed.title.as_str())
+            .filter(|title| !title.is_empty())
+            .unwrap_or(source.name.as_str());
+
+        let header = widget::row()
+            .push(
+                a11y::icon_button(bidi::icon("go-previous-symbolic"), fl!("a11y-back"))
+                    .on_press(Message::CatalogBack),
+            )
+            .push(widget::text::title3(title).width(Length::Fill))
+            .push(
+                a11y::icon_button("window-close-symbolic", fl!("a11y-close"))
+                    .on_press(Message::CloseCatalog),
+            )
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let mut entries = widget::column().spacing(10);
+
+        if !self.online {
+            entries = entries.push(
+                widget::container(widget::text(fl!("catalog-offline")))
+                    .class(theme::Container::Card)
+                    .padding(10)
+                    .width(Length::Fill),
+            );
+        }
+
+        if let Some(feed) = &self.catalog.feed {
+            for entry in &feed.entries {
+                entries = entries.push(self.catalog_entry(
+                    entry,
+                    None,
+                    Message::BrowseCatalog,
+                    |acquisition, title| {
+                        Some(Message::DownloadBook(
+                            acquisition.url.clone(),
+                            acquisition.file_name(title),
+                        ))
+                    },
+                ));
+            }
+        }
+
+        let feed = self.catalog.feed.as_ref();
+
+        entries = entries.push(if self.catalog.task.is_some() {
+            Element::from(widget::text(fl!("catalog-loading")))
+        } else if let Some(why) = &self.catalog.error {
+            widget::text(fl!("catalog-failed", reason = why.as_str())).into()
+        } else if feed.is_some_and(|feed| feed.next.is_some()) {
+            widget::button::standard(fl!("feed-more"))
+                .on_press(Message::CatalogMore)
+                .into()
+        } else if feed.is_some_and(|feed| feed.entries.is_empty()) {
+            widget::text(fl!("catalog-empty")).into()
+        } else {
+            widget::vertical_space().height(0).into()
+        });
+
+        widget::column()
+            .push(header)
+            .push(widget::scrollable(entries.padding([0, 12, 0, 0])).height(Length::Fill))
+            .spacing(space_xxs)
+            .padding(20)
+            .into()
+    }
+
+    /// The nearby page, listing other instances sharing their library on the local
+    /// network, and the books of the one opened.
+    pub fn nearby_page(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text::title1(fl!("nearby")))
+            .push(
+                widget::checkbox(fl!("nearby-share"), self.config.lan_sharing)
+                    .on_toggle(Message::SetLanSharing),
+            )
+            .spacing(10)
+            .padding(20);
+
+        if self.lan.is_none() {
+            return col.push(widget::text(fl!("nearby-off"))).into();
+        }
+
+        let peer = self
+            .peer
+            .id
+            .as_ref()
+            .and_then(|id| self.peers.iter().find(|peer| &peer.id == id));
+
+        let Some(peer) = peer else {
+            if self.peers.is_empty() {
+                col = col.push(widget::text(fl!("nearby-empty")));
+            }
+
+            for peer in &self.peers {
+                col = col.push(
+                    widget::container(
+                        widget::row()
+                            .push(widget::text::heading(&peer.device).width(Length::Fill))
+                            .push(
+                                widget::button::standard(fl!("catalog-open"))
+                                    .on_press(Message::OpenPeer(peer.id.clone())),
+                            )
+                            .spacing(10)
+                            .align_y(Alignment::Center),
+                    )
+                    .class(theme::Container::Card)
+                    .padding(10)
+                    .width(Length::Fill),
+                );
+            }
+
+            return col.into();
+        };
+
+        col = col.push(
+            widget::row()
+                .push(
+                    a11y::icon_button(bidi::icon("go-previous-symbolic"), fl!("a11y-back"))
+                        .on_press(Message::ClosePeer),
+                )
+                .push(widget::text::title3(&peer.device))
+                .spacing(10)
+                .align_y(Alignment::Center),
+        );
+
+        let mut books = widget::column().spacing(space_xxs);
+
+        for book in self.peer.books.iter().flatten() {
+            books =
+                books.push(
+                    widget::container(
+                        widget::row()
+                            .push(
+                                widget::column()
+                                    .push(widget::text::heading(&book.title))
+                                    .push(widget::text::caption(format!(
+                                        "{} · {} · {}",
+                                        book.author,
+                                        book.extension.to_uppercase(),
+                                        format_size(book.size)
+                                    )))
+                                    .width(Length::Fill),
+                            )
+                            .push(widget::button::standard(fl!("download")).on_press(
+                                Message::DownloadFromPeer(peer.url.clone(), book.clone()),
+                            ))
+                            .spacing(10)
+                            .align_y(Alignment::Center),
+                    )
+                    .class(theme::Container::Card)
+                    .padding(10)
+                    .width(Length::Fill),
+                );
+        }
+
+        books = books.push(if self.peer.task.is_some() {
+            // Without a token, the other instance waits for its user to let this one in.
+            let text = if self.peer_tokens.contains_key(&peer.url) {
+                fl!("catalog-loading")
+            } else {
+                fl!("nearby-asking", device = peer.device.as_str())
+            };
+
+            Element::from(widget::text(text))
+        } else if let Some(why) = &self.peer.error {
+            widget::text(fl!("catalog-failed", reason = why.as_str())).into()
+        } else if self.peer.books.as_ref().is_some_and(Vec::is_empty) {
+            widget::text(fl!("catalog-empty")).into()
+        } else {
+            widget::vertical_space().height(0).into()
+        });
+
+        col.push(widget::scrollable(books.padding([0, 12, 0, 0])).height(Length::Fill))
+            .into()
+    }
+
+    /// The discovery page, browsing and searching sites of free books to add to the
+    /// library.
+    pub fn discover_page(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let sites = discover::SITES.iter().enumerate().fold(
+            widget::row().spacing(space_xxs),
+            |row, (index, site)| {
+                row.push(widget::radio(
+                    site.name,
+                    index,
+                    Some(self.discover.site),
+                    Message::DiscoverSite,
+                ))
+            },
+        );
+
+        let can_search = !self.discover.query.trim().is_empty();
+
+        let search = widget::row()
+            .push(
+                a11y::icon_button(bidi::icon("go-previous-symbolic"), fl!("a11y-back"))
+                    .on_press_maybe(
+                        (!self.discover.history.is_empty()).then_some(Message::DiscoverBack),
+                    ),
+            )
+            .push(
+                widget::text_input(fl!("discover-search"), &self.discover.query)
+                    .on_input(Message::DiscoverQuery)
+                    .on_submit_maybe(can_search.then_some(|_| Message::DiscoverSearch))
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::button::standard(fl!("search-book"))
+                    .on_press_maybe(can_search.then_some(Message::DiscoverSearch)),
+            )
+            .spacing(space_xxs)
+            .align_y(Alignment::Center);
+
+        let mut entries = widget::column().spacing(10);
+
+        if !self.online {
+            entries = entries.push(
+                widget::container(widget::text(fl!("discover-offline")))
+                    .class(theme::Container::Card)
+                    .padding(10)
+                    .width(Length::Fill),
+            );
+        }
+
+        let feed = self.discover.feed.as_ref();
+
+        if let Some(feed) = feed.filter(|feed| !feed.title.is_empty()) {
+            entries = entries.push(widget::text::title3(&feed.title));
+        }
+
+        for entry in feed.into_iter().flat_map(|feed| &feed.entries) {
+            let cover = entry
+                .cover
+                .as_ref()
+                .and_then(|url| self.discover.covers.get(url));
+
+            entries = entries.push(self.catalog_entry(
+                entry,
+                cover,
+                Message::DiscoverBrowse,
+                |acquisition, title| {
+                    discover::importable(acquisition.extension()).then(|| {
+                        Message::ImportBook(acquisition.url.clone(), acquisition.file_name(title))
+                    })
+                },
+            ));
+        }
+
+        entries = entries.push(if self.discover.task.is_some() {
+            Element::from(widget::text(fl!("catalog-loading")))
+        } else if let Some(why) = &self.discover.error {
+            widget::text(fl!("catalog-failed", reason = why.as_str())).into()
+        } else if feed.is_some_and(|feed| feed.next.is_some()) {
+            widget::button::standard(fl!("feed-more"))
+                .on_press(Message::DiscoverMore)
+                .into()
+        } else if feed.is_some_and(|feed| feed.entries.is_empty()) {
+            widget::text(fl!("catalog-empty")).into()
+        } else {
+            widget::vertical_space().height(0).into()
+        });
+
+        widget::column()
+            .push(widget::text::title1(fl!("discover")))
+            .push(sites)
+            .push(search)
+            .push(widget::scrollable(entries.padding([0, 12, 0, 0])).height(Length::Fill))
+            .spacing(10)
+            .padding(20)
+            .into()
+    }
+
+    /// The enabled sources, to open one of them.
+    fn catalog_sources(&self) -> Element<Message> {
+        let mut col = widget::column()
+            .push(
+                widget::row()
+                    .push(widget::text::title1(fl!("catalogs")).width(Length::Fill))
+                    .push(
+                        widget::button::standard(fl!("sources"))
+                            .on_press(Message::ToggleContextPage(ContextPage::Sources)),
+                    )
+                    .align_y(Alignment::Center),
+            )
+            .spacing(10)
+            .padding(20);
+
+        let enabled: Vec<&Source> = self
+            .config
+            .sources
+            .iter()
+            .filter(|source| source.enabled)
+            .collect();
+
+        if enabled.is_empty() {
+            col = col.push(widget::text(fl!("catalogs-empty")));
+        }
+
+        for source in enabled {
+            col = col.push(
+                widget::container(
+                    widget::row()
+                        .push(
+                            widget::column()
+                                .push(widget::text::heading(&source.name))
+                                .push(widget::text::caption(&source.url))
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            widget::button::standard(fl!("catalog-open"))
+                                .on_press(Message::OpenCatalog(source.id)),
+                        )
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                )
+                .class(theme::Container::Card)
+                .padding(10)
+                .width(Length::Fill),
+            );
+        }
+
+        col.into()
+    }
+
+    /// An entry of a catalog, with buttons to browse into it and to download the book
+    /// in each format `download` gives a message for.
+    fn catalog_entry<'a>(
+        &'a self,
+        entry: &'a opds::Entry,
+        cover: Option<&'a widget::image::Handle>,
+        browse: fn(String) -> Message,
+        download: fn(&opds::Acquisition, &str) -> Option<Message>,
+    ) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text::heading(&entry.title))
+            .spacing(space_xxs);
+
+        if !entry.author.is_empty() {
+            col = col.push(widget::text::caption(&entry.author));
+        }
+
+        if !entry.summary.is_empty() {
+            col = col.push(widget::text(&entry.summary));
+        }
+
+        let mut buttons = widget::row().spacing(space_xxs);
+
+        if let Some(url) = &entry.navigation {
+            buttons = buttons
+                .push(widget::button::standard(fl!("catalog-open")).on_press(browse(url.clone())));
+        }
+
+        for acquisition in &entry.acquisitions {
+            let Some(message) = download(acquisition, &entry.title) else {
+                continue;
+            };
+
+            let format = acquisition
+                .extension()
+                .map(str::to_uppercase)
+                .unwrap_or_else(|| acquisition.mime.clone());
+
+            buttons = buttons.push(
+                widget::button::standard(fl!("catalog-download", format = format))
+                    .on_press(message),
+            );
+        }
+
+        let cover: Element<Message> = match cover {
+            Some(cover) => widget::image(cover.clone()).width(64).into(),
+            None => widget::icon(icons::cover()).size(64).into(),
+        };
+
+        let content = widget::row()
+            .push(cover)
+            .push(col.push(buttons).width(Length::Fill))
+            .spacing(10);
+
+        widget::container(content)
+            .class(theme::Container::Card)
+            .padding(10)
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// The library page, listing the catalog with multi-select and bulk actions.
+    pub fn library_page(&self) -> Element<Message> {
+        let mut col = widget::column().push(widget::text::title1(fl!("page-id", num = 3)));
+
+        if let Some(account) = &self.account {
+            col = col.push(self.account_badge(account));
+        }
+
+        if let Some(job) = &self.bulk_job {
+            col = col.push(
+                widget::row::with_children(bidi::ordered(vec![
+                    bidi::progress_bar(0.0..=job.total as f32, job.done as f32).into(),
+                    widget::text(fl!("bulk-progress", done = job.done, total = job.total)).into(),
+                    widget::button::standard(fl!("cancel"))
+                        .on_press(Message::CancelBulk)
+                        .into(),
+                ]))
+                .spacing(10)
+                .align_y(Alignment::Center),
+            );
+        } else if !self.selected.is_empty() {
+            col = col.push(self.bulk_toolbar());
+        }
+
+        col = col.push(
+            [
+                (library::Sort::Added, fl!("sort-added")),
+                (library::Sort::Title, fl!("sort-title")),
+                (library::Sort::Author, fl!("sort-author")),
+                (library::Sort::Progress, fl!("sort-progress")),
+            ]
+            .into_iter()
+            .fold(
+                widget::row()
+                    .push(widget::text(fl!("sort-by")))
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                |row, (sort, label)| {
+                    row.push(widget::radio(
+                        label,
+                        sort,
+                        Some(self.library_sort),
+                        Message::SetLibrarySort,
+                    ))
+                },
+            ),
+        );
+
+        let visible = self.visible_books();
+
+        if visible.is_empty() && !self.search_query.is_empty() {
+            col = col.push(widget::vertical_space().height(20));
+            col = col.push(widget::text::title3(fl!("search-no-results")));
+            col = col.push(widget::text(fl!(
+                "search-no-matches",
+                query = self.search_query.as_str()
+            )));
+            col = col.push(widget::text(fl!("search-try-again")));
+        } else if visible.is_empty() && self.library_loaded {
+            col = col.push(widget::vertical_space().height(20));
+            col = col.push(widget::text::title3(fl!("library-empty")));
+            col = col.push(widget::text(fl!("library-empty-hint")));
+            col = col.push(
+                widget::button::suggested(fl!("import-files"))
+                    .on_press(Message::ChooseFile(FileTarget::ImportBooks)),
+            );
+        } else {
+            col = col.push(self.book_list(&visible));
+        }
+
+        col.spacing(10)
+            .apply(widget::container)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+            .into()
+    }
+
+    /// The books on the library page, building only the rows in view, so that large
+    /// libraries scroll smoothly.
+    fn book_list(&self, books: &[&Book]) -> Element<Message> {
+        let shown_first =
+            ((self.library_offset / LIBRARY_ROW_HEIGHT).floor() as usize).min(books.len());
+        let shown_end = (((self.library_offset + self.library_height) / LIBRARY_ROW_HEIGHT).ceil()
+            as usize)
+            .clamp(shown_first, books.len());
+
+        let first = shown_first.saturating_sub(LIBRARY_OVERSCAN);
+        let end = (shown_end + LIBRARY_OVERSCAN).min(books.len());
+
+        let mut list = widget::column()
+            .push(widget::vertical_space().height(first as f32 * LIBRARY_ROW_HEIGHT));
+
+        for (index, book) in books.iter().enumerate().take(end).skip(first) {
+            if !(shown_first..shown_end).contains(&index) {
+                list = list.push(book_placeholder());
+                continue;
+            }
+
+            // Sorted by title or author, the first book of each letter is marked with it.
+            let Some(key) = grouped_by(self.library_sort, book) else {
+                list = list.push(self.book_row(book));
+                continue;
+            };
+
+            let starts_group = index == 0
+                || grouped_by(self.library_sort, books[index - 1])
+                    .is_none_or(|previous| !self.collation.same_initial(previous, key));
+
+            let initial = if starts_group {
+                collation::initial(key)
+            } else {
+                String::new()
+            };
+
+            list = list.push(
+                widget::row::with_children(bidi::ordered(vec![
+                    widget::text::title4(initial)
+                        .width(Length::Fixed(LIBRARY_LETTER_WIDTH))
+                        .into(),
+                    self.book_row(book),
+                ]))
+                .align_y(Alignment::Center),
+            );
+        }
+
+        list = list
+            .push(widget::vertical_space().height((books.len() - end) as f32 * LIBRARY_ROW_HEIGHT));
+
+        widget::scrollable(list.padding([0, 12, 0, 0]))
+            .id(self.library_scroll_id.clone())
+            .on_scroll(Message::LibraryScrolled)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// A book's row on the library page.
+    fn book_row(&self, book: &Book) -> Element<Message> {
+        let id = book.id;
+
+        // Read out with the title, as the card shows these in ways a screen reader
+        // can't tell apart.
+        let progress = (book.progress * 100.0).round() as u32;
+        let description = if book.author.is_empty() {
+            fl!("a11y-book", progress = progress)
+        } else {
+            fl!(
+                "a11y-book-by",
+                author = book.author.clone(),
+                progress = progress
+            )
+        };
+
+        // Rows are of a height, so only the start of the description fits.
+        let full = book.description.trim();
+        let first_line = full.lines().next().unwrap_or_default();
+        let summary: String = if first_line.chars().count() > LIBRARY_DESCRIPTION_LENGTH
+            || first_line.len() < full.len()
+        {
+            first_line
+                .chars()
+                .take(LIBRARY_DESCRIPTION_LENGTH - 1)
+                .chain(['…'])
+                .collect()
+        } else {
+            first_line.to_string()
+        };
+
+        let row = widget::row()
+            .push(
+                widget::checkbox("", self.selected.contains(&id))
+                    .on_toggle(move |selected| Message::ToggleSelected(id, selected)),
+            )
+            .push(
+                widget::column()
+                    .push(
+                        a11y::described_link(book.title.clone(), description)
+                            .on_press(Message::ToggleContextPage(ContextPage::BookDetails(id)))
+                            .padding(0),
+                    )
+                    .push(widget::text(summary)),
+            )
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .apply(widget::container)
+            .width(Length::Fill)
+            .height(Length::Fixed(LIBRARY_ROW_HEIGHT))
+            .align_y(Vertical::Center)
+            .clip(true);
+
+        // Books with a file can be dragged out as that file.
+        match book.path.as_deref().and_then(BookFile::new) {
+            Some(file) => widget::dnd_source(row)
+                .drag_content(move || file.clone())
+                .into(),
+            None => row.into(),
+        }
+    }
+
+    /// Scrolls the library's list back to its first book, such as when it's searched.
+    fn scroll_library_to_top(&mut self) -> Task<cosmic::Action<Message>> {
+        self.library_offset = 0.0;
+        scrollable::snap_to(
+            self.library_scroll_id.clone(),
+            scrollable::RelativeOffset::START,
+        )
+    }
+
+    /// Actions offered while books are selected on the library page.
+    fn bulk_toolbar(&self) -> Element<Message> {
+        let value = self.bulk_input.trim();
+        let with_value = |action: fn(String) -> BulkAction| {
+            (!value.is_empty()).then(|| Message::Bulk(action(value.to_string())))
+        };
+
+        widget::column()
+            .push(
+                widget::row()
+                    .push(widget::text(fl!(
+                        "bulk-selected",
+                        count = self.selected.len()
+                    )))
+                    .push(widget::button::text(fl!("select-all")).on_press(Message::SelectAll))
+                    .push(
+                        widget::button::text(fl!("clear-selection"))
+                            .on_press(Message::ClearSelection),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            )
+            .push(
+                widget::text_input(fl!("bulk-value-placeholder"), &self.bulk_input)
+                    .on_input(Message::BulkInput)
+                    .width(Length::Fixed(320.0)),
+            )
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(fl!("bulk-add-tag"))
+                            .on_press_maybe(with_value(BulkAction::AddTag)),
+                    )
+                    .push(
+                        widget::button::standard(fl!("bulk-set-series"))
+                            .on_press_maybe(with_value(BulkAction::SetSeries)),
+                    )
+                    .push(
+                        widget::button::standard(fl!("bulk-move-to-collection"))
+                            .on_press_maybe(with_value(BulkAction::MoveToCollection)),
+                    )
+                    .push(
+                        widget::button::standard(fl!("bulk-convert-epub"))
+                            .on_press(Message::Bulk(BulkAction::Convert("epub".to_string()))),
+                    )
+                    .push(
+                        widget::button::destructive(fl!("bulk-delete")).on_press(Message::Bulk(
+                            BulkAction::Delete {
+                                move_file: self.config.trash_files,
+                            },
+                        )),
+                    )
+                    .spacing(10),
+            )
+            .spacing(10)
+            .align_x(Alignment::Center)
+            .into()
+    }
+
+    /// Books on the library page matching the current search query.
+    fn visible_books(&self) -> Vec<&Book> {
+        self.library.query(&library::Query {
+            text: Some(self.search_query.clone()),
+            sort: self.library_sort,
+            ..library::Query::default()
+        })
+    }
+
+    /// The details drawer of a book, including its notes.
+    pub fn book_details(&self, id: BookId) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(book) = self.library.get(id) else {
+            return widget::text(fl!("book-missing")).into();
+        };
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if !book.author.is_empty() {
+            col = col.push(widget::text::title4(&book.author));
+        }
+
+        col = col.push(widget::text(&book.description));
+
+        if matches!(
+            book.extension().as_deref(),
+            Some("epub" | "pdf" | "cbz" | "cbr")
+        ) {
+            col = col.push(
+                widget::row()
+                    .push(widget::button::suggested(fl!("read")).on_press(Message::OpenReader(id)))
+                    .push(
+                        widget::button::standard(fl!("read-in-window"))
+                            .on_press(Message::OpenReaderWindow(id)),
+                    )
+                    .spacing(space_xxs),
+            );
+        }
+
+        if book.is_finished() && cfg!(feature = "atproto") {
+            col = col.push(
+                widget::button::standard(fl!("share"))
+                    .on_press_maybe(self.account.is_some().then_some(Message::ShareBook(id))),
+            );
+        }
+
+        col = col.push(
+            citation::Style::ALL.into_iter().fold(
+                widget::row()
+                    .push(widget::text(fl!("copy-citation")))
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+                |row, style| {
+                    row.push(
+                        widget::button::text(style.name())
+                            .on_press(Message::CopyCitation(id, style)),
+                    )
+                },
+            ),
+        );
+
+        if self.lan.is_some() && book.path.is_some() {
+            for peer in &self.peers {
+                col = col.push(
+                    widget::button::standard(fl!("nearby-send", device = peer.device.as_str()))
+                        .on_press(Message::SendToPeer(peer.id.clone(), id)),
+                );
+            }
+        }
+
+        if !book.tags.is_empty() {
+            col = col.push(widget::text::caption(book.tags.join(", ")));
+        }
+
+        if let Some(series) = &book.series {
+            col = col.push(widget::text::caption(fl!(
+                "book-series",
+                series = series.as_str()
+            )));
+        }
+
+        if let Some(collection) = &book.collection {
+            col = col.push(widget::text::caption(fl!(
+                "book-collection",
+                collection = collection.as_str()
+            )));
+        }
+
+        col = col
+            .push(widget::vertical_space().height(20))
+            .push(widget::text::title4(fl!("notes")));
+
+        for (index, note) in book.notes.iter().enumerate() {
+            col = col.push(
+                widget::column()
+                    .push(widget::text::caption(format_timestamp(note.created)))
+                    .push(
+                        widget::row()
+                            .push(
+                                widget::text_input("", &note.text)
+                                    .on_input(move |text| Message::EditNote(id, index, text))
+                                    .on_submit(|_| Message::WriteLibrary(true))
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                a11y::icon_button("edit-delete-symbolic", fl!("a11y-delete"))
+                                    .on_press(Message::DeleteNote(id, index)),
+                            )
+                            .spacing(space_xxs)
+                            .align_y(Alignment::Center),
+                    ),
+            );
+        }
+
+        let can_add = !self.note_input.trim().is_empty();
+
+        col.push(
+            widget::text_input(fl!("note-placeholder"), &self.note_input)
+                .on_input(Message::NoteInput)
+                .on_submit_maybe(can_add.then_some(move |_| Message::AddNote(id)))
+                .width(Length::Fill),
+        )
+        .push(
+            widget::button::standard(fl!("add-note"))
+                .on_press_maybe(can_add.then_some(Message::AddNote(id))),
+        )
+        .into()
+    }
+
+    /// The bookmarks of the open book, with a field for adding one at the current position.
+    pub fn bookmarks(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(reader) = &self.reader else {
+            return widget::text(fl!("bookmarks-no-book")).into();
+        };
+
+        let bookmarks = self
+            .library
+            .get(reader.book_id())
+            .map_or(&[][..], |book| &book.bookmarks);
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if bookmarks.is_empty() {
+            col = col.push(widget::text(fl!("bookmarks-empty")));
+        }
+
+        for (index, bookmark) in bookmarks.iter().enumerate() {
+            let position = match reader {
+                Reader::Epub(_) => fl!(
+                    "bookmark-chapter",
+                    chapter = bookmark.location.chapter + 1,
+                    percent = (bookmark.location.offset * 100.0).round() as u32
+                ),
+                Reader::Pdf(_) | Reader::Comic(_) => {
+                    fl!("bookmark-page", page = bookmark.location.chapter + 1)
+                }
+            };
+
+            let (title, caption) = if bookmark.label.is_empty() {
+                (position, format_timestamp(bookmark.created))
+            } else {
+                (
+                    bookmark.label.clone(),
+                    format!("{position} · {}", format_timestamp(bookmark.created)),
+                )
+            };
+
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::column()
+                            .push(
+                                widget::button::link(title).on_press(Message::GoToBookmark(index)),
+                            )
+                            .push(widget::text::caption(caption))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-delete"))
+                            .on_press(Message::DeleteBookmark(index)),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        col.push(widget::vertical_space().height(20))
+            .push(
+                widget::text_input(fl!("bookmark-placeholder"), &self.bookmark_input)
+                    .on_input(Message::BookmarkInput)
+                    .on_submit(|_| Message::AddBookmark)
+                    .width(Length::Fill),
+            )
+            .push(widget::button::standard(fl!("add-bookmark")).on_press(Message::AddBookmark))
+            .into()
+    }
+
+    /// Search within the open book, listing each match with the text around it.
+    pub fn book_search(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(reader) = &self.reader else {
+            return widget::text(fl!("search-book-no-book")).into();
+        };
+
+        let mut col = widget::column().spacing(space_xxs).push(
+            widget::text_input(fl!("search-book-placeholder"), &self.book_search_input)
+                .id(self.book_search_input_id.clone())
+                .on_input(Message::BookSearchInput)
+                .on_submit(|_| Message::BookSearch)
+                .width(Length::Fill),
+        );
+
+        let Some(book_search) = &self.book_search else {
+            return col.into();
+        };
+
+        let count = book_search.matches.len();
+        let status = match book_search.current {
+            _ if book_search.task.is_some() => fl!("search-book-searching", count = count),
+            Some(current) => fl!("search-book-position", current = current + 1, total = count),
+            None => fl!("search-book-count", count = count),
+        };
+
+        col = col.push(
+            widget::row()
+                .push(widget::text::caption(status).width(Length::Fill))
+                .push(
+                    a11y::icon_button("go-up-symbolic", fl!("a11y-previous-match"))
+                        .on_press_maybe((count > 0).then_some(Message::BookSearchStep(false))),
+                )
+                .push(
+                    a11y::icon_button("go-down-symbolic", fl!("a11y-next-match"))
+                        .on_press_maybe((count > 0).then_some(Message::BookSearchStep(true))),
+                )
+                .spacing(space_xxs)
+                .align_y(Alignment::Center),
+        );
+
+        for (index, found) in book_search.matches.iter().enumerate() {
+            let position = match reader {
+                Reader::Epub(_) => {
+                    fl!("reader-chapter-short", chapter = found.location.chapter + 1)
+                }
+                Reader::Pdf(_) | Reader::Comic(_) => {
+                    fl!("bookmark-page", page = found.location.chapter + 1)
+                }
+            };
+
+            col = col.push(
+                widget::column()
+                    .push(
+                        widget::button::link(found.snippet.clone())
+                            .selected(book_search.current == Some(index))
+                            .on_press(Message::BookSearchGoTo(index)),
+                    )
+                    .push(widget::text::caption(position)),
+            );
+        }
+
+        col.into()
+    }
+
+    /// Highlights of the open book with their notes, and buttons to export them.
+    pub fn annotations(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let Some(reader) = &self.reader else {
+            return widget::text(fl!("annotations-no-book")).into();
+        };
+
+        let highlights = self
+            .library
+            .get(reader.book_id())
+            .map_or(&[][..], |book| &book.highlights);
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if highlights.is_empty() {
+            col = col.push(widget::text(fl!("annotations-empty")));
+        }
+
+        for (index, highlight) in highlights.iter().enumerate() {
+            let excerpt: String = highlight.text.chars().take(140).collect();
+            let excerpt = if excerpt.len() < highlight.text.len() {
+                format!("{}…", excerpt.trim_end())
+            } else {
+                excerpt
+            };
+
+            col = col.push(
+                widget::row()
+                    .push(reader::swatch(highlight.color))
+                    .push(
+                        widget::column()
+                            .push(
+                                widget::button::link(excerpt)
+                                    .on_press(Message::GoToHighlight(index)),
+                            )
+                            .push(widget::text::caption(format!(
+                                "{} · {}",
+                                fl!(
+                                    "reader-chapter-short",
+                                    chapter = highlight.location.chapter + 1
+                                ),
+                                format_timestamp(highlight.created)
+                            )))
+                            .push(
+                                widget::text_input(
+                                    fl!("highlight-note-placeholder"),
+                                    &highlight.note,
+                                )
+                                .on_input(move |text| Message::EditHighlightNote(index, text)),
+                            )
+                            .spacing(space_xxs)
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        a11y::icon_button("emblem-shared-symbolic", fl!("a11y-share"))
+                            .on_press_maybe(
+                                self.account
+                                    .is_some()
+                                    .then_some(Message::ShareHighlight(index)),
+                            ),
+                    )
+                    .push(
+                        a11y::icon_button("edit-delete-symbolic", fl!("a11y-delete"))
+                            .on_press(Message::DeleteHighlight(index)),
+                    )
+                    .spacing(space_xxs),
+            );
+        }
+
+        let can_export = !highlights.is_empty();
+
+        col.push(widget::vertical_space().height(20))
+            .push(
+                widget::row()
+                    .push(
+                        widget::button::standard(fl!("export-markdown")).on_press_maybe(
+                            can_export.then_some(Message::ExportAnnotations(
+                                annotations::Format::Markdown,
+                            )),
+                        ),
+                    )
+                    .push(widget::button::standard(fl!("export-json")).on_press_maybe(
+                        can_export.then_some(Message::ExportAnnotations(annotations::Format::Json)),
+                    ))
+                    .push_maybe(cfg!(feature = "pdf").then(|| {
+                        widget::button::standard(fl!("print"))
+                            .on_press_maybe(can_export.then_some(Message::PrintAnnotations))
+                    }))
+                    .spacing(space_xxs),
+            )
+            .into()
+    }
+
+    /// Typography controls for the reader, editing the open book's own settings when it has
+    /// them and the global settings otherwise.
+    /// Settings from the catalog and configuration for displaying the open book.
+    fn appearance<'a>(&'a self, reader: &Reader) -> Appearance<'a> {
+        Appearance {
+            highlights: self
+                .library
+                .get(reader.book_id())
+                .map_or(&[][..], |book| &book.highlights),
+            typography: self.typography(),
+            palette: self.config.palette,
+            colors: self
+                .config
+                .palette
+                .colors(&self.config.custom_palette)
+                .map(|(foreground, background)| (rgb(foreground), rgb(background)))
+                .or_else(|| self.theme_fade_colors()),
+            palette_menu: self.palette_menu,
+            search_matches: self
+                .book_search
+                .as_ref()
+                .map_or(&[][..], |book_search| &book_search.matches),
+            current_match: self.book_search.as_ref().and_then(|book_search| {
+                book_search
+                    .current
+                    .and_then(|current| book_search.matches.get(current))
+            }),
+            rsvp_wpm: rsvp::wpm(self.config.rsvp_wpm),
+            fullscreen: self.fullscreen,
+            controls_hidden: self.controls_hidden,
+        }
+    }
+
+    /// The open book alone with a thin line of progress, while fullscreen reading has
+    /// hidden its controls. The cursor is hidden over the page too.
+    fn immersive_reader<'a>(&'a self, reader: &'a Reader) -> Element<'a, Message> {
+        let progress = reader.progress();
+
+        let indicator = widget::row::with_children(bidi::ordered(vec![
+            bidi::progress_bar(0.0..=1.0, progress)
+                .height(Length::Fixed(2.0))
+                .into(),
+            widget::text::caption(format!("{}%", (progress * 100.0).round() as u32)).into(),
+        ]))
+        .spacing(10)
+        .padding([0, 10, 4, 10])
+        .align_y(Alignment::Center);
+
+        let content = widget::column()
+            .push(reader.view(&self.appearance(reader)))
+            .push(indicator);
+
+        cosmic::iced::widget::mouse_area(content)
+            .interaction(mouse::Interaction::Hidden)
+            .into()
+    }
+
+    /// Enters or leaves fullscreen reading, hiding the header and navigation bar while
+    /// the window fills the screen.
+    fn set_fullscreen(&mut self, fullscreen: bool) -> Task<cosmic::Action<Message>> {
+        if self.fullscreen == fullscreen {
+            return Task::none();
+        }
+
+        self.fullscreen = fullscreen;
+        self.controls_hidden = false;
+        self.last_activity = Instant::now();
+        self.core.window.show_headerbar = !fullscreen;
+
+        if fullscreen {
+            self.nav_bar_before_fullscreen = self.core.nav_bar_active();
+            self.core.nav_bar_set_toggled(false);
+        } else {
+            self.core
+                .nav_bar_set_toggled(self.nav_bar_before_fullscreen);
+        }
+
+        let mode = if fullscreen {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        };
+
+        match self.reader_window.or(self.core.main_window_id()) {
+            Some(id) => window::change_mode(id, mode),
+            None => Task::none(),
+        }
+    }
+
+    /// The open book, with its table of contents if it's shown.
+    fn reader_view(&self) -> Option<Element<Message>> {
+        let reader = self.reader.as_ref()?;
+
+        Some(if self.controls_hidden {
+            self.immersive_reader(reader)
+        } else if self.toc_sidebar {
+            widget::row()
+                .push(self.toc_sidebar(reader))
+                .push(widget::divider::vertical::default())
+                .push(reader.view(&self.appearance(reader)))
+                .into()
+        } else {
+            reader.view(&self.appearance(reader))
+        })
+    }
+
+    /// Whether the open book is showing, in its own window or on the reader page.
+    fn reader_shown(&self) -> bool {
+        self.reader.is_some()
+            && (self.reader_window.is_some()
+                || self.nav.active_data::<Page>() == Some(&Page::Reader))
+    }
+
+    /// Shows the page in the main window.
+    fn activate_page(&mut self, page: Page) {
+        let id = self
+            .nav
+            .iter()
+            .find(|&id| self.nav.data::<Page>(id).copied() == Some(page));
+
+        if let Some(id) = id {
+            self.nav.activate(id);
+        }
+    }
+
+    /// The reader's table of contents, with the entry being read selected.
+    fn toc_sidebar<'a>(&'a self, reader: &'a Reader) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let rows = toc::rows(reader.toc(), reader.location().chapter, &self.toc_toggled);
+
+        let content: Element<Message> = if rows.is_empty() {
+            widget::text::caption(fl!("toc-empty")).into()
+        } else {
+            rows.into_iter()
+                .fold(widget::column(), |col, row| {
+                    let expander: Element<Message> = if row.entry.children.is_empty() {
+                        widget::Space::new(32, 0).into()
+                    } else {
+                        let (icon_name, name) = if row.expanded {
+                            ("go-down-symbolic", fl!("a11y-collapse"))
+                        } else {
+                            (bidi::icon("go-next-symbolic"), fl!("a11y-expand"))
+                        };
+
+                        a11y::icon_button(icon_name, name)
+                            .on_press(Message::ToggleTocEntry(row.id))
+                            .into()
+                    };
+
+                    col.push(
+                        widget::row::with_children(bidi::ordered(vec![
+                            widget::Space::new(16.0 * row.depth as f32, 0).into(),
+                            expander,
+                            widget::button::text(&row.entry.title)
+                                .selected(row.current)
+                                .on_press_maybe(row.entry.target.map(Message::TocJump))
+                                .width(Length::Fill)
+                                .into(),
+                        ]))
+                        .align_y(Alignment::Center),
+                    )
+                })
+                .into()
+        };
+
+        widget::column()
+            .push(widget::text::heading(fl!("toc")))
+            .push(widget::scrollable(content).height(Length::Fill))
+            .spacing(space_xxs)
+            .padding(10)
+            .width(Length::Fixed(280.0))
+            .into()
+    }
+
+    /// The shortcuts settings, listing the keys and gestures bound to each reader action.
+    pub fn shortcuts(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column()
+            .push(widget::text::caption(fl!("shortcuts-hint")))
+            .spacing(12);
+
+        for action in ReaderAction::ALL {
+            let bindings = self
+                .config
+                .shortcuts
+                .triggers(action)
+                .map(|trigger| {
+                    widget::button::standard(shortcuts::label(trigger))
+                        .trailing_icon(icon::from_name("edit-delete-symbolic"))
+                        .on_press(Message::RemoveShortcut(trigger.to_string()))
+                        .into()
+                })
+                .collect();
+
+            let add: Element<Message> = if self.capturing_shortcut == Some(action) {
+                widget::text::caption(fl!("shortcut-press")).into()
+            } else {
+                widget::button::text(fl!("shortcut-add"))
+                    .on_press(Message::CaptureShortcut(action))
+                    .into()
+            };
+
+            col = col.push(
+                widget::column()
+                    .push(widget::text::heading(action.label()))
+                    .push(
+                        widget::flex_row(bindings)
+                            .row_spacing(space_xxs)
+                            .column_spacing(space_xxs),
+                    )
+                    .push(add)
+                    .spacing(space_xxs),
+            );
+        }
+
+        col.push(widget::button::standard(fl!("shortcuts-reset")).on_press(Message::ResetShortcuts))
+            .into()
+    }
+
+    pub fn typography_settings(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let typography = self.typography();
+        let book = self
+            .reader
+            .as_ref()
+            .and_then(|reader| self.library.get(reader.book_id()));
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if let Some(book) = book {
+            col = col.push(
+                widget::checkbox(fl!("typography-this-book"), book.typography.is_some())
+                    .on_toggle(Message::TypographyOverride),
+            );
+        }
+
+        let fonts =
+            FontFamily::ALL
+                .into_iter()
+                .fold(widget::row().spacing(space_xxs), |row, font| {
+                    let label = match font {
+                        FontFamily::Serif => fl!("font-serif"),
+                        FontFamily::SansSerif => fl!("font-sans-serif"),
+                        FontFamily::Monospace => fl!("font-monospace"),
+                    };
+
+                    let typography = typography.clone();
+
+                    row.push(widget::radio(
+                        label,
+                        font,
+                        Some(typography.font),
+                        move |font| {
+                            Message::SetTypography(Typography {
+                                font,
+                                ..typography.clone()
+                            })
+                        },
+                    ))
+                });
+
+        let size = {
+            let typography = typography.clone();
+            widget::slider(Typography::SIZES, typography.size, move |size| {
+                Message::SetTypography(Typography {
+                    size,
+                    ..typography.clone()
+                })
+            })
+        };
+
+        let line_height = {
+            let typography = typography.clone();
+            widget::slider(
+                Typography::LINE_HEIGHTS,
+                typography.line_height,
+                move |line_height| {
+                    Message::SetTypography(Typography {
+                        line_height,
+                        ..typography.clone()
+                    })
+                },
+            )
+            .step(10u16)
+        };
+
+        let margin = {
+            let typography = typography.clone();
+            widget::slider(Typography::MARGINS, typography.margin, move |margin| {
+                Message::SetTypography(Typography {
+                    margin,
+                    ..typography.clone()
+                })
+            })
+            .step(4u16)
+        };
+
+        let justify = {
+            let typography = typography.clone();
+            widget::checkbox(fl!("typography-justify"), typography.justify).on_toggle(
+                move |justify| {
+                    Message::SetTypography(Typography {
+                        justify,
+                        ..typography.clone()
+                    })
+                },
+            )
+        };
+
+        col.push(widget::text::heading(fl!("typography-font")))
+            .push(fonts)
+            .push(widget::text::heading(fl!(
+                "typography-size",
+                size = typography.size
+            )))
+            .push(size)
+            .push(widget::text::heading(fl!(
+                "typography-line-height",
+                percent = typography.line_height
+            )))
+            .push(line_height)
+            .push(widget::text::heading(fl!(
+                "typography-margin",
+                margin = typography.margin
+            )))
+            .push(margin)
+            .push(justify)
+            .push(widget::vertical_space().height(20))
+            .push(widget::text::heading(fl!("spread")))
+            .push(
+                [
+                    (Spread::Auto, fl!("spread-auto")),
+                    (Spread::Single, fl!("spread-single")),
+                    (Spread::Double, fl!("spread-double")),
+                ]
+                .into_iter()
+                .fold(widget::row().spacing(space_xxs), |row, (spread, label)| {
+                    row.push(widget::radio(
+                        label,
+                        spread,
+                        Some(self.config.spread),
+                        Message::SetSpread,
+                    ))
+                }),
+            )
+            .push(widget::vertical_space().height(20))
+            .push(widget::text::heading(fl!("palette-custom")))
+            .push(self.custom_color_input(
+                fl!("palette-foreground"),
+                &self.custom_palette_input.0,
+                self.config.custom_palette.foreground,
+                Message::CustomForeground,
+            ))
+            .push(self.custom_color_input(
+                fl!("palette-background"),
+                &self.custom_palette_input.1,
+                self.config.custom_palette.background,
+                Message::CustomBackground,
+            ))
+            .into()
+    }
+
+    /// A hex entry for one of the custom palette's colors, previewing the saved color.
+    fn custom_color_input<'a>(
+        &self,
+        label: String,
+        input: &'a str,
+        color: palette::Rgb,
+        on_input: fn(String) -> Message,
+    ) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+        let preview = rgb(color);
+
+        widget::row()
+            .push(widget::text(label).width(Length::Fill))
+            .push(
+                widget::text_input(palette::to_hex(color), input)
+                    .on_input(on_input)
+                    .width(Length::Fixed(100.0)),
+            )
+            .push(
+                widget::container(widget::Space::new(24, 24)).class(theme::Container::custom(
+                    move |_| widget::container::Style {
+                        background: Some(preview.into()),
+                        border: cosmic::iced::Border {
+                            radius: 4.0.into(),
+                            width: 1.0,
+                            color: Color::from_rgba(0.5, 0.5, 0.5, 0.5),
+                        },
+                        ..Default::default()
+                    },
+                )),
+            )
+            .spacing(space_xxs)
+            .align_y(Alignment::Center)
+            .into()
+    }
+
+    /// The verification page, showing progress and books whose files need attention.
+    pub fn verify(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if let Some(job) = &self.verify_job {
+            col = col
+                .push(bidi::progress_bar(0.0..=job.total as f32, job.done as f32))
+                .push(widget::text(fl!(
+                    "verify-progress",
+                    done = job.done,
+                    total = job.total
+                )))
+                .push(widget::button::standard(fl!("cancel")).on_press(Message::CancelVerify));
+        } else {
+            if self.verify_results.is_empty() {
+                col = col.push(widget::text(fl!("verify-clean")));
+            }
+
+            if let Some(summary) = &self.last_verify {
+                col = col.push(widget::text::caption(fl!(
+                    "verify-summary",
+                    total = summary.checked,
+                    hashed = summary.hashed,
+                    seconds = format!("{:.1}", summary.duration.as_secs_f32())
+                )));
+            }
+
+            col = col.push(
+                widget::button::standard(fl!("verify-full")).on_press(Message::VerifyLibrary(true)),
+            );
+        }
+
+        for (id, status) in &self.verify_results {
+            let id = *id;
+            let title = self
+                .library
+                .get(id)
+                .map(|book| book.title.as_str())
+                .unwrap_or_default();
+
+            let (description, relocate) = match status {
+                integrity::Status::Ok => continue,
+                integrity::Status::Missing => (fl!("verify-missing"), None),
+                integrity::Status::Corrupted => (fl!("verify-corrupted"), None),
+                integrity::Status::Moved(path) => (
+                    fl!("verify-moved", path = path.to_string_lossy()),
+                    Some(Message::RelocateBook(id, path.clone())),
+                ),
+            };
+
+            let mut actions = widget::row().spacing(space_xxs);
+
+            if let Some(relocate) = relocate {
+                actions =
+                    actions.push(widget::button::standard(fl!("relocate")).on_press(relocate));
+            }
+
+            col = col.push(
+                widget::column()
+                    .push(widget::text(title))
+                    .push(widget::text::caption(description))
+                    .push(
+                        actions.push(
+                            widget::button::destructive(fl!("remove"))
+                                .on_press(Message::RemoveMissingBook(id)),
+                        ),
+                    )
+                    .spacing(space_xxs),
+            );
+        }
+
+        col.into()
+    }
+
+    /// The trash page, listing deleted books until they are purged.
+    pub fn trash(&self) -> Element<Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let now = trash::now();
+        let trashed: Vec<&Book> = self.library.trashed().collect();
+
+        let mut col = widget::column().spacing(space_xxs);
+
+        if trashed.is_empty() {
+            col = col.push(widget::text(fl!("trash-empty")));
+        }
+
+        for book in &trashed {
+            let days = book
+                .trashed
+                .as_ref()
+                .map_or(0, |trashed| trash::days_left(trashed.at, now));
+
+            col = col.push(
+                widget::row()
+                    .push(
+                        widget::column()
+                            .push(widget::text(&book.title))
+                            .push(widget::text::caption(fl!("trash-days-left", days = days)))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        widget::button::standard(fl!("restore"))
+                            .on_press(Message::RestoreBooks(vec![book.id])),
+                    )
+                    .push(
+                        widget::button::destructive(fl!("delete-permanently"))
+                            .on_press(Message::PurgeBooks(vec![book.id])),
+                    )
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        let all: Vec<BookId> = trashed.iter().map(|book| book.id).collect();
+
+        col.push(widget::vertical_space().height(20))
+            .push(
+                widget::button::destructive(fl!("empty-trash"))
+                    .on_press_maybe((!all.is_empty()).then(|| Message::PurgeBooks(all)))
+                    .width(Length::Fill),
+            )
+            .into()
+    }
+
+    /// What cancels the dialog being shown, checked in the order `dialog` shows them,
+    /// unless it has to be answered with a button.
+    fn dialog_cancel(&self) -> Option<Message> {
+        if self.export_dialog.is_some() {
+            return Some(Message::CloseExportDialog);
+        }
+
+        // Dismissing crash reports deletes them, so that takes a button press.
+        if self.crash_dialog.is_some() {
+            return None;
+        }
+
+        if self.restore_dialog.is_some() {
+            return Some(Message::DismissSession);
+        }
+
+        if self.pairing_request.is_some() {
+            return Some(Message::ConfirmPairing(false));
+        }
+
+        if self.access_request.is_some() {
+            return Some(Message::ConfirmAccess(false));
+        }
+
+        if !self.offers.is_empty() {
+            return Some(Message::DeclineOffer);
+        }
+
+        if self.import_dialog.is_some() {
+            return Some(Message::CloseImportDialog);
+        }
+
+        if self.share_dialog.is_some() {
+            return Some(Message::CloseShareDialog);
+        }
+
+        self.show_popup.then_some(Message::TogglePopup)
+    }
+
+    /// The dialog asking for consent to send the reports of earlier crashes.
+    fn crash_dialog<'a>(&'a self, state: &'a CrashDialog) -> Element<'a, Message> {
+        let mut control = widget::column()
+            .push(
+                widget::scrollable(widget::text::monotext(&state.preview))
+                    .height(Length::Fixed(240.0)),
+            )
+            .spacing(10);
+
+        if let Some(why) = &state.error {
+            control = control.push(widget::text(fl!("crash-failed", reason = why.as_str())));
+        }
+
+        dialog()
+            .title(fl!("crash-title"))
+            .body(fl!(
+                "crash-body",
+                count = state.reports.len(),
+                endpoint = self.config.crash_endpoint.as_str()
+            ))
+            .control(control)
+            .primary_action(
+                button::suggested(if state.sending {
+                    fl!("crash-sending")
+                } else {
+                    fl!("crash-send")
+                })
+                .on_press_maybe((!state.sending).then_some(Message::SendCrashReports)),
+            )
+            .secondary_action(
+                button::standard(fl!("crash-dont-send")).on_press(Message::DismissCrashReports),
+            )
+            .into()
+    }
+
+    /// The dialog offering to restore the session of a run that ended unexpectedly.
+    fn restore_dialog(&self) -> Element<Message> {
+        dialog()
+            .title(fl!("restore-title"))
+            .body(fl!("restore-body"))
+            .primary_action(button::suggested(fl!("restore")).on_press(Message::RestoreSession))
+            .secondary_action(
+                button::standard(fl!("restore-dismiss")).on_press(Message::DismissSession),
+            )
+            .into()
+    }
+
+    /// The dialog asking whether to let a device which got the pairing code right pair.
+    fn pairing_dialog<'a>(&'a self, request: &'a host::Request) -> Element<'a, Message> {
+        dialog()
+            .title(fl!("companion-confirm-title"))
+            .body(fl!(
+                "companion-confirm-body",
+                device = request.device.as_str(),
+                address = request.address.to_string()
+            ))
+            .primary_action(
+                button::suggested(fl!("companion-confirm")).on_press(Message::ConfirmPairing(true)),
+            )
+            .secondary_action(
+                button::standard(fl!("companion-confirm-decline"))
+                    .on_press(Message::ConfirmPairing(false)),
+            )
+            .into()
+    }
+
+    /// The dialog asking whether to let another instance browse the library.
+    fn access_dialog<'a>(&'a self, request: &'a lan::Request) -> Element<'a, Message> {
+        dialog()
+            .title(fl!("nearby-access-title"))
+            .body(fl!(
+                "nearby-access-body",
+                device = request.device.as_str(),
+                address = request.address.to_string()
+            ))
+            .primary_action(
+                button::suggested(fl!("nearby-access-allow"))
+                    .on_press(Message::ConfirmAccess(true)),
+            )
+            .secondary_action(
+                button::standard(fl!("nearby-access-deny")).on_press(Message::ConfirmAccess(false)),
+            )
+            .into()
+    }
+
+    /// The dialog asking whether to accept a book another instance offers to send.
+    fn offer_dialog<'a>(&'a self, offer: &'a lan::Offer) -> Element<'a, Message> {
+        dialog()
+            .title(fl!("nearby-offer-title"))
+            .body(fl!(
+                "nearby-offer-body",
+                device = offer.device.as_str(),
+                title = offer.book.title.as_str(),
+                size = format_size(offer.book.size)
+            ))
+            .primary_action(button::suggested(fl!("nearby-accept")).on_press(Message::AcceptOffer))
+            .secondary_action(
+                button::standard(fl!("nearby-decline")).on_press(Message::DeclineOffer),
+            )
+            .into()
+    }
+
+    /// The dialog taking the address of a book to download into the library.
+    fn import_dialog<'a>(&'a self, url: &'a str) -> Element<'a, Message> {
+        let can_import = url.trim().starts_with("http://") || url.trim().starts_with("https://");
+
+        dialog()
+            .title(fl!("import-url"))
+            .body(fl!("import-url-hint"))
+            .control(
+                widget::text_input(fl!("import-url-placeholder"), url)
+                    .id(self.import_input_id.clone())
+                    .on_input(Message::ImportUrl)
+                    .on_submit_maybe(can_import.then_some(|_| Message::ImportFromUrl))
+                    .width(Length::Fill),
+            )
+            .primary_action(
+                button::suggested(fl!("import"))
+                    .on_press_maybe(can_import.then_some(Message::ImportFromUrl)),
+            )
+            .secondary_action(button::standard(fl!("cancel")).on_press(Message::CloseImportDialog))
+            .tertiary_action(
+                button::text(fl!("import-files"))
+                    .on_press(Message::ChooseFile(FileTarget::ImportBooks)),
+            )
+            .into()
+    }
+
+    /// The dialog choosing the format, fields and destination of a library export.
+    fn export_dialog<'a>(&'a self, state: &'a ExportDialog) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let format = widget::row()
+            .push(widget::radio(
+                "CSV",
+                export::Format::Csv,
+                Some(state.format),
+                Message::ExportFormat,
+            ))
+            .push(widget::radio(
+                "JSON",
+                export::Format::Json,
+                Some(state.format),
+                Message::ExportFormat,
+            ))
+            .spacing(space_xxs);
+
+        let fields =
+            Field::ALL
+                .into_iter()
+                .fold(widget::column().spacing(space_xxs), |col, field| {
+                    col.push(
+                        widget::checkbox(field_label(field), state.fields.contains(&field))
+                            .on_toggle(move |included| Message::ExportField(field, included)),
+                    )
+                });
+
+        let path = widget::row()
+            .push(
+                widget::text_input(fl!("export-path"), &state.path)
+                    .on_input(Message::ExportPath)
+                    .width(Length::Fill),
+            )
+            .push(
+                widget::button::standard(fl!("choose"))
+                    .on_press(Message::ChooseFile(FileTarget::ExportPath)),
+            )
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let can_export = !state.fields.is_empty() && !state.path.trim().is_empty();
+
+        dialog()
+            .title(fl!("export-library"))
+            .control(
+                widget::column()
+                    .push(format)
+                    .push(fields)
+                    .push(path)
+                    .spacing(10),
+            )
+            .primary_action(
+                button::suggested(fl!("export"))
+                    .on_press_maybe(can_export.then_some(Message::Export)),
+            )
+            .secondary_action(button::standard(fl!("cancel")).on_press(Message::CloseExportDialog))
+            .into()
+    }
+
+    fn share_dialog<'a>(&'a self, state: &'a ShareDialog) -> Element<'a, Message> {
+        let length = state.text.text().trim().chars().count();
+        let can_post = !state.posting
+            && self.account.is_some()
+            && length > 0
+            && length <= atproto::MAX_POST_CHARS;
+
+        let mut control = widget::column()
+            .push(
+                text_editor(&state.text)
+                    .on_action(Message::ShareEdit)
+                    .height(Length::Fixed(160.0)),
+            )
+            .push(widget::text::caption(fl!(
+                "share-length",
+                length = length,
+                max = atproto::MAX_POST_CHARS
+            )))
+            .spacing(10);
+
+        if let Some(handle) = &state.cover_handle {
+            control = control
+                .push(widget::image(handle.clone()).height(Length::Fixed(160.0)))
+                .push(
+                    widget::checkbox(fl!("share-attach-cover"), state.attach_cover)
+                        .on_toggle(Message::ShareAttachCover),
+                );
+        }
+
+        if let Some(why) = &state.error {
+            control = control.push(widget::text(fl!("share-failed", reason = why.as_str())));
+        }
+
+        dialog()
+            .title(fl!("share-title"))
+            .control(control)
+            .primary_action(
+                button::suggested(if state.posting {
+                    fl!("share-posting")
+                } else {
+                    fl!("share-post")
+                })
+                .on_press_maybe(can_post.then_some(Message::SharePost)),
+            )
+            .secondary_action(button::standard(fl!("cancel")).on_press(Message::CloseShareDialog))
+            .into()
+    }
+
+    /// Announces books moved into the trash with a toast offering to undo.
+    fn push_trash_toast(&mut self, trashed: Vec<BookId>) -> Task<cosmic::Action<Message>> {
+        if trashed.is_empty() {
+            return Task::none();
+        }
+
+        let text = fl!("trash-toast", count = trashed.len());
+        let announcement = self.announce(text.clone());
+
+        let toast = widget::toaster::Toast::new(text)
+            .action(fl!("undo"), move |_| Message::RestoreBooks(trashed.clone()));
+
+        Task::batch([
+            self.toasts.push(toast).map(cosmic::Action::App),
+            announcement,
+        ])
+    }
+
+    /// Shows a toast, reading it out to any screen reader.
+    fn push_toast(&mut self, text: String) -> Task<cosmic::Action<Message>> {
+        let announcement = self.announce(text.clone());
+        let toast = self
+            .toasts
+            .push(widget::toaster::Toast::new(text))
+            .map(cosmic::Action::App);
+
+        Task::batch([toast, announcement])
+    }
+
+    /// Reads `text` out to the screen reader, if one is running.
+    fn announce(&self, text: String) -> Task<cosmic::Action<Message>> {
+        if !self.screen_reader {
+            return Task::none();
+        }
+
+        Task::future(a11y::announce(text)).discard()
+    }
+
+    /// Reads out the title of a dialog that was just opened.
+    fn announce_dialog(&self, title: String) -> Task<cosmic::Action<Message>> {
+        self.announce(fl!("a11y-dialog-opened", title = title))
+    }
+
+    /// Retitles the window for the page just shown, and reads out which page it is.
+    fn page_shown(&mut self) -> Task<cosmic::Action<Message>> {
+        let announcement = match self.nav.text(self.nav.active()) {
+            Some(page) => self.announce(fl!("a11y-page-shown", page = page)),
+            None => Task::none(),
+        };
+
+        Task::batch([self.update_title(), announcement])
+    }
+
+    /// Switches to the reader page showing a newly opened book.
+    fn show_reader(
+        &mut self,
+        reader: Reader,
+        fallback_title: String,
+    ) -> Task<cosmic::Action<Message>> {
+        // Remember where the previously open book was left.
+        let finished = self.record_reading_position();
+        let stop = self.stop_speaking();
+        let push = Task::batch([self.push_positions(), self.sync_pds()]);
+
+        let title = match self.library.get(reader.book_id()) {
+            Some(book) if !book.title.is_empty() => book.title.clone(),
+            _ => fallback_title,
+        };
+
+        self.reader = Some(reader);
+        self.toc_toggled.clear();
+        self.book_search = None;
+
+        // A book opened while the reader has a window of its own is shown there.
+        if let Some(id) = self.reader_window {
+            return Task::batch([
+                stop,
+                push,
+                finished,
+                self.set_window_title(bidi::plain(&fl!("reader-window-title", title = title)), id),
+                window::gain_focus(id),
+                self.apply_spread(),
+            ]);
+        }
+
+        let nav_id = match self.reader_nav {
+            Some(nav_id) => nav_id,
+            None => self
+                .nav
+                .insert()
+                .data::<Page>(Page::Reader)
+                .icon(icon::from_name("accessories-dictionary-symbolic"))
+                .id(),
+        };
+
+        self.nav.text_set(nav_id, title);
+        self.nav.activate(nav_id);
+        self.reader_nav = Some(nav_id);
+        self.core.window.show_context = false;
+        self.close_pairing();
+
+        Task::batch([
+            stop,
+            push,
+            finished,
+            self.update_title(),
+            self.apply_spread(),
+        ])
+    }
+
+    /// Shows two-page spreads in the reader when configured to, or when the window is
+    /// wide enough.
+    fn apply_spread(&mut self) -> Task<cosmic::Action<Message>> {
+        let spread = match self.config.spread {
+            Spread::Auto => self.window_width >= SPREAD_WIDTH,
+            Spread::Single => false,
+            Spread::Double => true,
+        };
+
+        match self.reader.as_mut() {
+            Some(reader) if reader.spread() != spread => reader.set_spread(spread),
+            _ => Task::none(),
+        }
+    }
+
+    /// Typography of the open book, falling back to the global settings.
+    fn typography(&self) -> &Typography {
+        self.reader
+            .as_ref()
+            .and_then(|reader| self.library.get(reader.book_id()))
+            .and_then(|book| book.typography.as_ref())
+            .unwrap_or(&self.config.typography)
+    }
+
+    fn read_aloud_mut(&mut self) -> Option<&mut reader::ReadAloud> {
+        match self.reader.as_mut() {
+            Some(Reader::Epub(reader)) => reader.read_aloud.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Silences the open book if it is being read aloud.
+    fn stop_speaking(&mut self) -> Task<cosmic::Action<Message>> {
+        match self.reader.as_mut() {
+            Some(Reader::Epub(reader)) => reader.stop_speaking(),
+            _ => Task::none(),
+        }
+    }
+
+    /// Restarts reading aloud from the current sentence so changed options take effect.
+    fn restart_speaking(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+            return Task::none();
+        };
+
+        if !reader
+            .read_aloud
+            .as_ref()
+            .is_some_and(|read_aloud| read_aloud.is_playing())
+        {
+            return Task::none();
+        }
+
+        reader.stop_speaking().chain(reader.play())
+    }
+
+    fn translation_mut(&mut self) -> Option<&mut reader::Translation> {
+        match self.reader.as_mut() {
+            Some(Reader::Epub(reader)) => reader.translation.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Sends the passage in the translation popover to the server, replacing any pending
+    /// request.
+    fn translate(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(Reader::Epub(reader)) = self.reader.as_mut() else {
+            return Task::none();
+        };
+
+        let Some(translation) = reader.translation.as_mut() else {
+            return Task::none();
+        };
+
+        translation.result = None;
+
+        let (task, handle) = Task::perform(
+            translate::translate(
+                self.config.translate_url.clone(),
+                self.config.translate_api_key.clone(),
+                translation.text.clone(),
+                translation.source.clone(),
+                translation.target.clone(),
+            ),
+            |result| cosmic::Action::App(Message::Translated(result)),
+        )
+        .abortable();
+
+        translation.task = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Binds the key or gesture to the action awaiting one, or otherwise performs the
+    /// reader action it is bound to.
+    fn trigger_shortcut(&mut self, trigger: String) -> Task<cosmic::Action<Message>> {
+        if let Some(action) = self.capturing_shortcut.take() {
+            self.config.shortcuts.bind(trigger, action);
+            self.save_config();
+            return Task::none();
+        }
+
+        if !self.reader_shown() {
+            return Task::none();
+        }
+
+        let Some(mut action) = self.config.shortcuts.action(&trigger) else {
+            return Task::none();
+        };
+
+        if let Some(Reader::Comic(reader)) = &self.reader {
+            if reader.right_to_left && shortcuts::is_horizontal(&trigger) {
+                action = action.mirrored();
+            }
+        }
+
+        self.update(Message::ReaderAction(action))
+    }
+
+    /// Raises a desktop notification, with a button sending `message` if given.
+    fn notify(
+        &self,
+        summary: String,
+        body: String,
+        action: Option<(String, Message)>,
+    ) -> Task<cosmic::Action<Message>> {
+        let (label, message) = action.unzip();
+
+        Task::future(async move {
+            match notify::notify(summary, body, label).await {
+                Ok(true) => message,
+                Ok(false) => None,
+                Err(why) => {
+                    tracing::error!("failed to raise notification: {why}");
+                    None
+                }
+            }
+        })
+        .and_then(|message| {
+            Task::done(cosmic::Action::App(Message::NotificationClicked(Box::new(
+                message,
+            ))))
+        })
+    }
+
+    /// Raises a desktop notification of something finishing, if enabled and the window
+    /// is in the background, where a toast would go unseen.
+    fn notify_in_background(
+        &self,
+        summary: String,
+        action: Option<(String, Message)>,
+    ) -> Task<cosmic::Action<Message>> {
+        if !self.config.notifications || (self.focused && !self.window_hidden) {
+            return Task::none();
+        }
+
+        self.notify(summary, String::new(), action)
+    }
+
+    /// The book open in the reader, as the control interface reports it.
+    fn reading_state(&self) -> Option<control::ReadingState> {
+        let reader = self.reader.as_ref()?;
+        let book = self.library.get(reader.book_id());
+
+        Some(control::ReadingState {
+            book: reader.book_id(),
+            title: book.map(|book| book.title.clone()).unwrap_or_default(),
+            author: book.map(|book| book.author.clone()).unwrap_or_default(),
+            path: book.and_then(|book| book.path.clone()),
+            location: reader.location().chapter,
+            progress: reader.progress(),
+        })
+    }
+
+    /// What is being read aloud, if the read-aloud controls are open.
+    fn playback(&self) -> Option<mpris::State> {
+        let Some(Reader::Epub(reader)) = &self.reader else {
+            return None;
+        };
+
+        let read_aloud = reader.read_aloud.as_ref()?;
+        let book = self.library.get(reader.book_id)?;
+
+        let status = if read_aloud.is_playing() {
+            mpris::Status::Playing
+        } else {
+            mpris::Status::Paused
+        };
+
+        Some(mpris::State {
+            status,
+            book: book.id,
+            path: book.path.clone(),
+            title: book.title.clone(),
+            author: book.author.clone(),
+            chapter: toc_title(&reader.epub.toc, reader.chapter_index).map(str::to_string),
+            chapter_index: reader.chapter_index,
+            sentence: read_aloud.current(),
+            sentences: read_aloud.sentence_count(),
+            has_previous: reader.chapter_index > 0,
+            has_next: reader.has_next_chapter(),
+        })
+    }
+
+    /// Where the user is, as written to restore after an unexpected exit.
+    fn current_session(&self) -> Session {
+        let details = match self.context_page {
+            ContextPage::BookDetails(id) if self.core.window.show_context => Some(id),
+            _ => None,
+        };
+
+        Session {
+            page: self.nav.active_data::<Page>().copied(),
+            book: self
+                .reader
+                .as_ref()
+                .map(|reader| (reader.book_id(), reader.location())),
+            details,
+            search_query: self.search_query.clone(),
+            import_url: self.import_dialog.clone(),
+            note_input: self.note_input.clone(),
+            bookmark_input: self.bookmark_input.clone(),
+            bulk_input: self.bulk_input.clone(),
+        }
+    }
+
+    /// Goes back to where the user was in an earlier session.
+    fn restore_session(&mut self, session: Session) -> Task<cosmic::Action<Message>> {
+        self.search_query = session.search_query;
+        self.search_expanded = !self.search_query.is_empty();
+        self.import_dialog = session.import_url;
+        self.note_input = session.note_input;
+        self.bookmark_input = session.bookmark_input;
+        self.bulk_input = session.bulk_input;
+
+        // The reader is opened where it was left, which shows the reader page.
+        if let Some((id, location)) = session.book {
+            if let Some(book) = self.library.get_mut(id) {
+                book.location = Some(location);
+                return self.update(Message::OpenReader(id));
+            }
+        }
+
+        if let Some(id) = session.details.filter(|&id| self.library.get(id).is_some()) {
+            self.context_page = ContextPage::BookDetails(id);
+            self.core.window.show_context = true;
+        }
+
+        if let Some(page) = session.page {
+            self.activate_page(page);
+        }
+
+        self.update_title()
+    }
+
+    /// Quits, removing the session as there's nothing to restore after quitting, and
+    /// writing out settings and the catalog changed too recently to have been.
+    fn quit(&self) -> Task<cosmic::Action<Message>> {
+        if self.config_changed.is_some() {
+            write_config_now(&self.config);
+        }
+
+        if self.library_changed.is_some() {
+            let save = LIBRARY_SAVES.fetch_add(1, Ordering::Relaxed) + 1;
+            write_library_now(&self.library, save);
+        }
+
+        session::clear();
+        cosmic::iced::exit()
+    }
+
+    /// Primary actions of the page being shown, placed in the header bar.
+    fn page_actions(&self) -> Vec<Element<Message>> {
+        let action = |name: &'static str, message: Message| -> Element<Message> {
+            icon::from_name(name)
+                .size(16)
+                .apply(widget::button::custom)
+                .on_press(message)
+                .padding(8)
+                .into()
+        };
+
+        match self.nav.active_data::<Page>() {
+            Some(Page::Page3) => vec![action(
+                "list-add-symbolic",
+                Message::ChooseFile(FileTarget::ImportBooks),
+            )],
+            Some(Page::Feed) => vec![action("view-refresh-symbolic", Message::LoadFeed(true))],
+            Some(Page::News) => vec![action("view-refresh-symbolic", Message::RefreshNews)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Switches to the configured theme, fading the reading area into it if it turns
+    /// from light to dark or back.
+    fn apply_theme(&mut self) -> Task<cosmic::Action<Message>> {
+        let theme = self.config.app_theme.theme();
+        let dark = theme.cosmic().is_dark;
+
+        if dark != self.dark {
+            self.dark = dark;
+            self.theme_switched = Some(Instant::now());
+        }
+
+        cosmic::command::set_theme(theme)
+    }
+
+    /// How far through fading into the current theme, from 0.0 to 1.0.
+    fn theme_fade(&self) -> f32 {
+        self.theme_switched.map_or(1.0, |switched| {
+            (switched.elapsed().as_secs_f32() / THEME_FADE.as_secs_f32()).min(1.0)
+        })
+    }
+
+    /// How dark the app is, from 0.0 to 1.0, which is in between while fading.
+    fn darkness(&self) -> f32 {
+        if self.dark {
+            self.theme_fade()
+        } else {
+            1.0 - self.theme_fade()
+        }
+    }
+
+    /// Colors of the reading area while it fades from the last theme into the current
+    /// one, when it follows the app theme.
+    fn theme_fade_colors(&self) -> Option<(Color, Color)> {
+        let fade = self.theme_fade();
+
+        if fade >= 1.0 {
+            return None;
+        }
+
+        let from = if self.dark {
+            theme::system_light()
+        } else {
+            theme::system_dark()
+        };
+        let to = theme::active();
+
+        let mix = |from: Color, to: Color| {
+            Color::from_rgba(
+                from.r + (to.r - from.r) * fade,
+                from.g + (to.g - from.g) * fade,
+                from.b + (to.b - from.b) * fade,
+                from.a + (to.a - from.a) * fade,
+            )
+        };
+
+        Some((
+            mix(
+                Color::from(from.cosmic().background.on),
+                Color::from(to.cosmic().background.on),
+            ),
+            mix(
+                Color::from(from.cosmic().background.base),
+                Color::from(to.cosmic().background.base),
+            ),
+        ))
+    }
+
+    /// Whether to hold back animations and background work, to save power.
+    fn saving_power(&self) -> bool {
+        self.power.is_saving() && !self.config.ignore_power_saving
+    }
+
+    /// Whether anything animates where it can be seen: the canvas on its page, or the
+    /// colors fading after the theme switched. A window hidden to the tray, minimized or
+    /// in the background isn't watched.
+    fn animating(&self) -> bool {
+        let canvas = self
+            .nav
+            .active_data::<Page>()
+            .is_none_or(|page| *page == Page::Page1);
+
+        !self.window_hidden && self.focused && (canvas || self.theme_switched.is_some())
+    }
+
+    /// What the app is doing, deciding which subscriptions run.
+    fn activity(&self) -> Activity {
+        let syncing = (self.config.webdav_scheduled && !self.config.webdav_url.trim().is_empty())
+            || self.companion_remote().is_some()
+            || self.host.is_some()
+            || self.lan.is_some();
+
+        let downloading = self.downloads.downloads().iter().any(|download| {
+            matches!(
+                download.state,
+                download::State::Queued | download::State::Active
+            )
+        });
+
+        Activity {
+            shown: !self.window_hidden,
+            animating: self.animating(),
+            background: self.online && !self.saving_power(),
+            syncing,
+            downloading,
+        }
+    }
+
+    /// Hears about the system: power, accessibility, the theme, the network and settings
+    /// changed elsewhere.
+    fn system_subscriptions(&self, activity: &Activity) -> Vec<Subscription<Message>> {
+        let mut subscriptions = vec![
+            // Hear when the computer goes on battery or into power-saver mode.
+            Subscription::run(power::watch).map(Message::PowerChanged),
+            // Hear when a screen reader starts or stops, to announce changes to it.
+            Subscription::run(a11y::screen_reader).map(Message::ScreenReaderChanged),
+            // Follow the system between light and dark.
+            cosmic_config::config_subscription::<_, cosmic::cosmic_theme::ThemeMode>(
+                std::any::TypeId::of::<cosmic::cosmic_theme::ThemeMode>(),
+                cosmic::cosmic_theme::THEME_MODE_ID.into(),
+                cosmic::cosmic_theme::ThemeMode::version(),
+            )
+            .map(|update| Message::SystemThemeMode(update.config)),
+            // Hear when the network goes away or comes back.
+            Subscription::run(net::status).map(Message::Online),
+        ];
+
+        // Watch for settings changed elsewhere while the window is shown. Watching again
+        // once it is reads them afresh, so nothing changed while hidden is missed.
+        if activity.shown {
+            subscriptions.push(
+                self.core()
+                    .watch_config::<Config>(Self::APP_ID)
+                    .map(|update| Message::UpdateConfig(update.config)),
+            );
+        }
+
+        // Check for the network while it's away, as long as anything is waiting on it.
+        if !self.online && (activity.shown || activity.syncing || activity.downloading) {
+            subscriptions.push(
+                cosmic::iced::time::every(net::PROBE_INTERVAL).map(|_| Message::ProbeNetwork),
+            );
+        }
+
+        subscriptions
+    }
+
+    /// Listens to the keyboard and mouse for what the window is showing.
+    fn input_subscriptions(&self) -> Vec<Subscription<Message>> {
+        let mut subscriptions = vec![event::listen_with(|event, _status, id| match event {
+            event::Event::Window(window::Event::Resized(size)) => {
+                Some(Message::WindowResized(id, size.width))
+            }
+            event::Event::Window(window::Event::Focused) => Some(Message::WindowFocused(true)),
+            event::Event::Window(window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+            _ => None,
+        })];
+
+        // Navigate with keys and gestures while a book is showing, or while one is being
+        // bound in the shortcuts settings.
+        if self.capturing_shortcut.is_some() || self.reader_shown() {
+            subscriptions.push(keyboard::on_key_press(|key, modifiers| {
+                Some(Message::ReaderKey(key, modifiers))
+            }));
+            subscriptions.push(event::listen_with(|event, _status, _id| match event {
+                event::Event::Mouse(mouse::Event::WheelScrolled {
+                    delta: mouse::ScrollDelta::Pixels { x, y },
+                }) if x.abs() > y.abs() => Some(Message::ReaderSwipe(x)),
+                _ => None,
+            }));
+        }
+
+        // Scrolling past either end of a chapter moves on to the next one.
+        if matches!(self.reader, Some(Reader::Epub(_))) && self.reader_shown() {
+            subscriptions.push(event::listen_with(|event, _status, _id| match event {
+                event::Event::Mouse(mouse::Event::WheelScrolled {
+                    delta: mouse::ScrollDelta::Lines { x, y } | mouse::ScrollDelta::Pixels { x, y },
+                }) if y.abs() > x.abs() => Some(Message::ReaderWheel(y)),
+                _ => None,
+            }));
+        }
+
+        // Scrolling up past the top of the feed refreshes it.
+        if self.nav.active_data::<Page>() == Some(&Page::Feed) {
+            subscriptions.push(event::listen_with(|event, _status, _id| match event {
+                event::Event::Mouse(mouse::Event::WheelScrolled {
+                    delta: mouse::ScrollDelta::Lines { y, .. },
+                }) => Some(Message::FeedWheel(y * 20.0)),
+                event::Event::Mouse(mouse::Event::WheelScrolled {
+                    delta: mouse::ScrollDelta::Pixels { y, .. },
+                }) => Some(Message::FeedWheel(y)),
+                _ => None,
+            }));
+        }
+
+        // Bring back the controls when the mouse moves in fullscreen.
+        if self.fullscreen {
+            subscriptions.push(event::listen_with(|event, _status, _id| match event {
+                event::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                    Some(Message::ReaderActivity)
+                }
+                _ => None,
+            }));
+        }
+
+        subscriptions
+    }
+
+    /// Syncs with the WebDAV server, the companion server, paired devices and other
+    /// instances on the local network, for those set up.
+    fn sync_subscriptions(&self, activity: &Activity) -> Vec<Subscription<Message>> {
+        let mut subscriptions = Vec::new();
+
+        if !activity.syncing {
+            return subscriptions;
+        }
+
+        // Sync the catalog with the WebDAV server every so often.
+        if activity.background
+            && self.config.webdav_scheduled
+            && !self.config.webdav_url.trim().is_empty()
+        {
+            subscriptions.push(
+                cosmic::iced::time::every(webdav::SYNC_INTERVAL).map(|_| Message::SyncWebdav),
+            );
+        }
+
+        // Sync with the companion server or the devices paired with this one, every so
+        // often and whenever the server tells of changes.
+        if let Some(host) = &self.host {
+            subscriptions.push(
+                Subscription::run_with_id(
+                    "companion-host",
+                    host::serve(host.clone(), companion::PORT),
+                )
+                .map(Message::HostEvent),
+            );
+        }
+
+        if let (Some(token), true) = (&self.companion_token, self.online) {
+            if !self.config.companion_url.is_empty() {
+                subscriptions.push(
+                    Subscription::run_with_id(
+                        (
+                            "companion-events",
+                            self.config.companion_url.clone(),
+                            token.clone(),
+                        ),
+                        companion::events(self.config.companion_url.clone(), token.clone()),
+                    )
+                    .map(|_| Message::SyncCompanion),
+                );
+            }
+        }
+
+        if activity.background && self.companion_remote().is_some() {
+            subscriptions.push(
+                cosmic::iced::time::every(companion::SYNC_INTERVAL).map(|_| Message::SyncCompanion),
+            );
+        }
+
+        // Share the library on the local network, and look for other instances sharing
+        // theirs.
+        if let Some(lan) = &self.lan {
+            subscriptions.push(
+                Subscription::run_with_id("lan-serve", lan::serve(lan.clone(), lan::PORT))
+                    .map(Message::LanEvent),
+            );
+            subscriptions.push(
+                Subscription::run_with_id("lan-discover", lan::discover(sync::device_name()))
+                    .map(Message::Discovered),
+            );
+        }
+
+        subscriptions
+    }
+
+    /// Shows the app in the tray, the dock and the launcher, and lets the desktop drive
+    /// it.
+    fn desktop_subscriptions(&self) -> Vec<Subscription<Message>> {
+        let mut subscriptions = Vec::new();
+
+        // Show the tray icon, offering to continue the book last read. The tray is shown
+        // again whenever its menu changes.
+        if self.config.tray {
+            let menu = tray::Menu {
+                title: fl!("app-title"),
+                toggle: fl!("tray-toggle"),
+                continue_reading: self
+                    .library
+                    .last_read()
+                    .map(|book| fl!("tray-continue", title = book.title.clone())),
+                quit: fl!("tray-quit"),
+            };
+
+            subscriptions.push(
+                Subscription::run_with_id(("tray", menu.clone()), tray::show(menu))
+                    .map(Message::Tray),
+            );
+        }
+
+        // Offer the books read most recently in the menu of the app's icon in the dock.
+        let recent: Vec<quicklist::Recent> = if self.config.hide_recent_books {
+            Vec::new()
+        } else {
+            self.library
+                .recently_read(quicklist::RECENT_BOOKS)
+                .into_iter()
+                .filter_map(|book| {
+                    Some(quicklist::Recent {
+                        label: fl!("recent-continue", title = book.title.clone()),
+                        path: book.path.clone()?,
+                    })
+                })
+                .collect()
+        };
+
+        subscriptions.push(
+            Subscription::run_with_id(("quicklist", recent.clone()), quicklist::publish(recent))
+                .map(|never| match never {}),
+        );
+
+        if self.config.global_shortcuts {
+            let (summon_trigger, search_trigger) = self.global_triggers.clone();
+            let bindings = global_shortcut::Bindings {
+                summon: fl!("global-summon"),
+                summon_trigger,
+                search: fl!("global-search"),
+                search_trigger,
+            };
+
+            subscriptions.push(
+                Subscription::run_with_id(
+                    ("global-shortcuts", bindings.clone()),
+                    global_shortcut::listen(bindings),
+                )
+                .map(Message::GlobalShortcut),
+            );
+        }
+
+        // Let the desktop's launcher find books and open them.
+        subscriptions.push(
+            Subscription::run_with_id(
+                "search-provider",
+                search_provider::serve(self.search_provider.clone()),
+            )
+            .map(Message::SearchProvider),
+        );
+
+        // Let scripts and the desktop drive the app over D-Bus, reporting what is being
+        // read as of the last update.
+        self.control.set(self.reading_state());
+        subscriptions.push(
+            Subscription::run_with_id("control", control::serve(self.control.clone()))
+                .map(Message::Control),
+        );
+
+        // Take the same commands over a local socket, where there may be no session bus.
+        if self.config.command_socket {
+            subscriptions.push(
+                Subscription::run_with_id("ipc", ipc::serve(self.control.clone()))
+                    .map(Message::Control),
+            );
+        }
+
+        // Let media keys and the panel control reading aloud, keeping what they show
+        // current; this runs after every update.
+        if let Some(state) = self.playback() {
+            self.mpris.set(state);
+            subscriptions.push(
+                Subscription::run_with_id("mpris", mpris::serve(self.mpris.clone()))
+                    .map(Message::Mpris),
+            );
+        }
+
+        // Keep the screen on while reading fullscreen, when nothing else touches it, or
+        // while listening.
+        let listening = self
+            .playback()
+            .is_some_and(|state| state.status == mpris::Status::Playing);
+
+        if (self.fullscreen && self.reader.is_some()) || listening {
+            subscriptions.push(
+                Subscription::run_with_id("inhibit", inhibit::inhibit(fl!("inhibit-reason")))
+                    .map(|never| match never {}),
+            );
+        }
+
+        subscriptions
+    }
+
+    /// Runs the timers of whatever is under way: animations, reading aloud word by word,
+    /// polls, and writing out the session and settings.
+    fn timer_subscriptions(&self, activity: &Activity) -> Vec<Subscription<Message>> {
+        let mut subscriptions = Vec::new();
+
+        // Animation timer for kawaii canvas and theme fades, slowed down to save power,
+        // and stopped while nothing on screen moves.
+        if activity.animating {
+            subscriptions.push(
+                cosmic::iced::time::every(if self.saving_power() {
+                    SAVING_FRAME_INTERVAL
+                } else {
+                    FRAME_INTERVAL
+                })
+                .map(|_| Message::Tick),
+            );
+        }
+
+        // Flash the next word while speed reading.
+        if let Some(Reader::Epub(reader)) = &self.reader {
+            if reader.rsvp.as_ref().is_some_and(|rsvp| rsvp.playing) {
+                let interval = rsvp::interval(rsvp::wpm(self.config.rsvp_wpm));
+                subscriptions.push(cosmic::iced::time::every(interval).map(|_| Message::RsvpTick));
+            }
+        }
+
+        // Hide the controls in fullscreen once the mouse has been still for a while.
+        if self.fullscreen && !self.controls_hidden {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(1))
+                    .map(|_| Message::HideReaderControls),
+            );
+        }
+
+        // Background checks wait while offline or saving power.
+        if activity.background && self.config.update_check != update::Frequency::Never {
+            subscriptions.push(
+                cosmic::iced::time::every(update::POLL_INTERVAL)
+                    .map(|_| Message::CheckForUpdates(false)),
+            );
+        }
+
+        // Fetch followed news feeds again every so often.
+        if activity.background && !self.config.news.is_empty() {
+            subscriptions
+                .push(cosmic::iced::time::every(rss::POLL_INTERVAL).map(|_| Message::RefreshNews));
+        }
+
+        // Check followed accounts for new posts every so often.
+        if cfg!(feature = "atproto")
+            && activity.background
+            && self.config.followed.iter().any(|followed| followed.notify)
+        {
+            subscriptions.push(
+                cosmic::iced::time::every(follow::POLL_INTERVAL).map(|_| Message::PollFollowed),
+            );
+        }
+
+        // Save where the user is now and then. Nothing changes it while the window is
+        // hidden.
+        if activity.shown {
+            subscriptions.push(
+                cosmic::iced::time::every(session::SAVE_INTERVAL).map(|_| Message::SaveSession),
+            );
+        }
+
+        // Write changed settings out once they settle.
+        if self.config_changed.is_some() {
+            subscriptions
+                .push(cosmic::iced::time::every(CONFIG_SAVE_DELAY).map(|_| Message::WriteConfig));
+        }
+
+        // Save the catalog once it settles.
+        if self.library_changed.is_some() {
+            subscriptions.push(
+                cosmic::iced::time::every(LIBRARY_SAVE_DELAY).map(|_| Message::WriteLibrary(false)),
+            );
+        }
+
+        subscriptions
+    }
+
+    /// Starts the work held back while saving power, once it no longer is.
+    fn resume_deferred_work(&mut self) -> Task<cosmic::Action<Message>> {
+        if self.saving_power() {
+            return Task::none();
+        }
+
+        match self.reader.as_mut() {
+            Some(Reader::Pdf(reader)) if !reader.has_thumbnails() => reader.render_thumbnails(),
+            _ => Task::none(),
+        }
+    }
+
+    /// Whether the tray is showing, to bring back a hidden main window from.
+    fn tray_shown(&self) -> bool {
+        self.config.tray && !self.tray_failed
+    }
+
+    /// Hides the window to the tray, or shows and focuses it again.
+    fn set_window_hidden(&mut self, hidden: bool) -> Task<cosmic::Action<Message>> {
+        let Some(id) = self.core.main_window_id() else {
+            return Task::none();
+        };
+
+        self.window_hidden = hidden;
+
+        if hidden {
+            window::change_mode(id, window::Mode::Hidden)
+        } else {
+            Task::batch([
+                window::change_mode(id, window::Mode::Windowed),
+                window::gain_focus(id),
+            ])
+        }
+    }
+
+    /// Marks the configuration as changed, to be written to persistent storage once it
+    /// stops changing, along with whatever else changes meanwhile.
+    fn save_config(&mut self) {
+        self.config_changed = Some(Instant::now());
+    }
+
+    fn reader_open_failed(&mut self) -> Task<cosmic::Action<Message>> {
+        // Don't leave a window open with no book in it.
+        let close = match self.reader_window.filter(|_| self.reader.is_none()) {
+            Some(id) => {
+                self.reader_window = None;
+                window::close(id)
+            }
+            None => Task::none(),
+        };
+
+        let toast = self.push_toast(fl!("reader-open-failed"));
+
+        Task::batch([close, toast])
+    }
+
+    /// Opens a comic in the reader, on the page where reading left off.
+    fn show_comic(
+        &mut self,
+        id: BookId,
+        comic: Comic,
+        fallback_title: String,
+    ) -> Task<cosmic::Action<Message>> {
+        let location = self.resume_location(id);
+        let right_to_left = self.library.get(id).is_some_and(|book| book.right_to_left);
+        let reader = ComicReader::new(id, comic, location.chapter, right_to_left);
+        let load_page = reader.load_page();
+
+        Task::batch([
+            self.show_reader(Reader::Comic(reader), fallback_title),
+            load_page,
+        ])
+    }
+
+    /// Where reading of a book should resume: where it was left, unless a page was asked
+    /// for on the command line.
+    fn resume_location(&mut self, id: BookId) -> Location {
+        let book = self.library.get(id);
+
+        if let Some((path, page)) = &self.start_page {
+            if book.and_then(|book| book.path.as_ref()) == Some(path) {
+                let chapter = *page;
+                self.start_page = None;
+
+                return Location {
+                    chapter,
+                    offset: 0.0,
+                };
+            }
+        }
+
+        book.and_then(|book| book.location).unwrap_or_default()
+    }
+
+    /// Loads a chapter of the open book in the background, to be shown at `offset`.
+    fn load_chapter(&self, index: usize, offset: f32) -> Task<cosmic::Action<Message>> {
+        let Some(Reader::Epub(reader)) = &self.reader else {
+            return Task::none();
+        };
+
+        Task::perform(
+            reader::load_chapter(reader.epub.clone(), index),
+            move |result| cosmic::Action::App(Message::ReaderChapterLoaded(index, offset, result)),
+        )
+    }
+
+    /// Turns the open book's page, or moves to the next or previous chapter at either end
+    /// of one; `None` when there is no further to go.
+    fn turn_page(&mut self, forward: bool) -> Option<Task<cosmic::Action<Message>>> {
+        let reader = match self.reader.as_mut()? {
+            Reader::Epub(reader) => reader,
+            Reader::Pdf(reader) => {
+                let index = reader.turned_page(forward)?;
+                return Some(self.update(Message::PdfPage(index)));
+            }
+            Reader::Comic(reader) => {
+                let task = reader.turn(forward);
+                let finished = self.record_reading_position();
+                return Some(Task::batch([task, finished]));
+            }
+        };
+
+        let index = reader.chapter_index;
+
+        if forward && reader.at_end() {
+            reader
+                .has_next_chapter()
+                .then(|| self.load_chapter(index + 1, 0.0))
+        } else if !forward && reader.at_start() {
+            (index > 0).then(|| self.load_chapter(index - 1, 1.0))
+        } else {
+            Some(reader.turn_page(forward))
+        }
+    }
+
+    /// Plays a sound effect, if it's turned on in the settings.
+    fn play_sound(&self, sound: Sound) -> Task<cosmic::Action<Message>> {
+        let enabled = match sound {
+            Sound::PageTurn => self.config.sound_page_turn,
+            Sound::Imported => self.config.sound_imported,
+            Sound::Achievement => self.config.sound_achievement,
+        };
+
+        if !enabled {
+            return Task::none();
+        }
+
+        Task::future(sound::play(sound)).discard()
+    }
+
+    /// Copies the reader's position and progress into the open book's catalog entry,
+    /// firing the webhooks for finished books if this finishes it.
+    fn record_reading_position(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(reader) = &self.reader else {
+            return Task::none();
+        };
+
+        self.library.note_read_today();
+
+        let Some(book) = self.library.get_mut(reader.book_id()) else {
+            return Task::none();
+        };
+
+        let was_finished = book.is_finished();
+        book.location = Some(reader.location());
+        book.progress = reader.progress();
+        book.location_updated = library::now();
+
+        if was_finished || !book.is_finished() {
+            return Task::none();
+        }
+
+        let book = book.clone();
+
+        Task::batch([
+            self.fire_webhooks(webhook::Event::BookFinished, &book),
+            self.play_sound(Sound::Achievement),
+        ])
+    }
+
+    /// Fires the enabled webhooks set to fire on an event about a book.
+    fn fire_webhooks(&self, event: webhook::Event, book: &Book) -> Task<cosmic::Action<Message>> {
+        Task::batch(
+            self.config
+                .webhooks
+                .iter()
+                .filter(|webhook| webhook.enabled && webhook.events.contains(&event))
+                .map(|webhook| {
+                    let name = webhook.name.clone();
+
+                    match webhook::render(&webhook.template, event, book) {
+                        Ok(payload) => Task::perform(
+                            webhook::fire(webhook.url.clone(), payload),
+                            move |result| {
+                                cosmic::Action::App(Message::WebhookFired(name.clone(), result))
+                            },
+                        ),
+                        Err(why) => {
+                            tracing::error!("failed to fire webhook {name}: {why}");
+                            Task::none()
+                        }
+                    }
+                }),
+        )
+    }
+
+    /// Writes the reading positions of the catalog into the sync folder, if one is set.
+    fn push_positions(&self) -> Task<cosmic::Action<Message>> {
+        let folder = self.config.sync_folder.trim();
+
+        if folder.is_empty() {
+            return Task::none();
+        }
+
+        let device = sync::device_name();
+        let positions = self
+            .library
+            .books()
+            .iter()
+            .filter(|book| !book.is_trashed())
+            .filter_map(|book| Some((sync::key(book), sync::record(book, &device)?)))
+            .collect();
+
+        Task::perform(
+            sync::push(PathBuf::from(folder), device, positions),
+            |result| {
+                cosmic::Action::App(Message::PositionsPushed(
+                    result.map_err(|why| why.to_string()),
+                ))
+            },
+        )
+    }
+
+    /// Reads the positions other devices left in the sync folder, if one is set, once the
+    /// catalog is read.
+    fn pull_positions(&self) -> Task<cosmic::Action<Message>> {
+        let folder = self.config.sync_folder.trim();
+
+        if folder.is_empty() || !self.library_loaded {
+            return Task::none();
+        }
+
+        Task::perform(
+            sync::pull(
+                PathBuf::from(folder),
+                sync::device_name(),
+                self.config.sync_resolution,
+            ),
+            |result| {
+                cosmic::Action::App(Message::PositionsPulled(
+                    result.map_err(|why| why.to_string()),
+                ))
+            },
+        )
+    }
+
+    /// Opens the dialog previewing a post about the book, rendering its cover to attach.
+    fn open_share_dialog(&mut self, id: BookId, text: String) -> Task<cosmic::Action<Message>> {
+        self.share_dialog = Some(ShareDialog {
+            book: id,
+            text: text_editor::Content::with_text(&text),
+            cover: None,
+            cover_handle: None,
+            attach_cover: true,
+            posting: false,
+            error: None,
+        });
+
+        let announcement = self.announce_dialog(fl!("share-title"));
+
+        let Some(path) = self.library.get(id).and_then(|book| book.path.clone()) else {
+            return announcement;
+        };
+
+        let cover = Task::perform(share::cover(path), move |result| {
+            cosmic::Action::App(Message::ShareCoverLoaded(id, result))
+        });
+
+        Task::batch([announcement, cover])
+    }
+
+    /// Keeps the signed in account and fetches its profile.
+    fn sign_in(&mut self, session: atproto::Session) -> Task<cosmic::Action<Message>> {
+        let did = session.did.clone();
+
+        self.account = Some(Account {
+            session,
+            profile: None,
+            avatar: None,
+        });
+
+        let profile = Task::perform(atproto::profile(did), |result| {
+            cosmic::Action::App(Message::ProfileLoaded(result))
+        });
+
+        Task::batch([profile, self.reset_feed(), self.sync_pds()])
+    }
+
+    /// Syncs reading data with the PDS, if signed in and enabled, once the catalog is
+    /// read.
+    fn sync_pds(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(account) = self.account.as_ref().filter(|_| self.config.pds_sync) else {
+            return Task::none();
+        };
+
+        if self.pds_syncing || !self.library_loaded {
+            return Task::none();
+        }
+
+        let books: Vec<Book> = self
+            .library
+            .books()
+            .iter()
+            .filter(|book| !book.is_trashed())
+            .cloned()
+            .collect();
+
+        let task = Task::perform(
+            roaming::sync(
+                account.session.clone(),
+                books,
+                self.config.pds_deleted.clone(),
+                sync::device_name(),
+                self.config.sync_resolution,
+            ),
+            |result| cosmic::Action::App(Message::PdsSynced(result)),
+        );
+
+        self.pds_syncing = true;
+        task
+    }
+
+    /// Takes into the library what a catalog sync brought from other devices.
+    fn apply_synced_catalog(&mut self, outcome: catalog::Outcome) -> Task<cosmic::Action<Message>> {
+        let changes = outcome.changes();
+        let conflicts = outcome.conflicts;
+        let open = self.reader.as_ref().map(Reader::book_id);
+
+        outcome.apply(&mut self.library, open);
+
+        if changes > 0 {
+            self.save_library();
+        }
+
+        let mut toasts = Vec::new();
+
+        if changes > 0 {
+            toasts.push(fl!("sync-catalog-updated", count = changes));
+        }
+
+        if conflicts > 0 {
+            toasts.push(fl!("sync-catalog-conflicts", count = conflicts));
+        }
+
+        let notification = match toasts.first() {
+            Some(summary) => self.notify_in_background(
+                summary.clone(),
+                Some((fl!("notification-show-library"), Message::GoToPage3)),
+            ),
+            None => Task::none(),
+        };
+
+        Task::batch(
+            toasts
+                .into_iter()
+                .map(|toast| self.push_toast(toast))
+                .chain([notification]),
+        )
+    }
+
+    /// Where the catalog is synced with over the companion protocol: the server this
+    /// device is paired with, or else the one it hosts.
+    fn companion_remote(&self) -> Option<companion::Remote> {
+        match (&self.companion_token, &self.host) {
+            (Some(token), _) if !self.config.companion_url.is_empty() => {
+                Some(companion::Remote::Server {
+                    url: self.config.companion_url.clone(),
+                    token: token.clone(),
+                })
+            }
+            (_, Some(host)) => Some(companion::Remote::Local(host.clone())),
+            _ => None,
+        }
+    }
+
+    /// Syncs the catalog with the companion server or host, if there is one, once the
+    /// catalog is read.
+    fn sync_companion(&mut self) -> Task<cosmic::Action<Message>> {
+        if self.companion_syncing || !self.library_loaded {
+            return Task::none();
+        }
+
+        let Some(remote) = self.companion_remote() else {
+            return Task::none();
+        };
+
+        let task = Task::perform(
+            companion::sync(
+                remote,
+                self.library.books().to_vec(),
+                sync::device_name(),
+                self.config.sync_resolution,
+            ),
+            |result| cosmic::Action::App(Message::CompanionSynced(result)),
+        );
+
+        self.companion_syncing = true;
+        task
+    }
+
+    /// Stops devices from pairing with the host, turning away any being confirmed.
+    fn close_pairing(&mut self) {
+        if let Some(host) = &self.host {
+            host.close_pairing();
+        }
+
+        self.pairing_request = None;
+        self.refresh_pairing();
+    }
+
+    /// Shows the host's current pairing link as a QR code, while devices can pair.
+    fn refresh_pairing(&mut self) {
+        self.host_qr = self.host.as_ref().and_then(|host| {
+            if !host.pairing_open() {
+                return None;
+            }
+
+            let address = host::address(companion::PORT)?;
+            qr_code::Data::new(companion::link(&address, &host.code()?)).ok()
+        });
+    }
+
+    /// Syncs the catalog with the WebDAV server, if one is set and its password and the
+    /// catalog were read.
+    fn sync_webdav(&mut self) -> Task<cosmic::Action<Message>> {
+        if self.config.webdav_url.trim().is_empty() || self.webdav_syncing || !self.library_loaded {
+            return Task::none();
+        }
+
+        let Some(password) = self.webdav_password.clone() else {
+            return Task::none();
+        };
+
+        let server = webdav::Server {
+            url: self.config.webdav_url.trim().to_string(),
+            user: self.config.webdav_user.clone(),
+            password,
+        };
+
+        let task = Task::perform(
+            webdav::sync(
+                server,
+                self.library.books().to_vec(),
+                sync::device_name(),
+                self.config.sync_resolution,
+            ),
+            |result| cosmic::Action::App(Message::WebdavSynced(result)),
+        );
+
+        self.webdav_syncing = true;
+        task
+    }
+
+    /// Fetches a page of the open catalog, adding its entries to those shown if
+    /// `append` is set, or else showing it in their place.
+    fn load_catalog(&mut self, url: String, append: bool) -> Task<cosmic::Action<Message>> {
+        let Some(source) = self
+            .catalog
+            .source
+            .and_then(|id| self.config.sources.iter().find(|source| source.id == id))
+        else {
+            return Task::none();
+        };
+
+        self.catalog.error = None;
+
+        if !append {
+            self.catalog.feed = None;
+        }
+
+        let (task, handle) = Task::perform(
+            sources::fetch(source.kind, url, self.catalog.credentials.clone()),
+            move |result| cosmic::Action::App(Message::CatalogLoaded(append, result)),
+        )
+        .abortable();
+
+        self.catalog.task = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Fetches a page of the site open on the discovery page, adding its entries to
+    /// those shown if `append` is set, or else showing it in their place.
+    fn load_discover(&mut self, url: String, append: bool) -> Task<cosmic::Action<Message>> {
+        self.discover.error = None;
+
+        if !append {
+            self.discover.feed = None;
+        }
+
+        let (task, handle) = Task::perform(opds::fetch_feed(url, None), move |result| {
+            cosmic::Action::App(Message::DiscoverLoaded(append, result))
+        })
+        .abortable();
+
+        self.discover.task = Some(handle.abort_on_drop());
+        task
+    }
 
-impl AppModel {
-    /// The about page for this app.
-    pub fn about(&self) -> Element<Message> {
-        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+    /// Queues the download of a book into the library, saved under `name`, signing in
+    /// with `auth` if the server asks.
+    fn import_book(
+        &mut self,
+        url: String,
+        name: String,
+        auth: Option<Credentials>,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(dir) = import::dir() else {
+            tracing::warn!("no data folder to import {url} into");
+            return Task::none();
+        };
 
-        let icon = widget::svg(widget::svg::Handle::from_memory(APP_ICON));
+        let destination = download::destination(&dir, &name);
+        self.downloads
+            .push(url, destination, download::Purpose::Import, auth);
 
-        let title = widget::text::title2(fl!("app-title"));
-        let author = widget::button::link("nandi.weird.one").on_press(Message::OpenAuthorUrl);
+        let toast = self.push_toast(fl!("import-queued"));
 
-        let hash = env!("VERGEN_GIT_SHA");
-        let short_hash: String = hash.chars().take(7).collect();
-        let date = env!("VERGEN_GIT_COMMIT_DATE");
+        Task::batch([toast, self.start_downloads()])
+    }
 
-        let link = widget::button::link(REPOSITORY)
-            .on_press(Message::OpenRepositoryUrl)
-            .padding(0);
+    /// Queues the import of a book another instance shares, signing in with `auth`.
+    fn import_from_peer(
+        &mut self,
+        url: &str,
+        book: &lan::SharedBook,
+        auth: Option<Credentials>,
+    ) -> Task<cosmic::Action<Message>> {
+        let Some(name) = book.file_name() else {
+            tracing::warn!("refused {:?}.{:?} from {url}", book.title, book.extension);
+            return self.push_toast(fl!("nearby-refused", title = book.title.as_str()));
+        };
 
-        widget::column()
-            .push(icon)
-            .push(title)
-            .push(author)
-            .push(link)
-            .push(
-                widget::button::link(fl!(
-                    "git-description",
-                    hash = short_hash.as_str(),
-                    date = date
-                ))
-                .on_press(Message::LaunchUrl(format!("{REPOSITORY}/commits/{hash}")))
-                .padding(0),
-            )
-            .align_x(Alignment::Center)
-            .spacing(space_xxs)
-            .into()
+        self.import_book(lan::file_url(url, book.id), name, auth)
     }
 
-    /// The settings page for this app.
-    pub fn settings(&self) -> Element<Message> {
-        widget::column()
-            .push(widget::text::title2("Settings"))
-            .push(widget::vertical_space().height(20))
-            .push(widget::text("Username:"))
-            .push(
-                widget::text_input("Enter your username", &self.config.username)
-                    .on_input(Message::UpdateUsername)
-                    .width(Length::Fill),
-            )
-            .push(widget::vertical_space().height(20))
-            .push(
-                widget::button::standard("Save Settings")
-                    .on_press(Message::SaveSettings)
-                    .width(Length::Fill),
-            )
-            .spacing(10)
-            .padding(20)
-            .align_x(Alignment::Center)
-            .into()
+    /// Starts queued downloads while there is room for more to run.
+    fn start_downloads(&mut self) -> Task<cosmic::Action<Message>> {
+        let tasks: Vec<_> = self
+            .downloads
+            .start_next()
+            .into_iter()
+            .map(|item| {
+                let id = item.id;
+                let (task, handle) = Task::run(
+                    download::run(item, self.download_throttle.clone()),
+                    move |progress| Message::DownloadProgress(id, progress),
+                )
+                .abortable();
+
+                self.download_tasks.insert(id, handle.abort_on_drop());
+                task.map(cosmic::Action::App)
+            })
+            .collect();
+
+        Task::batch(tasks)
+    }
+
+    /// Notes the PDS records of deleted books, to be deleted with the next sync.
+    fn forget_pds_records(&mut self, books: &[Book]) {
+        if !self.config.pds_sync || books.is_empty() {
+            return;
+        }
+
+        self.config
+            .pds_deleted
+            .extend(books.iter().map(roaming::rkey));
+        self.save_config();
+    }
+
+    /// The feed chosen on the feed page, or the app author's posts in place of the
+    /// timeline until signed in.
+    fn chosen_feed(&self) -> atproto::Feed {
+        match atproto::Feed::parse(&self.config.feed) {
+            atproto::Feed::Timeline if self.account.is_none() => {
+                atproto::Feed::Author(AUTHOR_DID.to_string())
+            }
+            feed => feed,
+        }
+    }
+
+    /// Forgets the posts fetched, fetching the feed afresh if it is showing.
+    fn reset_feed(&mut self) -> Task<cosmic::Action<Message>> {
+        self.feed.posts.clear();
+        self.feed.cursor = None;
+        self.feed.loaded = false;
+        self.feed.task = None;
+        self.feed.error = None;
+
+        if self.nav.active_data::<Page>() == Some(&Page::Feed) {
+            self.update(Message::LoadFeed(true))
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Persists which news articles have been read, logging any failure.
+    fn save_news(&self) {
+        if let Err(why) = self.news.read.save() {
+            tracing::error!("failed to save read news: {why}");
+        }
+    }
+
+    /// Persists the wishlist, logging any failure.
+    fn save_wishlist(&self) {
+        if let Err(why) = self.wishlist.save() {
+            tracing::error!("failed to save wishlist: {why}");
+        }
+    }
+
+    /// The reading streak and the progress through each book, laid out for printing.
+    fn reading_report(&self) -> print::Document {
+        let books: Vec<&Book> = self
+            .library
+            .books()
+            .iter()
+            .filter(|book| !book.is_trashed())
+            .collect();
+
+        let (finished, reading): (Vec<&Book>, Vec<&Book>) = books
+            .into_iter()
+            .filter(|book| book.progress > 0.0)
+            .partition(|book| book.is_finished());
+
+        let mut blocks = vec![
+            print::Block::Title(fl!("report-title")),
+            print::Block::Text(fl!("report-streak", streak = self.library.streak())),
+            print::Block::Text(fl!("report-finished", count = finished.len())),
+        ];
+
+        let entry = |book: &Book| {
+            let progress = (book.progress * 100.0).round() as u32;
+
+            if book.author.is_empty() {
+                fl!(
+                    "report-book",
+                    title = book.title.clone(),
+                    progress = progress
+                )
+            } else {
+                fl!(
+                    "report-book-by",
+                    title = book.title.clone(),
+                    author = book.author.clone(),
+                    progress = progress
+                )
+            }
+        };
+
+        for (heading, books) in [
+            (fl!("report-reading"), reading),
+            (fl!("report-finished-books"), finished),
+        ] {
+            if !books.is_empty() {
+                blocks.push(print::Block::Heading(heading));
+                blocks.extend(
+                    books
+                        .into_iter()
+                        .map(|book| print::Block::Text(entry(book))),
+                );
+            }
+        }
+
+        print::Document {
+            title: fl!("report-title"),
+            blocks,
+        }
+    }
+
+    /// Marks the catalog as changed, to be saved once it stops changing, along with
+    /// whatever else changes meanwhile. Nothing is saved until the catalog was read.
+    fn save_library(&mut self) {
+        if self.library_loaded {
+            self.library_changed = Some(Instant::now());
+        }
+    }
+
+    /// Saves the catalog off the UI thread, then shares and indexes the books as saved.
+    fn write_library(&self) -> Task<cosmic::Action<Message>> {
+        let save = LIBRARY_SAVES.fetch_add(1, Ordering::Relaxed) + 1;
+        let library = self.library.clone();
+        let lan = self.lan.clone();
+        let search_provider = self.search_provider.clone();
+
+        Task::future(async move {
+            let written = tokio::task::spawn_blocking(move || {
+                write_library_now(&library, save);
+
+                if let Some(lan) = lan {
+                    lan.set_books(library.books());
+                }
+
+                search_provider.set_books(library.books());
+            })
+            .await;
+
+            if let Err(why) = written {
+                tracing::error!("failed to save library: {why}");
+            }
+        })
+        .discard()
+    }
+
+    /// The menus of the header bar.
+    fn header_menus(&self) -> Vec<Element<Message>> {
+        let mut file = vec![
+            menu::Item::Button(fl!("import-files"), None, MenuAction::ImportFiles),
+            menu::Item::Button(fl!("import-url"), None, MenuAction::ImportUrl),
+            menu::Item::Button(fl!("export-library"), None, MenuAction::Export),
+            menu::Item::Button(fl!("verify-library"), None, MenuAction::Verify),
+        ];
+
+        if cfg!(feature = "pdf") {
+            file.push(menu::Item::Button(
+                fl!("print-report"),
+                None,
+                MenuAction::PrintReport,
+            ));
+        }
+
+        let view = vec![
+            menu::Item::Button(fl!("about"), None, MenuAction::About),
+            menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
+            menu::Item::Button(fl!("trash"), None, MenuAction::Trash),
+            menu::Item::Button(fl!("shortcuts"), None, MenuAction::Shortcuts),
+            menu::Item::Button(fl!("sources"), None, MenuAction::Sources),
+        ];
+
+        // A compact header folds both menus into one behind a single button.
+        let menu_bar = if self.config.compact_header {
+            let items = file
+                .into_iter()
+                .chain([menu::Item::Divider])
+                .chain(view)
+                .collect();
+
+            menu::bar(vec![menu::Tree::with_children(
+                icon::from_name("open-menu-symbolic")
+                    .size(16)
+                    .apply(widget::button::custom)
+                    .class(cosmic::theme::Button::MenuRoot)
+                    .padding(8)
+                    .apply(Element::from),
+                menu::items(&self.key_binds, items),
+            )])
+        } else {
+            menu::bar(vec![
+                menu::Tree::with_children(
+                    menu::root(fl!("file")).apply(Element::from),
+                    menu::items(&self.key_binds, file),
+                ),
+                menu::Tree::with_children(
+                    menu::root(fl!("view")).apply(Element::from),
+                    menu::items(&self.key_binds, view),
+                ),
+            ])
+        };
+
+        vec![menu_bar.into()]
+    }
+
+    /// The search field, or the page's actions and buttons for downloads and search.
+    fn header_actions(&self) -> Vec<Element<Message>> {
+        if self.search_expanded {
+            let search_input =
+                widget::text_input::search_input(fl!("search-placeholder"), &self.search_query)
+                    .on_input(Message::SearchChanged)
+                    .on_clear(Message::ClearSearch)
+                    .id(self.search_input_id.clone().into())
+                    .width(Length::Fixed(200.0));
+
+            vec![search_input.into()]
+        } else {
+            // Show just the search icon
+            let search_icon = icon::from_name("system-search-symbolic")
+                .size(16)
+                .apply(widget::button::custom)
+                .on_press(Message::ExpandSearch)
+                .padding(8);
+
+            let downloads_icon = icon::from_name("folder-download-symbolic")
+                .size(16)
+                .apply(widget::button::custom)
+                .on_press(Message::ToggleContextPage(ContextPage::Downloads))
+                .padding(8);
+
+            let mut elements = self.page_actions();
+            elements.push(downloads_icon.into());
+            elements.push(search_icon.into());
+            bidi::ordered(elements)
+        }
+    }
+
+    /// Shows the interface in the language set. What's built from scratch each frame
+    /// follows by itself; the nav bar's labels, kept since the start, are set again,
+    /// and the library is sorted again as the language sorts.
+    fn apply_language(&mut self) {
+        i18n::select(&self.config.language, &self.config.language_fallback);
+        self.collation = Collation::new();
+        self.library.recollate();
+
+        self.language_names = self
+            .languages
+            .iter()
+            .map(|language| {
+                if language.is_empty() {
+                    fl!("language-system")
+                } else {
+                    language.clone()
+                }
+            })
+            .collect();
+
+        let pages: Vec<_> = self.nav.iter().collect();
+
+        for id in pages {
+            if let Some(label) = self.nav.data::<Page>(id).and_then(|page| page.label()) {
+                self.nav.text_set(id, label);
+            }
+        }
     }
 
     /// Updates the header and window titles.
@@ -595,20 +11092,200 @@ impl AppModel {
             window_title.push_str(page);
         }
 
+        // The header shows which page this is, unless it's kept compact.
+        let header_title = match self.nav.text(self.nav.active()) {
+            Some(page) if !self.config.compact_header => page.to_string(),
+            _ => String::new(),
+        };
+
+        self.set_header_title(header_title);
+
         if let Some(id) = self.core.main_window_id() {
-            self.set_window_title(window_title, id)
+            self.set_window_title(bidi::plain(&window_title), id)
         } else {
             Task::none()
         }
     }
 }
 
+/// Focuses a text field by its ID, selecting what's in it so it can be typed over.
+fn focus_field(id: cosmic::iced::widget::text_input::Id) -> Task<cosmic::Action<Message>> {
+    Task::batch([
+        cosmic::iced::widget::text_input::focus(id.clone()),
+        cosmic::iced::widget::text_input::select_all(id),
+    ])
+}
+
+/// Reads the catalog off the UI thread with `load`, once the daemon isn't changing it,
+/// and holds it so that the daemon doesn't while the app runs.
+fn load_library(load: fn() -> Result<Library, String>) -> Task<cosmic::Action<Message>> {
+    Task::perform(
+        async {
+            match tokio::task::spawn_blocking(library::Lock::hold).await {
+                Ok(Ok(())) => (),
+                Ok(Err(why)) => tracing::warn!("failed to lock the library: {why}"),
+                Err(why) => tracing::warn!("failed to lock the library: {why}"),
+            }
+
+            worker::run(Priority::Visible, load).await?
+        },
+        |result| cosmic::Action::App(Message::LibraryLoaded(result)),
+    )
+}
+
+/// Writes the catalog to disk as it was at `save`, unless a later save was written
+/// already.
+fn write_library_now(library: &Library, save: u64) {
+    let mut written = LIBRARY_WRITTEN
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if *written > save {
+        return;
+    }
+
+    if let Err(why) = library.save() {
+        tracing::error!("failed to save library: {why}");
+    }
+
+    *written = save;
+}
+
+/// Writes the configuration to persistent storage, off the UI thread.
+async fn write_config(config: Config) {
+    let written = tokio::task::spawn_blocking(move || write_config_now(&config)).await;
+
+    if let Err(why) = written {
+        tracing::error!("failed to save config: {why}");
+    }
+}
+
+/// Writes the configuration to persistent storage.
+fn write_config_now(config: &Config) {
+    if let Ok(config_context) = cosmic_config::Config::new(AppModel::APP_ID, Config::VERSION) {
+        if let Err(why) = config.write_entry(&config_context) {
+            tracing::error!("failed to save config: {why:?}");
+        }
+    }
+}
+
+/// Keeps the WebDAV password in the keyring, or forgets it once cleared.
+fn store_webdav_password(password: String) -> Task<cosmic::Action<Message>> {
+    Task::future(async move {
+        let key = webdav::PASSWORD_KEY.to_string();
+
+        let result = if password.is_empty() {
+            secrets::forget(key).await
+        } else {
+            secrets::store(key, password).await
+        };
+
+        if let Err(why) = result {
+            tracing::error!("failed to store the WebDAV password: {why}");
+        }
+    })
+    .discard()
+}
+
+/// What the library is grouped by letter of when sorted in `sort`, if anything.
+fn grouped_by(sort: library::Sort, book: &Book) -> Option<&str> {
+    match sort {
+        library::Sort::Title => Some(&book.title),
+        library::Sort::Author => Some(&book.author),
+        library::Sort::Added | library::Sort::Progress => None,
+    }
+}
+
+/// Stands in for a book's row on the library page just out of view.
+fn book_placeholder<'a>() -> Element<'a, Message> {
+    widget::container(widget::Space::new(Length::Fill, Length::Fill))
+        .class(theme::Container::Card)
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fixed(LIBRARY_ROW_HEIGHT))
+        .padding([4, 0])
+        .into()
+}
+
+/// Title of a chapter in a table of contents, if it's listed there.
+fn toc_title(entries: &[toc::Entry], chapter: usize) -> Option<&str> {
+    entries.iter().find_map(|entry| {
+        if entry.target == Some(chapter) {
+            Some(entry.title.as_str())
+        } else {
+            toc_title(&entry.children, chapter)
+        }
+    })
+}
+
 /// The page to display in the application.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Page {
     Page1,
     Page2,
     Page3,
+    Feed,
+    Catalogs,
+    Discover,
+    News,
+    Nearby,
+    Reader,
+}
+
+impl Page {
+    /// Whether the page's feature is built in.
+    fn built(self) -> bool {
+        match self {
+            Page::Feed => cfg!(feature = "atproto"),
+            Page::Discover => cfg!(feature = "opds"),
+            _ => true,
+        }
+    }
+
+    /// The page's label in the nav bar, or `None` for the reader, labelled by the book
+    /// open in it.
+    fn label(self) -> Option<String> {
+        Some(match self {
+            Page::Page1 => fl!("page-id", num = 1),
+            Page::Page2 => fl!("page-id", num = 2),
+            Page::Page3 => fl!("page-id", num = 3),
+            Page::Feed => fl!("feed"),
+            Page::Catalogs => fl!("catalogs"),
+            Page::Discover => fl!("discover"),
+            Page::News => fl!("news"),
+            Page::Nearby => fl!("nearby"),
+            Page::Reader => return None,
+        })
+    }
+}
+
+/// What libby was started with.
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    /// When libby started, before parsing its arguments.
+    pub started: Instant,
+    /// Books to open, such as those opened with libby from the file manager.
+    pub files: Vec<PathBuf>,
+    /// Index of the page to open the first book at, or of the chapter for EPUBs.
+    pub page: Option<usize>,
+    /// Name of the catalog to browse.
+    pub library: Option<String>,
+    /// Whether to start fullscreen.
+    pub fullscreen: bool,
+}
+
+impl cosmic::app::CosmicFlags for Flags {
+    type SubCommand = String;
+    type Args = Vec<String>;
+}
+
+/// What a file or folder is chosen for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTarget {
+    ImportBooks,
+    ExportPath,
+    SyncFolder,
+    Certificates,
 }
 
 /// The context page to display in the context drawer.
@@ -617,12 +11294,33 @@ pub enum ContextPage {
     #[default]
     About,
     Settings,
+    Trash,
+    Verify,
+    Bookmarks,
+    Annotations,
+    Typography,
+    Shortcuts,
+    Search,
+    Following,
+    Downloads,
+    Sources,
+    Webhooks,
+    Wishlist,
+    BookDetails(BookId),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
     Settings,
+    Trash,
+    ImportFiles,
+    ImportUrl,
+    Export,
+    Verify,
+    PrintReport,
+    Shortcuts,
+    Sources,
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -632,32 +11330,179 @@ impl menu::action::MenuAction for MenuAction {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
             MenuAction::Settings => Message::ToggleContextPage(ContextPage::Settings),
+            MenuAction::Trash => Message::ToggleContextPage(ContextPage::Trash),
+            MenuAction::ImportFiles => Message::ChooseFile(FileTarget::ImportBooks),
+            MenuAction::ImportUrl => Message::OpenImportDialog,
+            MenuAction::Export => Message::OpenExportDialog,
+            MenuAction::Verify => Message::VerifyLibrary(false),
+            MenuAction::PrintReport => Message::PrintReport,
+            MenuAction::Shortcuts => Message::ToggleContextPage(ContextPage::Shortcuts),
+            MenuAction::Sources => Message::ToggleContextPage(ContextPage::Sources),
         }
     }
 }
 
+fn rgb([r, g, b]: palette::Rgb) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+/// Formats a number of bytes with the largest unit it reaches.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Formats seconds since the Unix epoch as a local date and time.
+fn format_timestamp(secs: u64) -> String {
+    i64::try_from(secs)
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Localized name of an export field.
+fn field_label(field: Field) -> String {
+    match field {
+        Field::Title => fl!("field-title"),
+        Field::Author => fl!("field-author"),
+        Field::Description => fl!("field-description"),
+        Field::Tags => fl!("field-tags"),
+        Field::Series => fl!("field-series"),
+        Field::Collection => fl!("field-collection"),
+        Field::Progress => fl!("field-progress"),
+        Field::Rating => fl!("field-rating"),
+        Field::Path => fl!("field-path"),
+    }
+}
+
+/// Colors of the circles drifting behind the hearts, taken in turn.
+const KAWAII_CIRCLE_COLORS: [Color; 4] = [
+    Color::from_rgba(1.0, 0.7, 0.8, 0.4), // Pink
+    Color::from_rgba(0.8, 0.9, 1.0, 0.4), // Light blue
+    Color::from_rgba(1.0, 1.0, 0.8, 0.4), // Light yellow
+    Color::from_rgba(0.9, 0.8, 1.0, 0.4), // Light purple
+];
+
+const KAWAII_HEART_COLOR: Color = Color::from_rgba(1.0, 0.4, 0.6, 0.7);
+const KAWAII_STAR_COLOR: Color = Color::from_rgba(1.0, 1.0, 0.6, 0.8);
+
+/// Points of a 4-pointed sparkle star of unit size.
+const KAWAII_STAR: [(f32, f32); 8] = [
+    (0.0, -1.0),
+    (0.3, -0.3),
+    (1.0, 0.0),
+    (0.3, 0.3),
+    (0.0, 1.0),
+    (-0.3, 0.3),
+    (-1.0, 0.0),
+    (-0.3, -0.3),
+];
+
+/// Distance from the mouse within which shapes move out of its way.
+const KAWAII_AVOIDANCE_RADIUS: f32 = 20.0;
+const KAWAII_REPULSION_STRENGTH: f32 = 15.0;
+
 /// Kawaii animated canvas with floating hearts and sparkles
 pub struct KawaiiCanvas {
     animation_time: Instant,
+    /// How dark the app is, from 0.0 to 1.0, to dim the pastels against.
+    darkness: f32,
 }
 
 impl KawaiiCanvas {
-    pub fn new(animation_time: Instant) -> Self {
-        Self { animation_time }
+    pub fn new(animation_time: Instant, darkness: f32) -> Self {
+        Self {
+            animation_time,
+            darkness,
+        }
+    }
+
+    /// The color dimmed to sit on the app's background.
+    fn dimmed(&self, color: Color) -> Color {
+        let scale = 1.0 - 0.35 * self.darkness;
+        Color::from_rgba(color.r * scale, color.g * scale, color.b * scale, color.a)
+    }
+}
+
+/// The shapes of the canvas at unit size, built once for as long as the canvas is shown
+/// and only moved, scaled and turned into place each frame.
+pub struct KawaiiShapes {
+    circle: Path,
+    heart: Path,
+    star: Path,
+}
+
+impl Default for KawaiiShapes {
+    fn default() -> Self {
+        let heart = Path::new(|path| {
+            path.move_to(Point::new(0.0, 0.25));
+            path.bezier_curve_to(
+                Point::new(0.5, -0.5),
+                Point::new(1.0, 0.0),
+                Point::new(0.0, 1.0),
+            );
+            path.bezier_curve_to(
+                Point::new(-1.0, 0.0),
+                Point::new(-0.5, -0.5),
+                Point::new(0.0, 0.25),
+            );
+            path.close();
+        });
+
+        let star = Path::new(|path| {
+            let (x, y) = KAWAII_STAR[0];
+            path.move_to(Point::new(x, y));
+
+            for &(x, y) in &KAWAII_STAR[1..] {
+                path.line_to(Point::new(x, y));
+            }
+            path.close();
+        });
+
+        Self {
+            circle: Path::circle(Point::ORIGIN, 1.0),
+            heart,
+            star,
+        }
     }
 }
 
 impl canvas::Program<Message, cosmic::Theme, cosmic::Renderer> for KawaiiCanvas {
-    type State = ();
+    type State = KawaiiShapes;
 
     fn draw(
         &self,
-        _state: &Self::State,
+        shapes: &Self::State,
         renderer: &cosmic::Renderer,
         _theme: &cosmic::Theme,
         bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
+        #[cfg(feature = "count-allocations")]
+        let allocations = alloc_count::allocations();
+        #[cfg(feature = "profiling")]
+        let _span = tracing::info_span!("draw").entered();
+        #[cfg(feature = "profiling")]
+        let started = Instant::now();
+
         let mut frame = Frame::new(renderer, bounds.size());
         let center = frame.center();
         let time = self.animation_time.elapsed().as_secs_f32();
@@ -672,8 +11517,10 @@ impl canvas::Program<Message, cosmic::Theme, cosmic::Renderer> for KawaiiCanvas
         } else {
             Point::new(-1.0, -1.0)
         };
-        let avoidance_radius = 20.0;
-        let repulsion_strength = 15.0;
+
+        let circle_colors = KAWAII_CIRCLE_COLORS.map(|color| self.dimmed(color));
+        let heart_color = self.dimmed(KAWAII_HEART_COLOR);
+        let star_color = self.dimmed(KAWAII_STAR_COLOR);
 
         // Kawaii background gradient circles with smooth loops
         for i in 0..5 {
@@ -681,27 +11528,18 @@ impl canvas::Program<Message, cosmic::Theme, cosmic::Renderer> for KawaiiCanvas
             let angle = loop_time * 0.3 + phase;
             let radius = 30.0 + (loop_time * 1.5 + phase).sin() * 8.0;
             let orbit_radius = 60.0 + i as f32 * 25.0;
-            let mut x = center.x + angle.cos() * orbit_radius;
-            let mut y = center.y + angle.sin() * orbit_radius * 0.7; // Slightly elliptical
-
-            // Mouse avoidance
-            let dx = x - mouse_pos.x;
-            let dy = y - mouse_pos.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-            if distance < avoidance_radius {
-                let repel_factor = (1.0 - distance / avoidance_radius) * repulsion_strength;
-                x += dx / distance * repel_factor;
-                y += dy / distance * repel_factor;
-            }
-
-            let circle = Path::circle(Point::new(x, y), radius);
-            let color = match i % 4 {
-                0 => Color::from_rgba(1.0, 0.7, 0.8, 0.4), // Pink
-                1 => Color::from_rgba(0.8, 0.9, 1.0, 0.4), // Light blue
-                2 => Color::from_rgba(1.0, 1.0, 0.8, 0.4), // Light yellow
-                _ => Color::from_rgba(0.9, 0.8, 1.0, 0.4), // Light purple
-            };
-            frame.fill(&circle, color);
+            let x = center.x + angle.cos() * orbit_radius;
+            let y = center.y + angle.sin() * orbit_radius * 0.7; // Slightly elliptical
+
+            let at = avoid_mouse(Point::new(x, y), mouse_pos);
+            place(
+                &mut frame,
+                &shapes.circle,
+                at,
+                radius,
+                0.0,
+                circle_colors[i % 4],
+            );
         }
 
         // Floating hearts with smooth circular motion
@@ -709,37 +11547,13 @@ impl canvas::Program<Message, cosmic::Theme, cosmic::Renderer> for KawaiiCanvas
             let phase = i as f32 * 0.785; // 2π/8 for even distribution
             let t = loop_time * 0.8 + phase;
             let orbit_radius = 90.0 + (i % 3) as f32 * 20.0;
-            let mut x = center.x + t.cos() * orbit_radius;
-            let mut y = center.y + t.sin() * orbit_radius * 0.6 + (t * 2.0).sin() * 15.0;
-
-            // Mouse avoidance
-            let dx = x - mouse_pos.x;
-            let dy = y - mouse_pos.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-            if distance < avoidance_radius {
-                let repel_factor = (1.0 - distance / avoidance_radius) * repulsion_strength;
-                x += dx / distance * repel_factor;
-                y += dy / distance * repel_factor;
-            }
+            let x = center.x + t.cos() * orbit_radius;
+            let y = center.y + t.sin() * orbit_radius * 0.6 + (t * 2.0).sin() * 15.0;
 
             // Pulsing heart size
             let heart_size = 8.0 + (t * 2.5).sin() * 3.0;
-            let heart = Path::new(|path| {
-                path.move_to(Point::new(x, y + heart_size * 0.25));
-                path.bezier_curve_to(
-                    Point::new(x + heart_size * 0.5, y - heart_size * 0.5),
-                    Point::new(x + heart_size, y),
-                    Point::new(x, y + heart_size),
-                );
-                path.bezier_curve_to(
-                    Point::new(x - heart_size, y),
-                    Point::new(x - heart_size * 0.5, y - heart_size * 0.5),
-                    Point::new(x, y + heart_size * 0.25),
-                );
-                path.close();
-            });
-
-            frame.fill(&heart, Color::from_rgba(1.0, 0.4, 0.6, 0.7));
+            let at = avoid_mouse(Point::new(x, y), mouse_pos);
+            place(&mut frame, &shapes.heart, at, heart_size, 0.0, heart_color);
         }
 
         // Sparkle stars with smooth rotation
@@ -747,54 +11561,77 @@ impl canvas::Program<Message, cosmic::Theme, cosmic::Renderer> for KawaiiCanvas
             let phase = i as f32 * 0.524; // 2π/12 for even distribution
             let t = loop_time * 1.2 + phase;
             let orbit_radius = 120.0 + (i % 4) as f32 * 15.0;
-            let mut x = center.x + t.cos() * orbit_radius;
-            let mut y = center.y + t.sin() * orbit_radius * 0.8;
+            let x = center.x + t.cos() * orbit_radius;
+            let y = center.y + t.sin() * orbit_radius * 0.8;
             let size = 4.0 + (t * 3.0).sin().abs() * 2.0;
 
-            // Mouse avoidance
-            let dx = x - mouse_pos.x;
-            let dy = y - mouse_pos.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-            if distance < avoidance_radius {
-                let repel_factor = (1.0 - distance / avoidance_radius) * repulsion_strength;
-                x += dx / distance * repel_factor;
-                y += dy / distance * repel_factor;
-            }
-
-            // 4-pointed star with smooth rotation
-            let star_rotation = t * 0.5;
-            let star = Path::new(|path| {
-                let cos_r = star_rotation.cos();
-                let sin_r = star_rotation.sin();
-
-                // Rotate the star points
-                let points = [
-                    (0.0, -size),
-                    (size * 0.3, -size * 0.3),
-                    (size, 0.0),
-                    (size * 0.3, size * 0.3),
-                    (0.0, size),
-                    (-size * 0.3, size * 0.3),
-                    (-size, 0.0),
-                    (-size * 0.3, -size * 0.3),
-                ];
-
-                let first_point = points[0];
-                let rotated_x = first_point.0 * cos_r - first_point.1 * sin_r;
-                let rotated_y = first_point.0 * sin_r + first_point.1 * cos_r;
-                path.move_to(Point::new(x + rotated_x, y + rotated_y));
-
-                for &point in &points[1..] {
-                    let rot_x = point.0 * cos_r - point.1 * sin_r;
-                    let rot_y = point.0 * sin_r + point.1 * cos_r;
-                    path.line_to(Point::new(x + rot_x, y + rot_y));
-                }
-                path.close();
-            });
-
-            frame.fill(&star, Color::from_rgba(1.0, 1.0, 0.6, 0.8));
+            let at = avoid_mouse(Point::new(x, y), mouse_pos);
+            place(&mut frame, &shapes.star, at, size, t * 0.5, star_color);
         }
 
-        vec![frame.into_geometry()]
+        let geometry = vec![frame.into_geometry()];
+
+        #[cfg(feature = "count-allocations")]
+        tracing::trace!(
+            "drew the canvas with {} allocations",
+            alloc_count::allocations() - allocations
+        );
+        #[cfg(feature = "profiling")]
+        profiling::record(profiling::Phase::Draw, started.elapsed());
+
+        geometry
+    }
+}
+
+/// Shows how long the last frames took and how many messages are handled, over the
+/// top right corner of the page.
+#[cfg(feature = "profiling")]
+fn profiling_overlay(page: Element<Message>) -> Element<Message> {
+    let summary = profiling::summary();
+
+    let figures = widget::text::caption(fl!(
+        "profiling-overlay",
+        view = format!("{:.1}", summary.view),
+        draw = format!("{:.1}", summary.draw),
+        messages = summary.messages
+    ));
+
+    Stack::new()
+        .push(page)
+        .push(
+            widget::container(figures)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(8)
+                .align_x(Horizontal::Right)
+                .align_y(Vertical::Top),
+        )
+        .into()
+}
+
+/// Where a shape at `at` moves to, out of the way of the mouse.
+fn avoid_mouse(at: Point, mouse: Point) -> Point {
+    let dx = at.x - mouse.x;
+    let dy = at.y - mouse.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    if distance >= KAWAII_AVOIDANCE_RADIUS || distance == 0.0 {
+        return at;
     }
+
+    let repel_factor = (1.0 - distance / KAWAII_AVOIDANCE_RADIUS) * KAWAII_REPULSION_STRENGTH;
+    Point::new(
+        at.x + dx / distance * repel_factor,
+        at.y + dy / distance * repel_factor,
+    )
+}
+
+/// Fills a unit shape moved to `at`, scaled to `size` and turned by `rotation` radians.
+fn place(frame: &mut Frame, shape: &Path, at: Point, size: f32, rotation: f32, color: Color) {
+    frame.with_save(|frame| {
+        frame.translate(at - Point::ORIGIN);
+        frame.rotate(rotation);
+        frame.scale(size);
+        frame.fill(shape, color);
+    });
 }
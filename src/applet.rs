@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A COSMIC panel applet showing the book being read and the reading streak, which
+//! opens libby when clicked.
+//!
+//! The applet is built into the same binary as the app with the `applet` feature, and
+//! runs instead of it when started with `--applet`.
+
+use crate::fl;
+use crate::icons;
+use crate::library::Library;
+use cosmic::applet::cosmic_panel_config::PanelAnchor;
+use cosmic::iced::{Alignment, Subscription};
+use cosmic::prelude::*;
+use cosmic::widget;
+use std::time::Duration;
+
+/// How often the catalog is read again, to follow the reading done in the app.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most characters of the book's title shown in the panel.
+const TITLE_LENGTH: usize = 24;
+
+/// Name of the app's icon.
+const ICON: &str = "com.github.codegod100.libby";
+
+/// Runs the applet until the panel removes it.
+pub fn run() -> cosmic::iced::Result {
+    cosmic::applet::run::<Applet>(())
+}
+
+pub struct Applet {
+    core: cosmic::Core,
+    /// Title of the book last read, if any.
+    book: Option<String>,
+    /// Days in a row on which a book was read.
+    streak: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    Loaded(Option<String>, u32),
+    OpenApp,
+}
+
+impl Applet {
+    /// Reads the book last read and the streak from the catalog.
+    fn load() -> Task<cosmic::Action<Message>> {
+        let load = tokio::task::spawn_blocking(|| {
+            let library = Library::load()
+                .inspect_err(|why| tracing::error!("{why}"))
+                .unwrap_or_default();
+            let book = library.last_read().map(|book| book.title.clone());
+            (book, library.streak())
+        });
+
+        Task::perform(load, |result| {
+            let (book, streak) = result.unwrap_or_default();
+            cosmic::Action::App(Message::Loaded(book, streak))
+        })
+    }
+}
+
+impl cosmic::Application for Applet {
+    type Executor = cosmic::executor::Default;
+
+    type Flags = ();
+
+    type Message = Message;
+
+    const APP_ID: &'static str = "com.github.codegod100.libby.Applet";
+
+    fn core(&self) -> &cosmic::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::Core {
+        &mut self.core
+    }
+
+    fn init(core: cosmic::Core, _flags: Self::Flags) -> (Self, Task<cosmic::Action<Message>>) {
+        let applet = Applet {
+            core,
+            book: None,
+            streak: 0,
+        };
+
+        (applet, Self::load())
+    }
+
+    fn update(&mut self, message: Message) -> Task<cosmic::Action<Message>> {
+        match message {
+            Message::Refresh => return Self::load(),
+            Message::Loaded(book, streak) => {
+                self.book = book;
+                self.streak = streak;
+            }
+            Message::OpenApp => {
+                let exe = match std::env::current_exe() {
+                    Ok(exe) => exe,
+                    Err(why) => {
+                        tracing::error!("failed to find libby to open: {why}");
+                        return Task::none();
+                    }
+                };
+
+                // A running app is focused instead, being single-instance.
+                return Task::future(async move {
+                    if let Err(why) = tokio::process::Command::new(exe).status().await {
+                        tracing::error!("failed to open libby: {why}");
+                    }
+                })
+                .discard();
+            }
+        }
+
+        Task::none()
+    }
+
+    fn view(&self) -> Element<Message> {
+        let horizontal = matches!(
+            self.core.applet.anchor,
+            PanelAnchor::Top | PanelAnchor::Bottom
+        );
+
+        // Vertical panels are too narrow for more than the icon.
+        if !horizontal {
+            return self
+                .core
+                .applet
+                .icon_button(ICON)
+                .on_press(Message::OpenApp)
+                .into();
+        }
+
+        let label = match &self.book {
+            Some(title) => {
+                let title: String = if title.chars().count() > TITLE_LENGTH {
+                    title.chars().take(TITLE_LENGTH - 1).chain(['…']).collect()
+                } else {
+                    title.clone()
+                };
+
+                fl!("applet-reading", title = title, streak = self.streak)
+            }
+            None => fl!("applet-streak", streak = self.streak),
+        };
+
+        let (size, _) = self.core.applet.suggested_size(true);
+
+        let content = widget::row()
+            .push(widget::icon(icons::app_symbolic()).size(size))
+            .push(self.core.applet.text(label))
+            .spacing(8)
+            .align_y(Alignment::Center);
+
+        let button = widget::button::custom(content)
+            .class(cosmic::theme::Button::AppletIcon)
+            .on_press(Message::OpenApp);
+
+        self.core.applet.autosize_window(button).into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        cosmic::iced::time::every(REFRESH_INTERVAL).map(|_| Message::Refresh)
+    }
+
+    fn style(&self) -> Option<cosmic::iced::theme::Style> {
+        Some(cosmic::applet::style())
+    }
+}
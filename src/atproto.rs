@@ -0,0 +1,968 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Signs in to an atproto account, such as a Bluesky one, with an app password.
+//!
+//! The session is kept in the system keyring rather than the config, so its tokens
+//! never land in a plain file. OAuth is not supported yet: it needs a client metadata
+//! document hosted on the web, which a desktop app doesn't have.
+//!
+//! Built without the `atproto` feature, libby keeps the types but can't reach any
+//! server: there is never a session, and every call fails saying so.
+
+#[cfg(feature = "atproto")]
+use crate::net;
+#[cfg(feature = "atproto")]
+use crate::secrets;
+#[cfg(feature = "atproto")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Key the session is stored under in the keyring.
+#[cfg(feature = "atproto")]
+const KEYRING_KEY: &str = "atproto-session";
+
+/// Server signing in accounts hosted by Bluesky, and anyone logging in by email.
+#[cfg(feature = "atproto")]
+const ENTRYWAY: &str = "https://bsky.social";
+
+/// Public Bluesky API, which resolves handles and serves profiles without signing in.
+#[cfg(feature = "atproto")]
+const APPVIEW: &str = "https://public.api.bsky.app";
+
+#[cfg(feature = "atproto")]
+const PLC_DIRECTORY: &str = "https://plc.directory";
+
+/// Service a PDS forwards Bluesky methods to on behalf of a signed in account.
+#[cfg(feature = "atproto")]
+const APPVIEW_PROXY: &str = "did:web:api.bsky.app#bsky_appview";
+
+/// Posts asked for per page of a feed.
+#[cfg(feature = "atproto")]
+const FEED_PAGE_SIZE: &str = "30";
+
+/// Records asked for per page when listing a collection.
+#[cfg(feature = "atproto")]
+const RECORDS_PAGE_SIZE: &str = "100";
+
+/// Longest post Bluesky accepts, in characters.
+pub const MAX_POST_CHARS: usize = 300;
+
+/// Largest image Bluesky accepts in a post.
+pub const MAX_IMAGE_BYTES: usize = 1_000_000;
+
+/// A signed in account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub did: String,
+    pub handle: String,
+    /// Personal data server hosting the account, which issued the tokens.
+    pub pds: String,
+    access_jwt: String,
+    refresh_jwt: String,
+}
+
+/// How the account presents itself.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub handle: String,
+    pub display_name: Option<String>,
+    /// The encoded avatar image, if the account has one.
+    pub avatar: Option<Vec<u8>>,
+}
+
+/// An image attached to a post.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "atproto"), allow(dead_code))]
+pub struct Image {
+    /// The encoded image, at most [`MAX_IMAGE_BYTES`] long.
+    pub bytes: Vec<u8>,
+    pub mime: &'static str,
+    /// Description of the image for screen readers.
+    pub alt: String,
+}
+
+/// Where the posts of the feed page come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feed {
+    /// Posts by the accounts the signed in user follows.
+    Timeline,
+    /// Posts by one account, given by its handle or DID.
+    Author(String),
+    /// A custom feed, given by the AT URI of its generator.
+    Generator(String),
+}
+
+impl Feed {
+    /// Reads a feed as typed: a web link to a profile or custom feed, an AT URI, a
+    /// handle, or nothing for the timeline.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim().trim_start_matches('@');
+
+        if text.is_empty() {
+            return Feed::Timeline;
+        }
+
+        if text.starts_with("at://") {
+            return Feed::Generator(text.to_string());
+        }
+
+        // Links from bsky.app and other clients share the same paths.
+        let path = text
+            .split_once("/profile/")
+            .map_or(text, |(_, path)| path)
+            .trim_end_matches('/');
+
+        match path.split('/').collect::<Vec<_>>()[..] {
+            [actor, "feed", rkey, ..] => {
+                Feed::Generator(format!("at://{actor}/app.bsky.feed.generator/{rkey}"))
+            }
+            [actor, ..] => Feed::Author(actor.to_string()),
+            [] => Feed::Timeline,
+        }
+    }
+}
+
+/// A post shown in a feed.
+#[derive(Debug, Clone)]
+pub struct Post {
+    pub uri: String,
+    pub handle: String,
+    pub display_name: Option<String>,
+    pub text: String,
+    /// When the post was written, as an RFC 3339 timestamp.
+    pub created: String,
+    pub likes: u64,
+    pub reposts: u64,
+    pub replies: u64,
+    /// Thumbnails of the attached images, with their descriptions.
+    pub images: Vec<(String, String)>,
+    /// Handle of the account whose repost brought the post into the feed.
+    pub reposted_by: Option<String>,
+}
+
+impl Post {
+    /// Where the post can be opened on the web.
+    pub fn web_url(&self) -> String {
+        let rkey = self.uri.rsplit('/').next().unwrap_or_default();
+        format!("https://bsky.app/profile/{}/post/{rkey}", self.handle)
+    }
+}
+
+/// A page of posts, with the cursor the next page is fetched from.
+#[derive(Debug, Clone)]
+pub struct FeedPage {
+    pub posts: Vec<Post>,
+    pub cursor: Option<String>,
+}
+
+/// Makes calls on behalf of a signed in account, refreshing its tokens should they
+/// expire along the way.
+pub struct Agent {
+    session: Session,
+    refreshed: bool,
+}
+
+/// Why a session couldn't be refreshed.
+#[cfg(feature = "atproto")]
+enum Refresh {
+    /// The server no longer accepts the session.
+    Rejected(String),
+    /// The server couldn't be reached or failed.
+    Failed(String),
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Serialize)]
+struct CreateSession<'a> {
+    identifier: &'a str,
+    password: &'a str,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionResponse {
+    did: String,
+    handle: String,
+    access_jwt: String,
+    refresh_jwt: String,
+    did_doc: Option<DidDocument>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct DidDocument {
+    #[serde(default)]
+    service: Vec<Service>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Service {
+    id: String,
+    service_endpoint: String,
+}
+
+#[cfg(feature = "atproto")]
+impl DidDocument {
+    fn pds(&self) -> Option<String> {
+        self.service
+            .iter()
+            .find(|service| service.id.ends_with("#atproto_pds"))
+            .map(|service| service.service_endpoint.trim_end_matches('/').to_string())
+    }
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Serialize)]
+struct CreateRecord<'a> {
+    repo: &'a str,
+    collection: &'a str,
+    record: serde_json::Value,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct UploadBlob {
+    blob: serde_json::Value,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct FeedResponse {
+    feed: Vec<FeedItem>,
+    cursor: Option<String>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct FeedItem {
+    post: PostView,
+    reason: Option<Reason>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct Reason {
+    by: Option<Author>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostView {
+    uri: String,
+    author: Author,
+    record: PostRecord,
+    embed: Option<Embed>,
+    #[serde(default)]
+    like_count: u64,
+    #[serde(default)]
+    repost_count: u64,
+    #[serde(default)]
+    reply_count: u64,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Author {
+    handle: String,
+    display_name: Option<String>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostRecord {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    created_at: String,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct Embed {
+    #[serde(default)]
+    images: Vec<ImageView>,
+    /// Images of a post quoting another.
+    media: Option<Box<Embed>>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct ImageView {
+    thumb: String,
+    #[serde(default)]
+    alt: String,
+}
+
+#[cfg(feature = "atproto")]
+impl From<FeedItem> for Post {
+    fn from(item: FeedItem) -> Self {
+        let post = item.post;
+        let embed = post.embed.map(|embed| match embed.media {
+            Some(media) if embed.images.is_empty() => *media,
+            _ => embed,
+        });
+
+        Post {
+            uri: post.uri,
+            handle: post.author.handle,
+            display_name: post
+                .author
+                .display_name
+                .filter(|name| !name.trim().is_empty()),
+            text: post.record.text,
+            created: post.record.created_at,
+            likes: post.like_count,
+            reposts: post.repost_count,
+            replies: post.reply_count,
+            images: embed
+                .map(|embed| {
+                    embed
+                        .images
+                        .into_iter()
+                        .map(|image| (image.thumb, image.alt))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            reposted_by: item.reason.and_then(|reason| reason.by).map(|by| by.handle),
+        }
+    }
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct ListRecords {
+    records: Vec<ListedRecord>,
+    cursor: Option<String>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct ListedRecord {
+    uri: String,
+    value: serde_json::Value,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct ResolveHandle {
+    did: String,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileResponse {
+    handle: String,
+    display_name: Option<String>,
+    avatar: Option<String>,
+}
+
+#[cfg(feature = "atproto")]
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    message: Option<String>,
+}
+
+#[cfg(feature = "atproto")]
+fn xrpc(server: &str, method: &str) -> String {
+    format!("{}/xrpc/{method}", server.trim_end_matches('/'))
+}
+
+/// Signs in with a handle, DID or email and an app password, and stores the session.
+#[cfg(feature = "atproto")]
+pub async fn login(identifier: String, password: String) -> Result<Session, String> {
+    let identifier = identifier.trim().trim_start_matches('@').to_string();
+
+    // Emails can only be looked up by the entryway; handles and DIDs lead to their PDS.
+    let pds = if identifier.contains('@') {
+        ENTRYWAY.to_string()
+    } else {
+        match find_pds(&identifier).await {
+            Ok(pds) => pds,
+            Err(why) => {
+                tracing::error!("failed to find the PDS of {identifier}: {why}");
+                ENTRYWAY.to_string()
+            }
+        }
+    };
+
+    let response = net::send(
+        net::client()
+            .post(xrpc(&pds, "com.atproto.server.createSession"))
+            .json(&CreateSession {
+                identifier: &identifier,
+                password: &password,
+            }),
+    )
+    .await
+    .map_err(|why| why.to_string())?;
+
+    let response: SessionResponse = parse(response).await?;
+    let session = session(response, pds);
+    store(session.clone()).await?;
+    Ok(session)
+}
+
+/// Restores the stored session, refreshing its tokens, or `None` if nobody is signed in.
+///
+/// A session the server no longer accepts is forgotten; one which can't be refreshed
+/// for any other reason, such as being offline, is kept as it is.
+#[cfg(feature = "atproto")]
+pub async fn resume() -> Result<Option<Session>, String> {
+    let Some(stored) = load().await? else {
+        return Ok(None);
+    };
+
+    match refresh(&stored).await {
+        Ok(session) => Ok(Some(session)),
+        Err(Refresh::Rejected(why)) => {
+            forget().await?;
+            Err(why)
+        }
+        Err(Refresh::Failed(why)) => {
+            tracing::error!("failed to refresh the atproto session: {why}");
+            Ok(Some(stored))
+        }
+    }
+}
+
+/// Signs out, revoking the session on the server and removing it from the keyring.
+#[cfg(feature = "atproto")]
+pub async fn logout(session: Session) -> Result<(), String> {
+    let revoked = net::send(
+        net::client()
+            .post(xrpc(&session.pds, "com.atproto.server.deleteSession"))
+            .bearer_auth(&session.refresh_jwt),
+    )
+    .await;
+
+    // The tokens expire on their own, so signing out goes ahead regardless.
+    if let Err(why) = revoked.and_then(reqwest::Response::error_for_status) {
+        tracing::error!("failed to revoke the atproto session: {why}");
+    }
+
+    forget().await
+}
+
+/// Fetches the account's public profile along with its avatar.
+#[cfg(feature = "atproto")]
+pub async fn profile(did: String) -> Result<Profile, String> {
+    let response = net::send(
+        net::client()
+            .get(xrpc(APPVIEW, "app.bsky.actor.getProfile"))
+            .query(&[("actor", &did)]),
+    )
+    .await
+    .map_err(|why| why.to_string())?;
+
+    let profile: ProfileResponse = parse(response).await?;
+
+    let avatar = match profile.avatar {
+        Some(url) => match fetch_bytes(&url).await {
+            Ok(bytes) => Some(bytes),
+            Err(why) => {
+                tracing::error!("failed to fetch avatar of {}: {why}", profile.handle);
+                None
+            }
+        },
+        None => None,
+    };
+
+    Ok(Profile {
+        handle: profile.handle,
+        display_name: profile.display_name.filter(|name| !name.trim().is_empty()),
+        avatar,
+    })
+}
+
+/// Publishes a post with an optional image, returning the session with the fresh tokens
+/// it was made with.
+#[cfg(feature = "atproto")]
+pub async fn post(session: Session, text: String, image: Option<Image>) -> Result<Session, String> {
+    let session = refresh(&session).await.map_err(|why| match why {
+        Refresh::Rejected(why) | Refresh::Failed(why) => why,
+    })?;
+
+    let client = net::client();
+
+    let mut record = serde_json::json!({
+        "$type": "app.bsky.feed.post",
+        "text": text,
+        "createdAt": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    });
+
+    if let Some(image) = image {
+        let response = net::send(
+            client
+                .post(xrpc(&session.pds, "com.atproto.repo.uploadBlob"))
+                .bearer_auth(&session.access_jwt)
+                .header(reqwest::header::CONTENT_TYPE, image.mime)
+                .body(image.bytes),
+        )
+        .await
+        .map_err(|why| why.to_string())?;
+
+        let upload: UploadBlob = parse(response).await?;
+
+        record["embed"] = serde_json::json!({
+            "$type": "app.bsky.embed.images",
+            "images": [{ "alt": image.alt, "image": upload.blob }],
+        });
+    }
+
+    let response = net::send(
+        client
+            .post(xrpc(&session.pds, "com.atproto.repo.createRecord"))
+            .bearer_auth(&session.access_jwt)
+            .json(&CreateRecord {
+                repo: &session.did,
+                collection: "app.bsky.feed.post",
+                record,
+            }),
+    )
+    .await
+    .map_err(|why| why.to_string())?;
+
+    parse::<serde_json::Value>(response).await?;
+    Ok(session)
+}
+
+/// Fetches a page of a feed, after `cursor` if given. Feeds are read through the
+/// account's PDS when signed in, which the timeline needs, and from the public API
+/// otherwise. Returns the session too if its tokens had to be refreshed.
+#[cfg(feature = "atproto")]
+pub async fn feed(
+    session: Option<Session>,
+    feed: Feed,
+    cursor: Option<String>,
+) -> Result<(FeedPage, Option<Session>), String> {
+    let (method, key, value) = match feed {
+        Feed::Timeline => ("app.bsky.feed.getTimeline", None, String::new()),
+        Feed::Author(actor) => ("app.bsky.feed.getAuthorFeed", Some("actor"), actor),
+        Feed::Generator(uri) => (
+            "app.bsky.feed.getFeed",
+            Some("feed"),
+            generator_uri(uri).await?,
+        ),
+    };
+
+    let mut query = vec![("limit", FEED_PAGE_SIZE.to_string())];
+    query.extend(key.map(|key| (key, value)));
+    query.extend(cursor.map(|cursor| ("cursor", cursor)));
+
+    let (response, session) = match session {
+        Some(session) => {
+            let mut agent = Agent::new(session);
+            let response = agent
+                .send(|session| {
+                    net::client()
+                        .get(xrpc(&session.pds, method))
+                        .query(&query)
+                        .header("atproto-proxy", APPVIEW_PROXY)
+                })
+                .await?;
+
+            (response, agent.into_refreshed())
+        }
+        None => {
+            let response = net::send(net::client().get(xrpc(APPVIEW, method)).query(&query))
+                .await
+                .map_err(|why| why.to_string())?;
+
+            (parse::<FeedResponse>(response).await?, None)
+        }
+    };
+
+    let page = FeedPage {
+        posts: response.feed.into_iter().map(Post::from).collect(),
+        cursor: response.cursor,
+    };
+
+    Ok((page, session))
+}
+
+/// Fetches an image shown in a feed.
+#[cfg(feature = "atproto")]
+pub async fn fetch_image(url: String) -> Result<Vec<u8>, String> {
+    fetch_bytes(&url).await
+}
+
+/// The generator's AT URI with its handle, as found in web links, resolved to a DID.
+#[cfg(feature = "atproto")]
+async fn generator_uri(uri: String) -> Result<String, String> {
+    let Some((authority, rest)) = uri
+        .strip_prefix("at://")
+        .and_then(|path| path.split_once('/'))
+    else {
+        return Ok(uri);
+    };
+
+    if authority.starts_with("did:") {
+        return Ok(uri);
+    }
+
+    let did = resolve_handle(authority).await?;
+    Ok(format!("at://{did}/{rest}"))
+}
+
+impl Agent {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            refreshed: false,
+        }
+    }
+
+    /// The session with its new tokens, if they had to be refreshed.
+    pub fn into_refreshed(self) -> Option<Session> {
+        self.refreshed.then_some(self.session)
+    }
+
+    /// Lists every record of a collection in the account's repository, by record key.
+    #[cfg(feature = "atproto")]
+    pub async fn list_records(
+        &mut self,
+        collection: &str,
+    ) -> Result<Vec<(String, serde_json::Value)>, String> {
+        let mut records = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let did = self.session.did.clone();
+            let page: ListRecords = self
+                .send(|session| {
+                    let mut query = vec![
+                        ("repo", did.as_str()),
+                        ("collection", collection),
+                        ("limit", RECORDS_PAGE_SIZE),
+                    ];
+                    query.extend(cursor.as_deref().map(|cursor| ("cursor", cursor)));
+
+                    net::client()
+                        .get(xrpc(&session.pds, "com.atproto.repo.listRecords"))
+                        .query(&query)
+                })
+                .await?;
+
+            let done = page.records.is_empty() || page.cursor.is_none();
+
+            records.extend(page.records.into_iter().map(|record| {
+                let rkey = record
+                    .uri
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                (rkey, record.value)
+            }));
+
+            if done {
+                return Ok(records);
+            }
+
+            cursor = page.cursor;
+        }
+    }
+
+    /// Creates or replaces the record at `rkey` in a collection.
+    #[cfg(feature = "atproto")]
+    pub async fn put_record(
+        &mut self,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), String> {
+        let body = serde_json::json!({
+            "repo": self.session.did,
+            "collection": collection,
+            "rkey": rkey,
+            "record": record,
+        });
+
+        self.send::<serde_json::Value>(|session| {
+            net::client()
+                .post(xrpc(&session.pds, "com.atproto.repo.putRecord"))
+                .json(&body)
+        })
+        .await
+        .map(drop)
+    }
+
+    /// Deletes the record at `rkey` in a collection, if there is one.
+    #[cfg(feature = "atproto")]
+    pub async fn delete_record(&mut self, collection: &str, rkey: &str) -> Result<(), String> {
+        let body = serde_json::json!({
+            "repo": self.session.did,
+            "collection": collection,
+            "rkey": rkey,
+        });
+
+        self.send::<serde_json::Value>(|session| {
+            net::client()
+                .post(xrpc(&session.pds, "com.atproto.repo.deleteRecord"))
+                .json(&body)
+        })
+        .await
+        .map(drop)
+    }
+
+    /// Sends the request built for the session, refreshing its tokens and sending it
+    /// again if they have expired, and decodes the response.
+    #[cfg(feature = "atproto")]
+    async fn send<T: DeserializeOwned>(
+        &mut self,
+        request: impl Fn(&Session) -> reqwest::RequestBuilder,
+    ) -> Result<T, String> {
+        let mut retried = false;
+
+        loop {
+            let response = net::send(request(&self.session).bearer_auth(&self.session.access_jwt))
+                .await
+                .map_err(|why| why.to_string())?;
+
+            let status = response.status();
+            let bytes = response.bytes().await.map_err(|why| why.to_string())?;
+
+            if status.is_success() {
+                // Some methods answer with an empty body.
+                let bytes = if bytes.is_empty() {
+                    &b"null"[..]
+                } else {
+                    &bytes
+                };
+                return serde_json::from_slice(bytes).map_err(|why| why.to_string());
+            }
+
+            match serde_json::from_slice::<ErrorResponse>(&bytes) {
+                Ok(error) if error.error == "ExpiredToken" && !retried => {
+                    self.session = refresh(&self.session).await.map_err(|why| match why {
+                        Refresh::Rejected(why) | Refresh::Failed(why) => why,
+                    })?;
+                    self.refreshed = true;
+                    retried = true;
+                }
+                Ok(error) => return Err(error.message.unwrap_or(error.error)),
+                Err(_) => return Err(status.to_string()),
+            }
+        }
+    }
+}
+
+/// Trades the session's refresh token for new tokens and stores them.
+#[cfg(feature = "atproto")]
+async fn refresh(stored: &Session) -> Result<Session, Refresh> {
+    let response = net::send(
+        net::client()
+            .post(xrpc(&stored.pds, "com.atproto.server.refreshSession"))
+            .bearer_auth(&stored.refresh_jwt),
+    )
+    .await
+    .map_err(|why| Refresh::Failed(why.to_string()))?;
+
+    let status = response.status();
+
+    let response = parse::<SessionResponse>(response).await.map_err(|why| {
+        if status.is_client_error() {
+            Refresh::Rejected(why)
+        } else {
+            Refresh::Failed(why)
+        }
+    })?;
+
+    let session = session(response, stored.pds.clone());
+    store(session.clone()).await.map_err(Refresh::Failed)?;
+    Ok(session)
+}
+
+#[cfg(feature = "atproto")]
+fn session(response: SessionResponse, pds: String) -> Session {
+    Session {
+        pds: response
+            .did_doc
+            .as_ref()
+            .and_then(DidDocument::pds)
+            .unwrap_or(pds),
+        did: response.did,
+        handle: response.handle,
+        access_jwt: response.access_jwt,
+        refresh_jwt: response.refresh_jwt,
+    }
+}
+
+/// Finds the server hosting a handle or DID through its DID document.
+#[cfg(feature = "atproto")]
+async fn find_pds(identifier: &str) -> Result<String, String> {
+    let did = if identifier.starts_with("did:") {
+        identifier.to_string()
+    } else {
+        resolve_handle(identifier).await?
+    };
+
+    let url = if let Some(host) = did.strip_prefix("did:web:") {
+        format!("https://{host}/.well-known/did.json")
+    } else if did.starts_with("did:plc:") {
+        format!("{PLC_DIRECTORY}/{did}")
+    } else {
+        return Err(format!("unsupported DID method: {did}"));
+    };
+
+    let response = net::get(url).await.map_err(|why| why.to_string())?;
+    let document: DidDocument = parse(response).await?;
+
+    document.pds().ok_or_else(|| format!("{did} names no PDS"))
+}
+
+/// Resolves a handle to its DID through the handle's domain, or else the public API.
+#[cfg(feature = "atproto")]
+async fn resolve_handle(handle: &str) -> Result<String, String> {
+    let well_known = async {
+        let response = net::get(format!("https://{handle}/.well-known/atproto-did"))
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .ok()?;
+        let did = response.text().await.ok()?.trim().to_string();
+        did.starts_with("did:").then_some(did)
+    };
+
+    if let Some(did) = well_known.await {
+        return Ok(did);
+    }
+
+    let response = net::send(
+        net::client()
+            .get(xrpc(APPVIEW, "com.atproto.identity.resolveHandle"))
+            .query(&[("handle", handle)]),
+    )
+    .await
+    .map_err(|why| why.to_string())?;
+
+    parse::<ResolveHandle>(response)
+        .await
+        .map(|resolved| resolved.did)
+}
+
+#[cfg(feature = "atproto")]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, String> {
+    net::get(url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|why| why.to_string())?
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|why| why.to_string())
+}
+
+/// Decodes a successful response, or the server's explanation of a failed one.
+#[cfg(feature = "atproto")]
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, String> {
+    let status = response.status();
+
+    if status.is_success() {
+        return response.json().await.map_err(|why| why.to_string());
+    }
+
+    match response.json::<ErrorResponse>().await {
+        Ok(error) => Err(error.message.unwrap_or(error.error)),
+        Err(_) => Err(status.to_string()),
+    }
+}
+
+#[cfg(feature = "atproto")]
+async fn store(session: Session) -> Result<(), String> {
+    let json = serde_json::to_string(&session).map_err(|why| why.to_string())?;
+    secrets::store(KEYRING_KEY.to_string(), json).await
+}
+
+#[cfg(feature = "atproto")]
+async fn load() -> Result<Option<Session>, String> {
+    match secrets::load(KEYRING_KEY.to_string()).await? {
+        Some(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|why| why.to_string()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(feature = "atproto")]
+async fn forget() -> Result<(), String> {
+    secrets::forget(KEYRING_KEY.to_string()).await
+}
+
+/// What every call fails with when built without the `atproto` feature.
+#[cfg(not(feature = "atproto"))]
+const UNSUPPORTED: &str = "this build of libby can't sign in to atproto accounts";
+
+#[cfg(not(feature = "atproto"))]
+pub async fn login(_identifier: String, _password: String) -> Result<Session, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+/// There is never a session to resume, as none can be started.
+#[cfg(not(feature = "atproto"))]
+pub async fn resume() -> Result<Option<Session>, String> {
+    Ok(None)
+}
+
+#[cfg(not(feature = "atproto"))]
+pub async fn logout(_session: Session) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(feature = "atproto"))]
+pub async fn profile(_did: String) -> Result<Profile, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(feature = "atproto"))]
+pub async fn post(
+    _session: Session,
+    _text: String,
+    _image: Option<Image>,
+) -> Result<Session, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(feature = "atproto"))]
+pub async fn feed(
+    _session: Option<Session>,
+    _feed: Feed,
+    _cursor: Option<String>,
+) -> Result<(FeedPage, Option<Session>), String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(feature = "atproto"))]
+pub async fn fetch_image(_url: String) -> Result<Vec<u8>, String> {
+    Err(UNSUPPORTED.to_string())
+}
+
+#[cfg(not(feature = "atproto"))]
+impl Agent {
+    pub async fn list_records(
+        &mut self,
+        _collection: &str,
+    ) -> Result<Vec<(String, serde_json::Value)>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn put_record(
+        &mut self,
+        _collection: &str,
+        _rkey: &str,
+        _record: serde_json::Value,
+    ) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn delete_record(&mut self, _collection: &str, _rkey: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Mirrors the layout for languages written right to left, such as Arabic and Hebrew.
+//!
+//! The toolkit lays rows out from the left whatever the language, so rows whose order
+//! means something, such as toolbars and pagers, put their children through
+//! [`ordered`], and show progress with [`progress_bar`]. The nav bar moves to the
+//! right edge when [`nav_trailing`] says so.
+
+use crate::i18n;
+use cosmic::iced::widget::progress_bar::{self as bar, Catalog};
+use cosmic::iced::widget::ProgressBar;
+use cosmic::{theme, widget, Element};
+use std::ops::RangeInclusive;
+
+/// The children of a row in the order they are laid out, starting from the left.
+pub fn ordered<'a, Message>(mut children: Vec<Element<'a, Message>>) -> Vec<Element<'a, Message>> {
+    if i18n::is_rtl() {
+        children.reverse();
+    }
+
+    children
+}
+
+/// Whether the nav bar sits at the right edge of the window rather than the left.
+///
+/// A condensed window shows the nav bar across its whole width, so there is no side
+/// to pick.
+pub fn nav_trailing(condensed: bool) -> bool {
+    i18n::is_rtl() && !condensed
+}
+
+/// The arrow pointing back or forward in the direction lines run.
+pub fn icon(name: &'static str) -> &'static str {
+    if !i18n::is_rtl() {
+        return name;
+    }
+
+    match name {
+        "go-previous-symbolic" => "go-next-symbolic",
+        "go-next-symbolic" => "go-previous-symbolic",
+        _ => name,
+    }
+}
+
+/// A progress bar filling from where lines start.
+pub fn progress_bar<'a>(range: RangeInclusive<f32>, value: f32) -> ProgressBar<'a, cosmic::Theme> {
+    if !i18n::is_rtl() {
+        return widget::progress_bar(range, value);
+    }
+
+    // What's left, filled from the left in the bar's colors swapped, looks like what's
+    // done filled from the right.
+    let (start, end) = (*range.start(), *range.end());
+    let left = end - (value.clamp(start, end) - start);
+
+    widget::progress_bar(range, left).class(theme::ProgressBar::Custom(Box::new(|theme| {
+        let style = theme.style(&theme::ProgressBar::Primary);
+
+        bar::Style {
+            background: style.bar,
+            bar: style.background,
+            ..style
+        }
+    })))
+}
+
+/// Text without the marks Fluent puts around arguments to keep their direction, for
+/// what the toolkit doesn't show itself, such as the window title.
+pub fn plain(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{2068}' | '\u{2069}'))
+        .collect()
+}
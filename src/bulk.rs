@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Bulk edits applied to a selection of books as a cancellable background batch.
+
+use crate::download;
+use crate::library::{Book, BookId, Trashed};
+use crate::trash;
+use futures_util::{Stream, StreamExt};
+use std::path::{Path, PathBuf};
+
+/// An operation applied to every selected book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkAction {
+    AddTag(String),
+    SetSeries(String),
+    MoveToCollection(String),
+    /// Moves the book into the trash, optionally moving its file into the trash folder.
+    Delete {
+        move_file: bool,
+    },
+    /// Converts the book's file to the given extension with Calibre's `ebook-convert`,
+    /// beside it and numbered so as not to replace another file. Books already in that
+    /// format are left as they are.
+    Convert(String),
+}
+
+/// What the action changed about a book, applied to its entry as it is by the time the
+/// action is done, so that edits made meanwhile, such as reading on, are kept.
+#[derive(Debug, Clone)]
+pub enum Change {
+    AddTag(String),
+    Series(Option<String>),
+    Collection(Option<String>),
+    /// Moved into the trash, along with its file if that was moved to `path`.
+    Trashed {
+        path: Option<PathBuf>,
+        trashed: Option<Trashed>,
+    },
+    /// Converted into the file at the path.
+    Converted(PathBuf),
+    /// Left as it was, such as when already in the format converted to.
+    Unchanged,
+}
+
+impl Change {
+    pub fn apply(self, book: &mut Book) {
+        match self {
+            Change::AddTag(tag) => {
+                if !book.tags.contains(&tag) {
+                    book.tags.push(tag);
+                }
+            }
+            Change::Series(series) => book.series = series,
+            Change::Collection(collection) => book.collection = collection,
+            Change::Trashed { path, trashed } => {
+                book.path = path;
+                book.trashed = trashed;
+            }
+            Change::Converted(path) => {
+                book.path = Some(path);
+                // The hash and stamp were of the file converted from.
+                book.hash = None;
+                book.stamp = None;
+            }
+            Change::Unchanged => {}
+        }
+    }
+}
+
+/// Result of applying the action to a single book.
+#[derive(Debug, Clone)]
+pub enum Progress {
+    Updated(BookId, Change),
+    Failed(BookId, String),
+}
+
+/// Applies the action to each book in turn, yielding one progress event per book.
+///
+/// Dropping the stream cancels the remaining work; books already processed keep
+/// their changes.
+pub fn run(books: Vec<Book>, action: BulkAction) -> impl Stream<Item = Progress> {
+    futures_util::stream::iter(books).then(move |book| {
+        let action = action.clone();
+        async move { apply(book, &action).await }
+    })
+}
+
+async fn apply(book: Book, action: &BulkAction) -> Progress {
+    let id = book.id;
+
+    let change = match action {
+        BulkAction::AddTag(tag) => Change::AddTag(tag.clone()),
+
+        BulkAction::SetSeries(series) => {
+            Change::Series(Some(series.clone()).filter(|s| !s.is_empty()))
+        }
+
+        BulkAction::MoveToCollection(collection) => {
+            Change::Collection(Some(collection.clone()).filter(|c| !c.is_empty()))
+        }
+
+        BulkAction::Delete { move_file } => match trash::trash(book, *move_file).await {
+            Ok(book) => Change::Trashed {
+                path: book.path,
+                trashed: book.trashed,
+            },
+            Err(why) => return Progress::Failed(id, why.to_string()),
+        },
+
+        BulkAction::Convert(extension) => {
+            let Some(input) = book.path else {
+                return Progress::Failed(id, "book has no file to convert".to_string());
+            };
+
+            let converted = input
+                .extension()
+                .is_some_and(|current| current.eq_ignore_ascii_case(extension));
+
+            if converted {
+                return Progress::Updated(id, Change::Unchanged);
+            }
+
+            let Some(name) = input
+                .with_extension(extension)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+            else {
+                return Progress::Failed(id, "book has no file to convert".to_string());
+            };
+
+            let output = download::destination(input.parent().unwrap_or(Path::new("")), &name);
+
+            let status = tokio::process::Command::new("ebook-convert")
+                .arg(&input)
+                .arg(&output)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .status()
+                .await;
+
+            match status {
+                Ok(status) if status.success() => Change::Converted(output),
+                Ok(status) => {
+                    return Progress::Failed(id, format!("ebook-convert exited with {status}"))
+                }
+                Err(why) => return Progress::Failed(id, why.to_string()),
+            }
+        }
+    };
+
+    Progress::Updated(id, change)
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keeps responses to GET requests on disk, so pages seen before open quickly and
+//! offline.
+//!
+//! Each response is kept in the cache folder as two files named by the hash of its
+//! address: the body, and the validators the server sent with it to ask whether it
+//! changed. The least recently used responses are deleted once the folder outgrows its
+//! capacity, which is set in the settings.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// Most the cache holds unless set otherwise, in MiB.
+pub const DEFAULT_CAPACITY: u32 = 256;
+
+/// Most the cache holds, in bytes.
+static CAPACITY: AtomicU64 = AtomicU64::new(DEFAULT_CAPACITY as u64 * 1024 * 1024);
+
+/// What the server said about a cached response.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// A cached response.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub validators: Validators,
+    pub body: Vec<u8>,
+}
+
+/// Sets the most the cache holds, in MiB, or 0 for [`DEFAULT_CAPACITY`]. Responses over
+/// it are deleted as the next is cached, or by [`shrink`].
+pub fn set_capacity(mib: u32) {
+    let mib = if mib == 0 { DEFAULT_CAPACITY } else { mib };
+    CAPACITY.store(u64::from(mib) * 1024 * 1024, Ordering::Relaxed);
+}
+
+fn capacity() -> u64 {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+fn dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("libby").join("http"))
+}
+
+fn paths(key: &str) -> Option<(PathBuf, PathBuf)> {
+    let hash: String = Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    let dir = dir()?;
+    Some((
+        dir.join(format!("{hash}.body")),
+        dir.join(format!("{hash}.json")),
+    ))
+}
+
+/// The response cached under `key`, if any.
+pub async fn load(key: &str) -> Option<Entry> {
+    let (body_path, validators_path) = paths(key)?;
+
+    let body = tokio::fs::read(&body_path).await.ok()?;
+    let validators = tokio::fs::read(&validators_path)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+
+    // Note the use, which keeps the response from being the first evicted.
+    if let Ok(file) = std::fs::File::options().append(true).open(&body_path) {
+        _ = file.set_modified(SystemTime::now());
+    }
+
+    Some(Entry { validators, body })
+}
+
+/// Caches a response under `key`, making room for it if the cache is full.
+pub async fn store(key: String, entry: Entry) {
+    let result = tokio::task::spawn_blocking(move || {
+        let (body_path, validators_path) = paths(&key).ok_or(std::io::ErrorKind::NotFound)?;
+
+        if let Some(parent) = body_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&body_path, &entry.body)?;
+        std::fs::write(&validators_path, serde_json::to_vec(&entry.validators)?)?;
+
+        trim(body_path.parent().unwrap_or(Path::new("")), capacity())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => (),
+        Ok(Err(why)) => tracing::warn!("failed to cache a response: {why}"),
+        Err(why) => tracing::warn!("failed to cache a response: {why}"),
+    }
+}
+
+/// Deletes the least recently used responses until the cache holds at most `capacity`
+/// bytes.
+fn trim(dir: &Path, capacity: u64) -> std::io::Result<()> {
+    let mut bodies: Vec<(SystemTime, u64, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "body"))
+        .filter_map(|path| {
+            let metadata = std::fs::metadata(&path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), path))
+        })
+        .collect();
+
+    let mut size: u64 = bodies.iter().map(|(_, len, _)| len).sum();
+
+    if size <= capacity {
+        return Ok(());
+    }
+
+    bodies.sort();
+
+    for (_, len, path) in bodies {
+        if size <= capacity {
+            break;
+        }
+
+        std::fs::remove_file(&path)?;
+        _ = std::fs::remove_file(path.with_extension("json"));
+        size = size.saturating_sub(len);
+    }
+
+    Ok(())
+}
+
+/// Bytes the cache takes up.
+pub async fn size() -> u64 {
+    tokio::task::spawn_blocking(measure).await.unwrap_or(0)
+}
+
+/// Deletes the least recently used responses over the capacity, as after it's lowered,
+/// returning the bytes the cache then takes up.
+pub async fn shrink() -> u64 {
+    tokio::task::spawn_blocking(|| {
+        if let Some(dir) = dir().filter(|dir| dir.is_dir()) {
+            if let Err(why) = trim(&dir, capacity()) {
+                tracing::warn!("failed to shrink the cache: {why}");
+            }
+        }
+
+        measure()
+    })
+    .await
+    .unwrap_or(0)
+}
+
+fn measure() -> u64 {
+    let Some(entries) = dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Deletes every cached response.
+pub async fn clear() -> Result<(), String> {
+    let Some(dir) = dir() else {
+        return Ok(());
+    };
+
+    match tokio::fs::remove_dir_all(&dir).await {
+        Ok(()) => Ok(()),
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(why) => Err(why.to_string()),
+    }
+}
@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The catalog as devices share it: what they know of each book, keyed so the same
+//! book is recognized on every device, and the merging of changes made to it on
+//! different devices.
+//!
+//! Merges are three-way, against the catalog as it was after this device's last sync,
+//! so deletions carry over as well as additions.
+
+use crate::library::{now, Book, BookId, Bookmark, Highlight, Library, Note, Trashed};
+use crate::sync::{self, Resolution};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Books as shared between devices, keyed by [`sync::key`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Catalog {
+    pub books: BTreeMap<String, Entry>,
+}
+
+/// What devices share of a book: everything but where its file is and how it is shown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub details: Details,
+    #[serde(default)]
+    pub position: Option<sync::Record>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+}
+
+/// Catalog details of a book, which change together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Details {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub series: Option<String>,
+    #[serde(default)]
+    pub collection: Option<String>,
+    #[serde(default)]
+    pub rating: Option<u8>,
+}
+
+impl Entry {
+    pub fn new(book: &Book, device: &str) -> Self {
+        Self {
+            details: Details {
+                title: book.title.clone(),
+                author: book.author.clone(),
+                description: book.description.clone(),
+                tags: book.tags.clone(),
+                series: book.series.clone(),
+                collection: book.collection.clone(),
+                rating: book.rating,
+            },
+            position: sync::record(book, device),
+            notes: book.notes.clone(),
+            bookmarks: book.bookmarks.clone(),
+            highlights: book.highlights.clone(),
+        }
+    }
+
+    /// Writes the entry into a book of the catalog, leaving its position alone unless
+    /// `move_position` is set.
+    pub fn apply(self, book: &mut Book, move_position: bool) {
+        let details = self.details;
+        book.title = details.title;
+        book.author = details.author;
+        book.description = details.description;
+        book.tags = details.tags;
+        book.series = details.series;
+        book.collection = details.collection;
+        book.rating = details.rating;
+        book.notes = self.notes;
+        book.bookmarks = self.bookmarks;
+        book.highlights = self.highlights;
+
+        if let Some(record) = self.position.filter(|_| move_position) {
+            if let Some(location) = record.location() {
+                book.location = Some(location);
+                book.progress = record.progress;
+                book.location_updated = record.updated;
+            }
+        }
+    }
+
+    /// A catalog entry, without a file, for a book added on another device.
+    pub fn into_book(self) -> Book {
+        let mut book = Book::default();
+        self.apply(&mut book, true);
+        book
+    }
+}
+
+impl Catalog {
+    /// The catalog of the books in the library, leaving out those in the trash.
+    pub fn new(books: &[Book], device: &str) -> Self {
+        let mut catalog = Self::default();
+
+        for book in books.iter().filter(|book| !book.is_trashed()) {
+            catalog
+                .books
+                .entry(sync::key(book))
+                .or_insert_with(|| Entry::new(book, device));
+        }
+
+        catalog
+    }
+}
+
+/// What a sync changed in the library.
+#[derive(Debug, Clone, Default)]
+pub struct Outcome {
+    /// Books changed on other devices.
+    pub updated: Vec<(BookId, Entry)>,
+    /// Books added on other devices.
+    pub added: Vec<Entry>,
+    /// Books deleted on other devices.
+    pub removed: Vec<BookId>,
+    /// Changes made both here and elsewhere, which were settled in favor of this device.
+    pub conflicts: usize,
+}
+
+impl Outcome {
+    pub fn changes(&self) -> usize {
+        self.updated.len() + self.added.len() + self.removed.len()
+    }
+
+    /// Takes the changes into the library, leaving the `open` book where it is being
+    /// read, and in the library.
+    pub fn apply(self, library: &mut Library, open: Option<BookId>) {
+        for (id, entry) in self.updated {
+            if let Some(book) = library.get_mut(id) {
+                entry.apply(book, Some(id) != open);
+            }
+        }
+
+        for entry in self.added {
+            library.insert(entry.into_book());
+        }
+
+        for id in self.removed {
+            if Some(id) == open {
+                continue;
+            }
+
+            if let Some(book) = library.get_mut(id) {
+                trash(book);
+            }
+        }
+    }
+}
+
+/// What the library should take from the merged catalog, given the catalog of the
+/// library before the merge.
+pub fn outcome(books: &[Book], ours: &Catalog, merged: Catalog, conflicts: usize) -> Outcome {
+    let mut outcome = Outcome {
+        conflicts,
+        ..Outcome::default()
+    };
+    let mut seen = BTreeSet::new();
+
+    for book in books.iter().filter(|book| !book.is_trashed()) {
+        let key = sync::key(book);
+
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+
+        match merged.books.get(&key) {
+            Some(entry) if ours.books.get(&key) != Some(entry) => {
+                outcome.updated.push((book.id, entry.clone()));
+            }
+            Some(_) => (),
+            None => outcome.removed.push(book.id),
+        }
+    }
+
+    // Books in the trash here stay there, rather than coming back as new ones.
+    let trashed: BTreeSet<String> = books
+        .iter()
+        .filter(|book| book.is_trashed())
+        .map(sync::key)
+        .collect();
+
+    outcome.added = merged
+        .books
+        .into_iter()
+        .filter(|(key, _)| !seen.contains(key) && !trashed.contains(key))
+        .map(|(_, entry)| entry)
+        .collect();
+
+    outcome
+}
+
+/// Flags a book deleted on another device as trashed, leaving its file where it is.
+pub fn trash(book: &mut Book) {
+    book.trashed = Some(Trashed {
+        at: now(),
+        original_path: None,
+    });
+}
+
+/// Merges the catalogs of this device and another, three-way against the catalog as
+/// it was after the last sync, returning the result and the number of conflicting
+/// changes.
+pub fn merge(
+    base: &Catalog,
+    ours: &Catalog,
+    theirs: &Catalog,
+    resolution: Resolution,
+) -> (Catalog, usize) {
+    let mut merged = Catalog::default();
+    let mut conflicts = 0;
+
+    let keys: BTreeSet<&String> = ours.books.keys().chain(theirs.books.keys()).collect();
+
+    for key in keys {
+        let base = base.books.get(key);
+
+        let entry = match (ours.books.get(key), theirs.books.get(key)) {
+            (Some(ours), Some(theirs)) => {
+                merge_entry(base, ours, theirs, resolution, &mut conflicts)
+            }
+            (Some(only), None) | (None, Some(only)) => {
+                if !keep(base, only, &mut conflicts) {
+                    continue;
+                }
+
+                only.clone()
+            }
+            (None, None) => continue,
+        };
+
+        merged.books.insert(key.clone(), entry);
+    }
+
+    (merged, conflicts)
+}
+
+fn merge_entry(
+    base: Option<&Entry>,
+    ours: &Entry,
+    theirs: &Entry,
+    resolution: Resolution,
+    conflicts: &mut usize,
+) -> Entry {
+    let position = match (&ours.position, &theirs.position) {
+        (Some(mine), Some(other)) if other.wins_over(mine, resolution) => Some(other.clone()),
+        (Some(mine), _) => Some(mine.clone()),
+        (None, other) => other.clone(),
+    };
+
+    let mut bookmarks = merge_list(
+        base.map_or(&[], |base| &base.bookmarks),
+        &ours.bookmarks,
+        &theirs.bookmarks,
+        |bookmark| bookmark.created,
+        conflicts,
+    );
+    bookmarks.sort_by(|a, b| {
+        (a.location.chapter, a.location.offset)
+            .partial_cmp(&(b.location.chapter, b.location.offset))
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut highlights = merge_list(
+        base.map_or(&[], |base| &base.highlights),
+        &ours.highlights,
+        &theirs.highlights,
+        |highlight| highlight.created,
+        conflicts,
+    );
+    highlights.sort_by_key(|highlight| (highlight.location.chapter, highlight.blocks.0));
+
+    let mut notes = merge_list(
+        base.map_or(&[], |base| &base.notes),
+        &ours.notes,
+        &theirs.notes,
+        |note| note.created,
+        conflicts,
+    );
+    notes.sort_by_key(|note| note.created);
+
+    Entry {
+        details: pick(
+            base.map(|base| &base.details),
+            &ours.details,
+            &theirs.details,
+            conflicts,
+        ),
+        position,
+        notes,
+        bookmarks,
+        highlights,
+    }
+}
+
+/// Merges lists of items identified by `id`.
+fn merge_list<T: Clone + PartialEq>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    id: fn(&T) -> u64,
+    conflicts: &mut usize,
+) -> Vec<T> {
+    let find = |items: &[T], key: u64| items.iter().find(|item| id(item) == key).cloned();
+    let keys: BTreeSet<u64> = ours.iter().chain(theirs).map(id).collect();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let base = find(base, key);
+
+            match (find(ours, key), find(theirs, key)) {
+                (Some(ours), Some(theirs)) => Some(pick(base.as_ref(), &ours, &theirs, conflicts)),
+                (Some(only), None) | (None, Some(only)) => {
+                    keep(base.as_ref(), &only, conflicts).then_some(only)
+                }
+                (None, None) => None,
+            }
+        })
+        .collect()
+}
+
+/// Picks whichever side changed since the last sync, or this device's if both did.
+fn pick<T: Clone + PartialEq>(base: Option<&T>, ours: &T, theirs: &T, conflicts: &mut usize) -> T {
+    if ours == theirs || base == Some(theirs) {
+        ours.clone()
+    } else if base == Some(ours) {
+        theirs.clone()
+    } else {
+        *conflicts += 1;
+        ours.clone()
+    }
+}
+
+/// Whether to keep something only one side has: kept if it is new, dropped if the
+/// other side deleted it, and kept if it was changed since even though it was deleted.
+fn keep<T: PartialEq>(base: Option<&T>, only: &T, conflicts: &mut usize) -> bool {
+    match base {
+        None => true,
+        Some(base) if base == only => false,
+        Some(_) => {
+            *conflicts += 1;
+            true
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Citations of books and quotations from them, for copying into other documents.
+//!
+//! The catalog keeps no publisher or date, so citations give "n.d." where a style asks
+//! for a date and leave the publisher out.
+
+use crate::clipboard::{escape, RichText};
+use crate::library::Book;
+
+/// A style of citation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Apa,
+    Mla,
+    Bibtex,
+}
+
+impl Style {
+    pub const ALL: [Style; 3] = [Style::Apa, Style::Mla, Style::Bibtex];
+
+    /// Name of the style, which isn't translated.
+    pub fn name(self) -> &'static str {
+        match self {
+            Style::Apa => "APA",
+            Style::Mla => "MLA",
+            Style::Bibtex => "BibTeX",
+        }
+    }
+}
+
+/// A citation of the book in the given style.
+pub fn cite(book: &Book, style: Style) -> RichText {
+    let authors = authors(&book.author);
+    let title = book.title.trim();
+
+    match style {
+        Style::Apa => {
+            let names = list(
+                &authors
+                    .iter()
+                    .map(|name| apa_name(name))
+                    .collect::<Vec<_>>(),
+                "&",
+            );
+
+            if names.is_empty() {
+                RichText {
+                    plain: format!("{title}. (n.d.)."),
+                    html: format!("<i>{}</i>. (n.d.).", escape(title)),
+                }
+            } else {
+                let names = with_period(&names);
+                RichText {
+                    plain: format!("{names} (n.d.). {title}."),
+                    html: format!("{} (n.d.). <i>{}</i>.", escape(&names), escape(title)),
+                }
+            }
+        }
+        Style::Mla => {
+            let names = match authors.as_slice() {
+                [] => String::new(),
+                [one] => inverted(one),
+                [first, second] => format!("{}, and {second}", inverted(first)),
+                [first, ..] => format!("{}, et al", inverted(first)),
+            };
+
+            if names.is_empty() {
+                RichText {
+                    plain: format!("{title}."),
+                    html: format!("<i>{}</i>.", escape(title)),
+                }
+            } else {
+                let names = with_period(&names);
+                RichText {
+                    plain: format!("{names} {title}."),
+                    html: format!("{} <i>{}</i>.", escape(&names), escape(title)),
+                }
+            }
+        }
+        Style::Bibtex => {
+            let mut fields = Vec::new();
+
+            if !authors.is_empty() {
+                fields.push(("author", authors.join(" and ")));
+            }
+
+            fields.push(("title", title.to_string()));
+
+            if let Some(series) = &book.series {
+                fields.push(("series", series.clone()));
+            }
+
+            let mut plain = format!("@book{{{},\n", bibtex_key(&authors, title));
+
+            for (name, value) in fields {
+                plain.push_str(&format!("  {name} = {{{}}},\n", bibtex_escape(&value)));
+            }
+
+            plain.push('}');
+
+            RichText {
+                html: format!("<pre>{}</pre>", escape(&plain)),
+                plain,
+            }
+        }
+    }
+}
+
+/// A quotation from the book, followed by where it's from.
+pub fn quote(text: &str, book: &Book, chapter: Option<&str>) -> RichText {
+    let title = book.title.trim();
+    let author = book.author.trim();
+
+    let mut source = Vec::new();
+    let mut source_html = Vec::new();
+
+    if !author.is_empty() {
+        source.push(author.to_string());
+        source_html.push(escape(author));
+    }
+
+    source.push(title.to_string());
+    source_html.push(format!("<i>{}</i>", escape(title)));
+
+    if let Some(chapter) = chapter.filter(|chapter| !chapter.trim().is_empty()) {
+        source.push(chapter.trim().to_string());
+        source_html.push(escape(chapter.trim()));
+    }
+
+    let paragraphs: Vec<&str> = text
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect();
+
+    let html = paragraphs
+        .iter()
+        .map(|paragraph| format!("<p>{}</p>", escape(paragraph)))
+        .collect::<String>();
+
+    RichText {
+        plain: format!("“{}”\n— {}", paragraphs.join("\n\n"), source.join(", ")),
+        html: format!(
+            "<blockquote>{html}</blockquote><p>— {}</p>",
+            source_html.join(", ")
+        ),
+    }
+}
+
+/// The book's authors, split from the one field they're kept in.
+fn authors(author: &str) -> Vec<&str> {
+    author
+        .split([';', '&'])
+        .flat_map(|names| names.split(" and "))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// The name with the family name first, as "Austen, Jane", unless it already is.
+fn inverted(name: &str) -> String {
+    if name.contains(',') {
+        return name.to_string();
+    }
+
+    match name.rsplit_once(' ') {
+        Some((given, family)) => format!("{family}, {given}"),
+        None => name.to_string(),
+    }
+}
+
+/// The name as APA gives it, the family name followed by initials: "Austen, J.".
+fn apa_name(name: &str) -> String {
+    let inverted = inverted(name);
+
+    let Some((family, given)) = inverted.split_once(", ") else {
+        return inverted;
+    };
+
+    let initials = given
+        .split([' ', '.'])
+        .filter_map(|part| part.chars().next())
+        .map(|initial| format!("{initial}."))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!("{family}, {initials}")
+}
+
+/// Names joined with commas, with `conjunction` before the last of several.
+fn list(names: &[String], conjunction: &str) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{}, {conjunction} {last}", rest.join(", ")),
+    }
+}
+
+/// The text ending with a single period.
+fn with_period(text: &str) -> String {
+    if text.ends_with('.') {
+        text.to_string()
+    } else {
+        format!("{text}.")
+    }
+}
+
+/// A key for the entry, from the first author's family name and the title's first word.
+fn bibtex_key(authors: &[&str], title: &str) -> String {
+    let family = authors
+        .first()
+        .map(|name| inverted(name))
+        .and_then(|name| name.split(',').next().map(str::to_string))
+        .unwrap_or_default();
+
+    let word = title.split_whitespace().next().unwrap_or_default();
+
+    let key: String = format!("{family}{word}")
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+
+    if key.is_empty() {
+        "book".to_string()
+    } else {
+        key
+    }
+}
+
+/// Escapes the characters that BibTeX treats specially.
+fn bibtex_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The command line libby is started with.
+
+use crate::export::{self, Field};
+use crate::headless;
+use crate::library::{Progress, Query, Sort};
+use clap::{CommandFactory, Parser};
+use std::io;
+use std::path::PathBuf;
+
+/// Reads and keeps a library of books.
+#[derive(Debug, Parser)]
+#[command(name = "libby", version)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Books to open, which are added to the library if they aren't in it yet.
+    pub files: Vec<PathBuf>,
+    /// Page to open the first book at, counting from 1; for EPUBs, the chapter.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub page: Option<u32>,
+    /// Catalog to browse, by the name it was added with.
+    #[arg(long, value_name = "NAME")]
+    pub library: Option<String>,
+    /// Start fullscreen, with the header and navigation bar hidden.
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Work on the library without starting the app, with `import` or `verify`.
+    #[arg(long, global = true)]
+    pub headless: bool,
+    /// Print what commands find or do as JSON, for scripts.
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Keep the library up to date in the background while the app isn't open, importing
+    /// books from watched folders and syncing the catalog.
+    #[arg(long, conflicts_with_all = ["files", "headless"])]
+    pub daemon: bool,
+    /// Folder the daemon imports new books from; may be given more than once.
+    #[arg(long, value_name = "DIR", requires = "daemon")]
+    pub watch: Vec<PathBuf>,
+    /// Log more of what goes on; given twice, everything. `RUST_LOG` overrides it.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Log slow queries of the library, with how they were answered.
+    #[arg(long, global = true)]
+    pub profile_queries: bool,
+    /// Show the interface accented and lengthened, to find strings cut off or not
+    /// translated.
+    #[arg(long)]
+    pub pseudo_locale: bool,
+    /// Run as the panel applet, as the panel starts it.
+    #[cfg(feature = "applet")]
+    #[arg(long, hide = true)]
+    pub applet: bool,
+}
+
+/// Exit status for a command line that couldn't be understood, as in `sysexits.h`.
+pub const USAGE: i32 = 64;
+
+/// How a command ended, as its exit status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Everything asked for was done.
+    Done,
+    /// Nothing could be done, such as when the catalog couldn't be saved.
+    Failed,
+    /// Some books failed, or were found with problems, while the rest went through.
+    Partial,
+}
+
+impl Outcome {
+    /// Outcome of work on `total` books of which `failed` failed.
+    pub fn of(failed: usize, total: usize) -> Self {
+        if failed == 0 {
+            Outcome::Done
+        } else if failed < total {
+            Outcome::Partial
+        } else {
+            Outcome::Failed
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            Outcome::Done => 0,
+            Outcome::Failed => 1,
+            Outcome::Partial => 2,
+        }
+    }
+}
+
+/// Something to do instead of starting the app.
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Print a script completing libby's arguments in a shell.
+    Completions { shell: clap_complete::Shell },
+    /// Print libby's man page.
+    Man,
+    /// Copy the books in a folder and its folders into the library.
+    Import {
+        /// Folder to import, or `-` to import the files and folders listed on standard
+        /// input, one a line.
+        source: PathBuf,
+    },
+    /// Check the library's book files against the hashes recorded for them.
+    Verify {
+        /// Hash every file, even those unchanged since they were last checked.
+        #[arg(long)]
+        full: bool,
+    },
+    /// List books in the library, one a line, with their fields separated by tabs.
+    Query {
+        #[command(flatten)]
+        filter: Filter,
+        /// Field to list, in order; may be given more than once.
+        #[arg(long = "field", value_name = "FIELD", default_values = ["title", "author"])]
+        fields: Vec<Field>,
+    },
+    /// Write books in the library out as CSV or JSON.
+    Export {
+        #[command(flatten)]
+        filter: Filter,
+        #[arg(long, value_enum, default_value = "csv")]
+        format: export::Format,
+        /// Field to write, in order, instead of all of them; may be given more than once.
+        #[arg(long = "field", value_name = "FIELD")]
+        fields: Vec<Field>,
+        /// File to write to, instead of standard output.
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Which books of the library a command covers, and in what order; each condition
+/// given has to match.
+#[derive(Debug, clap::Args)]
+pub struct Filter {
+    /// Books with this tag; may be given more than once.
+    #[arg(long, value_name = "TAG")]
+    pub tag: Vec<String>,
+    /// Books by an author whose name contains this.
+    #[arg(long, value_name = "NAME")]
+    pub author: Option<String>,
+    /// Books in this series.
+    #[arg(long, value_name = "NAME")]
+    pub series: Option<String>,
+    /// Books in this collection.
+    #[arg(long, value_name = "NAME")]
+    pub collection: Option<String>,
+    /// Books mentioning this in their title, author, description, tags or notes.
+    #[arg(long, value_name = "TEXT")]
+    pub search: Option<String>,
+    /// Books not started yet.
+    #[arg(long, conflicts_with_all = ["reading", "finished"])]
+    pub unread: bool,
+    /// Books started but not finished.
+    #[arg(long, conflicts_with = "finished")]
+    pub reading: bool,
+    /// Books read to the end.
+    #[arg(long)]
+    pub finished: bool,
+    /// Order to list the books in.
+    #[arg(long, value_enum, default_value_t = Sort::Added)]
+    pub sort: Sort,
+}
+
+impl Filter {
+    /// The query of the library asking for these books; books in the trash never match.
+    pub fn query(&self) -> Query {
+        let progress = if self.unread {
+            Some(Progress::Unread)
+        } else if self.reading {
+            Some(Progress::Reading)
+        } else if self.finished {
+            Some(Progress::Finished)
+        } else {
+            None
+        };
+
+        Query {
+            tags: self.tag.clone(),
+            author: self.author.clone(),
+            series: self.series.clone(),
+            collection: self.collection.clone(),
+            text: self.search.clone(),
+            progress,
+            sort: self.sort,
+        }
+    }
+}
+
+impl Command {
+    /// Does what the command asks, printing to standard output; commands working on the
+    /// library print JSON with `json`.
+    pub fn run(self, json: bool) -> Outcome {
+        let mut command = Args::command();
+
+        let printed = match self {
+            Command::Completions { shell } => {
+                clap_complete::generate(shell, &mut command, "libby", &mut io::stdout());
+                Ok(())
+            }
+            Command::Man => clap_mangen::Man::new(command).render(&mut io::stdout()),
+            Command::Import { source } => return headless::import(source, json),
+            Command::Verify { full } => return headless::verify(full, json),
+            Command::Query { filter, fields } => return headless::query(&filter, &fields, json),
+            Command::Export {
+                filter,
+                format,
+                fields,
+                output,
+            } => return headless::export(&filter, &fields, format, output, json),
+        };
+
+        match printed {
+            Ok(()) => Outcome::Done,
+            Err(why) => {
+                tracing::error!("failed to print: {why}");
+                Outcome::Failed
+            }
+        }
+    }
+}
+
+impl Args {
+    /// The books to open, made absolute so the running instance finds them.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter_map(|file| std::path::absolute(file).ok())
+            .collect()
+    }
+}
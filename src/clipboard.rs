@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Writing text to the clipboard both as HTML, for word processors and email, and as
+//! plain text for everything else.
+
+use cosmic::iced::clipboard::mime::AsMimeTypes;
+use cosmic::Task;
+use std::borrow::Cow;
+
+const HTML: &str = "text/html";
+
+const TEXT: [&str; 3] = ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING"];
+
+/// Text with a formatted form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RichText {
+    pub plain: String,
+    /// HTML fragment, which is all that's written to the clipboard.
+    pub html: String,
+}
+
+impl AsMimeTypes for RichText {
+    fn available(&self) -> Cow<'static, [String]> {
+        [HTML].into_iter().chain(TEXT).map(String::from).collect()
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        if mime_type == HTML {
+            Some(self.html.clone().into_bytes().into())
+        } else if TEXT.contains(&mime_type) {
+            Some(self.plain.clone().into_bytes().into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes the text to the clipboard, replacing what's there.
+pub fn write<Message>(text: RichText) -> Task<Message> {
+    cosmic::iced::clipboard::write_data(text)
+}
+
+/// Escapes text to be put in HTML.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
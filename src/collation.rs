@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Orders titles and names the way the language of the interface does, so that
+//! accented letters sort beside their base letter, or apart where the language counts
+//! them as letters of their own, as "Å" after "Z" in Swedish, and other scripts sort
+//! as their readers expect.
+
+use crate::i18n;
+use icu_collator::options::{CollatorOptions, Strength};
+use icu_collator::{Collator, CollatorBorrowed, CollatorPreferences};
+use icu_locale_core::Locale;
+use std::cmp::Ordering;
+
+/// Collators for the language of the interface.
+pub struct Collation {
+    /// Orders text, telling apart what differs only in accents or case last.
+    order: CollatorBorrowed<'static>,
+    /// Tells letters apart only where the language counts them as different letters.
+    letters: CollatorBorrowed<'static>,
+}
+
+impl Collation {
+    /// Collation for the language the interface is shown in, or the root collation,
+    /// which suits most languages, if there's none for it.
+    pub fn new() -> Self {
+        let language = i18n::language();
+        let locale = language
+            .parse::<Locale>()
+            .inspect_err(|why| tracing::warn!("can't sort as {language} does: {why}"))
+            .ok();
+
+        let mut letters = CollatorOptions::default();
+        letters.strength = Some(Strength::Primary);
+
+        Collation {
+            order: collator(locale.as_ref(), CollatorOptions::default()),
+            letters: collator(locale.as_ref(), letters),
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.order.compare(a, b)
+    }
+
+    /// Whether `a` and `b` are grouped under the same letter.
+    pub fn same_initial(&self, a: &str, b: &str) -> bool {
+        self.letters.compare(&initial(a), &initial(b)) == Ordering::Equal
+    }
+}
+
+impl Default for Collation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Collation").finish_non_exhaustive()
+    }
+}
+
+fn collator(locale: Option<&Locale>, options: CollatorOptions) -> CollatorBorrowed<'static> {
+    let preferences = locale.map(CollatorPreferences::from).unwrap_or_default();
+
+    // The collation data is built in, with the root collation for languages without one
+    // of their own, so this can't fail.
+    Collator::try_new(preferences, options).expect("collation data is built in")
+}
+
+/// The letter `text` is grouped under: its first letter in uppercase, or "#" if it
+/// starts with something else, such as a digit.
+pub fn initial(text: &str) -> String {
+    match text.trim_start().chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().collect(),
+        _ => "#".to_string(),
+    }
+}
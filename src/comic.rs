@@ -0,0 +1,362 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Books made of page images: comic archives and fixed-layout EPUBs.
+
+use crate::epub::{self, Epub};
+use crate::mapped;
+use crate::pdf::Bitmap;
+use crate::toc;
+use image::{DynamicImage, GrayImage};
+use std::cmp::Ordering;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Extensions of comic archives, which always open in the comic reader.
+pub const EXTENSIONS: [&str; 2] = ["cbz", "cbr"];
+
+/// Extensions of the page images read from archives.
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "gif", "webp"];
+
+/// Longest side pages are scaled down to, keeping memory in check for huge scans.
+const MAX_SIDE: u32 = 3200;
+
+/// Longest side of the copy of a page searched for panels.
+const PANEL_SCAN_SIDE: u32 = 400;
+
+/// Difference in brightness from the page's background still counted as a gutter.
+const GUTTER_TOLERANCE: u8 = 32;
+
+/// A comic open in the reader.
+#[derive(Debug, Clone)]
+pub struct Comic {
+    source: Source,
+    /// Archive names of the page images, in reading order; unused for EPUBs.
+    pages: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Zip(PathBuf),
+    /// Read through the `unrar` command, as there is no pure Rust RAR decoder.
+    Rar(PathBuf),
+    /// A fixed-layout EPUB, with the image of each spine document as a page.
+    Epub(Epub),
+}
+
+/// A decoded page, along with its panels when asked for.
+pub struct Page {
+    pub image: Bitmap,
+    /// The page cut into its panels in reading order, or empty when no panels were found.
+    pub panels: Vec<Bitmap>,
+}
+
+impl std::fmt::Debug for Page {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Page")
+            .field("image", &self.image)
+            .field("panels", &self.panels.len())
+            .finish()
+    }
+}
+
+impl Comic {
+    /// Lists the pages of the CBZ or CBR archive at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let rar = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("cbr") || ext.eq_ignore_ascii_case("rar"));
+
+        let (source, names) = if rar {
+            (Source::Rar(path.to_path_buf()), rar_names(path)?)
+        } else {
+            let archive = zip::ZipArchive::new(mapped::open(path)?).map_err(io::Error::other)?;
+            let names = archive.file_names().map(str::to_string).collect();
+            (Source::Zip(path.to_path_buf()), names)
+        };
+
+        let mut pages: Vec<String> = names.into_iter().filter(|name| is_page(name)).collect();
+        pages.sort_by(|a, b| natural_cmp(a, b));
+
+        if pages.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no page images in archive",
+            ));
+        }
+
+        Ok(Self { source, pages })
+    }
+
+    pub fn from_epub(epub: Epub) -> Self {
+        Self {
+            source: Source::Epub(epub),
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        match &self.source {
+            Source::Epub(epub) => epub.chapter_count(),
+            Source::Zip(_) | Source::Rar(_) => self.pages.len(),
+        }
+    }
+
+    /// The EPUB's table of contents, whose chapters are pages; archives have none.
+    pub fn toc(&self) -> &[toc::Entry] {
+        match &self.source {
+            Source::Epub(epub) => &epub.toc,
+            Source::Zip(_) | Source::Rar(_) => &[],
+        }
+    }
+
+    /// Reads the encoded image of a page.
+    fn read(&self, index: usize) -> io::Result<Vec<u8>> {
+        let not_found = || io::Error::new(io::ErrorKind::NotFound, "page out of range");
+
+        match &self.source {
+            Source::Zip(path) => {
+                let name = self.pages.get(index).ok_or_else(not_found)?;
+                let mut archive =
+                    zip::ZipArchive::new(mapped::open(path)?).map_err(io::Error::other)?;
+                let entry = archive.by_name(name).map_err(io::Error::other)?;
+                let size = entry.size();
+                epub::read_limited(entry, size)
+            }
+            Source::Rar(path) => {
+                let name = self.pages.get(index).ok_or_else(not_found)?;
+                let output = Command::new("unrar")
+                    .args(["p", "-inul", "--"])
+                    .arg(path)
+                    .arg(name)
+                    .output()?;
+
+                if !output.status.success() || output.stdout.is_empty() {
+                    return Err(io::Error::other(format!("unrar failed to extract {name}")));
+                }
+
+                Ok(output.stdout)
+            }
+            Source::Epub(epub) => {
+                let chapter = epub.chapter(index).map_err(io::Error::other)?;
+                let image = chapter.images.first().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "no image on page")
+                })?;
+
+                epub.image(image).map_err(io::Error::other)
+            }
+        }
+    }
+
+    /// Decodes a page, cutting it into panels when `panels` is set. Panels run from
+    /// right to left within each row when `right_to_left` is set.
+    pub fn page(&self, index: usize, panels: bool, right_to_left: bool) -> io::Result<Page> {
+        let image = image::load_from_memory(&self.read(index)?)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+
+        let image = if image.width().max(image.height()) > MAX_SIDE {
+            image.thumbnail(MAX_SIDE, MAX_SIDE)
+        } else {
+            image
+        };
+
+        let panels = if panels {
+            find_panels(&image, right_to_left)
+                .into_iter()
+                .map(|(x, y)| {
+                    bitmap(&image.crop_imm(x.start, y.start, x.len() as u32, y.len() as u32))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Page {
+            image: bitmap(&image),
+            panels,
+        })
+    }
+}
+
+fn bitmap(image: &DynamicImage) -> Bitmap {
+    let rgba = image.to_rgba8();
+
+    Bitmap {
+        width: rgba.width(),
+        height: rgba.height(),
+        pixels: rgba.into_raw(),
+    }
+}
+
+fn is_page(name: &str) -> bool {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+
+    !name.starts_with("__MACOSX/")
+        && !file_name.starts_with('.')
+        && file_name.rsplit_once('.').is_some_and(|(_, ext)| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|image| ext.eq_ignore_ascii_case(image))
+        })
+}
+
+/// Lists the files of a RAR archive.
+fn rar_names(path: &Path) -> io::Result<Vec<String>> {
+    let output = Command::new("unrar")
+        .args(["lb", "--"])
+        .arg(path)
+        .output()
+        .map_err(|why| match why.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(why.kind(), "reading CBR files needs unrar to be installed")
+            }
+            _ => why,
+        })?;
+
+    if !output.status.success() {
+        return Err(io::Error::other("unrar failed to list the archive"));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Compares file names with runs of digits as numbers, so `page2` sorts before `page10`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let ordering = number(&mut a).cmp(&number(&mut b));
+
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_ascii_lowercase().cmp(&y.to_ascii_lowercase());
+
+                if ordering.is_ne() {
+                    return ordering;
+                }
+
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// Takes a run of digits, as its length without leading zeros and the digits, which
+/// order like the numbers they spell without overflowing.
+fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> (usize, String) {
+    let mut digits = String::new();
+
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+
+    let digits = digits.trim_start_matches('0').to_string();
+    (digits.len(), digits)
+}
+
+/// Finds panels by cutting the page along gutters, first into rows and then each row
+/// into panels, returning their column and row ranges in reading order.
+fn find_panels(image: &DynamicImage, right_to_left: bool) -> Vec<(Range<u32>, Range<u32>)> {
+    let scan = image.thumbnail(PANEL_SCAN_SIDE, PANEL_SCAN_SIDE).to_luma8();
+    let (width, height) = scan.dimensions();
+
+    if width < 8 || height < 8 {
+        return Vec::new();
+    }
+
+    let background = background(&scan);
+    let blank = |x: u32, y: u32| scan.get_pixel(x, y)[0].abs_diff(background) <= GUTTER_TOLERANCE;
+
+    let mut panels = Vec::new();
+
+    for rows in runs(0..height, height / 20, |y| (0..width).all(|x| blank(x, y))) {
+        let mut row: Vec<_> = runs(0..width, width / 20, |x| rows.clone().all(|y| blank(x, y)))
+            .into_iter()
+            .filter_map(|columns| {
+                // Trim the gutter above and below a panel shorter than its row.
+                let rows = runs(rows.clone(), 1, |y| columns.clone().all(|x| blank(x, y)));
+                let top = rows.first()?.start;
+                let bottom = rows.last()?.end;
+                Some((columns, top..bottom))
+            })
+            .collect();
+
+        if right_to_left {
+            row.reverse();
+        }
+
+        panels.extend(row);
+    }
+
+    if panels.len() < 2 {
+        return Vec::new();
+    }
+
+    // Scale back up to the page, with a little margin around each panel.
+    let scale_x = image.width() as f32 / width as f32;
+    let scale_y = image.height() as f32 / height as f32;
+    let scale = |range: Range<u32>, scale: f32, max: u32| {
+        let start = ((range.start.saturating_sub(1)) as f32 * scale) as u32;
+        let end = (((range.end + 1) as f32 * scale) as u32).min(max);
+        start..end
+    };
+
+    panels
+        .into_iter()
+        .map(|(x, y)| {
+            (
+                scale(x, scale_x, image.width()),
+                scale(y, scale_y, image.height()),
+            )
+        })
+        .collect()
+}
+
+/// Brightness of the page's margins, taken as the color of its gutters.
+fn background(scan: &GrayImage) -> u8 {
+    let (width, height) = scan.dimensions();
+    let mut border: Vec<u8> = (0..width)
+        .flat_map(|x| [scan.get_pixel(x, 0)[0], scan.get_pixel(x, height - 1)[0]])
+        .chain((0..height).flat_map(|y| [scan.get_pixel(0, y)[0], scan.get_pixel(width - 1, y)[0]]))
+        .collect();
+
+    border.sort_unstable();
+    border[border.len() / 2]
+}
+
+/// Splits the range into the runs of lines which are not blank, dropping runs shorter
+/// than `min`.
+fn runs(range: Range<u32>, min: u32, is_blank: impl Fn(u32) -> bool) -> Vec<Range<u32>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    let end = range.end;
+
+    for line in range.chain([end]) {
+        match (start, line == end || is_blank(line)) {
+            (None, false) => start = Some(line),
+            (Some(from), true) => {
+                if line - from >= min.max(1) {
+                    runs.push(from..line);
+                }
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    runs
+}
@@ -0,0 +1,417 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Syncs the catalog and reading positions with a companion server: a self-hosted libby
+//! server, or another libby instance letting devices sync with it through [`host`].
+//!
+//! Devices exchange changes to the catalog rather than the whole of it, merging what
+//! they pull three-way as described in [`catalog`].
+//!
+//! # Protocol
+//!
+//! Version 1 of the protocol is JSON over HTTP, under `/v1` on the server:
+//!
+//! - `POST /v1/pair` with `{"code": "123456", "device": "laptop"}` trades the pairing
+//!   code shown by the server for a token, answering `{"token": "…"}`. Every other
+//!   request carries the token as `Authorization: Bearer <token>`.
+//! - `GET /v1/changes?since=<cursor>` answers `{"cursor": 42, "changes": […]}`: the
+//!   books changed since the cursor, which starts at 0, and the cursor to pass next.
+//! - `POST /v1/changes` with `{"since": 42, "changes": […]}` records the changes made
+//!   on a device, answering `{"cursor": 43}`; or `409 Conflict` if other changes were
+//!   recorded after `since`, which the device pulls and merges before trying again.
+//! - `GET /v1/events` upgrades to a WebSocket on which the server sends
+//!   `{"cursor": 43}` each time changes are recorded, for devices to pull them.
+//!
+//! A change is `{"key": "…", "entry": {…}}`: a book's [`Entry`], reading position
+//! included, under its [`sync::key`], or a `null` entry for a deleted book.
+//!
+//! Servers show their address and pairing code as a [`link`], also as a QR code, for
+//! devices to pair by.
+//!
+//! [`host`]: crate::host
+//! [`sync::key`]: crate::sync::key
+
+use crate::catalog::{self, Catalog, Entry, Outcome};
+use crate::host::Host;
+use crate::library::Book;
+use crate::net;
+use crate::sync::Resolution;
+use futures_util::{SinkExt, Stream, StreamExt};
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+/// Port servers listen on unless told otherwise.
+pub const PORT: u16 = 7316;
+
+/// How often the catalog is synced in the background while paired or hosting, besides
+/// when the server tells of changes.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Key the token from pairing is stored under in the keyring.
+pub const TOKEN_KEY: &str = "companion-token";
+
+/// Times changes are pulled and merged again when other devices keep pushing theirs
+/// first.
+const ATTEMPTS: usize = 3;
+
+/// Wait before connecting again when the server's events are cut off.
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A book changed on a device, or deleted if `entry` is `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub key: String,
+    pub entry: Option<Entry>,
+}
+
+/// Body of `POST /v1/pair`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pair {
+    pub code: String,
+    /// Name the device is listed under on the server.
+    pub device: String,
+}
+
+/// Answer to `POST /v1/pair`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paired {
+    pub token: String,
+}
+
+/// Answer to `GET /v1/changes`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Changes {
+    pub cursor: u64,
+    pub changes: Vec<Change>,
+}
+
+/// Body of `POST /v1/changes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Push {
+    /// Cursor the device pulled up to before merging.
+    pub since: u64,
+    pub changes: Vec<Change>,
+}
+
+/// Answer to `POST /v1/changes`, and the events sent on `/v1/events`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Cursor {
+    pub cursor: u64,
+}
+
+/// Where the catalog is synced with.
+#[derive(Debug, Clone)]
+pub enum Remote {
+    /// A server this device is paired with.
+    Server { url: String, token: String },
+    /// The server this instance hosts for other devices.
+    Local(Host),
+}
+
+impl Remote {
+    /// Tells remotes apart in the record of the last sync.
+    fn id(&self) -> &str {
+        match self {
+            Remote::Server { url, .. } => url,
+            Remote::Local(_) => "local",
+        }
+    }
+
+    async fn pull(&self, since: u64) -> Result<Changes, String> {
+        let (url, token) = match self {
+            Remote::Server { url, token } => (url, token),
+            Remote::Local(host) => return Ok(host.pull(since)),
+        };
+
+        let request = net::client()
+            .get(endpoint(url, "changes"))
+            .query(&[("since", since)])
+            .bearer_auth(token);
+
+        net::send(request)
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|why| why.to_string())?
+            .json()
+            .await
+            .map_err(|why| why.to_string())
+    }
+
+    /// Records changes, returning the new cursor, or `None` if others were recorded
+    /// first.
+    async fn push(&self, push: Push) -> Result<Option<u64>, String> {
+        let (url, token) = match self {
+            Remote::Server { url, token } => (url, token),
+            Remote::Local(host) => return Ok(host.push(push)),
+        };
+
+        let request = net::client()
+            .post(endpoint(url, "changes"))
+            .bearer_auth(token)
+            .json(&push);
+
+        let response = net::send(request).await.map_err(|why| why.to_string())?;
+
+        if response.status() == StatusCode::CONFLICT {
+            return Ok(None);
+        }
+
+        let pushed: Cursor = response
+            .error_for_status()
+            .map_err(|why| why.to_string())?
+            .json()
+            .await
+            .map_err(|why| why.to_string())?;
+
+        Ok(Some(pushed.cursor))
+    }
+}
+
+fn endpoint(url: &str, path: &str) -> String {
+    format!("{}/v1/{path}", url.trim().trim_end_matches('/'))
+}
+
+/// The link a server shows for devices to pair with it.
+pub fn link(url: &str, code: &str) -> String {
+    format!("libby://pair?server={url}&code={code}")
+}
+
+/// The server address and pairing code of a [`link`].
+pub fn parse_link(link: &str) -> Option<(String, String)> {
+    let query = link.trim().strip_prefix("libby://pair?")?;
+    let mut server = None;
+    let mut code = None;
+
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("server", value)) => server = Some(value.to_string()),
+            Some(("code", value)) => code = Some(value.to_string()),
+            _ => (),
+        }
+    }
+
+    Some((server?, code?))
+}
+
+/// Pairs with the server at `url` using the code it shows, returning the token to sign
+/// requests with.
+pub async fn pair(url: String, code: String, device: String) -> Result<String, String> {
+    let request = net::client().post(endpoint(&url, "pair")).json(&Pair {
+        code: code.trim().to_string(),
+        device,
+    });
+
+    let response = net::send(request).await.map_err(|why| why.to_string())?;
+
+    match response.status() {
+        StatusCode::FORBIDDEN => {
+            return Err(
+                "the pairing code is wrong or has expired, or pairing was declined".to_string(),
+            )
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            return Err("too many wrong pairing codes, try again later".to_string())
+        }
+        StatusCode::CONFLICT => {
+            return Err("another device is pairing, try again in a moment".to_string())
+        }
+        _ => (),
+    }
+
+    let paired: Paired = response
+        .error_for_status()
+        .map_err(|why| why.to_string())?
+        .json()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    Ok(paired.token)
+}
+
+/// Where the catalog stood after the last sync.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Base {
+    remote: String,
+    cursor: u64,
+    catalog: Catalog,
+}
+
+/// Brings the remote's catalog and the library up to date with each other, returning
+/// what the library should take from the remote.
+pub async fn sync(
+    remote: Remote,
+    books: Vec<Book>,
+    device: String,
+    resolution: Resolution,
+) -> Result<Outcome, String> {
+    let base = load_base()
+        .await
+        .filter(|base| base.remote == remote.id())
+        .unwrap_or_default();
+    let ours = Catalog::new(&books, &device);
+
+    // The remote's catalog, as far as this device has pulled it.
+    let mut theirs = base.catalog.clone();
+    let mut cursor = base.cursor;
+    let mut attempt = 0;
+
+    let (merged, conflicts) = loop {
+        attempt += 1;
+
+        let pulled = remote.pull(cursor).await?;
+        apply(&mut theirs, pulled.changes);
+        cursor = pulled.cursor;
+
+        let (merged, conflicts) = catalog::merge(&base.catalog, &ours, &theirs, resolution);
+        let changes = diff(&theirs, &merged);
+
+        if changes.is_empty() {
+            break (merged, conflicts);
+        }
+
+        if let Some(pushed) = remote
+            .push(Push {
+                since: cursor,
+                changes,
+            })
+            .await?
+        {
+            cursor = pushed;
+            break (merged, conflicts);
+        }
+
+        if attempt == ATTEMPTS {
+            return Err("the catalog kept changing on the server".to_string());
+        }
+    };
+
+    let base = Base {
+        remote: remote.id().to_string(),
+        cursor,
+        catalog: merged.clone(),
+    };
+
+    if let Err(why) = save_base(&base).await {
+        tracing::error!("failed to record the synced catalog: {why}");
+    }
+
+    Ok(catalog::outcome(&books, &ours, merged, conflicts))
+}
+
+/// Applies changes pulled from a remote to what is known of its catalog.
+fn apply(catalog: &mut Catalog, changes: Vec<Change>) {
+    for change in changes {
+        match change.entry {
+            Some(entry) => catalog.books.insert(change.key, entry),
+            None => catalog.books.remove(&change.key),
+        };
+    }
+}
+
+/// The changes turning `from` into `to`.
+fn diff(from: &Catalog, to: &Catalog) -> Vec<Change> {
+    let changed = to
+        .books
+        .iter()
+        .filter(|(key, entry)| from.books.get(*key) != Some(entry))
+        .map(|(key, entry)| Change {
+            key: key.clone(),
+            entry: Some(entry.clone()),
+        });
+
+    let deleted = from
+        .books
+        .keys()
+        .filter(|key| !to.books.contains_key(*key))
+        .map(|key| Change {
+            key: key.clone(),
+            entry: None,
+        });
+
+    changed.chain(deleted).collect()
+}
+
+/// Yields the server's cursor each time it records changes, reconnecting when the
+/// connection drops.
+pub fn events(url: String, token: String) -> impl Stream<Item = u64> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        loop {
+            if let Err(why) = listen(&url, &token, &mut output).await {
+                tracing::warn!("lost the companion server's events: {why}");
+            }
+
+            if output.is_closed() {
+                break;
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+async fn listen(
+    url: &str,
+    token: &str,
+    output: &mut cosmic::iced::futures::channel::mpsc::Sender<u64>,
+) -> Result<(), String> {
+    let url = endpoint(url, "events")
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1);
+
+    let mut request = url.into_client_request().map_err(|why| why.to_string())?;
+    let authorization = format!("Bearer {token}")
+        .parse()
+        .map_err(|_| "the token can't be sent".to_string())?;
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, authorization);
+
+    let mut socket = net::websocket(request).await?;
+
+    while let Some(message) = socket.next().await {
+        let text = match message.map_err(|why| why.to_string())? {
+            tungstenite::Message::Text(text) => text,
+            tungstenite::Message::Close(_) => break,
+            _ => continue,
+        };
+
+        match serde_json::from_str::<Cursor>(&text) {
+            Ok(event) => {
+                if output.send(event.cursor).await.is_err() {
+                    break;
+                }
+            }
+            Err(why) => tracing::warn!("skipping malformed companion event: {why}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn base_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("companion.json"))
+}
+
+async fn load_base() -> Option<Base> {
+    let bytes = tokio::fs::read(base_path()?).await.ok()?;
+
+    match serde_json::from_slice(&bytes) {
+        Ok(base) => Some(base),
+        Err(why) => {
+            tracing::error!("failed to parse the last synced catalog: {why}");
+            None
+        }
+    }
+}
+
+async fn save_base(base: &Base) -> std::io::Result<()> {
+    let path = base_path().ok_or(std::io::ErrorKind::NotFound)?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    tokio::fs::write(path, serde_json::to_vec(base)?).await
+}
@@ -1,10 +1,172 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use crate::follow::Followed;
+use crate::palette::{CustomPalette, Palette};
+use crate::rss;
+use crate::shortcuts::Shortcuts;
+use crate::sources::Source;
+use crate::sync::Resolution;
+use crate::typography::Typography;
+use crate::update::Frequency;
+use crate::webhook::Webhook;
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
     demo: String,
     pub username: String,
+    /// Language of the interface, such as "de" or "pt-BR", or empty for the system's.
+    pub language: String,
+    /// Languages for strings the language leaves out, in order and separated by commas,
+    /// before English.
+    pub language_fallback: String,
+    /// Move the files of deleted books into the trash folder.
+    pub trash_files: bool,
+    /// Speech rate for reading aloud, from -100 to 100.
+    pub speech_rate: i32,
+    /// Voice for reading aloud, or empty for the synthesizer's default.
+    pub speech_voice: String,
+    /// Typography of books without their own.
+    pub typography: Typography,
+    /// Colors of the reading area.
+    pub palette: Palette,
+    /// Colors used by [`Palette::Custom`].
+    pub custom_palette: CustomPalette,
+    /// When the reader shows two pages side by side.
+    pub spread: Spread,
+    /// Look words up on Wiktionary when no local dictionary has them.
+    pub dictionary_online: bool,
+    /// Address of the LibreTranslate server used to translate passages.
+    pub translate_url: String,
+    /// API key for the translation server, if it requires one.
+    pub translate_api_key: String,
+    /// Language code translated from, or empty to detect it.
+    pub translate_source: String,
+    /// Language code translated into, or empty for English.
+    pub translate_target: String,
+    /// Keys and gestures bound to reader actions.
+    pub shortcuts: Shortcuts,
+    /// Folder shared between devices to sync reading positions through, or empty to not
+    /// sync them.
+    pub sync_folder: String,
+    /// Which position wins when devices disagree about a book.
+    pub sync_resolution: Resolution,
+    /// Words per minute of speed reading, or 0 for the default.
+    pub rsvp_wpm: u32,
+    /// Feed shown on the feed page, as typed there: a link to a profile or custom feed,
+    /// an AT URI or a handle, or empty for the timeline.
+    pub feed: String,
+    /// Accounts followed from the feed page.
+    pub followed: Vec<Followed>,
+    /// Keep reading progress, finished books and ratings as records on the signed in
+    /// account's PDS.
+    pub pds_sync: bool,
+    /// Keys of the PDS records of books deleted since the last sync.
+    pub pds_deleted: Vec<String>,
+    /// WebDAV folder, such as one on a Nextcloud server, to sync the catalog through, or
+    /// empty to not sync it.
+    pub webdav_url: String,
+    pub webdav_user: String,
+    /// Sync the catalog with the WebDAV server in the background.
+    pub webdav_scheduled: bool,
+    /// Bandwidth shared by downloads, in KiB per second, or 0 for no limit.
+    pub download_limit: u32,
+    /// Most the cache of pages and covers holds, in MiB, or 0 for the default.
+    pub cache_limit: u32,
+    /// Proxy for network requests, or empty to use the one in the environment, if any.
+    pub proxy: String,
+    /// PEM file of certificates to trust besides the built-in ones, or empty for only
+    /// those.
+    pub certificates: String,
+    /// Catalogs to browse and download books from.
+    pub sources: Vec<Source>,
+    /// Addresses sent a payload when something happens in libby.
+    pub webhooks: Vec<Webhook>,
+    /// RSS and Atom feeds followed on the news page.
+    pub news: Vec<rss::Followed>,
+    /// Address of the companion server this device is paired with, or empty if none.
+    pub companion_url: String,
+    /// Let other devices pair and sync with this one.
+    pub companion_host: bool,
+    /// How often to check for a newer version of libby.
+    pub update_check: Frequency,
+    /// Seconds since the Unix epoch at which a newer version was last checked for.
+    pub update_checked: u64,
+    /// Offer to send crash reports to `crash_endpoint` on the launch after a crash.
+    pub crash_reports: bool,
+    /// Address crash reports are sent to.
+    pub crash_endpoint: String,
+    /// Share the library with other instances of libby on the local network.
+    pub lan_sharing: bool,
+    /// Show an icon in the panel's tray with quick actions.
+    pub tray: bool,
+    /// Hide the window to the tray when it's closed, rather than quitting.
+    pub close_to_tray: bool,
+    /// Raise desktop notifications when imports and syncs finish while the window isn't
+    /// focused.
+    pub notifications: bool,
+    /// Bind shortcuts that summon the window and its search from anywhere.
+    pub global_shortcuts: bool,
+    /// Keys asked for to summon the window, or empty to leave them to the user.
+    pub summon_trigger: String,
+    /// Keys asked for to summon the search, or empty to leave them to the user.
+    pub search_trigger: String,
+    /// Fold the menus into a single button and leave the page's title out of the header,
+    /// for small windows and tiling layouts.
+    pub compact_header: bool,
+    /// Whether the app is light or dark.
+    pub app_theme: AppTheme,
+    /// Keep animating and working in the background at full speed on battery and in
+    /// power-saver mode.
+    pub ignore_power_saving: bool,
+    /// Leave the books read most recently out of the menu of the app's icon in the dock.
+    pub hide_recent_books: bool,
+    /// Play a rustle as pages are turned.
+    pub sound_page_turn: bool,
+    /// Play a chime when a book is imported.
+    pub sound_imported: bool,
+    /// Play a fanfare when a book is finished.
+    pub sound_achievement: bool,
+    /// Take the commands of the D-Bus control interface over a Unix socket too.
+    pub command_socket: bool,
+}
+
+/// Whether the app is light or dark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppTheme {
+    /// Follow the system, switching whenever it does.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl AppTheme {
+    pub fn theme(self) -> cosmic::Theme {
+        match self {
+            AppTheme::System => cosmic::theme::system_preference(),
+            AppTheme::Light => {
+                let mut theme = cosmic::theme::system_light();
+                theme.theme_type.prefer_dark(Some(false));
+                theme
+            }
+            AppTheme::Dark => {
+                let mut theme = cosmic::theme::system_dark();
+                theme.theme_type.prefer_dark(Some(true));
+                theme
+            }
+        }
+    }
+}
+
+/// When the reader shows two pages side by side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Spread {
+    /// Show two pages once the window is wide enough.
+    #[default]
+    Auto,
+    Single,
+    Double,
 }
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A D-Bus interface through which scripts and the desktop drive libby: opening books,
+//! raising the window, searching the library, and asking what is being read. The same
+//! commands are taken over a local socket by [`crate::ipc`].
+//!
+//! The interface is named after the app, but served under a bus name of its own, as the
+//! app's own name is held for `org.freedesktop.Application`. It can be tried with:
+//!
+//! ```sh
+//! busctl --user call com.github.codegod100.libby.Control \
+//!     /com/github/codegod100/libby/Control com.github.codegod100.libby GetReadingState
+//! ```
+
+use crate::library::BookId;
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::Stream;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use zbus::fdo;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Where the interface is served.
+const PATH: &str = "/com/github/codegod100/libby/Control";
+
+/// Bus name the interface is served under.
+const NAME: &str = "com.github.codegod100.libby.Control";
+
+/// A request made over the interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Show and focus the window.
+    Activate,
+    /// Open this file in the reader, adding it to the library if it isn't there yet.
+    Open(PathBuf),
+    /// Show the library searched for these words.
+    Search(String),
+}
+
+/// The book being read, as the interface reports it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReadingState {
+    pub book: BookId,
+    pub title: String,
+    pub author: String,
+    pub path: Option<PathBuf>,
+    /// Index of the chapter being read, or of the page for PDFs and comics.
+    pub location: usize,
+    /// How much of the book has been read, from 0.0 to 1.0.
+    pub progress: f32,
+}
+
+/// What is being read, shared with the served interface.
+#[derive(Debug, Clone, Default)]
+pub struct Control(Arc<Mutex<Option<ReadingState>>>);
+
+impl Control {
+    /// Updates what is being read, or that nothing is.
+    pub fn set(&self, state: Option<ReadingState>) {
+        *self.lock() = state;
+    }
+
+    /// What is being read, if anything.
+    pub fn get(&self) -> Option<ReadingState> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<ReadingState>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct Interface {
+    state: Control,
+    commands: Sender<Command>,
+}
+
+impl Interface {
+    fn send(&self, command: Command) -> fdo::Result<()> {
+        self.commands
+            .clone()
+            .try_send(command)
+            .map_err(|why| fdo::Error::Failed(why.to_string()))
+    }
+}
+
+#[zbus::interface(name = "com.github.codegod100.libby")]
+impl Interface {
+    /// Opens the book at an absolute path in the reader.
+    fn open_book(&self, path: &str) -> fdo::Result<()> {
+        let path = book_file(path).map_err(fdo::Error::InvalidArgs)?;
+        self.send(Command::Open(path))
+    }
+
+    fn activate(&self) -> fdo::Result<()> {
+        self.send(Command::Activate)
+    }
+
+    fn search(&self, query: String) -> fdo::Result<()> {
+        self.send(Command::Search(query))
+    }
+
+    /// The book being read, keyed by `id`, `title`, `author`, `path`, `location` and
+    /// `progress`; empty when no book is open.
+    fn get_reading_state(&self) -> HashMap<String, OwnedValue> {
+        let mut reply = HashMap::new();
+
+        let Some(state) = self.state.get() else {
+            return reply;
+        };
+
+        let mut insert = |key: &str, value: Value| {
+            if let Ok(value) = OwnedValue::try_from(value) {
+                reply.insert(key.to_string(), value);
+            }
+        };
+
+        insert("id", Value::from(state.book.to_string()));
+        insert("title", Value::from(state.title));
+        insert("author", Value::from(state.author));
+        insert("location", Value::from(state.location as u32));
+        insert("progress", Value::from(f64::from(state.progress)));
+
+        if let Some(path) = state.path {
+            insert("path", Value::from(path.to_string_lossy().into_owned()));
+        }
+
+        reply
+    }
+}
+
+/// The book file at `path` asked to be opened, which has to be absolute and exist.
+pub fn book_file(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+
+    if !path.is_absolute() {
+        return Err(format!("{} is not an absolute path", path.display()));
+    }
+
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+
+    Ok(path)
+}
+
+/// Serves the interface on the session bus for as long as the stream is polled,
+/// yielding the requests made over it.
+pub fn serve(state: Control) -> impl Stream<Item = Command> {
+    cosmic::iced::stream::channel(4, move |output| async move {
+        let interface = Interface {
+            state,
+            commands: output,
+        };
+
+        let connection = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.serve_at(PATH, interface));
+
+        let connection = match connection {
+            Ok(builder) => builder.build().await,
+            Err(why) => Err(why),
+        };
+
+        // The connection serves the interface until it's dropped.
+        let _connection = match connection {
+            Ok(connection) => connection,
+            Err(why) => {
+                tracing::error!("failed to serve the control interface: {why}");
+                return;
+            }
+        };
+
+        futures_util::future::pending::<()>().await;
+    })
+}
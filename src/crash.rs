@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Writes a report when libby panics, for the user to send on the next launch if they
+//! opted in to crash reporting.
+//!
+//! Reports hold the version, the panic and its backtrace, and the names of the last
+//! messages handled, but none of their contents, which may include passwords. They are
+//! kept in the crashes folder until the user is asked about them, then moved into its
+//! `seen` folder.
+
+use crate::net;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Messages remembered for a report.
+const HISTORY: usize = 20;
+
+static MESSAGES: Mutex<VecDeque<&'static str>> = Mutex::new(VecDeque::new());
+
+/// Notes a message handled by the app, by the name of its variant.
+pub fn record(name: &'static str) {
+    let mut messages = MESSAGES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if messages.len() == HISTORY {
+        messages.pop_front();
+    }
+
+    messages.push_back(name);
+}
+
+fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("crashes"))
+}
+
+/// Writes a report of any panic, before the default hook prints it.
+pub fn install() {
+    let default = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let mut report = String::new();
+        _ = writeln!(
+            report,
+            "libby {} ({})",
+            env!("CARGO_PKG_VERSION"),
+            env!("VERGEN_GIT_SHA")
+        );
+        _ = writeln!(
+            report,
+            "{} {}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        _ = writeln!(report, "\n{info}");
+        _ = writeln!(report, "\n{}", Backtrace::force_capture());
+
+        // The panic may have struck while the history was locked.
+        if let Ok(messages) = MESSAGES.try_lock() {
+            _ = writeln!(report, "Last messages:");
+
+            for message in messages.iter() {
+                _ = writeln!(report, "  {message}");
+            }
+        }
+
+        if let Err(why) = write(&report) {
+            tracing::error!("failed to write crash report: {why}");
+        }
+
+        default(info);
+    }));
+}
+
+fn write(report: &str) -> std::io::Result<()> {
+    let dir = dir().ok_or(std::io::ErrorKind::NotFound)?;
+    std::fs::create_dir_all(&dir)?;
+
+    let name = format!("crash-{}.txt", crate::library::now());
+    std::fs::write(dir.join(name), report)
+}
+
+/// Reports written since the user was last asked about them, oldest first.
+pub fn pending() -> Vec<PathBuf> {
+    let Some(entries) = dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+
+    reports.sort();
+    reports
+}
+
+/// Sends reports to `endpoint`, each as the plain-text body of a POST request.
+pub async fn submit(endpoint: String, reports: Vec<PathBuf>) -> Result<(), String> {
+    for path in &reports {
+        let report = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|why| why.to_string())?;
+
+        let request = net::client()
+            .post(endpoint.trim())
+            .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(report);
+
+        net::send(request)
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|why| why.to_string())?;
+
+        seen(path).await;
+    }
+
+    Ok(())
+}
+
+/// Keeps reports the user chose not to send, without asking about them again.
+pub async fn dismiss(reports: Vec<PathBuf>) {
+    for path in &reports {
+        seen(path).await;
+    }
+}
+
+async fn seen(path: &Path) {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+
+    let seen = parent.join("seen");
+
+    let result = match tokio::fs::create_dir_all(&seen).await {
+        Ok(()) => tokio::fs::rename(path, seen.join(name)).await,
+        Err(why) => Err(why),
+    };
+
+    if let Err(why) = result {
+        tracing::error!("failed to put away crash report {}: {why}", path.display());
+    }
+}
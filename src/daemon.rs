@@ -0,0 +1,351 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keeps the library up to date while the app isn't open, as a user service: importing
+//! new books from watched folders, generating their covers, and syncing the catalog with
+//! the WebDAV server.
+//!
+//! The app does this work itself while it runs, so rounds are skipped then. It's found
+//! by the name it holds on the session bus as the single instance, and as the daemon
+//! reads the catalog afresh each round, neither overwrites what the other saved. The
+//! catalog's lock, which the app holds for as long as it runs, keeps an app started
+//! partway through a round from reading the catalog until the round has saved it.
+//!
+//! A round starts shortly after files change in the watched folders, and otherwise
+//! every few minutes to catch what the file system didn't report. Only files whose size
+//! or modification time changed since they were last seen are read, even across
+//! restarts.
+
+use crate::cli::Outcome;
+use crate::config::Config;
+use crate::headless::{self, ImportStatus};
+use crate::library::{self, BookId, FileStamp, Library};
+use crate::{secrets, sync, thumbnail, webdav};
+use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+/// How long the daemon waits between rounds when nothing changes.
+const INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How long files must have stopped changing before a round starts, so that a book is
+/// not read while it is still being copied.
+const SETTLE: Duration = Duration::from_secs(5);
+
+/// What the daemon remembers between rounds.
+struct Daemon {
+    app_id: &'static str,
+    watched: Vec<PathBuf>,
+    /// Files in the watched folders already imported or tried, as they were then, so
+    /// they aren't hashed again each round.
+    seen: HashMap<PathBuf, FileStamp>,
+    last_sync: Option<Instant>,
+    /// Books whose covers were generated, or found to be missing, this run.
+    covered: HashSet<BookId>,
+}
+
+/// Keeps the library up to date until stopped, importing the books put in the
+/// `watched` folders.
+pub fn run(app_id: &'static str, watched: Vec<PathBuf>) -> Outcome {
+    headless::run(async move {
+        let (mut terminate, mut interrupt) = match (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) {
+            (Ok(terminate), Ok(interrupt)) => (terminate, interrupt),
+            (Err(why), _) | (_, Err(why)) => {
+                tracing::error!("failed to listen for signals: {why}");
+                return Outcome::Failed;
+            }
+        };
+
+        let mut daemon = Daemon {
+            app_id,
+            watched,
+            seen: load_seen(),
+            last_sync: None,
+            covered: HashSet::new(),
+        };
+
+        tracing::info!(
+            "keeping the library up to date, watching {} folders",
+            daemon.watched.len()
+        );
+
+        // Kept for as long as the daemon runs, as dropping it stops the watching.
+        let (_watcher, mut changes) = watch(&daemon.watched);
+
+        loop {
+            if app_running(app_id).await {
+                tracing::debug!("skipping a round, as the app is keeping the library up to date");
+            } else {
+                daemon.round().await;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(INTERVAL) => {}
+                Some(()) = changes.recv() => settle(&mut changes).await,
+                _ = terminate.recv() => break,
+                _ = interrupt.recv() => break,
+            }
+        }
+
+        tracing::info!("stopped keeping the library up to date");
+        Outcome::Done
+    })
+}
+
+impl Daemon {
+    async fn round(&mut self) {
+        let started = Instant::now();
+
+        let lock = match library::Lock::try_acquire() {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                tracing::debug!("skipping a round, as the app holds the library");
+                return;
+            }
+            Err(why) => {
+                tracing::warn!("failed to lock the library: {why}");
+                None
+            }
+        };
+
+        let mut library = match Library::load() {
+            Ok(library) => library,
+            Err(why) => {
+                tracing::error!("{why}");
+                return;
+            }
+        };
+        let mut changed = self.import(&mut library).await;
+
+        let config = cosmic_config::Config::new(self.app_id, Config::VERSION)
+            .map(|context| Config::get_entry(&context).unwrap_or_else(|(_, config)| config))
+            .unwrap_or_default();
+
+        let due = self
+            .last_sync
+            .is_none_or(|last| last.elapsed() >= webdav::SYNC_INTERVAL);
+
+        if due && !config.webdav_url.trim().is_empty() {
+            self.last_sync = Some(Instant::now());
+            changed |= sync_webdav(&mut library, &config).await;
+        }
+
+        if changed {
+            if let Err(why) = library.save() {
+                tracing::error!("failed to save library: {why}");
+            }
+        }
+
+        // Covers aren't part of the catalog, so an app starting meanwhile needn't wait.
+        drop(lock);
+        self.generate_covers(&library).await;
+
+        tracing::debug!("finished a round in {:?}", started.elapsed());
+    }
+
+    /// Generates the covers of the books which have none on disk yet.
+    async fn generate_covers(&mut self, library: &Library) {
+        for book in library.books() {
+            let Some(path) = book.path.clone().filter(|_| !book.is_trashed()) else {
+                continue;
+            };
+
+            if !self.covered.insert(book.id) {
+                continue;
+            }
+
+            if let Err(why) = thumbnail::book_cover(book.id, path).await {
+                tracing::warn!("failed to generate the cover of book {}: {why}", book.id);
+            }
+        }
+    }
+
+    /// Imports the books new to the watched folders, and whether any were.
+    async fn import(&mut self, library: &mut Library) -> bool {
+        let mut files = Vec::new();
+
+        for dir in &self.watched {
+            if let Err(why) = headless::collect_books(dir, &mut files) {
+                tracing::error!("failed to list {}: {why}", dir.display());
+            }
+        }
+
+        let mut imported = false;
+        let mut read = 0;
+
+        // Files gone from the folders are forgotten, so the list doesn't grow forever.
+        let present: HashSet<&PathBuf> = files.iter().collect();
+        self.seen.retain(|file, _| present.contains(file));
+
+        for file in files {
+            let Some(stamp) = FileStamp::of(&file) else {
+                continue;
+            };
+
+            if self.seen.get(&file) == Some(&stamp) {
+                continue;
+            }
+
+            self.seen.insert(file.clone(), stamp);
+            read += 1;
+
+            let outcome = headless::import_one(library, file).await;
+
+            match outcome.status {
+                ImportStatus::Imported => {
+                    imported = true;
+                    tracing::info!("imported {}", outcome.title.as_deref().unwrap_or_default());
+                }
+                ImportStatus::Skipped => {}
+                ImportStatus::Failed => tracing::error!(
+                    "failed to import {}: {}",
+                    outcome.file.display(),
+                    outcome.error.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+
+        if read > 0 {
+            tracing::debug!("read {read} new or changed files in the watched folders");
+            save_seen(&self.seen);
+        }
+
+        imported
+    }
+}
+
+/// Watches the folders for files being added or changed, sending a message for each
+/// change. Without a watcher, the daemon still finds new books on its regular rounds.
+fn watch(
+    dirs: &[PathBuf],
+) -> (
+    Option<notify::RecommendedWatcher>,
+    mpsc::UnboundedReceiver<()>,
+) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+                _ = sender.send(());
+            }
+            Ok(_) => {}
+            Err(why) => tracing::warn!("failed to watch for changes: {why}"),
+        });
+
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(why) => {
+            tracing::error!("failed to watch the folders for changes: {why}");
+            return (None, receiver);
+        }
+    };
+
+    for dir in dirs {
+        if let Err(why) = watcher.watch(dir, RecursiveMode::Recursive) {
+            tracing::error!("failed to watch {} for changes: {why}", dir.display());
+        }
+    }
+
+    (Some(watcher), receiver)
+}
+
+/// Waits until no more changes come for a while.
+async fn settle(changes: &mut mpsc::UnboundedReceiver<()>) {
+    while let Ok(Some(())) = tokio::time::timeout(SETTLE, changes.recv()).await {}
+}
+
+/// Where the files already seen in the watched folders are remembered.
+fn seen_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("libby").join("watched.json"))
+}
+
+fn load_seen() -> HashMap<PathBuf, FileStamp> {
+    seen_path()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_seen(seen: &HashMap<PathBuf, FileStamp>) {
+    let Some(path) = seen_path() else {
+        return;
+    };
+
+    let saved = serde_json::to_vec(seen)
+        .map_err(std::io::Error::other)
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+
+            std::fs::write(&path, contents)
+        });
+
+    if let Err(why) = saved {
+        tracing::error!("failed to save {}: {why}", path.display());
+    }
+}
+
+/// Syncs the catalog with the WebDAV server, and whether the library took changes.
+async fn sync_webdav(library: &mut Library, config: &Config) -> bool {
+    let password = match secrets::load(webdav::PASSWORD_KEY.to_string()).await {
+        Ok(password) => password.unwrap_or_default(),
+        Err(why) => {
+            tracing::error!("failed to read the WebDAV password: {why}");
+            return false;
+        }
+    };
+
+    let server = webdav::Server {
+        url: config.webdav_url.trim().to_string(),
+        user: config.webdav_user.clone(),
+        password,
+    };
+
+    let synced = webdav::sync(
+        server,
+        library.books().to_vec(),
+        sync::device_name(),
+        config.sync_resolution,
+    )
+    .await;
+
+    match synced {
+        Ok(outcome) => {
+            let changes = outcome.changes();
+
+            if changes > 0 {
+                tracing::info!("took {changes} changes from the WebDAV server");
+            }
+
+            outcome.apply(library, None);
+            changes > 0
+        }
+        Err(why) => {
+            tracing::error!("failed to sync the catalog with the WebDAV server: {why}");
+            false
+        }
+    }
+}
+
+/// Whether the app is running, by the name it holds on the session bus.
+async fn app_running(app_id: &str) -> bool {
+    let running = async {
+        let connection = zbus::Connection::session().await?;
+        let bus = zbus::fdo::DBusProxy::new(&connection).await?;
+        let name = zbus::names::BusName::try_from(app_id)?;
+        Ok::<_, zbus::Error>(bus.name_has_owner(name).await?)
+    };
+
+    // Without a session bus, as on a server, there's no app to find either.
+    running.await.unwrap_or(false)
+}
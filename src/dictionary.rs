@@ -0,0 +1,355 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Looks up words in local StarDict dictionaries, falling back to Wiktionary online.
+
+use crate::net;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The definition of a word, as shown in the reader's popover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// The headword as written in the dictionary.
+    pub word: String,
+    pub pronunciation: Option<String>,
+    /// Definition lines, with markup removed.
+    pub lines: Vec<String>,
+    /// Name of the dictionary the definition came from.
+    pub source: String,
+}
+
+impl Definition {
+    /// Plain text of the definition, for copying to the clipboard.
+    pub fn to_text(&self) -> String {
+        let mut text = self.word.clone();
+
+        if let Some(pronunciation) = &self.pronunciation {
+            text.push_str(&format!(" /{pronunciation}/"));
+        }
+
+        for line in &self.lines {
+            text.push('\n');
+            text.push_str(line);
+        }
+
+        text
+    }
+}
+
+/// Folder holding StarDict dictionaries, each as `.ifo`, `.idx` and `.dict[.dz]` files.
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("dictionaries"))
+}
+
+/// Strips surrounding punctuation from a word clicked in the reader.
+pub fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
+
+/// Looks the word up in the local dictionaries, then online when `online` is set.
+pub async fn lookup(word: String, online: bool) -> Result<Option<Definition>, String> {
+    let local = {
+        let word = word.clone();
+        tokio::task::spawn_blocking(move || lookup_local(&word))
+            .await
+            .map_err(|why| why.to_string())?
+            .map_err(|why| why.to_string())?
+    };
+
+    match local {
+        Some(definition) => Ok(Some(definition)),
+        None if online => lookup_online(&word).await,
+        None => Ok(None),
+    }
+}
+
+/// Searches every installed dictionary, in file name order, for the word.
+pub fn lookup_local(word: &str) -> io::Result<Option<Definition>> {
+    let Some(dir) = dir() else {
+        return Ok(None);
+    };
+
+    let mut ifos = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ifo"))
+            .collect::<Vec<_>>(),
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(why) => return Err(why),
+    };
+
+    ifos.sort();
+
+    for ifo in ifos {
+        match StarDict::open(&ifo).and_then(|dict| dict.lookup(word)) {
+            Ok(Some(definition)) => return Ok(Some(definition)),
+            Ok(None) => (),
+            Err(why) => tracing::error!("failed to read dictionary {}: {why}", ifo.display()),
+        }
+    }
+
+    Ok(None)
+}
+
+/// A StarDict dictionary on disk.
+struct StarDict {
+    name: String,
+    /// Field types shared by every entry, when the dictionary declares them.
+    same_type_sequence: Option<String>,
+    /// Whether `.idx` offsets are 64 rather than 32 bits wide.
+    wide_offsets: bool,
+    /// The `.ifo` path, from which the other files are found.
+    path: PathBuf,
+}
+
+impl StarDict {
+    fn open(ifo: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(ifo)?;
+        let mut lines = text.lines();
+
+        if lines.next().map(str::trim) != Some("StarDict's dict ifo file") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a StarDict .ifo file",
+            ));
+        }
+
+        let options: HashMap<&str, &str> = lines
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+
+        Ok(Self {
+            name: options
+                .get("bookname")
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| ifo.file_stem().unwrap_or_default().to_string_lossy().into()),
+            same_type_sequence: options
+                .get("sametypesequence")
+                .map(|types| types.to_string()),
+            wide_offsets: options.get("idxoffsetbits") == Some(&"64"),
+            path: ifo.to_path_buf(),
+        })
+    }
+
+    fn lookup(&self, word: &str) -> io::Result<Option<Definition>> {
+        let index = std::fs::read(self.path.with_extension("idx"))?;
+        let Some((headword, offset, size)) = self.find(&index, word) else {
+            return Ok(None);
+        };
+
+        let data = self.read_dict()?;
+        let entry = data
+            .get(offset..offset + size)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "entry out of bounds"))?;
+
+        let mut pronunciation = None;
+        let mut lines = Vec::new();
+
+        for (kind, field) in fields(entry, self.same_type_sequence.as_deref()) {
+            let text = String::from_utf8_lossy(field);
+
+            match kind {
+                't' => pronunciation = Some(text.trim().to_string()),
+                'm' | 'l' | 'y' | 'k' => lines.extend(plain_lines(&text)),
+                'h' | 'g' | 'x' => lines.extend(plain_lines(&strip_markup(&text))),
+                _ => (),
+            }
+        }
+
+        Ok(Some(Definition {
+            word: headword,
+            pronunciation,
+            lines,
+            source: self.name.clone(),
+        }))
+    }
+
+    /// Finds the entry for the word, preferring an exact match over a case-insensitive one.
+    fn find(&self, index: &[u8], word: &str) -> Option<(String, usize, usize)> {
+        let offset_len = if self.wide_offsets { 8 } else { 4 };
+        let lowercase = word.to_lowercase();
+        let mut fallback = None;
+        let mut rest = index;
+
+        while let Some(end) = rest.iter().position(|&b| b == 0) {
+            let headword = String::from_utf8_lossy(&rest[..end]);
+            let fields = rest.get(end + 1..end + 1 + offset_len + 4)?;
+            let (offset, size) = fields.split_at(offset_len);
+            let offset = offset.iter().fold(0usize, |n, &b| n << 8 | usize::from(b));
+            let size = size.iter().fold(0usize, |n, &b| n << 8 | usize::from(b));
+
+            if headword == word {
+                return Some((headword.into_owned(), offset, size));
+            }
+
+            if fallback.is_none() && headword.to_lowercase() == lowercase {
+                fallback = Some((headword.into_owned(), offset, size));
+            }
+
+            rest = &rest[end + 1 + offset_len + 4..];
+        }
+
+        fallback
+    }
+
+    fn read_dict(&self) -> io::Result<Vec<u8>> {
+        let plain = self.path.with_extension("dict");
+
+        if plain.exists() {
+            return std::fs::read(plain);
+        }
+
+        // Dictzip files are gzip streams with an index of chunks in the header.
+        let mut data = Vec::new();
+        GzDecoder::new(std::fs::File::open(self.path.with_extension("dict.dz"))?)
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Splits a dictionary entry into its typed fields.
+fn fields<'a>(mut entry: &'a [u8], same_type_sequence: Option<&str>) -> Vec<(char, &'a [u8])> {
+    let mut fields = Vec::new();
+
+    let mut take = |entry: &mut &'a [u8], kind: char, last: bool| {
+        let len = if kind.is_ascii_uppercase() {
+            // Binary fields are prefixed with their size.
+            let size = entry.get(..4).map_or(0, |size| {
+                u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize
+            });
+            *entry = entry.get(4..).unwrap_or_default();
+            size.min(entry.len())
+        } else if last {
+            entry.len()
+        } else {
+            entry.iter().position(|&b| b == 0).unwrap_or(entry.len())
+        };
+
+        fields.push((kind, &entry[..len]));
+        let skip = if kind.is_ascii_uppercase() {
+            len
+        } else {
+            (len + 1).min(entry.len())
+        };
+        *entry = &entry[skip..];
+    };
+
+    match same_type_sequence {
+        Some(types) => {
+            let count = types.chars().count();
+            for (n, kind) in types.chars().enumerate() {
+                take(&mut entry, kind, n + 1 == count);
+            }
+        }
+        None => {
+            while let Some((&kind, rest)) = entry.split_first() {
+                entry = rest;
+                take(&mut entry, char::from(kind), false);
+            }
+        }
+    }
+
+    fields
+}
+
+fn plain_lines(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Removes tags from HTML, Pango and XDXF definitions, keeping line breaks.
+pub fn strip_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        plain.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let tag = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .to_lowercase();
+        if ["br", "p", "div", "li", "def"]
+            .iter()
+            .any(|name| tag.split([' ', '/']).next() == Some(name))
+        {
+            plain.push('\n');
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+
+    plain.push_str(rest);
+
+    plain
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[derive(Deserialize)]
+struct WiktionaryUsage {
+    #[serde(rename = "partOfSpeech")]
+    part_of_speech: String,
+    definitions: Vec<WiktionarySense>,
+}
+
+#[derive(Deserialize)]
+struct WiktionarySense {
+    definition: String,
+}
+
+/// Fetches English definitions from Wiktionary's REST API.
+async fn lookup_online(word: &str) -> Result<Option<Definition>, String> {
+    let url = format!("https://en.wiktionary.org/api/rest_v1/page/definition/{word}");
+    let response = net::get(url).await.map_err(|why| why.to_string())?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let usages: HashMap<String, Vec<WiktionaryUsage>> = response
+        .error_for_status()
+        .map_err(|why| why.to_string())?
+        .json()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    let lines: Vec<String> = usages
+        .get("en")
+        .into_iter()
+        .flatten()
+        .flat_map(|usage| {
+            usage.definitions.iter().filter_map(|sense| {
+                let definition = strip_markup(&sense.definition).trim().to_string();
+                (!definition.is_empty())
+                    .then(|| format!("{}: {definition}", usage.part_of_speech.to_lowercase()))
+            })
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Definition {
+        word: word.to_string(),
+        pronunciation: None,
+        lines,
+        source: "Wiktionary".to_string(),
+    }))
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Sites of free books built into the discovery page, browsed through their OPDS
+//! catalogs.
+
+use reqwest::Url;
+
+/// A site offering free books.
+#[derive(Debug, Clone, Copy)]
+pub struct Site {
+    pub name: &'static str,
+    /// Catalog the site is entered by, leading to its categories.
+    pub home: &'static str,
+    /// Catalog of search results, searched through its `query` parameter.
+    search: &'static str,
+}
+
+pub const SITES: [Site; 2] = [
+    Site {
+        name: "Project Gutenberg",
+        home: "https://www.gutenberg.org/ebooks.opds/",
+        search: "https://www.gutenberg.org/ebooks/search.opds/",
+    },
+    Site {
+        name: "Standard Ebooks",
+        home: "https://standardebooks.org/feeds/opds",
+        search: "https://standardebooks.org/feeds/opds/all",
+    },
+];
+
+impl Site {
+    /// Address of the results of searching the site for `query`.
+    pub fn search_url(&self, query: &str) -> String {
+        match Url::parse(self.search) {
+            Ok(mut url) => {
+                url.query_pairs_mut().append_pair("query", query.trim());
+                url.into()
+            }
+            Err(_) => self.home.to_string(),
+        }
+    }
+}
+
+/// Whether the library can import files of the given extension.
+pub fn importable(extension: Option<&str>) -> bool {
+    matches!(extension, Some("epub" | "pdf"))
+}
@@ -0,0 +1,419 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Downloads files in the background, a few at a time from a queue.
+//!
+//! Each download is written beside its destination with a `.part` extension until it
+//! completes, so a paused or interrupted download picks up where it left off when the
+//! server supports ranges. Dropped connections and server errors are retried, and all
+//! downloads share one bandwidth limit.
+
+use crate::import;
+use crate::net::{self, Credentials};
+use futures_util::{SinkExt, Stream};
+use reqwest::{header, StatusCode};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Downloads running at once; the rest wait in the queue.
+pub const MAX_ACTIVE: usize = 2;
+
+/// Times a download is retried before it is given up on.
+const RETRIES: u32 = 3;
+
+/// Wait before the first retry, growing with each one after.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Least time between progress reports of a download.
+const REPORT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Span over which the bandwidth limit is averaged.
+const THROTTLE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Identifies a download within the queue.
+pub type DownloadId = u64;
+
+/// What a download is for, deciding what happens once it finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Purpose {
+    /// A file saved into the downloads folder.
+    Save,
+    /// A book added to the library once downloaded.
+    Import,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum State {
+    Queued,
+    Active,
+    Paused,
+    Failed(String),
+    Done,
+}
+
+/// A file being downloaded.
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub id: DownloadId,
+    pub url: String,
+    pub destination: PathBuf,
+    pub purpose: Purpose,
+    /// Credentials to sign in to the server with, if it asks for them.
+    pub auth: Option<Credentials>,
+    pub state: State,
+    /// Bytes received so far, including those of earlier attempts.
+    pub received: u64,
+    /// Size of the file, if the server gave it.
+    pub total: Option<u64>,
+}
+
+impl Download {
+    /// Name of the file being downloaded.
+    pub fn name(&self) -> String {
+        self.destination
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.url.clone())
+    }
+
+    /// Fraction of the file received, if its size is known.
+    pub fn fraction(&self) -> Option<f32> {
+        match self.state {
+            State::Done => Some(1.0),
+            _ => self
+                .total
+                .filter(|&total| total > 0)
+                .map(|total| (self.received as f32 / total as f32).min(1.0)),
+        }
+    }
+}
+
+/// Downloads in the order they were added, including finished ones until cleared.
+#[derive(Debug, Default)]
+pub struct Queue {
+    next_id: DownloadId,
+    downloads: Vec<Download>,
+}
+
+impl Queue {
+    /// Adds a download to the end of the queue.
+    pub fn push(
+        &mut self,
+        url: String,
+        destination: PathBuf,
+        purpose: Purpose,
+        auth: Option<Credentials>,
+    ) -> DownloadId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.downloads.push(Download {
+            id,
+            url,
+            destination,
+            purpose,
+            auth,
+            state: State::Queued,
+            received: 0,
+            total: None,
+        });
+
+        id
+    }
+
+    pub fn downloads(&self) -> &[Download] {
+        &self.downloads
+    }
+
+    pub fn get_mut(&mut self, id: DownloadId) -> Option<&mut Download> {
+        self.downloads.iter_mut().find(|download| download.id == id)
+    }
+
+    pub fn remove(&mut self, id: DownloadId) -> Option<Download> {
+        let index = self
+            .downloads
+            .iter()
+            .position(|download| download.id == id)?;
+        Some(self.downloads.remove(index))
+    }
+
+    /// Marks as many queued downloads active as there is room for, returning them.
+    pub fn start_next(&mut self) -> Vec<Download> {
+        let active = self
+            .downloads
+            .iter()
+            .filter(|download| download.state == State::Active)
+            .count();
+
+        self.downloads
+            .iter_mut()
+            .filter(|download| download.state == State::Queued)
+            .take(MAX_ACTIVE.saturating_sub(active))
+            .map(|download| {
+                download.state = State::Active;
+                download.clone()
+            })
+            .collect()
+    }
+
+    /// Forgets finished downloads.
+    pub fn clear_finished(&mut self) {
+        self.downloads
+            .retain(|download| download.state != State::Done);
+    }
+}
+
+/// Events of a running download.
+#[derive(Debug, Clone)]
+pub enum Progress {
+    /// The server answered, with the file's size if it gave it.
+    Started {
+        received: u64,
+        total: Option<u64>,
+    },
+    /// Bytes received so far.
+    Received(u64),
+    /// The download failed and is about to be tried again.
+    Retrying(String),
+    Finished,
+    Failed(String),
+}
+
+/// A bandwidth limit shared between downloads.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    /// Bytes per second, or 0 for no limit.
+    limit: Arc<AtomicU64>,
+    /// Start of the current window, and the bytes let through since.
+    window: Arc<Mutex<(Instant, u64)>>,
+}
+
+impl Throttle {
+    pub fn new(kib_per_second: u32) -> Self {
+        Self {
+            limit: Arc::new(AtomicU64::new(u64::from(kib_per_second) * 1024)),
+            window: Arc::new(Mutex::new((Instant::now(), 0))),
+        }
+    }
+
+    /// Changes the limit, taking effect on running downloads as well.
+    pub fn set_limit(&self, kib_per_second: u32) {
+        self.limit
+            .store(u64::from(kib_per_second) * 1024, Ordering::Relaxed);
+    }
+
+    /// Waits until `bytes` more can be received without going over the limit.
+    async fn take(&self, bytes: u64) {
+        let limit = self.limit.load(Ordering::Relaxed);
+
+        if limit == 0 {
+            return;
+        }
+
+        // Holding the lock while waiting makes other downloads wait their turn.
+        let mut window = self.window.lock().await;
+        let (start, sent) = &mut *window;
+
+        if start.elapsed() > THROTTLE_WINDOW {
+            *start = Instant::now();
+            *sent = 0;
+        }
+
+        *sent += bytes;
+
+        let due = Duration::from_secs_f64(*sent as f64 / limit as f64);
+
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Name of the file at `url`, from the last segment of its path.
+pub fn file_name(url: &str) -> String {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download")
+        .to_string()
+}
+
+/// A path in `dir` to save a file named `name` to, numbered so it doesn't replace
+/// another file.
+pub fn destination(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut number = 1;
+
+    while path.exists() || partial(&path).exists() {
+        number += 1;
+        path = dir.join(format!("{stem} ({number}){extension}"));
+    }
+
+    path
+}
+
+/// Where a download is written until it completes.
+pub fn partial(destination: &Path) -> PathBuf {
+    let mut name = destination.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Runs a download, retrying it when that might help, until it finishes or fails.
+///
+/// Dropping the stream pauses the download, leaving what was received to resume from.
+pub fn run(download: Download, throttle: Throttle) -> impl Stream<Item = Progress> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let client = net::client();
+        let mut attempt = 0;
+
+        loop {
+            let progress = match fetch(&client, &download, &throttle, &mut output).await {
+                Ok(()) => Progress::Finished,
+                Err(Failure::Retry(why)) if attempt < RETRIES => {
+                    attempt += 1;
+
+                    if output.send(Progress::Retrying(why)).await.is_err() {
+                        break;
+                    }
+
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                    continue;
+                }
+                Err(Failure::Retry(why) | Failure::Fatal(why)) => Progress::Failed(why),
+            };
+
+            _ = output.send(progress).await;
+            break;
+        }
+    })
+}
+
+/// Why an attempt at a download failed.
+enum Failure {
+    /// Something which may go away by trying again, such as a dropped connection.
+    Retry(String),
+    Fatal(String),
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(why: std::io::Error) -> Self {
+        Failure::Fatal(why.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(why: reqwest::Error) -> Self {
+        Failure::Retry(why.to_string())
+    }
+}
+
+async fn fetch(
+    client: &reqwest::Client,
+    download: &Download,
+    throttle: &Throttle,
+    output: &mut cosmic::iced::futures::channel::mpsc::Sender<Progress>,
+) -> Result<(), Failure> {
+    let partial = partial(&download.destination);
+    let offset = tokio::fs::metadata(&partial)
+        .await
+        .map_or(0, |metadata| metadata.len());
+
+    let mut request = net::authenticate(client.get(&download.url), download.auth.as_ref());
+
+    if offset > 0 {
+        request = request.header(header::RANGE, format!("bytes={offset}-"));
+    }
+
+    let mut response = net::send(request).await?;
+    let status = response.status();
+
+    // What was kept is already the whole file, or no longer matches it.
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        tokio::fs::remove_file(&partial).await?;
+        return Err(Failure::Retry(status.to_string()));
+    }
+
+    if !status.is_success() {
+        let retry = status.is_server_error()
+            || matches!(
+                status,
+                StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS
+            );
+
+        return Err(if retry {
+            Failure::Retry(status.to_string())
+        } else {
+            Failure::Fatal(status.to_string())
+        });
+    }
+
+    if download.purpose == Purpose::Import {
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if !import::accepts(content_type) {
+            return Err(Failure::Fatal(format!(
+                "not an EPUB or PDF, but {content_type}"
+            )));
+        }
+    }
+
+    // A server which ignores the range sends the whole file again.
+    let resumed = offset > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let mut received = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|length| length + received);
+
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&partial)
+            .await?
+    } else {
+        if let Some(parent) = partial.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::File::create(&partial).await?
+    };
+
+    _ = output.send(Progress::Started { received, total }).await;
+
+    let mut reported = Instant::now();
+
+    while let Some(chunk) = response.chunk().await? {
+        throttle.take(chunk.len() as u64).await;
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+
+        if reported.elapsed() >= REPORT_INTERVAL {
+            reported = Instant::now();
+            _ = output.send(Progress::Received(received)).await;
+        }
+    }
+
+    file.flush().await?;
+
+    if total.is_some_and(|total| received < total) {
+        return Err(Failure::Retry("the connection closed early".to_string()));
+    }
+
+    tokio::fs::rename(&partial, &download.destination).await?;
+    Ok(())
+}
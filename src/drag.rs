@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Books dragged out of the library, offered as files to whatever they're dropped on,
+//! such as a file manager or an email client.
+
+use cosmic::iced::clipboard::mime::AsMimeTypes;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// List of file URIs, which file managers and email clients take as files.
+const URI_LIST: &str = "text/uri-list";
+
+/// Plain text, for anything that only takes text, which is given the file's URI.
+const TEXT: [&str; 2] = ["text/plain;charset=utf-8", "text/plain"];
+
+/// A book's file being dragged.
+#[derive(Debug, Clone)]
+pub struct BookFile {
+    uri: String,
+}
+
+impl BookFile {
+    /// The book's file to drag, if its path is absolute as URIs need.
+    pub fn new(path: &Path) -> Option<Self> {
+        let uri = reqwest::Url::from_file_path(path).ok()?;
+        Some(Self { uri: uri.into() })
+    }
+}
+
+impl AsMimeTypes for BookFile {
+    fn available(&self) -> Cow<'static, [String]> {
+        [URI_LIST]
+            .into_iter()
+            .chain(TEXT)
+            .map(String::from)
+            .collect()
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        if mime_type == URI_LIST {
+            Some(format!("{}\r\n", self.uri).into_bytes().into())
+        } else if TEXT.contains(&mime_type) {
+            Some(self.uri.clone().into_bytes().into())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,1114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reads the package and chapters of EPUB files for the reader.
+
+use crate::mapped;
+use crate::math;
+use crate::toc;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// An EPUB's archive, opened once and shared by every copy of the [`Epub`], so that its
+/// directory of entries is read only once however many chapters are read from it.
+type Archive = Arc<Mutex<zip::ZipArchive<mapped::Contents>>>;
+
+/// The most of an archive entry read into memory, past which the archive is taken to be
+/// broken or crafted rather than read on.
+pub const ENTRY_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// Errors raised while reading an EPUB.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Zip(zip::result::ZipError),
+    Xml(quick_xml::Error),
+    /// The container is missing a required part.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(why) => write!(f, "{why}"),
+            Error::Zip(why) => write!(f, "invalid archive: {why}"),
+            Error::Xml(why) => write!(f, "invalid XML: {why}"),
+            Error::Malformed(what) => write!(f, "malformed EPUB: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(why: io::Error) -> Self {
+        Error::Io(why)
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(why: zip::result::ZipError) -> Self {
+        Error::Zip(why)
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(why: quick_xml::Error) -> Self {
+        Error::Xml(why)
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for Error {
+    fn from(why: quick_xml::events::attributes::AttrError) -> Self {
+        Error::Xml(why.into())
+    }
+}
+
+/// The package of an EPUB: its metadata and reading order.
+///
+/// Only the package is kept in memory; chapters, and the images in them, are read from
+/// the archive on demand.
+#[derive(Debug, Clone)]
+pub struct Epub {
+    archive: Archive,
+    pub title: String,
+    pub author: String,
+    /// Archive paths of the spine's documents, in reading order.
+    spine: Vec<String>,
+    /// Table of contents from the navigation document, or the NCX of older EPUBs.
+    pub toc: Vec<toc::Entry>,
+    /// Whether each spine document is a page laid out as an image, as in comics.
+    pub fixed_layout: bool,
+    /// Archive path of the cover image, if the package names one.
+    cover: Option<String>,
+}
+
+/// The document holding an EPUB's table of contents.
+enum TocDocument {
+    /// An EPUB 3 navigation document.
+    Nav(String),
+    /// An EPUB 2 NCX file.
+    Ncx(String),
+}
+
+/// A chapter's content, reduced to the blocks the reader knows how to display.
+#[derive(Debug, Clone, Default)]
+pub struct Chapter {
+    pub blocks: Vec<Block>,
+    /// Images referenced by [`Block::Image`], read with [`Epub::image`].
+    pub images: Vec<Image>,
+    /// Blocks spanned by each element with an `id`, for finding the targets of links.
+    pub anchors: HashMap<String, Range<usize>>,
+}
+
+/// An image in a chapter, left in the archive until it is shown.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Image {
+    /// An image file, by its archive path.
+    Entry(String),
+    /// An SVG drawing written out in the chapter itself.
+    Inline(Vec<u8>),
+}
+
+/// The text of a footnote or endnote, read from wherever its reference points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub blocks: Vec<Block>,
+    /// Chapter holding the note, if it is in the spine.
+    pub chapter: Option<usize>,
+    /// Relative offset of the note within its chapter.
+    pub offset: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// A heading of level 1 to 6.
+    Heading(u8, Vec<Span>),
+    Paragraph(Vec<Span>),
+    Quote(Vec<Span>),
+    /// A formula shown on its own line, written out as text.
+    Math(String),
+    /// Index into [`Chapter::images`].
+    Image(usize),
+    Rule,
+}
+
+/// A run of text sharing the same emphasis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    /// Target of the footnote reference this text is, as an archive path and fragment.
+    pub note: Option<String>,
+}
+
+impl Block {
+    /// The block's text without emphasis, empty for images and rules.
+    pub fn text(&self) -> String {
+        match self {
+            Block::Heading(_, spans) | Block::Paragraph(spans) | Block::Quote(spans) => {
+                spans.iter().map(|span| span.text.as_str()).collect()
+            }
+            Block::Math(text) => text.clone(),
+            Block::Image(_) | Block::Rule => String::new(),
+        }
+    }
+}
+
+/// Whether an image is an SVG drawing rather than a bitmap.
+pub fn is_svg(data: &[u8]) -> bool {
+    data[..data.len().min(1024)]
+        .windows(4)
+        .any(|window| window == b"<svg")
+}
+
+impl Epub {
+    /// Reads the package of the EPUB at `path`.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let mut archive = zip::ZipArchive::new(mapped::open(path)?)?;
+
+        let container = read_entry(&mut archive, "META-INF/container.xml")?;
+        let package_path = rootfile(&container)?;
+        let package = read_entry(&mut archive, &package_path)?;
+
+        let base = parent(&package_path);
+        let (mut epub, toc_document) =
+            parse_package(&package, base, Arc::new(Mutex::new(archive)))?;
+
+        if let Some(document) = toc_document {
+            match epub.read_toc(&document) {
+                Ok(toc) => epub.toc = toc,
+                Err(why) => {
+                    tracing::error!(
+                        "failed to read table of contents of {}: {why}",
+                        path.display()
+                    );
+                }
+            }
+        }
+
+        Ok(epub)
+    }
+
+    /// Number of documents in the spine.
+    pub fn chapter_count(&self) -> usize {
+        self.spine.len()
+    }
+
+    /// Reads and parses the spine document at `index`, leaving its images in the
+    /// archive.
+    pub fn chapter(&self, index: usize) -> Result<Chapter, Error> {
+        let href = self
+            .spine
+            .get(index)
+            .ok_or(Error::Malformed("chapter out of range"))?;
+
+        parse_chapter(&self.read(href)?, href)
+    }
+
+    /// Reads an encoded image of a chapter.
+    pub fn image(&self, image: &Image) -> Result<Vec<u8>, Error> {
+        match image {
+            Image::Entry(path) => self.read(path),
+            Image::Inline(data) => Ok(data.clone()),
+        }
+    }
+
+    /// Reads the encoded cover image, if the package names one.
+    pub fn cover(&self) -> Result<Option<Vec<u8>>, Error> {
+        let Some(cover) = &self.cover else {
+            return Ok(None);
+        };
+
+        self.read(cover).map(Some)
+    }
+
+    /// Index of the spine document a note's target points into.
+    pub fn chapter_of(&self, target: &str) -> Option<usize> {
+        let path = target.split('#').next().unwrap_or_default();
+        self.spine.iter().position(|href| href == path)
+    }
+
+    /// Reads the note at `target`, an archive path and fragment as found in
+    /// [`Span::note`], or `None` if the document has no element with that ID.
+    pub fn note(&self, target: &str) -> Result<Option<Note>, Error> {
+        let (path, id) = target.split_once('#').unwrap_or((target, ""));
+
+        let chapter = parse_chapter(&self.read(path)?, path)?;
+
+        let Some(range) = chapter.anchors.get(id) else {
+            return Ok(None);
+        };
+
+        let blocks: Vec<Block> = chapter.blocks[range.clone()]
+            .iter()
+            .filter(|block| !matches!(block, Block::Image(_)))
+            .cloned()
+            .collect();
+
+        if blocks.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Note {
+            blocks,
+            chapter: self.chapter_of(target),
+            offset: range.start as f32 / chapter.blocks.len() as f32,
+        }))
+    }
+
+    /// Reads the entry at archive path `name`.
+    fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut archive = self
+            .archive
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        read_entry(&mut archive, name)
+    }
+
+    fn read_toc(&self, document: &TocDocument) -> Result<Vec<toc::Entry>, Error> {
+        match document {
+            TocDocument::Nav(href) => parse_nav(&self.read(href)?, parent(href), &self.spine),
+            TocDocument::Ncx(href) => parse_ncx(&self.read(href)?, parent(href), &self.spine),
+        }
+    }
+}
+
+fn read_entry(
+    archive: &mut zip::ZipArchive<mapped::Contents>,
+    name: &str,
+) -> Result<Vec<u8>, Error> {
+    let entry = archive.by_name(name)?;
+    let size = entry.size();
+    read_limited(entry, size).map_err(Error::from)
+}
+
+/// Reads an archive entry whose header claims it holds `size` bytes, failing once more
+/// than [`ENTRY_LIMIT`] comes out of it, whatever the header claimed.
+pub fn read_limited(entry: impl Read, size: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(size.min(ENTRY_LIMIT) as usize);
+    entry.take(ENTRY_LIMIT + 1).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 > ENTRY_LIMIT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "an archive entry is too large",
+        ));
+    }
+
+    Ok(bytes)
+}
+
+/// Finds the package document named by `META-INF/container.xml`.
+fn rootfile(container: &[u8]) -> Result<String, Error> {
+    let mut reader = quick_xml::Reader::from_reader(container);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rootfile" => {
+                if let Some(path) = attribute(&e, b"full-path")? {
+                    return Ok(path);
+                }
+            }
+            Event::Eof => return Err(Error::Malformed("no rootfile in container")),
+            _ => {}
+        }
+    }
+}
+
+/// Parses the OPF package document into an [`Epub`] reading from `archive`, with an
+/// empty table of contents, along with the document the table of contents is read from.
+fn parse_package(
+    package: &[u8],
+    base: &str,
+    archive: Archive,
+) -> Result<(Epub, Option<TocDocument>), Error> {
+    let mut reader = quick_xml::Reader::from_reader(package);
+
+    let mut manifest = Vec::new();
+    let mut itemrefs = Vec::new();
+    let mut nav = None;
+    let mut ncx = None;
+    let mut spine_toc = None;
+    let mut title = String::new();
+    let mut author = String::new();
+    let mut in_title = false;
+    let mut in_creator = false;
+    let mut in_layout = false;
+    let mut fixed_layout = false;
+    let mut cover = None;
+    let mut cover_id = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"title" => in_title = title.is_empty(),
+                b"creator" => in_creator = author.is_empty(),
+                b"spine" => spine_toc = attribute(&e, b"toc")?,
+                b"meta" => {
+                    in_layout = attribute(&e, b"property")?.as_deref() == Some("rendition:layout");
+                }
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"item" => {
+                    if let (Some(id), Some(href)) = (attribute(&e, b"id")?, attribute(&e, b"href")?)
+                    {
+                        let href = resolve(base, &href);
+
+                        let properties = attribute(&e, b"properties")?.unwrap_or_default();
+
+                        if properties.split_whitespace().any(|p| p == "nav") {
+                            nav = Some(href.clone());
+                        }
+
+                        if properties.split_whitespace().any(|p| p == "cover-image") {
+                            cover = Some(href.clone());
+                        }
+
+                        if attribute(&e, b"media-type")?.as_deref()
+                            == Some("application/x-dtbncx+xml")
+                        {
+                            ncx = Some((id.clone(), href.clone()));
+                        }
+
+                        manifest.push((id, href));
+                    }
+                }
+                b"itemref" if attribute(&e, b"linear")?.as_deref() != Some("no") => {
+                    itemrefs.extend(attribute(&e, b"idref")?);
+                }
+                // Older fixed-layout books declare themselves in EPUB 2 metadata.
+                b"meta" => {
+                    let name = attribute(&e, b"name")?;
+                    let content = attribute(&e, b"content")?;
+
+                    fixed_layout |= matches!(
+                        (name.as_deref(), content.as_deref()),
+                        (Some("fixed-layout"), Some("true")) | (Some("book-type"), Some("comic"))
+                    );
+
+                    // EPUB 2 names the cover by its manifest ID.
+                    if name.as_deref() == Some("cover") {
+                        cover_id = content;
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) if in_layout => {
+                fixed_layout |= text.unescape()?.trim() == "pre-paginated";
+            }
+            Event::Text(text) if in_title || in_creator => {
+                let text = text.unescape()?;
+                let field = if in_title { &mut title } else { &mut author };
+                field.push_str(text.trim());
+            }
+            Event::End(_) => {
+                in_title = false;
+                in_creator = false;
+                in_layout = false;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let spine: Vec<String> = itemrefs
+        .iter()
+        .filter_map(|idref| {
+            manifest
+                .iter()
+                .find(|(id, _)| id == idref)
+                .map(|(_, href)| href.clone())
+        })
+        .collect();
+
+    if spine.is_empty() {
+        return Err(Error::Malformed("empty spine"));
+    }
+
+    // Prefer the navigation document, then the NCX named by the spine.
+    let ncx = match spine_toc {
+        Some(idref) => manifest
+            .iter()
+            .find(|(id, _)| *id == idref)
+            .map(|(_, href)| href.clone())
+            .or(ncx.map(|(_, href)| href)),
+        None => ncx.map(|(_, href)| href),
+    };
+    let toc_document = nav.map(TocDocument::Nav).or(ncx.map(TocDocument::Ncx));
+
+    let cover = cover.or_else(|| {
+        let cover_id = cover_id?;
+        manifest
+            .iter()
+            .find(|(id, _)| *id == cover_id)
+            .map(|(_, href)| href.clone())
+    });
+
+    Ok((
+        Epub {
+            archive,
+            title,
+            author,
+            spine,
+            toc: Vec::new(),
+            fixed_layout,
+            cover,
+        },
+        toc_document,
+    ))
+}
+
+/// Reads the `toc` navigation list of an EPUB 3 navigation document.
+fn parse_nav(document: &[u8], base: &str, spine: &[String]) -> Result<Vec<toc::Entry>, Error> {
+    let mut reader = quick_xml::Reader::from_reader(document);
+    reader.config_mut().check_end_names = false;
+
+    let mut builder = toc::Builder::new(spine);
+    // Depth of `nav` elements inside the table of contents.
+    let mut nav = 0;
+    // Depth inside an entry's label.
+    let mut label: usize = 0;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"nav" if nav > 0 => nav += 1,
+                b"nav"
+                    if attribute(&e, b"type")?
+                        .is_some_and(|kind| kind.split_whitespace().any(|kind| kind == "toc")) =>
+                {
+                    nav = 1;
+                }
+                b"li" if nav > 0 => builder.open(),
+                b"a" | b"span" if nav > 0 => {
+                    label += 1;
+
+                    if let Some(href) = attribute(&e, b"href")? {
+                        builder.set_target(&resolve(base, &href));
+                    }
+                }
+                _ => {}
+            },
+            Event::End(e) if nav > 0 => match e.local_name().as_ref() {
+                b"nav" => {
+                    nav -= 1;
+
+                    if nav == 0 {
+                        break;
+                    }
+                }
+                b"li" => builder.close(),
+                b"a" | b"span" => label = label.saturating_sub(1),
+                _ => {}
+            },
+            Event::Text(text) if label > 0 => {
+                let raw = String::from_utf8_lossy(&text);
+                let text = quick_xml::escape::unescape_with(&raw, resolve_entity)
+                    .unwrap_or(Cow::Borrowed(&raw));
+                builder.push_text(&text);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(builder.finish())
+}
+
+/// Reads the navigation map of an EPUB 2 NCX file.
+fn parse_ncx(document: &[u8], base: &str, spine: &[String]) -> Result<Vec<toc::Entry>, Error> {
+    let mut reader = quick_xml::Reader::from_reader(document);
+    reader.config_mut().check_end_names = false;
+
+    let mut builder = toc::Builder::new(spine);
+    let mut in_label = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"navPoint" => builder.open(),
+                b"text" => in_label = true,
+                b"content" => {
+                    if let Some(src) = attribute(&e, b"src")? {
+                        builder.set_target(&resolve(base, &src));
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"content" => {
+                if let Some(src) = attribute(&e, b"src")? {
+                    builder.set_target(&resolve(base, &src));
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"navPoint" => builder.close(),
+                b"text" => in_label = false,
+                b"navMap" => break,
+                _ => {}
+            },
+            Event::Text(text) if in_label => {
+                let raw = String::from_utf8_lossy(&text);
+                let text = quick_xml::escape::unescape_with(&raw, resolve_entity)
+                    .unwrap_or(Cow::Borrowed(&raw));
+                builder.push_text(&text);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(builder.finish())
+}
+
+/// An open element and the emphasis it added.
+struct Open {
+    name: Vec<u8>,
+    bold: bool,
+    italic: bool,
+    /// The element's `id` and the number of blocks before it.
+    anchor: Option<(String, usize)>,
+    /// Set on links which may be footnote references.
+    link: Option<Link>,
+}
+
+/// A link within the book, made into a footnote reference if it turns out to be one.
+struct Link {
+    target: String,
+    /// Whether the markup says it refers to a note, rather than it looking like one.
+    noteref: bool,
+    /// Index of the first span written inside the link.
+    first_span: usize,
+}
+
+/// Builds the blocks of a chapter while walking its XHTML.
+#[derive(Default)]
+struct Builder {
+    chapter: Chapter,
+    spans: Vec<Span>,
+    kind: Option<BlockKind>,
+    bold: usize,
+    italic: usize,
+    /// Depth inside elements whose text is never displayed.
+    hidden: usize,
+    /// Depth inside block quotes; paragraphs within them become quotes.
+    quote: usize,
+    /// Target of the open link, given to the spans written inside it.
+    link: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum BlockKind {
+    Heading(u8),
+    Paragraph,
+    ListItem,
+}
+
+impl Builder {
+    fn start_block(&mut self, kind: BlockKind) {
+        self.flush();
+        self.kind = Some(kind);
+
+        if let BlockKind::ListItem = kind {
+            self.push_text("• ");
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let bold = self.bold > 0;
+        let italic = self.italic > 0;
+
+        match self.spans.last_mut() {
+            Some(span) if span.bold == bold && span.italic == italic && span.note == self.link => {
+                span.text.push_str(text)
+            }
+            _ => self.spans.push(Span {
+                text: text.to_string(),
+                bold,
+                italic,
+                note: self.link.clone(),
+            }),
+        }
+    }
+
+    /// Appends text, collapsing whitespace as HTML rendering would.
+    fn push_collapsed(&mut self, text: &str) {
+        let mut collapsed = String::with_capacity(text.len());
+        let mut after_space = self
+            .spans
+            .last()
+            .is_none_or(|span| span.text.ends_with([' ', '\n']));
+
+        for c in text.chars() {
+            // Only ASCII whitespace collapses; non-breaking spaces are kept.
+            if c.is_ascii_whitespace() {
+                if !after_space {
+                    collapsed.push(' ');
+                }
+                after_space = true;
+            } else {
+                collapsed.push(c);
+                after_space = false;
+            }
+        }
+
+        if !collapsed.is_empty() {
+            self.push_text(&collapsed);
+        }
+    }
+
+    /// Ends the current block, discarding it if it holds no text.
+    fn flush(&mut self) {
+        let mut spans = std::mem::take(&mut self.spans);
+
+        if let Some(last) = spans.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+        }
+
+        spans.retain(|span| !span.text.is_empty());
+
+        if spans.is_empty() {
+            return;
+        }
+
+        self.chapter
+            .blocks
+            .push(match self.kind.unwrap_or(BlockKind::Paragraph) {
+                BlockKind::Heading(level) => Block::Heading(level, spans),
+                _ if self.quote > 0 => Block::Quote(spans),
+                BlockKind::Paragraph | BlockKind::ListItem => Block::Paragraph(spans),
+            });
+    }
+
+    /// Ends a link, keeping it only if it refers to a note: either its markup says so,
+    /// or it points within the book and reads like a note marker such as `3` or `[*]`.
+    fn close_link(&mut self, link: Link) {
+        self.link = None;
+
+        let spans = self.spans.get_mut(link.first_span..).unwrap_or_default();
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        let marker = text.trim().trim_matches(['[', ']', '(', ')']);
+
+        if link.noteref || (!marker.is_empty() && marker.chars().count() <= 3) {
+            return;
+        }
+
+        for span in spans {
+            span.note = None;
+        }
+    }
+
+    fn push_image(&mut self, image: Image) {
+        self.flush();
+        self.chapter
+            .blocks
+            .push(Block::Image(self.chapter.images.len()));
+        self.chapter.images.push(image);
+    }
+}
+
+/// Parses the XHTML document at archive path `path`, noting where the images it shows
+/// are without reading them.
+fn parse_chapter(document: &[u8], path: &str) -> Result<Chapter, Error> {
+    let base = parent(path);
+    let mut reader = quick_xml::Reader::from_reader(document);
+    reader.config_mut().check_end_names = false;
+
+    let mut builder = Builder::default();
+    let mut open: Vec<Open> = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+
+                // Drawings and formulas are taken whole rather than walked as text.
+                if let b"svg" | b"math" = name.as_slice() {
+                    let end = e.name().as_ref().to_vec();
+                    let span = reader.read_to_end(QName(&end))?;
+
+                    if builder.hidden == 0 {
+                        let element =
+                            element(&e, &document[span.start as usize..span.end as usize]);
+
+                        match name.as_slice() {
+                            b"svg" => push_svg(&mut builder, &element, base)?,
+                            _ => push_math(&mut builder, &e, &element)?,
+                        }
+                    }
+
+                    continue;
+                }
+
+                let (bold, italic) = emphasis(&name, &e)?;
+
+                match name.as_slice() {
+                    b"head" | b"script" | b"style" => builder.hidden += 1,
+                    b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => {
+                        builder.start_block(BlockKind::Heading(name[1] - b'0'));
+                    }
+                    b"p" | b"div" | b"section" | b"td" | b"dt" | b"dd" => {
+                        builder.start_block(BlockKind::Paragraph);
+                    }
+                    b"blockquote" => {
+                        builder.start_block(BlockKind::Paragraph);
+                        builder.quote += 1;
+                    }
+                    b"li" => builder.start_block(BlockKind::ListItem),
+                    // Some generators close images with an end tag rather than `/>`.
+                    b"img" | b"image" => push_image(&mut builder, &e, base)?,
+                    _ => {}
+                }
+
+                let mut link = match name.as_slice() {
+                    b"a" if builder.link.is_none() => note_link(&e, path)?,
+                    _ => None,
+                };
+
+                if let Some(link) = link.as_mut() {
+                    link.first_span = builder.spans.len();
+                    builder.link = Some(link.target.clone());
+                }
+
+                let anchor = attribute(&e, b"id")?.map(|id| (id, builder.chapter.blocks.len()));
+
+                builder.bold += usize::from(bold);
+                builder.italic += usize::from(italic);
+                open.push(Open {
+                    name,
+                    bold,
+                    italic,
+                    anchor,
+                    link,
+                });
+            }
+
+            Event::End(e) => {
+                let name = e.local_name().as_ref().to_ascii_lowercase();
+
+                // Close everything up to the matching element to tolerate sloppy markup.
+                while let Some(element) = open.pop() {
+                    builder.bold -= usize::from(element.bold);
+                    builder.italic -= usize::from(element.italic);
+
+                    match element.name.as_slice() {
+                        b"head" | b"script" | b"style" => builder.hidden -= 1,
+                        b"blockquote" => {
+                            builder.flush();
+                            builder.kind = None;
+                            builder.quote -= 1;
+                        }
+                        b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" | b"p" | b"div"
+                        | b"section" | b"li" | b"td" | b"dt" | b"dd" => {
+                            builder.flush();
+                            builder.kind = None;
+                        }
+                        _ => {}
+                    }
+
+                    if let Some(link) = element.link {
+                        builder.close_link(link);
+                    }
+
+                    // An element within a paragraph spans the paragraph still being built.
+                    if let Some((id, start)) = element.anchor {
+                        let end = builder.chapter.blocks.len().max(start + 1);
+                        builder.chapter.anchors.entry(id).or_insert(start..end);
+                    }
+
+                    if element.name == name {
+                        break;
+                    }
+                }
+            }
+
+            Event::Empty(e) => match e.local_name().as_ref().to_ascii_lowercase().as_slice() {
+                b"a" => {
+                    if let Some(id) = attribute(&e, b"id")? {
+                        let start = builder.chapter.blocks.len();
+                        builder
+                            .chapter
+                            .anchors
+                            .entry(id)
+                            .or_insert(start..start + 1);
+                    }
+                }
+                b"br" => builder.push_text("\n"),
+                b"hr" => {
+                    builder.flush();
+                    builder.chapter.blocks.push(Block::Rule);
+                }
+                b"img" | b"image" => push_image(&mut builder, &e, base)?,
+                _ => {}
+            },
+
+            Event::Text(text) if builder.hidden == 0 => {
+                let raw = String::from_utf8_lossy(&text);
+                let text = quick_xml::escape::unescape_with(&raw, resolve_entity)
+                    .unwrap_or(Cow::Borrowed(&raw));
+                builder.push_collapsed(&text);
+            }
+
+            Event::CData(text) if builder.hidden == 0 => {
+                builder.push_collapsed(&String::from_utf8_lossy(&text));
+            }
+
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    builder.flush();
+
+    // Anchors at the very end may point past the last block.
+    let count = builder.chapter.blocks.len();
+    builder
+        .chapter
+        .anchors
+        .retain(|_, range| range.start < count);
+    for range in builder.chapter.anchors.values_mut() {
+        range.end = range.end.min(count);
+    }
+
+    Ok(builder.chapter)
+}
+
+/// Rebuilds an element from its start tag and the markup inside it, as a standalone
+/// document declaring the namespaces it uses.
+fn element(start: &BytesStart, inner: &[u8]) -> Vec<u8> {
+    let mut tag = String::from_utf8_lossy(start).into_owned();
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+
+    let namespace = match start.local_name().as_ref() {
+        b"svg" => "http://www.w3.org/2000/svg",
+        _ => "http://www.w3.org/1998/Math/MathML",
+    };
+
+    // Namespaces are often declared once on the document's root, which is left behind.
+    match name.split_once(':') {
+        Some((prefix, _)) if !tag.contains(&format!("xmlns:{prefix}=")) => {
+            tag.push_str(&format!(" xmlns:{prefix}=\"{namespace}\""));
+        }
+        None if !tag.contains("xmlns=") => {
+            tag.push_str(&format!(" xmlns=\"{namespace}\""));
+        }
+        _ => {}
+    }
+
+    let uses_xlink = inner.windows(6).any(|window| window == b"xlink:");
+    if uses_xlink && !tag.contains("xmlns:xlink=") {
+        tag.push_str(" xmlns:xlink=\"http://www.w3.org/1999/xlink\"");
+    }
+
+    let mut element = format!("<{tag}>").into_bytes();
+    element.extend_from_slice(inner);
+    element.extend_from_slice(format!("</{name}>").as_bytes());
+    element
+}
+
+/// Adds an SVG drawing, or the image it merely frames, as is common for covers.
+fn push_svg(builder: &mut Builder, element: &[u8], base: &str) -> Result<(), Error> {
+    let mut reader = quick_xml::Reader::from_reader(element);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"image" => {
+                if let Some(href) = attribute(&e, b"href")? {
+                    builder.push_image(Image::Entry(resolve(base, &href)));
+                }
+
+                return Ok(());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    builder.push_image(Image::Inline(element.to_vec()));
+    Ok(())
+}
+
+/// Adds the image an `img` or `image` element points to.
+fn push_image(builder: &mut Builder, e: &BytesStart, base: &str) -> Result<(), Error> {
+    let src = match attribute(e, b"src")? {
+        Some(src) => Some(src),
+        None => attribute(e, b"href")?,
+    };
+
+    if let Some(src) = src {
+        builder.push_image(Image::Entry(resolve(base, &src)));
+    }
+
+    Ok(())
+}
+
+/// Adds a formula, on its own line when it is displayed as a block.
+fn push_math(builder: &mut Builder, start: &BytesStart, element: &[u8]) -> Result<(), Error> {
+    let text = math::to_text(element);
+
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    if attribute(start, b"display")?.as_deref() == Some("block") {
+        builder.flush();
+        builder.chapter.blocks.push(Block::Math(text));
+    } else {
+        builder.push_collapsed(&text);
+    }
+
+    Ok(())
+}
+
+/// The link an `a` element makes within the book, if it has a fragment and so might
+/// point at a note. Links to other sites are left alone.
+fn note_link(e: &BytesStart, path: &str) -> Result<Option<Link>, Error> {
+    let Some(href) = attribute(e, b"href")? else {
+        return Ok(None);
+    };
+
+    let Some((document, fragment)) = href.split_once('#') else {
+        return Ok(None);
+    };
+
+    if fragment.is_empty() || document.contains(':') {
+        return Ok(None);
+    }
+
+    let document = if document.is_empty() {
+        path.to_string()
+    } else {
+        resolve(parent(path), document)
+    };
+
+    // EPUB 3 marks references with `epub:type`, and DPUB-ARIA with `role`.
+    let noteref = attribute(e, b"type")?
+        .into_iter()
+        .chain(attribute(e, b"role")?)
+        .any(|kind| {
+            kind.split_whitespace()
+                .any(|kind| matches!(kind, "noteref" | "doc-noteref"))
+        });
+
+    Ok(Some(Link {
+        target: format!("{document}#{}", percent_decode(fragment)),
+        noteref,
+        first_span: 0,
+    }))
+}
+
+/// Emphasis added by an element, from its name or a basic inline `style`.
+fn emphasis(name: &[u8], e: &BytesStart) -> Result<(bool, bool), Error> {
+    let mut bold = matches!(name, b"b" | b"strong" | b"th");
+    let mut italic = matches!(name, b"i" | b"em" | b"cite" | b"var");
+
+    if let Some(style) = attribute(e, b"style")? {
+        for declaration in style.split(';') {
+            let Some((property, value)) = declaration.split_once(':') else {
+                continue;
+            };
+
+            match (property.trim(), value.trim()) {
+                ("font-weight", "bold" | "bolder" | "600" | "700" | "800" | "900") => bold = true,
+                ("font-style", "italic" | "oblique") => italic = true,
+                _ => {}
+            }
+        }
+    }
+
+    Ok((bold, italic))
+}
+
+/// Resolves the HTML entities commonly found in EPUB documents.
+fn resolve_entity(entity: &str) -> Option<&'static str> {
+    Some(match entity {
+        "nbsp" => "\u{a0}",
+        "shy" => "\u{ad}",
+        "mdash" => "—",
+        "ndash" => "–",
+        "hellip" => "…",
+        "lsquo" => "‘",
+        "rsquo" => "’",
+        "ldquo" => "“",
+        "rdquo" => "”",
+        "laquo" => "«",
+        "raquo" => "»",
+        "copy" => "©",
+        "eacute" => "é",
+        _ => return quick_xml::escape::resolve_predefined_entity(entity),
+    })
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Result<Option<String>, Error> {
+    for attr in e.attributes().with_checks(false) {
+        let attr = attr?;
+        if attr.key.local_name().as_ref() == name {
+            return Ok(Some(attr.unescape_value()?.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The directory part of an archive path, without a trailing slash.
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Resolves an href relative to an archive directory, dropping any fragment.
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+
+    let mut parts: Vec<&str> = if href.starts_with('/') {
+        Vec::new()
+    } else {
+        base.split('/').filter(|part| !part.is_empty()).collect()
+    };
+
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        if let Some(byte) = escaped {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Writes the catalog out as CSV or JSON for spreadsheets and other tools.
+
+use crate::library::Book;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// A catalog field which may be included in an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Field {
+    Title,
+    Author,
+    Description,
+    Tags,
+    Series,
+    Collection,
+    Progress,
+    Rating,
+    Path,
+}
+
+impl Field {
+    pub const ALL: [Field; 9] = [
+        Field::Title,
+        Field::Author,
+        Field::Description,
+        Field::Tags,
+        Field::Series,
+        Field::Collection,
+        Field::Progress,
+        Field::Rating,
+        Field::Path,
+    ];
+
+    /// Column header and JSON key of the field.
+    pub fn key(self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Author => "author",
+            Field::Description => "description",
+            Field::Tags => "tags",
+            Field::Series => "series",
+            Field::Collection => "collection",
+            Field::Progress => "progress",
+            Field::Rating => "rating",
+            Field::Path => "path",
+        }
+    }
+
+    /// The field's value as plain text, as in a CSV cell.
+    pub fn text(self, book: &Book) -> String {
+        csv_cell(self.value(book))
+    }
+
+    fn value(self, book: &Book) -> Value {
+        match self {
+            Field::Title => Value::from(book.title.as_str()),
+            Field::Author => Value::from(book.author.as_str()),
+            Field::Description => Value::from(book.description.as_str()),
+            Field::Tags => Value::from(book.tags.clone()),
+            Field::Series => Value::from(book.series.clone()),
+            Field::Collection => Value::from(book.collection.clone()),
+            // Rounded in `f64`, as widening the `f32` shows digits it never held.
+            Field::Progress => Value::from((f64::from(book.progress) * 1e4).round() / 1e4),
+            Field::Rating => Value::from(book.rating),
+            Field::Path => Value::from(
+                book.path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().into_owned()),
+            ),
+        }
+    }
+}
+
+/// Default location offered for an export in the given format.
+pub fn default_path(format: Format) -> PathBuf {
+    dirs::document_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join("libby-library")
+        .with_extension(format.extension())
+}
+
+/// Renders the selected fields of each book in the requested format.
+pub fn render(books: &[Book], fields: &[Field], format: Format) -> String {
+    match format {
+        Format::Csv => {
+            let mut out = String::new();
+
+            push_csv_row(&mut out, fields.iter().map(|field| field.key().to_string()));
+
+            for book in books {
+                push_csv_row(
+                    &mut out,
+                    fields.iter().map(|field| csv_cell(field.value(book))),
+                );
+            }
+
+            out
+        }
+
+        Format::Json => {
+            let records: Vec<Value> = books
+                .iter()
+                .map(|book| {
+                    let record: Map<String, Value> = fields
+                        .iter()
+                        .map(|field| (field.key().to_string(), field.value(book)))
+                        .collect();
+
+                    Value::Object(record)
+                })
+                .collect();
+
+            serde_json::to_string_pretty(&records).unwrap_or_default()
+        }
+    }
+}
+
+/// Flattens a value into the text of a CSV cell; lists are joined with semicolons.
+fn csv_cell(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        Value::Array(items) => items
+            .into_iter()
+            .map(csv_cell)
+            .collect::<Vec<_>>()
+            .join("; "),
+        other => other.to_string(),
+    }
+}
+
+fn push_csv_row(out: &mut String, cells: impl Iterator<Item = String>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        if cell.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&cell);
+        }
+    }
+
+    out.push_str("\r\n");
+}
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Choosing files and folders through the XDG desktop portal, which shows the system's
+//! file chooser and grants access to what is chosen from inside a Flatpak sandbox.
+//!
+//! Each chooser returns nothing when it's cancelled.
+
+use crate::fl;
+use ashpd::desktop::file_chooser::{FileFilter, SelectedFiles};
+use ashpd::desktop::ResponseError;
+use std::path::PathBuf;
+
+/// Kinds of file a chooser offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// EPUBs, PDFs and comic archives.
+    Books,
+    /// PEM files of certificates.
+    Certificates,
+    /// Files the library is exported to.
+    Exports,
+}
+
+impl Filter {
+    fn file_filter(self) -> FileFilter {
+        match self {
+            Filter::Books => {
+                let filter = FileFilter::new(&fl!("file-dialog-books"))
+                    .mimetype("application/epub+zip")
+                    .mimetype("application/vnd.comicbook+zip")
+                    .mimetype("application/vnd.comicbook-rar")
+                    .glob("*.epub")
+                    .glob("*.cbz")
+                    .glob("*.cbr");
+
+                if cfg!(feature = "pdf") {
+                    filter.mimetype("application/pdf").glob("*.pdf")
+                } else {
+                    filter
+                }
+            }
+            Filter::Certificates => FileFilter::new(&fl!("file-dialog-certificates"))
+                .mimetype("application/x-pem-file")
+                .glob("*.pem")
+                .glob("*.crt"),
+            Filter::Exports => FileFilter::new(&fl!("file-dialog-exports"))
+                .mimetype("text/csv")
+                .mimetype("application/json")
+                .glob("*.csv")
+                .glob("*.json"),
+        }
+    }
+}
+
+/// Asks for files to open, several at once if `multiple` is set.
+pub async fn open_files(
+    title: String,
+    filter: Filter,
+    multiple: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let request = SelectedFiles::open_file()
+        .title(title.as_str())
+        .modal(true)
+        .multiple(multiple)
+        .filter(filter.file_filter())
+        .send()
+        .await;
+
+    paths(request)
+}
+
+/// Asks for a folder.
+pub async fn open_folder(title: String) -> Result<Option<PathBuf>, String> {
+    let request = SelectedFiles::open_file()
+        .title(title.as_str())
+        .modal(true)
+        .directory(true)
+        .send()
+        .await;
+
+    paths(request).map(|paths| paths.into_iter().next())
+}
+
+/// Asks where to save a file, suggesting `current` if it's given.
+pub async fn save_file(
+    title: String,
+    filter: Filter,
+    current: Option<PathBuf>,
+) -> Result<Option<PathBuf>, String> {
+    let mut request = SelectedFiles::save_file()
+        .title(title.as_str())
+        .modal(true)
+        .filter(filter.file_filter());
+
+    if let Some(current) = &current {
+        let name = current
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+
+        request = request.current_name(name.as_deref());
+
+        if let Some(folder) = current.parent().filter(|folder| folder.is_dir()) {
+            request = request
+                .current_folder(folder)
+                .map_err(|why| why.to_string())?;
+        }
+    }
+
+    paths(request.send().await).map(|paths| paths.into_iter().next())
+}
+
+/// The local paths chosen in answer to a request.
+fn paths(
+    request: ashpd::Result<ashpd::desktop::Request<SelectedFiles>>,
+) -> Result<Vec<PathBuf>, String> {
+    let response = request.and_then(|request| request.response());
+
+    match response {
+        Ok(files) => Ok(files
+            .uris()
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect()),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(Vec::new()),
+        Err(why) => Err(why.to_string()),
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Follows atproto accounts within the app, raising desktop notifications for their
+//! new posts.
+
+use crate::atproto::{self, Feed, Post};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often followed accounts are checked for new posts.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Notifications raised per account and check, beyond which they are summed up.
+pub const MAX_NOTIFICATIONS: usize = 3;
+
+/// An account followed from the feed page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Followed {
+    /// Handle or DID the account was followed by.
+    pub actor: String,
+    /// Whether new posts raise a desktop notification.
+    pub notify: bool,
+    /// Seconds since the Unix epoch at which the newest post seen was written, or 0
+    /// before the account has been checked.
+    #[serde(default)]
+    pub last_seen: u64,
+}
+
+/// Posts by a followed account newer than the last one seen.
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub actor: String,
+    /// New posts, newest first; empty on the first check, which only notes where the
+    /// account is at.
+    pub posts: Vec<Post>,
+    /// When the newest post was written.
+    pub newest: u64,
+}
+
+/// Checks each account for posts written since its last check.
+pub async fn poll(followed: Vec<Followed>) -> Vec<Update> {
+    let mut updates = Vec::new();
+
+    for account in followed {
+        let page = match atproto::feed(None, Feed::Author(account.actor.clone()), None).await {
+            Ok((page, _)) => page,
+            Err(why) => {
+                tracing::error!("failed to check {} for new posts: {why}", account.actor);
+                continue;
+            }
+        };
+
+        // Reposts bring up older posts by others, which aren't news.
+        let posts: Vec<(u64, Post)> = page
+            .posts
+            .into_iter()
+            .filter(|post| post.reposted_by.is_none())
+            .filter_map(|post| Some((written(&post)?, post)))
+            .collect();
+
+        let Some(newest) = posts.iter().map(|(written, _)| *written).max() else {
+            continue;
+        };
+
+        if newest <= account.last_seen {
+            continue;
+        }
+
+        let posts = if account.last_seen == 0 {
+            Vec::new()
+        } else {
+            posts
+                .into_iter()
+                .filter(|(written, _)| *written > account.last_seen)
+                .map(|(_, post)| post)
+                .collect()
+        };
+
+        updates.push(Update {
+            actor: account.actor,
+            posts,
+            newest,
+        });
+    }
+
+    updates
+}
+
+/// Seconds since the Unix epoch at which the post was written.
+fn written(post: &Post) -> Option<u64> {
+    let time = chrono::DateTime::parse_from_rfc3339(&post.created).ok()?;
+    u64::try_from(time.timestamp()).ok()
+}
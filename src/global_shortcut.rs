@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Shortcuts working from anywhere on the desktop, bound through the XDG desktop
+//! portal, which lets the user confirm or change the keys.
+
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use ashpd::desktop::Session;
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream, StreamExt};
+
+/// ID of the shortcut showing the window.
+const SUMMON: &str = "summon";
+
+/// ID of the shortcut showing the search field.
+const SEARCH: &str = "search";
+
+/// A global shortcut that was pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// Show and focus the window.
+    Summon,
+    /// Show the window with the search field focused, or close the search if it is.
+    Search,
+    /// The shortcuts couldn't be bound, such as when the desktop doesn't offer them.
+    Failed,
+}
+
+/// What the shortcuts are called in the desktop's settings, and the keys they ask for.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bindings {
+    pub summon: String,
+    /// Preferred keys for summoning, as the XDG shortcuts specification writes them,
+    /// such as `LOGO+ALT+L`, or empty to leave them to the user.
+    pub summon_trigger: String,
+    pub search: String,
+    pub search_trigger: String,
+}
+
+/// Closes the portal's session once the subscription binding the shortcuts ends, which
+/// unbinds them.
+struct Bound(Option<Session<'static, GlobalShortcuts<'static>>>);
+
+impl Drop for Bound {
+    fn drop(&mut self) {
+        let (Some(session), Ok(runtime)) = (self.0.take(), tokio::runtime::Handle::try_current())
+        else {
+            return;
+        };
+
+        runtime.spawn(async move {
+            _ = session.close().await;
+        });
+    }
+}
+
+/// Binds the shortcuts for as long as the stream is polled, yielding those pressed.
+pub fn listen(bindings: Bindings) -> impl Stream<Item = Shortcut> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        if let Err(why) = bind(bindings, output.clone()).await {
+            tracing::error!("failed to bind global shortcuts: {why}");
+            _ = output.send(Shortcut::Failed).await;
+        }
+    })
+}
+
+async fn bind(bindings: Bindings, mut output: Sender<Shortcut>) -> ashpd::Result<()> {
+    let shortcuts = [
+        NewShortcut::new(SUMMON, bindings.summon)
+            .preferred_trigger(trigger(&bindings.summon_trigger)),
+        NewShortcut::new(SEARCH, bindings.search)
+            .preferred_trigger(trigger(&bindings.search_trigger)),
+    ];
+
+    let portal = GlobalShortcuts::new().await?;
+    let session = portal.create_session().await?;
+
+    portal
+        .bind_shortcuts(&session, &shortcuts, None)
+        .await?
+        .response()?;
+
+    let _bound = Bound(Some(session));
+    let mut activated = portal.receive_activated().await?;
+
+    while let Some(activated) = activated.next().await {
+        let shortcut = match activated.shortcut_id() {
+            SUMMON => Shortcut::Summon,
+            SEARCH => Shortcut::Search,
+            _ => continue,
+        };
+
+        if output.send(shortcut).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// The preferred keys to ask for, unless none are.
+fn trigger(trigger: &str) -> Option<&str> {
+    Some(trigger.trim()).filter(|trigger| !trigger.is_empty())
+}
@@ -0,0 +1,548 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Work on the library without starting the app, such as preparing a library on a
+//! server, or reading the catalog from shell pipelines.
+//!
+//! Imports and checks read the catalog once and write it back at the end, so they hold
+//! the catalog's lock meanwhile, and fail straight away while the app or the daemon
+//! holds it instead.
+//!
+//! With `--json`, each command prints a single JSON document instead of its text:
+//!
+//! - `import`: `{"total", "failed", "duration_ms", "books": [{"file", "status", "id", "title",
+//!   "error"}]}`, where `status` is `imported`, `skipped` when already in the library, or
+//!   `failed`.
+//! - `verify`: `{"total", "failed", "duration_ms", "books": [{"id", "title", "path", "status",
+//!   "moved_to", "hashed"}]}`, where `status` is `ok`, `missing`, `corrupted` or `moved`, and
+//!   `hashed` is false for files taken as intact for being unchanged since last checked.
+//! - `query`: an array of objects keyed by the fields asked for.
+//! - `export`: the books as JSON, or `{"output", "total"}` when written to a file.
+//!
+//! Keys are always present, being `null` when they don't apply.
+
+use crate::cli::{Filter, Outcome};
+use crate::comic;
+use crate::export::{self, Field};
+use crate::import;
+use crate::integrity::{self, Status};
+use crate::library::{Book, BookId, FileStamp, Library, Lock};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Extensions of the files imported from a folder.
+const EXTENSIONS: [&str; 2] = ["epub", "pdf"];
+
+/// What a command did to each book, as printed with `--json`.
+#[derive(Debug, Serialize)]
+struct Summary<T> {
+    total: usize,
+    /// How many books failed, or were found with problems.
+    failed: usize,
+    /// How long the command took, in milliseconds.
+    duration_ms: u128,
+    books: Vec<T>,
+}
+
+/// What became of a file found by an import.
+#[derive(Debug, Serialize)]
+pub struct Imported {
+    pub file: PathBuf,
+    pub status: ImportStatus,
+    /// The book in the library, unless it failed.
+    pub id: Option<BookId>,
+    pub title: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Imported,
+    /// Already in the library.
+    Skipped,
+    Failed,
+}
+
+/// How a book's file was found by a check.
+#[derive(Debug, Serialize)]
+struct Verified {
+    id: BookId,
+    title: String,
+    path: Option<PathBuf>,
+    status: &'static str,
+    moved_to: Option<PathBuf>,
+    hashed: bool,
+}
+
+/// Where an export was written.
+#[derive(Debug, Serialize)]
+struct Exported {
+    output: PathBuf,
+    total: usize,
+}
+
+/// Copies each book found in `source` and its folders into the library, skipping those
+/// already in it. With `-` as the source, the books are the files and folders listed on
+/// standard input, one a line, such as by `find`.
+pub fn import(source: PathBuf, json: bool) -> Outcome {
+    let (files, from) = if source == Path::new("-") {
+        (
+            listed_books(io::stdin().lock()),
+            "standard input".to_string(),
+        )
+    } else {
+        let mut files = Vec::new();
+
+        if let Err(why) = collect_books(&source, &mut files) {
+            tracing::error!("failed to list {}: {why}", source.display());
+            return Outcome::Failed;
+        }
+
+        files.sort();
+        (files, source.display().to_string())
+    };
+
+    run(async move {
+        let started = Instant::now();
+        let Some(_lock) = lock_library() else {
+            return Outcome::Failed;
+        };
+        let Some(mut library) = load_library() else {
+            return Outcome::Failed;
+        };
+        let total = files.len();
+        let mut books = Vec::new();
+
+        if !json {
+            println!("Found {total} books in {from}");
+        }
+
+        for (index, file) in files.into_iter().enumerate() {
+            let progress = format!("[{}/{total}]", index + 1);
+
+            let imported = import_one(&mut library, file).await;
+
+            if !json {
+                match (&imported.title, &imported.error) {
+                    (_, Some(why)) => {
+                        println!(
+                            "{progress} Failed to import {}: {why}",
+                            imported.file.display()
+                        )
+                    }
+                    (Some(title), None) if imported.status == ImportStatus::Skipped => {
+                        println!("{progress} Already in the library: {title}")
+                    }
+                    (title, None) => {
+                        println!(
+                            "{progress} Imported {}",
+                            title.as_deref().unwrap_or_default()
+                        )
+                    }
+                }
+            }
+
+            books.push(imported);
+        }
+
+        if let Err(why) = library.save() {
+            tracing::error!("failed to save library: {why}");
+            return Outcome::Failed;
+        }
+
+        let failed = books
+            .iter()
+            .filter(|book| book.status == ImportStatus::Failed)
+            .count();
+
+        if json {
+            print_json(&Summary {
+                total,
+                failed,
+                duration_ms: started.elapsed().as_millis(),
+                books,
+            });
+        } else {
+            println!(
+                "Imported {} of {total} books in {:.1}s",
+                total - failed,
+                started.elapsed().as_secs_f32()
+            );
+        }
+
+        Outcome::of(failed, total)
+    })
+}
+
+/// Copies a file into the library, unless a book with the same contents is in it.
+pub async fn import_one(library: &mut Library, file: PathBuf) -> Imported {
+    let failed = |file, why| Imported {
+        file,
+        status: ImportStatus::Failed,
+        id: None,
+        title: None,
+        error: Some(why),
+    };
+
+    let hash = match integrity::hash_file(file.clone()).await {
+        Ok(hash) => hash,
+        Err(why) => return failed(file, format!("failed to read: {why}")),
+    };
+
+    if let Some(book) = library
+        .books()
+        .iter()
+        .find(|book| !book.is_trashed() && book.hash.as_deref() == Some(hash.as_str()))
+    {
+        return Imported {
+            file,
+            status: ImportStatus::Skipped,
+            id: Some(book.id),
+            title: Some(display_title(book)),
+            error: None,
+        };
+    }
+
+    match import::import_file(file.clone()).await {
+        Ok(book) => {
+            let title = display_title(&book);
+            let stamp = book.path.as_deref().and_then(FileStamp::of);
+            let id = library.insert(Book {
+                hash: Some(hash),
+                stamp,
+                ..book
+            });
+
+            Imported {
+                file,
+                status: ImportStatus::Imported,
+                id: Some(id),
+                title: Some(title),
+                error: None,
+            }
+        }
+        Err(why) => failed(file, why),
+    }
+}
+
+/// Checks every book's file against the hash recorded for it, recording hashes for
+/// books that have none. With `full`, files unchanged since they were last checked are
+/// hashed again too.
+pub fn verify(full: bool, json: bool) -> Outcome {
+    run(async move {
+        let started = Instant::now();
+        let Some(_lock) = lock_library() else {
+            return Outcome::Failed;
+        };
+        let Some(mut library) = load_library() else {
+            return Outcome::Failed;
+        };
+
+        let books: Vec<Book> = library
+            .books()
+            .iter()
+            .filter(|book| !book.is_trashed() && book.path.is_some())
+            .cloned()
+            .collect();
+
+        let total = books.len();
+        let mut verified = Vec::new();
+        let mut reports = std::pin::pin!(integrity::verify(books, full));
+
+        while let Some(report) = reports.next().await {
+            let Some(book) = library.get_mut(report.id) else {
+                continue;
+            };
+
+            let (status, moved_to) = match &report.status {
+                Status::Ok => ("ok", None),
+                Status::Missing => ("missing", None),
+                Status::Corrupted => ("corrupted", None),
+                Status::Moved(path) => ("moved", Some(path.clone())),
+            };
+
+            if !json {
+                let outcome = match &moved_to {
+                    Some(path) => format!("Moved to {}", path.display()),
+                    None => status.to_uppercase(),
+                };
+
+                println!(
+                    "[{}/{total}] {outcome}: {}",
+                    verified.len() + 1,
+                    display_title(book)
+                );
+            }
+
+            report.apply(book);
+
+            verified.push(Verified {
+                id: book.id,
+                title: display_title(book),
+                path: book.path.clone(),
+                status,
+                moved_to,
+                hashed: report.hashed,
+            });
+        }
+
+        if let Err(why) = library.save() {
+            tracing::error!("failed to save library: {why}");
+            return Outcome::Failed;
+        }
+
+        let failed = verified.iter().filter(|book| book.status != "ok").count();
+
+        if json {
+            print_json(&Summary {
+                total,
+                failed,
+                duration_ms: started.elapsed().as_millis(),
+                books: verified,
+            });
+        } else {
+            let hashed = verified.iter().filter(|book| book.hashed).count();
+
+            println!(
+                "Verified {total} books in {:.1}s, {failed} with problems; hashed {hashed}, \
+                 the rest unchanged since last checked",
+                started.elapsed().as_secs_f32()
+            );
+        }
+
+        // Books found with problems don't make the check itself a failure.
+        if failed > 0 {
+            Outcome::Partial
+        } else {
+            Outcome::Done
+        }
+    })
+}
+
+/// Prints the fields of each book matching the filter, one book a line with the fields
+/// separated by tabs.
+pub fn query(filter: &Filter, fields: &[Field], json: bool) -> Outcome {
+    let Some(library) = load_library() else {
+        return Outcome::Failed;
+    };
+    let books = library.query(&filter.query()).into_iter();
+
+    if json {
+        let books: Vec<Book> = books.cloned().collect();
+        let mut contents = export::render(&books, fields, export::Format::Json);
+        contents.push('\n');
+        return write_stdout(&contents);
+    }
+
+    let mut out = io::stdout().lock();
+
+    for book in books {
+        let line: Vec<String> = fields
+            .iter()
+            .map(|field| field.text(book).replace(['\t', '\n', '\r'], " "))
+            .collect();
+
+        if let Err(why) = writeln!(out, "{}", line.join("\t")) {
+            return print_failed(why);
+        }
+    }
+
+    Outcome::Done
+}
+
+/// Writes the books matching the filter out in the format, to the file or else to
+/// standard output, with all fields when none are given.
+///
+/// With `json`, standard output gets the books as JSON whatever the format.
+pub fn export(
+    filter: &Filter,
+    fields: &[Field],
+    format: export::Format,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Outcome {
+    let Some(library) = load_library() else {
+        return Outcome::Failed;
+    };
+
+    let books: Vec<Book> = library
+        .query(&filter.query())
+        .into_iter()
+        .cloned()
+        .collect();
+
+    let fields = if fields.is_empty() {
+        &Field::ALL[..]
+    } else {
+        fields
+    };
+
+    let Some(output) = output else {
+        let format = if json { export::Format::Json } else { format };
+        return write_stdout(&export::render(&books, fields, format));
+    };
+
+    if let Err(why) = std::fs::write(&output, export::render(&books, fields, format)) {
+        tracing::error!("failed to export to {}: {why}", output.display());
+        return Outcome::Failed;
+    }
+
+    if json {
+        print_json(&Exported {
+            output,
+            total: books.len(),
+        });
+    }
+
+    Outcome::Done
+}
+
+/// The catalog, or `None` once why it can't be read is logged, so that nothing is
+/// saved over it.
+/// Holds the catalog for a command which writes it back, unless another process does.
+fn lock_library() -> Option<Lock> {
+    match Lock::try_acquire() {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            tracing::error!(
+                "the library is in use by libby or its daemon, try again once they're done"
+            );
+            None
+        }
+        Err(why) => {
+            tracing::error!("failed to lock the library: {why}");
+            None
+        }
+    }
+}
+
+fn load_library() -> Option<Library> {
+    Library::load()
+        .inspect_err(|why| tracing::error!("{why}"))
+        .ok()
+}
+
+fn print_json(value: &impl Serialize) -> Outcome {
+    match serde_json::to_string_pretty(value) {
+        Ok(mut contents) => {
+            contents.push('\n');
+            write_stdout(&contents)
+        }
+        Err(why) => {
+            tracing::error!("failed to print: {why}");
+            Outcome::Failed
+        }
+    }
+}
+
+fn write_stdout(contents: &str) -> Outcome {
+    match io::stdout().lock().write_all(contents.as_bytes()) {
+        Ok(()) => Outcome::Done,
+        Err(why) => print_failed(why),
+    }
+}
+
+fn print_failed(why: io::Error) -> Outcome {
+    // Such as when piped into `head`, which has read all it wants.
+    if why.kind() == io::ErrorKind::BrokenPipe {
+        return Outcome::Done;
+    }
+
+    tracing::error!("failed to print: {why}");
+    Outcome::Failed
+}
+
+/// Runs batch work to completion, without the app's runtime.
+pub fn run(work: impl std::future::Future<Output = Outcome>) -> Outcome {
+    match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(work),
+        Err(why) => {
+            tracing::error!("failed to start the runtime: {why}");
+            Outcome::Failed
+        }
+    }
+}
+
+/// The book's title, or its file's name when it has none.
+pub fn display_title(book: &Book) -> String {
+    if !book.title.is_empty() {
+        return book.title.clone();
+    }
+
+    book.path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The files listed in `input`, in order, with the books below each folder listed.
+fn listed_books(input: impl io::BufRead) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(why) => {
+                tracing::error!("failed to read the books to import: {why}");
+                break;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let path = PathBuf::from(line);
+
+        if path.is_dir() {
+            let mut below = Vec::new();
+
+            if let Err(why) = collect_books(&path, &mut below) {
+                tracing::error!("failed to list {}: {why}", path.display());
+            }
+
+            below.sort();
+            files.extend(below);
+        } else {
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+/// Gathers the books below `dir`, by their extensions, failing if `dir` itself can't
+/// be listed. Folders within it which can't be are left out.
+pub fn collect_books(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if let Err(why) = collect_books(&path, out) {
+                tracing::error!("failed to list {}: {why}", path.display());
+            }
+
+            continue;
+        }
+
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if EXTENSIONS.contains(&extension.as_str())
+            || comic::EXTENSIONS.contains(&extension.as_str())
+        {
+            out.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,631 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Lets other devices sync with this one, serving the protocol described in
+//! [`companion`] on the local network.
+//!
+//! The host keeps every book's latest entry along with the cursor it was recorded at,
+//! so devices pull only what changed since they last did. Devices pair by the code
+//! shown in Settings, which changes once used; they are remembered by a hash of the
+//! token they were given, so the file the host is saved to holds no usable tokens.
+//!
+//! Pairing is only possible for a short while after it is started from Settings, the
+//! user confirms each device that gets the code right, and an address which keeps
+//! getting it wrong is locked out for a while.
+//!
+//! [`companion`]: crate::companion
+
+use crate::catalog::Entry;
+use crate::companion::{Change, Changes, Cursor, Pair, Paired, Push};
+use axum::extract::ws::{self, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::{SinkExt, Stream};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::future::IntoFuture;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot};
+
+/// Wrong pairing codes tried before the code is replaced, and before the address they
+/// came from is locked out, so it can't be guessed.
+const PAIRING_ATTEMPTS: u32 = 5;
+
+/// How long pairing stays possible once it is started.
+pub const PAIRING_WINDOW: Duration = Duration::from_secs(120);
+
+/// How long an address which got the code wrong too often can't try again.
+const LOCKOUT: Duration = Duration::from_secs(600);
+
+/// How long a device waits for the user to confirm it, short of the time devices give
+/// up waiting for an answer.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Random bytes in a device's token.
+const TOKEN_BYTES: usize = 32;
+
+/// A book as last recorded, or deleted if `entry` is `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    /// Cursor the change was recorded at.
+    cursor: u64,
+    entry: Option<Entry>,
+}
+
+/// A device paired with the host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub name: String,
+    /// Hash of the device's token.
+    token: String,
+}
+
+/// What the host keeps between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    cursor: u64,
+    books: BTreeMap<String, Record>,
+    devices: Vec<Device>,
+}
+
+#[derive(Debug)]
+struct Pairing {
+    /// The code devices pair with, if one could be made.
+    code: Option<String>,
+    failures: u32,
+    /// Until when devices can pair, if pairing was started.
+    open_until: Option<Instant>,
+    /// Wrong codes by the address they came from.
+    strikes: HashMap<IpAddr, Strikes>,
+    /// The device waiting for the user to confirm it.
+    pending: Option<Pending>,
+    /// Counts up with each device asking to pair, to tell their requests apart.
+    requests: u64,
+}
+
+impl Default for Pairing {
+    fn default() -> Self {
+        Self {
+            code: new_code(),
+            failures: 0,
+            open_until: None,
+            strikes: HashMap::new(),
+            pending: None,
+            requests: 0,
+        }
+    }
+}
+
+impl Pairing {
+    fn is_open(&self) -> bool {
+        self.open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Strikes {
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    id: u64,
+    answer: oneshot::Sender<bool>,
+}
+
+/// A device which got the pairing code right, waiting for the user to confirm it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub id: u64,
+    pub device: String,
+    pub address: IpAddr,
+}
+
+/// Events of a running host.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A device pushed changes, recorded at the cursor.
+    Changed(u64),
+    /// A device got the pairing code right, and is to be confirmed.
+    Requested(Request),
+    /// The device with this request ID stopped waiting to be confirmed.
+    Withdrawn(u64),
+    /// A device paired, using up the pairing code.
+    Paired(String),
+    Failed(String),
+}
+
+/// The host's state, shared between the app and the server.
+#[derive(Debug, Clone)]
+pub struct Host {
+    store: Arc<Mutex<Store>>,
+    pairing: Arc<Mutex<Pairing>>,
+    events: broadcast::Sender<Event>,
+}
+
+impl Host {
+    /// Opens the host as it was left by the last run.
+    pub fn load() -> Self {
+        let store = path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| match serde_json::from_slice(&bytes) {
+                Ok(store) => Some(store),
+                Err(why) => {
+                    tracing::error!("failed to parse the companion host's state: {why}");
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            store: Arc::new(Mutex::new(store)),
+            pairing: Arc::new(Mutex::new(Pairing::default())),
+            events: broadcast::channel(16).0,
+        }
+    }
+
+    /// The code devices pair with, or `None` if none could be made, so none can pair.
+    pub fn code(&self) -> Option<String> {
+        lock(&self.pairing).code.clone()
+    }
+
+    /// Replaces the pairing code, so the old one can no longer be used.
+    pub fn renew_code(&self) {
+        let mut pairing = lock(&self.pairing);
+        pairing.code = new_code();
+        pairing.failures = 0;
+    }
+
+    /// Lets devices pair for the next [`PAIRING_WINDOW`], with a new code.
+    pub fn open_pairing(&self) {
+        let mut pairing = lock(&self.pairing);
+        pairing.code = new_code();
+        pairing.failures = 0;
+        pairing.open_until = Some(Instant::now() + PAIRING_WINDOW);
+    }
+
+    /// Stops devices from pairing, turning away any still waiting to be confirmed.
+    pub fn close_pairing(&self) {
+        let mut pairing = lock(&self.pairing);
+        pairing.open_until = None;
+        pairing.pending = None;
+    }
+
+    /// Whether devices can pair.
+    pub fn pairing_open(&self) -> bool {
+        lock(&self.pairing).is_open()
+    }
+
+    /// Answers the device waiting to be confirmed, if it is still the one `id` asked.
+    pub fn confirm(&self, id: u64, accepted: bool) {
+        let mut pairing = lock(&self.pairing);
+
+        if pairing
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.id == id)
+        {
+            if let Some(pending) = pairing.pending.take() {
+                _ = pending.answer.send(accepted);
+            }
+        }
+    }
+
+    /// Names of the paired devices.
+    pub fn devices(&self) -> Vec<String> {
+        lock(&self.store)
+            .devices
+            .iter()
+            .map(|device| device.name.clone())
+            .collect()
+    }
+
+    /// Unpairs a device, which can no longer sync until it pairs again.
+    pub fn forget_device(&self, index: usize) {
+        let mut store = lock(&self.store);
+
+        if index < store.devices.len() {
+            store.devices.remove(index);
+            save(&store);
+        }
+    }
+
+    /// Checks the code a device at `address` pairs with, returning the ID of its
+    /// request and where the user's answer arrives if it is right.
+    fn request(
+        &self,
+        address: IpAddr,
+        request: &Pair,
+    ) -> Result<(u64, oneshot::Receiver<bool>), StatusCode> {
+        let now = Instant::now();
+        let mut pairing = lock(&self.pairing);
+
+        if !pairing.is_open() {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        pairing
+            .strikes
+            .retain(|_, strikes| strikes.locked_until.is_none_or(|until| now < until));
+
+        let locked = pairing
+            .strikes
+            .get(&address)
+            .is_some_and(|strikes| strikes.locked_until.is_some());
+
+        if locked {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        // One device is confirmed at a time, without using up the code for another.
+        if pairing.pending.is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        let Some(code) = &pairing.code else {
+            return Err(StatusCode::FORBIDDEN);
+        };
+
+        if request.code.trim() != code {
+            pairing.failures += 1;
+
+            if pairing.failures >= PAIRING_ATTEMPTS {
+                pairing.code = new_code();
+                pairing.failures = 0;
+            }
+
+            let strikes = pairing.strikes.entry(address).or_default();
+            strikes.failures += 1;
+
+            if strikes.failures >= PAIRING_ATTEMPTS {
+                strikes.locked_until = Some(now + LOCKOUT);
+            }
+
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        pairing.code = new_code();
+        pairing.failures = 0;
+        pairing.strikes.remove(&address);
+        pairing.requests += 1;
+
+        let id = pairing.requests;
+        let (answer, answered) = oneshot::channel();
+        pairing.pending = Some(Pending { id, answer });
+
+        Ok((id, answered))
+    }
+
+    /// Pairs a device the user confirmed, returning the token it is to use.
+    fn pair(&self, device: String) -> Option<String> {
+        let token = token()?;
+
+        let mut store = lock(&self.store);
+        store.devices.push(Device {
+            name: device.clone(),
+            token: hash(&token),
+        });
+        save(&store);
+        drop(store);
+
+        // Each device pairs on its own, so another has to be started for the next.
+        lock(&self.pairing).open_until = None;
+
+        _ = self.events.send(Event::Paired(device));
+        Some(token)
+    }
+
+    /// Whether a request's `Authorization` header holds the token of a paired device.
+    fn authorize(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+
+        let token = hash(token.trim());
+
+        lock(&self.store)
+            .devices
+            .iter()
+            .any(|device| device.token == token)
+    }
+
+    /// Books changed after `since`.
+    pub fn pull(&self, since: u64) -> Changes {
+        let store = lock(&self.store);
+
+        let changes = store
+            .books
+            .iter()
+            .filter(|(_, record)| record.cursor > since)
+            .map(|(key, record)| Change {
+                key: key.clone(),
+                entry: record.entry.clone(),
+            })
+            .collect();
+
+        Changes {
+            cursor: store.cursor,
+            changes,
+        }
+    }
+
+    /// Records changes pulled up to `since`, returning the new cursor, or `None` if
+    /// other changes were recorded after `since`.
+    pub fn push(&self, push: Push) -> Option<u64> {
+        let mut store = lock(&self.store);
+
+        if push.since != store.cursor {
+            return None;
+        }
+
+        if push.changes.is_empty() {
+            return Some(store.cursor);
+        }
+
+        store.cursor += 1;
+        let cursor = store.cursor;
+
+        for change in push.changes {
+            store.books.insert(
+                change.key,
+                Record {
+                    cursor,
+                    entry: change.entry,
+                },
+            );
+        }
+
+        save(&store);
+
+        _ = self.events.send(Event::Changed(cursor));
+        Some(cursor)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("host.json"))
+}
+
+/// Saves the host's state, while it is locked so saves can't land out of order.
+fn save(store: &Store) {
+    let Some(path) = path() else {
+        return;
+    };
+
+    let result = serde_json::to_vec(store)
+        .map_err(std::io::Error::from)
+        .and_then(|bytes| {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&path, bytes)
+        });
+
+    if let Err(why) = result {
+        tracing::error!("failed to save the companion host's state: {why}");
+    }
+}
+
+/// A new token for a device, from the operating system's random source.
+fn token() -> Option<String> {
+    let mut bytes = [0; TOKEN_BYTES];
+
+    if let Err(why) = getrandom::fill(&mut bytes) {
+        tracing::error!("failed to make a token for a device: {why}");
+        return None;
+    }
+
+    Some(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// A new pairing code, from the operating system's random source, or `None` if it
+/// can't be read, in which case no device can pair.
+fn new_code() -> Option<String> {
+    let mut bytes = [0; 8];
+
+    if let Err(why) = getrandom::fill(&mut bytes) {
+        tracing::error!("failed to make a pairing code: {why}");
+        return None;
+    }
+
+    Some(format!("{:06}", u64::from_le_bytes(bytes) % 1_000_000))
+}
+
+fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The address devices on the local network reach the host at.
+pub fn address(port: u16) -> Option<String> {
+    // Connecting a UDP socket sends nothing, but picks the interface a packet to the
+    // address would leave from.
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+
+    Some(format!("http://{ip}:{port}"))
+}
+
+/// Serves the host on `port` until the stream is dropped, yielding what devices do.
+pub fn serve(host: Host, port: u16) -> impl Stream<Item = Event> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let listener =
+            match tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+                .await
+            {
+                Ok(listener) => listener,
+                Err(why) => {
+                    _ = output.send(Event::Failed(why.to_string())).await;
+                    return;
+                }
+            };
+
+        let mut events = host.events.subscribe();
+
+        let router = Router::new()
+            .route("/v1/pair", post(pair))
+            .route("/v1/changes", get(changes).post(push))
+            .route("/v1/events", get(events_socket))
+            .with_state(host);
+
+        let forward = async {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if output.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        tokio::select! {
+            result = axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            ).into_future() => {
+                if let Err(why) = result {
+                    tracing::error!("the companion host stopped: {why}");
+                }
+            }
+            () = forward => (),
+        }
+    })
+}
+
+async fn pair(
+    State(host): State<Host>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Json(request): Json<Pair>,
+) -> Result<Json<Paired>, StatusCode> {
+    let (id, answered) = host.request(address.ip(), &request)?;
+
+    _ = host.events.send(Event::Requested(Request {
+        id,
+        device: request.device.clone(),
+        address: address.ip(),
+    }));
+
+    match tokio::time::timeout(CONFIRM_TIMEOUT, answered).await {
+        Ok(Ok(true)) => host
+            .pair(request.device)
+            .map(|token| Json(Paired { token }))
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Ok(_) => Err(StatusCode::FORBIDDEN),
+        Err(_) => {
+            // Nobody answered in time, so the request can't be confirmed any more.
+            let mut pairing = lock(&host.pairing);
+
+            if pairing
+                .pending
+                .as_ref()
+                .is_some_and(|pending| pending.id == id)
+            {
+                pairing.pending = None;
+            }
+
+            drop(pairing);
+            _ = host.events.send(Event::Withdrawn(id));
+
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Since {
+    #[serde(default)]
+    since: u64,
+}
+
+async fn changes(
+    State(host): State<Host>,
+    headers: HeaderMap,
+    Query(query): Query<Since>,
+) -> Result<Json<Changes>, StatusCode> {
+    if !host.authorize(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(host.pull(query.since)))
+}
+
+async fn push(
+    State(host): State<Host>,
+    headers: HeaderMap,
+    Json(push): Json<Push>,
+) -> Result<Json<Cursor>, StatusCode> {
+    if !host.authorize(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    host.push(push)
+        .map(|cursor| Json(Cursor { cursor }))
+        .ok_or(StatusCode::CONFLICT)
+}
+
+async fn events_socket(
+    State(host): State<Host>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    if !host.authorize(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(upgrade.on_upgrade(move |socket| notify(socket, host)))
+}
+
+/// Tells a device of each change recorded, until it disconnects.
+async fn notify(mut socket: WebSocket, host: Host) {
+    let mut events = host.events.subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let cursor = match event {
+                    Ok(Event::Changed(cursor)) => cursor,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                let Ok(text) = serde_json::to_string(&Cursor { cursor }) else {
+                    continue;
+                };
+
+                if socket.send(ws::Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(ws::Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+}
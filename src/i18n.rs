@@ -2,20 +2,89 @@
 
 //! Provides localization support for this crate.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::LazyLock;
 
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
-    unic_langid::LanguageIdentifier,
-    DefaultLocalizer, LanguageLoader, Localizer,
+    unic_langid::{CharacterDirection, LanguageIdentifier},
+    DefaultLocalizer, DesktopLanguageRequester, LanguageLoader, Localizer,
 };
 use rust_embed::RustEmbed;
 
 /// Applies the requested language(s) to requested translations from the `fl!()` macro.
+/// Each is followed by the language it's a regional variant of, such as "pt" after
+/// "pt-BR", so that strings a regional translation leaves out are taken from the
+/// closest one before English.
 pub fn init(requested_languages: &[LanguageIdentifier]) {
-    if let Err(why) = localizer().select(requested_languages) {
-        eprintln!("error while loading fluent localizations: {why}");
+    if let Err(why) = localizer().select(&fallback_chain(requested_languages)) {
+        tracing::error!("error while loading fluent localizations: {why}");
     }
+
+    let direction = LANGUAGE_LOADER.current_language().character_direction();
+    RIGHT_TO_LEFT.store(direction == CharacterDirection::RTL, Ordering::Relaxed);
+}
+
+/// The language shown, such as "pt-BR".
+pub fn language() -> String {
+    LANGUAGE_LOADER.current_language().to_string()
+}
+
+/// Whether the language shown is written right to left, such as Arabic or Hebrew.
+pub fn is_rtl() -> bool {
+    RIGHT_TO_LEFT.load(Ordering::Relaxed)
+}
+
+/// Shows the interface in `language`, such as "de" or "pt-BR", or in the system's
+/// languages when it's empty, then in the `fallback` languages, separated by commas,
+/// for strings it leaves out. Strings are looked up anew by the next `fl!()`.
+pub fn select(language: &str, fallback: &str) {
+    let mut requested = match parse(language) {
+        Some(language) => vec![language],
+        None => DesktopLanguageRequester::requested_languages(),
+    };
+
+    requested.extend(fallback.split(',').filter_map(parse));
+    init(&requested);
+}
+
+fn parse(language: &str) -> Option<LanguageIdentifier> {
+    let language = language.trim();
+
+    if language.is_empty() {
+        return None;
+    }
+
+    language
+        .parse()
+        .inspect_err(|why| tracing::warn!("can't show the interface in {language}: {why}"))
+        .ok()
+}
+
+/// The languages to look strings up in, in order, each followed by the language it's a
+/// regional variant of.
+fn fallback_chain(languages: &[LanguageIdentifier]) -> Vec<LanguageIdentifier> {
+    let mut chain = Vec::with_capacity(languages.len() * 2);
+
+    for language in languages {
+        let parent = language.language.as_str().parse().ok();
+
+        for language in std::iter::once(language.clone()).chain(parent) {
+            if !chain.contains(&language) {
+                chain.push(language);
+            }
+        }
+    }
+
+    chain
+}
+
+/// Languages the interface is translated into.
+pub fn available() -> Vec<LanguageIdentifier> {
+    localizer().available_languages().unwrap_or_else(|why| {
+        tracing::error!("error while listing fluent localizations: {why}");
+        Vec::new()
+    })
 }
 
 // Get the `Localizer` to be used for localizing this library.
@@ -28,6 +97,15 @@ pub fn localizer() -> Box<dyn Localizer> {
 #[folder = "i18n/"]
 struct Localizations;
 
+static RIGHT_TO_LEFT: AtomicBool = AtomicBool::new(false);
+
+/// Whether strings are shown pseudo-translated, as set by `--pseudo-locale`.
+pub static PSEUDO: AtomicBool = AtomicBool::new(false);
+
+/// Letters replaced when pseudo-translating, and what they are replaced with.
+const PLAIN_LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const PSEUDO_LETTERS: &str = "ȧƀƈḓḗƒɠħīĵķŀḿƞǿƥɋřşŧŭṽẇẋẏẑȦƁƇḒḖƑƓĦĪĴĶĿḾȠǾƤɊŘŞŦŬṼẆẊẎẐ";
+
 pub static LANGUAGE_LOADER: LazyLock<FluentLanguageLoader> = LazyLock::new(|| {
     let loader: FluentLanguageLoader = fluent_language_loader!();
 
@@ -38,16 +116,122 @@ pub static LANGUAGE_LOADER: LazyLock<FluentLanguageLoader> = LazyLock::new(|| {
     loader
 });
 
+/// With `--pseudo-locale`, `text` accented, lengthened by about a third and put in
+/// brackets, so that strings cut off show a missing bracket and strings which aren't
+/// translated at all stand out by being plain.
+pub fn pseudo(text: String) -> String {
+    if !PSEUDO.load(Ordering::Relaxed) {
+        return text;
+    }
+
+    let mut letters = 0;
+    let mut pseudo = String::with_capacity(text.len() * 2);
+    pseudo.push('[');
+
+    for c in text.chars() {
+        match PLAIN_LETTERS
+            .find(c)
+            .and_then(|index| PSEUDO_LETTERS.chars().nth(index))
+        {
+            Some(accented) => {
+                letters += 1;
+                pseudo.push(accented);
+            }
+            None => pseudo.push(c),
+        }
+    }
+
+    pseudo.extend(std::iter::repeat_n('~', letters.div_ceil(3)));
+    pseudo.push(']');
+    pseudo
+}
 
 /// Request a localized string by ID from the i18n/ directory.
 #[macro_export]
 macro_rules! fl {
     ($message_id:literal) => {{
-        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id)
+        $crate::i18n::pseudo(i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id))
     }};
 
     ($message_id:literal, $($args:expr),*) => {{
-        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args), *)
+        $crate::i18n::pseudo(i18n_embed_fl::fl!(
+            $crate::i18n::LANGUAGE_LOADER,
+            $message_id,
+            $($args), *
+        ))
     }};
 }
 
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    /// Message IDs asked for by `fl!()` anywhere in the source.
+    fn used_ids(dir: &Path, ids: &mut BTreeSet<String>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                used_ids(&path, ids);
+                continue;
+            }
+
+            if path.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+
+            let source = std::fs::read_to_string(&path).unwrap();
+
+            let mut rest = source.as_str();
+
+            while let Some(start) = rest.find("fl!(") {
+                rest = rest[start + 4..].trim_start();
+
+                let id = rest
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.split('"').next())
+                    .filter(|id| id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+
+                if let Some(id) = id {
+                    ids.insert(id.to_string());
+                }
+            }
+        }
+    }
+
+    /// Message IDs defined in a Fluent file, leaving out terms and attributes.
+    fn defined_ids(ftl: &str) -> BTreeSet<String> {
+        ftl.lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once('='))
+            .map(|(id, _)| id.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_locale_has_every_message() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+        let mut used = BTreeSet::new();
+        used_ids(&root.join("src"), &mut used);
+        assert!(!used.is_empty(), "found no fl!() calls");
+
+        let mut missing = Vec::new();
+
+        for locale in std::fs::read_dir(root.join("i18n")).unwrap().flatten() {
+            let ftl = std::fs::read_to_string(locale.path().join("libby.ftl")).unwrap();
+            let defined = defined_ids(&ftl);
+
+            for id in used.difference(&defined) {
+                missing.push(format!("{}: {id}", locale.file_name().to_string_lossy()));
+            }
+        }
+
+        assert!(
+            missing.is_empty(),
+            "messages missing:\n{}",
+            missing.join("\n")
+        );
+    }
+}
@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The app's own icons: its icon in full color and in one color recolored by the theme,
+//! rendered at build time in the sizes panels and HiDPI displays ask for, and the
+//! placeholder shown for books without covers.
+
+use cosmic::widget::icon;
+
+// The app's icon as PNGs by size in pixels, smallest first, written by `build.rs`.
+include!(concat!(env!("OUT_DIR"), "/icons/icons.rs"));
+
+const APP: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
+
+const APP_SYMBOLIC: &[u8] =
+    include_bytes!("../resources/icons/hicolor/symbolic/apps/icon-symbolic.svg");
+
+const COVER: &[u8] = include_bytes!("../resources/icons/cover-symbolic.svg");
+
+/// The app's icon in full color.
+pub fn app() -> icon::Handle {
+    icon::from_svg_bytes(APP)
+}
+
+/// The app's icon in one color, drawn in the theme's text color, as the panel shows it.
+#[cfg(feature = "applet")]
+pub fn app_symbolic() -> icon::Handle {
+    icon::from_svg_bytes(APP_SYMBOLIC).symbolic(true)
+}
+
+/// Stands in for the cover of a book that has none, drawn in the theme's text color.
+pub fn cover() -> icon::Handle {
+    icon::from_svg_bytes(COVER).symbolic(true)
+}
+
+/// The app's icon as RGBA pixels at each size it was rendered at.
+pub fn app_pixels() -> Vec<(u32, Vec<u8>)> {
+    APP_PNGS
+        .iter()
+        .filter_map(|(size, png)| {
+            let image = image::load_from_memory(png).ok()?;
+            Some((*size, image.into_rgba8().into_raw()))
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Adds downloaded books to the library, checking that they are EPUBs, PDFs or comic
+//! archives and reading their title and author from the file.
+
+use crate::comic::Comic;
+use crate::epub::Epub;
+use crate::library::Book;
+use crate::worker::{self, Priority};
+use crate::{download, pdf};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Content types a server may send a book as. Generic ones are let through, as many
+/// servers send books that way, and the file is checked once downloaded.
+const ACCEPTED: [&str; 10] = [
+    "application/epub+zip",
+    "application/pdf",
+    "application/vnd.comicbook+zip",
+    "application/vnd.comicbook-rar",
+    "application/x-cbz",
+    "application/x-cbr",
+    "application/zip",
+    "application/octet-stream",
+    "application/x-download",
+    "binary/octet-stream",
+];
+
+/// Formats of books which can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Epub,
+    Pdf,
+    /// A comic archive of zipped page images.
+    Cbz,
+    /// A comic archive of page images in RAR.
+    Cbr,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Epub => "epub",
+            Format::Pdf => "pdf",
+            Format::Cbz => "cbz",
+            Format::Cbr => "cbr",
+        }
+    }
+
+    /// Recognizes a format from the first bytes of a file. EPUBs and comic archives are
+    /// both zips, so those named `.cbz` are taken for comics.
+    fn sniff(header: &[u8], path: &Path) -> Option<Self> {
+        let cbz = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("cbz"));
+
+        if header.starts_with(b"%PDF-") {
+            Some(Format::Pdf)
+        } else if header.starts_with(b"PK\x03\x04") && cbz {
+            Some(Format::Cbz)
+        } else if header.starts_with(b"PK\x03\x04") {
+            Some(Format::Epub)
+        } else if header.starts_with(b"Rar!") {
+            Some(Format::Cbr)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where imported books are kept.
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("books"))
+}
+
+/// Whether a download sent with the `Content-Type` header `content_type` may be a book.
+pub fn accepts(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    // A server which doesn't say is given the benefit of the doubt.
+    mime.is_empty() || ACCEPTED.contains(&mime.as_str())
+}
+
+/// Reads the downloaded book at `path` into a library entry, naming the file for its
+/// format. The file is deleted if it turns out not to be a book.
+pub async fn import(path: PathBuf) -> Result<Book, String> {
+    worker::run(Priority::Background, move || {
+        let result = read(&path);
+
+        if result.is_err() {
+            if let Err(why) = std::fs::remove_file(&path) {
+                tracing::error!("failed to delete {}: {why}", path.display());
+            }
+        }
+
+        result
+    })
+    .await?
+}
+
+/// Copies a book chosen from the file system into the library's folder and reads it,
+/// leaving the original where it was.
+pub async fn import_file(source: PathBuf) -> Result<Book, String> {
+    let dir = dir().ok_or("there is no folder to keep books in")?;
+
+    let path = tokio::task::spawn_blocking(move || {
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        std::fs::create_dir_all(&dir).map_err(|why| why.to_string())?;
+
+        let path = download::destination(&dir, &name);
+        std::fs::copy(&source, &path)
+            .map(|_| path)
+            .map_err(|why| format!("{}: {why}", source.display()))
+    })
+    .await
+    .map_err(|why| why.to_string())??;
+
+    import(path).await
+}
+
+fn read(path: &Path) -> Result<Book, String> {
+    let mut header = [0; 8];
+    let read = std::fs::File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .map_err(|why| why.to_string())?;
+
+    let format = Format::sniff(&header[..read], path)
+        .ok_or("the file is not an EPUB, PDF or comic archive")?;
+    let path = rename(path, format)?;
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let (title, author) = match format {
+        Format::Epub => {
+            let epub = Epub::open(&path).map_err(|why| why.to_string())?;
+            (epub.title, epub.author)
+        }
+        Format::Pdf => {
+            let document = pdf::open(&path).map_err(|why| why.to_string())?;
+            (document.title.unwrap_or_default(), String::new())
+        }
+        // Archives have no metadata to read, only pages.
+        Format::Cbz | Format::Cbr => {
+            Comic::open(&path).map_err(|why| why.to_string())?;
+            (String::new(), String::new())
+        }
+    };
+
+    Ok(Book {
+        title: if title.trim().is_empty() {
+            stem
+        } else {
+            title.trim().to_string()
+        },
+        author: author.trim().to_string(),
+        path: Some(path),
+        ..Book::default()
+    })
+}
+
+/// Gives a file the extension of its format, which a name taken from its address may
+/// lack.
+fn rename(path: &Path, format: Format) -> Result<PathBuf, String> {
+    let matches = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(format.extension()));
+
+    if matches {
+        return Ok(path.to_path_buf());
+    }
+
+    let name = path
+        .file_name()
+        .map(|name| format!("{}.{}", name.to_string_lossy(), format.extension()))
+        .unwrap_or_default();
+    let renamed = download::destination(path.parent().unwrap_or(Path::new("")), &name);
+
+    std::fs::rename(path, &renamed).map_err(|why| why.to_string())?;
+    Ok(renamed)
+}
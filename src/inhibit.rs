@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keeping the screen from blanking, through the XDG desktop portal, while the user
+//! reads without touching anything or listens to a book being read aloud.
+
+use ashpd::desktop::inhibit::{InhibitFlags, InhibitProxy};
+use ashpd::desktop::Request;
+use futures_util::Stream;
+use std::convert::Infallible;
+
+/// Ends the inhibition once the subscription holding it ends.
+struct Inhibited(Option<Request<()>>);
+
+impl Drop for Inhibited {
+    fn drop(&mut self) {
+        let (Some(request), Ok(runtime)) = (self.0.take(), tokio::runtime::Handle::try_current())
+        else {
+            return;
+        };
+
+        runtime.spawn(async move {
+            _ = request.close().await;
+        });
+    }
+}
+
+/// Keeps the screen on for as long as the stream is polled, giving `reason` to the
+/// desktop. The stream never yields.
+pub fn inhibit(reason: String) -> impl Stream<Item = Infallible> {
+    cosmic::iced::stream::channel(1, move |_output| async move {
+        let request = match InhibitProxy::new().await {
+            Ok(portal) => {
+                portal
+                    .inhibit(None, InhibitFlags::Idle.into(), &reason)
+                    .await
+            }
+            Err(why) => Err(why),
+        };
+
+        let _inhibited = match request {
+            Ok(request) => Inhibited(Some(request)),
+            Err(why) => {
+                tracing::error!("failed to keep the screen on: {why}");
+                return;
+            }
+        };
+
+        futures_util::future::pending::<()>().await;
+    })
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handing books opened from the file manager to the instance of libby that is already
+//! running, through its `org.freedesktop.Application` interface, instead of starting a
+//! second one.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use zbus::zvariant::Value;
+
+#[zbus::proxy(interface = "org.freedesktop.Application", gen_blocking = false)]
+trait Application {
+    fn open(&self, uris: &[&str], platform_data: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+/// Asks the running instance with the app ID to open the files, returning whether it
+/// took them; when none is running, this instance should open them itself.
+pub fn forward(app_id: &str, files: &[PathBuf]) -> bool {
+    // The app's own runtime hasn't started yet, and D-Bus needs one.
+    match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(open(app_id, files)).is_ok(),
+        Err(why) => {
+            tracing::error!("failed to reach a running instance: {why}");
+            false
+        }
+    }
+}
+
+async fn open(app_id: &str, files: &[PathBuf]) -> zbus::Result<()> {
+    let uris: Vec<String> = files
+        .iter()
+        .filter_map(|path| reqwest::Url::from_file_path(path).ok())
+        .map(String::from)
+        .collect();
+    let uris: Vec<&str> = uris.iter().map(String::as_str).collect();
+
+    // Lets the running instance raise its window above the file manager.
+    let token = std::env::var("XDG_ACTIVATION_TOKEN").ok();
+    let mut platform_data = HashMap::new();
+
+    if let Some(token) = &token {
+        platform_data.insert("activation-token", Value::from(token.as_str()));
+    }
+
+    let path = format!("/{}", app_id.replace('.', "/"));
+
+    let connection = zbus::Connection::session().await?;
+
+    // Only a running instance owns the name, so failing to reach it means there is none.
+    ApplicationProxy::builder(&connection)
+        .destination(app_id)?
+        .path(path)?
+        .build()
+        .await?
+        .open(&uris, platform_data)
+        .await
+}
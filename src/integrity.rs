@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hashes book files and checks them against the hashes recorded in the catalog.
+//!
+//! Files whose size and modification time haven't changed since they were last hashed
+//! are taken as intact, unless every file is asked to be hashed.
+
+use crate::import;
+use crate::library::{Book, BookId, FileStamp};
+use crate::worker::{self, Priority};
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// How many directory levels below the nearest surviving ancestor are searched
+/// for a moved file.
+const SEARCH_DEPTH: usize = 3;
+
+/// Outcome of verifying a single book's file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// The file no longer exists and could not be found nearby.
+    Missing,
+    /// The file exists but its contents no longer match the recorded hash.
+    Corrupted,
+    /// The file was found at a new location.
+    Moved(PathBuf),
+}
+
+/// Verification result for a book.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub id: BookId,
+    /// Hash of the file as it is now, if it could be read.
+    pub hash: Option<String>,
+    /// Size and modification time of the file as it is now, if it could be read.
+    pub stamp: Option<FileStamp>,
+    /// Whether the file was hashed, rather than taken as intact for being unchanged.
+    pub hashed: bool,
+    pub status: Status,
+}
+
+impl Report {
+    /// Records the hash and stamp of an intact file as the book's baseline.
+    pub fn apply(&self, book: &mut Book) {
+        if self.status != Status::Ok {
+            return;
+        }
+
+        if book.hash.is_none() {
+            book.hash.clone_from(&self.hash);
+        }
+
+        book.stamp = self.stamp;
+    }
+}
+
+/// Computes the SHA-256 hash of a file as a hex string, off the async runtime.
+pub async fn hash_file(path: PathBuf) -> io::Result<String> {
+    worker::run(Priority::Background, move || {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 64 * 1024];
+
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Verifies each book's file in turn, yielding one report per book. With `full`, files
+/// unchanged since they were last hashed are hashed again too.
+///
+/// Books without a file are skipped. Books without a recorded hash are reported
+/// as intact along with their current hash, which becomes the new baseline.
+pub fn verify(books: Vec<Book>, full: bool) -> impl Stream<Item = Report> {
+    futures_util::stream::iter(books.into_iter().filter(|book| book.path.is_some()))
+        .then(move |book| verify_book(book, full))
+}
+
+async fn verify_book(book: Book, full: bool) -> Report {
+    let path = book.path.clone().unwrap_or_default();
+
+    if !path.exists() {
+        let size = book.stamp.map(|stamp| stamp.size);
+        let found = find_moved(path, book.hash.clone(), size).await;
+
+        return Report {
+            id: book.id,
+            hash: None,
+            stamp: None,
+            hashed: false,
+            status: found.map_or(Status::Missing, Status::Moved),
+        };
+    }
+
+    // Taken before hashing, so a change made meanwhile is caught by the next check.
+    let stamp = FileStamp::of(&path);
+
+    if !full && book.hash.is_some() && stamp.is_some() && stamp == book.stamp {
+        return Report {
+            id: book.id,
+            hash: book.hash,
+            stamp,
+            hashed: false,
+            status: Status::Ok,
+        };
+    }
+
+    match hash_file(path).await {
+        Ok(hash) => Report {
+            id: book.id,
+            status: match &book.hash {
+                Some(expected) if *expected != hash => Status::Corrupted,
+                _ => Status::Ok,
+            },
+            hash: Some(hash),
+            stamp,
+            hashed: true,
+        },
+        Err(why) => {
+            tracing::error!("failed to hash book {}: {why}", book.id);
+
+            Report {
+                id: book.id,
+                hash: None,
+                stamp: None,
+                hashed: false,
+                status: Status::Corrupted,
+            }
+        }
+    }
+}
+
+/// Searches below the nearest existing ancestor of `path` for a file with the same
+/// name and the same hash, or the same size when no hash was recorded.
+///
+/// Nothing is searched when that ancestor is the home folder or outside it, as when a
+/// drive was unmounted, nor when neither the hash nor the size is known.
+async fn find_moved(path: PathBuf, hash: Option<String>, size: Option<u64>) -> Option<PathBuf> {
+    if hash.is_none() && size.is_none() {
+        return None;
+    }
+
+    let file_name = path.file_name()?.to_owned();
+    let root = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.is_dir())
+        .filter(|dir| searchable(dir))?
+        .to_path_buf();
+
+    let candidates = tokio::task::spawn_blocking(move || {
+        let mut candidates = Vec::new();
+        collect_named(&root, &file_name, SEARCH_DEPTH, &mut candidates);
+        candidates
+    })
+    .await
+    .ok()?;
+
+    for candidate in candidates {
+        let same_size = match size {
+            Some(size) => tokio::fs::metadata(&candidate)
+                .await
+                .is_ok_and(|metadata| metadata.len() == size),
+            None => true,
+        };
+
+        if !same_size {
+            continue;
+        }
+
+        match &hash {
+            None => return Some(candidate),
+            Some(expected) => {
+                if hash_file(candidate.clone()).await.ok().as_ref() == Some(expected) {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether a moved file may be searched for below `dir`: a folder inside the home
+/// folder, but not the home folder itself, or the folder books are imported into.
+fn searchable(dir: &Path) -> bool {
+    let in_home = dirs::home_dir().is_some_and(|home| dir.starts_with(&home) && dir != home);
+    let in_books = import::dir().is_some_and(|books| dir.starts_with(books));
+
+    in_home || in_books
+}
+
+fn collect_named(dir: &Path, name: &std::ffi::OsStr, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if depth > 0 {
+                collect_named(&path, name, depth - 1, out);
+            }
+        } else if path.file_name() == Some(name) {
+            out.push(path);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A Unix socket taking the commands of the D-Bus control interface as JSON, for
+//! scripts in containers and other places without a session bus.
+//!
+//! Each line sent is a request, answered with a line of its own:
+//!
+//! ```text
+//! {"command": "open_book", "path": "/home/me/Books/dune.epub"}
+//! {"command": "activate"}
+//! {"command": "search", "query": "herbert"}
+//! {"command": "get_reading_state"}
+//! ```
+//!
+//! Answers are `{"ok": true}`, with the `state` of the book being read for
+//! `get_reading_state`, or `{"ok": false, "error": "…"}`.
+
+use crate::control::{self, Command, Control};
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::Stream;
+use serde::Deserialize;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/// A request read from the socket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    OpenBook { path: String },
+    Activate,
+    Search { query: String },
+    GetReadingState,
+}
+
+/// Where the socket is made, in the user's runtime folder.
+pub fn path() -> Option<PathBuf> {
+    dirs::runtime_dir().map(|dir| dir.join("libby.sock"))
+}
+
+/// Removes the socket once the subscription listening on it ends.
+struct Bound(PathBuf);
+
+impl Drop for Bound {
+    fn drop(&mut self) {
+        _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Listens on the socket for as long as the stream is polled, yielding the commands
+/// sent to it.
+pub fn serve(state: Control) -> impl Stream<Item = Command> {
+    cosmic::iced::stream::channel(4, move |output| async move {
+        let Some(path) = path() else {
+            tracing::error!("failed to listen for commands: there is no runtime folder");
+            return;
+        };
+
+        // A socket left behind by a run that crashed would be in the way.
+        _ = std::fs::remove_file(&path);
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(why) => {
+                tracing::error!("failed to listen for commands on {}: {why}", path.display());
+                return;
+            }
+        };
+
+        let _bound = Bound(path.clone());
+
+        // Only the user may send commands.
+        if let Err(why) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+            tracing::error!("failed to restrict {}: {why}", path.display());
+            return;
+        }
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(answer(stream, state.clone(), output.clone()));
+                }
+                Err(why) => {
+                    tracing::error!("failed to accept a command connection: {why}");
+
+                    // Such as when out of file descriptors, which takes a while to pass.
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    })
+}
+
+/// Answers each request sent over a connection, until it's closed.
+async fn answer(stream: UnixStream, state: Control, commands: Sender<Command>) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut reply = respond(&line, &state, &commands).to_string();
+        reply.push('\n');
+
+        if write.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn respond(line: &str, state: &Control, commands: &Sender<Command>) -> serde_json::Value {
+    let request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(why) => return failure(why.to_string()),
+    };
+
+    let command = match request {
+        Request::OpenBook { path } => match control::book_file(&path) {
+            Ok(path) => Command::Open(path),
+            Err(why) => return failure(why),
+        },
+        Request::Activate => Command::Activate,
+        Request::Search { query } => Command::Search(query),
+        Request::GetReadingState => {
+            return serde_json::json!({ "ok": true, "state": state.get() });
+        }
+    };
+
+    match commands.clone().try_send(command) {
+        Ok(()) => serde_json::json!({ "ok": true }),
+        Err(why) => failure(why.to_string()),
+    }
+}
+
+fn failure(why: String) -> serde_json::Value {
+    serde_json::json!({ "ok": false, "error": why })
+}
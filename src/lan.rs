@@ -0,0 +1,723 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Shares the library with other instances of libby on the local network.
+//!
+//! Instances with sharing on announce themselves over mDNS and serve a small HTTP API:
+//!
+//! - `POST /v1/access` with `{"device": "…"}` asks to browse the library, answering
+//!   `{"token": "…"}` once the user accepts.
+//! - `GET /v1/books` lists the books whose files can be shared.
+//! - `GET /v1/books/{id}` sends a book's file.
+//! - `POST /v1/offers` with `{"device": "…", "port": 7317, "book": {…}, "token": "…"}`
+//!   offers to send a book, which the receiving instance fetches from the sender once
+//!   the user there accepts.
+//!
+//! Books are only listed and sent for a token, given as the password of basic
+//! authentication: one from `/v1/access`, or the one an offer carries, which fetches
+//! only the book offered. Tokens last until sharing is turned off.
+
+use crate::library::{Book, BookId};
+use crate::net::{self, Credentials};
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures_util::{SinkExt, Stream};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::IntoFuture;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Component, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot};
+use tokio_util::io::ReaderStream;
+
+/// The mDNS service instances announce themselves as.
+const SERVICE_TYPE: &str = "_libby._tcp.local.";
+
+/// Port the library is shared on.
+pub const PORT: u16 = 7317;
+
+/// Extensions of the files shared, the only ones taken from other instances.
+const EXTENSIONS: [&str; 4] = ["epub", "pdf", "cbz", "cbr"];
+
+/// User name tokens are sent under.
+const USER: &str = "libby";
+
+/// Random bytes in a token.
+const TOKEN_BYTES: usize = 32;
+
+/// How long an instance waits for the user to let it browse, short of the time
+/// instances give up waiting for an answer.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// How long an address the user didn't let browse can't ask again.
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// A book as listed to other instances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedBook {
+    pub id: BookId,
+    pub title: String,
+    pub author: String,
+    pub extension: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+impl SharedBook {
+    /// Name to save the file under, or `None` if the book isn't in a format libby reads,
+    /// or its title doesn't make the name of a file within the folder saved to.
+    pub fn file_name(&self) -> Option<String> {
+        let extension = self.extension.to_ascii_lowercase();
+
+        if !EXTENSIONS.contains(&extension.as_str()) {
+            return None;
+        }
+
+        let name = format!(
+            "{}.{extension}",
+            self.title.trim().replace(['/', '\\', '\0'], "-")
+        );
+
+        let mut components = std::path::Path::new(&name).components();
+
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Body of `POST /v1/access`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccessRequest {
+    device: String,
+}
+
+/// Answer to `POST /v1/access`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Granted {
+    token: String,
+}
+
+/// Body of `POST /v1/offers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OfferRequest {
+    device: String,
+    /// Port the sender shares its library on.
+    port: u16,
+    book: SharedBook,
+    /// Token fetching the book offered.
+    token: String,
+}
+
+/// A book another instance offers to send.
+#[derive(Debug, Clone)]
+pub struct Offer {
+    pub device: String,
+    /// Address of the sender's library.
+    pub url: String,
+    pub book: SharedBook,
+    /// Token fetching the book offered.
+    pub token: String,
+}
+
+/// An instance asking to browse the library, waiting for the user to let it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub id: u64,
+    pub device: String,
+    pub address: IpAddr,
+}
+
+/// Another instance found on the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peer {
+    /// Full mDNS name of the instance, which tells instances apart.
+    pub id: String,
+    pub device: String,
+    /// Address of the instance's library.
+    pub url: String,
+}
+
+/// Events of the shared library.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Offered(Offer),
+    /// An instance asked to browse the library, and is to be let or turned away.
+    Requested(Request),
+    /// The instance with this request ID stopped waiting to be let browse.
+    Withdrawn(u64),
+    Failed(String),
+}
+
+/// Changes to the instances found on the network.
+#[derive(Debug, Clone)]
+pub enum Discovery {
+    Found(Peer),
+    /// The instance with this ID left.
+    Lost(String),
+}
+
+/// Who may fetch what from the shared library.
+#[derive(Debug, Default)]
+struct Access {
+    /// Tokens of instances the user let browse the library.
+    browsing: HashSet<String>,
+    /// Tokens sent with offers, by the book each fetches.
+    offered: HashMap<String, BookId>,
+    /// The instance waiting for the user to let it browse.
+    pending: Option<Pending>,
+    /// Counts up with each instance asking, to tell their requests apart.
+    requests: u64,
+    /// Addresses the user didn't let browse, by when they may ask again.
+    turned_away: HashMap<IpAddr, Instant>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    id: u64,
+    address: IpAddr,
+    answer: oneshot::Sender<bool>,
+}
+
+/// The shared library's state, shared between the app and the server.
+#[derive(Debug, Clone)]
+pub struct Lan {
+    device: String,
+    books: Arc<Mutex<Vec<(SharedBook, PathBuf)>>>,
+    access: Arc<Mutex<Access>>,
+    events: broadcast::Sender<Event>,
+}
+
+impl Lan {
+    pub fn new(device: String, books: &[Book]) -> Self {
+        let lan = Self {
+            device,
+            books: Arc::default(),
+            access: Arc::default(),
+            events: broadcast::channel(16).0,
+        };
+
+        lan.set_books(books);
+        lan
+    }
+
+    /// Shares the books of the library which have files.
+    pub fn set_books(&self, books: &[Book]) {
+        let shared = books
+            .iter()
+            .filter(|book| !book.is_trashed())
+            .filter_map(|book| {
+                let path = book.path.clone()?;
+                let size = std::fs::metadata(&path).ok()?.len();
+
+                Some((
+                    SharedBook {
+                        id: book.id,
+                        title: book.title.clone(),
+                        author: book.author.clone(),
+                        extension: book.extension()?,
+                        size,
+                    },
+                    path,
+                ))
+            })
+            .collect();
+
+        *lock(&self.books) = shared;
+    }
+
+    fn listing(&self) -> Vec<SharedBook> {
+        lock(&self.books)
+            .iter()
+            .map(|(book, _)| book.clone())
+            .collect()
+    }
+
+    fn file(&self, id: BookId) -> Option<(SharedBook, PathBuf)> {
+        lock(&self.books)
+            .iter()
+            .find(|(book, _)| book.id == id)
+            .cloned()
+    }
+
+    /// The book with this ID, as listed to other instances.
+    pub fn book(&self, id: BookId) -> Option<SharedBook> {
+        self.file(id).map(|(book, _)| book)
+    }
+
+    /// A token for the instance a book is offered to, fetching only that book.
+    pub fn grant(&self, id: BookId) -> Option<String> {
+        let token = token()?;
+        lock(&self.access).offered.insert(token.clone(), id);
+        Some(token)
+    }
+
+    /// Answers the instance waiting to browse, if it is still the one `id` asked.
+    pub fn confirm(&self, id: u64, accepted: bool) {
+        let mut access = lock(&self.access);
+
+        if access
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.id == id)
+        {
+            if let Some(pending) = access.pending.take() {
+                if !accepted {
+                    access
+                        .turned_away
+                        .insert(pending.address, Instant::now() + COOLDOWN);
+                }
+
+                _ = pending.answer.send(accepted);
+            }
+        }
+    }
+
+    /// Takes the request of an instance at `address` to browse, returning its ID and
+    /// where the user's answer arrives.
+    fn request(&self, address: IpAddr) -> Result<(u64, oneshot::Receiver<bool>), StatusCode> {
+        let now = Instant::now();
+        let mut access = lock(&self.access);
+
+        access.turned_away.retain(|_, until| now < *until);
+
+        if access.turned_away.contains_key(&address) {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        // One instance is asked about at a time.
+        if access.pending.is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        access.requests += 1;
+
+        let id = access.requests;
+        let (answer, answered) = oneshot::channel();
+        access.pending = Some(Pending {
+            id,
+            address,
+            answer,
+        });
+
+        Ok((id, answered))
+    }
+
+    /// Lets an instance the user accepted browse, returning the token it is to use.
+    fn admit(&self) -> Option<String> {
+        let token = token()?;
+        lock(&self.access).browsing.insert(token.clone());
+        Some(token)
+    }
+
+    /// Whether a request's basic authentication holds a token letting it browse, or,
+    /// given `book`, one sent with the offer of that book.
+    fn authorize(&self, headers: &HeaderMap, book: Option<BookId>) -> bool {
+        let Some(token) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|value| BASE64_STANDARD.decode(value.trim()).ok())
+            .and_then(|value| String::from_utf8(value).ok())
+            .and_then(|value| Some(value.split_once(':')?.1.to_string()))
+        else {
+            return false;
+        };
+
+        let access = lock(&self.access);
+
+        access.browsing.contains(&token)
+            || book.is_some_and(|book| access.offered.get(&token) == Some(&book))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A new token, from the operating system's random source.
+fn token() -> Option<String> {
+    let mut bytes = [0; TOKEN_BYTES];
+
+    if let Err(why) = getrandom::fill(&mut bytes) {
+        tracing::error!("failed to make a token for sharing: {why}");
+        return None;
+    }
+
+    Some(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Basic authentication sending `token`, as shared libraries take it.
+pub fn credentials(token: &str) -> Credentials {
+    Credentials {
+        user: USER.to_string(),
+        password: token.to_string(),
+    }
+}
+
+/// Shuts the mDNS daemon down when dropped, withdrawing the announcement.
+struct Daemon(ServiceDaemon);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        _ = self.0.shutdown();
+    }
+}
+
+/// The mDNS instance name of a device, which may not hold dots.
+fn instance(device: &str) -> String {
+    device.replace('.', "-")
+}
+
+/// Shares the library on `port` and announces it until the stream is dropped,
+/// yielding the offers other instances make.
+pub fn serve(lan: Lan, port: u16) -> impl Stream<Item = Event> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let listener =
+            match tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+                .await
+            {
+                Ok(listener) => listener,
+                Err(why) => {
+                    _ = output.send(Event::Failed(why.to_string())).await;
+                    return;
+                }
+            };
+
+        let name = instance(&lan.device);
+        let properties = [("device", lan.device.as_str())];
+
+        let announced = ServiceDaemon::new().and_then(|daemon| {
+            let info = ServiceInfo::new(
+                SERVICE_TYPE,
+                &name,
+                &format!("{name}.local."),
+                "",
+                port,
+                &properties[..],
+            )?
+            .enable_addr_auto();
+
+            daemon.register(info)?;
+            Ok(Daemon(daemon))
+        });
+
+        let _daemon = match announced {
+            Ok(daemon) => daemon,
+            Err(why) => {
+                _ = output.send(Event::Failed(why.to_string())).await;
+                return;
+            }
+        };
+
+        let mut events = lan.events.subscribe();
+
+        let router = Router::new()
+            .route("/v1/access", post(access))
+            .route("/v1/books", get(books))
+            .route("/v1/books/{id}", get(file))
+            .route("/v1/offers", post(offered))
+            .with_state(lan);
+
+        let forward = async {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if output.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        };
+
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
+
+        tokio::select! {
+            result = server.into_future() => {
+                if let Err(why) = result {
+                    tracing::warn!("stopped sharing the library: {why}");
+                }
+            }
+            () = forward => (),
+        }
+    })
+}
+
+async fn access(
+    State(lan): State<Lan>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Json(request): Json<AccessRequest>,
+) -> Result<Json<Granted>, StatusCode> {
+    let (id, answered) = lan.request(address.ip())?;
+
+    _ = lan.events.send(Event::Requested(Request {
+        id,
+        device: request.device,
+        address: address.ip(),
+    }));
+
+    match tokio::time::timeout(CONFIRM_TIMEOUT, answered).await {
+        Ok(Ok(true)) => lan
+            .admit()
+            .map(|token| Json(Granted { token }))
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Ok(_) => Err(StatusCode::FORBIDDEN),
+        Err(_) => {
+            // Nobody answered in time, so the request can't be answered any more.
+            let mut access = lock(&lan.access);
+
+            if access
+                .pending
+                .as_ref()
+                .is_some_and(|pending| pending.id == id)
+            {
+                access.pending = None;
+            }
+
+            drop(access);
+            _ = lan.events.send(Event::Withdrawn(id));
+
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+async fn books(
+    State(lan): State<Lan>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SharedBook>>, StatusCode> {
+    if !lan.authorize(&headers, None) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(lan.listing()))
+}
+
+async fn file(
+    State(lan): State<Lan>,
+    Path(id): Path<BookId>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !lan.authorize(&headers, Some(id)) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let (book, path) = lan.file(id).ok_or(StatusCode::NOT_FOUND)?;
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Response::builder()
+        .header(header::CONTENT_LENGTH, book.size)
+        .header(header::CONTENT_TYPE, mime(&book.extension))
+        .body(Body::from_stream(ReaderStream::new(file)))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn offered(
+    State(lan): State<Lan>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Json(request): Json<OfferRequest>,
+) -> StatusCode {
+    let offer = Offer {
+        device: request.device,
+        url: url(address.ip(), request.port),
+        book: request.book,
+        token: request.token,
+    };
+
+    match lan.events.send(Event::Offered(offer)) {
+        Ok(_) => StatusCode::ACCEPTED,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+fn mime(extension: &str) -> &'static str {
+    match extension {
+        "epub" => "application/epub+zip",
+        "pdf" => "application/pdf",
+        "cbz" => "application/vnd.comicbook+zip",
+        "cbr" => "application/vnd.comicbook-rar",
+        _ => "application/octet-stream",
+    }
+}
+
+fn url(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("http://{ip}:{port}"),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => format!("http://{ip}:{port}"),
+            None => format!("http://[{ip}]:{port}"),
+        },
+    }
+}
+
+/// Looks for other instances sharing their library until the stream is dropped,
+/// leaving out the one announced as `device`.
+pub fn discover(device: String) -> impl Stream<Item = Discovery> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let browsing = ServiceDaemon::new().and_then(|daemon| {
+            let events = daemon.browse(SERVICE_TYPE)?;
+            Ok((Daemon(daemon), events))
+        });
+
+        let (_daemon, events) = match browsing {
+            Ok(browsing) => browsing,
+            Err(why) => {
+                tracing::error!("failed to look for libraries on the network: {why}");
+                return;
+            }
+        };
+
+        let own = format!("{}.{SERVICE_TYPE}", instance(&device));
+
+        while let Ok(event) = events.recv_async().await {
+            let discovery = match event {
+                ServiceEvent::ServiceResolved(info) if info.get_fullname() != own => {
+                    // Prefer IPv4, which needs no scope to reach a link-local address.
+                    let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+                    addresses.sort_by_key(IpAddr::is_ipv6);
+
+                    let Some(ip) = addresses.first() else {
+                        continue;
+                    };
+
+                    Discovery::Found(Peer {
+                        id: info.get_fullname().to_string(),
+                        device: info
+                            .get_property_val_str("device")
+                            .unwrap_or(info.get_fullname())
+                            .to_string(),
+                        url: url(*ip, info.get_port()),
+                    })
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => Discovery::Lost(fullname),
+                _ => continue,
+            };
+
+            if output.send(discovery).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Lists the books another instance shares, with `token` if it still lets this one
+/// browse, or else asking it for a new one, which the user there has to accept first.
+/// Returns the token used along with the books.
+pub async fn browse(
+    url: String,
+    device: String,
+    token: Option<String>,
+) -> Result<(String, Vec<SharedBook>), String> {
+    if let Some(token) = token {
+        if let Some(books) = list(&url, &token).await? {
+            return Ok((token, books));
+        }
+    }
+
+    let token = request_access(&url, device).await?;
+    let books = list(&url, &token)
+        .await?
+        .ok_or("the other device didn't take the access it gave")?;
+
+    Ok((token, books))
+}
+
+/// Asks another instance to let this one browse, returning the token to browse with.
+async fn request_access(url: &str, device: String) -> Result<String, String> {
+    let request = net::client()
+        .post(format!("{url}/v1/access"))
+        .json(&AccessRequest { device });
+
+    let response = net::send(request).await.map_err(|why| why.to_string())?;
+
+    match response.status() {
+        StatusCode::FORBIDDEN => {
+            return Err("the other device didn't let you see its books".to_string())
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            return Err("the other device turned you away, try again later".to_string())
+        }
+        StatusCode::CONFLICT => {
+            return Err("another device is asking, try again in a moment".to_string())
+        }
+        _ => (),
+    }
+
+    let granted: Granted = response
+        .error_for_status()
+        .map_err(|why| why.to_string())?
+        .json()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    Ok(granted.token)
+}
+
+/// The books another instance shares, or `None` if it doesn't take the token.
+async fn list(url: &str, token: &str) -> Result<Option<Vec<SharedBook>>, String> {
+    let request = net::client().get(format!("{url}/v1/books"));
+    let response = net::send(net::authenticate(request, Some(&credentials(token))))
+        .await
+        .map_err(|why| why.to_string())?;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+
+    response
+        .error_for_status()
+        .map_err(|why| why.to_string())?
+        .json()
+        .await
+        .map(Some)
+        .map_err(|why| why.to_string())
+}
+
+/// Address a book's file is fetched from.
+pub fn file_url(url: &str, id: BookId) -> String {
+    format!("{url}/v1/books/{id}")
+}
+
+/// Offers to send a book to another instance, for the user there to accept, giving it
+/// `token` to fetch the book with.
+pub async fn offer(
+    url: String,
+    device: String,
+    book: SharedBook,
+    token: String,
+) -> Result<(), String> {
+    let request = OfferRequest {
+        device,
+        port: PORT,
+        book,
+        token,
+    };
+
+    net::send(
+        net::client()
+            .post(format!("{url}/v1/offers"))
+            .json(&request),
+    )
+    .await
+    .and_then(reqwest::Response::error_for_status)
+    .map(|_| ())
+    .map_err(|why| why.to_string())
+}
@@ -0,0 +1,764 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The book catalog shown on the library page and persisted between runs.
+
+use crate::collation::Collation;
+use crate::typography::Typography;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Identifies a book within the catalog.
+pub type BookId = u64;
+
+/// Progress from which a book counts as read to the end.
+const FINISHED: f32 = 0.99;
+
+/// Time from which a query counts as slow, to be logged when profiling queries.
+const SLOW_QUERY: Duration = Duration::from_millis(5);
+
+/// Whether slow queries are logged along with how they were answered, as asked for with
+/// `--profile-queries`.
+pub static PROFILE_QUERIES: AtomicBool = AtomicBool::new(false);
+
+/// Seconds since the Unix epoch, as used by catalog timestamps.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Today in local time, counted in days from the Common Era.
+fn today() -> i32 {
+    use chrono::Datelike;
+
+    chrono::Local::now().date_naive().num_days_from_ce()
+}
+
+/// A single catalog entry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Book {
+    pub id: BookId,
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub series: Option<String>,
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// Location of the book's file on disk, if it was imported from one.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// SHA-256 hash of the file's contents, recorded when it was first verified.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// The file's size and modification time when its hash was last checked.
+    #[serde(default)]
+    pub stamp: Option<FileStamp>,
+    /// Fraction of the book which has been read, from 0.0 to 1.0.
+    #[serde(default)]
+    pub progress: f32,
+    /// Where reading left off.
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// Seconds since the Unix epoch at which the location was last recorded.
+    #[serde(default)]
+    pub location_updated: u64,
+    /// Rating out of five stars, if the book has been rated.
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// Set while the book sits in the trash.
+    #[serde(default)]
+    pub trashed: Option<Trashed>,
+    /// The reader's own notes, oldest first.
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// Saved positions, in reading order.
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// Highlighted passages, in reading order.
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+    /// Typography used for this book instead of the global settings.
+    #[serde(default)]
+    pub typography: Option<Typography>,
+    /// Whether the book's pages run from right to left, as in manga.
+    #[serde(default)]
+    pub right_to_left: bool,
+    /// How the book's chapters are laid out in the reader.
+    #[serde(default)]
+    pub flow: Flow,
+}
+
+/// A position within a book's reading order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    /// Index of the chapter in the spine.
+    pub chapter: usize,
+    /// Relative offset within the chapter, from 0.0 to 1.0.
+    pub offset: f32,
+}
+
+/// A timestamped note attached to a book.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    /// Seconds since the Unix epoch at which the note was written.
+    pub created: u64,
+    pub text: String,
+}
+
+/// A saved position within a book, with an optional label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Seconds since the Unix epoch at which the bookmark was added.
+    pub created: u64,
+    pub location: Location,
+    #[serde(default)]
+    pub label: String,
+}
+
+/// A highlighted passage of a book, with an optional note.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    /// Seconds since the Unix epoch at which the passage was highlighted.
+    pub created: u64,
+    /// Start of the passage, used to jump back to it.
+    pub location: Location,
+    /// First and last block of the chapter covered by the highlight.
+    pub blocks: (usize, usize),
+    pub color: HighlightColor,
+    /// The highlighted text.
+    pub text: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+impl Highlight {
+    /// Whether the highlight covers a block of the given chapter.
+    pub fn covers(&self, chapter: usize, block: usize) -> bool {
+        self.location.chapter == chapter && (self.blocks.0..=self.blocks.1).contains(&block)
+    }
+}
+
+/// How a reflowable book moves through its text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flow {
+    /// Free vertical scrolling, running on from one chapter into the next.
+    #[default]
+    Continuous,
+    /// Whole screens at a time, turned with the keys, the wheel, or the pager.
+    Paginated,
+}
+
+/// Color of a highlight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightColor {
+    #[default]
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+}
+
+impl HighlightColor {
+    pub const ALL: [HighlightColor; 4] = [
+        HighlightColor::Yellow,
+        HighlightColor::Green,
+        HighlightColor::Blue,
+        HighlightColor::Pink,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HighlightColor::Yellow => "yellow",
+            HighlightColor::Green => "green",
+            HighlightColor::Blue => "blue",
+            HighlightColor::Pink => "pink",
+        }
+    }
+}
+
+/// Records when and from where a book was moved into the trash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trashed {
+    /// Seconds since the Unix epoch at which the book was deleted.
+    pub at: u64,
+    /// Where the file lived before it was moved into the trash folder.
+    pub original_path: Option<PathBuf>,
+}
+
+/// The size and modification time of a file, by which a file unchanged since it was
+/// hashed needn't be hashed again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Nanoseconds since the Unix epoch at which the file was last modified.
+    pub modified: u64,
+}
+
+impl FileStamp {
+    /// The stamp of the file at `path`, unless it can't be read.
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = path.metadata().ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(FileStamp {
+            size: metadata.len(),
+            modified: u64::try_from(modified.as_nanos()).unwrap_or(u64::MAX),
+        })
+    }
+}
+
+impl Book {
+    pub fn is_trashed(&self) -> bool {
+        self.trashed.is_some()
+    }
+
+    /// Whether the book has been read to the end.
+    pub fn is_finished(&self) -> bool {
+        self.progress >= FINISHED
+    }
+
+    /// Adds a bookmark, keeping the list in reading order.
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) {
+        let key = |location: &Location| (location.chapter, location.offset);
+        let index = self
+            .bookmarks
+            .partition_point(|existing| key(&existing.location) <= key(&bookmark.location));
+
+        self.bookmarks.insert(index, bookmark);
+    }
+
+    /// Adds a highlight, keeping the list in reading order.
+    pub fn add_highlight(&mut self, highlight: Highlight) {
+        let key = |highlight: &Highlight| (highlight.location.chapter, highlight.blocks.0);
+        let index = self
+            .highlights
+            .partition_point(|existing| key(existing) <= key(&highlight));
+
+        self.highlights.insert(index, highlight);
+    }
+
+    /// Lowercase extension of the book's file, such as `epub` or `pdf`.
+    pub fn extension(&self) -> Option<String> {
+        let extension = self.path.as_ref()?.extension()?.to_str()?;
+        Some(extension.to_lowercase())
+    }
+
+    /// The lowercase title, author, description, tags and notes searched through, kept
+    /// apart so that a search can't match across two of them.
+    fn search_text(&self) -> String {
+        let mut text = [&self.title, &self.author, &self.description]
+            .map(String::as_str)
+            .join("\0");
+
+        for field in self
+            .tags
+            .iter()
+            .chain(self.notes.iter().map(|note| &note.text))
+        {
+            text.push('\0');
+            text.push_str(field);
+        }
+
+        text.to_lowercase()
+    }
+}
+
+/// The lock the app holds for as long as it runs.
+static HELD: OnceLock<Lock> = OnceLock::new();
+
+/// A hold on the catalog, so that the app, the daemon and the headless commands never
+/// change it at once, released when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    _file: File,
+}
+
+impl Lock {
+    fn file() -> std::io::Result<File> {
+        let path = dirs::data_dir()
+            .map(|dir| dir.join("libby").join("library.lock"))
+            .ok_or(std::io::ErrorKind::NotFound)?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+    }
+
+    /// Holds the catalog, unless another process does.
+    pub fn try_acquire() -> std::io::Result<Option<Self>> {
+        let file = Self::file()?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(why)) => Err(why),
+        }
+    }
+
+    /// Holds the catalog for the rest of the run, waiting for another process to let
+    /// go of it first.
+    pub fn hold() -> std::io::Result<()> {
+        if HELD.get().is_none() {
+            let file = Self::file()?;
+            file.lock()?;
+            _ = HELD.set(Self { _file: file });
+        }
+
+        Ok(())
+    }
+}
+
+/// All books known to the application.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Library {
+    next_id: BookId,
+    books: Vec<Book>,
+    /// Days on which a book was read, in local time, counted from the Common Era.
+    #[serde(default)]
+    days_read: BTreeSet<i32>,
+    /// Lookups answering queries, built when first needed after the books change.
+    #[serde(skip)]
+    index: OnceLock<Index>,
+}
+
+impl Library {
+    /// Location of the catalog file under the user's data directory.
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("libby").join("library.json"))
+    }
+
+    /// Loads the catalog from disk, or an empty one when none exists yet.
+    ///
+    /// A catalog which can't be read is an error rather than an empty catalog, so that
+    /// saving doesn't replace it. One which can't be parsed, being damaged or written by
+    /// a newer version, is moved aside beside it first, for the user to recover.
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(why) => return Err(format!("failed to read {}: {why}", path.display())),
+        };
+
+        serde_json::from_slice(&bytes).map_err(|why| {
+            let broken = path.with_file_name(format!("library.json.broken-{}", now()));
+
+            match std::fs::rename(&path, &broken) {
+                Ok(()) => format!(
+                    "failed to parse {}, kept as {}: {why}",
+                    path.display(),
+                    broken.display()
+                ),
+                Err(moving) => format!(
+                    "failed to parse {}: {why}, nor to move it aside: {moving}",
+                    path.display()
+                ),
+            }
+        })
+    }
+
+    /// Loads the catalog again after loading it failed, which takes it being missing as
+    /// an error too, as one which couldn't be parsed was moved aside rather than being
+    /// for an empty catalog to replace.
+    pub fn reload() -> Result<Self, String> {
+        let path = Self::path().ok_or("there is no folder to keep the library in")?;
+
+        if !path.exists() {
+            return Err(format!("{} is missing", path.display()));
+        }
+
+        Self::load()
+    }
+
+    /// Writes the catalog to disk, beside the file and then renamed over it, so that
+    /// stopping partway never leaves half a catalog.
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or(std::io::ErrorKind::NotFound)?;
+        let parent = path.parent().ok_or(std::io::ErrorKind::NotFound)?;
+        std::fs::create_dir_all(parent)?;
+
+        let bytes = serde_json::to_vec_pretty(self)?;
+
+        // Named for the process, so the app and the daemon never share one.
+        let partial = parent.join(format!(".library.json.{}.partial", std::process::id()));
+        let mut file = std::fs::File::create(&partial)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(&partial, &path)?;
+
+        // Makes the rename itself last through a crash.
+        std::fs::File::open(parent)?.sync_all()
+    }
+
+    pub fn books(&self) -> &[Book] {
+        &self.books
+    }
+
+    pub fn books_mut(&mut self) -> &mut [Book] {
+        self.index.take();
+        &mut self.books
+    }
+
+    pub fn get(&self, id: BookId) -> Option<&Book> {
+        self.books.iter().find(|book| book.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: BookId) -> Option<&mut Book> {
+        self.index.take();
+        self.books.iter_mut().find(|book| book.id == id)
+    }
+
+    /// Adds a book to the catalog, assigning it a fresh ID.
+    pub fn insert(&mut self, mut book: Book) -> BookId {
+        let id = self.next_id;
+        self.next_id += 1;
+        book.id = id;
+        self.books.push(book);
+        self.index.take();
+        id
+    }
+
+    /// Replaces the catalog entry sharing the book's ID.
+    pub fn replace(&mut self, book: Book) {
+        if let Some(existing) = self.books.iter_mut().find(|b| b.id == book.id) {
+            *existing = book;
+            self.index.take();
+        }
+    }
+
+    pub fn remove(&mut self, id: BookId) -> Option<Book> {
+        let position = self.books.iter().position(|book| book.id == id)?;
+        self.index.take();
+        Some(self.books.remove(position))
+    }
+
+    /// The book whose reading position moved most recently, if any has been read.
+    pub fn last_read(&self) -> Option<&Book> {
+        self.read().max_by_key(|book| book.location_updated)
+    }
+
+    /// Up to `count` books that have been read, the one read most recently first.
+    pub fn recently_read(&self, count: usize) -> Vec<&Book> {
+        let mut books: Vec<&Book> = self.read().collect();
+        books.sort_by_key(|book| std::cmp::Reverse(book.location_updated));
+        books.truncate(count);
+        books
+    }
+
+    /// Books with files whose reading position has been recorded.
+    fn read(&self) -> impl Iterator<Item = &Book> {
+        self.books
+            .iter()
+            .filter(|book| book.location_updated > 0 && book.path.is_some() && !book.is_trashed())
+    }
+
+    /// Notes that a book was read today, counting towards the reading streak.
+    pub fn note_read_today(&mut self) {
+        self.days_read.insert(today());
+    }
+
+    /// Days in a row on which a book was read, up to today, or up to yesterday while
+    /// nothing has been read yet today.
+    pub fn streak(&self) -> u32 {
+        let today = today();
+
+        let mut day = if self.days_read.contains(&today) {
+            today
+        } else {
+            today - 1
+        };
+
+        let mut streak = 0;
+
+        while self.days_read.contains(&day) {
+            streak += 1;
+            day -= 1;
+        }
+
+        streak
+    }
+
+    /// Forgets the order of titles and authors, to sort them again after the language
+    /// they are sorted for changes.
+    pub fn recollate(&mut self) {
+        self.index.take();
+    }
+
+    /// Books currently in the trash.
+    pub fn trashed(&self) -> impl Iterator<Item = &Book> {
+        self.books.iter().filter(|book| book.is_trashed())
+    }
+
+    /// Removes and returns trashed books which were deleted before the cutoff.
+    pub fn take_expired(&mut self, cutoff: u64) -> Vec<Book> {
+        let (expired, kept) = std::mem::take(&mut self.books)
+            .into_iter()
+            .partition(|book| book.trashed.as_ref().is_some_and(|t| t.at < cutoff));
+
+        self.books = kept;
+        self.index.take();
+        expired
+    }
+
+    /// The books outside the trash matching the query, in the order it asks for.
+    pub fn query(&self, query: &Query) -> Vec<&Book> {
+        let started = Instant::now();
+        let built = self.index.get().is_none();
+        let index = self.index.get_or_init(|| Index::new(&self.books));
+
+        // Start from the fewest books an index can narrow the query down to.
+        let mut plan = String::from("scanning every book");
+        let mut candidates: Option<Vec<usize>> = None;
+
+        for tag in &query.tags {
+            let tagged = index
+                .tags
+                .get(&tag.to_lowercase())
+                .map_or(&[][..], Vec::as_slice);
+
+            if candidates
+                .as_ref()
+                .is_none_or(|shortest| tagged.len() < shortest.len())
+            {
+                plan = format!("using the tag index for {tag:?}");
+                candidates = Some(tagged.to_vec());
+            }
+        }
+
+        if let (None, Some(progress)) = (&candidates, query.progress) {
+            plan = format!("using the progress index for {progress:?}");
+            candidates =
+                Some(index.by_progress[index.progress_range(&self.books, progress)].to_vec());
+        }
+
+        let mut candidates = candidates.unwrap_or_else(|| (0..self.books.len()).collect());
+        let considered = candidates.len();
+
+        let author = query.author.as_deref().map(str::to_lowercase);
+        let text = query
+            .text
+            .as_deref()
+            .map(str::to_lowercase)
+            .filter(|text| !text.is_empty());
+
+        let same = |wanted: &Option<String>, value: &Option<String>| match wanted {
+            Some(wanted) => value
+                .as_ref()
+                .is_some_and(|value| value.eq_ignore_ascii_case(wanted)),
+            None => true,
+        };
+
+        candidates.retain(|&position| {
+            let book = &self.books[position];
+
+            !book.is_trashed()
+                && query
+                    .tags
+                    .iter()
+                    .all(|tag| book.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)))
+                && author
+                    .as_ref()
+                    .is_none_or(|author| book.author.to_lowercase().contains(author))
+                && same(&query.series, &book.series)
+                && same(&query.collection, &book.collection)
+                && query.progress.is_none_or(|progress| progress.matches(book))
+                && text
+                    .as_ref()
+                    .is_none_or(|text| index.text[position].contains(text))
+        });
+
+        match query.sort {
+            Sort::Added => candidates.sort_unstable(),
+            Sort::Title => candidates.sort_unstable_by_key(|&position| index.title_rank[position]),
+            Sort::Author => {
+                candidates.sort_unstable_by_key(|&position| index.author_rank[position])
+            }
+            Sort::Progress => {
+                candidates.sort_unstable_by_key(|&position| index.progress_rank[position])
+            }
+        }
+
+        let elapsed = started.elapsed();
+
+        if PROFILE_QUERIES.load(Ordering::Relaxed) && elapsed >= SLOW_QUERY {
+            tracing::info!(
+                "slow query took {elapsed:?}: {query:?}\n  plan: {}{plan}, considered {considered} \
+                 of {} books, matched {}, sorted by {:?}",
+                if built { "building the index, then " } else { "" },
+                self.books.len(),
+                candidates.len(),
+                query.sort
+            );
+        }
+
+        candidates
+            .into_iter()
+            .map(|position| &self.books[position])
+            .collect()
+    }
+}
+
+/// Which books to list, and in what order; each condition given has to hold.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Tags the books have, each of them.
+    pub tags: Vec<String>,
+    /// Part of the name of the books' author.
+    pub author: Option<String>,
+    pub series: Option<String>,
+    pub collection: Option<String>,
+    /// Text found in the books' title, author, description, tags or notes.
+    pub text: Option<String>,
+    pub progress: Option<Progress>,
+    pub sort: Sort,
+}
+
+/// How far into a book reading has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Unread,
+    /// Started but not finished.
+    Reading,
+    Finished,
+}
+
+impl Progress {
+    fn matches(self, book: &Book) -> bool {
+        match self {
+            Progress::Unread => book.progress <= 0.0,
+            Progress::Reading => book.progress > 0.0 && !book.is_finished(),
+            Progress::Finished => book.is_finished(),
+        }
+    }
+}
+
+/// Order books are listed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Sort {
+    /// As they were added to the library.
+    #[default]
+    Added,
+    /// By title, in the order of the interface's language.
+    Title,
+    /// By author, then title, likewise.
+    Author,
+    /// Least read first.
+    Progress,
+}
+
+/// Lookups into the catalog for common queries, by position in its list of books.
+#[derive(Debug, Clone, Default)]
+struct Index {
+    /// Books with each tag, by the tag in lowercase.
+    tags: HashMap<String, Vec<usize>>,
+    /// Books from the least read to the most.
+    by_progress: Vec<usize>,
+    /// Where each book comes when sorted by title, author or progress.
+    title_rank: Vec<usize>,
+    author_rank: Vec<usize>,
+    progress_rank: Vec<usize>,
+    /// The text searched through for each book.
+    text: Vec<String>,
+}
+
+impl Index {
+    fn new(books: &[Book]) -> Self {
+        let mut tags: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for (position, book) in books.iter().enumerate() {
+            for tag in &book.tags {
+                let tagged = tags.entry(tag.to_lowercase()).or_default();
+
+                if tagged.last() != Some(&position) {
+                    tagged.push(position);
+                }
+            }
+        }
+
+        let positions = || (0..books.len()).collect::<Vec<_>>();
+
+        let collation = Collation::new();
+
+        let mut by_title = positions();
+        by_title.sort_by(|&a, &b| collation.compare(&books[a].title, &books[b].title));
+
+        let mut by_author = positions();
+        by_author.sort_by(|&a, &b| {
+            let (a, b) = (&books[a], &books[b]);
+            collation
+                .compare(&a.author, &b.author)
+                .then_with(|| collation.compare(&a.title, &b.title))
+        });
+
+        let mut by_progress = positions();
+        by_progress.sort_by(|&a, &b| progress_key(&books[a]).total_cmp(&progress_key(&books[b])));
+
+        Index {
+            tags,
+            title_rank: ranks(&by_title),
+            author_rank: ranks(&by_author),
+            progress_rank: ranks(&by_progress),
+            by_progress,
+            text: books.iter().map(Book::search_text).collect(),
+        }
+    }
+
+    /// The part of [`Index::by_progress`] holding the books read as far as `progress`.
+    fn progress_range(&self, books: &[Book], progress: Progress) -> std::ops::Range<usize> {
+        let started = self
+            .by_progress
+            .partition_point(|&position| progress_key(&books[position]) <= 0.0);
+        let finished = self
+            .by_progress
+            .partition_point(|&position| progress_key(&books[position]) < FINISHED);
+
+        match progress {
+            Progress::Unread => 0..started,
+            Progress::Reading => started..finished,
+            Progress::Finished => finished..self.by_progress.len(),
+        }
+    }
+}
+
+/// A book's progress as sorted by, with nonsense last.
+fn progress_key(book: &Book) -> f32 {
+    if book.progress.is_nan() {
+        f32::INFINITY
+    } else {
+        book.progress
+    }
+}
+
+/// Where each position comes in `order`.
+fn ranks(order: &[usize]) -> Vec<usize> {
+    let mut ranks = vec![0; order.len()];
+
+    for (rank, &position) in order.iter().enumerate() {
+        ranks[position] = rank;
+    }
+
+    ranks
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Logs what goes wrong, and with `--verbose` what goes on, to standard error and to a
+//! log file rotated daily under the user's state folder.
+//!
+//! `RUST_LOG` takes the place of `--verbose` when set, such as
+//! `RUST_LOG=libby::sync=trace` to follow syncing alone.
+
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// How many days of log files are kept.
+const KEPT_FILES: usize = 7;
+
+/// Folder the log files are written to.
+pub fn dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("libby").join("logs"))
+}
+
+/// Starts logging, more of it the more `verbose` is. The returned guard writes out what
+/// is still queued for the log file when dropped, so it has to be kept until exiting.
+pub fn init(verbose: u8) -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(match verbose {
+            0 => "warn,libby=info",
+            1 => "info,libby=debug",
+            _ => "debug,libby=trace",
+        })
+    });
+
+    let appender = dir().map(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("libby")
+            .filename_suffix("log")
+            .max_log_files(KEPT_FILES)
+            .build(dir)
+    });
+
+    let (file, guard) = match appender {
+        Some(Ok(appender)) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer().with_ansi(false).with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        Some(Err(why)) => {
+            eprintln!("failed to open the log file: {why}");
+            (None, None)
+        }
+        None => (None, None),
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file);
+
+    // Spans go to Tracy as well, when built for profiling.
+    #[cfg(feature = "profiling")]
+    let registry = registry.with(tracing_tracy::TracyLayer::default());
+
+    let logged = registry.try_init();
+
+    if let Err(why) = logged {
+        eprintln!("failed to start logging: {why}");
+    }
+
+    guard
+}
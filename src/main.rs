@@ -1,23 +1,166 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod a11y;
+#[cfg(feature = "count-allocations")]
+mod alloc_count;
+mod annotations;
 mod app;
+#[cfg(feature = "applet")]
+mod applet;
+mod atproto;
+mod bidi;
+mod bulk;
+mod cache;
+mod catalog;
+mod citation;
+mod cli;
+mod clipboard;
+mod collation;
+mod comic;
+mod companion;
 mod config;
+mod control;
+mod crash;
+mod daemon;
+mod dictionary;
+mod discover;
+mod download;
+mod drag;
+mod epub;
+mod export;
+mod file_dialog;
+mod follow;
+mod global_shortcut;
+mod headless;
+mod host;
 mod i18n;
+mod icons;
+mod import;
+mod inhibit;
+mod instance;
+mod integrity;
+mod ipc;
+mod lan;
+mod library;
+mod logging;
+mod mapped;
+mod math;
+mod mpris;
+mod net;
+mod notify;
+mod opds;
+mod palette;
+mod pdf;
+mod power;
+mod print;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod quicklist;
+mod reader;
+mod roaming;
+mod rss;
+mod rsvp;
+mod search;
+mod search_provider;
+mod secrets;
+mod session;
+mod share;
+mod shortcuts;
+mod sound;
+mod sources;
+mod speech;
+mod sync;
+mod thumbnail;
+mod toc;
+mod translate;
+mod trash;
+mod tray;
+mod typography;
+mod update;
+mod webdav;
+mod webhook;
+mod wishlist;
+mod worker;
 
 fn main() -> cosmic::iced::Result {
+    let started = std::time::Instant::now();
+    let args = match <cli::Args as clap::Parser>::try_parse() {
+        Ok(args) => args,
+        // Help and the version are printed the same way, but aren't errors.
+        Err(why) => {
+            _ = why.print();
+            std::process::exit(if why.use_stderr() { cli::USAGE } else { 0 });
+        }
+    };
+
+    // Kept until exiting, to write out what is still queued for the log file.
+    let log_guard = logging::init(args.verbose);
+
+    library::PROFILE_QUERIES.store(args.profile_queries, std::sync::atomic::Ordering::Relaxed);
+
     // Get the system's preferred languages.
     let requested_languages = i18n_embed::DesktopLanguageRequester::requested_languages();
 
     // Enable localizations to be applied.
     i18n::init(&requested_languages);
+    i18n::PSEUDO.store(args.pseudo_locale, std::sync::atomic::Ordering::Relaxed);
+
+    // Write a report of any crash, to offer sending on the next launch.
+    crash::install();
+
+    // Print completions or the man page, or work on the library headless, when asked.
+    if let Some(command) = args.command {
+        let code = command.run(args.json).code();
+        drop(log_guard);
+        std::process::exit(code);
+    }
+
+    if args.headless {
+        eprintln!("--headless needs a command to run, such as `import` or `verify`");
+        drop(log_guard);
+        std::process::exit(cli::USAGE);
+    }
+
+    // Keep the library up to date in the background instead, as a user service.
+    if args.daemon {
+        let app_id = <app::AppModel as cosmic::Application>::APP_ID;
+        let code = daemon::run(app_id, args.watch).code();
+        drop(log_guard);
+        std::process::exit(code);
+    }
+
+    // Run as the panel applet instead, when the panel starts it.
+    #[cfg(feature = "applet")]
+    if args.applet {
+        return applet::run();
+    }
 
     // Settings for configuring the application window and iced runtime.
-    let settings = cosmic::app::Settings::default().size_limits(
-        cosmic::iced::Limits::NONE
-            .min_width(360.0)
-            .min_height(180.0),
-    );
-
-    // Starts the application's event loop with `()` as the application's flags.
-    cosmic::app::run::<app::AppModel>(settings, ())
+    //
+    // Closing the window is handled by the app, which may hide it to the tray instead.
+    let settings = cosmic::app::Settings::default()
+        .size_limits(
+            cosmic::iced::Limits::NONE
+                .min_width(360.0)
+                .min_height(180.0),
+        )
+        .exit_on_close(false);
+
+    let flags = app::Flags {
+        started,
+        files: args.files(),
+        page: args.page.map(|page| page as usize - 1),
+        library: args.library,
+        fullscreen: args.fullscreen,
+    };
+
+    // Books opened from the file manager go to the running instance, if there is one.
+    if !flags.files.is_empty()
+        && instance::forward(<app::AppModel as cosmic::Application>::APP_ID, &flags.files)
+    {
+        return Ok(());
+    }
+
+    // Starts the application's event loop, or focuses the running instance instead.
+    cosmic::app::run_single_instance::<app::AppModel>(settings, flags)
 }
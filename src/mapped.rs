@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reads book files through memory maps, so that parsing a large EPUB, PDF or comic
+//! archive pages in only the parts it reads instead of copying them through buffers.
+//!
+//! A mapped file cut short by another program faults the process reading past its new
+//! end, so only the large copies libby keeps in its own folder are mapped, once they've
+//! gone unchanged for a while. Everything else, such as books in watched folders or
+//! ones still being imported, and files which can't be mapped, as on some network file
+//! systems, are read as usual.
+
+use crate::import;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Files smaller than this are read into buffers, as mapping them gains nothing.
+const MIN_SIZE: u64 = 1024 * 1024;
+
+/// How long a file must have gone unchanged before it's mapped, so that one still being
+/// copied or rewritten is read as usual.
+const SETTLED: Duration = Duration::from_secs(30);
+
+/// The contents of a file, mapped into memory where possible.
+#[derive(Debug)]
+pub enum Contents {
+    Mapped(Cursor<Mmap>),
+    Buffered(BufReader<File>),
+}
+
+impl Read for Contents {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Contents::Mapped(map) => map.read(buf),
+            Contents::Buffered(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for Contents {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Contents::Mapped(map) => map.seek(pos),
+            Contents::Buffered(file) => file.seek(pos),
+        }
+    }
+}
+
+/// Opens the file at `path` for reading, through a memory map where possible.
+pub fn open(path: &Path) -> io::Result<Contents> {
+    let file = File::open(path)?;
+
+    Ok(match map_file(&file, path) {
+        Some(map) => Contents::Mapped(Cursor::new(map)),
+        None => Contents::Buffered(BufReader::new(file)),
+    })
+}
+
+/// Maps the file at `path` into memory, or `None` where it can't be, to be read as
+/// usual instead.
+pub fn map(path: &Path) -> Option<Mmap> {
+    map_file(&File::open(path).ok()?, path)
+}
+
+fn map_file(file: &File, path: &Path) -> Option<Mmap> {
+    if !mappable(file, path) {
+        return None;
+    }
+
+    // SAFETY: only settled files in libby's own folder are mapped, which libby writes
+    // once and then leaves alone, and other programs have no reason to touch.
+    match unsafe { Mmap::map(file) } {
+        Ok(map) => Some(map),
+        Err(why) => {
+            tracing::debug!("reading {} without mapping it: {why}", path.display());
+            None
+        }
+    }
+}
+
+/// Whether `file`, opened from `path`, is safe and worth mapping.
+fn mappable(file: &File, path: &Path) -> bool {
+    if !import::dir().is_some_and(|books| path.starts_with(books)) {
+        return false;
+    }
+
+    let Ok(metadata) = file.metadata() else {
+        return false;
+    };
+
+    let settled = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= SETTLED);
+
+    metadata.len() >= MIN_SIZE && settled
+}
@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Turns MathML into plain text the reader can lay out with the rest of a chapter.
+//!
+//! Formulas are written out on one line the way they would be typed: superscripts and
+//! subscripts use Unicode forms where every character has one, fractions become `a/b`
+//! and roots `√x`, with parentheses added where grouping would otherwise be lost.
+
+use quick_xml::events::Event;
+
+/// Encodings of TeX annotations, used when a formula has no presentation markup.
+const TEX_ENCODINGS: [&str; 2] = ["application/x-tex", "application/x-latex"];
+
+/// Operators given a space on either side when between two operands.
+const SPACED_OPERATORS: &[&str] = &[
+    "=", "+", "-", "−", "±", "×", "÷", "<", ">", "≤", "≥", "≠", "≈", "≡", "→", "←", "↔", "⇒", "⇔",
+    "∈", "∉", "⊂", "⊆", "∪", "∩", "∧", "∨",
+];
+
+/// An element of a formula.
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Child>,
+}
+
+#[derive(Debug)]
+enum Child {
+    Element(Node),
+    Text(String),
+}
+
+impl Node {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Child::Element(node) => Some(node),
+            Child::Text(_) => None,
+        })
+    }
+
+    /// The text directly inside the element, with whitespace collapsed.
+    fn text(&self) -> String {
+        let text: String = self
+            .children
+            .iter()
+            .map(|child| match child {
+                Child::Text(text) => text.as_str(),
+                Child::Element(_) => "",
+            })
+            .collect();
+
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Writes out a `math` element, given as its XML including the element itself.
+///
+/// Falls back to a TeX annotation, and then to the `alttext` attribute, when the
+/// formula has no presentation markup.
+pub fn to_text(mathml: &[u8]) -> String {
+    let Some(root) = parse(mathml) else {
+        return String::new();
+    };
+
+    let text = render(&root);
+
+    if !text.trim().is_empty() {
+        return text.trim().to_string();
+    }
+
+    tex_annotation(&root)
+        .or_else(|| root.attribute("alttext").map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse(mathml: &[u8]) -> Option<Node> {
+    let mut reader = quick_xml::Reader::from_reader(mathml);
+    reader.config_mut().check_end_names = false;
+
+    let mut stack: Vec<Node> = Vec::new();
+    let mut root = None;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(why) => {
+                tracing::warn!("failed to parse formula: {why}");
+                break;
+            }
+        };
+
+        match event {
+            Event::Start(e) => stack.push(node(&e)),
+            Event::Empty(e) => {
+                let node = node(&e);
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Child::Element(node)),
+                    None => root = Some(node),
+                }
+            }
+            Event::End(_) => {
+                let Some(node) = stack.pop() else {
+                    continue;
+                };
+
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Child::Element(node)),
+                    None => {
+                        root = Some(node);
+                        break;
+                    }
+                }
+            }
+            Event::Text(text) => {
+                let raw = String::from_utf8_lossy(&text);
+                let text = quick_xml::escape::unescape(&raw)
+                    .map(|text| text.into_owned())
+                    .unwrap_or_else(|_| raw.into_owned());
+
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Child::Text(text));
+                }
+            }
+            Event::CData(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent
+                        .children
+                        .push(Child::Text(String::from_utf8_lossy(&text).into_owned()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    root
+}
+
+fn node(e: &quick_xml::events::BytesStart) -> Node {
+    let attributes = e
+        .attributes()
+        .with_checks(false)
+        .filter_map(Result::ok)
+        .map(|attr| {
+            (
+                String::from_utf8_lossy(attr.key.local_name().as_ref()).to_ascii_lowercase(),
+                attr.unescape_value()
+                    .map(|value| value.into_owned())
+                    .unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    Node {
+        name: String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase(),
+        attributes,
+        children: Vec::new(),
+    }
+}
+
+fn render(node: &Node) -> String {
+    let children: Vec<&Node> = node.elements().collect();
+    let arg = |index: usize| children.get(index).map_or(String::new(), |n| render(n));
+
+    match node.name.as_str() {
+        "mi" | "mn" | "mo" | "mtext" | "ms" => node.text(),
+        "mspace" => " ".to_string(),
+        "semantics" => arg(0),
+        "annotation" | "annotation-xml" | "mphantom" | "none" | "mprescripts" => String::new(),
+        "msup" => format!("{}{}", arg(0), superscript(&arg(1))),
+        "msub" => format!("{}{}", arg(0), subscript(&arg(1))),
+        "msubsup" => format!("{}{}{}", arg(0), subscript(&arg(1)), superscript(&arg(2))),
+        // Limits of sums and integrals, set apart from what they apply to.
+        "munder" => format!("{}{} ", arg(0), subscript(&arg(1))),
+        "mover" => format!("{}{}", arg(0), superscript(&arg(1))),
+        "munderover" => format!("{}{}{} ", arg(0), subscript(&arg(1)), superscript(&arg(2))),
+        "mfrac" => format!("{}/{}", group(&arg(0)), group(&arg(1))),
+        "msqrt" => format!("√{}", group(&row(&children))),
+        "mroot" => format!("{}√{}", superscript(&arg(1)), group(&arg(0))),
+        "mfenced" => {
+            let open = node.attribute("open").unwrap_or("(");
+            let close = node.attribute("close").unwrap_or(")");
+            let separator = node
+                .attribute("separators")
+                .and_then(|separators| separators.trim().chars().next())
+                .unwrap_or(',');
+            let items: Vec<String> = children.iter().map(|child| render(child)).collect();
+            format!("{open}{}{close}", items.join(&format!("{separator} ")))
+        }
+        "mtable" => children
+            .iter()
+            .map(|row| {
+                row.elements()
+                    .map(|cell| render(cell).trim().to_string())
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
+        _ => row(&children),
+    }
+}
+
+/// Writes out a row of elements, spacing out the operators between operands.
+fn row(children: &[&Node]) -> String {
+    let mut text = String::new();
+
+    for (index, child) in children.iter().enumerate() {
+        let rendered = render(child);
+        let spaced =
+            child.name == "mo" && index > 0 && SPACED_OPERATORS.contains(&rendered.as_str());
+
+        if spaced {
+            text.push(' ');
+            text.push_str(&rendered);
+            text.push(' ');
+        } else if child.name == "mo" && rendered == "," {
+            text.push_str(", ");
+        } else {
+            text.push_str(&rendered);
+        }
+    }
+
+    text.split(' ')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Wraps text in parentheses unless it is a single symbol or number.
+fn group(text: &str) -> String {
+    let text = text.trim();
+
+    let mut symbols = text.chars().filter(|&c| !is_script(c));
+    let single = match (symbols.next(), symbols.next()) {
+        (Some(_), None) => true,
+        _ => text
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || is_script(c)),
+    };
+
+    if single || (text.starts_with('(') && text.ends_with(')')) {
+        text.to_string()
+    } else {
+        format!("({text})")
+    }
+}
+
+fn superscript(text: &str) -> String {
+    script(text, '^', superscript_char)
+}
+
+fn subscript(text: &str) -> String {
+    script(text, '_', subscript_char)
+}
+
+/// Writes a script with Unicode characters if each has one, or else after `marker`.
+fn script(text: &str, marker: char, map: fn(char) -> Option<char>) -> String {
+    let text = text.trim();
+
+    if text.is_empty() {
+        return String::new();
+    }
+
+    match text.chars().map(map).collect::<Option<String>>() {
+        Some(mapped) => mapped,
+        None => format!("{marker}{}", group(text)),
+    }
+}
+
+fn is_script(c: char) -> bool {
+    "⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁼⁽⁾ⁿⁱ₀₁₂₃₄₅₆₇₈₉₊₋₌₍₎ₐₑₒₓₕₖₗₘₙₚₛₜᵢⱼ".contains(c)
+}
+
+fn superscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'n' => 'ⁿ',
+        'i' => 'ⁱ',
+        // Primes are already raised.
+        '′' | '″' | '‴' | '*' | '∗' | '†' => c,
+        _ => return None,
+    })
+}
+
+fn subscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'o' => 'ₒ',
+        'x' => 'ₓ',
+        'h' => 'ₕ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'p' => 'ₚ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        _ => return None,
+    })
+}
+
+/// The TeX source a formula was converted from, if it was kept as an annotation.
+fn tex_annotation(node: &Node) -> Option<String> {
+    let encoding = node.attribute("encoding").unwrap_or_default();
+
+    if node.name == "annotation" && TEX_ENCODINGS.contains(&encoding) {
+        let text = node.text();
+        return (!text.is_empty()).then_some(text);
+    }
+
+    node.elements().find_map(tex_annotation)
+}
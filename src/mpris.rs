@@ -0,0 +1,439 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Media controls over MPRIS, so media keys, the panel's sound applet and headsets can
+//! control reading aloud.
+//!
+//! Reading aloud has no clock, so positions are counted in sentences, each taken to last
+//! [`SENTENCE`]. Chapters are the tracks: next and previous move between them.
+
+use crate::library::BookId;
+use crate::thumbnail;
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::Stream;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+/// Where both interfaces are served, as MPRIS requires.
+const PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Bus name of libby's player.
+const NAME: &str = "org.mpris.MediaPlayer2.libby";
+
+/// How long a sentence is taken to last when positions are counted.
+pub const SENTENCE: Duration = Duration::from_secs(5);
+
+/// A request from the media controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Bring the window to the front.
+    Raise,
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    /// Go to the next chapter.
+    Next,
+    /// Go to the previous chapter.
+    Previous,
+    /// Skip this many sentences, back if negative.
+    Seek(i64),
+    /// Go to this sentence of the chapter.
+    SetPosition(usize),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Status {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+/// What is being read aloud, as shown by the media controls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct State {
+    pub status: Status,
+    pub book: BookId,
+    /// The book's file, from which the cover is shown.
+    pub path: Option<PathBuf>,
+    pub title: String,
+    pub author: String,
+    /// Title of the chapter from the table of contents, if it's listed there.
+    pub chapter: Option<String>,
+    pub chapter_index: usize,
+    /// Index of the sentence being read.
+    pub sentence: usize,
+    /// Sentences in the chapter.
+    pub sentences: usize,
+    pub has_previous: bool,
+    pub has_next: bool,
+}
+
+impl State {
+    fn metadata(&self, art_url: Option<&str>) -> HashMap<String, OwnedValue> {
+        let title = self.chapter.clone().unwrap_or_else(|| self.title.clone());
+        let length = micros(self.sentences);
+
+        let mut metadata = HashMap::new();
+        let mut insert = |key: &str, value: Value| {
+            if let Ok(value) = OwnedValue::try_from(value) {
+                metadata.insert(key.to_string(), value);
+            }
+        };
+
+        if let Ok(track) = ObjectPath::try_from(track_id(self.chapter_index)) {
+            insert("mpris:trackid", Value::from(track));
+        }
+
+        insert("mpris:length", Value::from(length));
+        insert("xesam:title", Value::from(title));
+        insert("xesam:album", Value::from(self.title.clone()));
+        insert(
+            "xesam:trackNumber",
+            Value::from(self.chapter_index as i32 + 1),
+        );
+
+        if !self.author.is_empty() {
+            insert("xesam:artist", Value::from(vec![self.author.clone()]));
+        }
+
+        if let Some(art_url) = art_url {
+            insert("mpris:artUrl", Value::from(art_url.to_string()));
+        }
+
+        metadata
+    }
+}
+
+/// Object path standing for a chapter, as MPRIS identifies tracks.
+fn track_id(chapter_index: usize) -> String {
+    format!("/com/github/codegod100/libby/chapter/{chapter_index}")
+}
+
+/// Microseconds into a chapter at which a sentence starts.
+fn micros(sentence: usize) -> i64 {
+    (sentence as i64).saturating_mul(SENTENCE.as_micros() as i64)
+}
+
+/// Playback shared with the served player.
+#[derive(Debug, Clone)]
+pub struct Mpris(Arc<watch::Sender<State>>);
+
+impl Mpris {
+    pub fn new() -> Self {
+        Self(Arc::new(watch::channel(State::default()).0))
+    }
+
+    /// Updates what the media controls show, if it changed.
+    pub fn set(&self, state: State) {
+        self.0.send_if_modified(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+    }
+}
+
+struct Root {
+    commands: Sender<Command>,
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {
+        _ = self.commands.clone().try_send(Command::Raise);
+    }
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Libby"
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        "com.github.codegod100.libby"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    commands: Sender<Command>,
+    state: State,
+    /// `file://` address of the cover of the book being read, if it has one.
+    art_url: Option<String>,
+}
+
+impl Player {
+    fn send(&self, command: Command) {
+        _ = self.commands.clone().try_send(command);
+    }
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.send(Command::Next);
+    }
+
+    fn previous(&self) {
+        self.send(Command::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(Command::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(Command::Stop);
+    }
+
+    fn play(&self) {
+        self.send(Command::Play);
+    }
+
+    fn seek(&self, offset: i64) {
+        let sentence = SENTENCE.as_micros() as i64;
+
+        // Any seek moves at least a sentence, as smaller ones would do nothing.
+        let sentences = match offset / sentence {
+            0 => offset.signum(),
+            sentences => sentences,
+        };
+
+        if sentences != 0 {
+            self.send(Command::Seek(sentences));
+        }
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        // Positions meant for a chapter no longer being read are ignored.
+        if track_id.as_str() == self::track_id(self.state.chapter_index) && position >= 0 {
+            let sentence = position / SENTENCE.as_micros() as i64;
+            self.send(Command::SetPosition(sentence as usize));
+        }
+    }
+
+    fn open_uri(&self, _uri: &str) {}
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        match self.state.status {
+            Status::Playing => "Playing",
+            Status::Paused => "Paused",
+            Status::Stopped => "Stopped",
+        }
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.state.metadata(self.art_url.as_deref())
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        micros(self.state.sentence)
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state.has_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state.has_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.state.sentences > 0
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.state.sentences > 0
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Serves the player on the session bus for as long as the stream is polled, yielding
+/// the requests of the media controls.
+pub fn serve(mpris: Mpris) -> impl Stream<Item = Command> {
+    cosmic::iced::stream::channel(4, move |output| async move {
+        let mut states = mpris.0.subscribe();
+        let state = states.borrow_and_update().clone();
+        let art_url = cover(&state).await;
+
+        let root = Root {
+            commands: output.clone(),
+        };
+
+        let player = Player {
+            commands: output,
+            state,
+            art_url,
+        };
+
+        let connection = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.serve_at(PATH, root))
+            .and_then(|builder| builder.serve_at(PATH, player));
+
+        let connection = match connection {
+            Ok(builder) => builder.build().await,
+            Err(why) => Err(why),
+        };
+
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(why) => {
+                tracing::error!("failed to serve media controls: {why}");
+                return;
+            }
+        };
+
+        let player = match connection
+            .object_server()
+            .interface::<_, Player>(PATH)
+            .await
+        {
+            Ok(player) => player,
+            Err(why) => {
+                tracing::error!("failed to serve media controls: {why}");
+                return;
+            }
+        };
+
+        while states.changed().await.is_ok() {
+            let state = states.borrow_and_update().clone();
+            let previous = player.get().await.state.clone();
+
+            let book_changed = previous.book != state.book || previous.path != state.path;
+            let track_changed = book_changed
+                || previous.chapter_index != state.chapter_index
+                || previous.sentences != state.sentences;
+
+            // The cover is found before taking hold of the player, which would hold up
+            // the media controls meanwhile.
+            let art_url = if book_changed {
+                Some(cover(&state).await)
+            } else {
+                None
+            };
+
+            let mut served = player.get_mut().await;
+            let emitter = player.signal_emitter();
+
+            served.state = state.clone();
+
+            if let Some(art_url) = art_url {
+                served.art_url = art_url;
+            }
+
+            if previous.status != state.status {
+                _ = served.playback_status_changed(emitter).await;
+            }
+
+            if track_changed || previous.chapter != state.chapter {
+                _ = served.metadata_changed(emitter).await;
+            }
+
+            if previous.has_next != state.has_next {
+                _ = served.can_go_next_changed(emitter).await;
+            }
+
+            if previous.has_previous != state.has_previous {
+                _ = served.can_go_previous_changed(emitter).await;
+            }
+
+            if previous.sentences != state.sentences {
+                _ = served.can_play_changed(emitter).await;
+                _ = served.can_seek_changed(emitter).await;
+            }
+
+            // Sentences are read one after another, so only jumps count as seeking.
+            let jumped =
+                state.sentence != previous.sentence && state.sentence != previous.sentence + 1;
+
+            if !track_changed && jumped {
+                _ = Player::seeked(emitter, micros(state.sentence)).await;
+            }
+        }
+    })
+}
+
+/// Writes the cover of the book being read where the media controls can show it from,
+/// returning its `file://` address.
+async fn cover(state: &State) -> Option<String> {
+    let path = state.path.clone()?;
+    let written = thumbnail::book_cover(state.book, path).await.ok()??;
+
+    reqwest::Url::from_file_path(written).ok().map(String::from)
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Desktop notifications, raised through `notify-send` so that any notification server
+//! shows them.
+
+use std::io;
+use tokio::process::Command;
+
+/// Name `notify-send` prints when the notification's button is clicked.
+const ACTION: &str = "open";
+
+/// Name of the action most notification servers invoke when the notification itself is
+/// clicked.
+const DEFAULT_ACTION: &str = "default";
+
+/// Raises a desktop notification, with a button labelled `action` if given.
+///
+/// With a button, this waits for the notification to be dismissed, and returns whether
+/// the button or the notification itself was clicked.
+pub async fn notify(summary: String, body: String, action: Option<String>) -> io::Result<bool> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=Libby", "--icon=com.github.codegod100.libby"]);
+
+    if let Some(label) = &action {
+        command
+            .arg(format!("--action={DEFAULT_ACTION}={label}"))
+            .arg(format!("--action={ACTION}={label}"));
+    }
+
+    let output = command.arg("--").arg(summary).arg(body).output().await?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "notify-send exited with {}",
+            output.status
+        )));
+    }
+
+    let chosen = String::from_utf8_lossy(&output.stdout);
+    Ok(action.is_some() && matches!(chosen.trim(), ACTION | DEFAULT_ACTION))
+}
@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reads OPDS catalogs, and plain web server listings of book files as if they were
+//! catalogs too.
+//!
+//! Only what browsing needs is read from an OPDS feed: its entries, the links leading
+//! further into the catalog, the links books are downloaded from, covers, and the next
+//! page.
+//!
+//! Built without the `opds` feature, libby reads only plain listings, and OPDS feeds
+//! fail to load saying so.
+
+use crate::download;
+use crate::net::{self, Credentials};
+#[cfg(feature = "opds")]
+use quick_xml::events::{BytesStart, Event};
+use reqwest::Url;
+
+/// Extensions of the files a listing offers for download.
+const BOOK_EXTENSIONS: [&str; 4] = ["epub", "pdf", "cbz", "cbr"];
+
+/// A page of a catalog.
+#[derive(Debug, Clone, Default)]
+pub struct Feed {
+    pub title: String,
+    pub entries: Vec<Entry>,
+    /// The page after this one.
+    pub next: Option<String>,
+}
+
+/// A book, or a section leading further into the catalog.
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    pub title: String,
+    pub author: String,
+    pub summary: String,
+    /// Feed the entry leads to, if it is a section rather than a book.
+    pub navigation: Option<String>,
+    /// Files the book can be downloaded as.
+    pub acquisitions: Vec<Acquisition>,
+    /// Address of the book's cover, preferring a thumbnail.
+    pub cover: Option<String>,
+}
+
+/// A file a book can be downloaded as.
+#[derive(Debug, Clone)]
+pub struct Acquisition {
+    pub url: String,
+    pub mime: String,
+}
+
+impl Acquisition {
+    /// Extension of the file, from its media type or else its address.
+    pub fn extension(&self) -> Option<&'static str> {
+        let from_mime = match self.mime.split(';').next().map(str::trim) {
+            Some("application/epub+zip") => Some("epub"),
+            Some("application/pdf") => Some("pdf"),
+            Some("application/vnd.comicbook+zip" | "application/x-cbz") => Some("cbz"),
+            Some("application/vnd.comicbook-rar" | "application/x-cbr") => Some("cbr"),
+            _ => None,
+        };
+
+        from_mime.or_else(|| {
+            let path = self.url.split(['?', '#']).next()?.to_lowercase();
+            BOOK_EXTENSIONS
+                .into_iter()
+                .find(|extension| path.ends_with(&format!(".{extension}")))
+        })
+    }
+
+    /// Name to save the file under: the book's title, as catalogs often serve files
+    /// from addresses which don't name them.
+    pub fn file_name(&self, title: &str) -> String {
+        match self.extension() {
+            Some(extension) if !title.trim().is_empty() => {
+                format!("{}.{extension}", title.trim().replace(['/', '\\'], "-"))
+            }
+            _ => download::file_name(&self.url),
+        }
+    }
+}
+
+/// Fetches and reads a page of an OPDS catalog.
+#[cfg(feature = "opds")]
+pub async fn fetch_feed(url: String, credentials: Option<Credentials>) -> Result<Feed, String> {
+    let body = net::fetch(url.clone(), credentials).await?;
+    parse_feed(&body, &url)
+}
+
+#[cfg(not(feature = "opds"))]
+pub async fn fetch_feed(_url: String, _credentials: Option<Credentials>) -> Result<Feed, String> {
+    Err("this build of libby can't read OPDS catalogs".to_string())
+}
+
+/// Fetches a web server's listing of a folder and reads the book files and folders it
+/// links to.
+pub async fn fetch_listing(
+    mut url: String,
+    credentials: Option<Credentials>,
+) -> Result<Feed, String> {
+    // Links in a listing are relative to the folder, not to its parent.
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+
+    let body = net::fetch(url.clone(), credentials).await?;
+    Ok(parse_listing(&String::from_utf8_lossy(&body), &url))
+}
+
+/// Fetches the cover of an entry.
+pub async fn fetch_cover(url: String, credentials: Option<Credentials>) -> Result<Vec<u8>, String> {
+    net::fetch(url, credentials).await
+}
+
+/// Resolves a link against the address of the page it was found on.
+fn resolve(base: &str, href: &str) -> Option<String> {
+    Url::parse(base)
+        .and_then(|base| base.join(href))
+        .ok()
+        .map(String::from)
+}
+
+#[cfg(feature = "opds")]
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .filter_map(Result::ok)
+        .find(|attr| attr.key.local_name().as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// Reads an OPDS 1 feed, an Atom document whose links are resolved against `base`.
+#[cfg(feature = "opds")]
+fn parse_feed(xml: &[u8], base: &str) -> Result<Feed, String> {
+    let mut reader = quick_xml::Reader::from_reader(xml);
+    reader.config_mut().check_end_names = false;
+
+    let mut feed = Feed::default();
+    let mut entry: Option<Entry> = None;
+    let mut path: Vec<Vec<u8>> = Vec::new();
+
+    loop {
+        let event = reader.read_event().map_err(|why| why.to_string())?;
+
+        match event {
+            Event::Start(e) => {
+                if e.local_name().as_ref() == b"entry" {
+                    entry = Some(Entry::default());
+                }
+
+                if e.local_name().as_ref() == b"link" {
+                    link(&e, base, &mut feed, entry.as_mut());
+                }
+
+                path.push(e.local_name().as_ref().to_vec());
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"link" => {
+                link(&e, base, &mut feed, entry.as_mut());
+            }
+            Event::End(e) => {
+                path.pop();
+
+                if e.local_name().as_ref() == b"entry" {
+                    feed.entries.extend(entry.take());
+                }
+            }
+            Event::Text(text) => {
+                let raw = String::from_utf8_lossy(&text);
+                let text = quick_xml::escape::unescape(&raw)
+                    .map(|text| text.into_owned())
+                    .unwrap_or_else(|_| raw.into_owned());
+
+                let path: Vec<&[u8]> = path.iter().map(Vec::as_slice).collect();
+
+                let field = match (&mut entry, path.as_slice()) {
+                    (None, [b"feed", b"title"]) => &mut feed.title,
+                    (Some(entry), [.., b"entry", b"title"]) => &mut entry.title,
+                    (Some(entry), [.., b"entry", b"author", b"name"]) => &mut entry.author,
+                    (Some(entry), [.., b"entry", b"summary" | b"content"]) => &mut entry.summary,
+                    _ => continue,
+                };
+
+                // Text broken up by markup is joined, and its spacing tidied below.
+                field.push(' ');
+                field.push_str(&text);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    for entry in &mut feed.entries {
+        entry.title = collapse(&entry.title);
+        entry.author = collapse(&entry.author);
+        entry.summary = collapse(&entry.summary);
+    }
+
+    feed.title = collapse(&feed.title);
+    Ok(feed)
+}
+
+/// Notes what a link of the feed, or of the entry being read, leads to.
+#[cfg(feature = "opds")]
+fn link(e: &BytesStart, base: &str, feed: &mut Feed, entry: Option<&mut Entry>) {
+    let Some(href) = attribute(e, b"href").and_then(|href| resolve(base, &href)) else {
+        return;
+    };
+
+    let rel = attribute(e, b"rel").unwrap_or_default();
+    let mime = attribute(e, b"type").unwrap_or_default();
+
+    match entry {
+        Some(entry) => {
+            if rel.starts_with("http://opds-spec.org/acquisition") {
+                entry.acquisitions.push(Acquisition { url: href, mime });
+            } else if rel == "http://opds-spec.org/image/thumbnail" {
+                entry.cover = Some(href);
+            } else if rel == "http://opds-spec.org/image" {
+                entry.cover.get_or_insert(href);
+            } else if rel == "subsection"
+                || mime.contains("profile=opds-catalog")
+                || mime.contains("kind=navigation")
+                || mime.contains("kind=acquisition")
+            {
+                entry.navigation.get_or_insert(href);
+            }
+        }
+        None if rel == "next" => feed.next = Some(href),
+        None => (),
+    }
+}
+
+#[cfg(feature = "opds")]
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Reads the links of an HTML folder listing, keeping subfolders and book files.
+fn parse_listing(html: &str, base: &str) -> Feed {
+    let mut feed = Feed {
+        title: Url::parse(base)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| base.to_string()),
+        ..Feed::default()
+    };
+
+    for anchor in html.split("<a ").skip(1) {
+        let Some(href) = anchor
+            .split_once("href=\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(href, _)| href)
+        else {
+            continue;
+        };
+
+        // Sorting links and the way back up aren't entries.
+        if href.starts_with('?') || href.starts_with('#') || href.starts_with("..") {
+            continue;
+        }
+
+        let Some(url) = resolve(
+            base,
+            &quick_xml::escape::unescape(href).unwrap_or(href.into()),
+        ) else {
+            continue;
+        };
+
+        // Only what lies below the listed folder.
+        if !url.starts_with(base) || url.len() <= base.len() {
+            continue;
+        }
+
+        let name = url[base.len()..].trim_end_matches('/');
+        let title = percent_decode(name);
+
+        let entry = if url.ends_with('/') {
+            Entry {
+                title,
+                navigation: Some(url),
+                ..Entry::default()
+            }
+        } else {
+            let acquisition = Acquisition {
+                url,
+                mime: String::new(),
+            };
+
+            if acquisition.extension().is_none() {
+                continue;
+            }
+
+            // The extension is shown on the download button instead.
+            let title = match title.rsplit_once('.') {
+                Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+                _ => title,
+            };
+
+            Entry {
+                title,
+                acquisitions: vec![acquisition],
+                ..Entry::default()
+            }
+        };
+
+        feed.entries.push(entry);
+    }
+
+    feed
+}
+
+/// Decodes the `%XX` escapes of a URL path segment.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Color schemes for the reading area, chosen independently of the app theme.
+
+use serde::{Deserialize, Serialize};
+
+/// An RGB color as stored in the configuration.
+pub type Rgb = [u8; 3];
+
+/// Colors of the reading area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Follow the app theme.
+    #[default]
+    App,
+    Light,
+    Sepia,
+    Dark,
+    Black,
+    /// The user's own foreground and background colors.
+    Custom,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 6] = [
+        Palette::App,
+        Palette::Light,
+        Palette::Sepia,
+        Palette::Dark,
+        Palette::Black,
+        Palette::Custom,
+    ];
+
+    /// Foreground and background colors, or `None` when following the app theme.
+    pub fn colors(self, custom: &CustomPalette) -> Option<(Rgb, Rgb)> {
+        match self {
+            Palette::App => None,
+            Palette::Light => Some(([0x1e, 0x1e, 0x1e], [0xff, 0xff, 0xff])),
+            Palette::Sepia => Some(([0x5b, 0x46, 0x36], [0xf4, 0xec, 0xd8])),
+            Palette::Dark => Some(([0xd8, 0xd8, 0xd8], [0x2b, 0x2b, 0x2b])),
+            Palette::Black => Some(([0xc0, 0xc0, 0xc0], [0x00, 0x00, 0x00])),
+            Palette::Custom => Some((custom.foreground, custom.background)),
+        }
+    }
+}
+
+/// Foreground and background of the custom palette.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomPalette {
+    pub foreground: Rgb,
+    pub background: Rgb,
+}
+
+impl Default for CustomPalette {
+    fn default() -> Self {
+        Self {
+            foreground: [0x33, 0x33, 0x33],
+            background: [0xe8, 0xf0, 0xe0],
+        }
+    }
+}
+
+/// Parses a color written as `#rrggbb` or `rrggbb`.
+pub fn parse_hex(text: &str) -> Option<Rgb> {
+    let hex = text.trim().trim_start_matches('#');
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Formats a color as `#rrggbb`.
+pub fn to_hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
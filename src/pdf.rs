@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Renders PDF pages through the system's pdfium library.
+//!
+//! Built without the `pdf` feature, libby leaves pdfium out, and every PDF fails to
+//! open with [`Error`] saying so.
+
+#[cfg(feature = "pdf")]
+use crate::mapped;
+use crate::toc;
+#[cfg(feature = "pdf")]
+use memmap2::Mmap;
+#[cfg(feature = "pdf")]
+use pdfium_render::prelude::*;
+use std::fmt;
+use std::path::Path;
+
+/// Why a PDF couldn't be read or rendered.
+#[cfg(feature = "pdf")]
+pub type Error = PdfiumError;
+
+/// Why a PDF couldn't be read or rendered: always, as pdfium was left out of the build.
+#[cfg(not(feature = "pdf"))]
+#[derive(Debug)]
+pub struct Error;
+
+#[cfg(not(feature = "pdf"))]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("this build of libby can't read PDFs")
+    }
+}
+
+#[cfg(not(feature = "pdf"))]
+impl std::error::Error for Error {}
+
+/// Page count and page sizes of a PDF.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "pdf"), allow(dead_code))]
+pub struct Document {
+    pub title: Option<String>,
+    /// Width and height of each page, in points.
+    pub page_sizes: Vec<(f32, f32)>,
+    /// The document's outline, with entries pointing at pages.
+    pub outline: Vec<toc::Entry>,
+}
+
+impl Document {
+    pub fn page_count(&self) -> usize {
+        self.page_sizes.len()
+    }
+}
+
+/// A rendered page as RGBA pixels.
+#[derive(Clone)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bitmap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn bind() -> Result<Pdfium, PdfiumError> {
+    Ok(Pdfium::new(Pdfium::bind_to_system_library()?))
+}
+
+/// Loads the PDF at `path` from its memory map, or from the file when it couldn't be
+/// mapped.
+#[cfg(feature = "pdf")]
+fn load<'a>(
+    pdfium: &'a Pdfium,
+    path: &Path,
+    map: Option<&'a Mmap>,
+) -> Result<PdfDocument<'a>, PdfiumError> {
+    match map {
+        Some(map) => pdfium.load_pdf_from_byte_slice(map, None),
+        None => pdfium.load_pdf_from_file(path, None),
+    }
+}
+
+/// Reads the page layout of the PDF at `path`.
+#[cfg(feature = "pdf")]
+pub fn open(path: &Path) -> Result<Document, Error> {
+    let pdfium = bind()?;
+    let map = mapped::map(path);
+    let document = load(&pdfium, path, map.as_ref())?;
+
+    let page_sizes = document
+        .pages()
+        .page_sizes()?
+        .into_iter()
+        .map(|rect| (rect.width().value, rect.height().value))
+        .collect();
+
+    let title = document
+        .metadata()
+        .get(PdfDocumentMetadataTagType::Title)
+        .map(|tag| tag.value().to_string())
+        .filter(|title| !title.is_empty());
+
+    let outline = outline(document.bookmarks().root());
+
+    Ok(Document {
+        title,
+        page_sizes,
+        outline,
+    })
+}
+
+/// Converts a bookmark and its following siblings into outline entries.
+#[cfg(feature = "pdf")]
+fn outline(first: Option<PdfBookmark>) -> Vec<toc::Entry> {
+    let mut entries = Vec::new();
+    let mut next = first;
+
+    while let Some(bookmark) = next {
+        entries.push(toc::Entry {
+            title: bookmark.title().unwrap_or_default().trim().to_string(),
+            target: bookmark
+                .destination()
+                .and_then(|destination| destination.page_index().ok())
+                .map(usize::from),
+            children: outline(bookmark.first_child()),
+        });
+
+        next = bookmark.next_sibling();
+    }
+
+    entries
+}
+
+/// Renders a page of the PDF at `path`, scaled to `width` pixels.
+#[cfg(feature = "pdf")]
+pub fn render(path: &Path, index: usize, width: u32) -> Result<Bitmap, Error> {
+    let pdfium = bind()?;
+    let map = mapped::map(path);
+    let document = load(&pdfium, path, map.as_ref())?;
+
+    render_page(&document, index, width)
+}
+
+/// Renders every page at `width` pixels, handing each to `sink` until it returns false.
+#[cfg(feature = "pdf")]
+pub fn render_all(
+    path: &Path,
+    width: u32,
+    mut sink: impl FnMut(usize, Bitmap) -> bool,
+) -> Result<(), Error> {
+    let pdfium = bind()?;
+    let map = mapped::map(path);
+    let document = load(&pdfium, path, map.as_ref())?;
+
+    for index in 0..usize::from(document.pages().len()) {
+        if !sink(index, render_page(&document, index, width)?) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts the text of every page, handing each to `sink` until it returns false.
+#[cfg(feature = "pdf")]
+pub fn page_texts(path: &Path, mut sink: impl FnMut(usize, String) -> bool) -> Result<(), Error> {
+    let pdfium = bind()?;
+    let map = mapped::map(path);
+    let document = load(&pdfium, path, map.as_ref())?;
+
+    for (index, page) in document.pages().iter().enumerate() {
+        if !sink(index, page.text()?.all()) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "pdf")]
+fn render_page(document: &PdfDocument, index: usize, width: u32) -> Result<Bitmap, PdfiumError> {
+    let index = PdfPageIndex::try_from(index).map_err(|_| PdfiumError::PageIndexOutOfBounds)?;
+    let page = document.pages().get(index)?;
+
+    let config = PdfRenderConfig::new().set_target_width(width.try_into().unwrap_or(Pixels::MAX));
+    let bitmap = page.render_with_config(&config)?;
+
+    Ok(Bitmap {
+        width: bitmap.width().try_into().unwrap_or_default(),
+        height: bitmap.height().try_into().unwrap_or_default(),
+        pixels: bitmap.as_rgba_bytes(),
+    })
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn open(_path: &Path) -> Result<Document, Error> {
+    Err(Error)
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn render(_path: &Path, _index: usize, _width: u32) -> Result<Bitmap, Error> {
+    Err(Error)
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn render_all(
+    _path: &Path,
+    _width: u32,
+    _sink: impl FnMut(usize, Bitmap) -> bool,
+) -> Result<(), Error> {
+    Err(Error)
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn page_texts(_path: &Path, _sink: impl FnMut(usize, String) -> bool) -> Result<(), Error> {
+    Err(Error)
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Whether the computer is running on battery or in power-saver mode, from UPower and
+//! power-profiles-daemon, so background work can be held back.
+
+use futures_util::{SinkExt, Stream, StreamExt};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower",
+    gen_blocking = false
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles",
+    gen_blocking = false
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+}
+
+/// How the computer is powered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Power {
+    pub on_battery: bool,
+    /// Whether the power-saver profile is active.
+    pub power_saver: bool,
+}
+
+impl Power {
+    /// Whether power should be saved.
+    pub fn is_saving(self) -> bool {
+        self.on_battery || self.power_saver
+    }
+}
+
+/// Yields how the computer is powered, then again whenever that changes.
+///
+/// Either service may be missing, in which case it's taken to not be saving power.
+pub fn watch() -> impl Stream<Item = Power> {
+    cosmic::iced::stream::channel(4, |mut output| async move {
+        let connection = match zbus::Connection::system().await {
+            Ok(connection) => connection,
+            Err(why) => {
+                tracing::error!("failed to watch the power state: {why}");
+                return;
+            }
+        };
+
+        let upower = UPowerProxy::new(&connection).await.ok();
+        let profiles = PowerProfilesProxy::new(&connection).await.ok();
+
+        let battery_changes = match &upower {
+            Some(upower) => upower
+                .receive_on_battery_changed()
+                .await
+                .map(|_| ())
+                .boxed(),
+            None => futures_util::stream::pending().boxed(),
+        };
+
+        let profile_changes = match &profiles {
+            Some(profiles) => profiles
+                .receive_active_profile_changed()
+                .await
+                .map(|_| ())
+                .boxed(),
+            None => futures_util::stream::pending().boxed(),
+        };
+
+        // Property streams yield the current value first, so this starts with it.
+        let mut changes = futures_util::stream::select(battery_changes, profile_changes);
+
+        while changes.next().await.is_some() {
+            let on_battery = match &upower {
+                Some(upower) => upower.on_battery().await.unwrap_or_default(),
+                None => false,
+            };
+
+            let power_saver = match &profiles {
+                Some(profiles) => profiles
+                    .active_profile()
+                    .await
+                    .is_ok_and(|profile| profile == "power-saver"),
+                None => false,
+            };
+
+            let power = Power {
+                on_battery,
+                power_saver,
+            };
+
+            if output.send(power).await.is_err() {
+                break;
+            }
+        }
+    })
+}
@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Prints simple documents, such as a book's annotations, through the XDG desktop
+//! portal's print dialog, which also offers to print to a PDF file.
+//!
+//! Documents are laid out here as lines of text on A4 pages and written to a PDF with
+//! pdfium, which the portal then sends to the printer chosen. Built without the `pdf`
+//! feature, libby can't print.
+
+#[cfg(feature = "pdf")]
+use ashpd::desktop::print::{PageSetup, PrintProxy, Settings};
+#[cfg(feature = "pdf")]
+use ashpd::desktop::ResponseError;
+#[cfg(feature = "pdf")]
+use pdfium_render::prelude::*;
+#[cfg(feature = "pdf")]
+use std::os::fd::AsFd;
+#[cfg(feature = "pdf")]
+use std::path::{Path, PathBuf};
+
+/// Width and height of an A4 page, in points.
+#[cfg(feature = "pdf")]
+const PAGE: (f32, f32) = (595.0, 842.0);
+
+/// Space left around the text, in points.
+#[cfg(feature = "pdf")]
+const MARGIN: f32 = 56.0;
+
+/// How far quotes are indented, in points.
+#[cfg(feature = "pdf")]
+const INDENT: f32 = 18.0;
+
+/// Height of a line, relative to the size of its text.
+#[cfg(feature = "pdf")]
+const LINE_HEIGHT: f32 = 1.4;
+
+/// Average width of a character, relative to the size of the text, by which lines are
+/// wrapped.
+#[cfg(feature = "pdf")]
+const CHAR_WIDTH: f32 = 0.5;
+
+/// A part of a document, each kind set in its own size.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Title(String),
+    Heading(String),
+    /// Quoted text, indented.
+    Quote(String),
+    Text(String),
+}
+
+#[cfg(feature = "pdf")]
+impl Block {
+    /// Size of the text in points, and how far it's indented.
+    fn style(&self) -> (f32, f32) {
+        match self {
+            Block::Title(_) => (20.0, 0.0),
+            Block::Heading(_) => (14.0, 0.0),
+            Block::Quote(_) => (11.0, INDENT),
+            Block::Text(_) => (11.0, 0.0),
+        }
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            Block::Title(text) | Block::Heading(text) | Block::Quote(text) | Block::Text(text) => {
+                text
+            }
+        }
+    }
+}
+
+/// Something to print.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    /// Shown in the print dialog and the printer's queue.
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+/// A line of text placed on a page.
+#[cfg(feature = "pdf")]
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    x: f32,
+    /// Distance of the line's baseline from the top of the page.
+    y: f32,
+    size: f32,
+    text: String,
+}
+
+/// Wraps the document's blocks into lines and breaks them into pages.
+#[cfg(feature = "pdf")]
+fn layout(document: &Document) -> Vec<Vec<Line>> {
+    let mut pages = vec![Vec::new()];
+    let mut y = MARGIN;
+
+    for block in &document.blocks {
+        let (size, indent) = block.style();
+        let line_height = size * LINE_HEIGHT;
+        let width = PAGE.0 - 2.0 * MARGIN - indent;
+        let columns = (width / (size * CHAR_WIDTH)).max(1.0) as usize;
+
+        for paragraph in block.text().lines() {
+            for text in wrap(paragraph, columns) {
+                if y + line_height > PAGE.1 - MARGIN {
+                    pages.push(Vec::new());
+                    y = MARGIN;
+                }
+
+                y += line_height;
+
+                if let Some(page) = pages.last_mut() {
+                    page.push(Line {
+                        x: MARGIN + indent,
+                        y,
+                        size,
+                        text,
+                    });
+                }
+            }
+        }
+
+        // A gap between blocks, which the top of a page needs no more of.
+        if y > MARGIN {
+            y += size * (LINE_HEIGHT - 1.0) * 2.0;
+        }
+    }
+
+    pages
+}
+
+/// Breaks a paragraph into lines of at most `columns` characters at spaces, and within
+/// words longer than a line.
+#[cfg(feature = "pdf")]
+fn wrap(paragraph: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in paragraph.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        while word.len() > columns {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            lines.push(word.drain(..columns).collect());
+        }
+
+        let length = line.chars().count();
+
+        if length > 0 && length + 1 + word.len() > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.extend(word);
+    }
+
+    // Blank lines are kept, as they space paragraphs.
+    lines.push(line);
+    lines
+}
+
+/// Path of the system's sans-serif font, which covers far more characters than the
+/// fonts built into PDF.
+#[cfg(feature = "pdf")]
+fn system_font() -> Option<PathBuf> {
+    let output = std::process::Command::new("fc-match")
+        .args(["--format=%{file}", "sans-serif"])
+        .output()
+        .ok()?;
+
+    let path = PathBuf::from(String::from_utf8(output.stdout).ok()?);
+    path.is_file().then_some(path)
+}
+
+/// Writes the document out as a PDF.
+#[cfg(feature = "pdf")]
+fn write_pdf(document: &Document, path: &Path) -> Result<(), PdfiumError> {
+    let pdfium = Pdfium::new(Pdfium::bind_to_system_library()?);
+    let mut pdf = pdfium.create_new_pdf()?;
+
+    let font = match system_font() {
+        Some(font) => pdf
+            .fonts_mut()
+            .load_true_type_from_file(&font, true)
+            .unwrap_or_else(|_| pdf.fonts_mut().helvetica()),
+        None => pdf.fonts_mut().helvetica(),
+    };
+
+    for lines in layout(document) {
+        let mut page = pdf.pages_mut().create_page_at_end(PdfPagePaperSize::a4())?;
+
+        for line in lines {
+            page.objects_mut().create_text_object(
+                PdfPoints::new(line.x),
+                PdfPoints::new(PAGE.1 - line.y),
+                line.text,
+                font,
+                PdfPoints::new(line.size),
+            )?;
+        }
+    }
+
+    pdf.save_to_file(path)
+}
+
+/// Shows the print dialog and prints the document, returning whether it was printed
+/// rather than cancelled.
+#[cfg(feature = "pdf")]
+pub async fn print(document: Document) -> Result<bool, String> {
+    let portal = PrintProxy::new().await.map_err(|why| why.to_string())?;
+
+    let prepared = portal
+        .prepare_print(
+            None,
+            &document.title,
+            Settings::default(),
+            PageSetup::default(),
+            None,
+            true,
+        )
+        .await
+        .and_then(|request| request.response());
+
+    let prepared = match prepared {
+        Ok(prepared) => prepared,
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => return Ok(false),
+        Err(why) => return Err(why.to_string()),
+    };
+
+    let path = dirs::cache_dir()
+        .ok_or("there is no folder to write the document to")?
+        .join("libby")
+        .join("print.pdf");
+
+    let title = document.title.clone();
+    let written = path.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = written.parent() {
+            std::fs::create_dir_all(parent).map_err(|why| why.to_string())?;
+        }
+
+        write_pdf(&document, &written).map_err(|why| why.to_string())
+    })
+    .await
+    .map_err(|why| why.to_string())??;
+
+    let file = std::fs::File::open(&path).map_err(|why| why.to_string())?;
+
+    portal
+        .print(None, &title, &file.as_fd(), Some(prepared.token), true)
+        .await
+        .and_then(|request| request.response())
+        .map_err(|why| why.to_string())?;
+
+    Ok(true)
+}
+
+#[cfg(not(feature = "pdf"))]
+pub async fn print(_document: Document) -> Result<bool, String> {
+    Err("this build of libby can't print".to_string())
+}
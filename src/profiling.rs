@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Times updates, views, drawing and work on the worker pool, when built with the
+//! `profiling` feature. The spans go to Tracy, which finds a running libby by itself,
+//! and the figures are shown in a corner of the window as well.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How much each new timing moves the shown average.
+const SMOOTHING: f32 = 0.1;
+
+/// Span over which messages are counted.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+static STATS: Mutex<Stats> = Mutex::new(Stats {
+    view: 0.0,
+    draw: 0.0,
+    messages: VecDeque::new(),
+});
+
+/// What is timed, for [`record`].
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    /// Building the widgets of the window.
+    View,
+    /// Drawing the canvas.
+    Draw,
+}
+
+/// Timings shown in the overlay.
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    /// Average milliseconds taken to build the window's widgets.
+    pub view: f32,
+    /// Average milliseconds taken to draw the canvas.
+    pub draw: f32,
+    /// Messages handled over the last second.
+    pub messages: usize,
+}
+
+struct Stats {
+    view: f32,
+    draw: f32,
+    messages: VecDeque<Instant>,
+}
+
+impl Stats {
+    fn forget_old_messages(&mut self, now: Instant) {
+        while self
+            .messages
+            .front()
+            .is_some_and(|handled| now.duration_since(*handled) > THROUGHPUT_WINDOW)
+        {
+            self.messages.pop_front();
+        }
+    }
+}
+
+fn stats() -> MutexGuard<'static, Stats> {
+    STATS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Notes how long a phase of a frame took.
+pub fn record(phase: Phase, took: Duration) {
+    let millis = took.as_secs_f32() * 1000.0;
+    let mut stats = stats();
+
+    let average = match phase {
+        Phase::View => &mut stats.view,
+        Phase::Draw => &mut stats.draw,
+    };
+
+    *average += (millis - *average) * SMOOTHING;
+}
+
+/// Counts a message handled by the app.
+pub fn message() {
+    let now = Instant::now();
+    let mut stats = stats();
+
+    stats.messages.push_back(now);
+    stats.forget_old_messages(now);
+}
+
+pub fn summary() -> Summary {
+    let mut stats = stats();
+    stats.forget_old_messages(Instant::now());
+
+    Summary {
+        view: stats.view,
+        draw: stats.draw,
+        messages: stats.messages.len(),
+    }
+}
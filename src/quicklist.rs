@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The books read most recently, offered in the menu of the app's icon in the dock and
+//! launcher.
+//!
+//! Desktop files can't change their actions at runtime, so a copy of the installed one
+//! listing the books is written to the user's applications folder, where it takes the
+//! installed one's place.
+
+use futures_util::Stream;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+
+/// Most books offered.
+pub const RECENT_BOOKS: usize = 5;
+
+/// The installed desktop file, which the copy adds the books to.
+const DESKTOP_ENTRY: &str = include_str!("../resources/app.desktop");
+
+/// Key marking the copy as written by libby, so a file the user wrote isn't replaced.
+const MARKER: &str = "X-Libby-Recent-Books=true";
+
+/// A book offered in the menu.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recent {
+    /// Label of the menu item.
+    pub label: String,
+    pub path: PathBuf,
+}
+
+/// Where the copy of the desktop file is written.
+fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| {
+        dir.join("applications")
+            .join("com.github.codegod100.libby.desktop")
+    })
+}
+
+/// The desktop file with an action opening each book, or `None` when there are none.
+fn desktop_entry(recent: &[Recent]) -> Option<String> {
+    if recent.is_empty() {
+        return None;
+    }
+
+    let mut entry = String::new();
+
+    for line in DESKTOP_ENTRY.lines() {
+        // Actions are listed after the entry's own keys, which end at the first blank.
+        if line.trim().is_empty() {
+            break;
+        }
+
+        entry.push_str(line);
+        entry.push('\n');
+    }
+
+    let actions: Vec<String> = (0..recent.len())
+        .map(|index| format!("recent-{index}"))
+        .collect();
+    entry.push_str(&format!("Actions={};\n{MARKER}\n", actions.join(";")));
+
+    for (action, book) in actions.iter().zip(recent) {
+        entry.push_str(&format!(
+            "\n[Desktop Action {action}]\nName={}\nExec=libby {}\n",
+            escape(&book.label),
+            escape(&quote(&book.path)),
+        ));
+    }
+
+    Some(entry)
+}
+
+/// Quotes a path as an argument of `Exec`, as the desktop entry specification asks.
+fn quote(path: &Path) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Escapes a string value of a desktop file.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// Writes the desktop file offering the books, or removes it when there are none, then
+/// waits until the subscription ends. The stream never yields.
+pub fn publish(recent: Vec<Recent>) -> impl Stream<Item = Infallible> {
+    cosmic::iced::stream::channel(1, move |_output| async move {
+        let written = tokio::task::spawn_blocking(move || write(&recent)).await;
+
+        if let Ok(Err(why)) = written {
+            tracing::error!("failed to offer recent books in the dock: {why}");
+        }
+
+        futures_util::future::pending::<()>().await;
+    })
+}
+
+fn write(recent: &[Recent]) -> std::io::Result<()> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+
+    let existing = std::fs::read_to_string(&path).ok();
+
+    // A desktop file the user wrote themselves is left alone.
+    if existing
+        .as_ref()
+        .is_some_and(|existing| !existing.lines().any(|line| line == MARKER))
+    {
+        return Ok(());
+    }
+
+    match desktop_entry(recent) {
+        Some(entry) if existing.as_ref() != Some(&entry) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&path, entry)
+        }
+        Some(_) => Ok(()),
+        None if existing.is_some() => std::fs::remove_file(&path),
+        None => Ok(()),
+    }
+}
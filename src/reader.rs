@@ -0,0 +1,2604 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The reader page, displaying an open EPUB or PDF.
+
+use crate::a11y;
+use crate::app::{ContextPage, Message};
+use crate::bidi;
+use crate::comic::{self, Comic};
+use crate::dictionary::Definition;
+use crate::epub::{self, Block, Chapter, Epub};
+use crate::fl;
+use crate::library::{self, BookId, Flow, Highlight, HighlightColor, Location};
+use crate::palette::Palette;
+use crate::pdf;
+use crate::rsvp;
+use crate::search;
+use crate::shortcuts;
+use crate::speech::{self, Sentence};
+use crate::toc;
+use crate::translate;
+use crate::typography::{FontFamily, Typography};
+use crate::worker::{self, Priority};
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use cosmic::iced::font::{Family, Style, Weight};
+use cosmic::iced::widget::container;
+use cosmic::iced::widget::scrollable::{self, AbsoluteOffset, RelativeOffset, Viewport};
+use cosmic::iced::widget::text::{self, LineHeight, Span};
+use cosmic::iced::{task, Alignment, Background, Border, Color, ContentFit, Font, Length, Padding};
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::{cosmic_theme, theme};
+use futures_util::{SinkExt, Stream};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Width of the text column, so lines stay readable in wide windows.
+const MAX_WIDTH: f32 = 720.0;
+
+/// Width at which PDF pages are rendered when fitted to the window.
+const FIT_RENDER_WIDTH: u32 = 1600;
+
+/// Width at which PDF thumbnails are rendered.
+const THUMBNAIL_WIDTH: u32 = 120;
+
+/// Width of the strips along the sides of the page which turn pages when clicked.
+const TAP_ZONE_WIDTH: f32 = 48.0;
+
+/// Settings from the catalog and configuration which shape how the book is displayed.
+pub struct Appearance<'a> {
+    /// Highlights of the open book.
+    pub highlights: &'a [Highlight],
+    pub typography: &'a Typography,
+    pub palette: Palette,
+    /// Foreground and background of the reading area, unless it follows the app theme.
+    pub colors: Option<(Color, Color)>,
+    /// Whether the quick menu of palettes is open.
+    pub palette_menu: bool,
+    /// Occurrences of the text searched for in the book.
+    pub search_matches: &'a [search::Match],
+    /// The occurrence last jumped to.
+    pub current_match: Option<&'a search::Match>,
+    /// Words per minute of speed reading.
+    pub rsvp_wpm: u32,
+    /// Whether the window fills the screen for distraction-free reading.
+    pub fullscreen: bool,
+    /// Whether the toolbars are hidden, after a while without moving the mouse in
+    /// fullscreen.
+    pub controls_hidden: bool,
+}
+
+/// The book open in the reader.
+pub enum Reader {
+    Epub(EpubReader),
+    Pdf(PdfReader),
+    Comic(ComicReader),
+}
+
+impl Reader {
+    pub fn book_id(&self) -> BookId {
+        match self {
+            Reader::Epub(reader) => reader.book_id,
+            Reader::Pdf(reader) => reader.book_id,
+            Reader::Comic(reader) => reader.book_id,
+        }
+    }
+
+    /// The position to resume reading from.
+    pub fn location(&self) -> Location {
+        match self {
+            Reader::Epub(reader) => Location {
+                chapter: reader.chapter_index,
+                offset: reader.offset,
+            },
+            Reader::Pdf(reader) => Location {
+                chapter: reader.page_index,
+                offset: 0.0,
+            },
+            Reader::Comic(reader) => Location {
+                chapter: reader.page_index,
+                offset: 0.0,
+            },
+        }
+    }
+
+    /// Fraction of the whole book read so far.
+    pub fn progress(&self) -> f32 {
+        match self {
+            Reader::Epub(reader) => reader.progress(),
+            Reader::Pdf(reader) => reader.progress(),
+            Reader::Comic(reader) => reader.progress(),
+        }
+    }
+
+    /// Whether two pages are shown side by side.
+    pub fn spread(&self) -> bool {
+        match self {
+            Reader::Epub(reader) => reader.spread,
+            Reader::Pdf(reader) => reader.spread,
+            Reader::Comic(reader) => reader.spread,
+        }
+    }
+
+    /// The book's table of contents, pointing at chapters or pages.
+    pub fn toc(&self) -> &[toc::Entry] {
+        match self {
+            Reader::Epub(reader) => &reader.epub.toc,
+            Reader::Pdf(reader) => &reader.document.outline,
+            Reader::Comic(reader) => reader.toc(),
+        }
+    }
+
+    pub fn set_spread(&mut self, spread: bool) -> Task<cosmic::Action<Message>> {
+        match self {
+            Reader::Epub(reader) => reader.set_spread(spread),
+            Reader::Pdf(reader) => reader.set_spread(spread),
+            Reader::Comic(reader) => reader.set_spread(spread),
+        }
+    }
+
+    pub fn view<'a>(&'a self, appearance: &Appearance<'a>) -> Element<'a, Message> {
+        match self {
+            Reader::Epub(reader) => reader.view(appearance),
+            Reader::Pdf(reader) => reader.view(appearance),
+            Reader::Comic(reader) => reader.view(appearance),
+        }
+    }
+}
+
+/// State of an EPUB open in the reader.
+pub struct EpubReader {
+    pub book_id: BookId,
+    pub epub: Epub,
+    /// Index of the displayed chapter in the spine.
+    pub chapter_index: usize,
+    chapter: Chapter,
+    /// The chapter's images, each once it has been read from the archive.
+    images: Vec<Option<Picture>>,
+    /// Reads the chapter's images in the background, stopping once it's left.
+    images_task: Option<task::Handle>,
+    scroll_id: scrollable::Id,
+    /// The right-hand column of a two-page spread, kept one screen below the left.
+    spread_scroll_id: scrollable::Id,
+    /// Whether the chapter is shown as two columns side by side.
+    pub spread: bool,
+    pub flow: Flow,
+    /// Offset of the page being shown in paginated flow, once one has been settled on.
+    page_y: Option<f32>,
+    /// Relative scroll offset within the chapter, from 0.0 to 1.0.
+    offset: f32,
+    /// Absolute scroll offset of the left-hand column.
+    scroll_y: f32,
+    viewport_height: f32,
+    /// Whether the end of the chapter is visible.
+    at_end: bool,
+    /// First and last block selected for highlighting.
+    selection: Option<(usize, usize)>,
+    /// Note to attach to the next highlight.
+    pub note_input: String,
+    /// Set while the read-aloud controls are shown.
+    pub read_aloud: Option<ReadAloud>,
+    /// Set while speed reading.
+    pub rsvp: Option<Rsvp>,
+    /// Block whose words can be clicked to look them up.
+    look_up_block: Option<usize>,
+    /// The word shown in the dictionary popover.
+    pub look_up: Option<LookUp>,
+    /// The passage shown in the translation popover.
+    pub translation: Option<Translation>,
+    /// The note shown in the footnote popover.
+    pub footnote: Option<Footnote>,
+}
+
+/// An image of the chapter, decoded by the widget suited to it.
+#[derive(Debug, Clone)]
+pub enum Picture {
+    Bitmap(widget::image::Handle),
+    Drawing(widget::svg::Handle),
+}
+
+/// A word looked up in the dictionary.
+pub struct LookUp {
+    pub word: String,
+    /// The definition, once the lookup has finished.
+    pub result: Option<Result<Option<Definition>, String>>,
+}
+
+/// A footnote opened from its reference.
+pub struct Footnote {
+    /// Archive path and fragment the reference points to.
+    pub target: String,
+    /// Chapter holding the note, for going to it when it cannot be shown in place.
+    pub chapter: Option<usize>,
+    /// The note, once it has been read; `None` inside if its target was not found.
+    pub result: Option<Result<Option<epub::Note>, String>>,
+}
+
+/// A passage sent for translation.
+pub struct Translation {
+    pub text: String,
+    /// Language codes translated from and into.
+    pub source: String,
+    pub target: String,
+    /// Languages offered by the server, once listed.
+    pub languages: Vec<translate::Language>,
+    /// Names for the language menus; the source menu starts with automatic detection.
+    source_names: Vec<String>,
+    target_names: Vec<String>,
+    /// The translated text, once the server has replied.
+    pub result: Option<Result<String, String>>,
+    /// The pending request; replacing it cancels the request.
+    pub task: Option<task::Handle>,
+}
+
+impl Translation {
+    pub fn new(text: String, source: String, target: String) -> Self {
+        Self {
+            text,
+            source,
+            target,
+            languages: Vec::new(),
+            source_names: Vec::new(),
+            target_names: Vec::new(),
+            result: None,
+            task: None,
+        }
+    }
+
+    pub fn set_languages(&mut self, languages: Vec<translate::Language>) {
+        self.target_names = languages.iter().map(|lang| lang.name.clone()).collect();
+        self.source_names = std::iter::once(fl!("translate-detect"))
+            .chain(self.target_names.iter().cloned())
+            .collect();
+        self.languages = languages;
+    }
+
+    /// Code of a language in the source menu.
+    pub fn source_code(&self, index: usize) -> Option<&str> {
+        match index {
+            0 => Some(translate::AUTO),
+            _ => self.target_code(index - 1),
+        }
+    }
+
+    /// Code of a language in the target menu.
+    pub fn target_code(&self, index: usize) -> Option<&str> {
+        self.languages.get(index).map(|lang| lang.code.as_str())
+    }
+
+    fn position(&self, code: &str) -> Option<usize> {
+        self.languages.iter().position(|lang| lang.code == code)
+    }
+}
+
+/// State of reading the current chapter aloud.
+pub struct ReadAloud {
+    sentences: Vec<Sentence>,
+    /// Index of the sentence being read, or to resume from while paused.
+    current: usize,
+    /// Speaks the chapter while playing; dropping it stops after the current sentence.
+    task: Option<task::Handle>,
+    /// Continue reading once the next chapter has loaded.
+    pub resume: bool,
+    /// Names of the available voices, with the synthesizer's default first.
+    pub voices: Vec<String>,
+    pub options: speech::Options,
+}
+
+impl ReadAloud {
+    pub fn is_playing(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Whether every sentence of the chapter has been read.
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.sentences.len()
+    }
+
+    /// Index of the sentence being read, or to resume from while paused.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Sentences in the chapter.
+    pub fn sentence_count(&self) -> usize {
+        self.sentences.len()
+    }
+
+    fn spoken(&self, block: usize) -> Option<&Range<usize>> {
+        self.sentences
+            .get(self.current)
+            .filter(|sentence| sentence.block == block)
+            .map(|sentence| &sentence.range)
+    }
+}
+
+/// State of speed reading the current chapter.
+pub struct Rsvp {
+    words: Vec<rsvp::Word>,
+    /// Index of the word shown.
+    current: usize,
+    pub playing: bool,
+    /// Intervals left before moving on from the current word.
+    hold: u32,
+}
+
+impl Rsvp {
+    fn word(&self) -> Option<&rsvp::Word> {
+        self.words.get(self.current)
+    }
+
+    fn shown(&self, block: usize) -> Option<&Range<usize>> {
+        self.word()
+            .filter(|word| word.block == block)
+            .map(|word| &word.range)
+    }
+}
+
+impl EpubReader {
+    pub fn new(book_id: BookId, epub: Epub) -> Self {
+        Self {
+            book_id,
+            epub,
+            chapter_index: 0,
+            chapter: Chapter::default(),
+            images: Vec::new(),
+            images_task: None,
+            scroll_id: scrollable::Id::unique(),
+            spread_scroll_id: scrollable::Id::unique(),
+            spread: false,
+            flow: Flow::default(),
+            page_y: None,
+            offset: 0.0,
+            scroll_y: 0.0,
+            viewport_height: 0.0,
+            at_end: false,
+            selection: None,
+            note_input: String::new(),
+            read_aloud: None,
+            rsvp: None,
+            look_up_block: None,
+            look_up: None,
+            translation: None,
+            footnote: None,
+        }
+    }
+
+    /// Displays a newly loaded chapter, scrolled to the given relative offset. Its text
+    /// is shown at once, and its images as they are read.
+    pub fn set_chapter(
+        &mut self,
+        index: usize,
+        chapter: Chapter,
+        offset: f32,
+    ) -> Task<cosmic::Action<Message>> {
+        self.images = vec![None; chapter.images.len()];
+
+        let book_id = self.book_id;
+        let (images, handle) = Task::run(
+            chapter_images(self.epub.clone(), chapter.images.clone()),
+            move |(image, picture)| {
+                cosmic::Action::App(Message::ReaderImage(book_id, index, image, picture))
+            },
+        )
+        .abortable();
+        self.images_task = Some(handle.abort_on_drop());
+
+        self.chapter = chapter;
+        self.chapter_index = index;
+        self.offset = offset;
+        self.page_y = None;
+        self.at_end = false;
+        self.selection = None;
+        self.close_look_up();
+        self.translation = None;
+        self.footnote = None;
+
+        let stop = self.stop_speaking();
+
+        if let Some(read_aloud) = self.read_aloud.as_mut() {
+            read_aloud.sentences = speech::sentences(&self.chapter.blocks);
+            read_aloud.current = 0;
+        }
+
+        if let Some(rsvp) = self.rsvp.as_mut() {
+            rsvp.words = rsvp::words(&self.chapter.blocks);
+            rsvp.current = 0;
+            rsvp.hold = 0;
+        }
+
+        // The offset is kept relative while the images come in and lengthen the chapter.
+        Task::batch([
+            stop.chain(scrollable::snap_to(
+                self.scroll_id.clone(),
+                RelativeOffset { x: 0.0, y: offset },
+            )),
+            images,
+        ])
+    }
+
+    /// Shows an image of the chapter, once it has been read.
+    pub fn set_image(&mut self, chapter: usize, image: usize, picture: Picture) {
+        if chapter != self.chapter_index {
+            return;
+        }
+
+        if let Some(slot) = self.images.get_mut(image) {
+            *slot = Some(picture);
+        }
+    }
+
+    /// Makes the words of a block clickable for looking them up, or stops if they already
+    /// are.
+    pub fn look_up_in(&mut self, block: usize) {
+        if self.look_up_block == Some(block) {
+            self.close_look_up();
+        } else {
+            self.look_up_block = Some(block);
+        }
+    }
+
+    /// Hides the dictionary popover and makes words plain text again.
+    pub fn close_look_up(&mut self) {
+        self.look_up_block = None;
+        self.look_up = None;
+    }
+
+    /// Shows the read-aloud controls, ready to start from the top of the visible text.
+    pub fn open_read_aloud(&mut self, options: speech::Options, default_voice: String) {
+        let sentences = speech::sentences(&self.chapter.blocks);
+        let visible_block = (self.offset * self.chapter.blocks.len() as f32) as usize;
+
+        self.read_aloud = Some(ReadAloud {
+            current: sentences
+                .iter()
+                .position(|sentence| sentence.block >= visible_block)
+                .unwrap_or_default(),
+            sentences,
+            task: None,
+            resume: false,
+            voices: vec![default_voice],
+            options,
+        });
+    }
+
+    /// Hides the read-aloud controls, silencing any speech.
+    pub fn close_read_aloud(&mut self) -> Task<cosmic::Action<Message>> {
+        let stop = self.stop_speaking();
+        self.read_aloud = None;
+        stop
+    }
+
+    /// Starts reading from the current sentence.
+    pub fn play(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(read_aloud) = self.read_aloud.as_mut() else {
+            return Task::none();
+        };
+
+        if read_aloud.is_playing() {
+            return Task::none();
+        }
+
+        let sentences = read_aloud
+            .sentences
+            .iter()
+            .map(|sentence| sentence.text.clone())
+            .collect();
+
+        let (task, handle) = Task::run(
+            speech::read_aloud(sentences, read_aloud.current, read_aloud.options.clone()),
+            |result| cosmic::Action::App(Message::SpeechProgress(result)),
+        )
+        .chain(Task::done(cosmic::Action::App(Message::SpeechFinished)))
+        .abortable();
+
+        read_aloud.task = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Stops reading, leaving the current sentence to resume from.
+    pub fn stop_speaking(&mut self) -> Task<cosmic::Action<Message>> {
+        match self.read_aloud.as_mut() {
+            Some(read_aloud) if read_aloud.task.take().is_some() => {
+                Task::future(speech::stop()).discard()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Moves reading aloud to another sentence of the chapter, carrying on from there if
+    /// it was playing.
+    pub fn speak_from(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        let Some(read_aloud) = self.read_aloud.as_mut() else {
+            return Task::none();
+        };
+
+        let playing = read_aloud.is_playing();
+        read_aloud.current = index.min(read_aloud.sentences.len().saturating_sub(1));
+
+        let block = read_aloud
+            .sentences
+            .get(read_aloud.current)
+            .map(|sentence| sentence.block);
+
+        let stop = self.stop_speaking();
+        let scroll = match block {
+            Some(block) => self.scroll_to_block(block),
+            None => Task::none(),
+        };
+
+        if playing {
+            Task::batch([scroll, stop.chain(self.play())])
+        } else {
+            Task::batch([scroll, stop])
+        }
+    }
+
+    /// Moves on from a sentence which has been read, scrolling to the next one's block.
+    pub fn sentence_spoken(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        let Some(read_aloud) = self.read_aloud.as_mut() else {
+            return Task::none();
+        };
+
+        let block = read_aloud
+            .sentences
+            .get(index)
+            .map(|sentence| sentence.block);
+        read_aloud.current = index + 1;
+
+        let next_block = read_aloud
+            .sentences
+            .get(read_aloud.current)
+            .map(|sentence| sentence.block);
+
+        match next_block {
+            Some(next_block) if Some(next_block) != block => self.scroll_to_block(next_block),
+            _ => Task::none(),
+        }
+    }
+
+    /// Scrolls the block to the top of the page.
+    fn scroll_to_block(&mut self, block: usize) -> Task<cosmic::Action<Message>> {
+        if self.chapter.blocks.is_empty() {
+            return Task::none();
+        }
+
+        self.offset = block as f32 / self.chapter.blocks.len() as f32;
+        scrollable::snap_to(
+            self.scroll_id.clone(),
+            RelativeOffset {
+                x: 0.0,
+                y: self.offset,
+            },
+        )
+    }
+
+    /// Shows the speed reading controls, paused on the first word of the visible text.
+    pub fn open_rsvp(&mut self) {
+        let words = rsvp::words(&self.chapter.blocks);
+        let visible_block = (self.offset * self.chapter.blocks.len() as f32) as usize;
+
+        self.rsvp = Some(Rsvp {
+            current: words
+                .iter()
+                .position(|word| word.block >= visible_block)
+                .unwrap_or_default(),
+            words,
+            playing: false,
+            hold: 0,
+        });
+    }
+
+    /// Starts or pauses speed reading, starting over once the chapter has been read.
+    pub fn toggle_rsvp(&mut self) {
+        if let Some(rsvp) = self.rsvp.as_mut() {
+            if !rsvp.playing && rsvp.current + 1 >= rsvp.words.len() {
+                rsvp.current = 0;
+            }
+
+            rsvp.playing = !rsvp.playing;
+            rsvp.hold = 0;
+        }
+    }
+
+    /// Moves on to the next word once the current one has been shown long enough,
+    /// scrolling the page along with it.
+    pub fn rsvp_tick(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(rsvp) = self.rsvp.as_mut() else {
+            return Task::none();
+        };
+
+        if rsvp.hold > 0 {
+            rsvp.hold -= 1;
+            return Task::none();
+        }
+
+        let block = rsvp.word().map(|word| word.block);
+
+        let Some((next_block, pause)) = rsvp
+            .words
+            .get(rsvp.current + 1)
+            .map(|next| (next.block, rsvp::pause(&next.text)))
+        else {
+            rsvp.playing = false;
+            return Task::none();
+        };
+
+        rsvp.current += 1;
+        rsvp.hold = pause;
+
+        if Some(next_block) != block {
+            self.scroll_to_block(next_block)
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Steps back a few words.
+    pub fn rewind_rsvp(&mut self) {
+        if let Some(rsvp) = self.rsvp.as_mut() {
+            rsvp.current = rsvp.current.saturating_sub(rsvp::REWIND_WORDS);
+            rsvp.hold = 0;
+        }
+    }
+
+    /// Extends the selection to a clicked block, or clears it when the block is already selected.
+    pub fn select_block(&mut self, index: usize) {
+        self.selection = match self.selection {
+            Some((first, last)) if (first..=last).contains(&index) => None,
+            Some((first, last)) => Some((first.min(index), last.max(index))),
+            None => Some((index, index)),
+        };
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+        self.note_input.clear();
+    }
+
+    /// Text of the selected blocks, as paragraphs separated by blank lines.
+    pub fn selected_text(&self) -> Option<String> {
+        let (first, last) = self.selection?;
+        let blocks = self.chapter.blocks.get(first..=last)?;
+
+        Some(
+            blocks
+                .iter()
+                .map(Block::text)
+                .filter(|text| !text.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        )
+    }
+
+    /// Turns the selected blocks into a highlight, clearing the selection.
+    pub fn take_highlight(&mut self, color: HighlightColor) -> Option<Highlight> {
+        let (first, last) = self.selection?;
+        let text = self.selected_text()?;
+
+        let highlight = Highlight {
+            created: library::now(),
+            location: Location {
+                chapter: self.chapter_index,
+                offset: first as f32 / self.chapter.blocks.len() as f32,
+            },
+            blocks: (first, last),
+            color,
+            text,
+            note: self.note_input.trim().to_string(),
+        };
+
+        self.clear_selection();
+        Some(highlight)
+    }
+
+    /// Records the scroll position reported by the chapter's scrollable, bringing the
+    /// right-hand column of a spread along with it.
+    pub fn scrolled(&mut self, viewport: Viewport) -> Task<cosmic::Action<Message>> {
+        let columns = if self.spread { 2.0 } else { 1.0 };
+
+        self.offset = viewport.relative_offset().y.clamp(0.0, 1.0);
+        self.scroll_y = viewport.absolute_offset().y;
+        self.viewport_height = viewport.bounds().height;
+        self.at_end = self.scroll_y + columns * self.viewport_height
+            >= viewport.content_bounds().height - 1.0;
+
+        if self.flow == Flow::Paginated {
+            let max = viewport.content_bounds().height - self.viewport_height;
+
+            if let Some(task) = self.align_page(max) {
+                return task;
+            }
+        }
+
+        if !self.spread {
+            return Task::none();
+        }
+
+        scrollable::scroll_to(
+            self.spread_scroll_id.clone(),
+            AbsoluteOffset {
+                x: 0.0,
+                y: self.scroll_y + self.viewport_height,
+            },
+        )
+    }
+
+    /// Moves a scroll which stopped between pages on to the page in the direction it was
+    /// heading, so that the wheel turns whole pages in paginated flow. `max` is the
+    /// furthest the chapter scrolls, where its last page ends.
+    fn align_page(&mut self, max: f32) -> Option<Task<cosmic::Action<Message>>> {
+        let page = self.page_distance();
+
+        if page <= 0.0 {
+            return None;
+        }
+
+        let pages = self.scroll_y / page;
+
+        if (pages - pages.round()).abs() * page < 1.0 || self.scroll_y >= max - 1.0 {
+            self.page_y = Some(self.scroll_y);
+            return None;
+        }
+
+        // After a jump there is no direction, so keep the line at the top in view.
+        let pages = match self.page_y {
+            Some(from) if self.scroll_y > from => pages.ceil(),
+            _ => pages.floor(),
+        };
+
+        let y = (pages * page).min(max.max(0.0));
+        self.page_y = Some(y);
+
+        Some(scrollable::scroll_to(
+            self.scroll_id.clone(),
+            AbsoluteOffset { x: 0.0, y },
+        ))
+    }
+
+    /// Switches between continuous and paginated flow, settling on the page holding the
+    /// line at the top of the screen.
+    pub fn set_flow(&mut self, flow: Flow) -> Task<cosmic::Action<Message>> {
+        self.flow = flow;
+        self.page_y = None;
+
+        let page = self.page_distance();
+
+        if flow == Flow::Continuous || page <= 0.0 {
+            return Task::none();
+        }
+
+        let y = (self.scroll_y / page).floor() * page;
+        self.page_y = Some(y);
+
+        scrollable::scroll_to(self.scroll_id.clone(), AbsoluteOffset { x: 0.0, y })
+    }
+
+    /// Follows scrolling of the right-hand column of a spread with the left.
+    pub fn spread_scrolled(&self, viewport: Viewport) -> Task<cosmic::Action<Message>> {
+        let y = viewport.absolute_offset().y - viewport.bounds().height;
+
+        // The right-hand column cannot scroll past the end, so only follow it from above.
+        if (y - self.scroll_y).abs() < 1.0 || y < 0.0 {
+            return Task::none();
+        }
+
+        scrollable::scroll_to(self.scroll_id.clone(), AbsoluteOffset { x: 0.0, y })
+    }
+
+    pub fn set_spread(&mut self, spread: bool) -> Task<cosmic::Action<Message>> {
+        self.spread = spread;
+
+        if !spread {
+            return Task::none();
+        }
+
+        scrollable::scroll_to(
+            self.spread_scroll_id.clone(),
+            AbsoluteOffset {
+                x: 0.0,
+                y: self.scroll_y + self.viewport_height,
+            },
+        )
+    }
+
+    /// Fraction of the whole book read so far.
+    pub fn progress(&self) -> f32 {
+        if self.epub.chapter_count() == 0 {
+            return 0.0;
+        }
+
+        ((self.chapter_index as f32 + self.offset) / self.epub.chapter_count() as f32)
+            .clamp(0.0, 1.0)
+    }
+
+    pub fn has_next_chapter(&self) -> bool {
+        self.chapter_index + 1 < self.epub.chapter_count()
+    }
+
+    pub fn at_start(&self) -> bool {
+        self.offset <= 0.0
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.at_end
+    }
+
+    /// Jumps to the start or end of the chapter.
+    pub fn scroll_to_edge(&self, end: bool) -> Task<cosmic::Action<Message>> {
+        let y = if end { 1.0 } else { 0.0 };
+        scrollable::snap_to(self.scroll_id.clone(), RelativeOffset { x: 0.0, y })
+    }
+
+    /// Distance scrolled by turning a page: one screenful, or two in a spread.
+    fn page_distance(&self) -> f32 {
+        // Keep a line of context from the previous page.
+        let mut distance = (self.viewport_height - 32.0).max(self.viewport_height / 2.0);
+
+        if self.spread {
+            distance += self.viewport_height;
+        }
+
+        distance
+    }
+
+    /// Scrolls by one page forwards or backwards.
+    pub fn turn_page(&self, forward: bool) -> Task<cosmic::Action<Message>> {
+        let distance = self.page_distance();
+        let y = if forward { distance } else { -distance };
+
+        scrollable::scroll_by(self.scroll_id.clone(), AbsoluteOffset { x: 0.0, y })
+    }
+
+    pub fn view<'a>(&'a self, appearance: &Appearance<'a>) -> Element<'a, Message> {
+        let chapter_count = self.epub.chapter_count();
+
+        let toolbar = widget::row::with_children(bidi::ordered(vec![
+            a11y::icon_button(
+                bidi::icon("go-previous-symbolic"),
+                fl!("a11y-previous-chapter"),
+            )
+            .on_press_maybe(
+                (self.chapter_index > 0).then(|| Message::ReaderChapter(self.chapter_index - 1)),
+            )
+            .into(),
+            widget::text(fl!(
+                "reader-chapter",
+                current = self.chapter_index + 1,
+                total = chapter_count
+            ))
+            .into(),
+            a11y::icon_button(bidi::icon("go-next-symbolic"), fl!("a11y-next-chapter"))
+                .on_press_maybe(
+                    self.has_next_chapter()
+                        .then(|| Message::ReaderChapter(self.chapter_index + 1)),
+                )
+                .into(),
+            widget::horizontal_space().into(),
+            a11y::icon_button("view-list-symbolic", fl!("a11y-contents"))
+                .on_press(Message::ToggleToc)
+                .into(),
+            a11y::icon_button("system-search-symbolic", fl!("a11y-search"))
+                .on_press(Message::ToggleContextPage(ContextPage::Search))
+                .into(),
+            a11y::icon_button("bookmark-new-symbolic", fl!("a11y-bookmarks"))
+                .on_press(Message::ToggleContextPage(ContextPage::Bookmarks))
+                .into(),
+            a11y::icon_button("accessories-text-editor-symbolic", fl!("a11y-annotations"))
+                .on_press(Message::ToggleContextPage(ContextPage::Annotations))
+                .into(),
+            a11y::icon_button("audio-speakers-symbolic", fl!("a11y-read-aloud"))
+                .on_press(Message::ToggleReadAloud)
+                .into(),
+            a11y::icon_button("media-seek-forward-symbolic", fl!("a11y-speed-reading"))
+                .on_press(Message::ToggleRsvp)
+                .into(),
+            a11y::icon_button("preferences-desktop-font-symbolic", fl!("a11y-typography"))
+                .on_press(Message::ToggleContextPage(ContextPage::Typography))
+                .into(),
+            flow_toggle(self.flow),
+            spread_toggle(self.spread),
+            fullscreen_toggle(appearance.fullscreen),
+            palette_menu(appearance),
+            a11y::icon_button("window-close-symbolic", fl!("a11y-close-book"))
+                .on_press(Message::CloseReader)
+                .into(),
+        ]))
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let page: Element<Message> = if self.spread {
+            let right = widget::scrollable(self.chapter_column(appearance))
+                .id(self.spread_scroll_id.clone())
+                .on_scroll(Message::ReaderSpreadScrolled)
+                .direction(scrollable::Direction::Vertical(
+                    scrollable::Scrollbar::new().width(0).scroller_width(0),
+                ))
+                .width(Length::Fill)
+                .height(Length::Fill);
+
+            widget::row()
+                .push(
+                    widget::scrollable(self.chapter_column(appearance))
+                        .id(self.scroll_id.clone())
+                        .on_scroll(Message::ReaderScrolled)
+                        .direction(scrollable::Direction::Vertical(
+                            scrollable::Scrollbar::new().width(0).scroller_width(0),
+                        ))
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                )
+                .push(widget::divider::vertical::default())
+                .push(right)
+                .into()
+        } else {
+            // Pages are turned rather than dragged in paginated flow.
+            let scrollbar = match self.flow {
+                Flow::Continuous => scrollable::Scrollbar::default(),
+                Flow::Paginated => scrollable::Scrollbar::new().width(0).scroller_width(0),
+            };
+
+            widget::scrollable(self.chapter_column(appearance))
+                .id(self.scroll_id.clone())
+                .on_scroll(Message::ReaderScrolled)
+                .direction(scrollable::Direction::Vertical(scrollbar))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        };
+
+        let mut page: Element<Message> = widget::container(tap_zones(page))
+            .class(page_style(appearance.colors))
+            .into();
+
+        if let Some(look_up) = &self.look_up {
+            page = widget::popover(page)
+                .popup(definition_card(look_up))
+                .on_close(Message::CloseDefinition)
+                .into();
+        } else if let Some(translation) = &self.translation {
+            page = widget::popover(page)
+                .popup(translation_card(translation))
+                .on_close(Message::CloseTranslation)
+                .into();
+        } else if let Some(footnote) = &self.footnote {
+            page = widget::popover(page)
+                .popup(footnote_card(footnote))
+                .on_close(Message::CloseFootnote)
+                .into();
+        }
+
+        let pager = widget::row::with_children(bidi::ordered(vec![
+            widget::button::standard(fl!("reader-previous-page"))
+                .on_press(Message::ReaderTurnPage(false))
+                .into(),
+            widget::horizontal_space().into(),
+            bidi::progress_bar(0.0..=1.0, self.progress())
+                .width(Length::Fixed(160.0))
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::standard(fl!("reader-next-page"))
+                .on_press(Message::ReaderTurnPage(true))
+                .into(),
+        ]))
+        .align_y(Alignment::Center);
+
+        // Speed reading needs its panel, so keeps it in view.
+        if appearance.controls_hidden && self.rsvp.is_none() {
+            return widget::container(page).padding(10).into();
+        }
+
+        let mut col = widget::column().push(toolbar).push(page);
+
+        if let Some(read_aloud) = &self.read_aloud {
+            let play_icon = if read_aloud.is_playing() {
+                "media-playback-pause-symbolic"
+            } else {
+                "media-playback-start-symbolic"
+            };
+
+            let voice = read_aloud
+                .options
+                .voice
+                .as_ref()
+                .and_then(|voice| read_aloud.voices.iter().position(|name| name == voice));
+
+            col = col.push(
+                widget::row()
+                    .push(
+                        a11y::icon_button(play_icon, fl!("a11y-play-pause"))
+                            .on_press(Message::SpeechPlay),
+                    )
+                    .push(widget::horizontal_space())
+                    .push(widget::text(fl!("read-aloud-speed")))
+                    .push(
+                        widget::slider(-100..=100, read_aloud.options.rate, Message::SpeechRate)
+                            .on_release(Message::SpeechApply)
+                            .width(Length::Fixed(160.0)),
+                    )
+                    .push(widget::dropdown(
+                        &read_aloud.voices,
+                        Some(voice.unwrap_or_default()),
+                        Message::SpeechVoice,
+                    ))
+                    .push(
+                        a11y::icon_button("window-close-symbolic", fl!("a11y-close"))
+                            .on_press(Message::ToggleReadAloud),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+            );
+        }
+
+        if let Some(rsvp) = &self.rsvp {
+            col = col.push(rsvp_panel(rsvp, appearance));
+        }
+
+        if self.selection.is_some() {
+            let bar = HighlightColor::ALL.into_iter().fold(
+                widget::row()
+                    .push(
+                        widget::text_input(fl!("highlight-note-placeholder"), &self.note_input)
+                            .on_input(Message::HighlightNoteInput)
+                            .width(Length::Fill),
+                    )
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                |row, color| {
+                    row.push(
+                        widget::button::custom(swatch(color))
+                            .padding(4)
+                            .on_press(Message::AddHighlight(color)),
+                    )
+                },
+            );
+
+            col = col.push(
+                bar.push(
+                    widget::button::standard(fl!("translate"))
+                        .on_press(Message::TranslateSelection),
+                )
+                .push(widget::button::standard(fl!("copy-quote")).on_press(Message::CopyQuote))
+                .push(widget::button::text(fl!("cancel")).on_press(Message::CancelHighlight)),
+            );
+        }
+
+        col.push(pager).spacing(10).padding(10).into()
+    }
+
+    /// The chapter's blocks laid out as a centered column of text.
+    fn chapter_column<'a>(&'a self, appearance: &Appearance<'a>) -> Element<'a, Message> {
+        let typography = appearance.typography;
+        let accent = Color::from(theme::active().cosmic().accent_color());
+        let match_color = Color::from_rgba8(250, 220, 50, 0.5);
+        let current_color = Color::from_rgba8(250, 150, 30, 0.7);
+
+        let content = self
+            .chapter
+            .blocks
+            .iter()
+            .enumerate()
+            .fold(widget::column().spacing(4), |col, (index, block)| {
+                let selected = self
+                    .selection
+                    .is_some_and(|(first, last)| (first..=last).contains(&index));
+                let highlight = appearance
+                    .highlights
+                    .iter()
+                    .find(|highlight| highlight.covers(self.chapter_index, index))
+                    .map(|highlight| highlight.color);
+
+                let in_block = |found: &&search::Match| {
+                    found.location.chapter == self.chapter_index && found.block == index
+                };
+
+                let marks: Vec<(Range<usize>, Color)> = appearance
+                    .search_matches
+                    .iter()
+                    .filter(in_block)
+                    .map(|found| (found.range.clone(), match_color))
+                    .chain(
+                        appearance
+                            .current_match
+                            .filter(in_block)
+                            .map(|found| (found.range.clone(), current_color)),
+                    )
+                    .chain(
+                        self.read_aloud
+                            .as_ref()
+                            .and_then(|read_aloud| read_aloud.spoken(index))
+                            .map(|range| (range.clone(), accent.scale_alpha(0.3))),
+                    )
+                    .chain(
+                        self.rsvp
+                            .as_ref()
+                            .and_then(|rsvp| rsvp.shown(index))
+                            .map(|range| (range.clone(), accent.scale_alpha(0.3))),
+                    )
+                    .collect();
+
+                let look_up = self.look_up_block == Some(index);
+
+                let block = widget::container(self.block(block, &marks, look_up, appearance))
+                    .padding([4, 6])
+                    .width(Length::Fill)
+                    .class(block_style(highlight, selected));
+
+                col.push(
+                    widget::mouse_area(block)
+                        .on_press(Message::SelectBlock(index))
+                        .on_right_press(Message::LookUpBlock(index)),
+                )
+            })
+            .padding([24, typography.margin])
+            .max_width(MAX_WIDTH + 2.0 * f32::from(typography.margin));
+
+        widget::container(content)
+            .width(Length::Fill)
+            .align_x(Horizontal::Center)
+            .into()
+    }
+
+    fn block<'a>(
+        &'a self,
+        block: &'a Block,
+        marks: &[(Range<usize>, Color)],
+        look_up: bool,
+        appearance: &Appearance,
+    ) -> Element<'a, Message> {
+        let size = f32::from(appearance.typography.size);
+
+        match block {
+            Block::Heading(level, spans) => {
+                let scale = match level {
+                    1 => 1.75,
+                    2 => 1.5,
+                    3 => 1.3,
+                    _ => 1.1,
+                };
+
+                rich_text(spans, size * scale, true, false, marks, look_up, appearance)
+            }
+            Block::Paragraph(spans) => {
+                rich_text(spans, size, false, false, marks, look_up, appearance)
+            }
+            Block::Quote(spans) => widget::container(rich_text(
+                spans, size, false, true, marks, look_up, appearance,
+            ))
+            .padding(Padding::ZERO.left(24))
+            .into(),
+            Block::Math(formula) => {
+                let mut text = widget::text(formula).size(size).font(Font {
+                    family: Family::Serif,
+                    style: Style::Italic,
+                    ..cosmic::font::default()
+                });
+
+                if let Some((foreground, _)) = appearance.colors {
+                    text = text.class(theme::Text::Color(foreground));
+                }
+
+                widget::container(text)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .into()
+            }
+            Block::Image(index) => {
+                let picture = self.images.get(*index).and_then(Option::as_ref);
+                let image: Element<Message> = match picture {
+                    Some(Picture::Bitmap(handle)) => widget::image(handle.clone()).into(),
+                    // Drawings are shown at the size they ask for, or fill the width.
+                    Some(Picture::Drawing(handle)) => {
+                        widget::svg(handle.clone()).width(Length::Shrink).into()
+                    }
+                    None => return widget::Space::new(0, 0).into(),
+                };
+
+                widget::container(image)
+                    .width(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .into()
+            }
+            Block::Rule => widget::divider::horizontal::default().into(),
+        }
+    }
+}
+
+/// The word being speed read, large and with its pivot kept in place, above the controls.
+fn rsvp_panel<'a>(rsvp: &'a Rsvp, appearance: &Appearance) -> Element<'a, Message> {
+    let (before, pivot, after) = rsvp
+        .word()
+        .map_or(("", "", ""), |word| rsvp::split(&word.text));
+    let size = f32::from(appearance.typography.size) * 2.5;
+
+    let word = widget::row()
+        .push(
+            widget::container(widget::text(before).size(size))
+                .width(Length::Fill)
+                .align_x(Horizontal::Right),
+        )
+        .push(widget::text(pivot).size(size).class(theme::Text::Accent))
+        .push(widget::container(widget::text(after).size(size)).width(Length::Fill));
+
+    let play_icon = if rsvp.playing {
+        "media-playback-pause-symbolic"
+    } else {
+        "media-playback-start-symbolic"
+    };
+
+    let controls = widget::row()
+        .push(
+            a11y::icon_button("media-seek-backward-symbolic", fl!("a11y-rewind"))
+                .on_press(Message::RsvpRewind),
+        )
+        .push(a11y::icon_button(play_icon, fl!("a11y-play-pause")).on_press(Message::RsvpPlay))
+        .push(widget::text::caption(fl!(
+            "rsvp-progress",
+            current = (rsvp.current + 1).min(rsvp.words.len()),
+            total = rsvp.words.len()
+        )))
+        .push(widget::horizontal_space())
+        .push(widget::text(fl!("rsvp-wpm", wpm = appearance.rsvp_wpm)))
+        .push(
+            widget::slider(
+                rsvp::MIN_WPM..=rsvp::MAX_WPM,
+                appearance.rsvp_wpm,
+                Message::RsvpWpm,
+            )
+            .step(25u32)
+            .on_release(Message::SaveSettings)
+            .width(Length::Fixed(160.0)),
+        )
+        .push(
+            a11y::icon_button("window-close-symbolic", fl!("a11y-close"))
+                .on_press(Message::ToggleRsvp),
+        )
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+    widget::column()
+        .push(widget::container(word).padding([16, 0]))
+        .push(controls)
+        .spacing(10)
+        .into()
+}
+
+/// Background of the reading area in the chosen palette.
+fn page_style<'a>(colors: Option<(Color, Color)>) -> theme::Container<'a> {
+    match colors {
+        Some((_, background)) => theme::Container::custom(move |_| container::Style {
+            background: Some(Background::Color(background)),
+            ..container::Style::default()
+        }),
+        None => theme::Container::default(),
+    }
+}
+
+/// Surrounds the page with strips which trigger the tap shortcuts when clicked.
+fn tap_zones(page: Element<'_, Message>) -> Element<'_, Message> {
+    let zone = |gesture| {
+        widget::mouse_area(widget::Space::new(
+            Length::Fixed(TAP_ZONE_WIDTH),
+            Length::Fill,
+        ))
+        .on_press(Message::ReaderGesture(gesture))
+    };
+
+    widget::row()
+        .push(zone(shortcuts::TAP_LEFT))
+        .push(page)
+        .push(zone(shortcuts::TAP_RIGHT))
+        .into()
+}
+
+/// Toolbar button switching between single pages and two-page spreads.
+fn spread_toggle<'a>(spread: bool) -> Element<'a, Message> {
+    let (icon_name, name) = if spread {
+        ("view-paged-symbolic", fl!("a11y-single-pages"))
+    } else {
+        ("view-dual-symbolic", fl!("a11y-spreads"))
+    };
+
+    a11y::icon_button(icon_name, name)
+        .on_press(Message::ToggleSpread)
+        .into()
+}
+
+/// Toolbar button switching between continuous scrolling and turning pages.
+fn flow_toggle<'a>(flow: Flow) -> Element<'a, Message> {
+    let (icon_name, next, name) = match flow {
+        Flow::Continuous => (
+            "x-office-presentation-symbolic",
+            Flow::Paginated,
+            fl!("a11y-paginated"),
+        ),
+        Flow::Paginated => (
+            "view-continuous-symbolic",
+            Flow::Continuous,
+            fl!("a11y-continuous"),
+        ),
+    };
+
+    a11y::icon_button(icon_name, name)
+        .on_press(Message::SetFlow(next))
+        .into()
+}
+
+/// Toolbar button entering and leaving fullscreen.
+fn fullscreen_toggle<'a>(fullscreen: bool) -> Element<'a, Message> {
+    let (icon_name, name) = if fullscreen {
+        ("view-restore-symbolic", fl!("a11y-leave-fullscreen"))
+    } else {
+        ("view-fullscreen-symbolic", fl!("a11y-fullscreen"))
+    };
+
+    a11y::icon_button(icon_name, name)
+        .on_press(Message::ToggleFullscreen)
+        .into()
+}
+
+/// Toolbar button opening a quick menu of reading palettes.
+fn palette_menu<'a>(appearance: &Appearance) -> Element<'a, Message> {
+    let button = a11y::icon_button("applications-graphics-symbolic", fl!("a11y-palettes"))
+        .on_press(Message::TogglePaletteMenu);
+
+    if !appearance.palette_menu {
+        return button.into();
+    }
+
+    let menu = Palette::ALL
+        .into_iter()
+        .fold(widget::column(), |col, palette| {
+            let label = match palette {
+                Palette::App => fl!("palette-app"),
+                Palette::Light => fl!("palette-light"),
+                Palette::Sepia => fl!("palette-sepia"),
+                Palette::Dark => fl!("palette-dark"),
+                Palette::Black => fl!("palette-black"),
+                Palette::Custom => fl!("palette-custom"),
+            };
+
+            col.push(
+                widget::button::text(label)
+                    .selected(palette == appearance.palette)
+                    .on_press(Message::SetPalette(palette))
+                    .width(Length::Fill),
+            )
+        });
+
+    let menu = menu.push(
+        widget::button::text(fl!("palette-customize"))
+            .on_press(Message::ToggleContextPage(ContextPage::Typography))
+            .width(Length::Fill),
+    );
+
+    widget::popover(button)
+        .popup(
+            widget::container(menu)
+                .padding(4)
+                .width(Length::Fixed(200.0))
+                .class(theme::Container::Dropdown),
+        )
+        .on_close(Message::TogglePaletteMenu)
+        .into()
+}
+
+/// Background of a chapter block, tinted when selected or highlighted.
+fn block_style<'a>(highlight: Option<HighlightColor>, selected: bool) -> theme::Container<'a> {
+    theme::Container::custom(move |theme| {
+        let background = if selected {
+            Some(Color::from(theme.cosmic().accent_color()).scale_alpha(0.3))
+        } else {
+            highlight.map(highlight_color)
+        };
+
+        container::Style {
+            background: background.map(Background::Color),
+            border: Border {
+                radius: 4.0.into(),
+                ..Border::default()
+            },
+            ..container::Style::default()
+        }
+    })
+}
+
+/// A small square of a highlight's color.
+pub fn swatch<'a>(color: HighlightColor) -> Element<'a, Message> {
+    widget::container(widget::Space::new(20, 20))
+        .class(block_style(Some(color), false))
+        .into()
+}
+
+/// Translucent tint drawn behind highlighted text.
+fn highlight_color(color: HighlightColor) -> Color {
+    match color {
+        HighlightColor::Yellow => Color::from_rgba8(250, 220, 50, 0.4),
+        HighlightColor::Green => Color::from_rgba8(90, 200, 90, 0.4),
+        HighlightColor::Blue => Color::from_rgba8(80, 160, 240, 0.4),
+        HighlightColor::Pink => Color::from_rgba8(240, 110, 170, 0.4),
+    }
+}
+
+/// Renders spans of a block with their emphasis, on top of the block's own, tinting the
+/// byte ranges in `marks` such as the sentence being read aloud or search matches. Where
+/// marks overlap, the later one wins. Footnote references link to their notes, and with
+/// `look_up` set, each other word links to its dictionary definition.
+fn rich_text<'a>(
+    spans: &'a [epub::Span],
+    size: f32,
+    bold: bool,
+    italic: bool,
+    marks: &[(Range<usize>, Color)],
+    look_up: bool,
+    appearance: &Appearance,
+) -> Element<'a, Message> {
+    let typography = appearance.typography;
+    let family = match typography.font {
+        FontFamily::Serif => Family::Serif,
+        FontFamily::SansSerif => Family::SansSerif,
+        FontFamily::Monospace => Family::Monospace,
+    };
+    let line_height = LineHeight::Relative(f32::from(typography.line_height) / 100.0);
+
+    let mut pieces: Vec<Span<'_, Message, Font>> = Vec::new();
+    let mut start = 0;
+
+    for span in spans {
+        let font = Font {
+            weight: if bold || span.bold {
+                Weight::Bold
+            } else {
+                Weight::Normal
+            },
+            style: if italic || span.italic {
+                Style::Italic
+            } else {
+                Style::Normal
+            },
+            family,
+            ..cosmic::font::default()
+        };
+
+        let end = start + span.text.len();
+
+        // Split the span wherever a mark starts or ends within it.
+        let mut cuts: Vec<usize> = marks
+            .iter()
+            .flat_map(|(range, _)| [range.start, range.end])
+            .filter(|&cut| cut > start && cut < end && span.text.is_char_boundary(cut - start))
+            .chain([start, end])
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        for cut in cuts.windows(2) {
+            let (from, to) = (cut[0], cut[1]);
+            let background = marks
+                .iter()
+                .rev()
+                .find(|(range, _)| range.start <= from && range.end >= to)
+                .map(|(_, color)| *color);
+
+            let text = &span.text[from - start..to - start];
+            let runs = if look_up && span.note.is_none() {
+                words(text)
+            } else {
+                vec![(text, false)]
+            };
+
+            for (text, is_word) in runs {
+                if text.is_empty() {
+                    continue;
+                }
+
+                let mut piece = Span::new(text)
+                    .font(font)
+                    .size(size)
+                    .line_height(line_height);
+
+                if let Some((foreground, _)) = appearance.colors {
+                    piece = piece.color(foreground);
+                }
+
+                if let Some(background) = background {
+                    piece = piece.background(background);
+                }
+
+                if let Some(note) = &span.note {
+                    piece = piece
+                        .color(Color::from(theme::active().cosmic().accent_color()))
+                        .link(Message::OpenFootnote(note.clone()));
+                } else if is_word {
+                    piece = piece
+                        .underline(true)
+                        .link(Message::LookUp(text.to_string()));
+                }
+
+                pieces.push(piece);
+            }
+        }
+
+        start = end;
+    }
+
+    let alignment = if typography.justify {
+        text::Alignment::Justified
+    } else {
+        text::Alignment::Left
+    };
+
+    cosmic::iced::widget::rich_text(pieces)
+        .on_link_click(std::convert::identity)
+        .align_x(alignment)
+        .into()
+}
+
+/// Splits text into alternating runs of words and the characters between them, flagging
+/// the words.
+fn words(text: &str) -> Vec<(&str, bool)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '\'' || c == '’' || c == '-';
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut in_word = false;
+
+    for (index, c) in text.char_indices() {
+        if is_word_char(c) != in_word {
+            runs.push((&text[start..index], in_word));
+            start = index;
+            in_word = !in_word;
+        }
+    }
+
+    runs.push((&text[start..], in_word));
+    runs
+}
+
+/// Popover content showing the definition of a looked-up word.
+fn definition_card(look_up: &LookUp) -> Element<'_, Message> {
+    let mut content = widget::column().spacing(8);
+    let mut copyable = false;
+
+    match &look_up.result {
+        Some(Ok(Some(definition))) => {
+            let mut heading = widget::row()
+                .push(widget::text::title4(&definition.word))
+                .spacing(10)
+                .align_y(Alignment::Center);
+
+            if let Some(pronunciation) = &definition.pronunciation {
+                heading = heading.push(widget::text::caption(format!("/{pronunciation}/")));
+            }
+
+            content = definition
+                .lines
+                .iter()
+                .fold(content.push(heading), |col, line| {
+                    col.push(widget::text(line))
+                })
+                .push(widget::text::caption(fl!(
+                    "dictionary-source",
+                    source = definition.source.as_str()
+                )));
+            copyable = true;
+        }
+        Some(Ok(None)) => {
+            content = content
+                .push(widget::text::title4(&look_up.word))
+                .push(widget::text(fl!("dictionary-not-found")));
+        }
+        Some(Err(why)) => {
+            content = content
+                .push(widget::text::title4(&look_up.word))
+                .push(widget::text(fl!(
+                    "dictionary-failed",
+                    reason = why.as_str()
+                )));
+        }
+        None => {
+            content = content
+                .push(widget::text::title4(&look_up.word))
+                .push(widget::text(fl!("dictionary-looking-up")));
+        }
+    }
+
+    let actions = widget::row()
+        .push(widget::horizontal_space())
+        .push(
+            widget::button::standard(fl!("dictionary-copy"))
+                .on_press_maybe(copyable.then_some(Message::CopyDefinition)),
+        )
+        .push(widget::button::text(fl!("close")).on_press(Message::CloseDefinition))
+        .spacing(10);
+
+    widget::container(
+        widget::column()
+            .push(widget::scrollable(content).height(Length::Shrink))
+            .push(actions)
+            .spacing(12),
+    )
+    .padding(16)
+    .width(Length::Fixed(380.0))
+    .max_height(400.0)
+    .class(theme::Container::Dropdown)
+    .into()
+}
+
+/// Popover content showing a footnote in place of its reference.
+fn footnote_card(footnote: &Footnote) -> Element<'_, Message> {
+    let content = match &footnote.result {
+        Some(Ok(Some(note))) => {
+            note.blocks
+                .iter()
+                .fold(widget::column().spacing(8), |col, block| match block {
+                    Block::Heading(_, _) => col.push(widget::text::title4(block.text())),
+                    Block::Rule => col.push(widget::divider::horizontal::default()),
+                    _ => col.push(widget::text(block.text())),
+                })
+        }
+        Some(Ok(None)) => widget::column().push(widget::text(fl!("footnote-not-found"))),
+        Some(Err(why)) => {
+            widget::column().push(widget::text(fl!("footnote-failed", reason = why.as_str())))
+        }
+        None => widget::column().push(widget::text(fl!("footnote-loading"))),
+    };
+
+    let actions = widget::row()
+        .push(widget::horizontal_space())
+        .push(
+            widget::button::standard(fl!("footnote-go-to"))
+                .on_press_maybe(footnote.chapter.map(|_| Message::GoToFootnote)),
+        )
+        .push(widget::button::text(fl!("close")).on_press(Message::CloseFootnote))
+        .spacing(10);
+
+    widget::container(
+        widget::column()
+            .push(widget::scrollable(content).height(Length::Shrink))
+            .push(actions)
+            .spacing(12),
+    )
+    .padding(16)
+    .width(Length::Fixed(380.0))
+    .max_height(400.0)
+    .class(theme::Container::Dropdown)
+    .into()
+}
+
+/// Popover content showing the translation of the selected passage.
+fn translation_card(translation: &Translation) -> Element<'_, Message> {
+    let mut content = widget::column()
+        .push(widget::text::title4(fl!("translation")))
+        .spacing(8);
+
+    if !translation.languages.is_empty() {
+        let source = match translation.source.as_str() {
+            translate::AUTO => Some(0),
+            code => translation.position(code).map(|index| index + 1),
+        };
+
+        content = content.push(
+            widget::row()
+                .push(widget::dropdown(
+                    &translation.source_names,
+                    source,
+                    Message::TranslateSource,
+                ))
+                .push(widget::text("→"))
+                .push(widget::dropdown(
+                    &translation.target_names,
+                    translation.position(&translation.target),
+                    Message::TranslateTarget,
+                ))
+                .spacing(8)
+                .align_y(Alignment::Center),
+        );
+    }
+
+    content = content.push(widget::text::caption(&translation.text));
+
+    let mut copyable = false;
+
+    content = match &translation.result {
+        Some(Ok(text)) => {
+            copyable = true;
+            content.push(widget::text(text))
+        }
+        Some(Err(why)) => {
+            content.push(widget::text(fl!("translate-failed", reason = why.as_str())))
+        }
+        None => content.push(widget::text(fl!("translating"))),
+    };
+
+    let actions = widget::row()
+        .push(widget::horizontal_space())
+        .push(
+            widget::button::standard(fl!("dictionary-copy"))
+                .on_press_maybe(copyable.then_some(Message::CopyTranslation)),
+        )
+        .push(widget::button::text(fl!("close")).on_press(Message::CloseTranslation))
+        .spacing(10);
+
+    widget::container(
+        widget::column()
+            .push(widget::scrollable(content).height(Length::Shrink))
+            .push(actions)
+            .spacing(12),
+    )
+    .padding(16)
+    .width(Length::Fixed(420.0))
+    .max_height(480.0)
+    .class(theme::Container::Dropdown)
+    .into()
+}
+
+/// How a PDF page is scaled to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zoom {
+    FitWidth,
+    FitPage,
+    /// A fixed scale, as a percentage of the page's printed size.
+    Percent(u16),
+}
+
+impl Zoom {
+    const STEPS: [u16; 9] = [25, 50, 75, 100, 125, 150, 200, 300, 400];
+
+    /// The next zoom step in or out from the current scale.
+    fn step(self, zoom_in: bool) -> Zoom {
+        let current = match self {
+            Zoom::Percent(percent) => percent,
+            Zoom::FitWidth | Zoom::FitPage => 100,
+        };
+
+        let next = if zoom_in {
+            Self::STEPS.into_iter().find(|&step| step > current)
+        } else {
+            Self::STEPS.into_iter().rev().find(|&step| step < current)
+        };
+
+        Zoom::Percent(next.unwrap_or(current))
+    }
+}
+
+/// State of a PDF open in the reader.
+pub struct PdfReader {
+    pub book_id: BookId,
+    pub path: PathBuf,
+    document: pdf::Document,
+    /// Index of the displayed page.
+    pub page_index: usize,
+    page: Option<widget::image::Handle>,
+    /// The page to the right of the displayed one in a two-page spread.
+    second_page: Option<widget::image::Handle>,
+    thumbnails: Vec<Option<widget::image::Handle>>,
+    pub zoom: Zoom,
+    /// Whether pages are shown in pairs, with the cover on its own.
+    pub spread: bool,
+    /// Stops thumbnail rendering once the reader is closed.
+    thumbnails_task: Option<task::Handle>,
+}
+
+impl PdfReader {
+    pub fn new(book_id: BookId, path: PathBuf, document: pdf::Document, page_index: usize) -> Self {
+        let page_count = document.page_count();
+
+        Self {
+            book_id,
+            path,
+            document,
+            page_index: page_index.min(page_count.saturating_sub(1)),
+            page: None,
+            second_page: None,
+            thumbnails: vec![None; page_count],
+            zoom: Zoom::FitWidth,
+            spread: false,
+            thumbnails_task: None,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.document.page_count()
+    }
+
+    /// Starts rendering thumbnails of every page in the background.
+    /// Whether the thumbnails have been rendered, or are being.
+    pub fn has_thumbnails(&self) -> bool {
+        self.thumbnails_task.is_some()
+    }
+
+    pub fn render_thumbnails(&mut self) -> Task<cosmic::Action<Message>> {
+        let book_id = self.book_id;
+        let (task, handle) = Task::run(thumbnails(self.path.clone()), move |(index, thumbnail)| {
+            cosmic::Action::App(Message::PdfThumbnail(book_id, index, thumbnail))
+        })
+        .abortable();
+
+        self.thumbnails_task = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Renders the displayed pages at the resolution their zoom requires.
+    pub fn render_page(&self) -> Task<cosmic::Action<Message>> {
+        let book_id = self.book_id;
+        let width = self.render_width();
+
+        let pages = if self.shows_second_page() {
+            self.page_index..self.page_index + 2
+        } else {
+            self.page_index..self.page_index + 1
+        };
+
+        Task::batch(pages.map(|index| {
+            Task::perform(
+                render_page(self.path.clone(), index, width),
+                move |result| cosmic::Action::App(Message::PdfPageRendered(book_id, index, result)),
+            )
+        }))
+    }
+
+    pub fn set_page(&mut self, index: usize, page: widget::image::Handle) {
+        if index == self.page_index {
+            self.page = Some(page);
+        } else if index == self.page_index + 1 && self.shows_second_page() {
+            self.second_page = Some(page);
+        }
+    }
+
+    /// Whether a second page is shown beside the displayed one.
+    fn shows_second_page(&self) -> bool {
+        self.spread && self.page_index > 0 && self.page_index + 1 < self.page_count()
+    }
+
+    /// First page of the spread containing `index`, keeping the cover on its own.
+    fn spread_start(&self, index: usize) -> usize {
+        if self.spread && index > 0 && index % 2 == 0 {
+            index - 1
+        } else {
+            index
+        }
+    }
+
+    /// The page to show after turning forwards or backwards, if there is one.
+    pub fn turned_page(&self, forward: bool) -> Option<usize> {
+        if forward {
+            let step = if self.shows_second_page() { 2 } else { 1 };
+            Some(self.page_index + step).filter(|&index| index < self.page_count())
+        } else {
+            let index = self.page_index.checked_sub(1)?;
+            Some(self.spread_start(index))
+        }
+    }
+
+    pub fn set_spread(&mut self, spread: bool) -> Task<cosmic::Action<Message>> {
+        self.spread = spread;
+        self.page_index = self.spread_start(self.page_index);
+        self.second_page = None;
+        self.render_page()
+    }
+
+    pub fn set_thumbnail(&mut self, index: usize, thumbnail: widget::image::Handle) {
+        if let Some(slot) = self.thumbnails.get_mut(index) {
+            *slot = Some(thumbnail);
+        }
+    }
+
+    /// Switches to another page, keeping the old one visible until the new one renders.
+    pub fn go_to(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        let index = self.spread_start(index);
+
+        if index >= self.page_count() || index == self.page_index {
+            return Task::none();
+        }
+
+        self.page_index = index;
+        self.render_page()
+    }
+
+    pub fn set_zoom(&mut self, zoom: Zoom) -> Task<cosmic::Action<Message>> {
+        let width = self.render_width();
+        self.zoom = zoom;
+
+        if self.render_width() == width {
+            Task::none()
+        } else {
+            self.render_page()
+        }
+    }
+
+    /// Width of the current page at the current zoom, in logical pixels.
+    fn display_width(&self) -> Option<f32> {
+        let Zoom::Percent(percent) = self.zoom else {
+            return None;
+        };
+
+        let (width, _) = self.document.page_sizes.get(self.page_index)?;
+
+        // Points are 1/72 inch and logical pixels 1/96 inch.
+        Some(width * 96.0 / 72.0 * f32::from(percent) / 100.0)
+    }
+
+    /// Width in pixels to render the current page at, doubled for HiDPI displays.
+    fn render_width(&self) -> u32 {
+        match self.display_width() {
+            Some(width) => ((width * 2.0) as u32).clamp(200, 6000),
+            None => FIT_RENDER_WIDTH,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.page_count() == 0 {
+            return 0.0;
+        }
+
+        (self.page_index + 1) as f32 / self.page_count() as f32
+    }
+
+    pub fn view<'a>(&'a self, appearance: &Appearance) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let zoom_label = match self.zoom {
+            Zoom::FitWidth => fl!("zoom-fit-width"),
+            Zoom::FitPage => fl!("zoom-fit-page"),
+            Zoom::Percent(percent) => format!("{percent}%"),
+        };
+
+        let position = if self.shows_second_page() {
+            fl!(
+                "reader-pages",
+                first = self.page_index + 1,
+                second = self.page_index + 2,
+                total = self.page_count()
+            )
+        } else {
+            fl!(
+                "reader-page",
+                current = self.page_index + 1,
+                total = self.page_count()
+            )
+        };
+
+        let toolbar = widget::row::with_children(bidi::ordered(vec![
+            a11y::icon_button(
+                bidi::icon("go-previous-symbolic"),
+                fl!("a11y-previous-page"),
+            )
+            .on_press_maybe(
+                self.turned_page(false)
+                    .map(|_| Message::ReaderTurnPage(false)),
+            )
+            .into(),
+            widget::text(position).into(),
+            a11y::icon_button(bidi::icon("go-next-symbolic"), fl!("a11y-next-page"))
+                .on_press_maybe(
+                    self.turned_page(true)
+                        .map(|_| Message::ReaderTurnPage(true)),
+                )
+                .into(),
+            widget::horizontal_space().into(),
+            a11y::icon_button("zoom-out-symbolic", fl!("a11y-zoom-out"))
+                .on_press(Message::PdfZoom(self.zoom.step(false)))
+                .into(),
+            widget::text(zoom_label).into(),
+            a11y::icon_button("zoom-in-symbolic", fl!("a11y-zoom-in"))
+                .on_press(Message::PdfZoom(self.zoom.step(true)))
+                .into(),
+            widget::button::text(fl!("zoom-fit-width"))
+                .on_press(Message::PdfZoom(Zoom::FitWidth))
+                .into(),
+            widget::button::text(fl!("zoom-fit-page"))
+                .on_press(Message::PdfZoom(Zoom::FitPage))
+                .into(),
+            widget::horizontal_space().into(),
+            a11y::icon_button("view-list-symbolic", fl!("a11y-contents"))
+                .on_press(Message::ToggleToc)
+                .into(),
+            a11y::icon_button("system-search-symbolic", fl!("a11y-search"))
+                .on_press(Message::ToggleContextPage(ContextPage::Search))
+                .into(),
+            a11y::icon_button("bookmark-new-symbolic", fl!("a11y-bookmarks"))
+                .on_press(Message::ToggleContextPage(ContextPage::Bookmarks))
+                .into(),
+            spread_toggle(self.spread),
+            fullscreen_toggle(appearance.fullscreen),
+            palette_menu(appearance),
+            a11y::icon_button("window-close-symbolic", fl!("a11y-close-book"))
+                .on_press(Message::CloseReader)
+                .into(),
+        ]))
+        .spacing(space_xxs)
+        .align_y(Alignment::Center);
+
+        let strip = self.thumbnails.iter().enumerate().fold(
+            widget::column().spacing(space_xxs).padding(space_xxs),
+            |col, (index, thumbnail)| {
+                let preview: Element<Message> = match thumbnail {
+                    Some(handle) => widget::image(handle.clone())
+                        .width(Length::Fixed(THUMBNAIL_WIDTH as f32))
+                        .into(),
+                    None => widget::Space::new(
+                        Length::Fixed(THUMBNAIL_WIDTH as f32),
+                        Length::Fixed(THUMBNAIL_WIDTH as f32 * 1.4),
+                    )
+                    .into(),
+                };
+
+                col.push(
+                    widget::button::custom(
+                        widget::column()
+                            .push(preview)
+                            .push(widget::text::caption((index + 1).to_string()))
+                            .align_x(Alignment::Center),
+                    )
+                    .selected(
+                        index == self.page_index
+                            || (index == self.page_index + 1 && self.shows_second_page()),
+                    )
+                    .on_press(Message::PdfPage(index)),
+                )
+            },
+        );
+
+        let strip = widget::scrollable(strip).width(Length::Shrink);
+
+        let second_page = self
+            .second_page
+            .as_ref()
+            .filter(|_| self.shows_second_page());
+
+        let pages = [self.page.as_ref(), second_page]
+            .into_iter()
+            .flatten()
+            .fold(widget::row().spacing(space_xxs), |row, handle| {
+                let image = widget::image(handle.clone());
+
+                row.push(match self.zoom {
+                    Zoom::FitPage => image
+                        .content_fit(ContentFit::Contain)
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                    Zoom::FitWidth => image.width(Length::Fill),
+                    Zoom::Percent(_) => {
+                        image.width(Length::Fixed(self.display_width().unwrap_or_default()))
+                    }
+                })
+            });
+
+        let page: Element<Message> = match (&self.page, self.zoom) {
+            (None, _) => widget::Space::new(Length::Fill, Length::Fill).into(),
+            (Some(_), Zoom::FitPage) => pages.width(Length::Fill).height(Length::Fill).into(),
+            (Some(_), Zoom::FitWidth) => widget::scrollable(pages)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            (Some(_), Zoom::Percent(_)) => widget::scrollable(pages)
+                .direction(scrollable::Direction::Both {
+                    vertical: scrollable::Scrollbar::default(),
+                    horizontal: scrollable::Scrollbar::default(),
+                })
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+        };
+
+        let page = widget::container(page)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center);
+
+        let page = widget::container(tap_zones(page.into())).class(page_style(appearance.colors));
+
+        if appearance.controls_hidden {
+            return widget::container(page).padding(10).into();
+        }
+
+        widget::column()
+            .push(toolbar)
+            .push(widget::row().push(strip).push(page).spacing(space_xxs))
+            .spacing(10)
+            .padding(10)
+            .into()
+    }
+}
+
+/// State of a comic open in the reader.
+pub struct ComicReader {
+    pub book_id: BookId,
+    comic: Comic,
+    /// Index of the displayed page.
+    pub page_index: usize,
+    page: Option<widget::image::Handle>,
+    /// Width of the displayed page in pixels, for zooming to a percentage of it.
+    page_width: u32,
+    /// The page beside the displayed one in a two-page spread.
+    second_page: Option<widget::image::Handle>,
+    /// Panels of the displayed page in reading order, while reading panel by panel.
+    panels: Vec<widget::image::Handle>,
+    panel_index: usize,
+    /// Start on the last panel of the page being loaded, after turning back onto it.
+    from_last_panel: bool,
+    pub zoom: Zoom,
+    /// Whether pages are shown in pairs, with the cover on its own.
+    pub spread: bool,
+    /// Whether pages and panels are read from right to left, as in manga.
+    pub right_to_left: bool,
+    /// Whether the page is shown one panel at a time.
+    pub panel_mode: bool,
+}
+
+impl ComicReader {
+    pub fn new(book_id: BookId, comic: Comic, page_index: usize, right_to_left: bool) -> Self {
+        let page_count = comic.page_count();
+
+        Self {
+            book_id,
+            comic,
+            page_index: page_index.min(page_count.saturating_sub(1)),
+            page: None,
+            page_width: 0,
+            second_page: None,
+            panels: Vec::new(),
+            panel_index: 0,
+            from_last_panel: false,
+            zoom: Zoom::FitPage,
+            spread: false,
+            right_to_left,
+            panel_mode: false,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.comic.page_count()
+    }
+
+    pub fn toc(&self) -> &[toc::Entry] {
+        self.comic.toc()
+    }
+
+    /// Decodes the displayed pages, cut into panels when reading panel by panel.
+    pub fn load_page(&self) -> Task<cosmic::Action<Message>> {
+        let book_id = self.book_id;
+
+        let pages = if self.shows_second_page() {
+            self.page_index..self.page_index + 2
+        } else {
+            self.page_index..self.page_index + 1
+        };
+
+        Task::batch(pages.map(|index| {
+            Task::perform(
+                load_comic_page(
+                    self.comic.clone(),
+                    index,
+                    self.panel_mode,
+                    self.right_to_left,
+                ),
+                move |result| cosmic::Action::App(Message::ComicPageLoaded(book_id, index, result)),
+            )
+        }))
+    }
+
+    pub fn set_page(&mut self, index: usize, page: comic::Page) {
+        if index == self.page_index {
+            self.page_width = page.image.width;
+            self.page = Some(image_handle(page.image));
+            self.panels = page.panels.into_iter().map(image_handle).collect();
+            self.panel_index = if self.from_last_panel {
+                self.panels.len().saturating_sub(1)
+            } else {
+                0
+            };
+            self.from_last_panel = false;
+        } else if index == self.page_index + 1 && self.shows_second_page() {
+            self.second_page = Some(image_handle(page.image));
+        }
+    }
+
+    /// Whether a second page is shown beside the displayed one.
+    fn shows_second_page(&self) -> bool {
+        self.spread
+            && !self.panel_mode
+            && self.page_index > 0
+            && self.page_index + 1 < self.page_count()
+    }
+
+    /// First page of the spread containing `index`, keeping the cover on its own.
+    fn spread_start(&self, index: usize) -> usize {
+        if self.spread && !self.panel_mode && index > 0 && index % 2 == 0 {
+            index - 1
+        } else {
+            index
+        }
+    }
+
+    /// The page to show after turning forwards or backwards, if there is one.
+    pub fn turned_page(&self, forward: bool) -> Option<usize> {
+        if forward {
+            let step = if self.shows_second_page() { 2 } else { 1 };
+            Some(self.page_index + step).filter(|&index| index < self.page_count())
+        } else {
+            let index = self.page_index.checked_sub(1)?;
+            Some(self.spread_start(index))
+        }
+    }
+
+    /// Whether there is a panel or page to turn to.
+    fn can_turn(&self, forward: bool) -> bool {
+        let within_page = if forward {
+            self.panel_index + 1 < self.panels.len()
+        } else {
+            self.panel_index > 0
+        };
+
+        within_page || self.turned_page(forward).is_some()
+    }
+
+    /// Moves to the next or previous panel while reading panel by panel, and otherwise
+    /// to the next or previous page.
+    pub fn turn(&mut self, forward: bool) -> Task<cosmic::Action<Message>> {
+        if self.panel_mode {
+            if forward && self.panel_index + 1 < self.panels.len() {
+                self.panel_index += 1;
+                return Task::none();
+            }
+
+            if !forward && self.panel_index > 0 {
+                self.panel_index -= 1;
+                return Task::none();
+            }
+        }
+
+        match self.turned_page(forward) {
+            Some(index) => {
+                self.from_last_panel = !forward;
+                self.go_to(index)
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Switches to another page, keeping the old one visible until the new one loads.
+    pub fn go_to(&mut self, index: usize) -> Task<cosmic::Action<Message>> {
+        let index = self.spread_start(index);
+
+        if index >= self.page_count() || index == self.page_index {
+            return Task::none();
+        }
+
+        self.page_index = index;
+        self.panels.clear();
+        self.load_page()
+    }
+
+    pub fn set_spread(&mut self, spread: bool) -> Task<cosmic::Action<Message>> {
+        self.spread = spread;
+        self.page_index = self.spread_start(self.page_index);
+        self.second_page = None;
+        self.load_page()
+    }
+
+    pub fn set_right_to_left(&mut self, right_to_left: bool) -> Task<cosmic::Action<Message>> {
+        self.right_to_left = right_to_left;
+
+        // Panels are cut in reading order, which has just changed.
+        if self.panel_mode {
+            self.load_page()
+        } else {
+            Task::none()
+        }
+    }
+
+    pub fn toggle_panels(&mut self) -> Task<cosmic::Action<Message>> {
+        self.panel_mode = !self.panel_mode;
+        self.panels.clear();
+        self.page_index = self.spread_start(self.page_index);
+        self.load_page()
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.page_count() == 0 {
+            return 0.0;
+        }
+
+        (self.page_index + 1) as f32 / self.page_count() as f32
+    }
+
+    pub fn view<'a>(&'a self, appearance: &Appearance) -> Element<'a, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let zoom_label = match self.zoom {
+            Zoom::FitWidth => fl!("zoom-fit-width"),
+            Zoom::FitPage => fl!("zoom-fit-page"),
+            Zoom::Percent(percent) => format!("{percent}%"),
+        };
+
+        let position = if self.panel_mode && !self.panels.is_empty() {
+            fl!(
+                "comic-panel",
+                page = self.page_index + 1,
+                panel = self.panel_index + 1,
+                panels = self.panels.len()
+            )
+        } else if self.shows_second_page() {
+            fl!(
+                "reader-pages",
+                first = self.page_index + 1,
+                second = self.page_index + 2,
+                total = self.page_count()
+            )
+        } else {
+            fl!(
+                "reader-page",
+                current = self.page_index + 1,
+                total = self.page_count()
+            )
+        };
+
+        // Pages of right-to-left comics turn forwards to the left.
+        let (left, right) = if self.right_to_left {
+            (true, false)
+        } else {
+            (false, true)
+        };
+
+        let toolbar = widget::row()
+            .push(
+                a11y::icon_button("go-previous-symbolic", fl!("a11y-previous-page"))
+                    .on_press_maybe(self.can_turn(left).then_some(Message::ReaderTurnPage(left))),
+            )
+            .push(widget::text(position))
+            .push(
+                a11y::icon_button("go-next-symbolic", fl!("a11y-next-page")).on_press_maybe(
+                    self.can_turn(right)
+                        .then_some(Message::ReaderTurnPage(right)),
+                ),
+            )
+            .push(widget::horizontal_space())
+            .push(
+                a11y::icon_button("zoom-out-symbolic", fl!("a11y-zoom-out"))
+                    .on_press(Message::ComicZoom(self.zoom.step(false))),
+            )
+            .push(widget::text(zoom_label))
+            .push(
+                a11y::icon_button("zoom-in-symbolic", fl!("a11y-zoom-in"))
+                    .on_press(Message::ComicZoom(self.zoom.step(true))),
+            )
+            .push(
+                widget::button::text(fl!("zoom-fit-width"))
+                    .on_press(Message::ComicZoom(Zoom::FitWidth)),
+            )
+            .push(
+                widget::button::text(fl!("zoom-fit-page"))
+                    .on_press(Message::ComicZoom(Zoom::FitPage)),
+            )
+            .push(widget::horizontal_space())
+            .push(
+                widget::button::text(fl!("comic-right-to-left"))
+                    .selected(self.right_to_left)
+                    .on_press(Message::ComicRightToLeft(!self.right_to_left)),
+            )
+            .push(
+                widget::button::text(fl!("comic-panels"))
+                    .selected(self.panel_mode)
+                    .on_press(Message::ToggleComicPanels),
+            )
+            .push(
+                a11y::icon_button("view-list-symbolic", fl!("a11y-contents"))
+                    .on_press(Message::ToggleToc),
+            )
+            .push(
+                a11y::icon_button("bookmark-new-symbolic", fl!("a11y-bookmarks"))
+                    .on_press(Message::ToggleContextPage(ContextPage::Bookmarks)),
+            )
+            .push(spread_toggle(self.spread))
+            .push(fullscreen_toggle(appearance.fullscreen))
+            .push(palette_menu(appearance))
+            .push(
+                a11y::icon_button("window-close-symbolic", fl!("a11y-close-book"))
+                    .on_press(Message::CloseReader),
+            )
+            .spacing(space_xxs)
+            .align_y(Alignment::Center);
+
+        let panel = self
+            .panels
+            .get(self.panel_index)
+            .filter(|_| self.panel_mode);
+
+        let page: Element<Message> = if let Some(panel) = panel {
+            widget::image(panel.clone())
+                .content_fit(ContentFit::Contain)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        } else {
+            let second_page = self
+                .second_page
+                .as_ref()
+                .filter(|_| self.shows_second_page());
+
+            let mut handles = [self.page.as_ref(), second_page];
+
+            if self.right_to_left {
+                handles.reverse();
+            }
+
+            let pages = handles.into_iter().flatten().fold(
+                widget::row().spacing(space_xxs),
+                |row, handle| {
+                    let image = widget::image(handle.clone());
+
+                    row.push(match self.zoom {
+                        Zoom::FitPage => image
+                            .content_fit(ContentFit::Contain)
+                            .width(Length::Fill)
+                            .height(Length::Fill),
+                        Zoom::FitWidth => image.width(Length::Fill),
+                        Zoom::Percent(percent) => image.width(Length::Fixed(
+                            self.page_width as f32 * f32::from(percent) / 100.0,
+                        )),
+                    })
+                },
+            );
+
+            match (&self.page, self.zoom) {
+                (None, _) => widget::Space::new(Length::Fill, Length::Fill).into(),
+                (Some(_), Zoom::FitPage) => pages.width(Length::Fill).height(Length::Fill).into(),
+                (Some(_), Zoom::FitWidth) => widget::scrollable(pages)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into(),
+                (Some(_), Zoom::Percent(_)) => widget::scrollable(pages)
+                    .direction(scrollable::Direction::Both {
+                        vertical: scrollable::Scrollbar::default(),
+                        horizontal: scrollable::Scrollbar::default(),
+                    })
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into(),
+            }
+        };
+
+        let page = widget::container(page)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center);
+
+        let page = widget::container(tap_zones(page.into())).class(page_style(appearance.colors));
+
+        if appearance.controls_hidden {
+            return widget::container(page).padding(10).into();
+        }
+
+        widget::column()
+            .push(toolbar)
+            .push(page)
+            .spacing(10)
+            .padding(10)
+            .into()
+    }
+}
+
+/// Lists the pages of the comic archive at `path` off the async runtime.
+pub async fn open_comic(path: PathBuf) -> Result<Comic, String> {
+    worker::run(Priority::Visible, move || Comic::open(&path))
+        .await?
+        .map_err(|why| why.to_string())
+}
+
+async fn load_comic_page(
+    comic: Comic,
+    index: usize,
+    panels: bool,
+    right_to_left: bool,
+) -> Result<comic::Page, String> {
+    worker::run(Priority::Visible, move || {
+        comic.page(index, panels, right_to_left)
+    })
+    .await?
+    .map_err(|why| why.to_string())
+}
+
+/// Reads the page layout of the PDF at `path` off the async runtime.
+pub async fn open_pdf(path: PathBuf) -> Result<pdf::Document, String> {
+    worker::run(Priority::Visible, move || pdf::open(&path))
+        .await?
+        .map_err(|why| why.to_string())
+}
+
+async fn render_page(
+    path: PathBuf,
+    index: usize,
+    width: u32,
+) -> Result<widget::image::Handle, String> {
+    worker::run(Priority::Visible, move || pdf::render(&path, index, width))
+        .await?
+        .map(image_handle)
+        .map_err(|why| why.to_string())
+}
+
+/// Renders thumbnails of every page on a blocking thread, yielding them as they finish.
+fn thumbnails(path: PathBuf) -> impl Stream<Item = (usize, widget::image::Handle)> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(4);
+
+        worker::spawn(Priority::Background, move || {
+            let result = pdf::render_all(&path, THUMBNAIL_WIDTH, |index, bitmap| {
+                sender.blocking_send((index, image_handle(bitmap))).is_ok()
+            });
+
+            if let Err(why) = result {
+                tracing::error!("failed to render thumbnails of {}: {why}", path.display());
+            }
+        });
+
+        while let Some(thumbnail) = receiver.recv().await {
+            if output.send(thumbnail).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn image_handle(bitmap: pdf::Bitmap) -> widget::image::Handle {
+    widget::image::Handle::from_rgba(bitmap.width, bitmap.height, bitmap.pixels)
+}
+
+/// Reads the package of the EPUB at `path` off the async runtime.
+pub async fn open_epub(path: PathBuf) -> Result<Epub, String> {
+    worker::run(Priority::Visible, move || Epub::open(&path))
+        .await?
+        .map_err(|why| why.to_string())
+}
+
+/// Reads and parses a chapter off the async runtime.
+pub async fn load_chapter(epub: Epub, index: usize) -> Result<Chapter, String> {
+    worker::run(Priority::Visible, move || epub.chapter(index))
+        .await?
+        .map_err(|why| why.to_string())
+}
+
+/// Reads a chapter's images from the archive in the background, in the order they are
+/// shown, yielding each as it is read.
+fn chapter_images(epub: Epub, images: Vec<epub::Image>) -> impl Stream<Item = (usize, Picture)> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(4);
+
+        worker::spawn(Priority::Visible, move || {
+            for (index, image) in images.iter().enumerate() {
+                let data = match epub.image(image) {
+                    Ok(data) => data,
+                    Err(why) => {
+                        tracing::warn!("failed to read image {index} of the chapter: {why}");
+                        continue;
+                    }
+                };
+
+                let picture = if epub::is_svg(&data) {
+                    Picture::Drawing(widget::svg::Handle::from_memory(data))
+                } else {
+                    Picture::Bitmap(widget::image::Handle::from_bytes(data))
+                };
+
+                if sender.blocking_send((index, picture)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(image) = receiver.recv().await {
+            if output.send(image).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Reads a note off the async runtime.
+pub async fn load_footnote(epub: Epub, target: String) -> Result<Option<epub::Note>, String> {
+    worker::run(Priority::Visible, move || epub.note(&target))
+        .await?
+        .map_err(|why| why.to_string())
+}
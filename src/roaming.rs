@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keeps reading data as records on the signed in account's PDS, so it roams with the
+//! account's identity instead of staying on one device.
+//!
+//! Each book is one record of the [`COLLECTION`] collection, keyed by a hash of the
+//! title and author which [`sync::key`] identifies books across devices by.
+
+use crate::atproto::{Agent, Session};
+use crate::library::{Book, BookId};
+use crate::sync::{self, Resolution};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Collection holding the records, named under the app's ID as lexicons are.
+pub const COLLECTION: &str = "com.github.codegod100.libby.book";
+
+/// Steps progress is stored in, as records can't hold fractions.
+const PROGRESS_SCALE: f32 = 10_000.0;
+
+/// A book's reading data as stored on the PDS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BookRecord {
+    #[serde(rename = "$type")]
+    kind: String,
+    title: String,
+    author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    position: Option<Position>,
+    #[serde(default)]
+    finished: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+}
+
+/// Where reading left off, as in a [`sync::Record`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Position {
+    cfi: String,
+    /// Fraction of the book read, in ten-thousandths.
+    progress: u32,
+    /// When the position was recorded, as an RFC 3339 timestamp.
+    updated_at: String,
+    device: String,
+}
+
+impl Position {
+    fn new(record: &sync::Record) -> Self {
+        let updated_at = i64::try_from(record.updated)
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+        Self {
+            cfi: record.cfi.clone(),
+            progress: (record.progress.clamp(0.0, 1.0) * PROGRESS_SCALE).round() as u32,
+            updated_at,
+            device: record.device.clone(),
+        }
+    }
+
+    fn record(&self) -> Option<sync::Record> {
+        let updated = chrono::DateTime::parse_from_rfc3339(&self.updated_at).ok()?;
+
+        Some(sync::Record {
+            cfi: self.cfi.clone(),
+            progress: self.progress as f32 / PROGRESS_SCALE,
+            updated: u64::try_from(updated.timestamp()).ok()?,
+            device: self.device.clone(),
+        })
+    }
+}
+
+/// Reading data taken from the PDS for a book in the library.
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub id: BookId,
+    /// A position which won over the book's own.
+    pub position: Option<sync::Record>,
+    /// A rating for a book which had none here.
+    pub rating: Option<u8>,
+}
+
+/// Key of a book's record.
+pub fn rkey(book: &Book) -> String {
+    let hash = Sha256::digest(sync::key(book).as_bytes());
+    hash[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Brings the PDS and the library up to date with each other: deletes the records
+/// with the given keys, writes what this device knows better, and returns what the
+/// library should take from the PDS, along with the session if its tokens were
+/// refreshed.
+pub async fn sync(
+    session: Session,
+    books: Vec<Book>,
+    deleted: Vec<String>,
+    device: String,
+    resolution: Resolution,
+) -> Result<(Vec<Update>, Option<Session>), String> {
+    let mut agent = Agent::new(session);
+
+    for rkey in &deleted {
+        agent.delete_record(COLLECTION, rkey).await?;
+    }
+
+    let stored: HashMap<String, BookRecord> = agent
+        .list_records(COLLECTION)
+        .await?
+        .into_iter()
+        .filter_map(|(rkey, value)| match serde_json::from_value(value) {
+            Ok(record) => Some((rkey, record)),
+            Err(why) => {
+                tracing::warn!("skipping malformed reading record {rkey}: {why}");
+                None
+            }
+        })
+        .collect();
+
+    let (writes, updates) = merge(&books, &stored, &device, resolution);
+
+    for (rkey, record) in writes {
+        let value = serde_json::to_value(&record).map_err(|why| why.to_string())?;
+        agent.put_record(COLLECTION, &rkey, value).await?;
+    }
+
+    Ok((updates, agent.into_refreshed()))
+}
+
+/// Works out the records to write, by key, and the updates to make to the library.
+fn merge(
+    books: &[Book],
+    stored: &HashMap<String, BookRecord>,
+    device: &str,
+    resolution: Resolution,
+) -> (Vec<(String, BookRecord)>, Vec<Update>) {
+    let mut writes = Vec::new();
+    let mut updates = Vec::new();
+
+    for book in books.iter().filter(|book| !book.is_trashed()) {
+        let rkey = rkey(book);
+        let local = sync::record(book, device);
+
+        let record = BookRecord {
+            kind: COLLECTION.to_string(),
+            title: book.title.clone(),
+            author: book.author.clone(),
+            position: local.as_ref().map(Position::new),
+            finished: book.is_finished(),
+            rating: book.rating,
+        };
+
+        let Some(stored) = stored.get(&rkey) else {
+            // Only books which have been read or rated are worth a record.
+            if record.position.is_some() || record.rating.is_some() {
+                writes.push((rkey, record));
+            }
+
+            continue;
+        };
+
+        let stored_position = stored.position.as_ref().and_then(Position::record);
+
+        let take_position = match (&stored_position, &local) {
+            (Some(theirs), Some(ours)) => {
+                stored.position != record.position && theirs.wins_over(ours, resolution)
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        let take_rating = book.rating.is_none() && stored.rating.is_some();
+
+        let merged = BookRecord {
+            position: if take_position {
+                stored.position.clone()
+            } else {
+                record.position
+            },
+            // A book read to the end on any device stays finished.
+            finished: record.finished || stored.finished,
+            rating: record.rating.or(stored.rating),
+            ..record
+        };
+
+        if merged != *stored {
+            writes.push((rkey, merged));
+        }
+
+        if take_position || take_rating {
+            updates.push(Update {
+                id: book.id,
+                position: stored_position.filter(|_| take_position),
+                rating: stored.rating.filter(|_| take_rating),
+            });
+        }
+    }
+
+    (writes, updates)
+}
@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Follows RSS and Atom feeds, such as those of book-review blogs and publishers.
+//!
+//! The feeds followed are kept in the config, and which of their articles have been
+//! read in the news file, pruned to the articles feeds still list each time they are
+//! fetched.
+
+use crate::dictionary;
+use crate::net;
+use quick_xml::events::{BytesStart, Event};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often followed feeds are fetched again.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// A feed followed on the news page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Followed {
+    pub url: String,
+    /// Title the feed gave itself when it was followed.
+    pub title: String,
+}
+
+/// A fetched feed.
+#[derive(Debug, Clone, Default)]
+pub struct Feed {
+    pub title: String,
+    /// Articles, newest first.
+    pub articles: Vec<Article>,
+}
+
+/// An article of a feed.
+#[derive(Debug, Clone, Default)]
+pub struct Article {
+    /// ID the feed gives the article, or its link if it gives none.
+    pub id: String,
+    pub title: String,
+    /// Address of the article on the web.
+    pub link: String,
+    /// Seconds since the Unix epoch at which the article was published, if known.
+    pub published: Option<i64>,
+    /// The article as plain text.
+    pub text: String,
+    /// ISBNs mentioned in the article, as ISBN-13s.
+    pub isbns: Vec<String>,
+}
+
+/// Fetches and reads a feed.
+pub async fn fetch(url: String) -> Result<Feed, String> {
+    let body = net::fetch(url.clone(), None).await?;
+    parse(&body, &url)
+}
+
+fn parse(xml: &[u8], base: &str) -> Result<Feed, String> {
+    let mut reader = quick_xml::Reader::from_reader(xml);
+    reader.config_mut().check_end_names = false;
+
+    let mut feed = Feed::default();
+    let mut article: Option<Article> = None;
+    let mut path: Vec<Vec<u8>> = Vec::new();
+
+    // Dates and bodies are collected raw, as their markup matters.
+    let mut date = String::new();
+    let mut summary = String::new();
+    let mut content = String::new();
+
+    loop {
+        let event = reader.read_event().map_err(|why| why.to_string())?;
+
+        let text = match event {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_vec();
+
+                if matches!(name.as_slice(), b"item" | b"entry") {
+                    article = Some(Article::default());
+                }
+
+                if name == b"link" {
+                    link(&e, base, article.as_mut());
+                }
+
+                path.push(name);
+                continue;
+            }
+            Event::Empty(e) => {
+                if e.local_name().as_ref() == b"link" {
+                    link(&e, base, article.as_mut());
+                }
+
+                continue;
+            }
+            Event::End(e) => {
+                path.pop();
+
+                if matches!(e.local_name().as_ref(), b"item" | b"entry") {
+                    if let Some(mut article) = article.take() {
+                        let body = if content.trim().is_empty() {
+                            &summary
+                        } else {
+                            &content
+                        };
+
+                        article.text = tidy(&dictionary::strip_markup(body));
+                        article.title = collapse(&article.title);
+                        article.published = published(&date);
+                        article.isbns = isbns(&article.text);
+
+                        if article.id.trim().is_empty() {
+                            article.id.clone_from(&article.link);
+                        }
+
+                        feed.articles.push(article);
+                    }
+
+                    date.clear();
+                    summary.clear();
+                    content.clear();
+                }
+
+                continue;
+            }
+            Event::Text(text) => {
+                let raw = String::from_utf8_lossy(&text);
+                quick_xml::escape::unescape(&raw)
+                    .map(|text| text.into_owned())
+                    .unwrap_or_else(|_| raw.into_owned())
+            }
+            Event::CData(text) => String::from_utf8_lossy(&text).into_owned(),
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let path: Vec<&[u8]> = path.iter().map(Vec::as_slice).collect();
+
+        let field = match (&mut article, path.as_slice()) {
+            (None, [b"rss", b"channel", b"title"] | [b"feed", b"title"]) => &mut feed.title,
+            (Some(article), [.., b"item" | b"entry", b"title"]) => &mut article.title,
+            (Some(article), [.., b"item" | b"entry", b"guid" | b"id"]) => &mut article.id,
+            (Some(article), [.., b"item", b"link"]) if article.link.is_empty() => {
+                article.link = resolve(base, text.trim()).unwrap_or_default();
+                continue;
+            }
+            // Entries may give both when they were published and last updated.
+            (
+                Some(_),
+                [.., b"item" | b"entry", b"pubDate" | b"published" | b"updated" | b"date"],
+            ) if date.trim().is_empty() => &mut date,
+            (Some(_), [.., b"item", b"description"] | [.., b"entry", b"summary"]) => &mut summary,
+            (Some(_), [.., b"item", b"encoded"] | [.., b"entry", b"content"]) => &mut content,
+            _ => continue,
+        };
+
+        field.push_str(&text);
+    }
+
+    feed.title = collapse(&feed.title);
+    feed.articles
+        .sort_by_key(|article| std::cmp::Reverse(article.published));
+
+    Ok(feed)
+}
+
+/// Notes an Atom entry's link to its page on the web.
+fn link(e: &BytesStart, base: &str, article: Option<&mut Article>) {
+    let Some(article) = article else {
+        return;
+    };
+
+    let attribute = |name: &[u8]| {
+        e.attributes()
+            .with_checks(false)
+            .filter_map(Result::ok)
+            .find(|attr| attr.key.local_name().as_ref() == name)
+            .map(|attr| String::from_utf8_lossy(&attr.value).into_owned())
+    };
+
+    let rel = attribute(b"rel").unwrap_or_default();
+
+    if let Some(href) = attribute(b"href").filter(|_| rel.is_empty() || rel == "alternate") {
+        if let Some(href) = resolve(base, &href) {
+            article.link = href;
+        }
+    }
+}
+
+fn resolve(base: &str, href: &str) -> Option<String> {
+    Url::parse(base)
+        .and_then(|base| base.join(href))
+        .ok()
+        .map(String::from)
+}
+
+fn published(date: &str) -> Option<i64> {
+    let date = date.trim();
+
+    chrono::DateTime::parse_from_rfc2822(date)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(date))
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapses the spacing of each paragraph, dropping empty ones.
+fn tidy(text: &str) -> String {
+    text.lines()
+        .map(collapse)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Finds the ISBNs in text, as ISBN-13s, in the order first mentioned.
+pub fn isbns(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+
+    for word in text.split(|c: char| !(c.is_ascii_digit() || matches!(c, 'X' | 'x' | '-'))) {
+        let isbn: String = word.chars().filter(|c| *c != '-').collect();
+
+        let isbn = match isbn.len() {
+            13 if is_isbn13(&isbn) => isbn,
+            10 if is_isbn10(&isbn) => isbn10_to_13(&isbn),
+            _ => continue,
+        };
+
+        if !found.contains(&isbn) {
+            found.push(isbn);
+        }
+    }
+
+    found
+}
+
+fn is_isbn13(isbn: &str) -> bool {
+    if !(isbn.starts_with("978") || isbn.starts_with("979")) {
+        return false;
+    }
+
+    let Some(digits) = isbn
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+    else {
+        return false;
+    };
+
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(index, digit)| if index % 2 == 0 { *digit } else { digit * 3 })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+fn is_isbn10(isbn: &str) -> bool {
+    let digits: Option<Vec<u32>> = isbn
+        .chars()
+        .enumerate()
+        .map(|(index, c)| match c {
+            'X' | 'x' if index == 9 => Some(10),
+            c => c.to_digit(10),
+        })
+        .collect();
+
+    let Some(digits) = digits else {
+        return false;
+    };
+
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(index, digit)| (10 - index as u32) * digit)
+        .sum();
+
+    sum.is_multiple_of(11)
+}
+
+fn isbn10_to_13(isbn: &str) -> String {
+    let stem = format!("978{}", &isbn[..9]);
+
+    let sum: u32 = stem
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(index, digit)| if index % 2 == 0 { digit } else { digit * 3 })
+        .sum();
+
+    format!("{stem}{}", (10 - sum % 10) % 10)
+}
+
+/// Which articles of each feed have been read, by the feed's address.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Read(HashMap<String, HashSet<String>>);
+
+impl Read {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("libby").join("news.json"))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|why| {
+                tracing::error!("failed to parse {}: {why}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or(std::io::ErrorKind::NotFound)?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    pub fn is_read(&self, feed: &str, article: &str) -> bool {
+        self.0.get(feed).is_some_and(|read| read.contains(article))
+    }
+
+    pub fn set_read(&mut self, feed: &str, article: &str, read: bool) {
+        if read {
+            self.0
+                .entry(feed.to_string())
+                .or_default()
+                .insert(article.to_string());
+        } else if let Some(articles) = self.0.get_mut(feed) {
+            articles.remove(article);
+        }
+    }
+
+    /// Forgets the articles a feed no longer lists.
+    pub fn prune(&mut self, url: &str, feed: &Feed) {
+        if let Some(read) = self.0.get_mut(url) {
+            read.retain(|id| feed.articles.iter().any(|article| &article.id == id));
+        }
+    }
+
+    /// Forgets a feed which is no longer followed.
+    pub fn forget(&mut self, url: &str) {
+        self.0.remove(url);
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Speed reading by flashing a chapter's words one at a time.
+
+use crate::epub::Block;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Speed used until one is chosen.
+pub const DEFAULT_WPM: u32 = 300;
+pub const MIN_WPM: u32 = 100;
+pub const MAX_WPM: u32 = 1000;
+
+/// Words skipped back by rewinding.
+pub const REWIND_WORDS: usize = 10;
+
+/// A word of the chapter and where it sits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub text: String,
+    /// Index of the block the word belongs to.
+    pub block: usize,
+    /// Byte range of the word within the block's text.
+    pub range: Range<usize>,
+}
+
+/// Splits the text of the chapter's blocks into words.
+pub fn words(blocks: &[Block]) -> Vec<Word> {
+    let mut words = Vec::new();
+
+    for (block, text) in blocks.iter().map(Block::text).enumerate() {
+        let mut start = None;
+
+        for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(index),
+                (Some(from), true) => {
+                    words.push(Word {
+                        text: text[from..index].to_string(),
+                        block,
+                        range: from..index,
+                    });
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    words
+}
+
+/// The configured speed, or the default when none has been chosen.
+pub fn wpm(configured: u32) -> u32 {
+    if configured == 0 {
+        DEFAULT_WPM
+    } else {
+        configured.clamp(MIN_WPM, MAX_WPM)
+    }
+}
+
+/// Time each word is shown at the given speed.
+pub fn interval(wpm: u32) -> Duration {
+    Duration::from_millis(60_000 / u64::from(wpm.max(1)))
+}
+
+/// Extra intervals a word stays on screen, giving the reader a breath at the end of
+/// sentences and clauses, and a moment longer for long words.
+pub fn pause(word: &str) -> u32 {
+    let end = word
+        .trim_end_matches(['"', '\'', '”', '’', ')', '»'])
+        .chars()
+        .last();
+
+    let pause = match end {
+        Some('.' | '!' | '?' | '…') => 2,
+        Some(',' | ';' | ':' | '—' | '–') => 1,
+        _ => 0,
+    };
+
+    if word.chars().count() > 12 {
+        pause + 1
+    } else {
+        pause
+    }
+}
+
+/// Splits the word around the character the eye should rest on, a little left of its
+/// middle, which is drawn in the accent color and kept in place from word to word.
+pub fn split(word: &str) -> (&str, &str, &str) {
+    let pivot = match word.chars().count() {
+        0..=1 => 0,
+        2..=5 => 1,
+        6..=9 => 2,
+        10..=13 => 3,
+        _ => 4,
+    };
+
+    let mut indices = word.char_indices().skip(pivot);
+
+    let Some((start, _)) = indices.next() else {
+        return (word, "", "");
+    };
+
+    let end = indices.next().map_or(word.len(), |(index, _)| index);
+
+    (&word[..start], &word[start..end], &word[end..])
+}
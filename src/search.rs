@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Finds text within the open book.
+
+use crate::epub::{Block, Epub};
+use crate::library::Location;
+use crate::pdf;
+use crate::worker::{self, Priority};
+use futures_util::{Stream, StreamExt};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Characters of context shown on each side of a match.
+const CONTEXT: usize = 40;
+
+/// An occurrence of the search query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// Chapter of an EPUB or page of a PDF, and roughly where the match sits within it.
+    pub location: Location,
+    /// Index of the block within the chapter; always 0 in PDFs.
+    pub block: usize,
+    /// Byte range of the match within the block's text.
+    pub range: Range<usize>,
+    /// The match with some of the text around it.
+    pub snippet: String,
+}
+
+/// Byte ranges of case-insensitive occurrences of `query` within `text`.
+pub fn find(text: &str, query: &str) -> Vec<Range<usize>> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // Each lowercased character with the byte range of the character it came from.
+    let haystack: Vec<(char, Range<usize>)> = text
+        .char_indices()
+        .flat_map(|(index, c)| {
+            let range = index..index + c.len_utf8();
+            c.to_lowercase().map(move |lower| (lower, range.clone()))
+        })
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start + query.len() <= haystack.len() {
+        let window = &haystack[start..start + query.len()];
+
+        if window.iter().map(|(c, _)| *c).eq(query.iter().copied()) {
+            matches.push(window[0].1.start..window[query.len() - 1].1.end);
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+
+    matches
+}
+
+/// Finds the query in the blocks of a chapter.
+pub fn find_in_chapter(chapter: usize, blocks: &[Block], query: &str) -> Vec<Match> {
+    let mut matches = Vec::new();
+
+    for (index, text) in blocks.iter().map(Block::text).enumerate() {
+        for range in find(&text, query) {
+            matches.push(Match {
+                location: Location {
+                    chapter,
+                    offset: index as f32 / blocks.len() as f32,
+                },
+                block: index,
+                snippet: snippet(&text, &range),
+                range,
+            });
+        }
+    }
+
+    matches
+}
+
+/// The match with up to [`CONTEXT`] characters on either side, on one line.
+fn snippet(text: &str, range: &Range<usize>) -> String {
+    let before = &text[..range.start];
+    let after = &text[range.end..];
+
+    let start = before
+        .char_indices()
+        .rev()
+        .nth(CONTEXT - 1)
+        .map_or(0, |(index, _)| index);
+    let end = after
+        .char_indices()
+        .nth(CONTEXT)
+        .map_or(text.len(), |(index, _)| range.end + index);
+
+    let mut snippet = String::new();
+
+    if start > 0 {
+        snippet.push('…');
+    }
+
+    snippet.push_str(&text[start..end]);
+
+    if end < text.len() {
+        snippet.push('…');
+    }
+
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Searches each chapter of an EPUB in turn, yielding the matches of each.
+pub fn search_epub(epub: Epub, query: String) -> impl Stream<Item = Vec<Match>> {
+    futures_util::stream::iter(0..epub.chapter_count()).then(move |index| {
+        let epub = epub.clone();
+        let query = query.clone();
+
+        async move {
+            let chapter = worker::run(Priority::Background, move || epub.chapter(index))
+                .await
+                .and_then(|result| result.map_err(|why| why.to_string()));
+
+            match chapter {
+                Ok(chapter) => find_in_chapter(index, &chapter.blocks, &query),
+                Err(why) => {
+                    tracing::error!("failed to search chapter {index}: {why}");
+                    Vec::new()
+                }
+            }
+        }
+    })
+}
+
+/// Searches the text of each page of a PDF in turn, yielding the matches of each.
+pub fn search_pdf(path: PathBuf, query: String) -> impl Stream<Item = Vec<Match>> {
+    let start = async move {
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+
+        worker::spawn(Priority::Background, move || {
+            let result = pdf::page_texts(&path, |index, text| {
+                let matches = find(&text, &query)
+                    .into_iter()
+                    .map(|range| Match {
+                        location: Location {
+                            chapter: index,
+                            offset: 0.0,
+                        },
+                        block: 0,
+                        snippet: snippet(&text, &range),
+                        range,
+                    })
+                    .collect();
+
+                sender.blocking_send(matches).is_ok()
+            });
+
+            if let Err(why) = result {
+                tracing::error!("failed to search {}: {why}", path.display());
+            }
+        });
+
+        receiver
+    };
+
+    futures_util::stream::once(start).flat_map(|receiver| {
+        futures_util::stream::unfold(receiver, |mut receiver| async move {
+            let matches = receiver.recv().await?;
+            Some((matches, receiver))
+        })
+    })
+}
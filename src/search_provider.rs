@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A search provider over D-Bus, through which the desktop's launcher finds books of the
+//! library by title, author or tag and opens them in the reader.
+//!
+//! The provider follows the `org.gnome.Shell.SearchProvider2` interface, which launchers
+//! find through the file installed from `resources/search-provider.ini`.
+
+use crate::library::{Book, BookId};
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Where the provider is served.
+const PATH: &str = "/com/github/codegod100/libby/SearchProvider";
+
+/// Bus name of the provider, as named in its `.ini` file.
+const NAME: &str = "com.github.codegod100.libby.SearchProvider";
+
+/// Name of the icon shown beside each result.
+const ICON: &str = "com.github.codegod100.libby";
+
+/// Most results offered for a search, as launchers show only a few.
+const MAX_RESULTS: usize = 20;
+
+/// A request from the launcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Open this book in the reader.
+    Open(BookId),
+    /// Show the library searched for these words.
+    Search(String),
+}
+
+/// A book as the launcher searches and shows it.
+#[derive(Debug, Clone)]
+struct Entry {
+    id: BookId,
+    title: String,
+    author: String,
+    tags: Vec<String>,
+}
+
+impl Entry {
+    /// Whether every term is found in the title, author or a tag, ignoring case.
+    fn matches(&self, terms: &[String]) -> bool {
+        terms.iter().all(|term| {
+            let term = term.to_lowercase();
+
+            self.title.to_lowercase().contains(&term)
+                || self.author.to_lowercase().contains(&term)
+                || self
+                    .tags
+                    .iter()
+                    .any(|tag| tag.to_lowercase().contains(&term))
+        })
+    }
+}
+
+/// Books shared with the served provider.
+#[derive(Debug, Clone, Default)]
+pub struct SearchProvider(Arc<Mutex<Vec<Entry>>>);
+
+impl SearchProvider {
+    /// Offers the books of the library not in the trash.
+    pub fn set_books(&self, books: &[Book]) {
+        let entries = books
+            .iter()
+            .filter(|book| !book.is_trashed())
+            .map(|book| Entry {
+                id: book.id,
+                title: book.title.clone(),
+                author: book.author.clone(),
+                tags: book.tags.clone(),
+            })
+            .collect();
+
+        *self.lock() = entries;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Entry>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// IDs of the books matching every term, among `within` if given.
+    fn search(&self, terms: &[String], within: Option<&[String]>) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|entry| within.is_none_or(|within| within.contains(&entry.id.to_string())))
+            .filter(|entry| entry.matches(terms))
+            .take(MAX_RESULTS)
+            .map(|entry| entry.id.to_string())
+            .collect()
+    }
+}
+
+struct Provider {
+    books: SearchProvider,
+    commands: Sender<Command>,
+}
+
+impl Provider {
+    fn send(&self, command: Command) {
+        _ = self.commands.clone().try_send(command);
+    }
+}
+
+#[zbus::interface(name = "org.gnome.Shell.SearchProvider2")]
+impl Provider {
+    fn get_initial_result_set(&self, terms: Vec<String>) -> Vec<String> {
+        self.books.search(&terms, None)
+    }
+
+    fn get_subsearch_result_set(
+        &self,
+        previous_results: Vec<String>,
+        terms: Vec<String>,
+    ) -> Vec<String> {
+        self.books.search(&terms, Some(&previous_results))
+    }
+
+    fn get_result_metas(&self, identifiers: Vec<String>) -> Vec<HashMap<String, OwnedValue>> {
+        let entries = self.books.lock();
+
+        identifiers
+            .iter()
+            .filter_map(|identifier| {
+                entries
+                    .iter()
+                    .find(|entry| entry.id.to_string() == *identifier)
+            })
+            .map(|entry| {
+                let mut meta = HashMap::new();
+                let mut insert = |key: &str, value: Value| {
+                    if let Ok(value) = OwnedValue::try_from(value) {
+                        meta.insert(key.to_string(), value);
+                    }
+                };
+
+                insert("id", Value::from(entry.id.to_string()));
+                insert("name", Value::from(entry.title.clone()));
+                insert("gicon", Value::from(ICON));
+
+                if !entry.author.is_empty() {
+                    insert("description", Value::from(entry.author.clone()));
+                }
+
+                meta
+            })
+            .collect()
+    }
+
+    fn activate_result(&self, identifier: &str, _terms: Vec<String>, _timestamp: u32) {
+        if let Ok(id) = identifier.parse() {
+            self.send(Command::Open(id));
+        }
+    }
+
+    fn launch_search(&self, terms: Vec<String>, _timestamp: u32) {
+        self.send(Command::Search(terms.join(" ")));
+    }
+}
+
+/// Serves the provider on the session bus for as long as the stream is polled, yielding
+/// the requests of the launcher.
+pub fn serve(books: SearchProvider) -> impl Stream<Item = Command> {
+    cosmic::iced::stream::channel(4, move |output| async move {
+        let provider = Provider {
+            books,
+            commands: output,
+        };
+
+        let connection = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.serve_at(PATH, provider));
+
+        let connection = match connection {
+            Ok(builder) => builder.build().await,
+            Err(why) => Err(why),
+        };
+
+        // The connection serves the provider until it's dropped.
+        let _connection = match connection {
+            Ok(connection) => connection,
+            Err(why) => {
+                tracing::error!("failed to serve the search provider: {why}");
+                return;
+            }
+        };
+
+        futures_util::future::pending::<()>().await;
+    })
+}
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keeps passwords and tokens in the desktop's keyring rather than in the config.
+
+/// Service the secrets are filed under, each with its own key as the user.
+const SERVICE: &str = "com.github.codegod100.libby";
+
+fn entry(key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, key).map_err(|why| why.to_string())
+}
+
+/// Runs a keyring call off the async runtime, as the keyring blocks on D-Bus.
+async fn with_keyring<T: Send + 'static>(
+    call: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|why| why.to_string())?
+}
+
+/// Stores a secret under `key`, replacing any stored before.
+pub async fn store(key: String, secret: String) -> Result<(), String> {
+    with_keyring(move || {
+        entry(&key)?
+            .set_password(&secret)
+            .map_err(|why| why.to_string())
+    })
+    .await
+}
+
+/// The secret stored under `key`, if there is one.
+pub async fn load(key: String) -> Result<Option<String>, String> {
+    with_keyring(move || match entry(&key)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(why) => Err(why.to_string()),
+    })
+    .await
+}
+
+/// Removes the secret stored under `key`, if there is one.
+pub async fn forget(key: String) -> Result<(), String> {
+    with_keyring(move || match entry(&key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(why) => Err(why.to_string()),
+    })
+    .await
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Where the user was — the book open and the position in it, the page shown and text
+//! typed but not yet saved — written every so often so it can be restored after libby
+//! exits unexpectedly.
+//!
+//! The file is removed when libby quits normally, so finding it at launch means the
+//! last run crashed or was killed.
+
+use crate::app::Page;
+use crate::library::{BookId, Location};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often the session is written.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub page: Option<Page>,
+    /// The book open in the reader, and where in it.
+    pub book: Option<(BookId, Location)>,
+    /// The book whose details were open in the drawer.
+    pub details: Option<BookId>,
+    pub search_query: String,
+    /// Address typed into the dialog adding a book from the web, if it was open.
+    pub import_url: Option<String>,
+    /// Note being written in the book details drawer.
+    pub note_input: String,
+    pub bookmark_input: String,
+    pub bulk_input: String,
+}
+
+impl Session {
+    /// Whether there's nothing worth restoring beyond the first page.
+    pub fn is_empty(&self) -> bool {
+        self.book.is_none()
+            && self.details.is_none()
+            && self.search_query.is_empty()
+            && self.import_url.is_none()
+            && self.note_input.is_empty()
+            && self.bookmark_input.is_empty()
+            && self.bulk_input.is_empty()
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("session.json"))
+}
+
+/// The session left behind by a run that didn't quit normally, if any.
+pub fn load() -> Option<Session> {
+    let bytes = std::fs::read(path()?).ok()?;
+
+    match serde_json::from_slice(&bytes) {
+        Ok(session) => Some(session),
+        Err(why) => {
+            tracing::error!("failed to read the previous session: {why}");
+            None
+        }
+    }
+}
+
+pub async fn save(session: Session) {
+    let Some(path) = path() else {
+        return;
+    };
+
+    let written = tokio::task::spawn_blocking(move || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let bytes = serde_json::to_vec(&session)?;
+        std::fs::write(path, bytes)
+    })
+    .await;
+
+    if let Ok(Err(why)) = written {
+        tracing::error!("failed to save the session: {why}");
+    }
+}
+
+/// Removes the session, as libby is quitting normally.
+pub fn clear() {
+    let Some(path) = path() else {
+        return;
+    };
+
+    if let Err(why) = std::fs::remove_file(&path) {
+        if why.kind() != std::io::ErrorKind::NotFound {
+            tracing::error!("failed to remove the session: {why}");
+        }
+    }
+}
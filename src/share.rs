@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Composes posts sharing a passage or a finished book, with the book's cover.
+
+use crate::atproto;
+use crate::comic::Comic;
+use crate::epub::Epub;
+use crate::pdf::{self, Bitmap};
+use crate::worker::{self, Priority};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Longest side of a cover attached to a post.
+const COVER_SIDE: u32 = 1000;
+
+const COVER_QUALITY: u8 = 85;
+
+/// A post quoting `quote` above its `attribution`, shortening the quote so the whole
+/// post fits in [`atproto::MAX_POST_CHARS`].
+pub fn quote(quote: &str, attribution: &str) -> String {
+    let quote = quote.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // Room left beside the attribution, the quotation marks and the line break.
+    let room = atproto::MAX_POST_CHARS.saturating_sub(attribution.chars().count() + 3);
+
+    let quote = if quote.chars().count() > room {
+        let shortened: String = quote.chars().take(room.saturating_sub(1)).collect();
+        format!("{}…", shortened.trim_end())
+    } else {
+        quote
+    };
+
+    format!("“{quote}”\n{attribution}")
+}
+
+/// The book's cover, or its first page, as a JPEG small enough to attach to a post.
+pub async fn cover(path: PathBuf) -> Result<Option<Vec<u8>>, String> {
+    worker::run(Priority::Visible, move || {
+        cover_image(&path)?.map(|image| encode(&image)).transpose()
+    })
+    .await?
+}
+
+/// The cover of the book at `path`, or its first page, at full size.
+pub fn cover_image(path: &Path) -> Result<Option<DynamicImage>, String> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+
+    let image = match extension.as_deref() {
+        Some("epub") => {
+            let epub = Epub::open(path).map_err(|why| why.to_string())?;
+
+            let Some(bytes) = epub.cover().map_err(|why| why.to_string())? else {
+                return Ok(None);
+            };
+
+            image::load_from_memory(&bytes).map_err(|why| why.to_string())?
+        }
+        Some("cbz" | "cbr") => {
+            let page = Comic::open(path)
+                .and_then(|comic| comic.page(0, false, false))
+                .map_err(|why| why.to_string())?;
+
+            from_bitmap(page.image)?
+        }
+        Some("pdf") => {
+            from_bitmap(pdf::render(path, 0, COVER_SIDE).map_err(|why| why.to_string())?)?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(image))
+}
+
+fn from_bitmap(bitmap: Bitmap) -> Result<DynamicImage, String> {
+    RgbaImage::from_raw(bitmap.width, bitmap.height, bitmap.pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "page has the wrong number of pixels".to_string())
+}
+
+fn encode(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let image = if image.width().max(image.height()) > COVER_SIDE {
+        image.thumbnail(COVER_SIDE, COVER_SIDE)
+    } else {
+        image.clone()
+    };
+
+    let mut bytes = Vec::new();
+
+    JpegEncoder::new_with_quality(&mut bytes, COVER_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|why| why.to_string())?;
+
+    if bytes.len() > atproto::MAX_IMAGE_BYTES {
+        return Err("cover is too large to post".to_string());
+    }
+
+    Ok(bytes)
+}
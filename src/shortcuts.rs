@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Keys and gestures bound to reader actions.
+
+use crate::fl;
+use cosmic::iced::keyboard::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Two-finger swipe to the left on a touchpad.
+pub const SWIPE_LEFT: &str = "SwipeLeft";
+/// Two-finger swipe to the right on a touchpad.
+pub const SWIPE_RIGHT: &str = "SwipeRight";
+/// Click on the strip along the left edge of the page.
+pub const TAP_LEFT: &str = "TapLeft";
+/// Click on the strip along the right edge of the page.
+pub const TAP_RIGHT: &str = "TapRight";
+
+/// Horizontal scroll distance, in pixels, which counts as a swipe.
+const SWIPE_DISTANCE: f32 = 120.0;
+
+/// Pause in scrolling after which a new swipe begins.
+const SWIPE_GAP: Duration = Duration::from_millis(300);
+
+/// Something the reader does in response to a key or gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReaderAction {
+    PreviousPage,
+    NextPage,
+    /// Jump to the start of the chapter, or the first page of a PDF.
+    ChapterStart,
+    /// Jump to the end of the chapter, or the last page of a PDF.
+    ChapterEnd,
+    PreviousChapter,
+    NextChapter,
+    /// Enter or leave distraction-free fullscreen reading.
+    ToggleFullscreen,
+}
+
+impl ReaderAction {
+    pub const ALL: [ReaderAction; 7] = [
+        ReaderAction::PreviousPage,
+        ReaderAction::NextPage,
+        ReaderAction::ChapterStart,
+        ReaderAction::ChapterEnd,
+        ReaderAction::PreviousChapter,
+        ReaderAction::NextChapter,
+        ReaderAction::ToggleFullscreen,
+    ];
+
+    /// The action in the opposite direction, for reading from right to left.
+    pub fn mirrored(self) -> ReaderAction {
+        match self {
+            ReaderAction::PreviousPage => ReaderAction::NextPage,
+            ReaderAction::NextPage => ReaderAction::PreviousPage,
+            ReaderAction::PreviousChapter => ReaderAction::NextChapter,
+            ReaderAction::NextChapter => ReaderAction::PreviousChapter,
+            action => action,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            ReaderAction::PreviousPage => fl!("shortcut-previous-page"),
+            ReaderAction::NextPage => fl!("shortcut-next-page"),
+            ReaderAction::ChapterStart => fl!("shortcut-chapter-start"),
+            ReaderAction::ChapterEnd => fl!("shortcut-chapter-end"),
+            ReaderAction::PreviousChapter => fl!("shortcut-previous-chapter"),
+            ReaderAction::NextChapter => fl!("shortcut-next-chapter"),
+            ReaderAction::ToggleFullscreen => fl!("shortcut-fullscreen"),
+        }
+    }
+}
+
+/// A key or gesture bound to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    /// A key as described by [`key_trigger`], or one of the gesture constants.
+    pub trigger: String,
+    pub action: ReaderAction,
+}
+
+/// The reader's key and gesture bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shortcuts {
+    pub bindings: Vec<Binding>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        use ReaderAction::*;
+
+        let bindings = [
+            ("ArrowLeft", PreviousPage),
+            ("PageUp", PreviousPage),
+            (SWIPE_RIGHT, PreviousPage),
+            (TAP_LEFT, PreviousPage),
+            ("ArrowRight", NextPage),
+            ("PageDown", NextPage),
+            ("Space", NextPage),
+            (SWIPE_LEFT, NextPage),
+            (TAP_RIGHT, NextPage),
+            ("Home", ChapterStart),
+            ("End", ChapterEnd),
+            ("Ctrl+ArrowLeft", PreviousChapter),
+            ("[", PreviousChapter),
+            ("Ctrl+ArrowRight", NextChapter),
+            ("]", NextChapter),
+            ("F11", ToggleFullscreen),
+        ];
+
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(trigger, action)| Binding {
+                    trigger: trigger.to_string(),
+                    action,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Shortcuts {
+    pub fn action(&self, trigger: &str) -> Option<ReaderAction> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.trigger == trigger)
+            .map(|binding| binding.action)
+    }
+
+    pub fn triggers(&self, action: ReaderAction) -> impl Iterator<Item = &str> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| binding.trigger.as_str())
+    }
+
+    /// Binds the trigger to an action, taking it from any action it was bound to.
+    pub fn bind(&mut self, trigger: String, action: ReaderAction) {
+        self.unbind(&trigger);
+        self.bindings.push(Binding { trigger, action });
+    }
+
+    pub fn unbind(&mut self, trigger: &str) {
+        self.bindings.retain(|binding| binding.trigger != trigger);
+    }
+}
+
+/// Describes a key press as a trigger such as `Ctrl+ArrowRight` or `]`.
+pub fn key_trigger(key: &Key, modifiers: Modifiers) -> Option<String> {
+    let mut trigger = String::new();
+
+    if modifiers.control() {
+        trigger.push_str("Ctrl+");
+    }
+
+    if modifiers.alt() {
+        trigger.push_str("Alt+");
+    }
+
+    match key {
+        Key::Named(named) => {
+            // Shift is already part of the character for other keys.
+            if modifiers.shift() {
+                trigger.push_str("Shift+");
+            }
+
+            trigger.push_str(&format!("{named:?}"));
+        }
+        Key::Character(c) => trigger.push_str(c),
+        Key::Unidentified => return None,
+    }
+
+    Some(trigger)
+}
+
+/// Whether the trigger points left or right, and so turns the other way when reading
+/// from right to left.
+pub fn is_horizontal(trigger: &str) -> bool {
+    trigger.ends_with("Left") || trigger.ends_with("Right")
+}
+
+/// A trigger as shown in the shortcuts settings.
+pub fn label(trigger: &str) -> String {
+    match trigger {
+        SWIPE_LEFT => fl!("gesture-swipe-left"),
+        SWIPE_RIGHT => fl!("gesture-swipe-right"),
+        TAP_LEFT => fl!("gesture-tap-left"),
+        TAP_RIGHT => fl!("gesture-tap-right"),
+        _ => trigger
+            .replace("ArrowLeft", "←")
+            .replace("ArrowRight", "→")
+            .replace("ArrowUp", "↑")
+            .replace("ArrowDown", "↓")
+            .replace('+', " + "),
+    }
+}
+
+/// Turns a stream of horizontal touchpad scrolling into swipes.
+#[derive(Debug, Default)]
+pub struct SwipeTracker {
+    distance: f32,
+    last: Option<Instant>,
+    /// Set once the current gesture has produced a swipe, so its momentum is ignored.
+    fired: bool,
+}
+
+impl SwipeTracker {
+    /// Adds a horizontal scroll, returning the swipe it completes, if any.
+    pub fn scrolled(&mut self, x: f32, now: Instant) -> Option<&'static str> {
+        if self
+            .last
+            .is_none_or(|last| now.duration_since(last) > SWIPE_GAP)
+        {
+            self.distance = 0.0;
+            self.fired = false;
+        }
+
+        self.last = Some(now);
+
+        if self.fired {
+            return None;
+        }
+
+        self.distance += x;
+
+        if self.distance.abs() < SWIPE_DISTANCE {
+            return None;
+        }
+
+        self.fired = true;
+
+        Some(if self.distance < 0.0 {
+            SWIPE_LEFT
+        } else {
+            SWIPE_RIGHT
+        })
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Short sound effects, synthesized on first use and played through whichever of
+//! PipeWire's, PulseAudio's or ALSA's players is installed.
+
+use std::io;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+/// Samples per second of the synthesized sounds.
+const RATE: u32 = 44_100;
+
+/// Loudness of the sounds, kept low so they stay in the background.
+const VOLUME: f32 = 0.25;
+
+/// Players tried in turn, with the arguments they take before the file.
+const PLAYERS: [(&str, &[&str]); 3] = [("pw-play", &[]), ("paplay", &[]), ("aplay", &["-q"])];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    /// A soft rustle as a page is turned.
+    PageTurn,
+    /// Two rising notes when a book is imported.
+    Imported,
+    /// A bright arpeggio when a book is finished.
+    Achievement,
+}
+
+impl Sound {
+    fn name(self) -> &'static str {
+        match self {
+            Sound::PageTurn => "page-turn",
+            Sound::Imported => "imported",
+            Sound::Achievement => "achievement",
+        }
+    }
+
+    /// The sound's samples, from -1.0 to 1.0.
+    fn samples(self) -> Vec<f32> {
+        match self {
+            Sound::PageTurn => rustle(0.12),
+            Sound::Imported => [659.25, 987.77]
+                .into_iter()
+                .flat_map(|frequency| tone(frequency, 0.11))
+                .collect(),
+            Sound::Achievement => [523.25, 659.25, 783.99, 1046.5]
+                .into_iter()
+                .flat_map(|frequency| tone(frequency, 0.09))
+                .collect(),
+        }
+    }
+}
+
+/// A sine wave fading in quickly and out slowly, lasting `seconds`.
+fn tone(frequency: f32, seconds: f32) -> Vec<f32> {
+    let count = (RATE as f32 * seconds) as usize;
+
+    (0..count)
+        .map(|index| {
+            let t = index as f32 / RATE as f32;
+            let attack = (t / 0.005).min(1.0);
+            let decay = 1.0 - index as f32 / count as f32;
+            (t * frequency * std::f32::consts::TAU).sin() * attack * decay * decay
+        })
+        .collect()
+}
+
+/// Smoothed noise swelling and fading over `seconds`, like paper brushing paper.
+fn rustle(seconds: f32) -> Vec<f32> {
+    let count = (RATE as f32 * seconds) as usize;
+    let mut seed: u32 = 0x2545_f491;
+    let mut smoothed = 0.0;
+
+    (0..count)
+        .map(|index| {
+            // A linear congruential generator is noisy enough, and repeats the same rustle.
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0;
+            smoothed += (noise - smoothed) * 0.3;
+
+            let envelope = (index as f32 / count as f32 * std::f32::consts::PI).sin();
+            smoothed * envelope * 0.6
+        })
+        .collect()
+}
+
+/// Encodes samples as a 16-bit mono WAV file.
+fn wav(samples: &[f32]) -> Vec<u8> {
+    let data = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data as usize);
+
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+    bytes.extend_from_slice(&RATE.to_le_bytes());
+    bytes.extend_from_slice(&(RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data.to_le_bytes());
+
+    for sample in samples {
+        let sample = (sample * VOLUME).clamp(-1.0, 1.0);
+        bytes.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+    }
+
+    bytes
+}
+
+/// Writes the sound out, unless it already has been, returning its file.
+fn file(sound: Sound) -> io::Result<PathBuf> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| io::Error::other("there is no cache folder"))?
+        .join("libby")
+        .join("sounds");
+
+    let path = dir.join(format!("{}.wav", sound.name()));
+
+    if !path.exists() {
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(&path, wav(&sound.samples()))?;
+    }
+
+    Ok(path)
+}
+
+/// Plays the sound, logging why if it can't be.
+pub async fn play(sound: Sound) {
+    let written = tokio::task::spawn_blocking(move || file(sound))
+        .await
+        .unwrap_or_else(|why| Err(io::Error::other(why)));
+
+    let path = match written {
+        Ok(path) => path,
+        Err(why) => {
+            tracing::error!("failed to write sound {}: {why}", sound.name());
+            return;
+        }
+    };
+
+    for (player, args) in PLAYERS {
+        match Command::new(player).args(args).arg(&path).status().await {
+            Ok(status) if !status.success() => {
+                tracing::warn!("{player} exited with {status} playing a sound");
+                return;
+            }
+            Ok(_) => return,
+            Err(why) if why.kind() == io::ErrorKind::NotFound => continue,
+            Err(why) => {
+                tracing::error!("failed to play a sound with {player}: {why}");
+                return;
+            }
+        }
+    }
+
+    tracing::error!("failed to play a sound: none of pw-play, paplay or aplay is installed");
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Catalogs books are browsed and downloaded from: OPDS feeds, and web servers listing
+//! book files in folders.
+//!
+//! Sources are kept in the config, except for their passwords, which are kept in the
+//! keyring under [`Source::secret_key`].
+
+use crate::net::Credentials;
+use crate::opds::{self, Feed};
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+
+/// How a source lists its books.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceKind {
+    /// An OPDS catalog feed.
+    #[cfg_attr(feature = "opds", default)]
+    Opds,
+    /// A web server's listings of folders of book files.
+    #[cfg_attr(not(feature = "opds"), default)]
+    Http,
+}
+
+/// A catalog added in Settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    pub id: u64,
+    pub name: String,
+    /// Address of the catalog's root feed or folder.
+    pub url: String,
+    pub kind: SourceKind,
+    /// User name to sign in with, or empty if the source is open to all.
+    #[serde(default)]
+    pub user: String,
+    /// Whether the source is shown in the catalog browser.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl Source {
+    /// Key the source's password is stored under in the keyring.
+    pub fn secret_key(&self) -> String {
+        secret_key(self.id)
+    }
+}
+
+pub fn secret_key(id: u64) -> String {
+    format!("source-{id}")
+}
+
+/// An ID no source has yet.
+pub fn next_id(sources: &[Source]) -> u64 {
+    sources
+        .iter()
+        .map(|source| source.id + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The credentials to sign in with, with the password from the keyring.
+pub async fn credentials(user: String, id: u64) -> Result<Option<Credentials>, String> {
+    if user.is_empty() {
+        return Ok(None);
+    }
+
+    let password = secrets::load(secret_key(id)).await?.unwrap_or_default();
+    Ok(Some(Credentials { user, password }))
+}
+
+/// Fetches a page of a source.
+pub async fn fetch(
+    kind: SourceKind,
+    url: String,
+    credentials: Option<Credentials>,
+) -> Result<Feed, String> {
+    match kind {
+        SourceKind::Opds => opds::fetch_feed(url, credentials).await,
+        SourceKind::Http => opds::fetch_listing(url, credentials).await,
+    }
+}
+
+/// Checks that a source can be reached and read, returning how many entries its root
+/// has.
+///
+/// The password is the one typed into the form, or the stored one if none was.
+pub async fn test(source: Source, password: String) -> Result<usize, String> {
+    let credentials = if password.is_empty() {
+        credentials(source.user, source.id).await?
+    } else {
+        Some(Credentials {
+            user: source.user,
+            password,
+        })
+    };
+
+    let feed = fetch(source.kind, source.url, credentials).await?;
+    Ok(feed.entries.len())
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reads chapters aloud through speech-dispatcher's `spd-say` client.
+
+use crate::epub::Block;
+use futures_util::{Stream, StreamExt};
+use std::ops::Range;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// A sentence of a chapter, located within its block's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sentence {
+    /// Index of the block in the chapter.
+    pub block: usize,
+    /// Byte range of the sentence within the block's text.
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Voice and speed to read with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Speech rate from -100 to 100, where 0 is the synthesizer's default.
+    pub rate: i32,
+    /// Synthesis voice, or the synthesizer's default when unset.
+    pub voice: Option<String>,
+}
+
+/// Splits the text of the chapter's blocks into sentences.
+pub fn sentences(blocks: &[Block]) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+
+    for (block, text) in blocks.iter().map(Block::text).enumerate() {
+        let mut push = |range: Range<usize>| {
+            let sentence = &text[range.clone()];
+            let trimmed = sentence.trim_start();
+            let start = range.start + sentence.len() - trimmed.len();
+            let trimmed = trimmed.trim_end();
+
+            if !trimmed.is_empty() {
+                sentences.push(Sentence {
+                    block,
+                    range: start..start + trimmed.len(),
+                    text: trimmed.to_string(),
+                });
+            }
+        };
+
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((_, c)) = chars.next() {
+            if !matches!(c, '.' | '!' | '?' | '…') {
+                continue;
+            }
+
+            // Closing quotes and brackets belong to the sentence they end.
+            while chars
+                .peek()
+                .is_some_and(|&(_, next)| matches!(next, '"' | '\'' | '”' | '’' | ')' | '»'))
+            {
+                chars.next();
+            }
+
+            match chars.peek() {
+                Some(&(end, next)) if next.is_whitespace() => {
+                    push(start..end);
+                    start = end;
+                }
+                None => {
+                    push(start..text.len());
+                    start = text.len();
+                }
+                Some(_) => (),
+            }
+        }
+
+        if start < text.len() {
+            push(start..text.len());
+        }
+    }
+
+    sentences
+}
+
+/// Names of the voices offered by the speech synthesizer.
+pub async fn voices() -> Result<Vec<String>, String> {
+    let output = Command::new("spd-say")
+        .arg("--list-synthesis-voices")
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("spd-say exited with {}", output.status));
+    }
+
+    // Each line after the header lists a name, language and variant; names may contain spaces.
+    let voices = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            (words.len() > 2).then(|| words[..words.len() - 2].join(" "))
+        })
+        .collect();
+
+    Ok(voices)
+}
+
+/// Speaks each sentence from `start` in turn, yielding the index of each one once it has
+/// been spoken.
+///
+/// Dropping the stream stops after the current sentence; call [`stop`] to silence it too.
+pub fn read_aloud(
+    sentences: Vec<String>,
+    start: usize,
+    options: Options,
+) -> impl Stream<Item = Result<usize, String>> {
+    futures_util::stream::iter(start..sentences.len()).then(move |index| {
+        let text = sentences[index].clone();
+        let options = options.clone();
+        async move { say(&text, &options).await.map(|()| index) }
+    })
+}
+
+/// Cancels whatever the synthesizer is currently saying.
+pub async fn stop() {
+    let status = Command::new("spd-say")
+        .arg("--cancel")
+        .stderr(Stdio::null())
+        .status()
+        .await;
+
+    if let Err(why) = status {
+        tracing::error!("failed to stop speech: {why}");
+    }
+}
+
+async fn say(text: &str, options: &Options) -> Result<(), String> {
+    let mut command = Command::new("spd-say");
+    command
+        .arg("--wait")
+        .arg("--rate")
+        .arg(options.rate.clamp(-100, 100).to_string());
+
+    if let Some(voice) = &options.voice {
+        command.arg("--synthesis-voice").arg(voice);
+    }
+
+    let status = command
+        .arg("--")
+        .arg(text)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("spd-say exited with {status}"))
+    }
+}
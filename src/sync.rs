@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Syncs reading positions between devices through a shared folder.
+//!
+//! Each device writes its positions to its own file in the folder, so a file syncing
+//! service never has two devices editing the same file, and reads every other device's
+//! file to pick up where they left off.
+
+use crate::library::{Book, Location};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Which position wins when devices disagree about a book.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    /// The position furthest into the book.
+    #[default]
+    Furthest,
+    /// The position recorded most recently.
+    Latest,
+}
+
+/// Where a device left off in a book.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Position in the form `epubcfi(/6/<spine step>!@<offset>)`, where the spine step is
+    /// twice the chapter's index plus two, as in an EPUB CFI, and the offset is the
+    /// fraction of the chapter read. The chapter of a PDF is its page.
+    pub cfi: String,
+    /// Fraction of the book which has been read, from 0.0 to 1.0.
+    pub progress: f32,
+    /// Seconds since the Unix epoch at which the position was recorded.
+    pub updated: u64,
+    /// Name of the device which recorded the position.
+    pub device: String,
+}
+
+impl Record {
+    pub fn location(&self) -> Option<Location> {
+        parse_cfi(&self.cfi)
+    }
+
+    /// Whether this record should replace `other` under the given resolution.
+    pub fn wins_over(&self, other: &Record, resolution: Resolution) -> bool {
+        let furthest = |record: &Record| {
+            record
+                .location()
+                .map(|location| (location.chapter, location.offset))
+        };
+
+        let ordering = match resolution {
+            Resolution::Furthest => furthest(self)
+                .partial_cmp(&furthest(other))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(self.updated.cmp(&other.updated)),
+            Resolution::Latest => self.updated.cmp(&other.updated).then(
+                furthest(self)
+                    .partial_cmp(&furthest(other))
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            ),
+        };
+
+        ordering.is_gt()
+    }
+}
+
+/// The positions written by one device, keyed by [`key`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DeviceFile {
+    positions: HashMap<String, Record>,
+}
+
+/// Describes a location as a CFI-like string which survives the trip between devices.
+pub fn to_cfi(location: Location) -> String {
+    format!(
+        "epubcfi(/6/{}!@{:.4})",
+        (location.chapter + 1) * 2,
+        location.offset
+    )
+}
+
+/// Reads back a location written by [`to_cfi`].
+pub fn parse_cfi(cfi: &str) -> Option<Location> {
+    let inner = cfi.strip_prefix("epubcfi(/6/")?.strip_suffix(')')?;
+    let (step, offset) = inner.split_once("!@")?;
+    let step: usize = step.parse().ok()?;
+
+    if step < 2 || !step.is_multiple_of(2) {
+        return None;
+    }
+
+    Some(Location {
+        chapter: step / 2 - 1,
+        offset: offset.parse::<f32>().ok()?.clamp(0.0, 1.0),
+    })
+}
+
+/// The book's reading position as this device shares it with others.
+pub fn record(book: &Book, device: &str) -> Option<Record> {
+    Some(Record {
+        cfi: to_cfi(book.location?),
+        progress: book.progress,
+        updated: book.location_updated,
+        device: device.to_string(),
+    })
+}
+
+/// Identifies a book across devices, where catalog IDs differ, by its title and author.
+pub fn key(book: &Book) -> String {
+    let normalize = |text: &str| {
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+
+    format!(
+        "{}\u{1f}{}",
+        normalize(&book.title),
+        normalize(&book.author)
+    )
+}
+
+/// Name of this device, used for its file in the sync folder.
+pub fn device_name() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "libby".to_string())
+}
+
+fn positions_dir(folder: &Path) -> PathBuf {
+    folder.join("libby").join("positions")
+}
+
+/// Writes this device's positions into the sync folder.
+pub async fn push(
+    folder: PathBuf,
+    device: String,
+    positions: HashMap<String, Record>,
+) -> io::Result<()> {
+    let dir = positions_dir(&folder);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let bytes = serde_json::to_vec_pretty(&DeviceFile { positions })?;
+
+    // Write beside the file and rename it over, so syncing never picks up half a file.
+    let path = dir.join(format!("{device}.json"));
+    let partial = dir.join(format!(".{device}.json.partial"));
+    tokio::fs::write(&partial, bytes).await?;
+    tokio::fs::rename(&partial, &path).await
+}
+
+/// Reads the positions of every other device in the sync folder, keeping the winning
+/// record for each book.
+pub async fn pull(
+    folder: PathBuf,
+    device: String,
+    resolution: Resolution,
+) -> io::Result<HashMap<String, Record>> {
+    let dir = positions_dir(&folder);
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(why) => return Err(why),
+    };
+
+    let own = format!("{device}.json");
+    let mut merged: HashMap<String, Record> = HashMap::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        if entry.file_name() == own.as_str() || path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        let file: DeviceFile = match tokio::fs::read(&path)
+            .await
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(io::Error::from))
+        {
+            Ok(file) => file,
+            Err(why) => {
+                tracing::error!("failed to read {}: {why}", path.display());
+                continue;
+            }
+        };
+
+        for (key, record) in file.positions {
+            match merged.get(&key) {
+                Some(current) if !record.wins_over(current, resolution) => (),
+                _ => {
+                    merged.insert(key, record);
+                }
+            }
+        }
+    }
+
+    Ok(merged)
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Decodes and scales down the images shown in lists, such as covers in catalogs and
+//! pictures in the feed, on the worker pool instead of while they are first drawn, and
+//! keeps those shown most recently in memory.
+//!
+//! The covers of books in the library are kept on disk instead, as the media controls
+//! show them from there.
+
+use crate::library::BookId;
+use crate::share;
+use crate::worker::{self, Priority};
+use cosmic::widget::image::Handle;
+use image::ImageFormat;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+
+/// Longest side of covers in catalogs, twice the size they are shown at so that they
+/// stay sharp on scaled screens.
+pub const COVER_SIDE: u32 = 128;
+
+/// Longest side of pictures in the feed, likewise twice their shown height.
+pub const PICTURE_SIDE: u32 = 320;
+
+/// Longest side of the covers of books in the library kept on disk.
+const BOOK_COVER_SIDE: u32 = 512;
+
+/// Most images kept decoded in each cache.
+const CAPACITY: usize = 256;
+
+/// Decodes the image `fetch` gets, as [`decode`] does.
+pub async fn load(
+    fetch: impl Future<Output = Result<Vec<u8>, String>>,
+    side: u32,
+) -> Result<Handle, String> {
+    decode(fetch.await?, side).await
+}
+
+/// Decodes an encoded image, scaling it down to fit within `side` pixels.
+pub async fn decode(bytes: Vec<u8>, side: u32) -> Result<Handle, String> {
+    worker::run(Priority::Visible, move || {
+        let image = image::load_from_memory(&bytes).map_err(|why| why.to_string())?;
+
+        let image = if image.width() > side || image.height() > side {
+            image.thumbnail(side, side)
+        } else {
+            image
+        };
+
+        let pixels = image.into_rgba8();
+        Ok(Handle::from_rgba(
+            pixels.width(),
+            pixels.height(),
+            pixels.into_raw(),
+        ))
+    })
+    .await?
+}
+
+/// The cover of the book with its file at `path`, written to disk unless it was before,
+/// or `None` if the book has no cover.
+pub async fn book_cover(id: BookId, path: PathBuf) -> Result<Option<PathBuf>, String> {
+    let Some(dir) = dirs::cache_dir().map(|dir| dir.join("libby").join("covers")) else {
+        return Ok(None);
+    };
+
+    worker::run(Priority::Background, move || {
+        let file = dir.join(format!("{id}.jpg"));
+
+        if file.exists() {
+            return Ok(Some(file));
+        }
+
+        let Some(image) = share::cover_image(&path)? else {
+            return Ok(None);
+        };
+
+        let image = if image.width() > BOOK_COVER_SIDE || image.height() > BOOK_COVER_SIDE {
+            image.thumbnail(BOOK_COVER_SIDE, BOOK_COVER_SIDE)
+        } else {
+            image
+        };
+
+        // Written beside it and renamed over, so a cover is never read half written.
+        let partial = dir.join(format!(".{id}.jpg.partial"));
+        std::fs::create_dir_all(&dir).map_err(|why| why.to_string())?;
+        image
+            .to_rgb8()
+            .save_with_format(&partial, ImageFormat::Jpeg)
+            .map_err(|why| why.to_string())?;
+        std::fs::rename(&partial, &file).map_err(|why| why.to_string())?;
+
+        Ok(Some(file))
+    })
+    .await?
+}
+
+/// Decoded images by address, forgetting those shown least recently once full.
+pub struct Cache {
+    entries: HashMap<String, Entry>,
+    /// Counts up each time an image is shown, to tell which was shown last.
+    clock: Cell<u64>,
+}
+
+struct Entry {
+    handle: Handle,
+    shown: Cell<u64>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::with_capacity(CAPACITY),
+            clock: Cell::new(0),
+        }
+    }
+}
+
+impl Cache {
+    /// The image at `url`, if it's decoded, counting it as shown.
+    pub fn get(&self, url: &str) -> Option<&Handle> {
+        let entry = self.entries.get(url)?;
+
+        self.clock.set(self.clock.get() + 1);
+        entry.shown.set(self.clock.get());
+
+        Some(&entry.handle)
+    }
+
+    /// Whether the image at `url` is decoded, without counting it as shown.
+    pub fn contains(&self, url: &str) -> bool {
+        self.entries.contains_key(url)
+    }
+
+    pub fn insert(&mut self, url: String, handle: Handle) {
+        if self.entries.len() >= CAPACITY && !self.entries.contains_key(&url) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.shown.get())
+                .map(|(url, _)| url.clone());
+
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.clock.set(self.clock.get() + 1);
+        self.entries.insert(
+            url,
+            Entry {
+                handle,
+                shown: Cell::new(self.clock.get()),
+            },
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Tables of contents of EPUBs and PDFs, as listed in the reader's sidebar.
+
+use std::collections::HashSet;
+
+/// An entry of a table of contents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry {
+    pub title: String,
+    /// Chapter of an EPUB or page of a PDF the entry points to, if any.
+    pub target: Option<usize>,
+    pub children: Vec<Entry>,
+}
+
+/// An entry as listed in the sidebar.
+#[derive(Debug)]
+pub struct Row<'a> {
+    /// Position of the entry when every entry is listed, used to expand and collapse it.
+    pub id: usize,
+    pub depth: usize,
+    pub entry: &'a Entry,
+    pub expanded: bool,
+    /// Whether this is the entry being read.
+    pub current: bool,
+}
+
+/// Lists the entries to show, in order, leaving out the children of collapsed entries.
+///
+/// Entries leading to the one being read at `position` start out expanded; `toggled`
+/// holds the IDs of entries expanded or collapsed from there.
+pub fn rows<'a>(entries: &'a [Entry], position: usize, toggled: &HashSet<usize>) -> Vec<Row<'a>> {
+    let mut all = Vec::new();
+    flatten(entries, 0, &mut all);
+
+    // The first entry pointing at the position, or else the last one before it.
+    let current = all
+        .iter()
+        .position(|(_, entry)| entry.target == Some(position))
+        .or_else(|| {
+            all.iter()
+                .rposition(|(_, entry)| entry.target.is_some_and(|target| target < position))
+        });
+
+    let mut ancestors = HashSet::new();
+
+    if let Some(current) = current {
+        let mut depth = all[current].0;
+
+        for (id, (entry_depth, _)) in all[..current].iter().enumerate().rev() {
+            if *entry_depth < depth {
+                ancestors.insert(id);
+                depth = *entry_depth;
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut collapsed_at: Option<usize> = None;
+
+    for (id, (depth, entry)) in all.into_iter().enumerate() {
+        match collapsed_at {
+            Some(collapsed) if depth > collapsed => continue,
+            _ => collapsed_at = None,
+        }
+
+        let expanded = ancestors.contains(&id) != toggled.contains(&id);
+
+        if !expanded && !entry.children.is_empty() {
+            collapsed_at = Some(depth);
+        }
+
+        rows.push(Row {
+            id,
+            depth,
+            entry,
+            expanded,
+            current: current == Some(id),
+        });
+    }
+
+    rows
+}
+
+fn flatten<'a>(entries: &'a [Entry], depth: usize, out: &mut Vec<(usize, &'a Entry)>) {
+    for entry in entries {
+        out.push((depth, entry));
+        flatten(&entry.children, depth + 1, out);
+    }
+}
+
+/// Assembles nested entries while walking a navigation document.
+pub struct Builder<'a> {
+    /// Archive paths of the spine's documents, for resolving targets to chapters.
+    spine: &'a [String],
+    open: Vec<Entry>,
+    entries: Vec<Entry>,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(spine: &'a [String]) -> Self {
+        Self {
+            spine,
+            open: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Starts an entry nested within the open one.
+    pub fn open(&mut self) {
+        self.open.push(Entry::default());
+    }
+
+    pub fn push_text(&mut self, text: &str) {
+        if let Some(entry) = self.open.last_mut() {
+            entry.title.push_str(text);
+        }
+    }
+
+    /// Points the open entry at the spine document with the given archive path.
+    pub fn set_target(&mut self, path: &str) {
+        if let Some(entry) = self.open.last_mut() {
+            if entry.target.is_none() {
+                entry.target = self.spine.iter().position(|href| href == path);
+            }
+        }
+    }
+
+    /// Finishes the open entry, dropping it if it has neither a title nor children.
+    pub fn close(&mut self) {
+        let Some(mut entry) = self.open.pop() else {
+            return;
+        };
+
+        entry.title = entry.title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if entry.title.is_empty() && entry.children.is_empty() {
+            return;
+        }
+
+        match self.open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<Entry> {
+        while !self.open.is_empty() {
+            self.close();
+        }
+
+        self.entries
+    }
+}
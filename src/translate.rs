@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Translates passages through a LibreTranslate server.
+
+use crate::net;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Source language code asking the server to detect the language itself.
+pub const AUTO: &str = "auto";
+
+/// A language offered by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    api_key: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn endpoint(url: &str, path: &str) -> String {
+    format!("{}/{path}", url.trim().trim_end_matches('/'))
+}
+
+/// Lists the languages the server at `url` translates between.
+pub async fn languages(url: String) -> Result<Vec<Language>, String> {
+    let response = net::get(endpoint(&url, "languages"))
+        .await
+        .map_err(|why| why.to_string())?;
+
+    parse(response).await
+}
+
+/// Translates `text` from `source`, or a detected language for [`AUTO`], into `target`.
+pub async fn translate(
+    url: String,
+    api_key: String,
+    text: String,
+    source: String,
+    target: String,
+) -> Result<String, String> {
+    let response = net::send(
+        net::client()
+            .post(endpoint(&url, "translate"))
+            .json(&Request {
+                q: &text,
+                source: &source,
+                target: &target,
+                format: "text",
+                api_key: &api_key,
+            }),
+    )
+    .await
+    .map_err(|why| why.to_string())?;
+
+    parse::<Response>(response)
+        .await
+        .map(|response| response.translated_text)
+}
+
+/// Decodes a successful response, or the server's explanation of a failed one.
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, String> {
+    let status = response.status();
+
+    if status.is_success() {
+        return response.json().await.map_err(|why| why.to_string());
+    }
+
+    match response.json::<ErrorResponse>().await {
+        Ok(error) => Err(error.error),
+        Err(_) => Err(status.to_string()),
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Moves deleted books into the trash and back again, and purges them once they expire.
+
+use crate::download;
+use crate::library::{now, Book, Trashed};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a deleted book stays in the trash before it is purged.
+pub const RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// The folder which trashed book files are moved into.
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("trash"))
+}
+
+/// Whole days remaining before a book trashed at `trashed_at` is purged.
+pub fn days_left(trashed_at: u64, now: u64) -> u64 {
+    (trashed_at + RETENTION.as_secs())
+        .saturating_sub(now)
+        .div_ceil(DAY)
+}
+
+/// Flags the book as trashed, optionally moving its file into the trash folder.
+pub async fn trash(mut book: Book, move_file: bool) -> io::Result<Book> {
+    let mut original_path = None;
+
+    if move_file {
+        if let (Some(path), Some(dir)) = (book.path.clone(), dir()) {
+            let file_name = path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+            let destination = dir.join(format!("{}-{}", book.id, file_name.to_string_lossy()));
+
+            tokio::fs::create_dir_all(&dir).await?;
+            relocate(&path, &destination).await?;
+
+            book.path = Some(destination);
+            original_path = Some(path);
+        }
+    }
+
+    book.trashed = Some(Trashed {
+        at: now(),
+        original_path,
+    });
+
+    Ok(book)
+}
+
+/// Takes the book back out of the trash, returning its file to where it came from, or
+/// beside it, numbered, if another file has taken its place since.
+pub async fn restore(mut book: Book) -> io::Result<Book> {
+    if let Some(Trashed {
+        original_path: Some(original),
+        ..
+    }) = &book.trashed
+    {
+        let mut restored = original.clone();
+
+        if let Some(current) = &book.path {
+            if let (Some(dir), Some(name)) = (original.parent(), original.file_name()) {
+                restored = download::destination(dir, &name.to_string_lossy());
+            }
+
+            relocate(current, &restored).await?;
+        }
+
+        book.path = Some(restored);
+    }
+
+    book.trashed = None;
+    Ok(book)
+}
+
+/// Deletes the files of books which were moved into the trash folder.
+pub async fn purge(books: Vec<Book>) {
+    for book in books {
+        let moved = book
+            .trashed
+            .as_ref()
+            .is_some_and(|trashed| trashed.original_path.is_some());
+
+        if let (true, Some(path)) = (moved, &book.path) {
+            if let Err(why) = tokio::fs::remove_file(path).await {
+                tracing::error!("failed to remove {}: {why}", path.display());
+            }
+        }
+    }
+}
+
+/// Moves a file, falling back to copy and delete across filesystems.
+async fn relocate(from: &Path, to: &Path) -> io::Result<()> {
+    if tokio::fs::rename(from, to).await.is_err() {
+        tokio::fs::copy(from, to).await?;
+        tokio::fs::remove_file(from).await?;
+    }
+
+    Ok(())
+}
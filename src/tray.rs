@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The status icon shown in the panel's tray, through the StatusNotifierItem protocol,
+//! with a menu of quick actions.
+
+use crate::icons;
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream};
+use ksni::menu::StandardItem;
+use ksni::{MenuItem, TrayMethods};
+
+/// ID of the tray, which is also the name of the application's icon.
+const ID: &str = "com.github.codegod100.libby";
+
+/// Something chosen from the tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Show the window, or hide it if it's showing.
+    Toggle,
+    /// Open the book last read.
+    Continue,
+    Quit,
+    /// The tray couldn't be shown, such as when the panel has none.
+    Failed,
+}
+
+/// Labels of the tray's menu, as shown to the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Menu {
+    pub title: String,
+    pub toggle: String,
+    /// Label of the item continuing the book last read, if any has been read.
+    pub continue_reading: Option<String>,
+    pub quit: String,
+}
+
+struct Tray {
+    menu: Menu,
+    /// The app's icon in pixels, for when the icon theme has none by its name, such as
+    /// before installing.
+    pixmaps: Vec<ksni::Icon>,
+    output: Sender<Action>,
+}
+
+impl Tray {
+    fn send(&mut self, action: Action) {
+        _ = self.output.try_send(action);
+    }
+}
+
+impl ksni::Tray for Tray {
+    fn id(&self) -> String {
+        ID.to_string()
+    }
+
+    fn title(&self) -> String {
+        self.menu.title.clone()
+    }
+
+    fn icon_name(&self) -> String {
+        ID.to_string()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        self.pixmaps.clone()
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(Action::Toggle);
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut items: Vec<MenuItem<Self>> = vec![StandardItem {
+            label: self.menu.toggle.clone(),
+            activate: Box::new(|tray: &mut Self| tray.send(Action::Toggle)),
+            ..Default::default()
+        }
+        .into()];
+
+        if let Some(label) = &self.menu.continue_reading {
+            items.push(
+                StandardItem {
+                    label: label.clone(),
+                    icon_name: "document-open-symbolic".into(),
+                    activate: Box::new(|tray: &mut Self| tray.send(Action::Continue)),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+
+        items.push(MenuItem::Separator);
+        items.push(
+            StandardItem {
+                label: self.menu.quit.clone(),
+                icon_name: "application-exit-symbolic".into(),
+                activate: Box::new(|tray: &mut Self| tray.send(Action::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        items
+    }
+}
+
+/// Removes the tray once the subscription showing it ends.
+struct Shown(ksni::Handle<Tray>);
+
+impl Drop for Shown {
+    fn drop(&mut self) {
+        _ = self.0.shutdown();
+    }
+}
+
+/// Shows the tray for as long as the stream is polled, yielding what is chosen from it.
+pub fn show(menu: Menu) -> impl Stream<Item = Action> {
+    cosmic::iced::stream::channel(4, move |mut output| async move {
+        let tray = Tray {
+            menu,
+            pixmaps: pixmaps(),
+            output: output.clone(),
+        };
+
+        // Sandboxes such as Flatpak's may not allow owning the tray's D-Bus name.
+        let _shown = match tray.disable_dbus_name(sandboxed()).spawn().await {
+            Ok(handle) => Shown(handle),
+            Err(why) => {
+                tracing::error!("failed to show the tray: {why}");
+                _ = output.send(Action::Failed).await;
+                return;
+            }
+        };
+
+        futures_util::future::pending::<()>().await;
+    })
+}
+
+/// The app's icon at each size it was rendered at, as the tray protocol takes it.
+fn pixmaps() -> Vec<ksni::Icon> {
+    icons::app_pixels()
+        .into_iter()
+        .map(|(size, mut data)| {
+            // RGBA to ARGB, which is in network byte order.
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.rotate_right(1);
+            }
+
+            ksni::Icon {
+                width: size as i32,
+                height: size as i32,
+                data,
+            }
+        })
+        .collect()
+}
+
+fn sandboxed() -> bool {
+    std::path::Path::new("/.flatpak-info").exists()
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Typography used to lay out EPUB chapters in the reader.
+
+use serde::{Deserialize, Serialize};
+
+/// Generic font family of the body text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontFamily {
+    Serif,
+    #[default]
+    SansSerif,
+    Monospace,
+}
+
+impl FontFamily {
+    pub const ALL: [FontFamily; 3] = [
+        FontFamily::Serif,
+        FontFamily::SansSerif,
+        FontFamily::Monospace,
+    ];
+}
+
+/// How chapter text is set, chosen globally or overridden per book.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Typography {
+    pub font: FontFamily,
+    /// Size of body text, in pixels.
+    pub size: u16,
+    /// Line height as a percentage of the text size.
+    pub line_height: u16,
+    /// Horizontal space on either side of the text column, in pixels.
+    pub margin: u16,
+    /// Justify paragraphs rather than aligning them to the left.
+    pub justify: bool,
+}
+
+impl Typography {
+    pub const SIZES: std::ops::RangeInclusive<u16> = 12..=32;
+    pub const LINE_HEIGHTS: std::ops::RangeInclusive<u16> = 100..=250;
+    pub const MARGINS: std::ops::RangeInclusive<u16> = 0..=160;
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            font: FontFamily::default(),
+            size: 17,
+            line_height: 150,
+            margin: 32,
+            justify: false,
+        }
+    }
+}
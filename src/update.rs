@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Checks the repository's GitHub releases for a newer version of libby.
+
+use crate::net;
+use reqwest::{header, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often it is looked whether a check is due.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often to check for a newer version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Frequency {
+    Never,
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl Frequency {
+    /// Seconds between checks, or `None` if updates aren't checked for.
+    fn interval(self) -> Option<u64> {
+        match self {
+            Frequency::Never => None,
+            Frequency::Daily => Some(24 * 60 * 60),
+            Frequency::Weekly => Some(7 * 24 * 60 * 60),
+        }
+    }
+
+    /// Whether a check is due, given when the last one was made.
+    pub fn is_due(self, last_checked: u64, now: u64) -> bool {
+        self.interval()
+            .is_some_and(|interval| now.saturating_sub(last_checked) >= interval)
+    }
+}
+
+/// A published release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// Page of the release notes.
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+/// Looks up the latest release of the GitHub repository at `repository`, returning it
+/// if it is newer than `current`.
+pub async fn check(repository: String, current: String) -> Result<Option<Release>, String> {
+    let url = Url::parse(&repository).map_err(|why| why.to_string())?;
+    let path = url.path().trim_matches('/').trim_end_matches(".git");
+
+    let request = net::client()
+        .get(format!(
+            "https://api.github.com/repos/{path}/releases/latest"
+        ))
+        .header(header::ACCEPT, "application/vnd.github+json");
+
+    let response = net::send(request).await.map_err(|why| why.to_string())?;
+
+    // Nothing has been released yet.
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let release: GithubRelease = response
+        .error_for_status()
+        .map_err(|why| why.to_string())?
+        .json()
+        .await
+        .map_err(|why| why.to_string())?;
+
+    let version = release.tag_name.trim_start_matches('v').to_string();
+
+    Ok(is_newer(&version, &current).then_some(Release {
+        version,
+        url: release.html_url,
+    }))
+}
+
+/// Whether `version` comes after `current`, comparing their dotted numbers in turn.
+pub fn is_newer(version: &str, current: &str) -> bool {
+    fn numbers(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+
+    let (mut version, mut current) = (numbers(version), numbers(current));
+    let length = version.len().max(current.len());
+    version.resize(length, 0);
+    current.resize(length, 0);
+
+    version > current
+}
@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Syncs the catalog, with its bookmarks, highlights and notes, through a WebDAV server
+//! such as Nextcloud.
+//!
+//! The server holds a single catalog file which each device reads, merges its own
+//! changes into and writes back, as described in [`catalog`]. A write only goes through
+//! if nobody else wrote the file since it was read; otherwise the merge is redone on top
+//! of what they wrote.
+
+use crate::catalog::{self, Catalog, Outcome};
+use crate::library::Book;
+use crate::net;
+use crate::sync::Resolution;
+use reqwest::{header, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often the catalog is synced in the background, when that is turned on.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Times the merge is redone when another device writes the catalog in the meantime.
+const ATTEMPTS: usize = 3;
+
+/// Key the WebDAV password is kept under in the keyring.
+pub const PASSWORD_KEY: &str = "webdav-password";
+
+/// A WebDAV server and the credentials to sign in to it with.
+#[derive(Debug, Clone)]
+pub struct Server {
+    /// Folder the catalog is kept under, such as
+    /// `https://cloud.example.com/remote.php/dav/files/<user>/`.
+    pub url: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl Server {
+    fn folder(&self) -> String {
+        format!("{}/libby", self.url.trim().trim_end_matches('/'))
+    }
+
+    fn catalog(&self) -> String {
+        format!("{}/catalog.json", self.folder())
+    }
+
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        method: Method,
+        url: &str,
+    ) -> reqwest::RequestBuilder {
+        client
+            .request(method, url)
+            .basic_auth(&self.user, Some(&self.password))
+    }
+}
+
+/// The catalog as of the last sync, along with the server it was synced with.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Base {
+    url: String,
+    catalog: Catalog,
+}
+
+/// Brings the catalog on the server and the library up to date with each other,
+/// returning what the library should take from the server.
+pub async fn sync(
+    server: Server,
+    books: Vec<Book>,
+    device: String,
+    resolution: Resolution,
+) -> Result<Outcome, String> {
+    let client = net::client();
+
+    let base = load_base()
+        .await
+        .filter(|base| base.url == server.url)
+        .map(|base| base.catalog)
+        .unwrap_or_default();
+    let ours = Catalog::new(&books, &device);
+
+    let mut attempt = 0;
+
+    let (merged, conflicts) = loop {
+        attempt += 1;
+
+        let (theirs, etag) = fetch(&client, &server).await?;
+        let (merged, conflicts) = catalog::merge(&base, &ours, &theirs, resolution);
+
+        if merged == theirs || store(&client, &server, &merged, etag).await? {
+            break (merged, conflicts);
+        }
+
+        if attempt == ATTEMPTS {
+            return Err("the catalog kept changing on the server".to_string());
+        }
+    };
+
+    if let Err(why) = save_base(&server.url, &merged).await {
+        tracing::error!("failed to record the synced catalog: {why}");
+    }
+
+    Ok(catalog::outcome(&books, &ours, merged, conflicts))
+}
+
+/// Reads the catalog on the server and its entity tag, or an empty catalog if there is
+/// none yet.
+async fn fetch(
+    client: &reqwest::Client,
+    server: &Server,
+) -> Result<(Catalog, Option<String>), String> {
+    let response = net::send(server.request(client, Method::GET, &server.catalog()))
+        .await
+        .map_err(|why| why.to_string())?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok((Catalog::default(), None));
+    }
+
+    let response = response.error_for_status().map_err(|why| why.to_string())?;
+
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+
+    let bytes = response.bytes().await.map_err(|why| why.to_string())?;
+    let catalog = serde_json::from_slice(&bytes).map_err(|why| why.to_string())?;
+
+    Ok((catalog, etag))
+}
+
+/// Writes the catalog to the server unless it changed since it was read with `etag`,
+/// returning whether it was written.
+async fn store(
+    client: &reqwest::Client,
+    server: &Server,
+    catalog: &Catalog,
+    etag: Option<String>,
+) -> Result<bool, String> {
+    let bytes = serde_json::to_vec_pretty(catalog).map_err(|why| why.to_string())?;
+
+    let put = || {
+        let request = server
+            .request(client, Method::PUT, &server.catalog())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(bytes.clone());
+
+        match &etag {
+            Some(etag) => request.header(header::IF_MATCH, etag),
+            None => request.header(header::IF_NONE_MATCH, "*"),
+        }
+    };
+
+    let mut response = net::send(put()).await.map_err(|why| why.to_string())?;
+
+    // The folder doesn't exist before the first sync.
+    if matches!(
+        response.status(),
+        StatusCode::NOT_FOUND | StatusCode::CONFLICT
+    ) {
+        let mkcol = Method::from_bytes(b"MKCOL").map_err(|why| why.to_string())?;
+
+        net::send(server.request(client, mkcol, &server.folder()))
+            .await
+            .map_err(|why| why.to_string())?;
+
+        response = net::send(put()).await.map_err(|why| why.to_string())?;
+    }
+
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        return Ok(false);
+    }
+
+    response.error_for_status().map_err(|why| why.to_string())?;
+    Ok(true)
+}
+
+fn base_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("libby").join("webdav.json"))
+}
+
+async fn load_base() -> Option<Base> {
+    let bytes = tokio::fs::read(base_path()?).await.ok()?;
+
+    match serde_json::from_slice(&bytes) {
+        Ok(base) => Some(base),
+        Err(why) => {
+            tracing::error!("failed to parse the last synced catalog: {why}");
+            None
+        }
+    }
+}
+
+async fn save_base(url: &str, catalog: &Catalog) -> std::io::Result<()> {
+    let path = base_path().ok_or(std::io::ErrorKind::NotFound)?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let base = Base {
+        url: url.to_string(),
+        catalog: catalog.clone(),
+    };
+
+    tokio::fs::write(path, serde_json::to_vec(&base)?).await
+}
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Webhooks: addresses sent a JSON payload when something happens in libby, for
+//! logging reads to services such as Notion or a personal tracker.
+//!
+//! Payloads are made from a template in which these placeholders are replaced, with
+//! their values escaped to go inside JSON strings:
+//!
+//! - `{{event}}`: `book-finished` or `book-imported`.
+//! - `{{title}}` and `{{author}}`: the book's.
+//! - `{{progress}}`: how much of the book has been read, in percent.
+//! - `{{time}}`: when the event happened, in RFC 3339.
+
+use crate::library::Book;
+use crate::net;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+
+/// Template of new webhooks.
+pub const DEFAULT_TEMPLATE: &str = r#"{
+  "event": "{{event}}",
+  "title": "{{title}}",
+  "author": "{{author}}",
+  "progress": {{progress}},
+  "time": "{{time}}"
+}"#;
+
+/// Things which happen in libby that webhooks may be fired on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Event {
+    /// A book was read to the end.
+    BookFinished,
+    /// A downloaded book was added to the library.
+    BookImported,
+}
+
+impl Event {
+    pub const ALL: [Event; 2] = [Event::BookFinished, Event::BookImported];
+
+    /// Name of the event in payloads.
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::BookFinished => "book-finished",
+            Event::BookImported => "book-imported",
+        }
+    }
+}
+
+/// A webhook added in Settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: u64,
+    pub name: String,
+    /// Address the payload is posted to.
+    pub url: String,
+    /// Events the webhook is fired on.
+    pub events: Vec<Event>,
+    /// Template of the JSON payload.
+    pub template: String,
+    pub enabled: bool,
+}
+
+/// An ID no webhook has yet.
+pub fn next_id(webhooks: &[Webhook]) -> u64 {
+    webhooks
+        .iter()
+        .map(|webhook| webhook.id + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The payload of a webhook fired on an event about a book, checked to be valid JSON.
+pub fn render(template: &str, event: Event, book: &Book) -> Result<String, String> {
+    let progress = (book.progress * 100.0).round().clamp(0.0, 100.0);
+
+    let payload = template
+        .replace("{{event}}", event.name())
+        .replace("{{title}}", &escape(&book.title))
+        .replace("{{author}}", &escape(&book.author))
+        .replace("{{progress}}", &progress.to_string())
+        .replace("{{time}}", &chrono::Local::now().to_rfc3339());
+
+    serde_json::from_str::<serde_json::Value>(&payload)
+        .map_err(|why| format!("the payload isn't valid JSON: {why}"))?;
+
+    Ok(payload)
+}
+
+/// Escapes text to go inside a JSON string.
+fn escape(text: &str) -> String {
+    let quoted = serde_json::Value::from(text).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Posts a payload to a webhook's address.
+pub async fn fire(url: String, payload: String) -> Result<(), String> {
+    let request = net::client()
+        .post(url.trim())
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(payload);
+
+    net::send(request)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map(|_| ())
+        .map_err(|why| why.to_string())
+}
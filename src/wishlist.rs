@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Books to get someday, noted by ISBN, such as from articles on the news page.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A book on the wishlist.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wish {
+    /// The book's ISBN-13.
+    pub isbn: String,
+    /// Where the book was come across, such as the title of an article.
+    pub note: String,
+    /// Seconds since the Unix epoch at which the book was added.
+    pub added: u64,
+}
+
+impl Wish {
+    /// Page of the book on Open Library.
+    pub fn url(&self) -> String {
+        format!("https://openlibrary.org/isbn/{}", self.isbn)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Wishlist {
+    /// Books, most recently added last.
+    pub wishes: Vec<Wish>,
+}
+
+impl Wishlist {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("libby").join("wishlist.json"))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|why| {
+                tracing::error!("failed to parse {}: {why}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or(std::io::ErrorKind::NotFound)?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn contains(&self, isbn: &str) -> bool {
+        self.wishes.iter().any(|wish| wish.isbn == isbn)
+    }
+
+    /// Adds a book unless it is on the wishlist already.
+    pub fn add(&mut self, isbn: String, note: String) {
+        if !self.contains(&isbn) {
+            self.wishes.push(Wish {
+                isbn,
+                note,
+                added: crate::library::now(),
+            });
+        }
+    }
+
+    pub fn remove(&mut self, isbn: &str) {
+        self.wishes.retain(|wish| wish.isbn != isbn);
+    }
+}
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A bounded pool of threads for CPU-heavy work, such as parsing books, rendering pages
+//! and covers, and hashing files, so that it neither stalls the UI nor takes every
+//! blocking thread of the runtime. Work for what the reader is looking at goes ahead of
+//! work in the background.
+
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Condvar, LazyLock, Mutex, MutexGuard};
+
+/// Most threads in the pool, however many cores there are.
+const MAX_WORKERS: usize = 8;
+
+static POOL: LazyLock<Pool> = LazyLock::new(Pool::new);
+
+/// How soon work should be done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// For what is on screen, or about to be: done before any background work.
+    Visible,
+    /// For what can wait, such as hashing, searching and thumbnails.
+    Background,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Queues {
+    visible: VecDeque<Job>,
+    background: VecDeque<Job>,
+}
+
+#[derive(Default)]
+struct Shared {
+    queues: Mutex<Queues>,
+    ready: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queues> {
+        self.queues
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Does the queued work, the most urgent first, for as long as the app runs.
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut queues = self.lock();
+
+                loop {
+                    if let Some(job) = queues
+                        .visible
+                        .pop_front()
+                        .or_else(|| queues.background.pop_front())
+                    {
+                        break job;
+                    }
+
+                    queues = self
+                        .ready
+                        .wait(queues)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            };
+
+            job();
+        }
+    }
+}
+
+struct Pool {
+    shared: Arc<Shared>,
+    workers: usize,
+}
+
+impl Pool {
+    fn new() -> Self {
+        let shared = Arc::new(Shared::default());
+        let wanted = std::thread::available_parallelism()
+            .map_or(2, |cores| cores.get())
+            .clamp(2, MAX_WORKERS);
+
+        let mut workers = 0;
+
+        for index in 0..wanted {
+            let shared = shared.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("libby-worker-{index}"))
+                .spawn(move || shared.work());
+
+            match spawned {
+                Ok(_) => workers += 1,
+                Err(why) => tracing::error!("failed to start a worker thread: {why}"),
+            }
+        }
+
+        Pool { shared, workers }
+    }
+}
+
+/// Queues work on the pool without waiting for it.
+pub fn spawn(priority: Priority, work: impl FnOnce() + Send + 'static) {
+    #[cfg(feature = "profiling")]
+    let work = {
+        let span = tracing::info_span!("work", ?priority);
+        move || span.in_scope(work)
+    };
+
+    // Without any threads of its own, the pool lends the runtime's.
+    if POOL.workers == 0 {
+        drop(tokio::task::spawn_blocking(work));
+        return;
+    }
+
+    let mut queues = POOL.shared.lock();
+
+    match priority {
+        Priority::Visible => queues.visible.push_back(Box::new(work)),
+        Priority::Background => queues.background.push_back(Box::new(work)),
+    }
+
+    drop(queues);
+    POOL.shared.ready.notify_one();
+}
+
+/// Does the work on the pool, returning what it made. Work no longer awaited by the
+/// time its turn comes is skipped.
+pub async fn run<T: Send + 'static>(
+    priority: Priority,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+
+    spawn(priority, move || {
+        if sender.is_closed() {
+            return;
+        }
+
+        // A panic is reported by the crash handler, and leaves the thread working.
+        _ = sender.send(std::panic::catch_unwind(AssertUnwindSafe(work)));
+    });
+
+    match receiver.await {
+        Ok(Ok(made)) => Ok(made),
+        Ok(Err(_)) => Err("the work failed unexpectedly".to_string()),
+        Err(_) => Err("the work was dropped".to_string()),
+    }
+}